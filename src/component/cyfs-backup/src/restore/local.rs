@@ -30,7 +30,8 @@ impl StackLocalObjectComponents {
         };
         let noc_dir = noc_dir.join("named-object-cache");
 
-        cyfs_noc::create_blob_storage(&noc_dir).await
+        let crypto = cyfs_util::StorageCryptoManager::get(isolate);
+        cyfs_noc::create_blob_storage(&noc_dir, crypto).await
     }
 
     pub async fn create_chunk_storage(
@@ -26,3 +26,9 @@ mod code;
 mod contract;
 mod nft;
 pub mod evm_def;
+
+pub fn register_meta_objects_format() {
+    use cyfs_base::*;
+
+    FORMAT_FACTORY.register(cyfs_core::CoreObjectType::MetaMinerGroup, format_json::<MinerGroup>);
+}
@@ -13,11 +13,17 @@ pub const CYFS_NDN_ACTION: &str = "cyfs-ndn-action";
 pub const CYFS_DEC_ID: &str = "cyfs-dec-id";
 pub const CYFS_TARGET_DEC_ID: &str = "cyfs-target-dec-id";
 
+// 本地app调用协议栈时携带的dec身份凭证，由app安装时分配的密钥签名
+pub const CYFS_DEC_AUTH: &str = "cyfs-dec-auth";
+
 // select请求的filter内部的dec-id，和请求本身的dec-id区分
 pub const CYFS_FILTER_DEC_ID: &str = "cyfs-filter-dec-id";
 
 pub const CYFS_REQ_PATH: &str = "cyfs-req-path";
 pub const CYFS_INNER_PATH: &str = "cyfs-inner-path";
+pub const CYFS_PROJECTION: &str = "cyfs-projection";
+// desc/body投影的结果只是对象的一部分，值为来源对象的object_id
+pub const CYFS_OBJECT_PART: &str = "cyfs-object-part";
 
 pub const CYFS_CONTEXT: &str = "cyfs-context";
 pub const CYFS_TASK_GROUP: &str = "cyfs-task-group";
@@ -0,0 +1,139 @@
+use crate::*;
+
+use serde_json::{Map, Value};
+use std::collections::{hash_map::Entry, HashMap};
+use std::sync::{Arc, Mutex};
+
+// 从json构造对象的参数，desc和body分别对应format_json输出里的desc.content和body.content
+#[derive(Clone, Debug)]
+pub struct ObjectJsonBuildParam {
+    pub object_type: u16,
+
+    pub owner: Option<ObjectId>,
+    pub author: Option<ObjectId>,
+    pub dec_id: Option<ObjectId>,
+
+    pub desc: Value,
+    pub body: Value,
+}
+
+impl ObjectJsonBuildParam {
+    pub fn owner(&self) -> BuckyResult<&ObjectId> {
+        self.owner.as_ref().ok_or_else(|| {
+            let msg = format!(
+                "build object from json but owner missing! obj_type={}",
+                self.object_type
+            );
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+        })
+    }
+
+    pub fn desc_content(&self) -> BuckyResult<&Map<String, Value>> {
+        Self::content_object("desc", &self.desc)
+    }
+
+    pub fn body_content(&self) -> BuckyResult<&Map<String, Value>> {
+        Self::content_object("body", &self.body)
+    }
+
+    pub fn content_object<'a>(key: &str, value: &'a Value) -> BuckyResult<&'a Map<String, Value>> {
+        value.as_object().ok_or_else(|| {
+            let msg = format!("invalid object build content, object expected: {}={}", key, value);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })
+    }
+}
+
+impl JsonCodec<ObjectJsonBuildParam> for ObjectJsonBuildParam {
+    fn encode_json(&self) -> Map<String, Value> {
+        let mut obj = Map::new();
+
+        JsonCodecHelper::encode_number_field(&mut obj, "object_type", self.object_type);
+        JsonCodecHelper::encode_option_string_field(&mut obj, "owner", self.owner.as_ref());
+        JsonCodecHelper::encode_option_string_field(&mut obj, "author", self.author.as_ref());
+        JsonCodecHelper::encode_option_string_field(&mut obj, "dec_id", self.dec_id.as_ref());
+        obj.insert("desc".to_owned(), self.desc.clone());
+        obj.insert("body".to_owned(), self.body.clone());
+
+        obj
+    }
+
+    fn decode_json(obj: &Map<String, Value>) -> BuckyResult<Self> {
+        Ok(Self {
+            object_type: JsonCodecHelper::decode_int_field(obj, "object_type")?,
+            owner: JsonCodecHelper::decode_option_string_field(obj, "owner")?,
+            author: JsonCodecHelper::decode_option_string_field(obj, "author")?,
+            dec_id: JsonCodecHelper::decode_option_string_field(obj, "dec_id")?,
+            desc: obj.get("desc").cloned().unwrap_or(Value::Null),
+            body: obj.get("body").cloned().unwrap_or(Value::Null),
+        })
+    }
+}
+
+type ObjectJsonBuilder = Arc<Box<dyn Fn(&ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> + Send + Sync>>;
+
+// 和FORMAT_FACTORY相对，按obj_type注册从json构造对象的方法，构造出的对象未签名
+pub struct BuilderFactory {
+    ext_types: Mutex<HashMap<u16, ObjectJsonBuilder>>,
+}
+
+impl BuilderFactory {
+    pub fn new() -> Self {
+        Self {
+            ext_types: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn register<F: 'static + Fn(&ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> + Send + Sync>(
+        &self,
+        obj_type: impl Into<u16>,
+        builder: F,
+    ) {
+        let f = Arc::new(Box::new(builder)
+            as Box<dyn Fn(&ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> + Send + Sync>);
+
+        let mut all = self.ext_types.lock().unwrap();
+        let obj_type = obj_type.into();
+        match all.entry(obj_type) {
+            Entry::Vacant(v) => {
+                v.insert(f);
+            }
+            Entry::Occupied(mut o) => {
+                warn!("register object json builder but already exists! obj_type={}", obj_type);
+                o.insert(f);
+            }
+        }
+    }
+
+    pub fn is_support(&self, obj_type: u16) -> bool {
+        self.ext_types.lock().unwrap().contains_key(&obj_type)
+    }
+
+    // 返回编码后的对象
+    pub fn build(&self, param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+        let f = self
+            .ext_types
+            .lock()
+            .unwrap()
+            .get(&param.object_type)
+            .map(|f| f.clone());
+
+        match f {
+            Some(f) => f(param),
+            None => {
+                let msg = format!(
+                    "build object from json but obj_type not support! obj_type={}",
+                    param.object_type
+                );
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
+            }
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    pub static ref BUILDER_FACTORY: BuilderFactory = BuilderFactory::new();
+}
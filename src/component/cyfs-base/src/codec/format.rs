@@ -170,8 +170,8 @@ where
             JsonCodecHelper::encode_string_field(&mut map, "owner", owner);
         }
 
-        if let Some(owner) = self.owner() {
-            JsonCodecHelper::encode_string_field(&mut map, "owner", owner);
+        if let Some(author) = self.author() {
+            JsonCodecHelper::encode_string_field(&mut map, "author", author);
         }
 
         if let Some(area) = self.area() {
@@ -439,8 +439,8 @@ impl ObjectFormat for TypelessObjectDesc {
             JsonCodecHelper::encode_string_field(&mut map, "owner", owner);
         }
 
-        if let Some(owner) = self.owner() {
-            JsonCodecHelper::encode_string_field(&mut map, "owner", owner);
+        if let Some(author) = self.author() {
+            JsonCodecHelper::encode_string_field(&mut map, "author", author);
         }
 
         if let Some(area) = self.area() {
@@ -555,6 +555,9 @@ impl ObjectFormat for PeopleBodyContent {
         JsonCodecHelper::encode_str_array_field(&mut map, "ood_list", &self.ood_list());
         JsonCodecHelper::encode_option_string_field(&mut map, "name", self.name());
         JsonCodecHelper::encode_option_string_field(&mut map, "icon", self.icon());
+        if !self.revoked_device_list().is_empty() {
+            JsonCodecHelper::encode_str_array_field(&mut map, "revoked_device_list", self.revoked_device_list());
+        }
 
         map.into()
     }
@@ -967,6 +970,10 @@ impl FormatFactory {
         }
     }
 
+    pub fn is_support(&self, obj_type: u16) -> bool {
+        self.ext_types.lock().unwrap().contains_key(&obj_type)
+    }
+
     pub fn format(&self, obj_type: u16, obj_raw: &[u8]) -> Option<serde_json::Value> {
         let f = self
             .ext_types
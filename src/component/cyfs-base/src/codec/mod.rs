@@ -2,6 +2,7 @@ mod protobuf;
 pub mod raw;
 mod serde_codec;
 mod format;
+mod builder;
 mod json_codec;
 
 pub use self::protobuf::*;
@@ -9,6 +10,7 @@ pub use raw::*;
 pub use serde_codec::*;
 pub use json_codec::*;
 pub use format::*;
+pub use builder::*;
 
 // ObjectContent的编码类型，默认为Raw
 pub const OBJECT_CONTENT_CODEC_FORMAT_RAW: u8 = 0;
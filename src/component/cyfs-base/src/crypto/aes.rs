@@ -5,12 +5,16 @@ use block_modes::block_padding::Pkcs7;
 use block_modes::{BlockMode, Cbc};
 use generic_array::typenum::{marker_traits::Unsigned, U48, U8};
 use generic_array::GenericArray;
+use hkdf::Hkdf;
+use hmac::Hmac;
 use sha2::Digest;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::hash::Hash;
 use std::str::FromStr;
 
+const AES_KEY_DERIVE_INFO: &[u8] = b"cyfs-aes-key";
+
 // aes key used to crypto data
 #[derive(Clone, Eq, PartialEq)]
 pub struct AesKey(GenericArray<u8, U48>);
@@ -267,6 +271,77 @@ impl AesKey {
         }
     }
 
+    // HKDF-SHA256(RFC5869)，相同的material+salt+info总是得到相同的key，info用来区分不同用途
+    pub fn hkdf(material: &[u8], salt: &[u8], info: &[u8]) -> AesKey {
+        let mut key = [0u8; 48];
+        Hkdf::<sha2::Sha256>::new(Some(salt), material)
+            .expand(info, &mut key)
+            .unwrap();
+
+        AesKey::from(&key)
+    }
+
+    // 从密钥材料(比如device私钥)派生
+    pub fn derive(material: &[u8], salt: &[u8]) -> AesKey {
+        Self::hkdf(material, salt, AES_KEY_DERIVE_INFO)
+    }
+
+    // 从用户口令派生，使用PBKDF2-HMAC-SHA256，通过rounds增加暴力破解的代价
+    pub fn derive_from_passphrase(passphrase: &str, salt: &[u8], rounds: u32) -> AesKey {
+        let mut key = [0u8; 48];
+        pbkdf2::pbkdf2::<Hmac<sha2::Sha256>>(passphrase.as_bytes(), salt, rounds as usize, &mut key);
+
+        AesKey::from(&key)
+    }
+
+    // AES-256-GCM加密，使用key的前32字节，输出为 nonce(12bytes) + 密文 + tag(16bytes)
+    pub fn gcm_encrypt(&self, data: &[u8]) -> BuckyResult<Vec<u8>> {
+        use ::aes_gcm::aead::{generic_array::GenericArray, Aead, NewAead};
+
+        let cipher = ::aes_gcm::Aes256Gcm::new(GenericArray::from_slice(&self.0.as_slice()[0..32]));
+        let nonce: [u8; 12] = rand::random();
+
+        let encrypted = cipher
+            .encrypt(GenericArray::from_slice(&nonce), data)
+            .map_err(|e| {
+                let msg = format!("AesKey::gcm_encrypt error, len={}, {:?}", data.len(), e);
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::CryptoError, msg)
+            })?;
+
+        let mut ret = Vec::with_capacity(nonce.len() + encrypted.len());
+        ret.extend_from_slice(&nonce);
+        ret.extend_from_slice(&encrypted);
+
+        Ok(ret)
+    }
+
+    pub fn gcm_decrypt(&self, data: &[u8]) -> BuckyResult<Vec<u8>> {
+        use ::aes_gcm::aead::{generic_array::GenericArray, Aead, NewAead};
+
+        if data.len() < Self::gcm_overhead() {
+            let msg = format!("AesKey::gcm_decrypt error, invalid data len={}", data.len());
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
+        }
+
+        let cipher = ::aes_gcm::Aes256Gcm::new(GenericArray::from_slice(&self.0.as_slice()[0..32]));
+        let (nonce, encrypted) = data.split_at(12);
+
+        cipher
+            .decrypt(GenericArray::from_slice(nonce), encrypted)
+            .map_err(|e| {
+                let msg = format!("AesKey::gcm_decrypt error, len={}, {:?}", data.len(), e);
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::CryptoError, msg)
+            })
+    }
+
+    // gcm加密后相对于明文增加的长度
+    pub const fn gcm_overhead() -> usize {
+        12 + 16
+    }
+
     pub fn as_slice(&self) -> &[u8] {
         self.0.as_slice()
     }
@@ -383,4 +458,39 @@ mod test_aes {
             String::from_utf8(d.to_vec())
         );
     }
+
+    #[test]
+    fn test_gcm() {
+        let key = AesKey::derive(b"device secret", b"salt");
+        assert_eq!(key, AesKey::derive(b"device secret", b"salt"));
+        assert_ne!(key, AesKey::derive(b"device secret", b"salt2"));
+
+        let d = b"Some Crypto Text11111dsfasdfsdsdSome Crypto Text1111";
+        let encrypted = key.gcm_encrypt(d).unwrap();
+        assert_eq!(encrypted.len(), d.len() + AesKey::gcm_overhead());
+        assert_eq!(key.gcm_decrypt(&encrypted).unwrap(), d.to_vec());
+
+        let other = AesKey::derive_from_passphrase("passphrase", b"salt", 16);
+        assert!(other.gcm_decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_kdf() {
+        // RFC5869 A.1，输出取前48字节
+        let ikm = [0x0bu8; 22];
+        let salt = hex::decode("000102030405060708090a0b0c").unwrap();
+        let info = hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap();
+        let key = AesKey::hkdf(&ikm, &salt, &info);
+        assert!(hex::encode(key.as_slice()).starts_with(
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        ));
+        assert_ne!(key, AesKey::hkdf(&ikm, &salt, b"other"));
+
+        // RFC7914 PBKDF2-HMAC-SHA256 c=1
+        let key = AesKey::derive_from_passphrase("passwd", b"salt", 1);
+        assert!(hex::encode(key.as_slice()).starts_with(
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc"
+        ));
+        assert_ne!(key, AesKey::derive_from_passphrase("passwd", b"salt", 2));
+    }
 }
@@ -197,6 +197,35 @@ impl AnyNamedObject {
         )
     }
 
+    // desc部分的编码，用于只传输对象的一部分
+    pub fn desc_raw(&self) -> BuckyResult<Vec<u8>> {
+        match_any_obj!(self, o, { o.desc().to_vec() }, chunk_id, {
+            let msg = format!("chunk has no desc: {}", chunk_id);
+            error!("{}", msg);
+            Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
+        })
+    }
+
+    // body部分的编码，没有body时返回None
+    pub fn body_raw(&self) -> BuckyResult<Option<Vec<u8>>> {
+        match_any_obj!(
+            self,
+            o,
+            {
+                match o.body() {
+                    Some(body) => Ok(Some(body.to_vec()?)),
+                    None => Ok(None),
+                }
+            },
+            _chunk_id,
+            {
+                let msg = format!("chunk has no body: {}", self.calculate_id());
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
+            }
+        )
+    }
+
     pub fn body_prev_version(&self) -> &Option<HashValue> {
         match_any_obj!(
             self,
@@ -269,6 +298,25 @@ impl AnyNamedObject {
         }
     }
 
+    // 去掉对象的body和body签名，desc部分和object_id保持不变
+    pub fn clear_body(&mut self) -> BuckyResult<()> {
+        match_any_obj!(
+            self,
+            o,
+            {
+                *o.body_mut() = None;
+                o.signs_mut().clear_body_signs();
+                Ok(())
+            },
+            chunk_id,
+            {
+                let msg = format!("chunk has no body: {}", chunk_id);
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
+            }
+        )
+    }
+
     // 设置对象body的修改时间
     pub fn set_body_update_time(&mut self, time: u64) {
         match_any_obj!(
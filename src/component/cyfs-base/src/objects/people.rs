@@ -71,6 +71,9 @@ pub struct PeopleBodyContent {
     ood_list: Vec<DeviceId>,
     name: Option<String>,
     icon: Option<FileId>,
+
+    // 已经被吊销的设备列表，这些设备的签名和请求不再被zone内信任
+    revoked_device_list: Vec<DeviceId>,
 }
 
 impl BodyContent for PeopleBodyContent {
@@ -89,6 +92,9 @@ impl TryFrom<protos::PeopleBodyContent> for PeopleBodyContent {
             ood_list: ProtobufCodecHelper::decode_buf_list(value.take_ood_list())?,
             name: None,
             icon: None,
+            revoked_device_list: ProtobufCodecHelper::decode_buf_list(
+                value.take_revoked_device_list(),
+            )?,
         };
 
         if value.has_ood_work_mode() {
@@ -112,6 +118,13 @@ impl TryFrom<&PeopleBodyContent> for protos::PeopleBodyContent {
     fn try_from(value: &PeopleBodyContent) -> BuckyResult<Self> {
         let mut ret = Self::new();
         ret.set_ood_list(ProtobufCodecHelper::encode_buf_list(&value.ood_list)?);
+
+        // 为空时不编码，保持和老版本的编码一致
+        if !value.revoked_device_list.is_empty() {
+            ret.set_revoked_device_list(ProtobufCodecHelper::encode_buf_list(
+                &value.revoked_device_list,
+            )?);
+        }
         
         if let Some(ood_work_mode) = &value.ood_work_mode {
             ret.set_ood_work_mode(ood_work_mode.to_string());
@@ -142,6 +155,7 @@ impl PeopleBodyContent {
             ood_list,
             name,
             icon,
+            revoked_device_list: vec![],
         }
     }
 
@@ -161,6 +175,14 @@ impl PeopleBodyContent {
         &mut self.ood_list
     }
 
+    pub fn revoked_device_list(&self) -> &Vec<DeviceId> {
+        &self.revoked_device_list
+    }
+
+    pub fn revoked_device_list_mut(&mut self) -> &mut Vec<DeviceId> {
+        &mut self.revoked_device_list
+    }
+
     pub fn name(&self) -> Option<&str> {
         self.name.as_ref().map(|f| f.as_str())
     }
@@ -232,6 +254,34 @@ impl People {
             .ood_list_mut()
     }
 
+    pub fn revoked_device_list(&self) -> &Vec<DeviceId> {
+        self.body().as_ref().unwrap().content().revoked_device_list()
+    }
+
+    pub fn revoked_device_list_mut(&mut self) -> &mut Vec<DeviceId> {
+        self.body_mut()
+            .as_mut()
+            .unwrap()
+            .content_mut()
+            .revoked_device_list_mut()
+    }
+
+    pub fn is_device_revoked(&self, device_id: &DeviceId) -> bool {
+        match self.body() {
+            Some(body) => body.content().revoked_device_list().contains(device_id),
+            None => false,
+        }
+    }
+
+    // 吊销设备，同时从ood_list里面移除
+    pub fn revoke_device(&mut self, device_id: &DeviceId) {
+        let content = self.body_mut().as_mut().unwrap().content_mut();
+        content.ood_list_mut().retain(|id| id != device_id);
+        if !content.revoked_device_list().contains(device_id) {
+            content.revoked_device_list_mut().push(device_id.to_owned());
+        }
+    }
+
     pub fn name(&self) -> Option<&str> {
         self.body().as_ref().unwrap().content().name()
     }
@@ -325,4 +375,35 @@ mod test {
         assert_eq!(p.desc().people_id(), pp.desc().people_id());
         assert_eq!(p.name(), pp.name());
     }
+
+    #[test]
+    fn revoke_device() {
+        let private_key = PrivateKey::generate_rsa(1024).unwrap();
+        let device = Device::new(
+            None,
+            UniqueId::default(),
+            vec![],
+            vec![],
+            vec![],
+            private_key.public(),
+            Area::default(),
+            DeviceCategory::OOD,
+        )
+        .build();
+        let device_id = device.desc().device_id();
+
+        let mut p = People::new(None, vec![device_id.clone()], private_key.public(), None, None, None)
+            .no_create_time()
+            .build();
+        assert!(!p.is_device_revoked(&device_id));
+
+        p.revoke_device(&device_id);
+        p.revoke_device(&device_id);
+        assert!(p.ood_list().is_empty());
+        assert_eq!(p.revoked_device_list(), &vec![device_id.clone()]);
+
+        let buf = p.to_vec().unwrap();
+        let pp = People::clone_from_slice(&buf).unwrap();
+        assert!(pp.is_device_revoked(&device_id));
+    }
 }
@@ -1,5 +1,6 @@
 use crate::*;
 use cyfs_base::*;
+use cyfs_bdt::DownloadTaskPriority;
 use cyfs_lib::*;
 
 use async_std::io::Read;
@@ -40,6 +41,31 @@ impl TargetDataManager {
         Box<dyn Read + Unpin + Send + Sync + 'static>,
         u64,
         Option<String>,
+    )> {
+        self.get_file_with_priority(
+            source,
+            file_obj,
+            group,
+            ranges,
+            DownloadTaskPriority::default(),
+            0,
+        )
+        .await
+    }
+
+    // 流式读取时使用Realtime优先级，并预先下载读取位置之后的prefetch个chunk
+    pub async fn get_file_with_priority(
+        &self,
+        source: &RequestSourceInfo,
+        file_obj: &File,
+        group: Option<&str>,
+        ranges: Option<Vec<Range<u64>>>,
+        priority: DownloadTaskPriority,
+        prefetch: usize,
+    ) -> BuckyResult<(
+        Box<dyn Read + Unpin + Send + Sync + 'static>,
+        u64,
+        Option<String>,
     )> {
         let file_id = file_obj.desc().calculate_id();
 
@@ -58,11 +84,13 @@ impl TargetDataManager {
 
         let group = TaskGroupHelper::new_opt_with_dec(&source.dec, group);
 
-        let (id, reader) = cyfs_bdt::download_file(
+        let (id, reader) = cyfs_bdt::download_file_with_priority(
             &self.named_data_components.bdt_stack(),
             file_obj.to_owned(),
             group,
             self.context.clone(),
+            priority,
+            prefetch,
         )
         .await
         .map_err(|e| {
@@ -71,12 +99,14 @@ impl TargetDataManager {
         })?;
 
         info!(
-            "get file data from target: {}, file={}, file_len={}, len={}, ranges={:?}, task={:?}",
+            "get file data from target: {}, file={}, file_len={}, len={}, ranges={:?}, priority={:?}, prefetch={}, task={:?}",
             self.context.debug_string(),
             file_id,
             file_obj.len(),
             total_size,
             ranges,
+            priority,
+            prefetch,
             reader.task().abs_group_path(),
         );
 
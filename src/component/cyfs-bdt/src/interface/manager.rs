@@ -285,6 +285,9 @@ impl NetListener {
             if outer.is_some() {
                 ep_set.insert(outer.unwrap());
             }
+            if let Some(relayed) = udp.turn_relayed() {
+                ep_set.insert(relayed);
+            }
         }
         for tcp in self.tcp() {
             if tcp.local().addr().is_ipv4() {
@@ -2,7 +2,8 @@ use crate::{
     types::*, 
     history::keystore,
     protocol::*,
-    stack::{Stack, WeakStack}
+    stack::{Stack, WeakStack}, 
+    pn::client::{StunMessage, StunMethod, StunClass}
 };
 use super::{
     manager::UpdateOuterResult
@@ -10,7 +11,14 @@ use super::{
 use async_std::sync::Arc;
 use cyfs_base::*;
 use log::*;
-use std::{cell::RefCell, net::UdpSocket, sync::RwLock, thread};
+use std::{
+    cell::RefCell, 
+    collections::{HashMap, HashSet}, 
+    net::{UdpSocket, SocketAddr, IpAddr}, 
+    sync::RwLock, 
+    thread, 
+    time::Duration
+};
 use socket2::{Socket, Domain, Type};
 
 #[derive(Clone)]
@@ -73,12 +81,50 @@ thread_local! {
     static BOX_CRYPTO_BUFFER: RefCell<[u8; MTU_LARGE]> = RefCell::new([0u8; MTU_LARGE]);
 }
 
+// 同时请求permission的ip上限，超过后不再为新的ip请求
+const MAX_TURN_PERMISSIONS: usize = 256;
+
+// 通过TURN server中转时的路由状态，由pn::client::TurnClient维护
+struct TurnRoute {
+    server: SocketAddr, 
+    relayed: Option<Endpoint>, 
+    // 通过relay收到过数据的peer，发往这些peer的数据也要经过relay
+    peers: HashSet<SocketAddr>, 
+    // 已经请求过permission的ip和请求时间，超过有效期后失效
+    permitted: HashMap<IpAddr, Timestamp>, 
+    permission_lifetime: Duration, 
+    permission_sender: async_std::channel::Sender<IpAddr>
+}
+
+impl TurnRoute {
+    fn is_permitted(&self, ip: &IpAddr, now: Timestamp) -> bool {
+        let lifetime = self.permission_lifetime.as_micros() as u64;
+        self.permitted.get(ip).map(|at| now < *at + lifetime).unwrap_or(false)
+    }
+
+    // 返回是否需要为ip请求permission
+    fn add_permission(&mut self, ip: IpAddr, now: Timestamp) -> bool {
+        if self.is_permitted(&ip, now) {
+            return false;
+        }
+        let lifetime = self.permission_lifetime.as_micros() as u64;
+        self.permitted.retain(|_, at| now < *at + lifetime);
+        if self.permitted.len() >= MAX_TURN_PERMISSIONS {
+            debug!("turn route ignore permission for {} for reach limit {}", ip, MAX_TURN_PERMISSIONS);
+            return false;
+        }
+        self.permitted.insert(ip, now);
+        true
+    }
+}
+
 struct InterfaceImpl {
     config: Config, 
     socket: UdpSocket, 
     mapping_port: Option<u16>,
     local: RwLock<Endpoint>,
     outer: RwLock<Option<Endpoint>>,
+    turn: RwLock<Option<TurnRoute>>,
 }
 
 #[derive(Clone)]
@@ -206,6 +252,7 @@ impl Interface {
             local: RwLock::new(local),
             socket,
             outer: RwLock::new(out),
+            turn: RwLock::new(None),
         })))
     }
 
@@ -280,11 +327,109 @@ impl Interface {
         Arc::ptr_eq(&self.0, &other.0)
     }
 
+    pub(crate) fn set_turn_server(&self, server: SocketAddr, permission_lifetime: Duration, permission_sender: async_std::channel::Sender<IpAddr>) {
+        info!("{} relay through turn server {}", self, server);
+        *self.0.turn.write().unwrap() = Some(TurnRoute {
+            server, 
+            relayed: None, 
+            peers: HashSet::new(), 
+            permitted: HashMap::new(), 
+            permission_lifetime, 
+            permission_sender
+        });
+    }
+
+    pub fn turn_server(&self) -> Option<SocketAddr> {
+        self.0.turn.read().unwrap().as_ref().map(|route| route.server)
+    }
+
+    // TURN server上分配的relay地址，会作为本地endpoint发布出去
+    pub fn turn_relayed(&self) -> Option<Endpoint> {
+        self.0.turn.read().unwrap().as_ref().and_then(|route| route.relayed)
+    }
+
+    pub(crate) fn set_turn_relayed(&self, relayed: Option<Endpoint>) {
+        if let Some(route) = self.0.turn.write().unwrap().as_mut() {
+            if route.relayed != relayed {
+                info!("{} update turn relayed endpoint to {:?}", self, relayed);
+                // relay地址变了之前的peer和permission都失效了
+                route.relayed = relayed;
+                route.peers.clear();
+                route.permitted.clear();
+            }
+        }
+    }
+
+    pub(crate) fn remove_turn_permission(&self, ip: &IpAddr) {
+        if let Some(route) = self.0.turn.write().unwrap().as_mut() {
+            route.permitted.remove(ip);
+        }
+    }
+
+    // 发往to的数据是否要经过TURN server中转；
+    // 只为经过relay的peer和发送tunnel包的peer请求permission，sn等其它目标不会发数据到relay地址
+    fn turn_route_to(&self, to: &SocketAddr, is_tunnel: bool) -> Option<SocketAddr> {
+        let now = bucky_time_now();
+        let (server, need_permission) = {
+            let turn = self.0.turn.read().unwrap();
+            if let Some(route) = turn.as_ref() {
+                if route.server.eq(to) || route.relayed.is_none() {
+                    return None;
+                }
+                let routed = route.peers.contains(to);
+                let server = if routed {
+                    Some(route.server)
+                } else {
+                    None
+                };
+                (server, (routed || is_tunnel) && !route.is_permitted(&to.ip(), now))
+            } else {
+                return None;
+            }
+        };
+
+        if need_permission {
+            if let Some(route) = self.0.turn.write().unwrap().as_mut() {
+                if route.add_permission(to.ip(), now) {
+                    let _ = route.permission_sender.try_send(to.ip());
+                }
+            }
+        }
+
+        server
+    }
+
+    fn on_turn_datagram(&self, stack: Stack, recv: &[u8]) {
+        match StunMessage::decode(recv) {
+            Ok(mut msg) => {
+                if msg.method == StunMethod::Data && msg.class == StunClass::Indication {
+                    if let (Some(peer), Some(mut data)) = (msg.xor_peer_address(), msg.take_data()) {
+                        trace!("{} recv {} bytes from {} through turn server", self, data.len(), peer);
+                        if let Some(route) = self.0.turn.write().unwrap().as_mut() {
+                            route.peers.insert(peer);
+                        }
+                        self.on_recv(stack, &mut data[..], Endpoint::from((Protocol::Udp, peer)));
+                    }
+                } else {
+                    let _ = stack.proxy_manager().on_turn_message(self, msg, recv);
+                }
+            }, 
+            Err(err) => {
+                debug!("{} ignore datagram from turn server for {}", self, err);
+            }
+        }
+    }
+
     fn on_recv(&self, stack: Stack, recv: &mut [u8], from: Endpoint) {
         if recv.len() == 0 {
             return
         }
 
+        if self.turn_server().map(|server| server.eq(from.addr())).unwrap_or(false) {
+            self.on_turn_datagram(stack, recv);
+            return;
+        }
+
         if recv[0] & 0x80 != 0 {
             match KeyMixHash::raw_decode(recv) {
                 Ok((mut mix_hash, raw_data)) => {
@@ -393,7 +538,7 @@ impl Interface {
                     e
                 })?;
             let send_len = buf_len - next_ptr.len();
-            self.send_buf_to(&crypto_buf[..send_len], to)
+            self.send_to(&crypto_buf[..send_len], to, Self::is_tunnel_box(package_box))
         })
     }
 
@@ -413,12 +558,13 @@ impl Interface {
                     e
                 })?;
             let send_len = buf_len - next_ptr.len();
+            let is_tunnel = Self::is_tunnel_box(package_box);
             let mut send_count = 0;
             for (from, to) in iter {
                 if from.local().is_same_ip_version(&to) {
                     send_count += 1;
                     let is_continue =
-                        on_result(&from, &to, from.send_buf_to(&crypto_buf[..send_len], &to));
+                        on_result(&from, &to, from.send_to(&crypto_buf[..send_len], &to, is_tunnel));
                     if !is_continue {
                         break;
                     }
@@ -443,7 +589,19 @@ impl Interface {
         self.send_buf_to(data, to)
     }
 
+    fn is_tunnel_box(package_box: &PackageBox) -> bool {
+        package_box
+            .packages_no_exchange()
+            .get(0)
+            .map(|pkg| pkg.cmd_code().is_tunnel())
+            .unwrap_or(false)
+    }
+
     pub fn send_buf_to(&self, buf: &[u8], to: &Endpoint) -> Result<usize, BuckyError> {
+        self.send_to(buf, to, false)
+    }
+
+    fn send_to(&self, buf: &[u8], to: &Endpoint, is_tunnel: bool) -> Result<usize, BuckyError> {
         trace!("{} send {} bytes to {}", self, buf.len(), to);
         if self.0.config.sim_loss_rate > 0 {
             if rand::random::<u8>() < self.0.config.sim_loss_rate {
@@ -451,6 +609,15 @@ impl Interface {
                 return Ok(buf.len());
             }
         }
+        if let Some(server) = self.turn_route_to(to.addr(), is_tunnel) {
+            // 加上Send indication的头之后可能超过MTU，依赖ip分片
+            let indication = StunMessage::send_indication(to.addr(), buf);
+            return self.0
+                .socket
+                .send_to(&indication, server)
+                .map(|_| buf.len())
+                .map_err(|e| BuckyError::from(e));
+        }
         self.0
             .socket
             .send_to(buf, to.addr())
@@ -1,5 +1,6 @@
 use std::{
     sync::{RwLock},
+    collections::LinkedList, 
     io::SeekFrom, 
     ops::Range
 };
@@ -27,9 +28,21 @@ struct DownloadingState {
     downloaded: u64,  
     cur_speed: ProgressCounter,  
     cur_chunk: (ChunkDownloader, usize), 
+    // 当前读取位置之后预先下载的chunk
+    prefetch: LinkedList<(ChunkDownloader, usize)>, 
     history_speed: HistorySpeed,
 }
 
+impl DownloadingState {
+    fn take_prefetch(&mut self, index: usize) -> Option<ChunkDownloader> {
+        let pos = self.prefetch.iter().position(|(_, i)| *i == index)?;
+        let mut tail = self.prefetch.split_off(pos);
+        let exists = tail.pop_front();
+        self.prefetch.append(&mut tail);
+        exists.map(|(downloader, _)| downloader)
+    }
+}
+
 enum ControlStateImpl {
     Normal(StateWaiter), 
     Canceled,
@@ -53,6 +66,8 @@ struct TaskImpl {
     name: String, 
     chunk_list: ChunkListDesc,
     context: Box<dyn DownloadContext>,
+    priority: DownloadTaskPriority, 
+    prefetch: usize, 
     state: RwLock<StateImpl>,  
 }
 
@@ -71,11 +86,25 @@ impl ChunkListTask {
         name: String,
         chunk_list: ChunkListDesc, 
         context: Box<dyn DownloadContext>, 
+    ) -> Self {
+        Self::with_priority(stack, name, chunk_list, context, DownloadTaskPriority::default(), 0)
+    } 
+
+    // prefetch: 读取时预先下载当前chunk之后的chunk数，用于流式读取
+    pub fn with_priority(
+        stack: WeakStack,  
+        name: String,
+        chunk_list: ChunkListDesc, 
+        context: Box<dyn DownloadContext>, 
+        priority: DownloadTaskPriority, 
+        prefetch: usize, 
     ) -> Self {
         Self(Arc::new(TaskImpl {
             stack, 
             name,
             context, 
+            priority, 
+            prefetch, 
             state: RwLock::new(StateImpl {
                 abs_path: None, 
                 task_state: if chunk_list.total_len() > 0 {
@@ -106,12 +135,15 @@ impl ChunkListTask {
             TaskStateImpl::Pending => {
                 debug!("{} create cache from pending, index={}, chunk={}", self, index, chunk);
                 let downloader = stack.ndn().chunk_manager().create_downloader(chunk, self.clone_as_leaf_task());
-                state.task_state = TaskStateImpl::Downloading(DownloadingState { 
+                let mut downloading = DownloadingState { 
                     downloaded: 0, 
                     cur_speed: ProgressCounter::new(0), 
                     cur_chunk: (downloader.clone(), index), 
+                    prefetch: LinkedList::new(), 
                     history_speed: HistorySpeed::new(0, stack.config().ndn.channel.history_speed.clone()), 
-                });
+                };
+                self.prefetch(&stack, &mut downloading);
+                state.task_state = TaskStateImpl::Downloading(downloading);
                 Ok(downloader.cache().clone())
             }, 
             TaskStateImpl::Downloading(downloading) => {
@@ -119,7 +151,14 @@ impl ChunkListTask {
                 if *cur_index != index {
                     debug!("{} create new cache, old_index={}, old_chunk={}, index={}, chunk={}", self, *cur_index, downloader.cache().chunk(), index, chunk);
                     downloading.downloaded += downloader.cache().stream().len() as u64;
-                    downloading.cur_chunk = (stack.ndn().chunk_manager().create_downloader(chunk, self.clone_as_leaf_task()), index);
+
+                    // 已经预先下载的chunk直接复用，避免取消正在进行的session
+                    let downloader = match downloading.take_prefetch(index) {
+                        Some(downloader) => downloader, 
+                        None => stack.ndn().chunk_manager().create_downloader(chunk, self.clone_as_leaf_task())
+                    };
+                    downloading.cur_chunk = (downloader, index);
+                    self.prefetch(&stack, downloading);
                 }
                 Ok(downloading.cur_chunk.0.cache().clone())
             },
@@ -127,10 +166,36 @@ impl ChunkListTask {
             TaskStateImpl::Error(err) => Err(err.clone())
         }
     }
+
+    // 保证当前chunk之后的prefetch个chunk已经开始下载
+    fn prefetch(&self, stack: &Stack, downloading: &mut DownloadingState) {
+        let cur_index = downloading.cur_chunk.1;
+        let end = self.chunk_list().chunks().len().min(cur_index + 1 + self.0.prefetch);
+
+        let mut prefetch = LinkedList::new();
+        for index in cur_index + 1..end {
+            let downloader = match downloading.take_prefetch(index) {
+                Some(downloader) => downloader, 
+                None => {
+                    let chunk = &self.chunk_list().chunks()[index];
+                    debug!("{} prefetch chunk, cur_index={}, index={}, chunk={}", self, cur_index, index, chunk);
+                    stack.ndn().chunk_manager().create_downloader(chunk, self.clone_as_leaf_task())
+                }
+            };
+            prefetch.push_back((downloader, index));
+        }
+
+        // 不在预取范围内的downloader直接释放
+        downloading.prefetch = prefetch;
+    }
 }
 
 #[async_trait::async_trait]
 impl LeafDownloadTask for ChunkListTask {
+    fn priority(&self) -> DownloadTaskPriority {
+        self.0.priority
+    }
+
     fn clone_as_leaf_task(&self) -> Box<dyn LeafDownloadTask> {
         Box::new(self.clone())
     }
@@ -414,4 +479,18 @@ impl ChunkListTask {
         
         (task, reader)
     }
+
+    pub fn reader_with_priority(
+        stack: WeakStack,  
+        name: String,
+        chunk_list: ChunkListDesc, 
+        context: Box<dyn DownloadContext>, 
+        priority: DownloadTaskPriority, 
+        prefetch: usize, 
+    ) -> (Self, ChunkListTaskReader) {
+        let task = Self::with_priority(stack, name, chunk_list, context, priority, prefetch);
+        let reader = ChunkListTaskReader::new(task.clone());
+        
+        (task, reader)
+    }
 }
\ No newline at end of file
@@ -126,7 +126,7 @@ impl Into<DownloadSource<DeviceId>> for DownloadSource<DeviceDesc> {
 }
 
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum DownloadTaskPriority {
     Backgroud, 
     Normal, 
@@ -9,6 +9,7 @@ use crate::{
     interface::udp::*, 
     stack::{WeakStack, Stack}
 };
+use super::turn::*;
 
 struct Proxies {
     active_proxies: BTreeSet<DeviceId>,
@@ -28,7 +29,8 @@ impl Proxies {
 
 pub struct ProxyManager {
     stack: WeakStack,
-    proxies: RwLock<Proxies>
+    proxies: RwLock<Proxies>, 
+    turn_clients: RwLock<Vec<TurnClient>>
 } 
 
 
@@ -43,6 +45,7 @@ impl ProxyManager {
         Self {
             stack, 
             proxies: RwLock::new(Proxies::new()), 
+            turn_clients: RwLock::new(vec![]), 
         }
     }
 
@@ -97,6 +100,39 @@ impl ProxyManager {
     pub fn dump_proxies(&self) -> Vec<DeviceId> {
         self.proxies.read().unwrap().dump_proxies.iter().cloned().collect()
     }
+
+    // 在同ip版本的第一个udp interface上使用配置的TURN server
+    pub(crate) async fn start_turn(&self) {
+        let stack = Stack::from(&self.stack);
+        let config = stack.config().turn.clone();
+        let net_listener = stack.net_manager().listener();
+        for server in config.servers.iter() {
+            if let Some(interface) = net_listener.udp().iter().find(|i| i.local().addr().is_ipv4() == server.server.is_ipv4() && i.turn_server().is_none()) {
+                let client = TurnClient::open(config.clone(), server.clone(), interface.clone());
+                info!("{} add turn client {}", self, client);
+                self.turn_clients.write().unwrap().push(client.clone());
+                client.start().await;
+            } else {
+                warn!("{} ignore turn server {} for no udp interface available", self, server.server);
+            }
+        }
+    }
+
+    pub fn turn_clients(&self) -> Vec<TurnClient> {
+        self.turn_clients.read().unwrap().clone()
+    }
+
+    pub(crate) fn on_turn_message(&self, interface: &Interface, msg: StunMessage, buf: &[u8]) -> BuckyResult<()> {
+        let client = self.turn_clients.read().unwrap().iter().find(|c| c.interface().is_same(interface)).cloned();
+        if let Some(client) = client {
+            client.on_message(msg, buf);
+            Ok(())
+        } else {
+            let err = BuckyError::new(BuckyErrorCode::NotFound, "turn client not exists");
+            debug!("{} ignore turn message from {} for {}", self, interface, err);
+            Err(err)
+        }
+    }
 }
 
 impl OnUdpPackageBox for ProxyManager {
@@ -1,2 +1,4 @@
 mod manager;
-pub use manager::ProxyManager;
\ No newline at end of file
+mod turn;
+pub use manager::ProxyManager;
+pub use turn::*;
\ No newline at end of file
@@ -0,0 +1,797 @@
+use log::*;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::RwLock,
+    time::Duration
+};
+use async_std::{
+    channel,
+    sync::Arc,
+    task,
+    future
+};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use cyfs_base::*;
+use crate::{
+    types::*,
+    interface::udp::Interface
+};
+
+// STUN/TURN(RFC 8489/8656)里中转udp用到的最小子集
+
+pub const STUN_MAGIC_COOKIE: u32 = 0x2112A442;
+pub const STUN_HEADER_LENGTH: usize = 20;
+// REQUESTED-TRANSPORT里的udp协议号
+pub const TURN_TRANSPORT_UDP: u8 = 17;
+
+const ATTR_USERNAME: u16 = 0x0006;
+const ATTR_MESSAGE_INTEGRITY: u16 = 0x0008;
+const ATTR_ERROR_CODE: u16 = 0x0009;
+const ATTR_LIFETIME: u16 = 0x000D;
+const ATTR_XOR_PEER_ADDRESS: u16 = 0x0012;
+const ATTR_DATA: u16 = 0x0013;
+const ATTR_REALM: u16 = 0x0014;
+const ATTR_NONCE: u16 = 0x0015;
+const ATTR_XOR_RELAYED_ADDRESS: u16 = 0x0016;
+const ATTR_REQUESTED_TRANSPORT: u16 = 0x0019;
+const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StunMethod {
+    Binding,
+    Allocate,
+    Refresh,
+    Send,
+    Data,
+    CreatePermission,
+}
+
+impl StunMethod {
+    fn code(&self) -> u16 {
+        match self {
+            Self::Binding => 0x001,
+            Self::Allocate => 0x003,
+            Self::Refresh => 0x004,
+            Self::Send => 0x006,
+            Self::Data => 0x007,
+            Self::CreatePermission => 0x008,
+        }
+    }
+
+    fn from_code(code: u16) -> BuckyResult<Self> {
+        match code {
+            0x001 => Ok(Self::Binding),
+            0x003 => Ok(Self::Allocate),
+            0x004 => Ok(Self::Refresh),
+            0x006 => Ok(Self::Send),
+            0x007 => Ok(Self::Data),
+            0x008 => Ok(Self::CreatePermission),
+            _ => Err(BuckyError::new(BuckyErrorCode::NotSupport, format!("unknown stun method {}", code)))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StunClass {
+    Request,
+    Indication,
+    SuccessResponse,
+    ErrorResponse,
+}
+
+impl StunClass {
+    fn code(&self) -> u16 {
+        match self {
+            Self::Request => 0b00,
+            Self::Indication => 0b01,
+            Self::SuccessResponse => 0b10,
+            Self::ErrorResponse => 0b11,
+        }
+    }
+
+    fn from_code(code: u16) -> Self {
+        match code & 0b11 {
+            0b00 => Self::Request,
+            0b01 => Self::Indication,
+            0b10 => Self::SuccessResponse,
+            _ => Self::ErrorResponse,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StunAttribute {
+    Username(String),
+    ErrorCode(u16, String),
+    Lifetime(u32),
+    XorPeerAddress(SocketAddr),
+    Data(Vec<u8>),
+    Realm(String),
+    Nonce(String),
+    XorRelayedAddress(SocketAddr),
+    RequestedTransport(u8),
+    XorMappedAddress(SocketAddr),
+    // 解码时才会出现，编码时由integrity key生成
+    MessageIntegrity(Vec<u8>),
+}
+
+pub type StunTransactionId = [u8; 12];
+
+#[derive(Clone, Debug)]
+pub struct StunMessage {
+    pub method: StunMethod,
+    pub class: StunClass,
+    pub transaction_id: StunTransactionId,
+    pub attributes: Vec<StunAttribute>,
+}
+
+impl StunMessage {
+    pub fn new(method: StunMethod, class: StunClass) -> Self {
+        Self::with_transaction(method, class, rand::random())
+    }
+
+    pub fn with_transaction(method: StunMethod, class: StunClass, transaction_id: StunTransactionId) -> Self {
+        Self {
+            method,
+            class,
+            transaction_id,
+            attributes: vec![]
+        }
+    }
+
+    pub fn add(&mut self, attr: StunAttribute) -> &mut Self {
+        self.attributes.push(attr);
+        self
+    }
+
+    // 对udp socket上复用的数据做区分，STUN消息开头两位是0，并且带magic cookie
+    pub fn is_stun(buf: &[u8]) -> bool {
+        buf.len() >= STUN_HEADER_LENGTH
+            && buf[0] & 0xc0 == 0
+            && u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]) == STUN_MAGIC_COOKIE
+    }
+
+    // long-term credential的key，MD5(username:realm:password)
+    pub fn long_term_key(username: &str, realm: &str, password: &str) -> Vec<u8> {
+        md5::compute(format!("{}:{}:{}", username, realm, password)).0.to_vec()
+    }
+
+    fn hmac_sha1(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut mac = Hmac::<Sha1>::new_from_slice(key).unwrap();
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    pub fn encode(&self, integrity_key: Option<&[u8]>) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(STUN_HEADER_LENGTH + 64);
+        let method = self.method.code();
+        let class = self.class.code();
+        let msg_type = ((method & 0x0f80) << 2)
+            | ((method & 0x0070) << 1)
+            | (method & 0x000f)
+            | ((class & 0b10) << 7)
+            | ((class & 0b01) << 4);
+        buf.extend_from_slice(&msg_type.to_be_bytes());
+        buf.extend_from_slice(&[0u8, 0u8]);
+        buf.extend_from_slice(&STUN_MAGIC_COOKIE.to_be_bytes());
+        buf.extend_from_slice(&self.transaction_id);
+
+        for attr in &self.attributes {
+            match attr {
+                StunAttribute::Username(v) => Self::encode_attr(&mut buf, ATTR_USERNAME, v.as_bytes()),
+                StunAttribute::ErrorCode(code, reason) => {
+                    let mut value = vec![0u8, 0u8, (code / 100) as u8, (code % 100) as u8];
+                    value.extend_from_slice(reason.as_bytes());
+                    Self::encode_attr(&mut buf, ATTR_ERROR_CODE, &value)
+                },
+                StunAttribute::Lifetime(v) => Self::encode_attr(&mut buf, ATTR_LIFETIME, &v.to_be_bytes()),
+                StunAttribute::XorPeerAddress(addr) => {
+                    let value = self.xor_address(addr);
+                    Self::encode_attr(&mut buf, ATTR_XOR_PEER_ADDRESS, &value)
+                },
+                StunAttribute::Data(v) => Self::encode_attr(&mut buf, ATTR_DATA, v),
+                StunAttribute::Realm(v) => Self::encode_attr(&mut buf, ATTR_REALM, v.as_bytes()),
+                StunAttribute::Nonce(v) => Self::encode_attr(&mut buf, ATTR_NONCE, v.as_bytes()),
+                StunAttribute::XorRelayedAddress(addr) => {
+                    let value = self.xor_address(addr);
+                    Self::encode_attr(&mut buf, ATTR_XOR_RELAYED_ADDRESS, &value)
+                },
+                StunAttribute::RequestedTransport(v) => Self::encode_attr(&mut buf, ATTR_REQUESTED_TRANSPORT, &[*v, 0, 0, 0]),
+                StunAttribute::XorMappedAddress(addr) => {
+                    let value = self.xor_address(addr);
+                    Self::encode_attr(&mut buf, ATTR_XOR_MAPPED_ADDRESS, &value)
+                },
+                StunAttribute::MessageIntegrity(_) => {}
+            }
+        }
+
+        if let Some(key) = integrity_key {
+            // 计算hmac时length要包含MESSAGE-INTEGRITY自己
+            Self::set_length(&mut buf, buf.len() - STUN_HEADER_LENGTH + 24);
+            let hmac = Self::hmac_sha1(key, &buf);
+            Self::encode_attr(&mut buf, ATTR_MESSAGE_INTEGRITY, &hmac);
+        }
+        Self::set_length(&mut buf, buf.len() - STUN_HEADER_LENGTH);
+
+        buf
+    }
+
+    fn set_length(buf: &mut Vec<u8>, len: usize) {
+        buf[2..4].copy_from_slice(&(len as u16).to_be_bytes());
+    }
+
+    fn encode_attr(buf: &mut Vec<u8>, attr_type: u16, value: &[u8]) {
+        buf.extend_from_slice(&attr_type.to_be_bytes());
+        buf.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        buf.extend_from_slice(value);
+        let padding = (4 - value.len() % 4) % 4;
+        buf.extend_from_slice(&[0u8; 3][..padding]);
+    }
+
+    fn xor_address(&self, addr: &SocketAddr) -> Vec<u8> {
+        let cookie = STUN_MAGIC_COOKIE.to_be_bytes();
+        let port = addr.port() ^ (STUN_MAGIC_COOKIE >> 16) as u16;
+        let mut value = vec![0u8];
+        match addr.ip() {
+            IpAddr::V4(ip) => {
+                value.push(0x01);
+                value.extend_from_slice(&port.to_be_bytes());
+                for (i, b) in ip.octets().iter().enumerate() {
+                    value.push(b ^ cookie[i]);
+                }
+            },
+            IpAddr::V6(ip) => {
+                value.push(0x02);
+                value.extend_from_slice(&port.to_be_bytes());
+                let mut mask = cookie.to_vec();
+                mask.extend_from_slice(&self.transaction_id);
+                for (i, b) in ip.octets().iter().enumerate() {
+                    value.push(b ^ mask[i]);
+                }
+            }
+        }
+        value
+    }
+
+    fn decode_xor_address(value: &[u8], transaction_id: &StunTransactionId) -> BuckyResult<SocketAddr> {
+        let invalid = || BuckyError::new(BuckyErrorCode::InvalidData, "invalid stun xor address");
+        if value.len() < 8 {
+            return Err(invalid());
+        }
+        let cookie = STUN_MAGIC_COOKIE.to_be_bytes();
+        let port = u16::from_be_bytes([value[2], value[3]]) ^ (STUN_MAGIC_COOKIE >> 16) as u16;
+        match value[1] {
+            0x01 => {
+                let mut octets = [0u8; 4];
+                for i in 0..4 {
+                    octets[i] = value[4 + i] ^ cookie[i];
+                }
+                Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::from(octets)), port))
+            },
+            0x02 => {
+                if value.len() < 20 {
+                    return Err(invalid());
+                }
+                let mut mask = cookie.to_vec();
+                mask.extend_from_slice(transaction_id);
+                let mut octets = [0u8; 16];
+                for i in 0..16 {
+                    octets[i] = value[4 + i] ^ mask[i];
+                }
+                Ok(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port))
+            },
+            _ => Err(invalid())
+        }
+    }
+
+    pub fn decode(buf: &[u8]) -> BuckyResult<Self> {
+        if !Self::is_stun(buf) {
+            return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, "not stun message"));
+        }
+        let msg_type = u16::from_be_bytes([buf[0], buf[1]]);
+        let length = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        if STUN_HEADER_LENGTH + length > buf.len() {
+            return Err(BuckyError::new(BuckyErrorCode::InvalidData, "stun message length mismatch"));
+        }
+        let method = (msg_type & 0x000f) | ((msg_type & 0x00e0) >> 1) | ((msg_type & 0x3e00) >> 2);
+        let class = ((msg_type >> 4) & 0b01) | ((msg_type >> 7) & 0b10);
+        let mut transaction_id = [0u8; 12];
+        transaction_id.copy_from_slice(&buf[8..STUN_HEADER_LENGTH]);
+
+        let mut msg = Self::with_transaction(StunMethod::from_code(method)?, StunClass::from_code(class), transaction_id);
+
+        let utf8 = |v: &[u8]| String::from_utf8(v.to_vec())
+            .map_err(|_| BuckyError::new(BuckyErrorCode::InvalidData, "invalid stun string attribute"));
+
+        let end = STUN_HEADER_LENGTH + length;
+        let mut offset = STUN_HEADER_LENGTH;
+        while offset + 4 <= end {
+            let attr_type = u16::from_be_bytes([buf[offset], buf[offset + 1]]);
+            let attr_len = u16::from_be_bytes([buf[offset + 2], buf[offset + 3]]) as usize;
+            let value_start = offset + 4;
+            if value_start + attr_len > end {
+                return Err(BuckyError::new(BuckyErrorCode::InvalidData, "stun attribute length mismatch"));
+            }
+            let value = &buf[value_start..value_start + attr_len];
+            let attr = match attr_type {
+                ATTR_USERNAME => Some(StunAttribute::Username(utf8(value)?)),
+                ATTR_MESSAGE_INTEGRITY => Some(StunAttribute::MessageIntegrity(value.to_vec())),
+                ATTR_ERROR_CODE => {
+                    if value.len() < 4 {
+                        return Err(BuckyError::new(BuckyErrorCode::InvalidData, "invalid stun error code"));
+                    }
+                    let code = (value[2] & 0x07) as u16 * 100 + value[3] as u16;
+                    Some(StunAttribute::ErrorCode(code, utf8(&value[4..])?))
+                },
+                ATTR_LIFETIME if value.len() == 4 => Some(StunAttribute::Lifetime(u32::from_be_bytes([value[0], value[1], value[2], value[3]]))),
+                ATTR_XOR_PEER_ADDRESS => Some(StunAttribute::XorPeerAddress(Self::decode_xor_address(value, &transaction_id)?)),
+                ATTR_DATA => Some(StunAttribute::Data(value.to_vec())),
+                ATTR_REALM => Some(StunAttribute::Realm(utf8(value)?)),
+                ATTR_NONCE => Some(StunAttribute::Nonce(utf8(value)?)),
+                ATTR_XOR_RELAYED_ADDRESS => Some(StunAttribute::XorRelayedAddress(Self::decode_xor_address(value, &transaction_id)?)),
+                ATTR_REQUESTED_TRANSPORT if value.len() == 4 => Some(StunAttribute::RequestedTransport(value[0])),
+                ATTR_XOR_MAPPED_ADDRESS => Some(StunAttribute::XorMappedAddress(Self::decode_xor_address(value, &transaction_id)?)),
+                // 不认识的属性直接忽略
+                _ => None
+            };
+            if let Some(attr) = attr {
+                msg.attributes.push(attr);
+            }
+            offset = value_start + attr_len + (4 - attr_len % 4) % 4;
+        }
+
+        Ok(msg)
+    }
+
+    // 校验MESSAGE-INTEGRITY，没有该属性时返回false
+    pub fn check_integrity(buf: &[u8], key: &[u8]) -> bool {
+        if !Self::is_stun(buf) {
+            return false;
+        }
+        let length = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        let end = std::cmp::min(STUN_HEADER_LENGTH + length, buf.len());
+        let mut offset = STUN_HEADER_LENGTH;
+        while offset + 4 <= end {
+            let attr_type = u16::from_be_bytes([buf[offset], buf[offset + 1]]);
+            let attr_len = u16::from_be_bytes([buf[offset + 2], buf[offset + 3]]) as usize;
+            if attr_type == ATTR_MESSAGE_INTEGRITY {
+                if attr_len != 20 || offset + 24 > end {
+                    return false;
+                }
+                let mut data = buf[..offset].to_vec();
+                let len = (offset + 24 - STUN_HEADER_LENGTH) as u16;
+                data[2..4].copy_from_slice(&len.to_be_bytes());
+                return Self::hmac_sha1(key, &data) == buf[offset + 4..offset + 24].to_vec();
+            }
+            offset += 4 + attr_len + (4 - attr_len % 4) % 4;
+        }
+        false
+    }
+
+    pub fn xor_peer_address(&self) -> Option<SocketAddr> {
+        self.attributes.iter().find_map(|attr| if let StunAttribute::XorPeerAddress(addr) = attr { Some(*addr) } else { None })
+    }
+
+    pub fn xor_peer_addresses(&self) -> Vec<SocketAddr> {
+        self.attributes.iter().filter_map(|attr| if let StunAttribute::XorPeerAddress(addr) = attr { Some(*addr) } else { None }).collect()
+    }
+
+    pub fn xor_relayed_address(&self) -> Option<SocketAddr> {
+        self.attributes.iter().find_map(|attr| if let StunAttribute::XorRelayedAddress(addr) = attr { Some(*addr) } else { None })
+    }
+
+    pub fn data(&self) -> Option<&[u8]> {
+        self.attributes.iter().find_map(|attr| if let StunAttribute::Data(data) = attr { Some(data.as_slice()) } else { None })
+    }
+
+    pub fn take_data(&mut self) -> Option<Vec<u8>> {
+        let index = self.attributes.iter().position(|attr| if let StunAttribute::Data(_) = attr { true } else { false })?;
+        if let StunAttribute::Data(data) = self.attributes.remove(index) {
+            Some(data)
+        } else {
+            None
+        }
+    }
+
+    pub fn lifetime(&self) -> Option<u32> {
+        self.attributes.iter().find_map(|attr| if let StunAttribute::Lifetime(v) = attr { Some(*v) } else { None })
+    }
+
+    pub fn username(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|attr| if let StunAttribute::Username(v) = attr { Some(v.as_str()) } else { None })
+    }
+
+    pub fn realm(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|attr| if let StunAttribute::Realm(v) = attr { Some(v.as_str()) } else { None })
+    }
+
+    pub fn nonce(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|attr| if let StunAttribute::Nonce(v) = attr { Some(v.as_str()) } else { None })
+    }
+
+    pub fn error_code(&self) -> Option<(u16, &str)> {
+        self.attributes.iter().find_map(|attr| if let StunAttribute::ErrorCode(code, reason) = attr { Some((*code, reason.as_str())) } else { None })
+    }
+
+    // 通过TURN server发给peer的Send indication
+    pub fn send_indication(peer: &SocketAddr, data: &[u8]) -> Vec<u8> {
+        let mut msg = Self::new(StunMethod::Send, StunClass::Indication);
+        msg.add(StunAttribute::XorPeerAddress(*peer));
+        msg.add(StunAttribute::Data(data.to_vec()));
+        msg.encode(None)
+    }
+}
+
+
+#[derive(Clone, Debug)]
+pub struct TurnServer {
+    pub server: SocketAddr,
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Clone)]
+pub struct TurnConfig {
+    pub servers: Vec<TurnServer>,
+    // 申请的allocation有效期，到一半时refresh
+    pub lifetime: Duration,
+    // TURN server上permission的固定有效期
+    pub permission_lifetime: Duration,
+    pub resend_interval: Duration,
+    pub request_timeout: Duration,
+}
+
+struct TurnAuth {
+    realm: String,
+    nonce: String,
+    key: Vec<u8>
+}
+
+struct Allocation {
+    relayed: Endpoint,
+    expire_at: Timestamp
+}
+
+struct Transaction {
+    waiter: StateWaiter,
+    // 带credential的请求，成功响应必须能用这个key校验MESSAGE-INTEGRITY
+    integrity_key: Option<Vec<u8>>,
+    response: Option<StunMessage>
+}
+
+struct ClientState {
+    auth: Option<TurnAuth>,
+    allocation: Option<Allocation>,
+    permissions: HashMap<IpAddr, Timestamp>,
+    transactions: HashMap<StunTransactionId, Transaction>
+}
+
+struct TurnClientImpl {
+    config: TurnConfig,
+    server: TurnServer,
+    interface: Interface,
+    state: RwLock<ClientState>
+}
+
+// 在一个udp interface上向TURN server申请relay地址，并维护peer的permission
+#[derive(Clone)]
+pub struct TurnClient(Arc<TurnClientImpl>);
+
+impl std::fmt::Display for TurnClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TurnClient{{server:{}, local:{}}}", self.0.server.server, self.0.interface.local())
+    }
+}
+
+impl TurnClient {
+    pub(crate) fn open(config: TurnConfig, server: TurnServer, interface: Interface) -> Self {
+        let (sender, receiver) = channel::bounded(256);
+        interface.set_turn_server(server.server, config.permission_lifetime, sender);
+
+        let client = Self(Arc::new(TurnClientImpl {
+            config,
+            server,
+            interface,
+            state: RwLock::new(ClientState {
+                auth: None,
+                allocation: None,
+                permissions: HashMap::new(),
+                transactions: HashMap::new()
+            })
+        }));
+
+        {
+            let client = client.clone();
+            task::spawn(async move {
+                client.permission_loop(receiver).await;
+            });
+        }
+
+        client
+    }
+
+    // 第一次allocate完成后再返回，这样sn ping带出去的endpoint里就有relay地址了
+    pub(crate) async fn start(&self) {
+        let _ = self.allocate().await;
+
+        let client = self.clone();
+        task::spawn(async move {
+            client.refresh_loop().await;
+        });
+    }
+
+    pub fn server(&self) -> &TurnServer {
+        &self.0.server
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.0.interface
+    }
+
+    pub fn relayed(&self) -> Option<Endpoint> {
+        self.0.state.read().unwrap().allocation.as_ref().map(|a| a.relayed)
+    }
+
+    fn send_to_server(&self, buf: &[u8]) -> BuckyResult<usize> {
+        self.0.interface.send_buf_to(buf, &Endpoint::from((Protocol::Udp, self.0.server.server)))
+    }
+
+    // buf是msg的原始数据，用来校验MESSAGE-INTEGRITY
+    pub(crate) fn on_message(&self, msg: StunMessage, buf: &[u8]) {
+        let waiter = {
+            let mut state = self.0.state.write().unwrap();
+            if let Some(stub) = state.transactions.get_mut(&msg.transaction_id) {
+                // 成功响应会被直接使用，没有credential或者校验失败的都忽略，等待重发的请求收到真正的响应
+                let verified = msg.class != StunClass::SuccessResponse
+                    || stub.integrity_key.as_ref().map(|key| StunMessage::check_integrity(buf, key)).unwrap_or(false);
+                if !verified {
+                    warn!("{} ignore {:?} {:?} for message integrity check failed", self, msg.method, msg.class);
+                    None
+                } else if stub.response.is_none() {
+                    let waiter = stub.waiter.transfer();
+                    stub.response = Some(msg);
+                    Some(waiter)
+                } else {
+                    None
+                }
+            } else {
+                debug!("{} ignore {:?} {:?} for transaction not found", self, msg.method, msg.class);
+                None
+            }
+        };
+
+        if let Some(waiter) = waiter {
+            waiter.wake();
+        }
+    }
+
+    async fn request(&self, msg: StunMessage, integrity_key: Option<Vec<u8>>) -> BuckyResult<StunMessage> {
+        let buf = msg.encode(integrity_key.as_ref().map(|k| k.as_slice()));
+        let transaction_id = msg.transaction_id;
+        let waiter = {
+            let mut state = self.0.state.write().unwrap();
+            let mut stub = Transaction {
+                waiter: StateWaiter::new(),
+                integrity_key: integrity_key.clone(),
+                response: None
+            };
+            let waiter = stub.waiter.new_waiter();
+            state.transactions.insert(transaction_id, stub);
+            waiter
+        };
+
+        let client = self.clone();
+        let resend = async move {
+            let _ = future::timeout(client.0.config.request_timeout, async {
+                loop {
+                    trace!("{} send {:?} request", client, msg.method);
+                    let _ = client.send_to_server(&buf);
+                    task::sleep(client.0.config.resend_interval).await;
+                }
+            }).await;
+            BuckyError::new(BuckyErrorCode::Timeout, "turn request timeout")
+        };
+
+        let ret = StateWaiter::abort_wait(resend, waiter, || {
+            self.0.state.write().unwrap().transactions.remove(&transaction_id).and_then(|stub| stub.response)
+        }).await;
+        // 超时的时候transaction还在
+        self.0.state.write().unwrap().transactions.remove(&transaction_id);
+
+        ret?.ok_or_else(|| BuckyError::new(BuckyErrorCode::ErrorState, "turn transaction without response"))
+    }
+
+    // 带long-term credential的请求，收到401/438时用server给的realm和nonce重试一次
+    async fn auth_request<F: Fn() -> StunMessage>(&self, build: F) -> BuckyResult<StunMessage> {
+        let mut retried = false;
+        loop {
+            let mut req = build();
+            let method = req.method;
+            let key = {
+                let state = self.0.state.read().unwrap();
+                state.auth.as_ref().map(|auth| {
+                    req.add(StunAttribute::Username(self.0.server.username.clone()));
+                    req.add(StunAttribute::Realm(auth.realm.clone()));
+                    req.add(StunAttribute::Nonce(auth.nonce.clone()));
+                    auth.key.clone()
+                })
+            };
+
+            let resp = self.request(req, key).await.map_err(|err| {
+                error!("{} {:?} failed for {}", self, method, err);
+                err
+            })?;
+            match resp.class {
+                StunClass::SuccessResponse => return Ok(resp),
+                StunClass::ErrorResponse => {
+                    let (code, reason) = resp.error_code().unwrap_or((500, ""));
+                    if (code == 401 || code == 438) && !retried {
+                        if let (Some(realm), Some(nonce)) = (resp.realm(), resp.nonce()) {
+                            debug!("{} {:?} retry with realm {} for {}", self, method, realm, code);
+                            retried = true;
+                            self.0.state.write().unwrap().auth = Some(TurnAuth {
+                                realm: realm.to_owned(),
+                                nonce: nonce.to_owned(),
+                                key: StunMessage::long_term_key(&self.0.server.username, realm, &self.0.server.password)
+                            });
+                            continue;
+                        }
+                    }
+                    let err_code = match code {
+                        401 | 403 => BuckyErrorCode::PermissionDenied,
+                        486 | 508 => BuckyErrorCode::OutOfLimit,
+                        _ => BuckyErrorCode::Failed
+                    };
+                    let msg = format!("{} {:?} failed for {} {}", self, method, code, reason);
+                    error!("{}", msg);
+                    return Err(BuckyError::new(err_code, msg));
+                },
+                _ => {
+                    let msg = format!("{} {:?} got unexpected {:?}", self, method, resp.class);
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
+                }
+            }
+        }
+    }
+
+    async fn allocate(&self) -> BuckyResult<Endpoint> {
+        let lifetime = self.0.config.lifetime.as_secs() as u32;
+        let resp = self.auth_request(|| {
+            let mut req = StunMessage::new(StunMethod::Allocate, StunClass::Request);
+            req.add(StunAttribute::RequestedTransport(TURN_TRANSPORT_UDP));
+            req.add(StunAttribute::Lifetime(lifetime));
+            req
+        }).await?;
+
+        let relayed = resp.xor_relayed_address()
+            .ok_or_else(|| BuckyError::new(BuckyErrorCode::InvalidData, "allocate response without relayed address"))?;
+        let relayed = Endpoint::from((Protocol::Udp, relayed));
+        let lifetime = resp.lifetime().unwrap_or(lifetime);
+        {
+            let mut state = self.0.state.write().unwrap();
+            state.allocation = Some(Allocation {
+                relayed,
+                expire_at: bucky_time_now() + Duration::from_secs(lifetime as u64).as_micros() as u64
+            });
+            state.permissions.clear();
+        }
+        self.0.interface.set_turn_relayed(Some(relayed));
+        info!("{} allocated relayed endpoint {} lifetime {}s", self, relayed, lifetime);
+
+        Ok(relayed)
+    }
+
+    async fn refresh(&self) -> BuckyResult<()> {
+        let lifetime = self.0.config.lifetime.as_secs() as u32;
+        let resp = self.auth_request(|| {
+            let mut req = StunMessage::new(StunMethod::Refresh, StunClass::Request);
+            req.add(StunAttribute::Lifetime(lifetime));
+            req
+        }).await?;
+        let lifetime = resp.lifetime().unwrap_or(lifetime);
+        if let Some(allocation) = self.0.state.write().unwrap().allocation.as_mut() {
+            allocation.expire_at = bucky_time_now() + Duration::from_secs(lifetime as u64).as_micros() as u64;
+        }
+        debug!("{} refreshed lifetime {}s", self, lifetime);
+        Ok(())
+    }
+
+    fn reset_allocation(&self) {
+        {
+            let mut state = self.0.state.write().unwrap();
+            state.allocation = None;
+            state.permissions.clear();
+        }
+        self.0.interface.set_turn_relayed(None);
+    }
+
+    async fn create_permission(&self, peers: &[IpAddr]) -> BuckyResult<()> {
+        let peers: Vec<IpAddr> = {
+            let now = bucky_time_now();
+            let state = self.0.state.read().unwrap();
+            if state.allocation.is_none() {
+                return Err(BuckyError::new(BuckyErrorCode::ErrorState, "turn not allocated"));
+            }
+            peers.iter().filter(|ip| state.permissions.get(*ip).map(|expire_at| *expire_at <= now).unwrap_or(true)).cloned().collect()
+        };
+        if peers.len() == 0 {
+            return Ok(());
+        }
+
+        let _ = self.auth_request(|| {
+            let mut req = StunMessage::new(StunMethod::CreatePermission, StunClass::Request);
+            for ip in peers.iter() {
+                req.add(StunAttribute::XorPeerAddress(SocketAddr::new(*ip, 0)));
+            }
+            req
+        }).await?;
+
+        let expire_at = bucky_time_now() + self.0.config.permission_lifetime.as_micros() as u64;
+        let mut state = self.0.state.write().unwrap();
+        for ip in peers.iter() {
+            state.permissions.insert(*ip, expire_at);
+        }
+        debug!("{} created permission for {:?}", self, peers);
+        Ok(())
+    }
+
+    // interface第一次向某个ip发送数据时通知这里创建permission，这样对端发到relay地址的数据才能被TURN server转发过来
+    async fn permission_loop(&self, receiver: channel::Receiver<IpAddr>) {
+        while let Ok(ip) = receiver.recv().await {
+            let mut peers = vec![ip];
+            while let Ok(ip) = receiver.try_recv() {
+                if !peers.contains(&ip) {
+                    peers.push(ip);
+                }
+            }
+            if let Err(err) = self.create_permission(&peers).await {
+                debug!("{} create permission for {:?} failed for {}", self, peers, err);
+                for ip in peers.iter() {
+                    self.0.interface.remove_turn_permission(ip);
+                }
+            }
+        }
+    }
+
+    async fn refresh_loop(&self) {
+        let tick = Duration::from_secs(30);
+        loop {
+            let _ = future::timeout(tick, future::pending::<()>()).await;
+
+            let now = bucky_time_now();
+            let expire_at = self.0.state.read().unwrap().allocation.as_ref().map(|a| a.expire_at);
+            match expire_at {
+                Some(expire_at) => {
+                    let half = self.0.config.lifetime.as_micros() as u64 / 2;
+                    if expire_at < now + half {
+                        if self.refresh().await.is_err() {
+                            warn!("{} refresh failed, will allocate again", self);
+                            self.reset_allocation();
+                            let _ = self.allocate().await;
+                        }
+                    }
+                },
+                None => {
+                    let _ = self.allocate().await;
+                }
+            }
+
+            // 快过期的permission不主动续期，从interface上移除后下次发送时重新创建
+            let expired: Vec<IpAddr> = {
+                let margin = tick.as_micros() as u64 * 2;
+                let mut state = self.0.state.write().unwrap();
+                let expired = state.permissions.iter().filter(|(_, expire_at)| **expire_at < now + margin).map(|(ip, _)| *ip).collect();
+                for ip in &expired {
+                    state.permissions.remove(ip);
+                }
+                expired
+            };
+            for ip in expired.iter() {
+                self.0.interface.remove_turn_permission(ip);
+            }
+        }
+    }
+}
@@ -1,7 +1,15 @@
 use cyfs_base::*;
-use super::proxy::ProxyDeviceStub;
+use super::{
+    proxy::ProxyDeviceStub, 
+    usage::ProxyUsageReport
+};
 
 #[async_trait::async_trait]
 pub trait ProxyServiceEvents: Send + Sync {
     async fn pre_create_tunnel(&self, mix_key: &AesKey, device_pair: &(ProxyDeviceStub, ProxyDeviceStub)) -> BuckyResult<()>;
+
+    // 定期上报上次上报之后各device的中转流量
+    async fn on_usage(&self, _usage: &[ProxyUsageReport]) -> BuckyResult<()> {
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -2,8 +2,10 @@ mod command;
 pub mod proxy;
 mod service;
 mod events;
+mod usage;
 
 
 pub use service::{Service, Config};
 pub use proxy::ProxyDeviceStub;
-pub use events::ProxyServiceEvents;
\ No newline at end of file
+pub use events::ProxyServiceEvents;
+pub use usage::{ProxyLimit, ProxyLimitConfig, ProxyDeviceUsage, ProxyUsageReport};
\ No newline at end of file
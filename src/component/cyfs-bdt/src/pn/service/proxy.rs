@@ -16,17 +16,21 @@ use crate::{
     types::*, 
     interface::udp::MTU_LARGE
 };
+use super::usage::*;
 use std::time::{UNIX_EPOCH, SystemTime};
 
 #[derive(Clone)]
 pub struct Config {
-    pub keepalive: Duration
+    pub keepalive: Duration, 
+    pub limit: ProxyLimitConfig
 }
 
 #[derive(Clone, Debug)]
 pub struct ProxyDeviceStub {
     pub id: DeviceId, 
     pub timestamp: Timestamp, 
+    // 验证过desc签名的owner
+    pub owner: Option<ObjectId>, 
 }
 
 #[derive(Clone, Debug)]
@@ -38,6 +42,8 @@ pub struct ProxyEndpointStub {
 #[derive(Clone)]
 struct ProxyTunnel {
     device_pair: (ProxyDeviceStub, ProxyDeviceStub), 
+    // 创建时计入限制的owner，移除时按这个集合扣减
+    owners: Vec<ObjectId>, 
     endpoint_pair: (Option<ProxyEndpointStub>, Option<ProxyEndpointStub>), 
     last_active: Timestamp
 }
@@ -49,9 +55,10 @@ impl std::fmt::Display for ProxyTunnel {
 }
 
 impl ProxyTunnel {
-    fn new(device_pair: (ProxyDeviceStub, ProxyDeviceStub)) -> Self {
+    fn new(device_pair: (ProxyDeviceStub, ProxyDeviceStub), owners: Vec<ObjectId>) -> Self {
         Self {
             device_pair, 
+            owners, 
             endpoint_pair: (None, None), 
             last_active: bucky_time_now()
         }
@@ -201,10 +208,11 @@ struct TunnelsManager {
     tunnel_mixkey_list: LinkedList<TunnelMixHash>,
     keepalive: Duration,
     mixhash_live_minutes: u64,
+    accounting: ProxyAccounting,
 }
 
 impl TunnelsManager {
-    pub fn default() -> Self {
+    pub fn new(limit: ProxyLimitConfig) -> Self {
         let def_keepalive = 60;
         let def_mixhash_live_minute = 31;
 
@@ -213,6 +221,7 @@ impl TunnelsManager {
             tunnel_mixkey_list: LinkedList::new(),
             keepalive: Duration::from_secs(def_keepalive),
             mixhash_live_minutes: def_mixhash_live_minute,
+            accounting: ProxyAccounting::new(limit),
         }
     }
 }
@@ -256,7 +265,10 @@ impl TunnelsManager {
     }
 
     fn mixkey_add(&mut self,  mix_key: AesKey, device_pair: (ProxyDeviceStub, ProxyDeviceStub)) -> BuckyResult<()> {
-        let mut tunnel = TunnelMixHash::new(mix_key.clone(), ProxyTunnel::new(device_pair));
+        let owners = self.accounting.check_tunnel(&device_pair)?;
+        self.accounting.on_tunnel_created(&device_pair, owners.as_slice());
+
+        let mut tunnel = TunnelMixHash::new(mix_key.clone(), ProxyTunnel::new(device_pair, owners));
 
         let (min, max) = self.minute_timestamp_range();
         let (added, _) = tunnel.rehash(min, max);
@@ -287,7 +299,13 @@ impl TunnelsManager {
                 mix_hash.as_mut()[0] &= 0x7f;
                 if let Some(tunnel) = self.tunnel_mixhash_map.get_mut(&mix_hash) {
                     trace!("{} recv datagram of mix_hash: {}", tunnel.tunnel, mix_hash);
-                    tunnel.tunnel.on_proxied_datagram(&mix_hash, from)
+                    let proxy_to = tunnel.tunnel.on_proxied_datagram(&mix_hash, from);
+                    if proxy_to.is_some() && !self.accounting.on_datagram(&tunnel.tunnel.device_pair, tunnel.tunnel.owners.as_slice(), datagram.len()) {
+                        trace!("{} drop datagram of mix_hash: {} for bandwidth limit", tunnel.tunnel, mix_hash);
+                        None
+                    } else {
+                        proxy_to
+                    }
                 } else {
                     trace!("ignore datagram of mix_hash: {}", mix_hash);
                     None
@@ -340,6 +358,7 @@ impl TunnelsManager {
             let mut last_part = self.tunnel_mixkey_list.split_off(*removed.get(i).unwrap());
             let tunnel = last_part.pop_front().unwrap();
             self.tunnel_mixkey_list.append(&mut last_part);
+            self.accounting.on_tunnel_removed(&tunnel.tunnel.device_pair, tunnel.tunnel.owners.as_slice());
 
             self.tunnel_mixhash_map.remove(&tunnel.mix_key.mix_hash(None));
             for i in 0..tunnel.mixhash.len() {
@@ -378,10 +397,10 @@ impl ProxyInterface {
                 e
             })?;
         let interface = Self(Arc::new(ProxyInterfaceImpl {
-            config, 
             socket, 
             outer: outer.unwrap_or(local), 
-            tunnels: Mutex::new(TunnelsManager::default()),
+            tunnels: Mutex::new(TunnelsManager::new(config.limit.clone())),
+            config, 
         }));
 
         let num_cpus = 4;
@@ -472,6 +491,26 @@ impl ProxyInterface {
     fn create_tunnel(&self, mix_key: AesKey, device_pair: (ProxyDeviceStub, ProxyDeviceStub)) -> BuckyResult<()> {
         self.0.tunnels.lock().unwrap().create_tunnel(mix_key, device_pair)
     }
+
+    fn verified_owner(&self, device: &Device) -> Option<ObjectId> {
+        self.0.config.limit.verified_owner(device)
+    }
+
+    fn usage_of(&self, device: &DeviceId) -> Option<ProxyDeviceUsage> {
+        self.0.tunnels.lock().unwrap().accounting.usage_of(device)
+    }
+
+    fn usage_list(&self) -> Vec<ProxyDeviceUsage> {
+        self.0.tunnels.lock().unwrap().accounting.usage_list()
+    }
+
+    fn reset_usage(&self, device: Option<&DeviceId>) {
+        self.0.tunnels.lock().unwrap().accounting.reset_usage(device)
+    }
+
+    fn take_usage_report(&self) -> Vec<ProxyUsageReport> {
+        self.0.tunnels.lock().unwrap().accounting.take_report()
+    }
 }
 
 pub struct ProxyTunnelManager {
@@ -503,4 +542,24 @@ impl ProxyTunnelManager {
         self.interface.has_tunnel(key);
         Some(self.interface.outer().clone())
     }
+
+    pub fn verified_owner(&self, device: &Device) -> Option<ObjectId> {
+        self.interface.verified_owner(device)
+    }
+
+    pub fn usage_of(&self, device: &DeviceId) -> Option<ProxyDeviceUsage> {
+        self.interface.usage_of(device)
+    }
+
+    pub fn usage_list(&self) -> Vec<ProxyDeviceUsage> {
+        self.interface.usage_list()
+    }
+
+    pub fn reset_usage(&self, device: Option<&DeviceId>) {
+        self.interface.reset_usage(device)
+    }
+
+    pub fn take_usage_report(&self) -> Vec<ProxyUsageReport> {
+        self.interface.take_usage_report()
+    }
 }
\ No newline at end of file
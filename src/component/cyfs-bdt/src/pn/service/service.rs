@@ -14,12 +14,23 @@ use crate::{
 use super::{
     command::*, 
     proxy::{self, ProxyTunnelManager, ProxyDeviceStub}, 
-    events::ProxyServiceEvents
+    events::ProxyServiceEvents, 
+    usage::*
 };
 
 pub struct Config {
     keystore: keystore::Config, 
-    tunnel: proxy::Config
+    tunnel: proxy::Config, 
+    // 向events上报流量增量的间隔
+    usage_report_interval: Duration
+}
+
+impl Config {
+    pub fn with_limit(limit: ProxyLimitConfig) -> Self {
+        let mut config = Self::default();
+        config.tunnel.limit = limit;
+        config
+    }
 }
 
 impl Default for Config {
@@ -30,8 +41,10 @@ impl Default for Config {
                 capacity: 10000,
             }, 
             tunnel: proxy::Config {
-                keepalive: Duration::from_secs(5 * 60)
-            }
+                keepalive: Duration::from_secs(5 * 60), 
+                limit: ProxyLimitConfig::default()
+            }, 
+            usage_report_interval: Duration::from_secs(60)
         }
     }
 }
@@ -105,9 +118,51 @@ impl Service {
         let service_impl = unsafe { &mut *(Arc::as_ptr(&service.0) as *mut ServiceImpl) };
         service_impl.command_tunnel = Some(CommandTunnel::open(service.to_weak(), command_port)?);
 
+        {
+            let weak = service.to_weak();
+            let interval = config.usage_report_interval;
+            task::spawn(async move {
+                Self::report_usage_loop(weak, interval).await;
+            });
+        }
+
         Ok(service)
     }
 
+    async fn report_usage_loop(weak: WeakService, interval: Duration) {
+        loop {
+            task::sleep(interval).await;
+            if let Some(service) = weak.0.upgrade().map(|s| Self(s)) {
+                service.report_usage().await;
+            } else {
+                break;
+            }
+        }
+    }
+
+    async fn report_usage(&self) {
+        let report = self.proxy_tunnels().take_usage_report();
+        if report.len() > 0 {
+            trace!("{} report usage of {} devices", self, report.len());
+            if let Err(err) = self.events().on_usage(report.as_slice()).await {
+                warn!("{} report usage failed for {}", self, err);
+            }
+        }
+    }
+
+    // 内存中的实时统计，持久化的累计值由events的实现负责
+    pub fn usage_of(&self, device: &DeviceId) -> Option<ProxyDeviceUsage> {
+        self.proxy_tunnels().usage_of(device)
+    }
+
+    pub fn usage_list(&self) -> Vec<ProxyDeviceUsage> {
+        self.proxy_tunnels().usage_list()
+    }
+
+    pub fn reset_usage(&self, device: Option<&DeviceId>) {
+        self.proxy_tunnels().reset_usage(device)
+    }
+
     pub(super) fn command_tunnel(&self) -> &CommandTunnel {
         &self.0.command_tunnel.as_ref().unwrap()
     }
@@ -143,10 +198,13 @@ impl OnPackage<SynProxy, (&PackageBox, &SocketAddr)> for Service {
             let stub_pair = (ProxyDeviceStub {
                     id: syn_proxy.from_peer_info.desc().device_id(), 
                     timestamp: syn_proxy.from_peer_info.body().as_ref().unwrap().update_time(), 
+                    owner: service.proxy_tunnels().verified_owner(&syn_proxy.from_peer_info), 
                 },
                 ProxyDeviceStub {
                     id: syn_proxy.to_peer_id.clone(), 
                     timestamp: syn_proxy.to_peer_timestamp, 
+                    // 只有device id，由accounting按之前记录的owner统计
+                    owner: None, 
                 }
             );
             
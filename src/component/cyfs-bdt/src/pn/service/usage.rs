@@ -0,0 +1,525 @@
+use std::{
+    collections::HashMap,
+    time::Duration,
+};
+use cyfs_base::*;
+use crate::types::*;
+use super::proxy::ProxyDeviceStub;
+
+// 单个device或者owner的中转限制，None表示不限制
+#[derive(Clone, Debug, Default)]
+pub struct ProxyLimit {
+    // 每秒最多中转的字节数
+    pub bandwidth: Option<u64>,
+    // 同时存在的proxy tunnel数
+    pub max_tunnels: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProxyLimitConfig {
+    pub device: ProxyLimit,
+    pub owner: ProxyLimit,
+    // 可信的owner公钥，device的desc有对应owner签名时才按该owner统计
+    pub owner_keys: HashMap<ObjectId, PublicKey>,
+}
+
+impl ProxyLimitConfig {
+    pub fn has_owner_limit(&self) -> bool {
+        self.owner.bandwidth.is_some() || self.owner.max_tunnels.is_some()
+    }
+
+    // 返回desc声明并且有签名的owner，没有声明、未知的owner或者签名不对都返回None
+    pub fn verified_owner(&self, device: &Device) -> Option<ObjectId> {
+        let owner = device.desc().owner().as_ref()?;
+        let public_key = self.owner_keys.get(owner)?;
+        let hash = device.desc().raw_hash_value().ok()?;
+
+        let signed = device
+            .signs()
+            .desc_signs()
+            .map(|signs| signs.iter().any(|sign| public_key.verify(hash.as_slice(), sign)))
+            .unwrap_or(false);
+        if signed {
+            Some(owner.clone())
+        } else {
+            warn!("proxy device desc not signed by owner! device={}, owner={}", device.desc().device_id(), owner);
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProxyDeviceUsage {
+    pub device: DeviceId,
+    pub owner: Option<ObjectId>,
+    pub tunnels: usize,
+    // 经过该device参与的tunnel中转的字节数
+    pub bytes: u64,
+    // 超过带宽限制被丢弃的字节数
+    pub dropped_bytes: u64,
+}
+
+// 上次上报之后的增量
+#[derive(Clone, Debug)]
+pub struct ProxyUsageReport {
+    pub device: DeviceId,
+    pub owner: Option<ObjectId>,
+    pub bytes: u64,
+    pub dropped_bytes: u64,
+}
+
+struct RateLimiter {
+    rate: u64,
+    tokens: u64,
+    last_update: Timestamp,
+}
+
+impl RateLimiter {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate,
+            tokens: rate,
+            last_update: bucky_time_now()
+        }
+    }
+
+    fn refill(&mut self, now: Timestamp) {
+        if now > self.last_update {
+            let elapsed = Duration::from_micros(now - self.last_update);
+            let refill = (self.rate as u128 * elapsed.as_micros() / 1_000_000) as u64;
+            if refill > 0 {
+                // 最多允许1秒的突发
+                self.tokens = std::cmp::min(self.rate, self.tokens.saturating_add(refill));
+                self.last_update = now;
+            }
+        }
+    }
+
+    fn can_consume(&mut self, now: Timestamp, len: u64) -> bool {
+        self.refill(now);
+        self.tokens >= len
+    }
+
+    fn consume(&mut self, len: u64) {
+        self.tokens = self.tokens.saturating_sub(len);
+    }
+}
+
+struct DeviceAccount {
+    owner: Option<ObjectId>,
+    tunnels: usize,
+    bytes: u64,
+    dropped_bytes: u64,
+    unreported_bytes: u64,
+    unreported_dropped_bytes: u64,
+    limiter: Option<RateLimiter>,
+}
+
+struct OwnerAccount {
+    tunnels: usize,
+    limiter: Option<RateLimiter>,
+}
+
+pub(super) struct ProxyAccounting {
+    limit: ProxyLimitConfig,
+    devices: HashMap<DeviceId, DeviceAccount>,
+    owners: HashMap<ObjectId, OwnerAccount>,
+}
+
+impl ProxyAccounting {
+    pub fn new(limit: ProxyLimitConfig) -> Self {
+        Self {
+            limit,
+            devices: HashMap::new(),
+            owners: HashMap::new(),
+        }
+    }
+
+    fn device_account(&mut self, stub: &ProxyDeviceStub) -> &mut DeviceAccount {
+        let limit = &self.limit.device;
+        let account = self.devices.entry(stub.id.clone()).or_insert_with(|| DeviceAccount {
+            owner: None,
+            tunnels: 0,
+            bytes: 0,
+            dropped_bytes: 0,
+            unreported_bytes: 0,
+            unreported_dropped_bytes: 0,
+            limiter: limit.bandwidth.map(|rate| RateLimiter::new(rate)),
+        });
+        // 只有发起syn proxy的一方带有验证过的owner
+        if stub.owner.is_some() {
+            account.owner = stub.owner.clone();
+        }
+        account
+    }
+
+    fn owner_account(&mut self, owner: &ObjectId) -> &mut OwnerAccount {
+        let limit = &self.limit.owner;
+        self.owners.entry(owner.clone()).or_insert_with(|| OwnerAccount {
+            tunnels: 0,
+            limiter: limit.bandwidth.map(|rate| RateLimiter::new(rate)),
+        })
+    }
+
+    // 参与tunnel的owner集合：验证过的owner，其次是之前记录的该device的owner，都没有时device自己作为owner
+    fn owners_of(&self, device_pair: &(ProxyDeviceStub, ProxyDeviceStub)) -> Vec<ObjectId> {
+        let mut owners = vec![];
+        for stub in [&device_pair.0, &device_pair.1] {
+            let owner = stub
+                .owner
+                .clone()
+                .or_else(|| self.devices.get(&stub.id).and_then(|account| account.owner.clone()))
+                .unwrap_or_else(|| stub.id.object_id().clone());
+            if !owners.contains(&owner) {
+                owners.push(owner);
+            }
+        }
+        owners
+    }
+
+    // 检查是否还能为device pair创建新的tunnel，返回tunnel计入的owner集合
+    pub fn check_tunnel(&mut self, device_pair: &(ProxyDeviceStub, ProxyDeviceStub)) -> BuckyResult<Vec<ObjectId>> {
+        // 有owner限制时，发起方必须有验证过的owner，否则不带owner就能绕过限制
+        if self.limit.has_owner_limit() && device_pair.0.owner.is_none() {
+            let msg = format!("proxy device {} without verified owner", device_pair.0.id);
+            warn!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+        }
+
+        if let Some(max) = self.limit.device.max_tunnels {
+            for stub in [&device_pair.0, &device_pair.1] {
+                if self.device_account(stub).tunnels >= max {
+                    let msg = format!("proxy tunnels of device {} reach limit {}", stub.id, max);
+                    warn!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+                }
+            }
+        }
+
+        let owners = self.owners_of(device_pair);
+        if let Some(max) = self.limit.owner.max_tunnels {
+            for owner in owners.iter() {
+                if self.owner_account(owner).tunnels >= max {
+                    let msg = format!("proxy tunnels of owner {} reach limit {}", owner, max);
+                    warn!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+                }
+            }
+        }
+
+        Ok(owners)
+    }
+
+    pub fn on_tunnel_created(&mut self, device_pair: &(ProxyDeviceStub, ProxyDeviceStub), owners: &[ObjectId]) {
+        self.device_account(&device_pair.0).tunnels += 1;
+        self.device_account(&device_pair.1).tunnels += 1;
+        for owner in owners {
+            self.owner_account(owner).tunnels += 1;
+        }
+    }
+
+    // owners是创建tunnel时计入的集合，期间device记录的owner变化不影响扣减
+    pub fn on_tunnel_removed(&mut self, device_pair: &(ProxyDeviceStub, ProxyDeviceStub), owners: &[ObjectId]) {
+        for owner in owners {
+            let account = self.owner_account(owner);
+            account.tunnels = account.tunnels.saturating_sub(1);
+        }
+        for stub in [&device_pair.0, &device_pair.1] {
+            let account = self.device_account(stub);
+            account.tunnels = account.tunnels.saturating_sub(1);
+        }
+    }
+
+    // 记录一个中转的datagram，超过带宽限制时返回false，该datagram应该被丢弃
+    pub fn on_datagram(&mut self, device_pair: &(ProxyDeviceStub, ProxyDeviceStub), owners: &[ObjectId], len: usize) -> bool {
+        let now = bucky_time_now();
+        let len = len as u64;
+
+        let mut allowed = true;
+        for stub in [&device_pair.0, &device_pair.1] {
+            if let Some(limiter) = self.device_account(stub).limiter.as_mut() {
+                allowed = allowed && limiter.can_consume(now, len);
+            }
+        }
+        for owner in owners {
+            if let Some(limiter) = self.owner_account(owner).limiter.as_mut() {
+                allowed = allowed && limiter.can_consume(now, len);
+            }
+        }
+
+        for stub in [&device_pair.0, &device_pair.1] {
+            let account = self.device_account(stub);
+            if allowed {
+                account.bytes += len;
+                account.unreported_bytes += len;
+                if let Some(limiter) = account.limiter.as_mut() {
+                    limiter.consume(len);
+                }
+            } else {
+                account.dropped_bytes += len;
+                account.unreported_dropped_bytes += len;
+            }
+        }
+        if allowed {
+            for owner in owners {
+                if let Some(limiter) = self.owner_account(owner).limiter.as_mut() {
+                    limiter.consume(len);
+                }
+            }
+        }
+
+        allowed
+    }
+
+    pub fn usage_of(&self, device: &DeviceId) -> Option<ProxyDeviceUsage> {
+        self.devices.get(device).map(|account| Self::to_usage(device, account))
+    }
+
+    pub fn usage_list(&self) -> Vec<ProxyDeviceUsage> {
+        self.devices.iter().map(|(device, account)| Self::to_usage(device, account)).collect()
+    }
+
+    fn to_usage(device: &DeviceId, account: &DeviceAccount) -> ProxyDeviceUsage {
+        ProxyDeviceUsage {
+            device: device.clone(),
+            owner: account.owner.clone(),
+            tunnels: account.tunnels,
+            bytes: account.bytes,
+            dropped_bytes: account.dropped_bytes,
+        }
+    }
+
+    // 清零字节计数，tunnel数是当前状态不清零
+    pub fn reset_usage(&mut self, device: Option<&DeviceId>) {
+        for (id, account) in self.devices.iter_mut() {
+            if device.map(|d| d == id).unwrap_or(true) {
+                account.bytes = 0;
+                account.dropped_bytes = 0;
+                account.unreported_bytes = 0;
+                account.unreported_dropped_bytes = 0;
+            }
+        }
+    }
+
+    pub fn take_report(&mut self) -> Vec<ProxyUsageReport> {
+        let mut report = vec![];
+        for (device, account) in self.devices.iter_mut() {
+            if account.unreported_bytes > 0 || account.unreported_dropped_bytes > 0 {
+                report.push(ProxyUsageReport {
+                    device: device.clone(),
+                    owner: account.owner.clone(),
+                    bytes: account.unreported_bytes,
+                    dropped_bytes: account.unreported_dropped_bytes,
+                });
+                account.unreported_bytes = 0;
+                account.unreported_dropped_bytes = 0;
+            }
+        }
+
+        // 已经上报过且没有tunnel的device不再保留，累计值由events持久化
+        self.devices.retain(|_, account| account.tunnels > 0);
+        self.owners.retain(|_, account| account.tunnels > 0);
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn new_people() -> (People, PrivateKey) {
+        let secret = PrivateKey::generate_rsa(1024).unwrap();
+        let people = People::new(None, vec![], secret.public(), None, None, None).build();
+        (people, secret)
+    }
+
+    fn new_device(owner: Option<ObjectId>) -> Device {
+        let secret = PrivateKey::generate_rsa(1024).unwrap();
+        Device::new(
+            owner,
+            UniqueId::default(),
+            vec![],
+            vec![],
+            vec![],
+            secret.public(),
+            Area::default(),
+            DeviceCategory::PC,
+        )
+        .build()
+    }
+
+    fn stub(device: &Device, owner: Option<&ObjectId>) -> ProxyDeviceStub {
+        ProxyDeviceStub {
+            id: device.desc().device_id(),
+            timestamp: 0,
+            owner: owner.cloned(),
+        }
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut limiter = RateLimiter::new(1000);
+        let now = limiter.last_update;
+
+        // 初始允许1秒的突发
+        assert!(limiter.can_consume(now, 1000));
+        limiter.consume(1000);
+        assert!(!limiter.can_consume(now, 1));
+
+        // 半秒后恢复一半
+        let now = now + 500 * 1000;
+        assert!(limiter.can_consume(now, 500));
+        assert!(!limiter.can_consume(now, 501));
+        limiter.consume(500);
+
+        // 长时间空闲也最多恢复到rate
+        let now = now + 10 * 1000 * 1000;
+        assert!(limiter.can_consume(now, 1000));
+        assert!(!limiter.can_consume(now, 1001));
+
+        // 时间回退不恢复
+        limiter.consume(1000);
+        assert!(!limiter.can_consume(now - 1000 * 1000, 1));
+    }
+
+    #[test]
+    fn test_device_limit() {
+        let mut limit = ProxyLimitConfig::default();
+        limit.device.max_tunnels = Some(1);
+        limit.device.bandwidth = Some(1000);
+        let mut accounting = ProxyAccounting::new(limit);
+
+        let a = new_device(None);
+        let b = new_device(None);
+        let c = new_device(None);
+        let ab = (stub(&a, None), stub(&b, None));
+        let ac = (stub(&a, None), stub(&c, None));
+
+        let owners = accounting.check_tunnel(&ab).unwrap();
+        accounting.on_tunnel_created(&ab, owners.as_slice());
+        assert_eq!(
+            accounting.check_tunnel(&ac).unwrap_err().code(),
+            BuckyErrorCode::OutOfLimit
+        );
+
+        assert!(accounting.on_datagram(&ab, owners.as_slice(), 600));
+        assert!(!accounting.on_datagram(&ab, owners.as_slice(), 600));
+        let usage = accounting.usage_of(&a.desc().device_id()).unwrap();
+        assert_eq!(usage.tunnels, 1);
+        assert_eq!(usage.bytes, 600);
+        assert_eq!(usage.dropped_bytes, 600);
+
+        let report = accounting.take_report();
+        assert_eq!(report.len(), 2);
+        assert!(accounting.take_report().is_empty());
+
+        accounting.on_tunnel_removed(&ab, owners.as_slice());
+        assert!(accounting.check_tunnel(&ac).is_ok());
+        // 没有tunnel的device上报后被清理
+        accounting.take_report();
+        assert!(accounting.usage_of(&a.desc().device_id()).is_none());
+    }
+
+    #[test]
+    fn test_owner_limit() {
+        let (owner, _) = new_people();
+        let owner_id = owner.desc().calculate_id();
+
+        let mut limit = ProxyLimitConfig::default();
+        limit.owner.max_tunnels = Some(1);
+        let mut accounting = ProxyAccounting::new(limit);
+
+        let a1 = new_device(Some(owner_id.clone()));
+        let a2 = new_device(Some(owner_id.clone()));
+        let b = new_device(None);
+        let c = new_device(None);
+
+        // 有owner限制时，没有验证过owner的发起方被拒绝
+        let unverified = (stub(&a1, None), stub(&b, None));
+        assert_eq!(
+            accounting.check_tunnel(&unverified).unwrap_err().code(),
+            BuckyErrorCode::PermissionDenied
+        );
+
+        let a1b = (stub(&a1, Some(&owner_id)), stub(&b, None));
+        let owners = accounting.check_tunnel(&a1b).unwrap();
+        assert!(owners.contains(&owner_id));
+        // 不知道owner的被叫按自己统计
+        assert!(owners.contains(b.desc().device_id().object_id()));
+        accounting.on_tunnel_created(&a1b, owners.as_slice());
+
+        let a2c = (stub(&a2, Some(&owner_id)), stub(&c, None));
+        assert_eq!(
+            accounting.check_tunnel(&a2c).unwrap_err().code(),
+            BuckyErrorCode::OutOfLimit
+        );
+
+        // 作为被叫时使用之前记录的owner
+        let ca1 = (stub(&c, None), stub(&a1, None));
+        assert!(accounting.owners_of(&ca1).contains(&owner_id));
+
+        accounting.on_tunnel_removed(&a1b, owners.as_slice());
+        assert!(accounting.check_tunnel(&a2c).is_ok());
+    }
+
+    #[test]
+    fn test_remove_by_created_owners() {
+        let (owner, _) = new_people();
+        let owner_id = owner.desc().calculate_id();
+        let mut accounting = ProxyAccounting::new(ProxyLimitConfig::default());
+
+        let a = new_device(None);
+        let b = new_device(Some(owner_id.clone()));
+
+        // b的owner未知时创建的tunnel计入b自己
+        let ab = (stub(&a, None), stub(&b, None));
+        let ab_owners = accounting.check_tunnel(&ab).unwrap();
+        accounting.on_tunnel_created(&ab, ab_owners.as_slice());
+
+        // 之后b带着验证过的owner发起tunnel
+        let ba = (stub(&b, Some(&owner_id)), stub(&a, None));
+        let ba_owners = accounting.check_tunnel(&ba).unwrap();
+        accounting.on_tunnel_created(&ba, ba_owners.as_slice());
+        assert!(accounting.owners_of(&ab).contains(&owner_id));
+
+        // 按创建时的集合扣减，不影响owner的计数
+        accounting.on_tunnel_removed(&ab, ab_owners.as_slice());
+        assert_eq!(accounting.owners.get(&owner_id).unwrap().tunnels, 1);
+        assert_eq!(
+            accounting.owners.get(b.desc().device_id().object_id()).unwrap().tunnels,
+            0
+        );
+        assert_eq!(accounting.owners.get(a.desc().device_id().object_id()).unwrap().tunnels, 1);
+    }
+
+    #[async_std::test]
+    async fn test_verified_owner() {
+        let (owner, owner_secret) = new_people();
+        let owner_id = owner.desc().calculate_id();
+        let (stranger, _) = new_people();
+
+        let mut limit = ProxyLimitConfig::default();
+        limit
+            .owner_keys
+            .insert(owner_id.clone(), owner.desc().public_key().clone());
+
+        let mut device = new_device(Some(owner_id.clone()));
+        assert!(limit.verified_owner(&device).is_none());
+
+        let signer = RsaCPUObjectSigner::new(owner_secret.public(), owner_secret);
+        sign_and_push_named_object_desc(
+            &signer,
+            &mut device,
+            &SignatureSource::RefIndex(SIGNATURE_SOURCE_REFINDEX_OWNER),
+        )
+        .await
+        .unwrap();
+        assert_eq!(limit.verified_owner(&device), Some(owner_id));
+
+        // 不在可信列表里的owner
+        let device = new_device(Some(stranger.desc().calculate_id()));
+        assert!(limit.verified_owner(&device).is_none());
+        assert!(limit.verified_owner(&new_device(None)).is_none());
+    }
+}
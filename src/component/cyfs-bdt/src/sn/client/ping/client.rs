@@ -286,7 +286,12 @@ impl PingClient {
 
     async fn update_local(&self, local: Endpoint, outer: Endpoint) {
         let update = self.net_listener().update_outer(&local, &outer);
-        if update > UpdateOuterResult::None {
+        // outer没变时本地endpoint也可能变化，比如turn relay地址重新分配了
+        let endpoints_changed = {
+            let bound_endpoints: Vec<Endpoint> = self.net_listener().endpoints().into_iter().collect();
+            self.local_device().connect_info().endpoints() != &bound_endpoints
+        };
+        if update > UpdateOuterResult::None || endpoints_changed {
             info!("{} update local {} => {}", self, local, outer);
             let mut local_dev = self.local_device();
             let device_sn_list = local_dev.mut_connect_info().mut_sn_list();
@@ -0,0 +1,534 @@
+use async_std::{
+    channel,
+    io::prelude::{ReadExt, WriteExt},
+    net::{TcpListener, TcpStream},
+    task,
+};
+use futures::StreamExt;
+use log::*;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+
+use cyfs_base::*;
+use cyfs_debug::Mutex;
+
+use crate::{
+    protocol::*,
+    types::*,
+};
+
+use super::SnService;
+
+// 单个集群包的最大长度，超过的认为是错误的连接
+const MAX_CLUSTER_PACKAGE_SIZE: usize = 1024 * 1024 * 4;
+// 集群包的时间戳和本地时间相差超过这个值就认为过期，窗口内用nonce去重防止重放
+const CLUSTER_PACKAGE_EXPIRE: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct SnClusterMember {
+    pub device: Device,
+    // 该sn用于集群间通信的tcp地址
+    pub addr: SocketAddr,
+}
+
+#[derive(Clone)]
+pub struct SnClusterConfig {
+    pub listen: SocketAddr,
+    // 集群内的所有sn，可以包含自己
+    pub members: Vec<SnClusterMember>,
+    // 向其它sn全量同步本地peer的间隔
+    pub sync_interval: Duration,
+    // 其它sn同步过来的peer，超过这个时间没有更新就被淘汰
+    pub peer_timeout: Duration,
+}
+
+impl SnClusterConfig {
+    pub fn new(listen: SocketAddr, members: Vec<SnClusterMember>) -> Self {
+        Self {
+            listen,
+            members,
+            sync_interval: Duration::from_secs(30),
+            peer_timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+#[derive(Clone, RawEncode, RawDecode)]
+pub(super) struct SnClusterPeer {
+    pub desc: Device,
+    // sn观察到的peer的外网地址
+    pub endpoints: Vec<Endpoint>,
+    pub last_ping: Timestamp,
+}
+
+#[derive(RawEncode, RawDecode)]
+pub(super) struct SnClusterCall {
+    pub protocol_version: u8,
+    pub stack_version: u32,
+    pub seq: TempSeq,
+    pub from_peer_id: DeviceId,
+    pub to_peer_id: DeviceId,
+    pub reverse_endpoint_array: Vec<Endpoint>,
+    pub active_pn_list: Vec<DeviceId>,
+    pub peer_info: Device,
+    pub send_time: Timestamp,
+    pub payload: Vec<u8>,
+    pub is_always_call: bool,
+}
+
+impl SnClusterCall {
+    pub fn take_from(call_req: &mut SnCall, from_peer_desc: Device) -> Self {
+        let mut payload = SizedOwnedData::from(vec![]);
+        std::mem::swap(&mut payload, &mut call_req.payload);
+
+        Self {
+            protocol_version: call_req.protocol_version,
+            stack_version: call_req.stack_version,
+            seq: call_req.seq,
+            from_peer_id: call_req.from_peer_id.clone(),
+            to_peer_id: call_req.to_peer_id.clone(),
+            reverse_endpoint_array: call_req.reverse_endpoint_array.take().unwrap_or_default(),
+            active_pn_list: call_req.active_pn_list.take().unwrap_or_default(),
+            peer_info: from_peer_desc,
+            send_time: call_req.send_time,
+            payload: payload.take(),
+            is_always_call: call_req.is_always_call,
+        }
+    }
+
+    pub fn into_call(self, sn_peer_id: DeviceId) -> SnCall {
+        SnCall {
+            protocol_version: self.protocol_version,
+            stack_version: self.stack_version,
+            seq: self.seq,
+            sn_peer_id,
+            to_peer_id: self.to_peer_id,
+            from_peer_id: self.from_peer_id,
+            reverse_endpoint_array: Some(self.reverse_endpoint_array),
+            active_pn_list: Some(self.active_pn_list),
+            peer_info: Some(self.peer_info),
+            send_time: self.send_time,
+            payload: SizedOwnedData::from(self.payload),
+            is_always_call: self.is_always_call,
+        }
+    }
+}
+
+#[derive(RawEncode, RawDecode)]
+enum SnClusterMessage {
+    // 新上线或者周期同步的peer
+    Register(Vec<SnClusterPeer>),
+    // 转发给被叫所在sn的call
+    Call(SnClusterCall),
+}
+
+#[derive(RawEncode, RawDecode)]
+struct SnClusterPackage {
+    from: DeviceId,
+    timestamp: Timestamp,
+    nonce: u64,
+    body: Vec<u8>,
+    sign: Signature,
+}
+
+impl SnClusterPackage {
+    // 签名覆盖发送者、时间戳、nonce和消息体
+    fn sign_hash(
+        from: &DeviceId,
+        timestamp: Timestamp,
+        nonce: u64,
+        body: &[u8],
+    ) -> BuckyResult<HashValue> {
+        let mut data = from.to_vec()?;
+        data.extend_from_slice(&timestamp.to_be_bytes());
+        data.extend_from_slice(&nonce.to_be_bytes());
+        data.extend_from_slice(body);
+
+        Ok(hash_data(&data))
+    }
+}
+
+struct RemotePeer {
+    desc: Device,
+    endpoints: Vec<Endpoint>,
+    sn: DeviceId,
+    last_ping: Timestamp,
+    update_time: Timestamp,
+}
+
+pub(super) struct FoundRemotePeer {
+    pub desc: Device,
+    // 所在sn观察到的外网地址
+    pub endpoints: Vec<Endpoint>,
+    pub sn: DeviceId,
+}
+
+impl FoundRemotePeer {
+    // 把sn观察到的地址合并进desc，返回给主叫
+    pub fn desc_with_endpoints(&self) -> Device {
+        let mut desc = self.desc.clone();
+        let eps = desc.mut_connect_info().mut_endpoints();
+        for ep in &self.endpoints {
+            if !eps.contains(ep) {
+                eps.push(ep.clone());
+            }
+        }
+
+        desc
+    }
+}
+
+struct ClusterMember {
+    public_key: PublicKey,
+    sender: channel::Sender<Vec<u8>>,
+}
+
+struct ClusterImpl {
+    local_device_id: DeviceId,
+    secret: PrivateKey,
+    config: SnClusterConfig,
+    members: HashMap<DeviceId, ClusterMember>,
+    peers: Mutex<HashMap<DeviceId, RemotePeer>>,
+    // 有效期内收到过的(from, nonce)，和时间戳一起用于拒绝重放的包
+    nonces: Mutex<HashMap<(DeviceId, u64), Timestamp>>,
+}
+
+// sn集群，在sn之间同步peer的注册信息，并把call转发到被叫所在的sn
+#[derive(Clone)]
+pub(super) struct SnCluster(Arc<ClusterImpl>);
+
+impl SnCluster {
+    pub fn new(local_device_id: DeviceId, secret: PrivateKey, config: SnClusterConfig) -> Self {
+        let mut members = HashMap::new();
+        for member in &config.members {
+            let id = member.device.desc().device_id();
+            if id == local_device_id {
+                continue;
+            }
+
+            let (sender, receiver) = channel::bounded(1024);
+            let addr = member.addr;
+            {
+                let id = id.clone();
+                task::spawn(async move {
+                    Self::run_sender(id, addr, receiver).await;
+                });
+            }
+
+            members.insert(
+                id,
+                ClusterMember {
+                    public_key: member.device.desc().public_key().clone(),
+                    sender,
+                },
+            );
+        }
+
+        Self(Arc::new(ClusterImpl {
+            local_device_id,
+            secret,
+            config,
+            members,
+            peers: Mutex::new(HashMap::new()),
+            nonces: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    pub async fn start(&self, service: SnService) -> BuckyResult<()> {
+        let listener = TcpListener::bind(self.0.config.listen).await.map_err(|e| {
+            error!("sn cluster listen failed! addr={}, {}", self.0.config.listen, e);
+            BuckyError::from(e)
+        })?;
+
+        info!(
+            "sn cluster listen at {}, members={}",
+            self.0.config.listen,
+            self.0.members.len()
+        );
+
+        {
+            let cluster = self.clone();
+            let service = service.clone();
+            task::spawn(async move {
+                let mut incoming = listener.incoming();
+                while let Some(stream) = incoming.next().await {
+                    match stream {
+                        Ok(stream) => {
+                            let cluster = cluster.clone();
+                            let service = service.clone();
+                            task::spawn(async move {
+                                cluster.run_receiver(stream, service).await;
+                            });
+                        }
+                        Err(e) => {
+                            warn!("sn cluster accept failed! {}", e);
+                        }
+                    }
+                }
+            });
+        }
+
+        // 周期全量同步本地的peer，同时淘汰过期的远端peer
+        {
+            let cluster = self.clone();
+            task::spawn(async move {
+                loop {
+                    if service.is_stopped() {
+                        return;
+                    }
+
+                    cluster.broadcast(SnClusterMessage::Register(service.cluster_peers()));
+                    cluster.knock_timeout(bucky_time_now());
+
+                    task::sleep(cluster.0.config.sync_interval).await;
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn register(&self, peer: SnClusterPeer) {
+        self.broadcast(SnClusterMessage::Register(vec![peer]));
+    }
+
+    pub fn forward_call(&self, sn: &DeviceId, call: SnClusterCall) {
+        self.send_to(sn, SnClusterMessage::Call(call));
+    }
+
+    pub fn find_peer(&self, id: &DeviceId) -> Option<FoundRemotePeer> {
+        let now = bucky_time_now();
+        let peers = self.0.peers.lock().unwrap();
+        peers.get(id).and_then(|peer| {
+            if now > peer.update_time
+                && Duration::from_micros(now - peer.update_time) > self.0.config.peer_timeout
+            {
+                None
+            } else {
+                Some(FoundRemotePeer {
+                    desc: peer.desc.clone(),
+                    endpoints: peer.endpoints.clone(),
+                    sn: peer.sn.clone(),
+                })
+            }
+        })
+    }
+
+    fn knock_timeout(&self, now: Timestamp) {
+        let timeout = self.0.config.peer_timeout;
+        self.0.peers.lock().unwrap().retain(|_, peer| {
+            now <= peer.update_time || Duration::from_micros(now - peer.update_time) <= timeout
+        });
+
+        self.0.nonces.lock().unwrap().retain(|_, timestamp| {
+            Self::is_fresh(*timestamp, now)
+        });
+    }
+
+    fn is_fresh(timestamp: Timestamp, now: Timestamp) -> bool {
+        let diff = if now > timestamp {
+            now - timestamp
+        } else {
+            timestamp - now
+        };
+
+        Duration::from_micros(diff) <= CLUSTER_PACKAGE_EXPIRE
+    }
+
+    fn on_register(&self, sn: &DeviceId, list: Vec<SnClusterPeer>) {
+        let now = bucky_time_now();
+        let mut peers = self.0.peers.lock().unwrap();
+        for peer in list {
+            let id = peer.desc.desc().device_id();
+            let remote = RemotePeer {
+                desc: peer.desc,
+                endpoints: peer.endpoints,
+                sn: sn.clone(),
+                last_ping: peer.last_ping,
+                update_time: now,
+            };
+
+            // 同一个peer可能先后在多个sn上线，以最后一次ping为准
+            match peers.get_mut(&id) {
+                Some(exists) => {
+                    if exists.sn == *sn || exists.last_ping <= remote.last_ping {
+                        *exists = remote;
+                    }
+                }
+                None => {
+                    debug!("sn cluster add peer: {}, sn={}", id, sn);
+                    peers.insert(id, remote);
+                }
+            }
+        }
+    }
+
+    fn encode(&self, msg: &SnClusterMessage) -> BuckyResult<Vec<u8>> {
+        let body = msg.to_vec()?;
+        let from = self.0.local_device_id.clone();
+        let timestamp = bucky_time_now();
+        let nonce = rand::random::<u64>();
+        let hash = SnClusterPackage::sign_hash(&from, timestamp, nonce, &body)?;
+        let sign = self.0.secret.sign(hash.as_slice(), SignatureSource::RefIndex(0))?;
+        let pkg = SnClusterPackage {
+            from,
+            timestamp,
+            nonce,
+            body,
+            sign,
+        };
+
+        let data = pkg.to_vec()?;
+        let mut frame = Vec::with_capacity(data.len() + 4);
+        frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&data);
+
+        Ok(frame)
+    }
+
+    fn broadcast(&self, msg: SnClusterMessage) {
+        if self.0.members.is_empty() {
+            return;
+        }
+
+        let frame = match self.encode(&msg) {
+            Ok(frame) => frame,
+            Err(e) => {
+                error!("sn cluster encode msg failed! {}", e);
+                return;
+            }
+        };
+
+        for (id, member) in &self.0.members {
+            if let Err(e) = member.sender.try_send(frame.clone()) {
+                warn!("sn cluster send to {} failed! {}", id, e);
+            }
+        }
+    }
+
+    fn send_to(&self, sn: &DeviceId, msg: SnClusterMessage) {
+        let member = match self.0.members.get(sn) {
+            Some(member) => member,
+            None => {
+                warn!("sn cluster member not found! sn={}", sn);
+                return;
+            }
+        };
+
+        match self.encode(&msg) {
+            Ok(frame) => {
+                if let Err(e) = member.sender.try_send(frame) {
+                    warn!("sn cluster send to {} failed! {}", sn, e);
+                }
+            }
+            Err(e) => {
+                error!("sn cluster encode msg failed! {}", e);
+            }
+        }
+    }
+
+    async fn run_sender(id: DeviceId, addr: SocketAddr, receiver: channel::Receiver<Vec<u8>>) {
+        let mut stream: Option<TcpStream> = None;
+        while let Ok(frame) = receiver.recv().await {
+            // 连接断开后重连一次，仍然失败则丢弃
+            for _ in 0..2 {
+                if stream.is_none() {
+                    match TcpStream::connect(addr).await {
+                        Ok(s) => stream = Some(s),
+                        Err(e) => {
+                            warn!("sn cluster connect to {} {} failed! {}", id, addr, e);
+                            break;
+                        }
+                    }
+                }
+
+                match stream.as_mut().unwrap().write_all(&frame).await {
+                    Ok(_) => break,
+                    Err(e) => {
+                        warn!("sn cluster write to {} {} failed! {}", id, addr, e);
+                        stream = None;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn run_receiver(&self, mut stream: TcpStream, service: SnService) {
+        let remote = stream.peer_addr().ok();
+        loop {
+            let mut len = [0u8; 4];
+            if stream.read_exact(&mut len).await.is_err() {
+                break;
+            }
+
+            let len = u32::from_be_bytes(len) as usize;
+            if len > MAX_CLUSTER_PACKAGE_SIZE {
+                warn!("sn cluster package too large! from={:?}, len={}", remote, len);
+                break;
+            }
+
+            let mut data = vec![0u8; len];
+            if let Err(e) = stream.read_exact(&mut data).await {
+                warn!("sn cluster read package failed! from={:?}, {}", remote, e);
+                break;
+            }
+
+            if let Err(e) = self.on_package(&data, &service) {
+                warn!("sn cluster process package failed! from={:?}, {}", remote, e);
+                break;
+            }
+        }
+    }
+
+    // 拒绝过期和重放的包
+    fn check_fresh(&self, pkg: &SnClusterPackage) -> BuckyResult<()> {
+        let now = bucky_time_now();
+        if !Self::is_fresh(pkg.timestamp, now) {
+            let msg = format!(
+                "sn cluster package expired! from={}, timestamp={}, now={}",
+                pkg.from, pkg.timestamp, now
+            );
+            return Err(BuckyError::new(BuckyErrorCode::Expired, msg));
+        }
+
+        let mut nonces = self.0.nonces.lock().unwrap();
+        let key = (pkg.from.clone(), pkg.nonce);
+        if nonces.contains_key(&key) {
+            let msg = format!(
+                "sn cluster package replayed! from={}, nonce={}",
+                pkg.from, pkg.nonce
+            );
+            return Err(BuckyError::new(BuckyErrorCode::AlreadyExists, msg));
+        }
+        nonces.insert(key, pkg.timestamp);
+
+        Ok(())
+    }
+
+    fn on_package(&self, data: &[u8], service: &SnService) -> BuckyResult<()> {
+        let pkg = SnClusterPackage::clone_from_slice(data)?;
+        let member = self.0.members.get(&pkg.from).ok_or_else(|| {
+            let msg = format!("sn cluster package from unknown sn: {}", pkg.from);
+            BuckyError::new(BuckyErrorCode::PermissionDenied, msg)
+        })?;
+
+        let hash = SnClusterPackage::sign_hash(&pkg.from, pkg.timestamp, pkg.nonce, &pkg.body)?;
+        if !member.public_key.verify(hash.as_slice(), &pkg.sign) {
+            let msg = format!("sn cluster package verify sign failed! from={}", pkg.from);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidSignature, msg));
+        }
+
+        self.check_fresh(&pkg)?;
+
+        match SnClusterMessage::clone_from_slice(&pkg.body)? {
+            SnClusterMessage::Register(peers) => self.on_register(&pkg.from, peers),
+            SnClusterMessage::Call(call) => service.on_cluster_call(&pkg.from, call),
+        }
+
+        Ok(())
+    }
+}
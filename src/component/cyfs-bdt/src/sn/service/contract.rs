@@ -0,0 +1,604 @@
+use log::*;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    str::FromStr,
+    sync::{mpsc, Arc},
+    time::Duration,
+};
+
+use cyfs_base::*;
+use cyfs_debug::Mutex;
+use cyfs_util::SqliteConnectionHolder;
+use rusqlite::{params, OptionalExtension};
+
+use crate::{
+    sn::types::SnServiceReceipt,
+    ReceiptWithSignature,
+};
+
+use super::receipt::*;
+
+// 接受所有客户端，也不要求服务清单
+pub struct SnAcceptAllContract;
+
+impl SnServiceContractServer for SnAcceptAllContract {
+    fn check_receipt(
+        &self,
+        _client_peer_desc: &Device,
+        _local_receipt: &SnServiceReceipt,
+        _client_receipt: &Option<ReceiptWithSignature>,
+        _last_request_time: &ReceiptRequestTime,
+    ) -> IsAcceptClient {
+        IsAcceptClient::Accept(false)
+    }
+
+    fn verify_auth(&self, _client_peer_id: &DeviceId) -> IsAcceptClient {
+        IsAcceptClient::Accept(false)
+    }
+}
+
+// 允许名单，按device或者device的owner(即所在的zone)授权
+pub struct SnAllowListAuthorizer {
+    devices: HashSet<DeviceId>,
+    // 允许的owner和其公钥，device的desc必须有owner的签名
+    owners: HashMap<ObjectId, PublicKey>,
+
+    // ping过并且验证了owner签名的peer，verify_auth时只有device_id
+    known_owners: Mutex<HashMap<DeviceId, ObjectId>>,
+}
+
+impl SnAllowListAuthorizer {
+    pub fn new(devices: Vec<DeviceId>, owners: Vec<People>) -> Self {
+        Self {
+            devices: devices.into_iter().collect(),
+            owners: owners
+                .into_iter()
+                .map(|people| (people.desc().calculate_id(), people.desc().public_key().clone()))
+                .collect(),
+            known_owners: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // 返回desc声明的、并且有对应签名的允许名单内的owner
+    pub fn signed_owner(&self, desc: &Device) -> Option<ObjectId> {
+        let owner = desc.desc().owner().as_ref()?;
+        let public_key = self.owners.get(owner)?;
+
+        let hash = match desc.desc().raw_hash_value() {
+            Ok(hash) => hash,
+            Err(e) => {
+                warn!("calc sn client desc hash failed! device={}, {}", desc.desc().device_id(), e);
+                return None;
+            }
+        };
+
+        let signed = desc
+            .signs()
+            .desc_signs()
+            .map(|signs| signs.iter().any(|sign| public_key.verify(hash.as_slice(), sign)))
+            .unwrap_or(false);
+        if signed {
+            Some(owner.clone())
+        } else {
+            warn!(
+                "sn client desc not signed by owner! device={}, owner={}",
+                desc.desc().device_id(),
+                owner
+            );
+            None
+        }
+    }
+
+    pub fn is_allowed(&self, device_id: &DeviceId, owner: Option<&ObjectId>) -> bool {
+        if self.devices.contains(device_id) {
+            return true;
+        }
+
+        match owner {
+            Some(owner) => self.owners.contains(owner),
+            None => false,
+        }
+    }
+}
+
+impl SnServiceContractServer for SnAllowListAuthorizer {
+    fn check_receipt(
+        &self,
+        client_peer_desc: &Device,
+        _local_receipt: &SnServiceReceipt,
+        _client_receipt: &Option<ReceiptWithSignature>,
+        _last_request_time: &ReceiptRequestTime,
+    ) -> IsAcceptClient {
+        let device_id = client_peer_desc.desc().device_id();
+        let owner = self.signed_owner(client_peer_desc);
+        if let Some(owner) = &owner {
+            self.known_owners
+                .lock()
+                .unwrap()
+                .insert(device_id.clone(), owner.clone());
+        }
+
+        if self.is_allowed(&device_id, owner.as_ref()) {
+            IsAcceptClient::Accept(false)
+        } else {
+            warn!("sn client not in allow list! device={}, owner={:?}", device_id, owner);
+            IsAcceptClient::Refuse
+        }
+    }
+
+    fn verify_auth(&self, client_peer_id: &DeviceId) -> IsAcceptClient {
+        let owner = self.known_owners.lock().unwrap().get(client_peer_id).cloned();
+        if self.is_allowed(client_peer_id, owner.as_ref()) {
+            IsAcceptClient::Accept(false)
+        } else {
+            IsAcceptClient::Refuse
+        }
+    }
+}
+
+// 每个统计周期内单个客户端的用量上限，None表示不限制
+#[derive(Clone, Debug)]
+pub struct SnUsageQuota {
+    pub period: Duration,
+    pub max_ping: Option<u64>,
+    pub max_called: Option<u64>,
+    pub max_call: Option<u64>,
+}
+
+impl Default for SnUsageQuota {
+    fn default() -> Self {
+        Self {
+            period: Duration::from_secs(3600 * 24 * 30),
+            max_ping: None,
+            max_called: None,
+            max_call: None,
+        }
+    }
+}
+
+impl SnUsageQuota {
+    pub fn is_exceeded(&self, usage: &SnClientUsage) -> bool {
+        let over = |max: Option<u64>, value: u64| max.map(|max| value > max).unwrap_or(false);
+        over(self.max_ping, usage.ping_count)
+            || over(self.max_called, usage.called_count)
+            || over(self.max_call, usage.call_peer_count)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SnClientUsage {
+    pub device_id: DeviceId,
+    pub owner: Option<ObjectId>,
+    pub period_start: u64,
+    pub ping_count: u64,
+    pub called_count: u64,
+    pub call_peer_count: u64,
+    pub update_time: u64,
+}
+
+const INIT_LEDGER_SQL: &str = r#"CREATE TABLE IF NOT EXISTS sn_usage (
+    device_id TEXT PRIMARY KEY NOT NULL UNIQUE,
+    owner TEXT,
+    period_start INTEGER NOT NULL,
+    ping_count INTEGER NOT NULL,
+    called_count INTEGER NOT NULL,
+    call_peer_count INTEGER NOT NULL,
+    update_time INTEGER NOT NULL
+);"#;
+
+// 后台写线程的请求，按发送顺序执行
+enum LedgerWrite {
+    Record(SnClientUsage),
+    Reset(Option<DeviceId>, mpsc::Sender<BuckyResult<usize>>),
+    Flush(mpsc::Sender<BuckyResult<()>>),
+}
+
+// 把sn本地统计的服务清单按客户端持久化到sqlite，并按周期检查用量上限
+pub struct SnReceiptLedger {
+    conn: Arc<SqliteConnectionHolder>,
+    quota: SnUsageQuota,
+    authorizer: Option<Box<dyn SnServiceContractServer + Send + Sync>>,
+
+    // 已经记账的服务清单，清单是累计值，记账时只累加增量
+    recorded: Mutex<HashMap<DeviceId, SnServiceReceipt>>,
+    // 客户端的最新用量，ping时只更新内存，由后台线程合并后写入sqlite
+    usages: Mutex<HashMap<DeviceId, SnClientUsage>>,
+    writer: Mutex<mpsc::Sender<LedgerWrite>>,
+}
+
+impl SnReceiptLedger {
+    pub fn open(
+        data_file: &Path,
+        quota: SnUsageQuota,
+        authorizer: Option<Box<dyn SnServiceContractServer + Send + Sync>>,
+    ) -> BuckyResult<Self> {
+        if let Some(dir) = data_file.parent() {
+            if !dir.is_dir() {
+                std::fs::create_dir_all(dir).map_err(|e| {
+                    let msg = format!("create sn ledger dir error! dir={}, {}", dir.display(), e);
+                    error!("{}", msg);
+                    BuckyError::new(BuckyErrorCode::IoError, msg)
+                })?;
+            }
+        }
+
+        let conn = Arc::new(SqliteConnectionHolder::new(data_file.to_owned()));
+        {
+            let (conn, _lock) = conn.get_write_conn()?;
+            conn.execute(INIT_LEDGER_SQL, []).map_err(|e| {
+                let msg = format!("init sn ledger table error! {}", e);
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::SqliteError, msg)
+            })?;
+        }
+
+        // ledger释放后sender关闭，写线程随之退出
+        let (writer, receiver) = mpsc::channel();
+        {
+            let conn = conn.clone();
+            std::thread::spawn(move || {
+                Self::run_writer(conn, receiver);
+            });
+        }
+
+        let ret = Self {
+            conn,
+            quota,
+            authorizer,
+            recorded: Mutex::new(HashMap::new()),
+            usages: Mutex::new(HashMap::new()),
+            writer: Mutex::new(writer),
+        };
+
+        info!("open sn ledger: {}, quota={:?}", data_file.display(), ret.quota);
+
+        Ok(ret)
+    }
+
+    pub fn quota(&self) -> &SnUsageQuota {
+        &self.quota
+    }
+
+    // 已经过了统计周期的用量不再计入上限
+    pub fn is_exceeded(&self, usage: &SnClientUsage) -> bool {
+        let now = bucky_time_now();
+        if now > usage.period_start
+            && Duration::from_micros(now - usage.period_start) > self.quota.period
+        {
+            return false;
+        }
+
+        self.quota.is_exceeded(usage)
+    }
+
+    fn row_to_usage(row: &rusqlite::Row) -> rusqlite::Result<(String, Option<String>, SnClientUsage)> {
+        let device_id: String = row.get(0)?;
+        let owner: Option<String> = row.get(1)?;
+        let usage = SnClientUsage {
+            device_id: DeviceId::default(),
+            owner: None,
+            period_start: row.get::<_, i64>(2)? as u64,
+            ping_count: row.get::<_, i64>(3)? as u64,
+            called_count: row.get::<_, i64>(4)? as u64,
+            call_peer_count: row.get::<_, i64>(5)? as u64,
+            update_time: row.get::<_, i64>(6)? as u64,
+        };
+
+        Ok((device_id, owner, usage))
+    }
+
+    fn parse_usage(ret: (String, Option<String>, SnClientUsage)) -> BuckyResult<SnClientUsage> {
+        let (device_id, owner, mut usage) = ret;
+        usage.device_id = DeviceId::from_str(&device_id)?;
+        usage.owner = match owner {
+            Some(owner) => Some(ObjectId::from_str(&owner)?),
+            None => None,
+        };
+
+        Ok(usage)
+    }
+
+    pub fn get_usage(&self, device_id: &DeviceId) -> BuckyResult<Option<SnClientUsage>> {
+        if let Some(usage) = self.usages.lock().unwrap().get(device_id) {
+            return Ok(Some(usage.clone()));
+        }
+
+        self.load_usage(device_id)
+    }
+
+    fn load_usage(&self, device_id: &DeviceId) -> BuckyResult<Option<SnClientUsage>> {
+        let (conn, _lock) = self.conn.get_read_conn()?;
+        let sql = "SELECT device_id, owner, period_start, ping_count, called_count, call_peer_count, update_time FROM sn_usage WHERE device_id=?1";
+        let ret = conn
+            .query_row(sql, params![device_id.to_string()], Self::row_to_usage)
+            .optional()
+            .map_err(|e| {
+                let msg = format!("get sn usage error! device={}, {}", device_id, e);
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::SqliteError, msg)
+            })?;
+
+        match ret {
+            Some(ret) => Ok(Some(Self::parse_usage(ret)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn list_usage(&self) -> BuckyResult<Vec<SnClientUsage>> {
+        // 先等待积压的记账写入
+        self.flush()?;
+
+        let (conn, _lock) = self.conn.get_read_conn()?;
+        let sql = "SELECT device_id, owner, period_start, ping_count, called_count, call_peer_count, update_time FROM sn_usage ORDER BY update_time DESC";
+        let mut stmt = conn.prepare(sql).map_err(|e| {
+            let msg = format!("list sn usage error! {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::SqliteError, msg)
+        })?;
+
+        let rows = stmt.query_map([], Self::row_to_usage).map_err(|e| {
+            let msg = format!("list sn usage error! {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::SqliteError, msg)
+        })?;
+
+        let mut list = vec![];
+        for row in rows {
+            let row = row.map_err(|e| {
+                let msg = format!("read sn usage row error! {}", e);
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::SqliteError, msg)
+            })?;
+            list.push(Self::parse_usage(row)?);
+        }
+
+        Ok(list)
+    }
+
+    // 重置指定客户端或者所有客户端的用量，返回重置的条数
+    pub fn reset_usage(&self, device_id: Option<&DeviceId>) -> BuckyResult<usize> {
+        {
+            let mut usages = self.usages.lock().unwrap();
+            match device_id {
+                Some(device_id) => {
+                    usages.remove(device_id);
+                }
+                None => usages.clear(),
+            }
+        }
+
+        // 在写线程上执行，保证排在之前的记账之后
+        let (sender, receiver) = mpsc::channel();
+        self.send_write(LedgerWrite::Reset(device_id.cloned(), sender))?;
+        let ret = Self::wait_write(receiver)?;
+
+        info!("reset sn usage: device={:?}, count={}", device_id, ret);
+
+        Ok(ret)
+    }
+
+    // 等待之前的记账都写入sqlite
+    pub fn flush(&self) -> BuckyResult<()> {
+        let (sender, receiver) = mpsc::channel();
+        self.send_write(LedgerWrite::Flush(sender))?;
+        Self::wait_write(receiver)
+    }
+
+    fn send_write(&self, req: LedgerWrite) -> BuckyResult<()> {
+        self.writer.lock().unwrap().send(req).map_err(|_| {
+            let msg = "sn ledger writer stopped!".to_owned();
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::ErrorState, msg)
+        })
+    }
+
+    fn wait_write<T>(receiver: mpsc::Receiver<BuckyResult<T>>) -> BuckyResult<T> {
+        receiver.recv().map_err(|_| {
+            let msg = "sn ledger writer stopped!".to_owned();
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::ErrorState, msg)
+        })?
+    }
+
+    fn run_writer(conn: Arc<SqliteConnectionHolder>, receiver: mpsc::Receiver<LedgerWrite>) {
+        let mut pending = HashMap::new();
+        while let Ok(req) = receiver.recv() {
+            // 把队列里积压的请求一起处理，同一个客户端的记账只写最后一次
+            let mut next = Some(req);
+            while let Some(req) = next.take() {
+                match req {
+                    LedgerWrite::Record(usage) => {
+                        pending.insert(usage.device_id.clone(), usage);
+                    }
+                    LedgerWrite::Reset(device_id, resp) => {
+                        let ret = Self::write_usages(&conn, &mut pending)
+                            .and_then(|_| Self::delete_usage(&conn, device_id.as_ref()));
+                        let _ = resp.send(ret);
+                    }
+                    LedgerWrite::Flush(resp) => {
+                        let _ = resp.send(Self::write_usages(&conn, &mut pending));
+                    }
+                }
+                next = receiver.try_recv().ok();
+            }
+
+            if let Err(e) = Self::write_usages(&conn, &mut pending) {
+                error!("write sn usage failed! {}", e);
+            }
+        }
+    }
+
+    fn write_usages(
+        conn: &SqliteConnectionHolder,
+        pending: &mut HashMap<DeviceId, SnClientUsage>,
+    ) -> BuckyResult<()> {
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let list = std::mem::take(pending);
+
+        let (mut conn, _lock) = conn.get_write_conn()?;
+        let tx = conn.transaction().map_err(|e| {
+            let msg = format!("begin sn usage transaction error! {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::SqliteError, msg)
+        })?;
+
+        for (device_id, usage) in list {
+            tx.execute(
+                "INSERT OR REPLACE INTO sn_usage (device_id, owner, period_start, ping_count, called_count, call_peer_count, update_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    device_id.to_string(),
+                    usage.owner.as_ref().map(|o| o.to_string()),
+                    usage.period_start as i64,
+                    usage.ping_count as i64,
+                    usage.called_count as i64,
+                    usage.call_peer_count as i64,
+                    usage.update_time as i64,
+                ],
+            )
+            .map_err(|e| {
+                let msg = format!("record sn usage error! device={}, {}", device_id, e);
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::SqliteError, msg)
+            })?;
+        }
+
+        tx.commit().map_err(|e| {
+            let msg = format!("commit sn usage error! {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::SqliteError, msg)
+        })
+    }
+
+    fn delete_usage(conn: &SqliteConnectionHolder, device_id: Option<&DeviceId>) -> BuckyResult<usize> {
+        let (conn, _lock) = conn.get_write_conn()?;
+        match device_id {
+            Some(device_id) => conn.execute(
+                "DELETE FROM sn_usage WHERE device_id=?1",
+                params![device_id.to_string()],
+            ),
+            None => conn.execute("DELETE FROM sn_usage", []),
+        }
+        .map_err(|e| {
+            let msg = format!("reset sn usage error! device={:?}, {}", device_id, e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::SqliteError, msg)
+        })
+    }
+
+    fn record(&self, desc: &Device, receipt: &SnServiceReceipt) -> BuckyResult<SnClientUsage> {
+        let device_id = desc.desc().device_id();
+
+        // 同一个start_time的清单是在上一次的基础上累加的，否则是新的统计
+        let (ping, called, call_peer) = {
+            let mut recorded = self.recorded.lock().unwrap();
+            let delta = match recorded.get(&device_id) {
+                Some(last) if last.start_time == receipt.start_time => (
+                    receipt.ping_count.saturating_sub(last.ping_count),
+                    receipt.called_count.saturating_sub(last.called_count),
+                    receipt.call_peer_count.saturating_sub(last.call_peer_count),
+                ),
+                _ => (receipt.ping_count, receipt.called_count, receipt.call_peer_count),
+            };
+            recorded.insert(device_id.clone(), receipt.clone());
+            delta
+        };
+
+        let now = bucky_time_now();
+        let mut usage = match self.get_usage(&device_id)? {
+            Some(usage) => usage,
+            None => SnClientUsage {
+                device_id: device_id.clone(),
+                owner: None,
+                period_start: now,
+                ping_count: 0,
+                called_count: 0,
+                call_peer_count: 0,
+                update_time: now,
+            },
+        };
+
+        if now > usage.period_start
+            && Duration::from_micros(now - usage.period_start) > self.quota.period
+        {
+            usage.period_start = now;
+            usage.ping_count = 0;
+            usage.called_count = 0;
+            usage.call_peer_count = 0;
+        }
+
+        usage.owner = desc.desc().owner().clone();
+        usage.ping_count += ping as u64;
+        usage.called_count += called as u64;
+        usage.call_peer_count += call_peer as u64;
+        usage.update_time = now;
+
+        // 只更新内存，sqlite由写线程批量写入
+        self.usages
+            .lock()
+            .unwrap()
+            .insert(device_id.clone(), usage.clone());
+        self.send_write(LedgerWrite::Record(usage.clone()))?;
+
+        Ok(usage)
+    }
+}
+
+impl SnServiceContractServer for SnReceiptLedger {
+    fn check_receipt(
+        &self,
+        client_peer_desc: &Device,
+        local_receipt: &SnServiceReceipt,
+        client_receipt: &Option<ReceiptWithSignature>,
+        last_request_time: &ReceiptRequestTime,
+    ) -> IsAcceptClient {
+        let accept = match &self.authorizer {
+            Some(authorizer) => authorizer.check_receipt(
+                client_peer_desc,
+                local_receipt,
+                client_receipt,
+                last_request_time,
+            ),
+            None => IsAcceptClient::Accept(false),
+        };
+        if let IsAcceptClient::Refuse = accept {
+            return accept;
+        }
+
+        match self.record(client_peer_desc, local_receipt) {
+            Ok(usage) => {
+                if self.quota.is_exceeded(&usage) {
+                    warn!("sn client exceed quota! usage={:?}", usage);
+                    return IsAcceptClient::Refuse;
+                }
+            }
+            // 记账失败不影响服务
+            Err(e) => {
+                error!(
+                    "record sn usage failed! device={}, {}",
+                    client_peer_desc.desc().device_id(),
+                    e
+                );
+            }
+        }
+
+        accept
+    }
+
+    fn verify_auth(&self, client_peer_id: &DeviceId) -> IsAcceptClient {
+        let accept = match &self.authorizer {
+            Some(authorizer) => authorizer.verify_auth(client_peer_id),
+            None => IsAcceptClient::Accept(false),
+        };
+        if let IsAcceptClient::Refuse = accept {
+            return accept;
+        }
+
+        match self.get_usage(client_peer_id) {
+            Ok(Some(usage)) if self.is_exceeded(&usage) => IsAcceptClient::Refuse,
+            _ => accept,
+        }
+    }
+}
@@ -5,8 +5,12 @@ mod service;
 mod resend_queue;
 mod call_stub;
 mod statistic;
+mod cluster;
+mod contract;
 
 pub use receipt::*;
 pub use service::*;
+pub use cluster::*;
+pub use contract::*;
 
 
@@ -1,15 +1,17 @@
 use std::{
     collections::{HashMap, hash_map}, 
-    time::Duration, 
+    time::{Duration, SystemTime}, 
     sync::{Arc, atomic::{AtomicU64, Ordering}}
 };
 use cyfs_debug::Mutex;
 use cyfs_base::*;
 use crate::{
     types::*, 
+    sn::types::{SnServiceReceipt, SnServiceReceiptVersion},
 };
 use super::{
-    net_listener::UdpSender, statistic::{PeerStatus, StatisticManager, }
+    net_listener::UdpSender, statistic::{PeerStatus, StatisticManager, }, 
+    receipt::ReceiptRequestTime,
 };
 
 struct Config {
@@ -32,6 +34,9 @@ pub struct FoundPeer {
     pub sender: Arc<UdpSender>,
     pub is_wan: bool,
     pub peer_status: PeerStatus,
+    pub last_send_time: Timestamp,
+    pub receipt: SnServiceReceipt,
+    pub last_receipt_request_time: ReceiptRequestTime,
 }
 
 
@@ -46,8 +51,8 @@ struct CachedPeerInfo {
     pub last_ping_seq: TempSeq,
     pub peer_status: PeerStatus,
     // pub call_peers: HashMap<DeviceId, TempSeq>, // <peerid, last_call_seq>
-    // pub receipt: SnServiceReceipt,
-    // pub last_receipt_request_time: ReceiptRequestTime,
+    pub receipt: SnServiceReceipt,
+    pub last_receipt_request_time: ReceiptRequestTime,
 }
 
 fn has_wan_endpoint(desc: &Device) -> bool {
@@ -87,8 +92,14 @@ impl CachedPeerInfo {
             last_call_time: 0,
             peer_status,
             // call_peers: Default::default(),
-            // receipt: Default::default(),
-            // last_receipt_request_time: ReceiptRequestTime::None,
+            receipt: SnServiceReceipt {
+                version: SnServiceReceiptVersion::Current,
+                start_time: SystemTime::now(),
+                ping_count: 1,
+                ping_resp_count: 1,
+                ..Default::default()
+            },
+            last_receipt_request_time: ReceiptRequestTime::None,
         }
     }
 
@@ -98,6 +109,9 @@ impl CachedPeerInfo {
             sender: self.sender.clone(), 
             is_wan: self.is_wan,
             peer_status: self.peer_status.clone(),
+            last_send_time: self.last_send_time,
+            receipt: self.receipt.clone(),
+            last_receipt_request_time: self.last_receipt_request_time,
         }
     }
 
@@ -211,6 +225,10 @@ impl PeerManager {
                 log::warn!("ping send-time little.");
                 return false;
             }
+            if cached_peer.last_ping_seq != seq {
+                cached_peer.receipt.ping_count += 1;
+                cached_peer.receipt.ping_resp_count += 1;
+            }
             if let Some(desc) = peer_desc {
                 if let Err(e) = cached_peer.update_desc(desc) {
                     log::warn!("ping update device-info failed, err: {:?}", e);
@@ -314,4 +332,32 @@ impl PeerManager {
     pub fn find_peer(&self, id: &DeviceId) -> Option<FoundPeer> {
         self.peers.lock().unwrap().find_peer(id, FindPeerReason::Other).map(|c| c.to_found_peer())
     }
+
+    pub fn remove_peer(&self, id: &DeviceId) -> bool {
+        let mut peers = self.peers.lock().unwrap();
+        peers.active_peers.remove(id).is_some() || peers.knock_peers.remove(id).is_some()
+    }
+
+    // 更新本地统计的服务清单
+    pub fn update_receipt(&self, id: &DeviceId, f: impl FnOnce(&mut SnServiceReceipt)) {
+        if let Some(p) = self.peers.lock().unwrap().find_peer(id, FindPeerReason::Other) {
+            f(&mut p.receipt);
+            p.receipt.duration = SystemTime::now().duration_since(p.receipt.start_time).unwrap_or_default();
+        }
+    }
+
+    pub fn set_receipt_request_time(&self, id: &DeviceId, t: ReceiptRequestTime) {
+        if let Some(p) = self.peers.lock().unwrap().find_peer(id, FindPeerReason::Other) {
+            p.last_receipt_request_time = t;
+        }
+    }
+
+    // 当前在线的所有peer，包括待淘汰的
+    pub fn peer_list(&self) -> Vec<FoundPeer> {
+        let peers = self.peers.lock().unwrap();
+        peers.active_peers.values()
+            .chain(peers.knock_peers.values())
+            .map(|c| c.to_found_peer())
+            .collect()
+    }
 }
\ No newline at end of file
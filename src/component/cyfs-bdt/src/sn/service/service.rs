@@ -7,7 +7,7 @@ use std::{
         atomic::{self, AtomicBool},
         Arc,
     },
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 
 use cyfs_base::*;
@@ -16,12 +16,14 @@ use crate::{
     history::keystore::{self, Keystore},
     protocol::{*, v0::*},
     types::*,
+    sn::types::SnServiceReceipt,
 };
 
 use super::{
     call_stub::CallStub,
+    cluster::*,
     net_listener::{MessageSender, NetListener, UdpSender},
-    peer_manager::PeerManager,
+    peer_manager::{PeerManager, FoundPeer},
     receipt::*,
     resend_queue::{ResendQueue, ResendCallbackTrait},
 };
@@ -45,7 +47,7 @@ struct ServiceImpl {
     peer_mgr: PeerManager,
     resend_queue: Option<ResendQueue>,
     call_stub: CallStub,
-
+    cluster: Option<SnCluster>,
 }
 
 #[derive(Clone)]
@@ -56,6 +58,25 @@ impl SnService {
         local_device: Device,
         local_secret: PrivateKey,
         contract: Box<dyn SnServiceContractServer + Send + Sync>,
+    ) -> SnService {
+        Self::create(local_device, local_secret, contract, None)
+    }
+
+    // 以集群方式运行，和其它sn同步在线peer并转发call
+    pub fn new_with_cluster(
+        local_device: Device,
+        local_secret: PrivateKey,
+        contract: Box<dyn SnServiceContractServer + Send + Sync>,
+        cluster: SnClusterConfig,
+    ) -> SnService {
+        Self::create(local_device, local_secret, contract, Some(cluster))
+    }
+
+    fn create(
+        local_device: Device,
+        local_secret: PrivateKey,
+        contract: Box<dyn SnServiceContractServer + Send + Sync>,
+        cluster: Option<SnClusterConfig>,
     ) -> SnService {
         let thread_pool = ThreadPool::new().unwrap();
 
@@ -80,6 +101,7 @@ impl SnService {
             stopped: AtomicBool::new(false),
             peer_mgr: PeerManager::new(),
             call_stub: CallStub::new(),
+            cluster: cluster.map(|config| SnCluster::new(local_device.desc().device_id(), local_secret.clone(), config)),
             thread_pool: thread_pool.clone(),
             contract,
             // call_tracker: CallTracker {
@@ -123,6 +145,10 @@ impl SnService {
             Err(e) => Err(e),
         }?;
 
+        if let Some(cluster) = self.cluster() {
+            cluster.start(self.clone()).await?;
+        }
+
         // 清理过期数据
         let timer = {
             let service = self.clone();
@@ -157,6 +183,11 @@ impl SnService {
         &self.0.local_device_id
     }
 
+    // peer通过集群同步到本sn时，返回其所在的sn
+    pub fn cluster_peer_sn(&self, id: &DeviceId) -> Option<DeviceId> {
+        self.cluster().and_then(|c| c.find_peer(id)).map(|peer| peer.sn)
+    }
+
     pub(super) fn key_store(&self) -> &Keystore {
         &self.0.key_store
     }
@@ -173,6 +204,19 @@ impl SnService {
         &self.0.thread_pool
     }
 
+    fn cluster(&self) -> Option<&SnCluster> {
+        self.0.cluster.as_ref()
+    }
+
+    // 同步给集群内其它sn的本地在线peer
+    pub(super) fn cluster_peers(&self) -> Vec<SnClusterPeer> {
+        self.peer_manager().peer_list().into_iter().map(|peer| SnClusterPeer {
+            endpoints: vec![Endpoint::from((Protocol::Udp, peer.sender.remote().clone()))],
+            desc: peer.desc,
+            last_ping: peer.last_send_time,
+        }).collect()
+    }
+
     fn send_resp(&self, mut sender: MessageSender, pkg: DynamicPackage, send_log: String) {
         self.thread_pool().spawn_ok(async move {
             if let Err(e) = sender.send(pkg).await {
@@ -404,6 +448,8 @@ impl SnService {
 
         info!("{}", log_key);
 
+        let is_new_peer = self.peer_manager().find_peer(from_peer_id).is_none();
+
         // let (result, endpoints, receipt) = if let Some((accept, local_receipt)) = self.ping_receipt(&ping_req, from_peer_id) {
         //     let receipt = match accept {
         //         IsAcceptClient::Refuse => {
@@ -434,6 +480,41 @@ impl SnService {
             return;
         };
 
+        let receipt = match self.ping_receipt(from_peer_id) {
+            Some(receipt) => receipt,
+            None => {
+                warn!("{} refused by contract.", log_key);
+                self.peer_manager().remove_peer(from_peer_id);
+
+                let ping_resp = SnPingResp {
+                    seq: ping_req.seq,
+                    sn_peer_id: self.local_device_id().clone(),
+                    result: BuckyErrorCode::PermissionDenied.into_u8(),
+                    peer_info: None,
+                    end_point_array: vec![],
+                    receipt: None,
+                };
+
+                self.send_resp_udp(
+                    resp_sender,
+                    DynamicPackage::from(ping_resp),
+                    format!("{}", log_key),
+                );
+                return;
+            }
+        };
+
+        // 新上线的peer立即通知集群内其它sn，之后由周期同步更新
+        if is_new_peer {
+            if let (Some(cluster), Some(desc)) = (self.cluster(), ping_req.peer_info.as_ref()) {
+                cluster.register(SnClusterPeer {
+                    desc: desc.clone(),
+                    endpoints: vec![Endpoint::from((Protocol::Udp, resp_sender.remote().clone()))],
+                    last_ping: send_time,
+                });
+            }
+        }
+
         let ping_resp = SnPingResp {
             seq: ping_req.seq,
             sn_peer_id: self.local_device_id().clone(),
@@ -443,7 +524,7 @@ impl SnService {
                 Protocol::Udp,
                 resp_sender.remote().clone(),
             ))],
-            receipt: None,
+            receipt,
         };
 
         self.send_resp_udp(
@@ -453,6 +534,28 @@ impl SnService {
         );
     }
 
+    // 由合约检查本地统计的服务清单，决定是否继续为peer服务；
+    // 拒绝服务返回None，同意时如果需要客户端确认服务清单，返回要确认的清单
+    fn ping_receipt(&self, from_peer_id: &DeviceId) -> Option<Option<SnServiceReceipt>> {
+        let peer = self.peer_manager().find_peer(from_peer_id)?;
+
+        // 客户端服务清单的签名校验还没有实现，暂不采信客户端提交的清单
+        match self.0.contract.check_receipt(&peer.desc, &peer.receipt, &None, &peer.last_receipt_request_time) {
+            IsAcceptClient::Refuse => None,
+            IsAcceptClient::Accept(is_request_receipt) => {
+                if is_request_receipt {
+                    match peer.last_receipt_request_time {
+                        ReceiptRequestTime::Wait(_) => {}
+                        _ => self.peer_manager().set_receipt_request_time(from_peer_id, ReceiptRequestTime::Wait(SystemTime::now())),
+                    }
+                    Some(Some(peer.receipt))
+                } else {
+                    Some(None)
+                }
+            }
+        }
+    }
+
     // fn verify_receipt_sign(
     //     &self,
     //     client_desc: &DeviceDesc,
@@ -553,17 +656,21 @@ impl SnService {
             call_req.seq.value()
         );
         info!("{}.", log_key);
-        // if let IsAcceptClient::Refuse = self.contract.verify_auth(&call_req.to_peer_id) {
-        //     warn!("{} refused by contract.", log_key);
-        //     send_responce(self,
-        //                   resp_sender,
-        //                   call_req.seq,
-        //                   BuckyErrorCode::PermissionDenied,
-        //                   None,
-        //                   log_key.as_str()
-        //     );
-        //     return;
-        // }
+        if let IsAcceptClient::Refuse = self.0.contract.verify_auth(&call_req.from_peer_id) {
+            warn!("{} refused by contract.", log_key);
+            let call_resp = SnCallResp {
+                seq: call_req.seq,
+                sn_peer_id: self.local_device_id().clone(),
+                result: BuckyErrorCode::PermissionDenied.into_u8(),
+                to_peer_info: None,
+            };
+            self.send_resp(
+                resp_sender,
+                DynamicPackage::from(call_resp),
+                format!("{} call-resp", log_key),
+            );
+            return;
+        }
 
         // if let Some(cached_from) = self.peer_mgr.find_peer(from_peer_id, FindPeerReason::CallFrom(*send_time)) {
         //     if &cached_from.last_call_time > send_time {
@@ -584,17 +691,18 @@ impl SnService {
 
         if let Some(call_requestor) = call_requestor.as_ref() {
             call_requestor.peer_status.add_record(call_req.to_peer_id.clone(), call_req.seq);
+            self.peer_manager().update_receipt(&call_req.from_peer_id, |r| r.call_peer_count += 1);
         }
 
+        let from_peer_desc = if call_req.peer_info.is_none() {
+            call_requestor.as_ref().map(|c| c.desc.clone())
+        } else {
+            call_req.peer_info.take()
+        };
+
         let call_resp =
             if let Some(to_peer_cache) = self.peer_manager().find_peer(&call_req.to_peer_id) {
                 // Self::call_stat_contract(to_peer_cache, &call_req);
-                let from_peer_desc = if call_req.peer_info.is_none() {
-                    self.peer_manager().find_peer(from_peer_id).map(|c| c.desc)
-                } else {
-                    call_req.peer_info
-                };
-
                 if let Some(from_peer_desc) = from_peer_desc {
                     info!(
                         "{} to-peer found, endpoints: {}, always_call: {}, to-peer.is_wan: {}.",
@@ -604,54 +712,41 @@ impl SnService {
                         to_peer_cache.is_wan
                     );
 
-                    if self.0.call_stub.insert(from_peer_id, &call_req.seq) {
-                        if call_req.is_always_call || !to_peer_cache.is_wan {
-                            let called_seq = self.0.seq_generator.generate();
-                            let mut called_req = SnCalled {
-                                seq: called_seq,
-                                to_peer_id: call_req.to_peer_id.clone(),
-                                sn_peer_id: self.local_device_id().clone(),
-                                peer_info: from_peer_desc,
-                                call_seq: call_req.seq,
-                                call_send_time: call_req.send_time,
-                                payload: SizedOwnedData::from(vec![]),
-                                reverse_endpoint_array: vec![],
-                                active_pn_list: vec![],
-                            };
-
-                            std::mem::swap(&mut call_req.payload, &mut called_req.payload);
-                            if let Some(eps) = call_req.reverse_endpoint_array.as_mut() {
-                                std::mem::swap(eps, &mut called_req.reverse_endpoint_array);
-                            }
-                            if let Some(pn_list) = call_req.active_pn_list.as_mut() {
-                                std::mem::swap(pn_list, &mut called_req.active_pn_list);
-                            }
-
-                            let called_log =
-                                format!("{} called-req seq({})", log_key, called_seq.value());
-                            log::debug!(
-                                "{} will send with payload(len={}) pn_list({:?}).",
-                                called_log,
-                                called_req.payload.len(),
-                                called_req.active_pn_list
-                            );
-                            self.resend_queue().send(
-                                to_peer_cache.sender.clone(),
-                                DynamicPackage::from(called_req),
-                                called_seq.value(),
-                                called_log,
-                            );
-                            // self.call_tracker.calls.insert(called_seq, (call_req.send_time, Instant::now(), call_req.to_peer_id.clone()));
-                        }
+                    self.send_called(&to_peer_cache, from_peer_desc, &mut call_req, log_key.as_str());
+
+                    SnCallResp {
+                        seq: call_req.seq,
+                        sn_peer_id: self.local_device_id().clone(),
+                        result: BuckyErrorCode::Ok.into_u8(),
+                        to_peer_info: Some(to_peer_cache.desc),
+                    }
+                } else {
+                    warn!("{} without from-desc.", log_key);
+
+                    SnCallResp {
+                        seq: call_req.seq,
+                        sn_peer_id: self.local_device_id().clone(),
+                        result: BuckyErrorCode::NotFound.into_u8(),
+                        to_peer_info: None,
+                    }
+                }
+            } else if let Some(remote_peer) = self.cluster().and_then(|c| c.find_peer(&call_req.to_peer_id)) {
+                // 被叫在集群内其它sn上线，转发给该sn发送called
+                if let Some(from_peer_desc) = from_peer_desc {
+                    info!("{} to-peer found on cluster sn {}.", log_key, remote_peer.sn);
+
+                    if self.0.call_stub.insert(&call_req.from_peer_id, &call_req.seq) {
+                        let call = SnClusterCall::take_from(&mut call_req, from_peer_desc);
+                        self.cluster().unwrap().forward_call(&remote_peer.sn, call);
                     } else {
-                        info!("{} ignore send called req for already exists.", log_key);
+                        info!("{} ignore forward call for already exists.", log_key);
                     }
 
                     SnCallResp {
                         seq: call_req.seq,
                         sn_peer_id: self.local_device_id().clone(),
                         result: BuckyErrorCode::Ok.into_u8(),
-                        to_peer_info: Some(to_peer_cache.desc),
+                        to_peer_info: Some(remote_peer.desc_with_endpoints()),
                     }
                 } else {
                     warn!("{} without from-desc.", log_key);
@@ -691,6 +786,79 @@ impl SnService {
         );
     }
 
+    fn send_called(&self, to_peer_cache: &FoundPeer, from_peer_desc: Device, call_req: &mut SnCall, log_key: &str) {
+        if !self.0.call_stub.insert(&call_req.from_peer_id, &call_req.seq) {
+            info!("{} ignore send called req for already exists.", log_key);
+            return;
+        }
+
+        if !call_req.is_always_call && to_peer_cache.is_wan {
+            return;
+        }
+
+        let called_seq = self.0.seq_generator.generate();
+        let mut called_req = SnCalled {
+            seq: called_seq,
+            to_peer_id: call_req.to_peer_id.clone(),
+            sn_peer_id: self.local_device_id().clone(),
+            peer_info: from_peer_desc,
+            call_seq: call_req.seq,
+            call_send_time: call_req.send_time,
+            payload: SizedOwnedData::from(vec![]),
+            reverse_endpoint_array: vec![],
+            active_pn_list: vec![],
+        };
+
+        std::mem::swap(&mut call_req.payload, &mut called_req.payload);
+        if let Some(eps) = call_req.reverse_endpoint_array.as_mut() {
+            std::mem::swap(eps, &mut called_req.reverse_endpoint_array);
+        }
+        if let Some(pn_list) = call_req.active_pn_list.as_mut() {
+            std::mem::swap(pn_list, &mut called_req.active_pn_list);
+        }
+
+        let called_log =
+            format!("{} called-req seq({})", log_key, called_seq.value());
+        log::debug!(
+            "{} will send with payload(len={}) pn_list({:?}).",
+            called_log,
+            called_req.payload.len(),
+            called_req.active_pn_list
+        );
+        self.resend_queue().send(
+            to_peer_cache.sender.clone(),
+            DynamicPackage::from(called_req),
+            called_seq.value(),
+            called_log,
+        );
+        self.peer_manager().update_receipt(&call_req.to_peer_id, |r| r.called_count += 1);
+        // self.call_tracker.calls.insert(called_seq, (call_req.send_time, Instant::now(), call_req.to_peer_id.clone()));
+    }
+
+    // 集群内其它sn转发过来的call，被叫在本sn上线
+    pub(super) fn on_cluster_call(&self, from_sn: &DeviceId, call: SnClusterCall) {
+        let log_key = format!(
+            "[cluster call {}->{} seq({}) from sn {}]",
+            call.from_peer_id,
+            call.to_peer_id,
+            call.seq.value(),
+            from_sn
+        );
+        info!("{}.", log_key);
+
+        let to_peer_cache = match self.peer_manager().find_peer(&call.to_peer_id) {
+            Some(peer) => peer,
+            None => {
+                warn!("{} to-peer not found.", log_key);
+                return;
+            }
+        };
+
+        let from_peer_desc = call.peer_info.clone();
+        let mut call_req = call.into_call(self.local_device_id().clone());
+        self.send_called(&to_peer_cache, from_peer_desc, &mut call_req, log_key.as_str());
+    }
+
     fn handle_called_resp(&self, called_resp: Box<SnCalledResp>, _aes_key: Option<&MixAesKey>) {
         info!("called-resp seq {}.", called_resp.seq.value());
         self.resend_queue().confirm_pkg(called_resp.seq.value());
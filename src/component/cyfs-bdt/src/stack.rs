@@ -31,7 +31,7 @@ use crate::{
     },
     stream::{self, StreamManager},
     tunnel::{self, TunnelManager},
-    pn::client::ProxyManager,
+    pn::client::{ProxyManager, TurnConfig},
     ndn::{self, HistorySpeedConfig, NdnStack, ChunkReader, NdnEventHandler, RawCacheConfig }, 
     debug::{self, DebugStub, PingStub}
 };
@@ -57,6 +57,7 @@ pub struct StackConfig {
     pub stream: stream::Config,
     pub datagram: datagram::Config,
     pub ndn: ndn::Config, 
+    pub turn: TurnConfig, 
     pub debug: Option<debug::Config>
 }
 
@@ -186,6 +187,13 @@ impl StackConfig {
                     }
                 }
             }, 
+            turn: TurnConfig {
+                servers: vec![], 
+                lifetime: Duration::from_secs(600), 
+                permission_lifetime: Duration::from_secs(300), 
+                resend_interval: Duration::from_millis(500), 
+                request_timeout: Duration::from_secs(5), 
+            }, 
             debug: None
         }
     }
@@ -376,6 +384,7 @@ impl Stack {
 
         let net_listener = stack.net_manager().listener();
         net_listener.start(stack.to_weak());
+        stack.proxy_manager().start_turn().await;
         
         let mut known_sn = vec![];
         if params.known_sn.is_some() {
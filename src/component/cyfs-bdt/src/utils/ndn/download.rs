@@ -169,3 +169,25 @@ pub async fn download_file(
     Ok((path, reader))
 }
 
+
+// 流式读取文件，优先下载当前读取位置的chunk，并预先下载之后的prefetch个chunk
+pub async fn download_file_with_priority(
+    stack: &Stack, 
+    file: File, 
+    group: Option<String>, 
+    context: impl DownloadContext, 
+    priority: DownloadTaskPriority, 
+    prefetch: usize, 
+) -> BuckyResult<(String, ChunkListTaskReader)> {
+    let chunk_list = ChunkListDesc::from_file(&file)?;
+    let (task, reader) = ChunkListTask::reader_with_priority(
+        stack.to_weak(), 
+        file.desc().file_id().to_string(), 
+        chunk_list, 
+        context.clone_as_context(), 
+        priority, 
+        prefetch
+    );
+    let path = stack.ndn().root_task().download().add_task(group.unwrap_or_default(), &task)?;
+    Ok((path, reader))
+}
@@ -498,3 +498,163 @@ async fn sn_with_no_endpoint() {
 
     assert!(rn_stack.reset_sn_list(vec![sn.clone()]).wait_online().await.is_err());
 }
+
+
+#[async_std::test]
+async fn sn_cluster_call() {
+    let mut sn_list = vec![];
+    for _ in 0..3 {
+        let ep = format!("W4udp127.0.0.1:{}", utils::free_port());
+        let (sn, sn_secret) = utils::create_device("5aSixgLuJjfrNKn9D4z66TEM6oxL3uNmWCWHk52cJDKR", &[ep.as_str()]).unwrap();
+        let cluster_addr: std::net::SocketAddr = format!("127.0.0.1:{}", utils::free_port()).parse().unwrap();
+        sn_list.push((sn, sn_secret, cluster_addr));
+    }
+
+    let members: Vec<SnClusterMember> = sn_list.iter().map(|(sn, _, addr)| SnClusterMember {
+        device: sn.clone(),
+        addr: addr.clone(),
+    }).collect();
+
+    let mut services = vec![];
+    for (sn, sn_secret, addr) in &sn_list {
+        let service = SnService::new_with_cluster(
+            sn.clone(),
+            sn_secret.clone(),
+            Box::new(TestServer {}),
+            SnClusterConfig::new(addr.clone(), members.clone()),
+        );
+        services.push(service.clone());
+
+        task::spawn(async move {
+            let _ = service.start().await;
+        });
+    }
+
+    let sn1 = sn_list[0].0.clone();
+    let sn3 = sn_list[2].0.clone();
+
+    let ln_ep = format!("L4udp127.0.0.1:{}", utils::free_port());
+    let rn_ep = format!("L4udp127.0.0.1:{}", utils::free_port());
+    let (ln_dev, ln_secret) = utils::create_device("5aSixgLuJjfrNKn9D4z66TEM6oxL3uNmWCWHk52cJDKR", &[ln_ep.as_str()]).unwrap();
+    let (rn_dev, rn_secret) = utils::create_device("5aSixgLuJjfrNKn9D4z66TEM6oxL3uNmWCWHk52cJDKR", &[rn_ep.as_str()]).unwrap();
+    let rn_id = rn_dev.desc().device_id();
+
+    // ln只知道sn3，rn只在sn1上线
+    let mut ln_params = StackOpenParams::new("");
+    ln_params.known_device = Some(vec![rn_dev.clone(), sn3.clone()]);
+    let ln_stack = Stack::open(
+        ln_dev.clone(), 
+        ln_secret, 
+        ln_params).await.unwrap();
+
+    let mut rn_params = StackOpenParams::new("");
+    rn_params.known_sn = Some(vec![sn1.clone()]);
+    let rn_stack = Stack::open(
+        rn_dev, 
+        rn_secret, 
+        rn_params).await.unwrap();
+
+    assert_eq!(SnStatus::Online, rn_stack.reset_sn_list(vec![sn1.clone()]).wait_online().await.unwrap());
+    // 等待sn1把rn同步到sn3
+    let sn1_id = sn1.desc().device_id();
+    future::timeout(Duration::from_secs(5), async {
+        while services[2].cluster_peer_sn(&rn_id) != Some(sn1_id.clone()) {
+            task::sleep(Duration::from_millis(50)).await;
+        }
+    }).await.unwrap();
+
+    let (sample_size, sample) = utils::random_mem(1024, 512);
+    let (signal_sender, signal_recver) = channel::bounded::<Vec<u8>>(1);
+    {
+        let rn_stack = rn_stack.clone();
+        task::spawn(async move {
+            recv_large_stream(rn_stack, signal_sender).await;
+        });
+    }
+
+    let param = BuildTunnelParams {
+        remote_const: rn_stack.local_const().clone(),
+        remote_sn: Some(vec![sn3.desc().device_id()]),
+        remote_desc: None,
+    };
+    let mut stream = ln_stack
+        .stream_manager()
+        .connect(0u16, vec![], param)
+        .await.unwrap();
+    stream.write_all(&sample[..]).await.unwrap();
+
+    let _ = stream.shutdown(Shutdown::Both);
+
+    let recv_sample = future::timeout(Duration::from_secs(5), signal_recver.recv()).await.unwrap().unwrap();
+
+    assert_eq!(recv_sample.len(), sample_size);
+    let sample_hash = hash_data(sample.as_ref());
+    let recv_hash = hash_data(recv_sample.as_ref());
+
+    assert_eq!(sample_hash, recv_hash);
+}
+
+
+#[test]
+fn sn_receipt_ledger() {
+    let (client, _) = utils::create_device("5aSixgLuJjfrNKn9D4z66TEM6oxL3uNmWCWHk52cJDKR", &["L4udp127.0.0.1:10050"]).unwrap();
+    let (stranger, _) = utils::create_device("5aSixgLuJjfrNKn9D4z66TEM6oxL3uNmWCWHk52cJDKR", &["L4udp127.0.0.1:10051"]).unwrap();
+    let client_id = client.desc().device_id();
+
+    let ledger_file = std::env::temp_dir().join(format!("sn-usage-{}.db", client_id));
+    let _ = std::fs::remove_file(&ledger_file);
+
+    let authorizer = SnAllowListAuthorizer::new(vec![client_id.clone()], vec![]);
+    let mut quota = SnUsageQuota::default();
+    quota.max_ping = Some(10);
+    let ledger = SnReceiptLedger::open(&ledger_file, quota, Some(Box::new(authorizer))).unwrap();
+
+    let mut receipt = SnServiceReceipt {
+        start_time: std::time::SystemTime::now(),
+        ping_count: 6,
+        ..Default::default()
+    };
+    assert!(matches!(ledger.check_receipt(&stranger, &receipt, &None, &ReceiptRequestTime::None), IsAcceptClient::Refuse));
+    assert!(matches!(ledger.check_receipt(&client, &receipt, &None, &ReceiptRequestTime::None), IsAcceptClient::Accept(_)));
+
+    // 同一份统计只累加增量
+    receipt.ping_count = 8;
+    assert!(matches!(ledger.check_receipt(&client, &receipt, &None, &ReceiptRequestTime::None), IsAcceptClient::Accept(_)));
+    assert_eq!(ledger.get_usage(&client_id).unwrap().unwrap().ping_count, 8);
+
+    // 重新统计的清单全部累加，超过上限
+    receipt.start_time = receipt.start_time + Duration::from_secs(1);
+    receipt.ping_count = 3;
+    assert!(matches!(ledger.check_receipt(&client, &receipt, &None, &ReceiptRequestTime::None), IsAcceptClient::Refuse));
+    assert!(matches!(ledger.verify_auth(&client_id), IsAcceptClient::Refuse));
+    assert_eq!(ledger.list_usage().unwrap().len(), 1);
+
+    assert_eq!(ledger.reset_usage(Some(&client_id)).unwrap(), 1);
+    assert!(matches!(ledger.verify_auth(&client_id), IsAcceptClient::Accept(_)));
+
+    let _ = std::fs::remove_file(&ledger_file);
+}
+
+
+#[async_std::test]
+async fn sn_allow_list_owner_sign() {
+    let owner_secret = PrivateKey::generate_rsa(1024).unwrap();
+    let owner = People::new(None, vec![], owner_secret.public(), None, None, None).build();
+    let owner_id = owner.desc().calculate_id();
+
+    let (mut client, _) = utils::create_device(&owner_id.to_string(), &["L4udp127.0.0.1:10052"]).unwrap();
+    let client_id = client.desc().device_id();
+
+    let authorizer = SnAllowListAuthorizer::new(vec![], vec![owner]);
+    let receipt = SnServiceReceipt::default();
+
+    // 只声明owner而没有owner签名的device不被信任
+    assert!(matches!(authorizer.check_receipt(&client, &receipt, &None, &ReceiptRequestTime::None), IsAcceptClient::Refuse));
+    assert!(matches!(authorizer.verify_auth(&client_id), IsAcceptClient::Refuse));
+
+    let signer = RsaCPUObjectSigner::new(owner_secret.public(), owner_secret);
+    sign_and_push_named_object_desc(&signer, &mut client, &SignatureSource::RefIndex(SIGNATURE_SOURCE_REFINDEX_OWNER)).await.unwrap();
+
+    assert!(matches!(authorizer.check_receipt(&client, &receipt, &None, &ReceiptRequestTime::None), IsAcceptClient::Accept(_)));
+    assert!(matches!(authorizer.verify_auth(&client_id), IsAcceptClient::Accept(_)));
+}
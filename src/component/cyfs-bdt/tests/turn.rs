@@ -0,0 +1,355 @@
+use async_std::{
+    channel, future,
+    io::prelude::{ReadExt, WriteExt},
+    task,
+};
+use cyfs_base::*;
+use futures::StreamExt;
+use cyfs_bdt::{
+    *,
+    sn::service::*,
+    pn::client::*
+};
+use std::{
+    collections::BTreeSet,
+    net::{IpAddr, Shutdown, SocketAddr, UdpSocket},
+    sync::{Arc, Mutex, atomic::{AtomicUsize, Ordering}},
+    thread,
+    time::Duration
+};
+mod utils;
+
+
+struct TestServer {
+
+}
+
+impl SnServiceContractServer for TestServer {
+    fn check_receipt(
+        &self,
+        _client_device: &Device,
+        _local_receipt: &SnServiceReceipt,
+        _client_receipt: &Option<ReceiptWithSignature>,
+        _last_request_time: &ReceiptRequestTime,
+    ) -> IsAcceptClient {
+        IsAcceptClient::Accept(false)
+    }
+
+    fn verify_auth(&self, _client_device_id: &DeviceId) -> IsAcceptClient {
+        IsAcceptClient::Accept(false)
+    }
+}
+
+
+// 本地的TURN server替身，只支持单个allocation
+struct TurnStandInImpl {
+    socket: UdpSocket,
+    relay: UdpSocket,
+    realm: String,
+    nonce: String,
+    key: Vec<u8>,
+    // 用错误的key给成功响应签名，模拟伪造的响应
+    forge: bool,
+    client: Mutex<Option<SocketAddr>>,
+    permissions: Mutex<BTreeSet<IpAddr>>,
+    to_peer: AtomicUsize,
+    to_client: AtomicUsize,
+}
+
+#[derive(Clone)]
+struct TurnStandIn(Arc<TurnStandInImpl>);
+
+impl TurnStandIn {
+    fn open(server: &str, username: &str, password: &str) -> Self {
+        Self::open_with(server, username, password, false)
+    }
+
+    fn open_with(server: &str, username: &str, password: &str, forge: bool) -> Self {
+        let socket = UdpSocket::bind(server).unwrap();
+        let relay = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let realm = "cyfs.test".to_owned();
+        let stand_in = Self(Arc::new(TurnStandInImpl {
+            socket,
+            relay,
+            key: StunMessage::long_term_key(username, &realm, password),
+            realm,
+            nonce: "turn-test-nonce".to_owned(),
+            forge,
+            client: Mutex::new(None),
+            permissions: Mutex::new(BTreeSet::new()),
+            to_peer: AtomicUsize::new(0),
+            to_client: AtomicUsize::new(0),
+        }));
+
+        {
+            let stand_in = stand_in.clone();
+            thread::spawn(move || stand_in.server_loop());
+        }
+        {
+            let stand_in = stand_in.clone();
+            thread::spawn(move || stand_in.relay_loop());
+        }
+
+        stand_in
+    }
+
+    fn relay_addr(&self) -> SocketAddr {
+        self.0.relay.local_addr().unwrap()
+    }
+
+    fn is_permitted(&self, ip: &IpAddr) -> bool {
+        self.0.permissions.lock().unwrap().contains(ip)
+    }
+
+    fn to_peer(&self) -> usize {
+        self.0.to_peer.load(Ordering::SeqCst)
+    }
+
+    fn to_client(&self) -> usize {
+        self.0.to_client.load(Ordering::SeqCst)
+    }
+
+    fn respond(&self, req: &StunMessage, resp: &StunMessage, to: &SocketAddr) {
+        let mut resp = resp.clone();
+        resp.transaction_id = req.transaction_id;
+        let key = if self.0.forge {
+            StunMessage::long_term_key("bdt", &self.0.realm, "forged")
+        } else {
+            self.0.key.clone()
+        };
+        let _ = self.0.socket.send_to(&resp.encode(Some(&key)), to);
+    }
+
+    fn unauthorized(&self, req: &StunMessage, to: &SocketAddr) {
+        let mut resp = StunMessage::with_transaction(req.method, StunClass::ErrorResponse, req.transaction_id);
+        resp.add(StunAttribute::ErrorCode(401, "Unauthorized".to_owned()));
+        resp.add(StunAttribute::Realm(self.0.realm.clone()));
+        resp.add(StunAttribute::Nonce(self.0.nonce.clone()));
+        let _ = self.0.socket.send_to(&resp.encode(None), to);
+    }
+
+    fn server_loop(&self) {
+        let mut buf = [0u8; 4096];
+        loop {
+            let (len, from) = self.0.socket.recv_from(&mut buf).unwrap();
+            let recv = &buf[..len];
+            let req = match StunMessage::decode(recv) {
+                Ok(req) => req,
+                Err(_) => continue
+            };
+
+            if req.class == StunClass::Indication {
+                if req.method == StunMethod::Send && Some(from) == *self.0.client.lock().unwrap() {
+                    if let (Some(peer), Some(data)) = (req.xor_peer_address(), req.data()) {
+                        if self.is_permitted(&peer.ip()) {
+                            let _ = self.0.relay.send_to(data, peer);
+                            self.0.to_peer.fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+                }
+                continue;
+            }
+
+            if !StunMessage::check_integrity(recv, &self.0.key) {
+                self.unauthorized(&req, &from);
+                continue;
+            }
+            assert_eq!(req.nonce(), Some(self.0.nonce.as_str()));
+
+            let mut resp = StunMessage::new(req.method, StunClass::SuccessResponse);
+            match req.method {
+                StunMethod::Allocate => {
+                    *self.0.client.lock().unwrap() = Some(from);
+                    resp.add(StunAttribute::XorRelayedAddress(self.relay_addr()));
+                    resp.add(StunAttribute::XorMappedAddress(from));
+                    resp.add(StunAttribute::Lifetime(req.lifetime().unwrap_or(600)));
+                },
+                StunMethod::Refresh => {
+                    resp.add(StunAttribute::Lifetime(req.lifetime().unwrap_or(600)));
+                },
+                StunMethod::CreatePermission => {
+                    let mut permissions = self.0.permissions.lock().unwrap();
+                    for peer in req.xor_peer_addresses() {
+                        permissions.insert(peer.ip());
+                    }
+                },
+                _ => continue
+            }
+            self.respond(&req, &resp, &from);
+        }
+    }
+
+    fn relay_loop(&self) {
+        let mut buf = [0u8; 4096];
+        loop {
+            let (len, from) = self.0.relay.recv_from(&mut buf).unwrap();
+            // 没有permission的peer发来的数据直接丢掉
+            if !self.is_permitted(&from.ip()) {
+                continue;
+            }
+            let client = self.0.client.lock().unwrap().clone();
+            if let Some(client) = client {
+                let mut indication = StunMessage::new(StunMethod::Data, StunClass::Indication);
+                indication.add(StunAttribute::XorPeerAddress(from));
+                indication.add(StunAttribute::Data(buf[..len].to_vec()));
+                let _ = self.0.socket.send_to(&indication.encode(None), client);
+                self.0.to_client.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+
+async fn recv_large_stream(stack: StackGuard, sender: channel::Sender<Vec<u8>>) {
+    let acceptor = stack.stream_manager().listen(0).unwrap();
+    let mut incoming = acceptor.incoming();
+    loop {
+        let mut pre_stream = incoming.next().await.unwrap().unwrap();
+        pre_stream.stream.confirm(vec![].as_ref()).await.unwrap();
+        let mut buffer = vec![];
+        let _ = pre_stream.stream.read_to_end(&mut buffer).await.unwrap();
+        let _ = pre_stream.stream.shutdown(Shutdown::Both);
+        sender.send(buffer).await.unwrap();
+    }
+}
+
+
+#[test]
+fn stun_message_codec() {
+    let peer: SocketAddr = "192.168.1.10:3478".parse().unwrap();
+    let mut msg = StunMessage::new(StunMethod::CreatePermission, StunClass::Request);
+    msg.add(StunAttribute::XorPeerAddress(peer));
+    msg.add(StunAttribute::Username("bdt".to_owned()));
+    let key = StunMessage::long_term_key("bdt", "cyfs.test", "pass");
+    let buf = msg.encode(Some(&key));
+
+    assert!(StunMessage::is_stun(&buf));
+    assert!(StunMessage::check_integrity(&buf, &key));
+    assert!(!StunMessage::check_integrity(&buf, &StunMessage::long_term_key("bdt", "cyfs.test", "wrong")));
+
+    let decoded = StunMessage::decode(&buf).unwrap();
+    assert_eq!(decoded.method, StunMethod::CreatePermission);
+    assert_eq!(decoded.class, StunClass::Request);
+    assert_eq!(decoded.transaction_id, msg.transaction_id);
+    assert_eq!(decoded.xor_peer_address(), Some(peer));
+    assert_eq!(decoded.username(), Some("bdt"));
+
+    let ipv6_peer: SocketAddr = "[fe80::1]:5000".parse().unwrap();
+    let buf = StunMessage::send_indication(&ipv6_peer, &[1u8, 2, 3, 4, 5]);
+    let decoded = StunMessage::decode(&buf).unwrap();
+    assert_eq!(decoded.method, StunMethod::Send);
+    assert_eq!(decoded.class, StunClass::Indication);
+    assert_eq!(decoded.xor_peer_address(), Some(ipv6_peer));
+    assert_eq!(decoded.data(), Some(&[1u8, 2, 3, 4, 5][..]));
+}
+
+
+#[async_std::test]
+async fn turn_relay() {
+    let turn = TurnStandIn::open("127.0.0.1:10063", "bdt", "turn-pass");
+
+    let (sn, sn_secret) = utils::create_device("5aSixgLuJjfrNKn9D4z66TEM6oxL3uNmWCWHk52cJDKR", &["W4udp127.0.0.1:10060"]).unwrap();
+    let service = SnService::new(
+        sn.clone(),
+        sn_secret,
+        Box::new(TestServer {}),
+    );
+    task::spawn(async move {
+        let _ = service.start().await;
+    });
+
+    let (ln_dev, ln_secret) = utils::create_device("5aSixgLuJjfrNKn9D4z66TEM6oxL3uNmWCWHk52cJDKR", &["L4udp127.0.0.1:10061"]).unwrap();
+    let (rn_dev, rn_secret) = utils::create_device("5aSixgLuJjfrNKn9D4z66TEM6oxL3uNmWCWHk52cJDKR", &["L4udp127.0.0.1:10062"]).unwrap();
+
+    let mut ln_params = StackOpenParams::new("");
+    ln_params.known_device = Some(vec![sn.clone()]);
+    let ln_stack = Stack::open(
+        ln_dev.clone(),
+        ln_secret,
+        ln_params).await.unwrap();
+
+    let mut rn_params = StackOpenParams::new("");
+    rn_params.known_sn = Some(vec![sn.clone()]);
+    rn_params.config.turn.servers = vec![TurnServer {
+        server: "127.0.0.1:10063".parse().unwrap(),
+        username: "bdt".to_owned(),
+        password: "turn-pass".to_owned()
+    }];
+    let rn_stack = Stack::open(
+        rn_dev,
+        rn_secret,
+        rn_params).await.unwrap();
+
+    // open返回时第一次allocate已经完成
+    let relayed = rn_stack.proxy_manager().turn_clients()[0].relayed().unwrap();
+    assert_eq!(*relayed.addr(), turn.relay_addr());
+
+    assert_eq!(SnStatus::Online, rn_stack.reset_sn_list(vec![sn.clone()]).wait_online().await.unwrap());
+
+    // relay地址通过sn ping发布出去，ping sn不会创建permission
+    future::timeout(Duration::from_secs(5), async {
+        loop {
+            let local = rn_stack.sn_client().ping().default_local();
+            if local.connect_info().endpoints().contains(&relayed) {
+                break;
+            }
+            task::sleep(Duration::from_millis(100)).await;
+        }
+    }).await.unwrap();
+
+    let (sample_size, sample) = utils::random_mem(1024, 512);
+    let (signal_sender, signal_recver) = channel::bounded::<Vec<u8>>(1);
+    {
+        let rn_stack = rn_stack.clone();
+        task::spawn(async move {
+            recv_large_stream(rn_stack, signal_sender).await;
+        });
+    }
+
+    let param = BuildTunnelParams {
+        remote_const: rn_stack.local_const().clone(),
+        remote_sn: Some(vec![sn.desc().device_id()]),
+        remote_desc: None,
+    };
+    let mut stream = ln_stack
+        .stream_manager()
+        .connect(0u16, vec![], param)
+        .await.unwrap();
+    stream.write_all(&sample[..]).await.unwrap();
+
+    let _ = stream.shutdown(Shutdown::Both);
+
+    let recv_sample = future::timeout(Duration::from_secs(5), signal_recver.recv()).await.unwrap().unwrap();
+
+    assert_eq!(recv_sample.len(), sample_size);
+    let sample_hash = hash_data(sample.as_ref());
+    let recv_hash = hash_data(recv_sample.as_ref());
+    assert_eq!(sample_hash, recv_hash);
+
+    // 建立tunnel时为ln所在的ip创建了permission，ln发到relay地址的数据被转发给rn，rn的回复也经过了TURN server
+    assert!(turn.is_permitted(&"127.0.0.1".parse().unwrap()));
+    assert!(turn.to_client() > 0);
+    assert!(turn.to_peer() > 0);
+}
+
+
+#[async_std::test]
+async fn turn_ignore_forged_response() {
+    let server = format!("127.0.0.1:{}", utils::free_port());
+    let _turn = TurnStandIn::open_with(server.as_str(), "bdt", "turn-pass", true);
+
+    let ep = format!("L4udp127.0.0.1:{}", utils::free_port());
+    let (dev, secret) = utils::create_device("5aSixgLuJjfrNKn9D4z66TEM6oxL3uNmWCWHk52cJDKR", &[ep.as_str()]).unwrap();
+    let mut params = StackOpenParams::new("");
+    params.config.turn.servers = vec![TurnServer {
+        server: server.parse().unwrap(),
+        username: "bdt".to_owned(),
+        password: "turn-pass".to_owned()
+    }];
+    let stack = Stack::open(dev, secret, params).await.unwrap();
+
+    // MESSAGE-INTEGRITY校验不过的allocate响应被忽略，不会使用其中的relay地址
+    let client = stack.proxy_manager().turn_clients()[0].clone();
+    assert!(client.relayed().is_none());
+    assert!(client.interface().turn_relayed().is_none());
+}
@@ -12,6 +12,16 @@ pub fn random_mem(piece: usize, count: usize) -> (usize, Vec<u8>) {
     (piece * count, buffer)
 }
 
+// 分配一个tcp和udp都空闲的本地端口
+pub fn free_port() -> u16 {
+    loop {
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        if std::net::UdpSocket::bind(("127.0.0.1", port)).is_ok() {
+            return port;
+        }
+    }
+}
+
 pub fn create_device(owner: &str, endpoints: &[&str]) -> BuckyResult<(Device, PrivateKey)> {
     let private_key = PrivateKey::generate_rsa(1024).unwrap();
     let public_key = private_key.public();
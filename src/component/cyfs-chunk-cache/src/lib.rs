@@ -39,7 +39,25 @@ pub async fn create_local_chunk_cache(
         }
     }
 
-    let cache: SingleDiskChunkCache = SingleDiskChunkCache::new(chunk_dir);
+    let crypto = cyfs_util::StorageCryptoManager::get(isolate);
+    let cache: SingleDiskChunkCache = SingleDiskChunkCache::new(chunk_dir, crypto);
 
     Ok(Box::new(cache))
 }
+
+// 迁移isolate下本地chunk缓存的加密状态，需要在协议栈停止后执行
+pub async fn migrate_chunk_cache_crypto(
+    data_root: &Path,
+    isolate: &str,
+    crypto: &cyfs_util::StorageCryptoRef,
+    encrypt: bool,
+) -> BuckyResult<cyfs_util::StorageCryptoMigrateStat> {
+    let isolate = if isolate.is_empty() {
+        "default"
+    } else {
+        isolate
+    };
+    let chunk_dir = data_root.join("chunk-cache").join(isolate);
+
+    cyfs_util::StorageCrypto::migrate_dir(crypto, &chunk_dir, encrypt, &["cache.meta"]).await
+}
@@ -2,6 +2,7 @@ use crate::{Chunk, ChunkCache, ChunkMut, ChunkType, MMapChunk, MMapChunkMut, Mem
 use cyfs_base::*;
 use cyfs_chunk_lib::{ChunkMeta};
 use cyfs_debug::Mutex;
+use cyfs_util::{StorageCrypto, StorageCryptoRef};
 use futures_lite::AsyncWriteExt;
 use num_traits::abs;
 use num_traits::float::Float;
@@ -189,12 +190,14 @@ pub(crate) struct LocalChunkCache<CACHE: TSingleDiskChunkCache + ChunkCache, SCA
     cache_meta: Mutex<LocalChunkCacheMeta>,
     scanner: SCANNER,
     isolate: String,
+    crypto: Option<StorageCryptoRef>,
 }
 
 impl<CACHE: TSingleDiskChunkCache + ChunkCache, SCANNER: DiskScanner>
     LocalChunkCache<CACHE, SCANNER>
 {
     pub async fn new(isolate: &str, scanner: SCANNER) -> BuckyResult<Self> {
+        let crypto = cyfs_util::StorageCryptoManager::get(isolate);
         let obj = Self {
             disk_cache_list: RwLock::new(Vec::new()),
             cache_meta: Mutex::new(LocalChunkCacheMeta::new()),
@@ -204,6 +207,7 @@ impl<CACHE: TSingleDiskChunkCache + ChunkCache, SCANNER: DiskScanner>
             } else {
                 isolate.to_string()
             },
+            crypto,
         };
         obj.refresh_cache().await?;
         Ok(obj)
@@ -237,7 +241,7 @@ impl<CACHE: TSingleDiskChunkCache + ChunkCache, SCANNER: DiskScanner>
             }
             let cache = match self.get_cache(path.as_path()) {
                 Some(cache) => cache,
-                None => Arc::new(CACHE::new(path.to_path_buf())),
+                None => Arc::new(CACHE::new(path.to_path_buf(), self.crypto.clone())),
             };
             let cache_meta = cache.get_local_cache_meta()?;
             let weight = (space / 1024 / 1024 / 1024) as u32;
@@ -387,7 +391,10 @@ impl<CACHE: TSingleDiskChunkCache + ChunkCache, SCANNER: DiskScanner>
             let mut max = f64::min_value();
             let mut max_cache = None;
             for (cache_path, weight) in record.list.iter() {
-                let tmp_cache = Arc::new(CACHE::new(PathBuf::from(cache_path.to_string())));
+                let tmp_cache = Arc::new(CACHE::new(
+                    PathBuf::from(cache_path.to_string()),
+                    self.crypto.clone(),
+                ));
                 let hash = Self::hash(chunk_id, tmp_cache.get_cache_id());
                 let v = (hash as f64 / u64::MAX as f64).ln() / (*weight as f64);
                 if v > max {
@@ -416,7 +423,10 @@ impl<CACHE: TSingleDiskChunkCache + ChunkCache, SCANNER: DiskScanner>
             let mut max = f64::min_value();
             let mut max_cache = None;
             for (cache_path, weight) in record.list.iter() {
-                let tmp_cache = Arc::new(CACHE::new(PathBuf::from(cache_path.to_string())));
+                let tmp_cache = Arc::new(CACHE::new(
+                    PathBuf::from(cache_path.to_string()),
+                    self.crypto.clone(),
+                ));
                 let hash = Self::hash(chunk_id, tmp_cache.get_cache_id());
                 let v = (hash as f64 / u64::MAX as f64).ln() / (*weight as f64);
                 if v > max {
@@ -518,7 +528,7 @@ impl<CACHE: TSingleDiskChunkCache + ChunkCache, SCANNER: DiskScanner> ChunkCache
 }
 
 pub(crate) trait TSingleDiskChunkCache {
-    fn new(path: PathBuf) -> Self;
+    fn new(path: PathBuf, crypto: Option<StorageCryptoRef>) -> Self;
     fn get_cache_id(&self) -> &HashValue;
     fn get_cache_path(&self) -> &Path;
     fn get_local_cache_meta(&self) -> BuckyResult<LocalChunkCacheMeta>;
@@ -529,6 +539,9 @@ pub struct SingleDiskChunkCache {
     path: PathBuf,
     cache_id: HashValue,
 
+    // 启用存储加密后，chunk以加密形式整体落盘，无法再使用mmap直接访问
+    crypto: Option<StorageCryptoRef>,
+
     #[cfg(target_os = "windows")]
     upgrade: super::old_base36::ChunkStorageUpgrade,
 }
@@ -617,11 +630,120 @@ impl SingleDiskChunkCache {
             }
         };
 
-        if chunk_id.len() as u64 != file_meta.len() {
+        if chunk_id.len() as u64 == file_meta.len() {
+            true
+        } else if let Some(crypto) = &self.crypto {
+            // 密文长度的文件还需要校验头部，并且使用的密钥可用
+            (chunk_id.len() + StorageCrypto::overhead()) as u64 == file_meta.len()
+                && Self::check_encrypted_header(crypto, &file_path)
+        } else {
             false
+        }
+    }
+
+    fn check_encrypted_header(crypto: &StorageCryptoRef, file_path: &Path) -> bool {
+        use std::io::Read;
+
+        let mut header = vec![0u8; StorageCrypto::header_len()];
+        let ret = std::fs::File::open(file_path).and_then(|mut file| file.read_exact(&mut header));
+        if let Err(e) = ret {
+            log::error!("read chunk file header error! file={}, {}", file_path.display(), e);
+            return false;
+        }
+
+        crypto.check_header(&header)
+    }
+
+    async fn read_decrypted(&self, chunk_id: &ChunkId, file_path: &Path) -> BuckyResult<Vec<u8>> {
+        let buf = async_std::fs::read(file_path).await.map_err(|e| {
+            let msg = format!(
+                "open chunk's file error! chunk={}, file={}, {}",
+                chunk_id,
+                file_path.display(),
+                e
+            );
+            log::error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::IoError, msg)
+        })?;
+
+        // 按文件长度区分启用加密前写入的明文和密文，明文可能恰好以magic开头
+        let chunk_len = chunk_id.len();
+        let data = if buf.len() == chunk_len {
+            buf
+        } else if buf.len() == chunk_len + StorageCrypto::overhead() {
+            let crypto = self.crypto.as_ref().unwrap();
+            crypto.decrypt(&buf).map_err(|e| {
+                let msg = format!(
+                    "decrypt chunk's file error! chunk={}, file={}, {}",
+                    chunk_id,
+                    file_path.display(),
+                    e
+                );
+                log::error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::CryptoError, msg)
+            })?
         } else {
-            true
+            let msg = format!(
+                "chunk's file length unmatch! chunk={}, file={}, len={}",
+                chunk_id,
+                file_path.display(),
+                buf.len()
+            );
+            log::error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
+        };
+
+        if data.len() != chunk_len {
+            let msg = format!(
+                "decrypted chunk length unmatch! chunk={}, file={}, len={}",
+                chunk_id,
+                file_path.display(),
+                data.len()
+            );
+            log::error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
         }
+
+        Ok(data)
+    }
+
+    // 加密需要完整的数据，所以先读取整个chunk并校验，再加密后写入文件
+    async fn write_encrypted_with_verify(
+        crypto: &StorageCryptoRef,
+        chunk_id: &ChunkId,
+        mut chunk: Box<dyn Chunk>,
+        mut file: async_std::fs::File,
+        file_path: &Path,
+    ) -> BuckyResult<()> {
+        let mut data = vec![0u8; chunk_id.len()];
+        let mut read = 0;
+        while read < data.len() {
+            let bytes = chunk.read(&mut data[read..]).await?;
+            if bytes == 0 {
+                break;
+            }
+            read += bytes;
+        }
+
+        let actual_id = ChunkId::calculate_sync(&data[..read])?;
+        if actual_id != *chunk_id {
+            let msg = format!("mismatched chunk hash value! chunk={}, len={}, got={}", chunk_id, chunk_id.len(), actual_id);
+            log::error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
+        }
+
+        let data = crypto.encrypt(&data)?;
+        file.write_all(&data).await.map_err(|e| {
+            let msg = format!(
+                "write encrypted chunk to file failed! chunk={}, file={}, {}",
+                chunk_id,
+                file_path.display(),
+                e
+            );
+            log::error!("{}", msg);
+            let e: BuckyError = e.into();
+            e.with_msg(msg)
+        })
     }
 
     async fn write_with_verify(
@@ -687,7 +809,7 @@ impl SingleDiskChunkCache {
 }
 
 impl TSingleDiskChunkCache for SingleDiskChunkCache {
-    fn new(path: PathBuf) -> Self {
+    fn new(path: PathBuf, crypto: Option<StorageCryptoRef>) -> Self {
         let cache_id = hash_data(path.to_string_lossy().to_string().as_bytes());
         Self {
             #[cfg(target_os = "windows")]
@@ -695,6 +817,7 @@ impl TSingleDiskChunkCache for SingleDiskChunkCache {
 
             path,
             cache_id,
+            crypto,
         }
     }
 
@@ -757,6 +880,12 @@ impl ChunkCache for SingleDiskChunkCache {
             }
         }
 
+        if self.crypto.is_some() {
+            let buf = self.read_decrypted(chunk_id, &file_path).await?;
+            let chunk: Box<dyn Chunk> = Box::new(MemChunk::from(buf));
+            return Ok(chunk);
+        }
+
         match chunk_type {
             ChunkType::MMapChunk => {
                 let chunk: Box<dyn Chunk> = Box::new(MMapChunk::open(file_path, None).await?);
@@ -782,6 +911,15 @@ impl ChunkCache for SingleDiskChunkCache {
     }
 
     async fn new_chunk(&self, chunk_id: &ChunkId) -> BuckyResult<Box<dyn ChunkMut>> {
+        if self.crypto.is_some() {
+            let msg = format!(
+                "new mmap chunk not support when storage crypto enabled! chunk={}",
+                chunk_id
+            );
+            log::error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+        }
+
         let file_path = self.get_file_path(chunk_id, true);
         log::info!("new chunk {}", file_path.to_string_lossy().to_string());
         if file_path.exists() {
@@ -850,7 +988,13 @@ impl ChunkCache for SingleDiskChunkCache {
 
         chunk.as_mut().seek(std::io::SeekFrom::Start(0)).await?;
 
-        if let Err(e) = Self::write_with_verify(chunk_id.to_owned(), chunk, file.clone(), &file_path).await {
+        let ret = match &self.crypto {
+            Some(crypto) => {
+                Self::write_encrypted_with_verify(crypto, chunk_id, chunk, file.clone(), &file_path).await
+            }
+            None => Self::write_with_verify(chunk_id.to_owned(), chunk, file.clone(), &file_path).await,
+        };
+        if let Err(e) = ret {
             if let Err(e) = async_std::fs::remove_file(&file_path).await {
                 log::error!("remove chunk local cache file failed! chunk={}, {}", chunk_id, e);
             }
@@ -932,6 +1076,11 @@ impl ChunkCache for SingleDiskChunkCache {
             return Err(BuckyError::new(BuckyErrorCode::NotFound, msg));
         }
 
+        if self.crypto.is_some() {
+            let buf = self.read_decrypted(chunk_id, &file_path).await?;
+            return Ok(ChunkMeta::MemChunk(buf));
+        }
+
         match chunk_type {
             ChunkType::MMapChunk => Ok(ChunkMeta::MMapChunk(
                 file_path.to_string_lossy().to_string(),
@@ -964,6 +1113,7 @@ mod test_local_chunk_cache {
     };
     use cyfs_base::{hash_data, BuckyError, BuckyErrorCode, BuckyResult, ChunkId, HashValue};
     use cyfs_chunk_lib::ChunkMeta;
+    use cyfs_util::StorageCryptoRef;
     use futures_lite::{AsyncReadExt, AsyncWriteExt};
     use std::collections::HashMap;
     use std::io::{SeekFrom, Write};
@@ -1047,7 +1197,7 @@ mod test_local_chunk_cache {
     }
 
     impl TSingleDiskChunkCache for SingleDiskChunkCacheMock {
-        fn new(path: PathBuf) -> Self {
+        fn new(path: PathBuf, _crypto: Option<StorageCryptoRef>) -> Self {
             let cache_id = hash_data(path.to_string_lossy().to_string().as_bytes());
             Self {
                 path,
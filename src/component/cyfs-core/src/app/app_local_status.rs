@@ -90,6 +90,12 @@ pub enum SubErrorCode {
     AssignContainerIpFailed = 10,
     RegisterAppFailed = 11,
     PubDirFailed = 12,
+    OutOfMemory = 13,
+    DependencyMissing = 14,
+    DependencyVersionMismatch = 15,
+    DependencyNotRunning = 16,
+    DependencyCycle = 17,
+    DiskQuotaExceeded = 18,
     Unknown = 255,
 }
 
@@ -109,6 +115,12 @@ impl fmt::Display for SubErrorCode {
             &SubErrorCode::AssignContainerIpFailed => write!(f, "AssignContainerIpFailed"),
             &SubErrorCode::RegisterAppFailed => write!(f, "RegisterAppFailed"),
             &SubErrorCode::PubDirFailed => write!(f, "PubDirFailed"),
+            &SubErrorCode::OutOfMemory => write!(f, "OutOfMemory"),
+            &SubErrorCode::DependencyMissing => write!(f, "DependencyMissing"),
+            &SubErrorCode::DependencyVersionMismatch => write!(f, "DependencyVersionMismatch"),
+            &SubErrorCode::DependencyNotRunning => write!(f, "DependencyNotRunning"),
+            &SubErrorCode::DependencyCycle => write!(f, "DependencyCycle"),
+            &SubErrorCode::DiskQuotaExceeded => write!(f, "DiskQuotaExceeded"),
             &SubErrorCode::Unknown => write!(f, "Unknown"),
         }
     }
@@ -163,6 +175,68 @@ pub struct PermissionNode {
     state: PermissionState,
 }
 
+// app运行时的资源使用情况，由app-manager定期采样
+#[derive(Clone, Debug, Default, Eq, PartialEq, ProtobufTransformType, Serialize)]
+#[cyfs_protobuf_type(crate::codec::protos::AppResourceUsage)]
+pub struct AppResourceUsage {
+    // 单核cpu的百分比
+    pub cpu_usage: u32,
+    // 内存，单位字节
+    pub mem_usage: u64,
+    pub mem_limit: u64,
+    // 网络累计收发字节数
+    pub net_rx: u64,
+    pub net_tx: u64,
+    // 块设备累计读写字节数
+    pub block_read: u64,
+    pub block_write: u64,
+    // 数据目录占用的磁盘空间，单位字节
+    pub disk_usage: u64,
+    pub pids: u32,
+    pub oom_killed: bool,
+    // 采样周期内cpu是否被限流
+    pub throttled: bool,
+    pub update_time: u64,
+}
+
+impl ProtobufTransform<protos::AppResourceUsage> for AppResourceUsage {
+    fn transform(value: protos::AppResourceUsage) -> BuckyResult<Self> {
+        Ok(Self {
+            cpu_usage: value.cpu_usage,
+            mem_usage: value.mem_usage.parse::<u64>()?,
+            mem_limit: value.mem_limit.parse::<u64>()?,
+            net_rx: value.net_rx.parse::<u64>()?,
+            net_tx: value.net_tx.parse::<u64>()?,
+            block_read: value.block_read.parse::<u64>()?,
+            block_write: value.block_write.parse::<u64>()?,
+            disk_usage: value.disk_usage.parse::<u64>()?,
+            pids: value.pids,
+            oom_killed: value.oom_killed,
+            throttled: value.throttled,
+            update_time: value.update_time.parse::<u64>()?,
+        })
+    }
+}
+
+impl ProtobufTransform<&AppResourceUsage> for protos::AppResourceUsage {
+    fn transform(value: &AppResourceUsage) -> BuckyResult<Self> {
+        Ok(Self {
+            cpu_usage: value.cpu_usage,
+            mem_usage: value.mem_usage.to_string(),
+            mem_limit: value.mem_limit.to_string(),
+            net_rx: value.net_rx.to_string(),
+            net_tx: value.net_tx.to_string(),
+            block_read: value.block_read.to_string(),
+            block_write: value.block_write.to_string(),
+            disk_usage: value.disk_usage.to_string(),
+            pids: value.pids,
+            oom_killed: value.oom_killed,
+            throttled: value.throttled,
+            update_time: value.update_time.to_string(),
+        })
+    }
+}
+
 #[derive(Clone, ProtobufEncode, ProtobufDecode, ProtobufTransformType, Serialize)]
 #[cyfs_protobuf_type(crate::codec::protos::AppLocalStatusDesc)]
 pub struct AppLocalStatusDesc {
@@ -175,6 +249,7 @@ pub struct AppLocalStatusDesc {
     last_status_update_time: u64,
     sub_error: SubErrorCode,
     auto_update: bool,
+    usage: Option<AppResourceUsage>,
 }
 impl DescContent for AppLocalStatusDesc {
     fn obj_type() -> u16 {
@@ -213,6 +288,7 @@ impl ProtobufTransform<protos::AppLocalStatusDesc> for AppLocalStatusDesc {
             last_status_update_time: value.last_status_update_time.parse::<u64>()?,
             sub_error: ProtobufCodecHelper::decode_value(value.sub_error as u8)?,
             auto_update: value.auto_update,
+            usage: None,
         };
         if value.version.is_some() {
             ret.version = Some(value.version.unwrap());
@@ -220,6 +296,9 @@ impl ProtobufTransform<protos::AppLocalStatusDesc> for AppLocalStatusDesc {
         if value.web_dir.is_some() {
             ret.web_dir = Some(ProtobufCodecHelper::decode_buf(value.web_dir.unwrap())?);
         }
+        if value.usage.is_some() {
+            ret.usage = Some(ProtobufTransform::transform(value.usage.unwrap())?);
+        }
 
         Ok(ret)
     }
@@ -243,6 +322,7 @@ impl ProtobufTransform<&AppLocalStatusDesc> for protos::AppLocalStatusDesc {
             last_status_update_time: value.last_status_update_time.to_string(),
             sub_error: value.sub_error as u32,
             auto_update: value.auto_update,
+            usage: None,
         };
         ret.id = value.id.to_vec()?;
         if let Some(dir) = &value.web_dir {
@@ -251,6 +331,9 @@ impl ProtobufTransform<&AppLocalStatusDesc> for protos::AppLocalStatusDesc {
         if let Some(version) = &value.version {
             ret.version = Some(version.to_owned());
         }
+        if let Some(usage) = &value.usage {
+            ret.usage = Some(ProtobufTransform::transform(usage)?);
+        }
 
         let mut permissions = Vec::new();
         for (k, v) in &value.permissions {
@@ -288,6 +371,7 @@ pub trait AppLocalStatusObj {
     fn last_status_update_time(&self) -> u64;
     fn sub_error(&self) -> SubErrorCode;
     fn auto_update(&self) -> bool;
+    fn usage(&self) -> Option<&AppResourceUsage>;
 
     fn set_status(&mut self, status: AppLocalStatusCode);
     fn set_web_dir(&mut self, web_dir: Option<ObjectId>);
@@ -301,6 +385,8 @@ pub trait AppLocalStatusObj {
     fn set_sub_error(&mut self, code: SubErrorCode);
     //return old auto_update value
     fn set_auto_update(&mut self, auto_update: bool) -> bool;
+    //更新资源使用情况，None表示app没有在运行
+    fn set_usage(&mut self, usage: Option<AppResourceUsage>);
 
     fn output(&self) -> String;
 }
@@ -323,6 +409,7 @@ impl AppLocalStatusObj for AppLocalStatus {
             last_status_update_time: bucky_time_now(),
             sub_error: SubErrorCode::None,
             auto_update: true,
+            usage: None,
         };
         let body = AppLocalStatusBody {};
         AppLocalStatusBuilder::new(desc, body)
@@ -456,6 +543,14 @@ impl AppLocalStatusObj for AppLocalStatus {
         old_value
     }
 
+    fn usage(&self) -> Option<&AppResourceUsage> {
+        self.desc().content().usage.as_ref()
+    }
+
+    fn set_usage(&mut self, usage: Option<AppResourceUsage>) {
+        self.desc_mut().content_mut().usage = usage;
+    }
+
     fn output(&self) -> String {
         let app_id = self.app_id();
         let status = self.status();
@@ -9,6 +9,8 @@ use serde::Serialize;
 pub struct DecIpInfo {
     pub name: String,
     pub ip: String,
+    // app安装时分配的dec公钥(hex)，用以校验app请求携带的凭证
+    pub public_key: Option<String>,
 }
 
 #[derive(Clone, ProtobufTransform, Serialize)]
@@ -35,6 +35,84 @@ pub struct DecAppContent {
     desc: Option<String>,
     source_desc: HashMap<String, String>,
     tags: HashMap<String, String>,
+    quota: Option<DecAppQuota>,
+    dependencies: Vec<DecAppDependency>,
+}
+
+// app依赖的其它dec app，version是semver的版本范围，比如"^1.2"
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct DecAppDependency {
+    pub id: DecAppId,
+    pub version: String,
+}
+
+impl ProtobufTransform<protos::DecAppDependency> for DecAppDependency {
+    fn transform(value: protos::DecAppDependency) -> BuckyResult<Self> {
+        Ok(Self {
+            id: DecAppId::clone_from_slice(value.id.as_slice())?,
+            version: value.version,
+        })
+    }
+}
+
+impl ProtobufTransform<&DecAppDependency> for protos::DecAppDependency {
+    fn transform(value: &DecAppDependency) -> BuckyResult<Self> {
+        Ok(Self {
+            id: value.id.to_vec()?,
+            version: value.version.clone(),
+        })
+    }
+}
+
+// app声明的资源配额，运行时由app-manager强制执行，未声明的项不做限制
+#[derive(Clone, Debug, Default, Eq, PartialEq, ProtobufTransformType, Serialize)]
+#[cyfs_protobuf_type(crate::codec::protos::DecAppQuota)]
+pub struct DecAppQuota {
+    // 单核cpu的百分比，200表示最多使用两个核
+    pub cpu: Option<u32>,
+    // 内存上限，单位MB
+    pub mem: Option<u64>,
+    // 数据目录的磁盘空间上限，单位MB
+    pub disk_space: Option<u64>,
+    // 网络带宽上限，单位kbit/s
+    pub net_bandwidth: Option<u64>,
+    // 最大进程/线程数
+    pub pids: Option<u32>,
+}
+
+impl ProtobufTransform<protos::DecAppQuota> for DecAppQuota {
+    fn transform(value: protos::DecAppQuota) -> BuckyResult<Self> {
+        let mut ret = Self {
+            cpu: value.cpu,
+            mem: None,
+            disk_space: None,
+            net_bandwidth: None,
+            pids: value.pids,
+        };
+        if let Some(mem) = value.mem {
+            ret.mem = Some(mem.parse::<u64>()?);
+        }
+        if let Some(disk_space) = value.disk_space {
+            ret.disk_space = Some(disk_space.parse::<u64>()?);
+        }
+        if let Some(net_bandwidth) = value.net_bandwidth {
+            ret.net_bandwidth = Some(net_bandwidth.parse::<u64>()?);
+        }
+
+        Ok(ret)
+    }
+}
+
+impl ProtobufTransform<&DecAppQuota> for protos::DecAppQuota {
+    fn transform(value: &DecAppQuota) -> BuckyResult<Self> {
+        Ok(Self {
+            cpu: value.cpu,
+            mem: value.mem.map(|v| v.to_string()),
+            disk_space: value.disk_space.map(|v| v.to_string()),
+            net_bandwidth: value.net_bandwidth.map(|v| v.to_string()),
+            pids: value.pids,
+        })
+    }
 }
 
 impl BodyContent for DecAppContent {
@@ -66,14 +144,23 @@ impl ProtobufTransform<protos::DecAppContent> for DecAppContent {
             icon: None,
             desc: None,
             tags,
+            quota: None,
+            dependencies: vec![],
         };
 
+        for item in value.dependencies {
+            ret.dependencies.push(ProtobufTransform::transform(item)?);
+        }
+
         if value.icon.is_some() {
             ret.icon = Some(value.icon.unwrap());
         }
         if value.desc.is_some() {
             ret.desc = Some(value.desc.unwrap());
         }
+        if value.quota.is_some() {
+            ret.quota = Some(ProtobufTransform::transform(value.quota.unwrap())?);
+        }
 
         Ok(ret)
     }
@@ -108,14 +195,23 @@ impl ProtobufTransform<&DecAppContent> for protos::DecAppContent {
             icon: None,
             desc: None,
             tags,
+            quota: None,
+            dependencies: vec![],
         };
 
+        for item in &value.dependencies {
+            ret.dependencies.push(ProtobufTransform::transform(item)?);
+        }
+
         if let Some(icon) = &value.icon {
             ret.icon = Some(icon.to_owned());
         }
         if let Some(desc) = &value.desc {
             ret.desc = Some(desc.to_owned());
         }
+        if let Some(quota) = &value.quota {
+            ret.quota = Some(ProtobufTransform::transform(quota)?);
+        }
 
         Ok(ret)
     }
@@ -153,6 +249,14 @@ pub trait DecAppObj {
     fn remove_tag(&mut self, tag: &str);
     fn tags(&self) -> &HashMap<String, String>;
 
+    fn quota(&self) -> Option<&DecAppQuota>;
+    fn set_quota(&mut self, quota: Option<DecAppQuota>);
+
+    fn dependencies(&self) -> &Vec<DecAppDependency>;
+    // 同一个app只保留一条依赖，重复设置会覆盖版本范围
+    fn set_dependency(&mut self, id: DecAppId, version: String);
+    fn remove_dependency(&mut self, id: &DecAppId);
+
     fn generate_id(owner: ObjectId, id: &str) -> ObjectId;
 }
 
@@ -190,6 +294,8 @@ impl DecAppObj for DecApp {
             desc: None,
             source_desc: HashMap::new(),
             tags: HashMap::new(),
+            quota: None,
+            dependencies: vec![],
         };
         let desc = DecAppDescContent { id: id.to_owned() };
         DecAppBuilder::new(desc, body)
@@ -381,6 +487,39 @@ impl DecAppObj for DecApp {
         &self.body_expect("").content().tags
     }
 
+    fn quota(&self) -> Option<&DecAppQuota> {
+        self.body_expect("").content().quota.as_ref()
+    }
+
+    fn set_quota(&mut self, quota: Option<DecAppQuota>) {
+        self.body_mut_expect("").content_mut().quota = quota;
+        self.body_mut_expect("")
+            .increase_update_time(bucky_time_now());
+    }
+
+    fn dependencies(&self) -> &Vec<DecAppDependency> {
+        &self.body_expect("").content().dependencies
+    }
+
+    fn set_dependency(&mut self, id: DecAppId, version: String) {
+        let dependencies = &mut self.body_mut_expect("").content_mut().dependencies;
+        match dependencies.iter_mut().find(|item| item.id == id) {
+            Some(item) => item.version = version,
+            None => dependencies.push(DecAppDependency { id, version }),
+        }
+        self.body_mut_expect("")
+            .increase_update_time(bucky_time_now());
+    }
+
+    fn remove_dependency(&mut self, id: &DecAppId) {
+        self.body_mut_expect("")
+            .content_mut()
+            .dependencies
+            .retain(|item| &item.id != id);
+        self.body_mut_expect("")
+            .increase_update_time(bucky_time_now());
+    }
+
     fn generate_id(owner: ObjectId, id: &str) -> ObjectId {
         Self::create(owner, id).desc().calculate_id()
     }
@@ -445,4 +584,47 @@ mod test {
         let ret = dec_app.find_version("<=1.3.8", None).unwrap();
         assert_eq!(ret, "1.3.7");
     }
+
+    #[test]
+    fn test_quota() {
+        let owner = ObjectId::default();
+        let mut dec_app = DecApp::create(owner, "test-dec-app");
+        assert!(dec_app.quota().is_none());
+
+        let quota = DecAppQuota {
+            cpu: Some(50),
+            mem: Some(256),
+            disk_space: None,
+            net_bandwidth: Some(1024),
+            pids: Some(100),
+        };
+        dec_app.set_quota(Some(quota.clone()));
+
+        let buf = dec_app.to_vec().unwrap();
+        let dec_app = DecApp::clone_from_slice(&buf).unwrap();
+        assert_eq!(dec_app.quota(), Some(&quota));
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let owner = ObjectId::default();
+        let mut dec_app = DecApp::create(owner.clone(), "test-dec-app");
+        assert!(dec_app.dependencies().is_empty());
+
+        let dep1 = DecAppId::try_from(DecApp::generate_id(owner.clone(), "dep1")).unwrap();
+        let dep2 = DecAppId::try_from(DecApp::generate_id(owner.clone(), "dep2")).unwrap();
+        dec_app.set_dependency(dep1.clone(), "^1.0".to_owned());
+        dec_app.set_dependency(dep2.clone(), "*".to_owned());
+        dec_app.set_dependency(dep1.clone(), "^1.2".to_owned());
+
+        let buf = dec_app.to_vec().unwrap();
+        let mut dec_app = DecApp::clone_from_slice(&buf).unwrap();
+        assert_eq!(dec_app.dependencies().len(), 2);
+        assert_eq!(dec_app.dependencies()[0].id, dep1);
+        assert_eq!(dec_app.dependencies()[0].version, "^1.2");
+
+        dec_app.remove_dependency(&dep1);
+        assert_eq!(dec_app.dependencies().len(), 1);
+        assert_eq!(dec_app.dependencies()[0].id, dep2);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,685 @@
+use crate::im::*;
+use crate::*;
+use cyfs_base::*;
+use serde_json::{Map, Value};
+use std::str::FromStr;
+
+fn build_text(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let id: String = JsonCodecHelper::decode_string_field(desc, "id")?;
+    let header: String = JsonCodecHelper::decode_string_field(desc, "header")?;
+
+    let body = param.body_content()?;
+    let value: String = JsonCodecHelper::decode_string_field(body, "value")?;
+
+    Text::build(&id, header, value)
+        .no_create_time()
+        .option_owner(param.owner.clone())
+        .option_dec_id(param.dec_id.clone())
+        .build()
+        .to_vec()
+}
+
+// body.content和format_json保持一致，为value的hex编码
+fn build_storage(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    if param.owner.is_some() || param.dec_id.is_some() {
+        let msg = "build storage object from json not support owner or dec_id!".to_owned();
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+    }
+
+    let desc = param.desc_content()?;
+    let id: String = JsonCodecHelper::decode_string_field(desc, "id")?;
+    let with_hash = match desc.get("hash") {
+        Some(Value::Null) | None => false,
+        Some(_) => true,
+    };
+
+    let value = match param.body.as_str() {
+        Some(v) => hex::decode(v).map_err(|e| {
+            let msg = format!("invalid storage body value, hex expected! {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?,
+        None => {
+            let msg = format!("invalid storage body value, hex expected! {}", param.body);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+        }
+    };
+
+    // hash字段只用来标识是否需要计算hash，实际值由value重新计算
+    let storage = if with_hash {
+        Storage::create_with_hash(&id, value)
+    } else {
+        Storage::create(&id, value)
+    };
+
+    storage.to_vec()
+}
+
+fn decode_msg_content(desc: &Map<String, Value>) -> BuckyResult<MsgContent> {
+    let content = desc.get("content").and_then(|v| v.as_object());
+    if let Some(content) = content {
+        if let Some(text) = content.get("Text") {
+            let text: String = JsonCodecHelper::decode_from_string(text)?;
+            return Ok(MsgContent::Text(text));
+        }
+
+        if let Some(Value::Object(obj)) = content.get("Object") {
+            let object = MsgObjectContent {
+                id: JsonCodecHelper::decode_string_field(obj, "id")?,
+                name: JsonCodecHelper::decode_string_field(obj, "name")?,
+            };
+            return Ok(MsgContent::Object(object));
+        }
+
+        if let Some(Value::Object(obj)) = content.get("Reply") {
+            return Ok(MsgContent::Reply(MsgReplyContent {
+                reply_to: JsonCodecHelper::decode_string_field(obj, "reply_to")?,
+                text: JsonCodecHelper::decode_string_field(obj, "text")?,
+            }));
+        }
+
+        if let Some(Value::Object(obj)) = content.get("Edit") {
+            return Ok(MsgContent::Edit(MsgEditContent {
+                target: JsonCodecHelper::decode_string_field(obj, "target")?,
+                text: JsonCodecHelper::decode_string_field(obj, "text")?,
+            }));
+        }
+
+        if let Some(Value::Object(obj)) = content.get("Delete") {
+            return Ok(MsgContent::Delete(MsgDeleteContent {
+                target: JsonCodecHelper::decode_string_field(obj, "target")?,
+            }));
+        }
+
+        if let Some(Value::Object(obj)) = content.get("Reaction") {
+            let remove = match obj.get("remove") {
+                Some(_) => JsonCodecHelper::decode_bool_field(obj, "remove")?,
+                None => false,
+            };
+            return Ok(MsgContent::Reaction(MsgReactionContent {
+                target: JsonCodecHelper::decode_string_field(obj, "target")?,
+                reaction: JsonCodecHelper::decode_string_field(obj, "reaction")?,
+                remove,
+            }));
+        }
+
+        if let Some(Value::Object(obj)) = content.get("File") {
+            return Ok(MsgContent::File(MsgFileContent {
+                id: JsonCodecHelper::decode_string_field(obj, "id")?,
+                name: JsonCodecHelper::decode_string_field(obj, "name")?,
+                mime: JsonCodecHelper::decode_string_field(obj, "mime")?,
+                size: JsonCodecHelper::decode_int_field(obj, "size")?,
+                thumb: JsonCodecHelper::decode_option_string_field(obj, "thumb")?,
+            }));
+        }
+
+        if let Some(Value::Object(obj)) = content.get("Receipt") {
+            let receipt_type: String = JsonCodecHelper::decode_string_field(obj, "receipt_type")?;
+            let receipt_type = match receipt_type.as_str() {
+                "Delivered" => MsgReceiptType::Delivered,
+                "Read" => MsgReceiptType::Read,
+                _ => {
+                    let msg = format!("invalid msg receipt type: {}", receipt_type);
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                }
+            };
+            return Ok(MsgContent::Receipt(MsgReceiptContent {
+                receipt_type,
+                targets: JsonCodecHelper::decode_str_array_field(obj, "targets")?,
+            }));
+        }
+    }
+
+    let msg = format!("invalid msg content: {:?}", desc.get("content"));
+    error!("{}", msg);
+    Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg))
+}
+
+fn build_msg(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let to: ObjectId = JsonCodecHelper::decode_string_field(desc, "to")?;
+    let content = decode_msg_content(desc)?;
+
+    NamedObjectBuilder::new(
+        MsgDescContent::create(to, content),
+        EmptyProtobufBodyContent::default(),
+    )
+    .owner(param.owner()?.to_owned())
+    .option_dec_id(param.dec_id.clone())
+    .build()
+    .to_vec()
+}
+
+fn build_add_friend(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let to: PeopleId = JsonCodecHelper::decode_string_field(desc, "to")?;
+
+    NamedObjectBuilder::new(
+        AddFriendDescContent::new(to),
+        EmptyProtobufBodyContent::default(),
+    )
+    .owner(param.owner()?.to_owned())
+    .option_author(param.author.clone())
+    .option_dec_id(param.dec_id.clone())
+    .build()
+    .to_vec()
+}
+
+fn build_remove_friend(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let to: PeopleId = JsonCodecHelper::decode_string_field(desc, "to")?;
+
+    NamedObjectBuilder::new(
+        RemoveFriendDescContent::new(to),
+        EmptyProtobufBodyContent::default(),
+    )
+    .owner(param.owner()?.to_owned())
+    .option_author(param.author.clone())
+    .option_dec_id(param.dec_id.clone())
+    .build()
+    .to_vec()
+}
+
+// format_json里的Option字段为空时输出null，和字段不存在同样处理
+fn option_field<'a>(obj: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    match obj.get(key) {
+        Some(Value::Null) | None => None,
+        Some(v) => Some(v),
+    }
+}
+
+fn decode_option_string<T>(obj: &Map<String, Value>, key: &str) -> BuckyResult<Option<T>>
+where
+    T: FromStr,
+    <T as FromStr>::Err: std::fmt::Display,
+{
+    match option_field(obj, key) {
+        Some(v) => Ok(Some(JsonCodecHelper::decode_from_string(v)?)),
+        None => Ok(None),
+    }
+}
+
+fn decode_string_map<T>(obj: &Map<String, Value>, key: &str) -> BuckyResult<Vec<(String, T)>>
+where
+    T: FromStr,
+    <T as FromStr>::Err: std::fmt::Display,
+{
+    let map = match option_field(obj, key) {
+        Some(Value::Object(map)) => map,
+        Some(v) => {
+            let msg = format!("invalid json field, except object: {}={}", key, v);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+        }
+        None => return Ok(vec![]),
+    };
+
+    let mut list = Vec::with_capacity(map.len());
+    for (k, v) in map {
+        list.push((k.to_owned(), JsonCodecHelper::decode_from_string(v)?));
+    }
+
+    Ok(list)
+}
+
+fn people_owner(param: &ObjectJsonBuildParam) -> BuckyResult<PeopleId> {
+    PeopleId::try_from(param.owner()?)
+}
+
+fn build_zone(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let owner: ObjectId = JsonCodecHelper::decode_string_field(desc, "owner")?;
+
+    let body = param.body_content()?;
+    let ood_list: Vec<DeviceId> = JsonCodecHelper::decode_str_array_field(body, "ood_list")?;
+    if ood_list.is_empty() {
+        let msg = "build zone object from json but ood_list is empty!".to_owned();
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+    }
+    let known_device_list: Vec<DeviceId> =
+        JsonCodecHelper::decode_str_array_field(body, "known_device_list")?;
+
+    // format_json输出的是枚举名，同时兼容to_string的格式
+    let ood_work_mode: String = JsonCodecHelper::decode_string_field(body, "ood_work_mode")?;
+    let ood_work_mode = match ood_work_mode.as_str() {
+        "Standalone" => OODWorkMode::Standalone,
+        "ActiveStandby" => OODWorkMode::ActiveStandby,
+        v @ _ => OODWorkMode::from_str(v)?,
+    };
+
+    Zone::create(owner, ood_work_mode, ood_list, known_device_list).to_vec()
+}
+
+fn build_friend_option(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let auto_confirm = match option_field(desc, "auto_confirm") {
+        Some(v) => Some(JsonCodecHelper::decode_to_int::<u8>(v)? != 0),
+        None => None,
+    };
+    let msg = decode_option_string(desc, "msg")?;
+
+    FriendOption::create(people_owner(param)?, auto_confirm, msg).to_vec()
+}
+
+fn build_friend_property(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    FriendProperty::create(people_owner(param)?).to_vec()
+}
+
+fn build_trans_context(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let context_path: String = JsonCodecHelper::decode_string_field(desc, "context_path")?;
+
+    TransContext::new(param.dec_id.clone(), &context_path).to_vec()
+}
+
+// body里的source和tags可以一起构造，其余字段需要通过DecAppObj的接口修改
+fn build_dec_app(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let id: String = JsonCodecHelper::decode_string_field(desc, "id")?;
+
+    let mut dec_app = DecApp::create(param.owner()?.to_owned(), &id);
+    if let Ok(body) = param.body_content() {
+        let source_desc: Vec<(String, String)> = decode_string_map(body, "source_desc")?;
+        for (version, source) in decode_string_map::<ObjectId>(body, "source")? {
+            let desc = source_desc
+                .iter()
+                .find(|(v, _)| *v == version)
+                .map(|(_, desc)| desc.to_owned());
+            dec_app.set_source(version, source, desc);
+        }
+
+        for (tag, version) in decode_string_map::<String>(body, "tags")? {
+            dec_app.set_tag(tag, version);
+        }
+    }
+
+    dec_app.to_vec()
+}
+
+fn build_app_status(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let id: DecAppId = JsonCodecHelper::decode_string_field(desc, "id")?;
+
+    let body = param.body_content()?;
+    let version: String = JsonCodecHelper::decode_string_field(body, "version")?;
+    let status: u8 = JsonCodecHelper::decode_int_field(body, "status")?;
+
+    AppStatus::create(param.owner()?.to_owned(), id, version, status != 0).to_vec()
+}
+
+// app列表的内容是完整的AppStatus对象，需要构造后通过AppListObj::put添加
+fn build_app_list(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let id: String = JsonCodecHelper::decode_string_field(desc, "id")?;
+    let category: String = JsonCodecHelper::decode_string_field(desc, "category")?;
+
+    AppList::create(param.owner()?.to_owned(), &id, &category).to_vec()
+}
+
+fn build_default_app_list(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let id: String = JsonCodecHelper::decode_string_field(desc, "id")?;
+
+    let mut list = DefaultAppList::create(param.owner()?.to_owned(), &id);
+    if let Ok(body) = param.body_content() {
+        if let Some(Value::Object(apps)) = option_field(body, "list") {
+            for (group, app) in apps {
+                let app = ObjectJsonBuildParam::content_object("list", app)?;
+                let info = DefaultAppInfo {
+                    name: JsonCodecHelper::decode_string_field(app, "name")?,
+                    desc: JsonCodecHelper::decode_string_field(app, "desc")?,
+                    copyright: JsonCodecHelper::decode_string_field(app, "copyright")?,
+                    dec_id: JsonCodecHelper::decode_string_field(app, "dec_id")?,
+                };
+                list.set(group, info);
+            }
+        }
+    }
+
+    list.to_vec()
+}
+
+fn build_app_cmd(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let owner = param.owner()?.to_owned();
+    let id: DecAppId = JsonCodecHelper::decode_string_field(desc, "app_id")?;
+
+    let invalid_cmd = || {
+        let msg = format!("invalid app cmd code: {:?}", desc.get("cmd_code"));
+        error!("{}", msg);
+        BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+    };
+
+    // 和serde的枚举格式一致，无参数的命令为字符串，有参数的为{命令: 参数}
+    let cmd = match desc.get("cmd_code") {
+        Some(Value::String(code)) => match code.as_str() {
+            "Remove" => AppCmd::remove(owner, id),
+            "Uninstall" => AppCmd::uninstall(owner, id),
+            "Start" => AppCmd::start(owner, id),
+            "Stop" => AppCmd::stop(owner, id),
+            _ => return Err(invalid_cmd()),
+        },
+        Some(Value::Object(code)) if code.len() == 1 => {
+            let (name, value) = code.iter().next().unwrap();
+            match name.as_str() {
+                "Add" => {
+                    let value = ObjectJsonBuildParam::content_object(name, value)?;
+                    AppCmd::add(owner, id, decode_option_string(value, "app_owner_id")?)
+                }
+                "Install" => {
+                    let value = ObjectJsonBuildParam::content_object(name, value)?;
+                    let ver: String = JsonCodecHelper::decode_string_field(value, "ver")?;
+                    let run = JsonCodecHelper::decode_bool_field(value, "run_after_install")?;
+                    AppCmd::install(owner, id, &ver, run)
+                }
+                "SetPermission" => {
+                    let value = ObjectJsonBuildParam::content_object(name, value)?;
+                    let mut permission = std::collections::HashMap::new();
+                    if let Some(Value::Object(map)) = value.get("permission") {
+                        for (k, v) in map {
+                            permission.insert(k.to_owned(), JsonCodecHelper::decode_from_boolean(v)?);
+                        }
+                    }
+                    AppCmd::set_permission(owner, id, ModifyAppPermission { permission })
+                }
+                "SetQuota" => {
+                    let value = ObjectJsonBuildParam::content_object(name, value)?;
+                    let quota = AppQuota {
+                        mem: JsonCodecHelper::decode_int_field(value, "mem")?,
+                        disk_space: JsonCodecHelper::decode_int_field(value, "disk_space")?,
+                        cpu: JsonCodecHelper::decode_int_field(value, "cpu")?,
+                    };
+                    AppCmd::set_quota(owner, id, quota)
+                }
+                "SetAutoUpdate" => {
+                    let auto_update = JsonCodecHelper::decode_from_boolean(value)?;
+                    AppCmd::set_auto_update(owner, id, auto_update)
+                }
+                _ => return Err(invalid_cmd()),
+            }
+        }
+        _ => return Err(invalid_cmd()),
+    };
+
+    cmd.to_vec()
+}
+
+fn build_app_local_status(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let id: DecAppId = JsonCodecHelper::decode_string_field(desc, "id")?;
+
+    AppLocalStatus::create(param.owner()?.to_owned(), id).to_vec()
+}
+
+fn build_app_setting(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let id: DecAppId = JsonCodecHelper::decode_string_field(desc, "id")?;
+
+    let mut setting = AppSetting::create(param.owner()?.to_owned(), id);
+    if option_field(desc, "auto_update").is_some() {
+        setting.set_auto_update(JsonCodecHelper::decode_bool_field(desc, "auto_update")?);
+    }
+
+    setting.to_vec()
+}
+
+fn build_app_local_list(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let id: String = JsonCodecHelper::decode_string_field(desc, "id")?;
+
+    let mut list = AppLocalList::create(param.owner()?.to_owned(), &id);
+    if option_field(desc, "list").is_some() {
+        let apps: Vec<DecAppId> = JsonCodecHelper::decode_str_array_field(desc, "list")?;
+        for app in apps {
+            list.insert(app);
+        }
+    }
+
+    list.to_vec()
+}
+
+fn build_group(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let name: String = JsonCodecHelper::decode_string_field(desc, "name")?;
+
+    let (admins, members) = match param.body_content() {
+        Ok(body) => (
+            JsonCodecHelper::decode_str_array_field(body, "admins")?,
+            JsonCodecHelper::decode_str_array_field(body, "members")?,
+        ),
+        Err(_) => (vec![], vec![]),
+    };
+
+    Group::create(people_owner(param)?, &name, admins, members).to_vec()
+}
+
+// 构造出的成员变化需要author签名后才能被应用
+fn build_group_member_change(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let group: ObjectId = JsonCodecHelper::decode_string_field(desc, "group")?;
+    let version: u64 = JsonCodecHelper::decode_int_field(desc, "version")?;
+    let members: Vec<ObjectId> = JsonCodecHelper::decode_str_array_field(desc, "members")?;
+
+    let action: String = JsonCodecHelper::decode_string_field(desc, "action")?;
+    let action = match action.as_str() {
+        "AddMember" => GroupMemberAction::AddMember,
+        "RemoveMember" => GroupMemberAction::RemoveMember,
+        "AddAdmin" => GroupMemberAction::AddAdmin,
+        "RemoveAdmin" => GroupMemberAction::RemoveAdmin,
+        _ => {
+            let msg = format!("invalid group member action: {}", action);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+        }
+    };
+
+    let author = param.author.clone().ok_or_else(|| {
+        let msg = "build group member change from json but author missing!".to_owned();
+        error!("{}", msg);
+        BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+    })?;
+
+    NamedObjectBuilder::new(
+        GroupMemberChangeDescContent::new(group, version, action, members),
+        EmptyProtobufBodyContent::default(),
+    )
+    .option_owner(param.owner.clone())
+    .author(author)
+    .option_dec_id(param.dec_id.clone())
+    .build()
+    .to_vec()
+}
+
+fn build_group_msg(param: &ObjectJsonBuildParam) -> BuckyResult<Vec<u8>> {
+    let desc = param.desc_content()?;
+    let group: ObjectId = JsonCodecHelper::decode_string_field(desc, "group")?;
+    let content = decode_msg_content(desc)?;
+
+    GroupMsg::create(people_owner(param)?, group, content).to_vec()
+}
+
+// 注册可以由json直接构造的core对象
+// NFTList、AppManagerAction和AppCmdList的内容是嵌套的对象，需要使用各自的构造接口；
+// AppStoreList、AppExtInfo、SetDefaultApp和MetaProto在rust里没有定义
+pub fn register_core_objects_builder() {
+    BUILDER_FACTORY.register(CoreObjectType::Zone, build_zone);
+    BUILDER_FACTORY.register(CoreObjectType::Text, build_text);
+    BUILDER_FACTORY.register(CoreObjectType::Storage, build_storage);
+
+    BUILDER_FACTORY.register(CoreObjectType::FriendOption, build_friend_option);
+    BUILDER_FACTORY.register(CoreObjectType::FriendProperty, build_friend_property);
+
+    BUILDER_FACTORY.register(CoreObjectType::TransContext, build_trans_context);
+    BUILDER_FACTORY.register(CoreObjectType::DecApp, build_dec_app);
+    BUILDER_FACTORY.register(CoreObjectType::AppStatus, build_app_status);
+    BUILDER_FACTORY.register(CoreObjectType::AppList, build_app_list);
+    BUILDER_FACTORY.register(CoreObjectType::DefaultAppList, build_default_app_list);
+    BUILDER_FACTORY.register(CoreObjectType::AppCmd, build_app_cmd);
+    BUILDER_FACTORY.register(CoreObjectType::AppLocalStatus, build_app_local_status);
+    BUILDER_FACTORY.register(CoreObjectType::AppSetting, build_app_setting);
+    BUILDER_FACTORY.register(CoreObjectType::AppLocalList, build_app_local_list);
+
+    BUILDER_FACTORY.register(CoreObjectType::Msg, build_msg);
+    BUILDER_FACTORY.register(CoreObjectType::AddFriend, build_add_friend);
+    BUILDER_FACTORY.register(CoreObjectType::RemoveFriend, build_remove_friend);
+    BUILDER_FACTORY.register(CoreObjectType::Group, build_group);
+    BUILDER_FACTORY.register(CoreObjectType::GroupMemberChange, build_group_member_change);
+    BUILDER_FACTORY.register(CoreObjectType::GroupMsg, build_group_msg);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_build_text_from_json() {
+        register_core_objects_builder();
+
+        let value = serde_json::json!({
+            "object_type": CoreObjectType::Text as u16,
+            "desc": {
+                "id": "test",
+                "header": "header",
+            },
+            "body": {
+                "value": "value",
+            },
+        });
+
+        let param = ObjectJsonBuildParam::decode_value(&value).unwrap();
+        let buf = BUILDER_FACTORY.build(&param).unwrap();
+        let text = Text::clone_from_slice(&buf).unwrap();
+        assert_eq!(text.id(), "test");
+        assert_eq!(text.header(), "header");
+        assert_eq!(text.value(), "value");
+        assert_eq!(
+            text.desc().calculate_id(),
+            Text::create("test", "header", "value").desc().calculate_id()
+        );
+
+        let value = serde_json::json!({
+            "object_type": CoreObjectType::Msg as u16,
+            "desc": {
+                "to": text.desc().calculate_id().to_string(),
+                "content": { "Text": "hello" },
+            },
+        });
+        let param = ObjectJsonBuildParam::decode_value(&value).unwrap();
+        assert!(BUILDER_FACTORY.build(&param).is_err());
+    }
+
+    fn new_people() -> ObjectId {
+        let secret = PrivateKey::generate_rsa(1024).unwrap();
+        let people = People::new(None, vec![], secret.public(), None, None, None).build();
+        people.desc().calculate_id()
+    }
+
+    // 用format_json的输出重新构造对象，两者格式化后的desc和body内容应该一致
+    fn rebuild(obj_type: CoreObjectType, buf: &[u8]) -> serde_json::Value {
+        let value = FORMAT_FACTORY.format(obj_type as u16, buf).unwrap();
+        let desc = &value["desc"];
+        let id_field = |key: &str| {
+            desc.get(key)
+                .and_then(|v| v.as_str())
+                .map(|v| ObjectId::from_str(v).unwrap())
+        };
+
+        let param = ObjectJsonBuildParam {
+            object_type: obj_type as u16,
+            owner: id_field("owner"),
+            author: id_field("author"),
+            dec_id: id_field("dec_id"),
+            desc: desc["content"].clone(),
+            body: value["body"]["content"].clone(),
+        };
+        let new_buf = BUILDER_FACTORY.build(&param).unwrap();
+        let new_value = FORMAT_FACTORY.format(obj_type as u16, &new_buf).unwrap();
+
+        assert_eq!(new_value["desc"]["content"], desc["content"]);
+        assert_eq!(new_value["desc"]["owner"], desc["owner"]);
+        assert_eq!(new_value["desc"]["author"], desc["author"]);
+        assert_eq!(new_value["body"]["content"], value["body"]["content"]);
+
+        new_value
+    }
+
+    #[test]
+    fn test_rebuild_from_format() {
+        crate::register_core_objects_format();
+        register_core_objects_builder();
+
+        let people_id = new_people();
+        let people = PeopleId::try_from(&people_id).unwrap();
+        let device = DeviceId::from_str("5aSixgLkHa2NR4vSKJLYLPo5Av6CY3RJeFJegtF5iR1g").unwrap();
+
+        let zone = Zone::create(people_id.clone(), OODWorkMode::ActiveStandby, vec![device.clone()], vec![device.clone()]);
+        rebuild(CoreObjectType::Zone, &zone.to_vec().unwrap());
+
+        let option = FriendOption::create(people.clone(), Some(true), Some("hello".to_owned()));
+        rebuild(CoreObjectType::FriendOption, &option.to_vec().unwrap());
+
+        let mut dec_app = DecApp::create(people_id.clone(), "test-app");
+        dec_app.set_source("1.0.0".to_owned(), device.object_id().to_owned(), Some("first".to_owned()));
+        dec_app.set_source("1.0.1".to_owned(), people_id.clone(), None);
+        dec_app.set_tag("latest".to_owned(), "1.0.1".to_owned());
+        rebuild(CoreObjectType::DecApp, &dec_app.to_vec().unwrap());
+        let dec_id = DecAppId::try_from(dec_app.desc().calculate_id()).unwrap();
+
+        let status = AppStatus::create(people_id.clone(), dec_id.clone(), "1.0.1".to_owned(), true);
+        rebuild(CoreObjectType::AppStatus, &status.to_vec().unwrap());
+
+        let mut list = DefaultAppList::create(people_id.clone(), "default");
+        list.set(
+            "im",
+            DefaultAppInfo {
+                name: "im".to_owned(),
+                desc: "default im".to_owned(),
+                copyright: "cyfs".to_owned(),
+                dec_id: dec_id.clone(),
+            },
+        );
+        rebuild(CoreObjectType::DefaultAppList, &list.to_vec().unwrap());
+
+        let cmds = vec![
+            AppCmd::start(people_id.clone(), dec_id.clone()),
+            AppCmd::add(people_id.clone(), dec_id.clone(), Some(people_id.clone())),
+            AppCmd::install(people_id.clone(), dec_id.clone(), "1.0.1", true),
+            AppCmd::set_auto_update(people_id.clone(), dec_id.clone(), false),
+        ];
+        for cmd in cmds {
+            rebuild(CoreObjectType::AppCmd, &cmd.to_vec().unwrap());
+        }
+
+        let group = Group::create(people.clone(), "group", vec![people_id.clone()], vec![people_id.clone()]);
+        rebuild(CoreObjectType::Group, &group.to_vec().unwrap());
+
+        let change = GroupMemberChange::create(&group, people_id.clone(), GroupMemberAction::AddMember, vec![device.object_id().to_owned()]);
+        let value = rebuild(CoreObjectType::GroupMemberChange, &change.to_vec().unwrap());
+        assert_eq!(value["desc"]["author"], people_id.to_string());
+
+        let msg = GroupMsg::create(people, group.desc().calculate_id(), MsgContent::Text("hello".to_owned()));
+        rebuild(CoreObjectType::GroupMsg, &msg.to_vec().unwrap());
+    }
+
+    #[test]
+    fn test_build_group_member_change_without_author() {
+        register_core_objects_builder();
+
+        let value = serde_json::json!({
+            "object_type": CoreObjectType::GroupMemberChange as u16,
+            "desc": {
+                "group": new_people().to_string(),
+                "version": 1,
+                "action": "AddMember",
+                "members": [],
+            },
+        });
+        let param = ObjectJsonBuildParam::decode_value(&value).unwrap();
+        assert!(BUILDER_FACTORY.build(&param).is_err());
+    }
+}
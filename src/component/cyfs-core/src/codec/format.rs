@@ -1,6 +1,6 @@
-use crate::im::{AddFriendDescContent, FriendOption, FriendOptionDescContent, FriendProperty, FriendPropertyDescContent, MsgDescContent, RemoveFriendDescContent};
+use crate::im::{AddFriend, AddFriendDescContent, FriendOption, FriendOptionDescContent, FriendProperty, FriendPropertyDescContent, Group, GroupBodyContent, GroupDescContent, GroupMemberChange, GroupMemberChangeDescContent, GroupMsg, GroupMsgDescContent, Msg, MsgDescContent, RemoveFriend, RemoveFriendDescContent};
 use crate::*;
-use cyfs_base::{ObjectFormat, ObjectFormatAutoWithSerde, FORMAT_FACTORY, format_json};
+use cyfs_base::{ObjectFormat, ObjectFormatAutoWithSerde, TypelessCoreObject, FORMAT_FACTORY, format_json};
 use serde_json::Value;
 
 impl ObjectFormatAutoWithSerde for TextDescContent {}
@@ -52,6 +52,11 @@ impl ObjectFormatAutoWithSerde for MsgDescContent {}
 
 impl ObjectFormatAutoWithSerde for RemoveFriendDescContent {}
 
+impl ObjectFormatAutoWithSerde for GroupDescContent {}
+impl ObjectFormatAutoWithSerde for GroupBodyContent {}
+impl ObjectFormatAutoWithSerde for GroupMemberChangeDescContent {}
+impl ObjectFormatAutoWithSerde for GroupMsgDescContent {}
+
 impl ObjectFormat for NFTListDescContent {
     fn format_json(&self) -> Value {
         let mut array = vec![];
@@ -88,11 +93,9 @@ pub fn register_core_objects_format() {
     FORMAT_FACTORY.register(CoreObjectType::DecApp, format_json::<DecApp>);
     FORMAT_FACTORY.register(CoreObjectType::AppStatus, format_json::<AppStatus>);
     FORMAT_FACTORY.register(CoreObjectType::AppList, format_json::<AppList>);
-    // FORMAT_FACTORY.register(CoreObjectType::AppStoreList, format_json::<AppStoreList>);
-    // FORMAT_FACTORY.register(CoreObjectType::AppExtInfo, format_json::<AppExtInfo>);
 
     FORMAT_FACTORY.register(CoreObjectType::DefaultAppList, format_json::<DefaultAppList>);
-    
+
     FORMAT_FACTORY.register(CoreObjectType::AppCmd, format_json::<AppCmd>);
     FORMAT_FACTORY.register(CoreObjectType::AppLocalStatus, format_json::<AppLocalStatus>);
     FORMAT_FACTORY.register(CoreObjectType::AppCmdList, format_json::<AppCmdList>);
@@ -101,4 +104,81 @@ pub fn register_core_objects_format() {
     FORMAT_FACTORY.register(CoreObjectType::AppLocalList, format_json::<AppLocalList>);
 
     FORMAT_FACTORY.register(CoreObjectType::NFTList, format_json::<NFTList>);
+
+    FORMAT_FACTORY.register(CoreObjectType::AddFriend, format_json::<AddFriend>);
+    FORMAT_FACTORY.register(CoreObjectType::Msg, format_json::<Msg>);
+    FORMAT_FACTORY.register(CoreObjectType::RemoveFriend, format_json::<RemoveFriend>);
+    FORMAT_FACTORY.register(CoreObjectType::Group, format_json::<Group>);
+    FORMAT_FACTORY.register(CoreObjectType::GroupMemberChange, format_json::<GroupMemberChange>);
+    FORMAT_FACTORY.register(CoreObjectType::GroupMsg, format_json::<GroupMsg>);
+
+    // 这些类型在rust里没有定义，按typeless格式输出desc的公共字段和hex编码的content
+    for obj_type in [
+        CoreObjectType::MetaProto,
+        CoreObjectType::AppStoreList,
+        CoreObjectType::AppExtInfo,
+        CoreObjectType::SetDefaultApp,
+        CoreObjectType::ErrObjType,
+    ] {
+        FORMAT_FACTORY.register(obj_type, format_json::<TypelessCoreObject>);
+    }
+
+    // Admin、PerfOperation和MetaMinerGroup定义在依赖cyfs-core的工程里，由各自的工程注册
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use cyfs_base::*;
+
+    #[test]
+    fn test_format_support() {
+        register_core_objects_format();
+
+        for obj_type in [
+            CoreObjectType::Zone,
+            CoreObjectType::Storage,
+            CoreObjectType::Text,
+            CoreObjectType::FriendOption,
+            CoreObjectType::FriendProperty,
+            CoreObjectType::MetaProto,
+            CoreObjectType::TransContext,
+            CoreObjectType::DecApp,
+            CoreObjectType::AppStatus,
+            CoreObjectType::AppList,
+            CoreObjectType::AppStoreList,
+            CoreObjectType::AppExtInfo,
+            CoreObjectType::DefaultAppList,
+            CoreObjectType::SetDefaultApp,
+            CoreObjectType::AppCmd,
+            CoreObjectType::AppLocalStatus,
+            CoreObjectType::AppCmdList,
+            CoreObjectType::AppSetting,
+            CoreObjectType::AppManagerAction,
+            CoreObjectType::AppLocalList,
+            CoreObjectType::NFTList,
+            CoreObjectType::AddFriend,
+            CoreObjectType::Msg,
+            CoreObjectType::RemoveFriend,
+            CoreObjectType::Group,
+            CoreObjectType::GroupMemberChange,
+            CoreObjectType::GroupMsg,
+            CoreObjectType::ErrObjType,
+        ] {
+            assert!(FORMAT_FACTORY.is_support(obj_type.as_u16()), "{:?}", obj_type);
+        }
+    }
+
+    #[test]
+    fn test_format_typeless() {
+        register_core_objects_format();
+
+        // 没有rust定义的类型按typeless输出，content为hex编码
+        let text = Text::create("test", "header", "value");
+        let value = FORMAT_FACTORY
+            .format(CoreObjectType::SetDefaultApp as u16, &text.to_vec().unwrap())
+            .unwrap();
+        assert_eq!(value["desc"]["object_id"], text.desc().calculate_id().to_string());
+        assert!(value["desc"]["content"].as_str().is_some());
+    }
 }
@@ -2,5 +2,7 @@ pub mod protos {
     include!(concat!(env!("OUT_DIR"), "/mod.rs"));
 }
 mod format;
+mod builder;
 
-pub use format::*;
\ No newline at end of file
+pub use format::*;
+pub use builder::*;
\ No newline at end of file
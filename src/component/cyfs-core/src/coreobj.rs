@@ -64,6 +64,9 @@ pub enum CoreObjectType {
     AddFriend = 1001,
     Msg = 1003,
     RemoveFriend = 1004,
+    Group = 1005,
+    GroupMemberChange = 1006,
+    GroupMsg = 1007,
 
     // 错误
     // cyfs_base::OBJECT_TYPE_CORE_END
@@ -0,0 +1,747 @@
+use super::msg::MsgContent;
+use crate::codec::*;
+use crate::CoreObjectType;
+use cyfs_base::*;
+use serde::Serialize;
+
+use std::convert::TryFrom;
+
+// Group，desc只包含名字，成员列表放在body里，成员变化不影响group_id
+#[derive(Clone, ProtobufEncode, ProtobufDecode, ProtobufTransform, Serialize)]
+#[cyfs_protobuf_type(crate::codec::protos::GroupDescContent)]
+pub struct GroupDescContent {
+    name: String,
+}
+
+impl DescContent for GroupDescContent {
+    fn obj_type() -> u16 {
+        CoreObjectType::Group as u16
+    }
+
+    fn format(&self) -> u8 {
+        OBJECT_CONTENT_CODEC_FORMAT_PROTOBUF
+    }
+
+    type OwnerType = Option<ObjectId>;
+    type AreaType = SubDescNone;
+    type AuthorType = SubDescNone;
+    type PublicKeyType = SubDescNone;
+}
+
+#[derive(Clone, ProtobufEncode, ProtobufDecode, ProtobufTransformType, Serialize)]
+#[cyfs_protobuf_type(crate::codec::protos::GroupBodyContent)]
+pub struct GroupBodyContent {
+    admins: Vec<ObjectId>,
+    members: Vec<ObjectId>,
+
+    // 每应用一次GroupMemberChange加一
+    version: u64,
+
+    // 成员列表来源的SimpleGroup
+    simple_group: Option<ObjectId>,
+}
+
+impl BodyContent for GroupBodyContent {
+    fn format(&self) -> u8 {
+        OBJECT_CONTENT_CODEC_FORMAT_PROTOBUF
+    }
+}
+
+impl ProtobufTransform<protos::GroupBodyContent> for GroupBodyContent {
+    fn transform(value: protos::GroupBodyContent) -> BuckyResult<Self> {
+        let simple_group = match value.simple_group {
+            Some(v) => Some(ProtobufCodecHelper::decode_buf(v)?),
+            None => None,
+        };
+
+        Ok(Self {
+            admins: ProtobufCodecHelper::decode_buf_list(value.admins)?,
+            members: ProtobufCodecHelper::decode_buf_list(value.members)?,
+            version: value.version,
+            simple_group,
+        })
+    }
+}
+
+impl ProtobufTransform<&GroupBodyContent> for protos::GroupBodyContent {
+    fn transform(value: &GroupBodyContent) -> BuckyResult<Self> {
+        let simple_group = match &value.simple_group {
+            Some(v) => Some(v.to_vec()?),
+            None => None,
+        };
+
+        Ok(Self {
+            admins: ProtobufCodecHelper::encode_buf_list(&value.admins)?.into_vec(),
+            members: ProtobufCodecHelper::encode_buf_list(&value.members)?.into_vec(),
+            version: value.version,
+            simple_group,
+        })
+    }
+}
+
+type GroupType = NamedObjType<GroupDescContent, GroupBodyContent>;
+type GroupBuilder = NamedObjectBuilder<GroupDescContent, GroupBodyContent>;
+
+pub type GroupId = NamedObjectId<GroupType>;
+pub type Group = NamedObjectBase<GroupType>;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum GroupMemberAction {
+    AddMember,
+    RemoveMember,
+    AddAdmin,
+    RemoveAdmin,
+}
+
+// 管理员签名的成员变化事件，需要按version顺序应用到Group上
+#[derive(Clone, ProtobufEncode, ProtobufDecode, ProtobufTransformType, Serialize)]
+#[cyfs_protobuf_type(crate::codec::protos::GroupMemberChangeDescContent)]
+pub struct GroupMemberChangeDescContent {
+    group: ObjectId,
+    version: u64,
+    action: GroupMemberAction,
+    members: Vec<ObjectId>,
+}
+
+impl DescContent for GroupMemberChangeDescContent {
+    fn obj_type() -> u16 {
+        CoreObjectType::GroupMemberChange as u16
+    }
+
+    fn format(&self) -> u8 {
+        OBJECT_CONTENT_CODEC_FORMAT_PROTOBUF
+    }
+
+    type OwnerType = Option<ObjectId>;
+    type AreaType = SubDescNone;
+    type AuthorType = Option<ObjectId>;
+    type PublicKeyType = SubDescNone;
+}
+
+impl ProtobufTransform<protos::GroupMemberChangeDescContent> for GroupMemberChangeDescContent {
+    fn transform(value: protos::GroupMemberChangeDescContent) -> BuckyResult<Self> {
+        let action = match value.action {
+            0 => GroupMemberAction::AddMember,
+            1 => GroupMemberAction::RemoveMember,
+            2 => GroupMemberAction::AddAdmin,
+            3 => GroupMemberAction::RemoveAdmin,
+            _ => {
+                return Err(BuckyError::new(
+                    BuckyErrorCode::InvalidFormat,
+                    format!("unknown group member action {}", value.action),
+                ));
+            }
+        };
+
+        Ok(Self {
+            group: ProtobufCodecHelper::decode_buf(value.group)?,
+            version: value.version,
+            action,
+            members: ProtobufCodecHelper::decode_buf_list(value.members)?,
+        })
+    }
+}
+
+impl ProtobufTransform<&GroupMemberChangeDescContent> for protos::GroupMemberChangeDescContent {
+    fn transform(value: &GroupMemberChangeDescContent) -> BuckyResult<Self> {
+        Ok(Self {
+            group: value.group.to_vec()?,
+            version: value.version,
+            action: match value.action {
+                GroupMemberAction::AddMember => 0,
+                GroupMemberAction::RemoveMember => 1,
+                GroupMemberAction::AddAdmin => 2,
+                GroupMemberAction::RemoveAdmin => 3,
+            },
+            members: ProtobufCodecHelper::encode_buf_list(&value.members)?.into_vec(),
+        })
+    }
+}
+
+impl GroupMemberChangeDescContent {
+    pub(crate) fn new(
+        group: ObjectId,
+        version: u64,
+        action: GroupMemberAction,
+        members: Vec<ObjectId>,
+    ) -> Self {
+        Self {
+            group,
+            version,
+            action,
+            members,
+        }
+    }
+}
+
+type GroupMemberChangeType = NamedObjType<GroupMemberChangeDescContent, EmptyProtobufBodyContent>;
+type GroupMemberChangeBuilder =
+    NamedObjectBuilder<GroupMemberChangeDescContent, EmptyProtobufBodyContent>;
+
+pub type GroupMemberChangeId = NamedObjectId<GroupMemberChangeType>;
+pub type GroupMemberChange = NamedObjectBase<GroupMemberChangeType>;
+
+// 群消息，owner为发送者
+#[derive(Clone, ProtobufEncode, ProtobufDecode, ProtobufTransform, Serialize)]
+#[cyfs_protobuf_type(crate::codec::protos::GroupMsgDescContent)]
+pub struct GroupMsgDescContent {
+    group: ObjectId,
+    content: MsgContent,
+}
+
+impl DescContent for GroupMsgDescContent {
+    fn obj_type() -> u16 {
+        CoreObjectType::GroupMsg as u16
+    }
+
+    fn format(&self) -> u8 {
+        OBJECT_CONTENT_CODEC_FORMAT_PROTOBUF
+    }
+
+    type OwnerType = Option<ObjectId>;
+    type AreaType = SubDescNone;
+    type AuthorType = SubDescNone;
+    type PublicKeyType = SubDescNone;
+}
+
+type GroupMsgType = NamedObjType<GroupMsgDescContent, EmptyProtobufBodyContent>;
+type GroupMsgBuilder = NamedObjectBuilder<GroupMsgDescContent, EmptyProtobufBodyContent>;
+
+pub type GroupMsgId = NamedObjectId<GroupMsgType>;
+pub type GroupMsg = NamedObjectBase<GroupMsgType>;
+
+pub trait GroupObject {
+    fn create(
+        owner: PeopleId,
+        name: &str,
+        admins: Vec<ObjectId>,
+        members: Vec<ObjectId>,
+    ) -> Self;
+    fn create_from_simple_group(owner: PeopleId, name: &str, simple_group: &SimpleGroup) -> Self;
+
+    fn group_id(&self) -> GroupId;
+    fn name(&self) -> &str;
+    fn admins(&self) -> &Vec<ObjectId>;
+    fn members(&self) -> &Vec<ObjectId>;
+    fn version(&self) -> u64;
+    fn simple_group(&self) -> Option<&ObjectId>;
+
+    fn is_admin(&self, id: &ObjectId) -> bool;
+    fn is_member(&self, id: &ObjectId) -> bool;
+
+    // 校验body带有group owner的签名，body里的管理员列表只有在签名有效时才可信
+    fn verify_body(&self, owner: &People) -> BuckyResult<()>;
+
+    // owner和author为group的owner和change的author对应的people对象，由调用者获取；
+    // owner的变化总是可以应用，其它管理员的变化要求body带有owner的有效签名，应用后body签名失效，需要owner重新签名
+    fn apply_change(&mut self, change: &GroupMemberChange, owner: &People, author: &People) -> BuckyResult<()>;
+
+    // 校验群消息属于本群，发送者是群成员，并且带有发送者的desc签名
+    fn check_msg(&self, msg: &GroupMsg, sender: &People) -> BuckyResult<()>;
+}
+
+impl GroupObject for Group {
+    fn create(
+        owner: PeopleId,
+        name: &str,
+        admins: Vec<ObjectId>,
+        members: Vec<ObjectId>,
+    ) -> Self {
+        let owner: ObjectId = owner.into();
+
+        // 创建者总是管理员和成员
+        let mut admins = admins;
+        if !admins.contains(&owner) {
+            admins.insert(0, owner.clone());
+        }
+        let mut members = members;
+        for id in &admins {
+            if !members.contains(id) {
+                members.push(id.clone());
+            }
+        }
+
+        let desc = GroupDescContent {
+            name: name.to_owned(),
+        };
+        let body = GroupBodyContent {
+            admins,
+            members,
+            version: 0,
+            simple_group: None,
+        };
+
+        GroupBuilder::new(desc, body).owner(owner).build()
+    }
+
+    fn create_from_simple_group(owner: PeopleId, name: &str, simple_group: &SimpleGroup) -> Self {
+        let members = simple_group.body().as_ref().unwrap().content().members().clone();
+        let mut group = Self::create(owner, name, vec![], members);
+        group.body_mut().as_mut().unwrap().content_mut().simple_group =
+            Some(simple_group.desc().calculate_id());
+
+        group
+    }
+
+    fn group_id(&self) -> GroupId {
+        GroupId::try_from(self.desc().calculate_id()).unwrap()
+    }
+
+    fn name(&self) -> &str {
+        &self.desc().content().name
+    }
+
+    fn admins(&self) -> &Vec<ObjectId> {
+        &self.body().as_ref().unwrap().content().admins
+    }
+
+    fn members(&self) -> &Vec<ObjectId> {
+        &self.body().as_ref().unwrap().content().members
+    }
+
+    fn version(&self) -> u64 {
+        self.body().as_ref().unwrap().content().version
+    }
+
+    fn simple_group(&self) -> Option<&ObjectId> {
+        self.body().as_ref().unwrap().content().simple_group.as_ref()
+    }
+
+    fn is_admin(&self, id: &ObjectId) -> bool {
+        self.admins().contains(id)
+    }
+
+    fn is_member(&self, id: &ObjectId) -> bool {
+        self.members().contains(id)
+    }
+
+    fn verify_body(&self, owner: &People) -> BuckyResult<()> {
+        let group_id = self.desc().calculate_id();
+        let owner_id = owner.desc().calculate_id();
+        if self.desc().owner().as_ref() != Some(&owner_id) {
+            let msg = format!(
+                "group owner not match! group={}, expected={:?}, got={}",
+                group_id,
+                self.desc().owner(),
+                owner_id
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotMatch, msg));
+        }
+
+        let hash = self.body().as_ref().unwrap().raw_hash_value()?;
+        if !has_valid_sign(&hash, self.signs().body_signs(), owner.desc().public_key()) {
+            let msg = format!("group body not signed by owner! group={}, owner={}", group_id, owner_id);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidSignature, msg));
+        }
+
+        Ok(())
+    }
+
+    fn apply_change(&mut self, change: &GroupMemberChange, owner: &People, author: &People) -> BuckyResult<()> {
+        let group_id = self.desc().calculate_id();
+        let content = change.desc().content();
+        if content.group != group_id {
+            let msg = format!(
+                "group member change not match group! group={}, change={}",
+                group_id, content.group
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotMatch, msg));
+        }
+
+        if content.version != self.version() + 1 {
+            let msg = format!(
+                "group member change version not match! group={}, current={}, change={}",
+                group_id,
+                self.version(),
+                content.version
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        let author_id = change.desc().author().as_ref().ok_or_else(|| {
+            let msg = format!("group member change author missing! group={}", group_id);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+        })?;
+
+        // 公钥来自author的people对象，people_id由desc计算，保证公钥属于author
+        if author.desc().calculate_id() != *author_id {
+            let msg = format!(
+                "group member change author not match! group={}, author={}, got={}",
+                group_id,
+                author_id,
+                author.desc().calculate_id()
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotMatch, msg));
+        }
+
+        // owner由desc决定，和group_id绑定；其它管理员来自body，需要校验owner的body签名
+        let is_owner = self.desc().owner().as_ref() == Some(author_id);
+        if !is_owner {
+            self.verify_body(owner)?;
+            if !self.is_admin(author_id) {
+                let msg = format!(
+                    "group member change author is not admin! group={}, author={}",
+                    group_id, author_id
+                );
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+            }
+        }
+
+        // 必须有author的desc签名
+        let hash = change.desc().raw_hash_value()?;
+        if !has_valid_sign(&hash, change.signs().desc_signs(), author.desc().public_key()) {
+            let msg = format!(
+                "group member change not signed by admin! group={}, author={}",
+                group_id, author_id
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidSignature, msg));
+        }
+
+        let group_owner = self.desc().owner().clone();
+        let body = self.body_mut().as_mut().unwrap();
+        let group = body.content_mut();
+        match content.action {
+            GroupMemberAction::AddMember => {
+                for id in &content.members {
+                    if !group.members.contains(id) {
+                        group.members.push(id.clone());
+                    }
+                }
+            }
+            GroupMemberAction::RemoveMember => {
+                if content.members.iter().any(|id| Some(id) == group_owner.as_ref()) {
+                    let msg = format!("group owner can't be removed! group={}", group_id);
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+                }
+                group.members.retain(|id| !content.members.contains(id));
+                group.admins.retain(|id| !content.members.contains(id));
+            }
+            GroupMemberAction::AddAdmin => {
+                for id in &content.members {
+                    if !group.members.contains(id) {
+                        let msg = format!("add group admin but not member! group={}, id={}", group_id, id);
+                        error!("{}", msg);
+                        return Err(BuckyError::new(BuckyErrorCode::NotFound, msg));
+                    }
+                }
+                for id in &content.members {
+                    if !group.admins.contains(id) {
+                        group.admins.push(id.clone());
+                    }
+                }
+            }
+            GroupMemberAction::RemoveAdmin => {
+                if content.members.iter().any(|id| Some(id) == group_owner.as_ref()) {
+                    let msg = format!("group owner can't be removed from admins! group={}", group_id);
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+                }
+                group.admins.retain(|id| !content.members.contains(id));
+            }
+        }
+
+        group.version = content.version;
+        body.increase_update_time(bucky_time_now());
+
+        // 之前的body签名已经失效
+        self.signs_mut().clear_body_signs();
+
+        info!(
+            "apply group member change: group={}, version={}, action={:?}, members={:?}",
+            group_id, content.version, content.action, content.members
+        );
+
+        Ok(())
+    }
+
+    fn check_msg(&self, msg: &GroupMsg, sender: &People) -> BuckyResult<()> {
+        let group_id = self.desc().calculate_id();
+        if *msg.group() != group_id {
+            let msg = format!("group msg not match group! group={}, msg={}", group_id, msg.group());
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotMatch, msg));
+        }
+
+        let owner = match msg.desc().owner() {
+            Some(owner) if self.is_member(owner) => owner,
+            owner => {
+                let msg = format!("group msg sender is not member! group={}, sender={:?}", group_id, owner);
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+            }
+        };
+
+        let sender_id = sender.desc().calculate_id();
+        if sender_id != *owner {
+            let msg = format!(
+                "group msg sender not match! group={}, owner={}, sender={}",
+                group_id, owner, sender_id
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotMatch, msg));
+        }
+
+        // desc里的owner未经签名不可信，必须有发送者的desc签名
+        let hash = msg.desc().raw_hash_value()?;
+        if !has_valid_sign(&hash, msg.signs().desc_signs(), sender.desc().public_key()) {
+            let msg = format!("group msg not signed by sender! group={}, sender={}", group_id, sender_id);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidSignature, msg));
+        }
+
+        Ok(())
+    }
+}
+
+fn has_valid_sign(hash: &HashValue, signs: Option<&Vec<Signature>>, key: &PublicKey) -> bool {
+    match signs {
+        Some(signs) => signs.iter().any(|sign| key.verify(hash.as_slice(), sign)),
+        None => false,
+    }
+}
+
+pub trait GroupMemberChangeObject {
+    // 生成的对象需要author签名(desc)后才能被应用
+    fn create(group: &Group, author: ObjectId, action: GroupMemberAction, members: Vec<ObjectId>) -> Self;
+
+    fn group(&self) -> &ObjectId;
+    fn version(&self) -> u64;
+    fn action(&self) -> GroupMemberAction;
+    fn members(&self) -> &Vec<ObjectId>;
+}
+
+impl GroupMemberChangeObject for GroupMemberChange {
+    fn create(group: &Group, author: ObjectId, action: GroupMemberAction, members: Vec<ObjectId>) -> Self {
+        let desc = GroupMemberChangeDescContent {
+            group: group.desc().calculate_id(),
+            version: group.version() + 1,
+            action,
+            members,
+        };
+
+        GroupMemberChangeBuilder::new(desc, EmptyProtobufBodyContent::default())
+            .option_owner(group.desc().owner().clone())
+            .author(author)
+            .build()
+    }
+
+    fn group(&self) -> &ObjectId {
+        &self.desc().content().group
+    }
+
+    fn version(&self) -> u64 {
+        self.desc().content().version
+    }
+
+    fn action(&self) -> GroupMemberAction {
+        self.desc().content().action
+    }
+
+    fn members(&self) -> &Vec<ObjectId> {
+        &self.desc().content().members
+    }
+}
+
+pub trait GroupMsgObject {
+    fn create(owner: PeopleId, group: ObjectId, content: MsgContent) -> Self;
+
+    fn group(&self) -> &ObjectId;
+    fn content(&self) -> &MsgContent;
+    fn group_msg_id(&self) -> GroupMsgId;
+}
+
+impl GroupMsgObject for GroupMsg {
+    fn create(owner: PeopleId, group: ObjectId, content: MsgContent) -> Self {
+        let desc = GroupMsgDescContent { group, content };
+
+        GroupMsgBuilder::new(desc, EmptyProtobufBodyContent::default())
+            .owner(owner.into())
+            .build()
+    }
+
+    fn group(&self) -> &ObjectId {
+        &self.desc().content().group
+    }
+
+    fn content(&self) -> &MsgContent {
+        &self.desc().content().content
+    }
+
+    fn group_msg_id(&self) -> GroupMsgId {
+        GroupMsgId::try_from(self.desc().calculate_id()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn new_people() -> (PrivateKey, People) {
+        let secret = PrivateKey::generate_rsa(1024).unwrap();
+        let people = People::new(None, vec![], secret.public(), None, None, None).build();
+        (secret, people)
+    }
+
+    async fn sign_change(secret: &PrivateKey, change: &mut GroupMemberChange) {
+        let signer = RsaCPUObjectSigner::new(secret.public(), secret.clone());
+        sign_and_push_named_object_desc(&signer, change, &SignatureSource::RefIndex(0))
+            .await
+            .unwrap();
+    }
+
+    async fn sign_body(secret: &PrivateKey, group: &mut Group) {
+        let signer = RsaCPUObjectSigner::new(secret.public(), secret.clone());
+        sign_and_push_named_object_body(&signer, group, &SignatureSource::RefIndex(0))
+            .await
+            .unwrap();
+    }
+
+    #[async_std::test]
+    async fn test_group_member_change() {
+        let (secret1, people1) = new_people();
+        let people1_id = people1.desc().people_id();
+        let (secret2, people2) = new_people();
+        let people2_id = people2.desc().calculate_id();
+        let (secret3, people3) = new_people();
+        let people3_id = people3.desc().calculate_id();
+
+        let mut group = Group::create(people1_id.clone(), "test", vec![], vec![]);
+        let group_id = group.desc().calculate_id();
+        assert!(group.is_admin(people1_id.object_id()));
+        assert!(!group.is_member(&people2_id));
+
+        // 未签名的变化无法应用
+        let mut change = GroupMemberChange::create(
+            &group,
+            people1_id.object_id().to_owned(),
+            GroupMemberAction::AddMember,
+            vec![people2_id.clone(), people3_id.clone()],
+        );
+        assert!(group.apply_change(&change, &people1, &people1).is_err());
+
+        // 签名者和author不一致
+        sign_change(&secret2, &mut change).await;
+        assert!(group.apply_change(&change, &people1, &people2).is_err());
+        assert!(group.apply_change(&change, &people1, &people1).is_err());
+
+        sign_change(&secret1, &mut change).await;
+        let change = GroupMemberChange::clone_from_slice(&change.to_vec().unwrap()).unwrap();
+        group.apply_change(&change, &people1, &people1).unwrap();
+        assert!(group.is_member(&people2_id));
+        assert_eq!(group.version(), 1);
+        assert_eq!(group.desc().calculate_id(), group_id);
+
+        // 重放
+        assert!(group.apply_change(&change, &people1, &people1).is_err());
+
+        // 非管理员的变化
+        let mut change = GroupMemberChange::create(
+            &group,
+            people2_id.clone(),
+            GroupMemberAction::RemoveMember,
+            vec![people3_id.clone()],
+        );
+        sign_change(&secret2, &mut change).await;
+        sign_body(&secret1, &mut group).await;
+        assert_eq!(
+            group.apply_change(&change, &people1, &people2).unwrap_err().code(),
+            BuckyErrorCode::PermissionDenied
+        );
+
+        // 直接修改body里的管理员列表，owner的body签名失效
+        let mut forged = Group::clone_from_slice(&group.to_vec().unwrap()).unwrap();
+        forged.body_mut().as_mut().unwrap().content_mut().admins.push(people2_id.clone());
+        assert!(forged.is_admin(&people2_id));
+        assert_eq!(
+            forged.apply_change(&change, &people1, &people2).unwrap_err().code(),
+            BuckyErrorCode::InvalidSignature
+        );
+
+        // owner添加管理员
+        let mut change = GroupMemberChange::create(
+            &group,
+            people1_id.object_id().to_owned(),
+            GroupMemberAction::AddAdmin,
+            vec![people2_id.clone()],
+        );
+        sign_change(&secret1, &mut change).await;
+        group.apply_change(&change, &people1, &people1).unwrap();
+        assert!(group.is_admin(&people2_id));
+        assert!(group.verify_body(&people1).is_err());
+
+        // 其它管理员的变化需要owner重新签名body
+        let mut change = GroupMemberChange::create(
+            &group,
+            people2_id.clone(),
+            GroupMemberAction::RemoveMember,
+            vec![people3_id.clone()],
+        );
+        sign_change(&secret2, &mut change).await;
+        assert!(group.apply_change(&change, &people1, &people2).is_err());
+
+        sign_body(&secret1, &mut group).await;
+        let mut group = Group::clone_from_slice(&group.to_vec().unwrap()).unwrap();
+        group.verify_body(&people1).unwrap();
+        assert!(group.verify_body(&people2).is_err());
+        group.apply_change(&change, &people1, &people2).unwrap();
+        assert!(!group.is_member(&people3_id));
+        assert_eq!(group.version(), 3);
+
+        // 群消息需要发送者的签名
+        let mut msg = GroupMsg::create(
+            PeopleId::try_from(people2_id.clone()).unwrap(),
+            group_id.clone(),
+            MsgContent::Text("hello".to_owned()),
+        );
+        assert_eq!(
+            group.check_msg(&msg, &people2).unwrap_err().code(),
+            BuckyErrorCode::InvalidSignature
+        );
+
+        let signer = RsaCPUObjectSigner::new(secret2.public(), secret2.clone());
+        sign_and_push_named_object_desc(&signer, &mut msg, &SignatureSource::RefIndex(0))
+            .await
+            .unwrap();
+        let msg = GroupMsg::clone_from_slice(&msg.to_vec().unwrap()).unwrap();
+        group.check_msg(&msg, &people2).unwrap();
+        assert!(group.check_msg(&msg, &people1).is_err());
+
+        // 冒充其它成员发送，签名不匹配
+        let mut msg = GroupMsg::create(
+            people1_id.clone(),
+            group_id.clone(),
+            MsgContent::Text("fake".to_owned()),
+        );
+        sign_and_push_named_object_desc(&signer, &mut msg, &SignatureSource::RefIndex(0))
+            .await
+            .unwrap();
+        assert!(group.check_msg(&msg, &people1).is_err());
+
+        // 被移出的成员
+        let mut msg = GroupMsg::create(
+            people3.desc().people_id(),
+            group_id.clone(),
+            MsgContent::Text("removed".to_owned()),
+        );
+        let signer = RsaCPUObjectSigner::new(secret3.public(), secret3.clone());
+        sign_and_push_named_object_desc(&signer, &mut msg, &SignatureSource::RefIndex(0))
+            .await
+            .unwrap();
+        assert_eq!(
+            group.check_msg(&msg, &people3).unwrap_err().code(),
+            BuckyErrorCode::PermissionDenied
+        );
+
+        let group = Group::clone_from_slice(&group.to_vec().unwrap()).unwrap();
+        assert_eq!(group.members().len(), 2);
+    }
+}
@@ -1,11 +1,15 @@
 mod add_friend;
 mod friend_option;
+mod group;
 mod msg;
+mod msg_crypto;
 mod remove_friend;
 mod friend_property;
 
 pub use add_friend::*;
 pub use friend_option::*;
+pub use group::*;
 pub use msg::*;
+pub use msg_crypto::*;
 pub use remove_friend::*;
 pub use friend_property::*;
\ No newline at end of file
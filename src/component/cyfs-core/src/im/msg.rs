@@ -12,11 +12,228 @@ pub struct MsgObjectContent {
     pub name: String,
 }
 
+// 回复某条消息
+#[derive(Clone, ProtobufTransform, Serialize)]
+#[cyfs_protobuf_type(crate::codec::protos::MsgReplyContent)]
+pub struct MsgReplyContent {
+    pub reply_to: ObjectId,
+    pub text: String,
+}
+
+// 编辑已发送的消息，target为原消息的MsgId
+#[derive(Clone, ProtobufTransform, Serialize)]
+#[cyfs_protobuf_type(crate::codec::protos::MsgEditContent)]
+pub struct MsgEditContent {
+    pub target: ObjectId,
+    pub text: String,
+}
+
+#[derive(Clone, ProtobufTransform, Serialize)]
+#[cyfs_protobuf_type(crate::codec::protos::MsgDeleteContent)]
+pub struct MsgDeleteContent {
+    pub target: ObjectId,
+}
+
+// 对消息添加或者撤销表情回应
+#[derive(Clone, Serialize)]
+pub struct MsgReactionContent {
+    pub target: ObjectId,
+    pub reaction: String,
+    pub remove: bool,
+}
+
+impl ProtobufTransform<protos::MsgReactionContent> for MsgReactionContent {
+    fn transform(value: protos::MsgReactionContent) -> BuckyResult<Self> {
+        Ok(Self {
+            target: ProtobufCodecHelper::decode_buf(value.target)?,
+            reaction: value.reaction,
+            remove: value.remove,
+        })
+    }
+}
+
+impl ProtobufTransform<&MsgReactionContent> for protos::MsgReactionContent {
+    fn transform(value: &MsgReactionContent) -> BuckyResult<Self> {
+        Ok(Self {
+            target: value.target.to_vec()?,
+            reaction: value.reaction.clone(),
+            remove: value.remove,
+        })
+    }
+}
+
+// 文件附件，id为File对象，thumb为缩略图所在的chunk
+#[derive(Clone, Serialize)]
+pub struct MsgFileContent {
+    pub id: ObjectId,
+    pub name: String,
+    pub mime: String,
+    pub size: u64,
+    pub thumb: Option<ChunkId>,
+}
+
+impl ProtobufTransform<protos::MsgFileContent> for MsgFileContent {
+    fn transform(value: protos::MsgFileContent) -> BuckyResult<Self> {
+        let thumb = match value.thumb {
+            Some(v) => Some(ProtobufCodecHelper::decode_buf(v)?),
+            None => None,
+        };
+
+        Ok(Self {
+            id: ProtobufCodecHelper::decode_buf(value.id)?,
+            name: value.name,
+            mime: value.mime,
+            size: value.size,
+            thumb,
+        })
+    }
+}
+
+impl ProtobufTransform<&MsgFileContent> for protos::MsgFileContent {
+    fn transform(value: &MsgFileContent) -> BuckyResult<Self> {
+        let thumb = match &value.thumb {
+            Some(v) => Some(v.to_vec()?),
+            None => None,
+        };
+
+        Ok(Self {
+            id: value.id.to_vec()?,
+            name: value.name.clone(),
+            mime: value.mime.clone(),
+            size: value.size,
+            thumb,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum MsgReceiptType {
+    Delivered,
+    Read,
+}
+
+// 送达或者已读回执，可以一次确认多条消息
+#[derive(Clone, Serialize)]
+pub struct MsgReceiptContent {
+    pub receipt_type: MsgReceiptType,
+    pub targets: Vec<ObjectId>,
+}
+
+impl ProtobufTransform<protos::MsgReceiptContent> for MsgReceiptContent {
+    fn transform(value: protos::MsgReceiptContent) -> BuckyResult<Self> {
+        let receipt_type = match value.r#type {
+            0 => MsgReceiptType::Delivered,
+            1 => MsgReceiptType::Read,
+            _ => {
+                return Err(BuckyError::new(
+                    BuckyErrorCode::InvalidFormat,
+                    format!("unknown msg receipt type {}", value.r#type),
+                ));
+            }
+        };
+
+        Ok(Self {
+            receipt_type,
+            targets: ProtobufCodecHelper::decode_buf_list(value.targets)?,
+        })
+    }
+}
+
+impl ProtobufTransform<&MsgReceiptContent> for protos::MsgReceiptContent {
+    fn transform(value: &MsgReceiptContent) -> BuckyResult<Self> {
+        let mut targets = Vec::with_capacity(value.targets.len());
+        for target in &value.targets {
+            targets.push(target.to_vec()?);
+        }
+
+        Ok(Self {
+            r#type: match value.receipt_type {
+                MsgReceiptType::Delivered => 0,
+                MsgReceiptType::Read => 1,
+            },
+            targets,
+        })
+    }
+}
+
+fn serialize_hex<S: serde::Serializer>(value: &Vec<u8>, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&hex::encode(value))
+}
+
+// 使用接收方公钥封装的会话密钥，target可以是people或者device
+#[derive(Clone, Serialize)]
+pub struct MsgEncryptedKey {
+    pub target: ObjectId,
+    #[serde(serialize_with = "serialize_hex")]
+    pub key: Vec<u8>,
+}
+
+// 端到端加密的消息内容，data是对应epoch的密钥链在index位置的密钥加密后的MsgDescContent
+#[derive(Clone, Serialize)]
+pub struct MsgEncryptedContent {
+    pub session: HashValue,
+    pub epoch: u32,
+    pub index: u64,
+
+    // 每条消息都携带当前epoch的会话密钥，所以不提供epoch内的前向安全
+    pub keys: Vec<MsgEncryptedKey>,
+
+    #[serde(serialize_with = "serialize_hex")]
+    pub data: Vec<u8>,
+}
+
+impl ProtobufTransform<protos::MsgEncryptedContent> for MsgEncryptedContent {
+    fn transform(value: protos::MsgEncryptedContent) -> BuckyResult<Self> {
+        let mut keys = Vec::with_capacity(value.keys.len());
+        for item in value.keys {
+            keys.push(MsgEncryptedKey {
+                target: ProtobufCodecHelper::decode_buf(item.target)?,
+                key: item.key,
+            });
+        }
+
+        Ok(Self {
+            session: ProtobufCodecHelper::decode_buf(value.session)?,
+            epoch: value.epoch,
+            index: value.index,
+            keys,
+            data: value.data,
+        })
+    }
+}
+
+impl ProtobufTransform<&MsgEncryptedContent> for protos::MsgEncryptedContent {
+    fn transform(value: &MsgEncryptedContent) -> BuckyResult<Self> {
+        let mut keys = Vec::with_capacity(value.keys.len());
+        for item in &value.keys {
+            keys.push(protos::MsgEncryptedKey {
+                target: item.target.to_vec()?,
+                key: item.key.clone(),
+            });
+        }
+
+        Ok(Self {
+            session: value.session.to_vec()?,
+            epoch: value.epoch,
+            index: value.index,
+            keys,
+            data: value.data.clone(),
+        })
+    }
+}
+
 #[derive(Clone, ProtobufTransformType, Serialize)]
 #[cyfs_protobuf_type(crate::codec::protos::MsgContent)]
 pub enum MsgContent {
     Text(String),
     Object(MsgObjectContent),
+    Encrypted(MsgEncryptedContent),
+    Reply(MsgReplyContent),
+    Edit(MsgEditContent),
+    Delete(MsgDeleteContent),
+    Reaction(MsgReactionContent),
+    File(MsgFileContent),
+    Receipt(MsgReceiptContent),
 }
 
 impl MsgContent {
@@ -26,6 +243,34 @@ impl MsgContent {
             _ => false,
         }
     }
+
+    pub fn is_encrypted(&self) -> bool {
+        match self {
+            MsgContent::Encrypted(_) => true,
+            _ => false,
+        }
+    }
+
+    // 回应、回执等作用于其它消息的内容，返回其目标消息
+    pub fn targets(&self) -> Vec<&ObjectId> {
+        match self {
+            MsgContent::Reply(v) => vec![&v.reply_to],
+            MsgContent::Edit(v) => vec![&v.target],
+            MsgContent::Delete(v) => vec![&v.target],
+            MsgContent::Reaction(v) => vec![&v.target],
+            MsgContent::Receipt(v) => v.targets.iter().collect(),
+            _ => vec![],
+        }
+    }
+
+    // 新增类型的字段都是optional，旧版本编码的消息里不存在
+    fn check_field<T>(value: Option<T>, name: &str) -> BuckyResult<T> {
+        value.ok_or_else(|| {
+            let msg = format!("msg content field missing: {}", name);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })
+    }
 }
 
 // MsgContent的编解码
@@ -36,6 +281,13 @@ impl ProtobufTransform<protos::MsgContent> for MsgContent {
             1 => Self::Object(
                 ProtobufTransform::transform(value.content.unwrap())?,
             ),
+            2 => Self::Encrypted(ProtobufTransform::transform(Self::check_field(value.encrypted, "encrypted")?)?),
+            3 => Self::Reply(ProtobufTransform::transform(Self::check_field(value.reply, "reply")?)?),
+            4 => Self::Edit(ProtobufTransform::transform(Self::check_field(value.edit, "edit")?)?),
+            5 => Self::Delete(ProtobufTransform::transform(Self::check_field(value.delete, "delete")?)?),
+            6 => Self::Reaction(ProtobufTransform::transform(Self::check_field(value.reaction, "reaction")?)?),
+            7 => Self::File(ProtobufTransform::transform(Self::check_field(value.file, "file")?)?),
+            8 => Self::Receipt(ProtobufTransform::transform(Self::check_field(value.receipt, "receipt")?)?),
             _ => {
                 return Err(BuckyError::new(BuckyErrorCode::Failed, format!("unknown msg content type {}", value.r#type)));
             }
@@ -57,6 +309,34 @@ impl ProtobufTransform<&MsgContent> for protos::MsgContent {
                 ret.r#type = 1;
                 ret.content = Some(ProtobufTransform::transform(o)?);
             }
+            MsgContent::Encrypted(e) => {
+                ret.r#type = 2;
+                ret.encrypted = Some(ProtobufTransform::transform(e)?);
+            }
+            MsgContent::Reply(v) => {
+                ret.r#type = 3;
+                ret.reply = Some(ProtobufTransform::transform(v)?);
+            }
+            MsgContent::Edit(v) => {
+                ret.r#type = 4;
+                ret.edit = Some(ProtobufTransform::transform(v)?);
+            }
+            MsgContent::Delete(v) => {
+                ret.r#type = 5;
+                ret.delete = Some(ProtobufTransform::transform(v)?);
+            }
+            MsgContent::Reaction(v) => {
+                ret.r#type = 6;
+                ret.reaction = Some(ProtobufTransform::transform(v)?);
+            }
+            MsgContent::File(v) => {
+                ret.r#type = 7;
+                ret.file = Some(ProtobufTransform::transform(v)?);
+            }
+            MsgContent::Receipt(v) => {
+                ret.r#type = 8;
+                ret.receipt = Some(ProtobufTransform::transform(v)?);
+            }
         }
         Ok(ret)
     }
@@ -94,6 +374,18 @@ impl MsgDescContent {
     pub fn create(to: ObjectId, content: MsgContent) -> Self {
         Self { to, content }
     }
+
+    pub fn to(&self) -> &ObjectId {
+        &self.to
+    }
+
+    pub fn content(&self) -> &MsgContent {
+        &self.content
+    }
+
+    pub fn into_content(self) -> MsgContent {
+        self.content
+    }
 }
 
 pub trait MsgObject {
@@ -135,3 +427,79 @@ impl MsgObject for Msg {
         self.to() == id
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_msg_content_codec() {
+        let owner = PeopleId::default();
+        let to = ObjectId::default();
+        let target = Msg::create(owner.clone(), to.clone(), MsgContent::Text("hello".to_owned()))
+            .desc()
+            .calculate_id();
+
+        let list = vec![
+            MsgContent::Text("hello".to_owned()),
+            MsgContent::Reply(MsgReplyContent {
+                reply_to: target.clone(),
+                text: "reply".to_owned(),
+            }),
+            MsgContent::Edit(MsgEditContent {
+                target: target.clone(),
+                text: "edit".to_owned(),
+            }),
+            MsgContent::Delete(MsgDeleteContent {
+                target: target.clone(),
+            }),
+            MsgContent::Reaction(MsgReactionContent {
+                target: target.clone(),
+                reaction: "👍".to_owned(),
+                remove: false,
+            }),
+            MsgContent::File(MsgFileContent {
+                id: target.clone(),
+                name: "a.png".to_owned(),
+                mime: "image/png".to_owned(),
+                size: 1024,
+                thumb: Some(ChunkId::calculate_sync(b"thumb").unwrap()),
+            }),
+            MsgContent::Receipt(MsgReceiptContent {
+                receipt_type: MsgReceiptType::Read,
+                targets: vec![target.clone(), to.clone()],
+            }),
+        ];
+
+        for content in list {
+            let msg = Msg::create(owner.clone(), to.clone(), content);
+            let buf = msg.to_vec().unwrap();
+            let got = Msg::clone_from_slice(&buf).unwrap();
+            assert_eq!(got.desc().calculate_id(), msg.desc().calculate_id());
+
+            match (msg.content(), got.content()) {
+                (MsgContent::Text(a), MsgContent::Text(b)) => assert_eq!(a, b),
+                (MsgContent::Reply(a), MsgContent::Reply(b)) => {
+                    assert_eq!(a.reply_to, b.reply_to);
+                    assert_eq!(a.text, b.text);
+                }
+                (MsgContent::Edit(a), MsgContent::Edit(b)) => assert_eq!(a.text, b.text),
+                (MsgContent::Delete(a), MsgContent::Delete(b)) => assert_eq!(a.target, b.target),
+                (MsgContent::Reaction(a), MsgContent::Reaction(b)) => {
+                    assert_eq!(a.reaction, b.reaction);
+                    assert_eq!(a.remove, b.remove);
+                }
+                (MsgContent::File(a), MsgContent::File(b)) => {
+                    assert_eq!(a.size, b.size);
+                    assert_eq!(a.thumb, b.thumb);
+                }
+                (MsgContent::Receipt(a), MsgContent::Receipt(b)) => {
+                    assert_eq!(a.receipt_type, b.receipt_type);
+                    assert_eq!(a.targets, b.targets);
+                }
+                _ => unreachable!(),
+            }
+            assert_eq!(got.content().targets().len(), msg.content().targets().len());
+        }
+    }
+}
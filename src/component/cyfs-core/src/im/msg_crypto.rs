@@ -0,0 +1,549 @@
+use super::msg::*;
+use cyfs_base::*;
+
+use std::collections::HashMap;
+
+// 接收端允许跳过(乱序或者丢失)的最大消息数
+pub const MSG_KEY_CHAIN_MAX_SKIP: u64 = 1024;
+
+// 每个epoch最多加密的消息数，超过后自动换新的会话密钥
+pub const MSG_EPOCH_MAX_MESSAGES: u64 = 256;
+
+// HKDF的info，区分chain_key和消息密钥的派生
+const MSG_KEY_CHAIN_INFO: &[u8] = b"cyfs-msg-key-chain";
+const MSG_KEY_INFO: &[u8] = b"cyfs-msg-key";
+
+// 消息加密的目标，一般是接收方(以及发送方自己)的people和各个device
+#[derive(Clone)]
+pub struct MsgEncryptTarget {
+    pub id: ObjectId,
+    pub public_key: PublicKey,
+}
+
+impl MsgEncryptTarget {
+    pub fn new(id: ObjectId, public_key: PublicKey) -> Self {
+        Self { id, public_key }
+    }
+
+    pub fn from_device(device: &Device) -> Self {
+        Self::new(
+            device.desc().calculate_id(),
+            device.desc().public_key().clone(),
+        )
+    }
+
+    pub fn from_people(people: &People) -> Self {
+        Self::new(
+            people.desc().calculate_id(),
+            people.desc().public_key().clone(),
+        )
+    }
+
+    // secp256k1使用ecies交换的密钥，rsa直接加密随机密钥，再用该密钥加密epoch的会话密钥
+    fn seal_key(&self, session_key: &AesKey) -> BuckyResult<MsgEncryptedKey> {
+        let (kek, mut key) = self.public_key.gen_aeskey_and_encrypt()?;
+        let sealed = kek.gcm_encrypt(session_key.as_slice())?;
+        key.extend_from_slice(&sealed);
+
+        Ok(MsgEncryptedKey {
+            target: self.id.clone(),
+            key,
+        })
+    }
+}
+
+// 从epoch的会话密钥依次派生每条消息的密钥，只用于区分每条消息的密钥和防止重放
+// 会话密钥随epoch内的每条消息携带，泄露接收方私钥即可解密整个epoch，不提供前向安全，
+// 泄露的影响范围由epoch的轮换(rekey)限制
+#[derive(Clone)]
+pub struct MsgKeyChain {
+    chain_key: AesKey,
+    index: u64,
+}
+
+impl MsgKeyChain {
+    // 派生时混入发送者，同一个会话密钥在其它发送者名下得到的是不同的密钥链
+    pub fn new(session_key: &AesKey, owner: &ObjectId) -> Self {
+        let chain_key = AesKey::hkdf(session_key.as_slice(), owner.as_slice(), MSG_KEY_CHAIN_INFO);
+        Self {
+            chain_key,
+            index: 0,
+        }
+    }
+
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    // 返回当前index和对应的消息密钥，并推进到下一步
+    pub fn next(&mut self) -> (u64, AesKey) {
+        let key = AesKey::hkdf(self.chain_key.as_slice(), &[], MSG_KEY_INFO);
+        self.chain_key = AesKey::hkdf(self.chain_key.as_slice(), &[], MSG_KEY_CHAIN_INFO);
+
+        let index = self.index;
+        self.index += 1;
+
+        (index, key)
+    }
+}
+
+// 发送端，每个会话(一般对应一个发送设备上的一个对话)一个实例
+pub struct MsgSealer {
+    owner: PeopleId,
+    session: HashValue,
+    epoch: u32,
+    key_chain: MsgKeyChain,
+    targets: Vec<MsgEncryptTarget>,
+
+    // 当前epoch的会话密钥，每条消息都携带，接收端可以从epoch内的任意一条消息开始解密
+    keys: Vec<MsgEncryptedKey>,
+}
+
+impl MsgSealer {
+    pub fn new(owner: PeopleId, targets: Vec<MsgEncryptTarget>) -> BuckyResult<Self> {
+        let session = hash_data(AesKey::random().as_slice());
+        let (key_chain, keys) = Self::new_epoch(owner.object_id(), &targets)?;
+
+        Ok(Self {
+            owner,
+            session,
+            epoch: 0,
+            key_chain,
+            targets,
+            keys,
+        })
+    }
+
+    pub fn owner(&self) -> &PeopleId {
+        &self.owner
+    }
+
+    pub fn session(&self) -> &HashValue {
+        &self.session
+    }
+
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+
+    pub fn targets(&self) -> &[MsgEncryptTarget] {
+        &self.targets
+    }
+
+    fn new_epoch(
+        owner: &ObjectId,
+        targets: &[MsgEncryptTarget],
+    ) -> BuckyResult<(MsgKeyChain, Vec<MsgEncryptedKey>)> {
+        if targets.is_empty() {
+            let msg = "encrypt msg but target list is empty!".to_owned();
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        let session_key = AesKey::random();
+        let mut keys = Vec::with_capacity(targets.len());
+        for target in targets {
+            keys.push(target.seal_key(&session_key)?);
+        }
+
+        Ok((MsgKeyChain::new(&session_key, owner), keys))
+    }
+
+    // 目标设备变化(新增或者撤销设备)时调用，开始新的epoch，旧设备无法解密之后的消息
+    pub fn rekey(&mut self, targets: Vec<MsgEncryptTarget>) -> BuckyResult<()> {
+        let (key_chain, keys) = Self::new_epoch(self.owner.object_id(), &targets)?;
+        self.key_chain = key_chain;
+        self.keys = keys;
+        self.targets = targets;
+        self.epoch += 1;
+
+        info!(
+            "msg session rekey: session={}, epoch={}",
+            self.session, self.epoch
+        );
+        Ok(())
+    }
+
+    pub fn seal_content(&mut self, to: &ObjectId, content: MsgContent) -> BuckyResult<MsgContent> {
+        if content.is_encrypted() {
+            let msg = format!("msg content already encrypted! to={}", to);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        if self.key_chain.index() >= MSG_EPOCH_MAX_MESSAGES {
+            let targets = self.targets.clone();
+            self.rekey(targets)?;
+        }
+
+        // 加密整个MsgDescContent，解密时校验to，避免密文被挪用到其它会话
+        let plain = MsgDescContent::create(to.to_owned(), content).to_vec()?;
+
+        let (index, key) = self.key_chain.next();
+        let data = key.gcm_encrypt(&plain)?;
+
+        Ok(MsgContent::Encrypted(MsgEncryptedContent {
+            session: self.session.clone(),
+            epoch: self.epoch,
+            index,
+            keys: self.keys.clone(),
+            data,
+        }))
+    }
+
+    pub fn seal(&mut self, to: ObjectId, content: MsgContent) -> BuckyResult<Msg> {
+        let content = self.seal_content(&to, content)?;
+        Ok(Msg::create(self.owner.clone(), to, content))
+    }
+}
+
+struct MsgReceiveChain {
+    key_chain: MsgKeyChain,
+
+    // 建立接收链时使用的本设备的会话密钥密文，epoch内后续消息携带的必须一致
+    sealed_key_hash: HashValue,
+
+    // 跳过的消息密钥，用来解密乱序到达的消息
+    skipped: HashMap<u64, AesKey>,
+}
+
+impl MsgReceiveChain {
+    fn key_at(&mut self, index: u64) -> BuckyResult<AesKey> {
+        if index < self.key_chain.index() {
+            return self.skipped.remove(&index).ok_or_else(|| {
+                let msg = format!("msg key already used or expired! index={}", index);
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::AlreadyExists, msg)
+            });
+        }
+
+        if index - self.key_chain.index() > MSG_KEY_CHAIN_MAX_SKIP
+            || self.skipped.len() as u64 + index - self.key_chain.index() > MSG_KEY_CHAIN_MAX_SKIP
+        {
+            let msg = format!(
+                "too many skipped msg! index={}, current={}, skipped={}",
+                index,
+                self.key_chain.index(),
+                self.skipped.len()
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+        }
+
+        loop {
+            let (i, key) = self.key_chain.next();
+            if i == index {
+                break Ok(key);
+            }
+            self.skipped.insert(i, key);
+        }
+    }
+}
+
+// 接收端，每个设备(或者people)一个实例，持有对应的私钥
+pub struct MsgOpener {
+    target: ObjectId,
+    secret: PrivateKey,
+
+    // (owner, session, epoch) -> 接收链
+    chains: HashMap<(ObjectId, HashValue, u32), MsgReceiveChain>,
+}
+
+impl MsgOpener {
+    pub fn new(target: ObjectId, secret: PrivateKey) -> Self {
+        Self {
+            target,
+            secret,
+            chains: HashMap::new(),
+        }
+    }
+
+    pub fn target(&self) -> &ObjectId {
+        &self.target
+    }
+
+    fn unseal_key(&self, key: &[u8]) -> BuckyResult<AesKey> {
+        let (sealed, kek) = self.secret.decrypt_aeskey_data(key)?;
+        if kek.len() != 48 {
+            let msg = format!("invalid msg key exchange result! len={}", kek.len());
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::CryptoError, msg));
+        }
+
+        let session_key = AesKey::from(kek).gcm_decrypt(sealed)?;
+        if session_key.len() != 48 {
+            let msg = format!("invalid msg session key! len={}", session_key.len());
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::CryptoError, msg));
+        }
+
+        Ok(AesKey::from(session_key))
+    }
+
+    // 明文消息原样返回，owner是消息的发送者
+    pub fn open_content(
+        &mut self,
+        owner: &ObjectId,
+        to: &ObjectId,
+        content: &MsgContent,
+    ) -> BuckyResult<MsgContent> {
+        let content = match content {
+            MsgContent::Encrypted(v) => v,
+            _ => return Ok(content.clone()),
+        };
+
+        let key = content
+            .keys
+            .iter()
+            .find(|v| v.target == self.target)
+            .ok_or_else(|| {
+                let msg = format!(
+                    "msg session key for target not found, rekey required! session={}, epoch={}, target={}",
+                    content.session, content.epoch, self.target
+                );
+                warn!("{}", msg);
+                BuckyError::new(BuckyErrorCode::NotFound, msg)
+            })?;
+        let sealed_key_hash = hash_data(&key.key);
+
+        // session和epoch是公开的，接收链需要绑定发送者和建立时的会话密钥，不能被其它消息替换
+        let id = (owner.to_owned(), content.session.clone(), content.epoch);
+        match self.chains.get(&id) {
+            Some(chain) => {
+                if chain.sealed_key_hash != sealed_key_hash {
+                    let msg = format!(
+                        "msg session key changed in epoch! owner={}, session={}, epoch={}, target={}",
+                        owner, content.session, content.epoch, self.target
+                    );
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::Unmatch, msg));
+                }
+            }
+            None => {
+                let session_key = self.unseal_key(&key.key)?;
+                self.chains.insert(
+                    id.clone(),
+                    MsgReceiveChain {
+                        key_chain: MsgKeyChain::new(&session_key, owner),
+                        sealed_key_hash,
+                        skipped: HashMap::new(),
+                    },
+                );
+            }
+        }
+
+        let key = self.chains.get_mut(&id).unwrap().key_at(content.index)?;
+        let plain = key.gcm_decrypt(&content.data)?;
+
+        let desc = MsgDescContent::clone_from_slice(&plain)?;
+        if desc.to() != to {
+            let msg = format!(
+                "encrypted msg target mismatch! expected={}, got={}",
+                to,
+                desc.to()
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
+        }
+
+        Ok(desc.into_content())
+    }
+
+    pub fn open(&mut self, msg: &Msg) -> BuckyResult<MsgContent> {
+        let owner = msg.desc().owner().as_ref().ok_or_else(|| {
+            let msg = format!("encrypted msg has no owner! msg={}", msg.desc().calculate_id());
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidData, msg)
+        })?;
+
+        self.open_content(owner, msg.to(), msg.content())
+    }
+
+    // 不再需要旧epoch时调用，比如设备被移出目标列表后
+    pub fn remove_session(&mut self, session: &HashValue) {
+        self.chains.retain(|(_, s, _), _| s != session);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn new_device(owner: &ObjectId, secret: &PrivateKey) -> Device {
+        Device::new(
+            Some(owner.to_owned()),
+            UniqueId::create(&secret.public().to_vec().unwrap()),
+            vec![],
+            vec![],
+            vec![],
+            secret.public(),
+            Area::default(),
+            DeviceCategory::PC,
+        )
+        .build()
+    }
+
+    #[test]
+    fn test_msg_multi_device() {
+        let alice_secret = PrivateKey::generate_secp256k1().unwrap();
+        let alice = People::new(None, vec![], alice_secret.public(), None, None, None).build();
+        let alice_id = alice.desc().calculate_id();
+        let bob_secret = PrivateKey::generate_secp256k1().unwrap();
+        let bob = People::new(None, vec![], bob_secret.public(), None, None, None).build();
+        let bob_id = bob.desc().calculate_id();
+
+        // alice的发送设备，以及bob的两台设备，其中一台使用rsa
+        let alice1_secret = PrivateKey::generate_secp256k1().unwrap();
+        let alice1 = new_device(&alice_id, &alice1_secret);
+        let bob1_secret = PrivateKey::generate_secp256k1().unwrap();
+        let bob1 = new_device(&bob_id, &bob1_secret);
+        let bob2_secret = PrivateKey::generate_rsa(1024).unwrap();
+        let bob2 = new_device(&bob_id, &bob2_secret);
+
+        let targets = vec![
+            MsgEncryptTarget::from_people(&bob),
+            MsgEncryptTarget::from_device(&bob1),
+            MsgEncryptTarget::from_device(&bob2),
+            MsgEncryptTarget::from_device(&alice1),
+        ];
+        let owner = PeopleId::try_from(alice_id).unwrap();
+        let mut sealer = MsgSealer::new(owner, targets).unwrap();
+
+        let mut openers = vec![
+            MsgOpener::new(bob_id.clone(), bob_secret),
+            MsgOpener::new(bob1.desc().calculate_id(), bob1_secret),
+            MsgOpener::new(bob2.desc().calculate_id(), bob2_secret),
+            MsgOpener::new(alice1.desc().calculate_id(), alice1_secret),
+        ];
+
+        let list: Vec<Msg> = (0..3)
+            .map(|i| {
+                let content = MsgContent::Text(format!("hello {}", i));
+                sealer.seal(bob_id.clone(), content).unwrap()
+            })
+            .collect();
+
+        // 编解码后依然可以解密
+        let list: Vec<Msg> = list
+            .iter()
+            .map(|msg| Msg::clone_from_slice(&msg.to_vec().unwrap()).unwrap())
+            .collect();
+        assert!(list[0].content().is_encrypted());
+
+        for opener in openers.iter_mut() {
+            // 乱序到达
+            for i in [0, 2, 1] {
+                match opener.open(&list[i]).unwrap() {
+                    MsgContent::Text(text) => assert_eq!(text, format!("hello {}", i)),
+                    _ => unreachable!(),
+                }
+            }
+
+            // 重放
+            assert!(opener.open(&list[1]).is_err());
+        }
+
+        // 不在目标列表里的设备无法解密
+        let other_secret = PrivateKey::generate_secp256k1().unwrap();
+        let mut other = MsgOpener::new(new_device(&bob_id, &other_secret).desc().calculate_id(), other_secret);
+        assert!(other.open(&list[0]).is_err());
+
+        // 移除bob2后换新的会话密钥
+        let targets = sealer
+            .targets()
+            .iter()
+            .filter(|v| v.id != openers[2].target().to_owned())
+            .cloned()
+            .collect();
+        sealer.rekey(targets).unwrap();
+        let msg = sealer
+            .seal(bob_id.clone(), MsgContent::Text("after rekey".to_owned()))
+            .unwrap();
+        assert!(openers[1].open(&msg).is_ok());
+        assert!(openers[2].open(&msg).is_err());
+    }
+
+    #[test]
+    fn test_msg_open_later_index_first() {
+        let bob_secret = PrivateKey::generate_secp256k1().unwrap();
+        let bob = People::new(None, vec![], bob_secret.public(), None, None, None).build();
+        let bob_id = bob.desc().calculate_id();
+        let alice_secret = PrivateKey::generate_secp256k1().unwrap();
+        let alice = People::new(None, vec![], alice_secret.public(), None, None, None).build();
+        let alice_id = alice.desc().calculate_id();
+
+        let owner = PeopleId::try_from(alice_id.clone()).unwrap();
+        let mut sealer = MsgSealer::new(owner, vec![MsgEncryptTarget::from_people(&bob)]).unwrap();
+        let list: Vec<MsgContent> = (0..4)
+            .map(|i| sealer.seal_content(&bob_id, MsgContent::Text(format!("hello {}", i))).unwrap())
+            .collect();
+
+        // 每条消息都携带会话密钥
+        for content in &list {
+            match content {
+                MsgContent::Encrypted(v) => assert_eq!(v.keys.len(), 1),
+                _ => unreachable!(),
+            }
+        }
+
+        // 先收到index>0的消息，之前的消息依然可以解密
+        let mut opener = MsgOpener::new(bob_id.clone(), bob_secret.clone());
+        for i in [2, 0, 3] {
+            match opener.open_content(&alice_id, &bob_id, &list[i]).unwrap() {
+                MsgContent::Text(text) => assert_eq!(text, format!("hello {}", i)),
+                _ => unreachable!(),
+            }
+        }
+
+        // 丢失epoch的第一条消息也可以解密
+        let mut opener = MsgOpener::new(bob_id.clone(), bob_secret);
+        match opener.open_content(&alice_id, &bob_id, &list[3]).unwrap() {
+            MsgContent::Text(text) => assert_eq!(text, "hello 3"),
+            _ => unreachable!(),
+        }
+        assert!(opener.open_content(&alice_id, &bob_id, &list[3]).is_err());
+    }
+
+    #[test]
+    fn test_msg_chain_bind_sender() {
+        let bob_secret = PrivateKey::generate_secp256k1().unwrap();
+        let bob = People::new(None, vec![], bob_secret.public(), None, None, None).build();
+        let bob_id = bob.desc().calculate_id();
+        let alice_secret = PrivateKey::generate_secp256k1().unwrap();
+        let alice = People::new(None, vec![], alice_secret.public(), None, None, None).build();
+        let alice_id = alice.desc().calculate_id();
+        let owner = PeopleId::try_from(alice_id.clone()).unwrap();
+
+        let targets = vec![MsgEncryptTarget::from_people(&bob)];
+        let mut sealer = MsgSealer::new(owner.clone(), targets.clone()).unwrap();
+        let list: Vec<MsgContent> = (0..2)
+            .map(|i| sealer.seal_content(&bob_id, MsgContent::Text(format!("hello {}", i))).unwrap())
+            .collect();
+
+        // 挪用到其它发送者名下无法解密
+        let mut opener = MsgOpener::new(bob_id.clone(), bob_secret.clone());
+        assert!(opener.open_content(&bob_id, &bob_id, &list[0]).is_err());
+        assert!(opener.open_content(&alice_id, &bob_id, &list[0]).is_ok());
+
+        // 使用相同的session和epoch，但是携带了另一个会话密钥的消息，会被拒绝
+        let mut other = MsgSealer::new(owner, targets).unwrap();
+        let content = MsgContent::Text("forged".to_owned());
+        let forged = match other.seal_content(&bob_id, content).unwrap() {
+            MsgContent::Encrypted(mut v) => {
+                v.session = sealer.session().to_owned();
+                v.epoch = sealer.epoch();
+                v.index = 1;
+                MsgContent::Encrypted(v)
+            }
+            _ => unreachable!(),
+        };
+        let e = opener.open_content(&alice_id, &bob_id, &forged).unwrap_err();
+        assert_eq!(e.code(), BuckyErrorCode::Unmatch);
+
+        // 真实的后续消息不受影响
+        match opener.open_content(&alice_id, &bob_id, &list[1]).unwrap() {
+            MsgContent::Text(text) => assert_eq!(text, "hello 1"),
+            _ => unreachable!(),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use super::audit::*;
 use crate::root_state::*;
 use cyfs_base::*;
 use cyfs_core::codec::protos::core_objects as protos;
@@ -15,6 +16,7 @@ pub struct AdminGlobalStateAccessModeData {
 #[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub enum AdminCommand {
     GlobalStateAccessMode(AdminGlobalStateAccessModeData),
+    QueryAuditLog(AuditLogFilter),
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -112,6 +114,87 @@ impl TryFrom<&AdminGlobalStateAccessModeData> for protos::AdminGlobalStateAccess
 
 impl_default_protobuf_raw_codec!(AdminGlobalStateAccessModeData);
 
+impl TryFrom<protos::AdminQueryAuditLogData> for AuditLogFilter {
+    type Error = BuckyError;
+
+    fn try_from(mut value: protos::AdminQueryAuditLogData) -> BuckyResult<Self> {
+        let source_device = if value.source_device.is_empty() {
+            None
+        } else {
+            Some(ProtobufCodecHelper::decode_buf(value.take_source_device())?)
+        };
+
+        let source_dec = if value.source_dec.is_empty() {
+            None
+        } else {
+            Some(ProtobufCodecHelper::decode_buf(value.take_source_dec())?)
+        };
+
+        let category = if value.category.is_empty() {
+            None
+        } else {
+            Some(value.take_category())
+        };
+
+        let decision = match value.decision {
+            0 => None,
+            v @ _ => Some(AuditDecision::try_from((v - 1) as u8)?),
+        };
+
+        let begin_time = if value.begin_time == 0 {
+            None
+        } else {
+            Some(value.begin_time)
+        };
+
+        let end_time = if value.end_time == 0 {
+            None
+        } else {
+            Some(value.end_time)
+        };
+
+        Ok(Self {
+            source_device,
+            source_dec,
+            category,
+            decision,
+            begin_time,
+            end_time,
+            page_index: value.page_index,
+            page_size: value.page_size,
+        })
+    }
+}
+
+impl TryFrom<&AuditLogFilter> for protos::AdminQueryAuditLogData {
+    type Error = BuckyError;
+
+    fn try_from(value: &AuditLogFilter) -> BuckyResult<Self> {
+        let mut ret = Self::new();
+
+        if let Some(source_device) = &value.source_device {
+            ret.set_source_device(source_device.to_vec()?);
+        }
+        if let Some(source_dec) = &value.source_dec {
+            ret.set_source_dec(source_dec.to_vec()?);
+        }
+        if let Some(category) = &value.category {
+            ret.set_category(category.to_owned());
+        }
+        if let Some(decision) = &value.decision {
+            ret.set_decision(*decision as u32 + 1);
+        }
+        ret.set_begin_time(value.begin_time.unwrap_or(0));
+        ret.set_end_time(value.end_time.unwrap_or(0));
+        ret.set_page_index(value.page_index);
+        ret.set_page_size(value.page_size);
+
+        Ok(ret)
+    }
+}
+
+impl_default_protobuf_raw_codec!(AuditLogFilter);
+
 impl TryFrom<protos::AdminDescContent> for AdminDescContent {
     type Error = BuckyError;
 
@@ -131,6 +214,19 @@ impl TryFrom<protos::AdminDescContent> for AdminDescContent {
                     ProtobufCodecHelper::decode_nested_item(value.take_global_state_access_mode())?;
                 AdminCommand::GlobalStateAccessMode(data)
             }
+            protos::AdminDescContent_Command::QueryAuditLog => {
+                if !value.has_query_audit_log() {
+                    let msg = format!(
+                        "invalid AdminDescContent query_audit_log field! {:?}",
+                        value
+                    );
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                }
+
+                let data = ProtobufCodecHelper::decode_nested_item(value.take_query_audit_log())?;
+                AdminCommand::QueryAuditLog(data)
+            }
         };
 
         let target = ProtobufCodecHelper::decode_buf(value.take_target())?;
@@ -152,6 +248,11 @@ impl TryFrom<&AdminDescContent> for protos::AdminDescContent {
                 let data = data.try_into()?;
                 ret.set_global_state_access_mode(data);
             }
+            AdminCommand::QueryAuditLog(ref data) => {
+                let data = data.try_into()?;
+                ret.set_cmd(protos::AdminDescContent_Command::QueryAuditLog);
+                ret.set_query_audit_log(data);
+            }
         }
 
         ret.set_target(value.target.to_vec().unwrap());
@@ -220,4 +321,42 @@ mod test {
         let c_cmd = obj.into_command();
         assert_eq!(c_cmd, cmd);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_query_audit_log_object() {
+        let filter = AuditLogFilter {
+            source_dec: Some(cyfs_core::get_system_dec_app().to_owned()),
+            decision: Some(AuditDecision::Deny),
+            begin_time: Some(bucky_time_now()),
+            page_size: 32,
+            ..Default::default()
+        };
+
+        let cmd = AdminCommand::QueryAuditLog(filter);
+
+        let target = DeviceId::from_str("5aSixgLkHa2NR4vSKJLYLPo5Av6CY3RJeFJegtF5iR1g").unwrap();
+        let owner = PeopleId::default();
+        let obj = AdminObject::create(owner.into(), target, cmd.clone());
+        let buf = obj.to_vec().unwrap();
+
+        let obj = AdminObject::clone_from_slice(&buf).unwrap();
+        assert_eq!(obj.into_command(), cmd);
+    }
+
+    #[test]
+    fn test_format() {
+        crate::register_core_objects_format();
+        assert!(FORMAT_FACTORY.is_support(cyfs_core::CoreObjectType::Admin as u16));
+
+        let cmd = AdminCommand::GlobalStateAccessMode(AdminGlobalStateAccessModeData {
+            category: GlobalStateCategory::LocalCache,
+            access_mode: GlobalStateAccessMode::Write,
+        });
+        let target = DeviceId::from_str("5aSixgLkHa2NR4vSKJLYLPo5Av6CY3RJeFJegtF5iR1g").unwrap();
+        let obj = AdminObject::create(PeopleId::default().into(), target, cmd);
+        let id = obj.desc().calculate_id();
+
+        let value = FORMAT_FACTORY.format(cyfs_core::CoreObjectType::Admin as u16, &obj.to_vec().unwrap()).unwrap();
+        assert_eq!(value["desc"]["object_id"], id.to_string());
+    }
+}
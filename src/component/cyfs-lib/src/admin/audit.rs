@@ -0,0 +1,192 @@
+use crate::base::DeviceZoneCategory;
+use cyfs_base::*;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[repr(u8)]
+pub enum AuditDecision {
+    Allow = 0,
+    Deny = 1,
+}
+
+impl AuditDecision {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+impl TryFrom<u8> for AuditDecision {
+    type Error = BuckyError;
+
+    fn try_from(value: u8) -> BuckyResult<Self> {
+        match value {
+            0 => Ok(Self::Allow),
+            1 => Ok(Self::Deny),
+            v @ _ => {
+                let msg = format!("unknown audit decision value: {}", v);
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg))
+            }
+        }
+    }
+}
+
+impl ToString for AuditDecision {
+    fn to_string(&self) -> String {
+        self.as_str().to_owned()
+    }
+}
+
+impl FromStr for AuditDecision {
+    type Err = BuckyError;
+
+    fn from_str(s: &str) -> BuckyResult<Self> {
+        match s {
+            "allow" => Ok(Self::Allow),
+            "deny" => Ok(Self::Deny),
+            v @ _ => {
+                let msg = format!("unknown audit decision: {}", v);
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg))
+            }
+        }
+    }
+}
+
+pub fn request_op_type_as_str(op_type: RequestOpType) -> &'static str {
+    match op_type {
+        RequestOpType::Read => "read",
+        RequestOpType::Write => "write",
+        RequestOpType::Call => "call",
+    }
+}
+
+pub fn request_op_type_from_str(s: &str) -> BuckyResult<RequestOpType> {
+    match s {
+        "read" => Ok(RequestOpType::Read),
+        "write" => Ok(RequestOpType::Write),
+        "call" => Ok(RequestOpType::Call),
+        v @ _ => {
+            let msg = format!("unknown request op type: {}", v);
+            error!("{}", msg);
+            Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg))
+        }
+    }
+}
+
+// 一条审计记录：谁(source)对什么(object/req_path)做了什么操作，以及acl的判定结果
+#[derive(Clone, Debug)]
+pub struct AuditLogRecord {
+    // 存储分配的自增id，新记录为0
+    pub id: u64,
+
+    // 记录时间，bucky_time
+    pub time: u64,
+
+    pub source_device: Option<DeviceId>,
+    pub source_zone: DeviceZoneCategory,
+    pub source_dec: ObjectId,
+
+    // 请求分类，比如put_object/get_data/op_env.commit/global_state.meta.add_access
+    pub category: String,
+    pub op_type: RequestOpType,
+
+    pub object_id: Option<ObjectId>,
+    pub req_path: Option<String>,
+
+    pub decision: AuditDecision,
+
+    // 拒绝的原因
+    pub reason: Option<String>,
+}
+
+impl JsonCodec<AuditLogRecord> for AuditLogRecord {
+    fn encode_json(&self) -> Map<String, Value> {
+        let mut obj = Map::new();
+
+        JsonCodecHelper::encode_string_field(&mut obj, "id", &self.id);
+        JsonCodecHelper::encode_string_field(&mut obj, "time", &self.time);
+        JsonCodecHelper::encode_option_string_field(
+            &mut obj,
+            "source_device",
+            self.source_device.as_ref(),
+        );
+        JsonCodecHelper::encode_string_field(&mut obj, "source_zone", &self.source_zone);
+        JsonCodecHelper::encode_string_field(&mut obj, "source_dec", &self.source_dec);
+        JsonCodecHelper::encode_string_field(&mut obj, "category", &self.category);
+        JsonCodecHelper::encode_string_field(
+            &mut obj,
+            "op_type",
+            request_op_type_as_str(self.op_type),
+        );
+        JsonCodecHelper::encode_option_string_field(&mut obj, "object_id", self.object_id.as_ref());
+        JsonCodecHelper::encode_option_string_field(&mut obj, "req_path", self.req_path.as_ref());
+        JsonCodecHelper::encode_string_field(&mut obj, "decision", &self.decision);
+        JsonCodecHelper::encode_option_string_field(&mut obj, "reason", self.reason.as_ref());
+
+        obj
+    }
+
+    fn decode_json(obj: &Map<String, Value>) -> BuckyResult<Self> {
+        let op_type: String = JsonCodecHelper::decode_string_field(obj, "op_type")?;
+
+        Ok(Self {
+            id: JsonCodecHelper::decode_string_field(obj, "id")?,
+            time: JsonCodecHelper::decode_string_field(obj, "time")?,
+            source_device: JsonCodecHelper::decode_option_string_field(obj, "source_device")?,
+            source_zone: JsonCodecHelper::decode_string_field(obj, "source_zone")?,
+            source_dec: JsonCodecHelper::decode_string_field(obj, "source_dec")?,
+            category: JsonCodecHelper::decode_string_field(obj, "category")?,
+            op_type: request_op_type_from_str(&op_type)?,
+            object_id: JsonCodecHelper::decode_option_string_field(obj, "object_id")?,
+            req_path: JsonCodecHelper::decode_option_string_field(obj, "req_path")?,
+            decision: JsonCodecHelper::decode_string_field(obj, "decision")?,
+            reason: JsonCodecHelper::decode_option_string_field(obj, "reason")?,
+        })
+    }
+}
+
+// 审计记录的查询条件，未指定的条件不做过滤，结果按时间倒序分页返回
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct AuditLogFilter {
+    pub source_device: Option<DeviceId>,
+    pub source_dec: Option<ObjectId>,
+    pub category: Option<String>,
+    pub decision: Option<AuditDecision>,
+
+    // [begin_time, end_time)
+    pub begin_time: Option<u64>,
+    pub end_time: Option<u64>,
+
+    pub page_index: u32,
+    pub page_size: u32,
+}
+
+// 查询结果通过Text对象返回，value为json编码的记录列表
+pub const AUDIT_LOG_QUERY_RESULT_TEXT_ID: &str = "audit_log";
+
+#[derive(Clone, Debug)]
+pub struct AuditLogQueryResult {
+    pub list: Vec<AuditLogRecord>,
+}
+
+impl JsonCodec<AuditLogQueryResult> for AuditLogQueryResult {
+    fn encode_json(&self) -> Map<String, Value> {
+        let mut obj = Map::new();
+        JsonCodecHelper::encode_as_list(&mut obj, "list", &self.list);
+        obj
+    }
+
+    fn decode_json(obj: &Map<String, Value>) -> BuckyResult<Self> {
+        Ok(Self {
+            list: JsonCodecHelper::decode_array_field(obj, "list")?,
+        })
+    }
+}
@@ -1,3 +1,5 @@
 mod admin_object;
+mod audit;
 
-pub use admin_object::*;
\ No newline at end of file
+pub use admin_object::*;
+pub use audit::*;
\ No newline at end of file
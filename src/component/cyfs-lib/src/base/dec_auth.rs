@@ -0,0 +1,249 @@
+use cyfs_base::*;
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+// app-manager在启动app时，通过该环境变量把分配给app的私钥文件路径传递给app
+pub const CYFS_DEC_AUTH_KEY_FILE_ENV: &str = "CYFS_DEC_AUTH_KEY_FILE";
+
+// token的有效期，超出后协议栈拒绝；有效期内同一个nonce只接受一次
+pub const CYFS_DEC_AUTH_TOKEN_VALID_DURATION: u64 = 1000 * 1000 * 60 * 5;
+
+// ws消息没有http方法，统一使用该方法名签名
+pub const CYFS_DEC_AUTH_WS_METHOD: &str = "WS";
+
+// 解析后的dec凭证，协议栈用nonce做防重放
+#[derive(Debug, Clone)]
+pub struct DecAuthToken {
+    pub timestamp: u64,
+    pub nonce: String,
+    pub sign: Signature,
+}
+
+// dec身份凭证：app私钥对(dec_id, method, path, timestamp, nonce)的签名
+// 格式为 {timestamp}.{nonce}.{sign_hex}，绑定到具体请求，截获后无法用于其它请求
+impl DecAuthToken {
+    fn sign_data(
+        dec_id: &ObjectId,
+        method: &str,
+        path: &str,
+        timestamp: u64,
+        nonce: &str,
+    ) -> HashValue {
+        let data = format!("{}\n{}\n{}\n{}\n{}", dec_id, method, path, timestamp, nonce);
+        hash_data(data.as_bytes())
+    }
+
+    // http请求的签名路径，包含query部分
+    pub fn request_path(url: &http_types::Url) -> String {
+        match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_owned(),
+        }
+    }
+
+    // ws注册/反注册消息的签名路径
+    pub fn ws_path(action: &str, category: &str, id: &str) -> String {
+        format!("/{}/{}/{}", action, category, id)
+    }
+
+    pub fn sign(
+        dec_id: &ObjectId,
+        method: &str,
+        path: &str,
+        secret: &PrivateKey,
+    ) -> BuckyResult<String> {
+        let timestamp = bucky_time_now();
+        let nonce = format!("{:016x}", rand::random::<u64>());
+        let hash = Self::sign_data(dec_id, method, path, timestamp, &nonce);
+        let sign = secret.sign(hash.as_slice(), SignatureSource::RefIndex(0))?;
+
+        Ok(format!("{}.{}.{}", timestamp, nonce, sign.to_hex()?))
+    }
+
+    fn parse(dec_id: &ObjectId, token: &str) -> BuckyResult<Self> {
+        let parts: Vec<&str> = token.splitn(3, '.').collect();
+        let ret = if parts.len() == 3 && !parts[1].is_empty() {
+            match (
+                u64::from_str(parts[0]),
+                Signature::clone_from_hex(parts[2], &mut vec![]),
+            ) {
+                (Ok(timestamp), Ok(sign)) => Some(Self {
+                    timestamp,
+                    nonce: parts[1].to_owned(),
+                    sign,
+                }),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        ret.ok_or_else(|| {
+            let msg = format!(
+                "invalid dec auth token format! dec={}, token={}",
+                dec_id, token
+            );
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })
+    }
+
+    pub fn verify(
+        dec_id: &ObjectId,
+        method: &str,
+        path: &str,
+        token: &str,
+        public_key: &PublicKey,
+    ) -> BuckyResult<Self> {
+        let token = Self::parse(dec_id, token)?;
+
+        let now = bucky_time_now();
+        let diff = if now > token.timestamp {
+            now - token.timestamp
+        } else {
+            token.timestamp - now
+        };
+        if diff > CYFS_DEC_AUTH_TOKEN_VALID_DURATION {
+            let msg = format!(
+                "dec auth token expired! dec={}, timestamp={}, now={}",
+                dec_id, token.timestamp, now
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::Expired, msg));
+        }
+
+        let hash = Self::sign_data(dec_id, method, path, token.timestamp, &token.nonce);
+        if !public_key.verify(hash.as_slice(), &token.sign) {
+            let msg = format!(
+                "verify dec auth token failed! dec={}, method={}, path={}",
+                dec_id, method, path
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+        }
+
+        Ok(token)
+    }
+}
+
+// 当前进程内持有的dec私钥，所有本地requestor发出请求时会为对应的dec_id附加凭证
+pub struct DecAuthKeyManager {
+    list: Mutex<HashMap<ObjectId, PrivateKey>>,
+}
+
+static DEC_AUTH_KEY_MANAGER: Lazy<DecAuthKeyManager> = Lazy::new(|| DecAuthKeyManager {
+    list: Mutex::new(HashMap::new()),
+});
+
+impl DecAuthKeyManager {
+    pub fn register(dec_id: &ObjectId, secret: PrivateKey) {
+        info!("register dec auth key: dec={}", dec_id);
+
+        DEC_AUTH_KEY_MANAGER
+            .list
+            .lock()
+            .unwrap()
+            .insert(dec_id.to_owned(), secret);
+    }
+
+    pub fn unregister(dec_id: &ObjectId) {
+        DEC_AUTH_KEY_MANAGER.list.lock().unwrap().remove(dec_id);
+    }
+
+    pub fn contains(dec_id: &ObjectId) -> bool {
+        DEC_AUTH_KEY_MANAGER
+            .list
+            .lock()
+            .unwrap()
+            .contains_key(dec_id)
+    }
+
+    // 从环境变量指定的文件加载app-manager分配的私钥，没有指定则忽略
+    pub fn load_from_env(dec_id: &ObjectId) -> BuckyResult<bool> {
+        let file = match std::env::var(CYFS_DEC_AUTH_KEY_FILE_ENV) {
+            Ok(value) => std::path::PathBuf::from(value),
+            Err(_) => return Ok(false),
+        };
+
+        let (secret, _) = PrivateKey::decode_from_file(&file, &mut vec![]).map_err(|e| {
+            let msg = format!(
+                "load dec auth key from file failed! dec={}, file={}, {}",
+                dec_id,
+                file.display(),
+                e
+            );
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+
+        Self::register(dec_id, secret);
+        Ok(true)
+    }
+
+    // 每次请求都重新签名，凭证只对该请求有效
+    pub fn gen_token(dec_id: &ObjectId, method: &str, path: &str) -> Option<String> {
+        let list = DEC_AUTH_KEY_MANAGER.list.lock().unwrap();
+        let secret = list.get(dec_id)?;
+
+        match DecAuthToken::sign(dec_id, method, path, secret) {
+            Ok(token) => Some(token),
+            Err(e) => {
+                error!("sign dec auth token failed! dec={}, {}", dec_id, e);
+                None
+            }
+        }
+    }
+
+    // 为带有dec_id的请求附加凭证头
+    pub fn sign_request(req: &mut http_types::Request) {
+        let dec_id = match req.header(CYFS_DEC_ID) {
+            Some(value) => match ObjectId::from_str(value.last().as_str()) {
+                Ok(dec_id) => dec_id,
+                Err(_) => return,
+            },
+            None => return,
+        };
+
+        let method = req.method().to_string();
+        let path = DecAuthToken::request_path(req.url());
+        if let Some(token) = Self::gen_token(&dec_id, &method, &path) {
+            req.insert_header(CYFS_DEC_AUTH, token);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dec_auth_token() {
+        let dec_id = ObjectId::default();
+        let secret = PrivateKey::generate_secp256k1().unwrap();
+
+        let token = DecAuthToken::sign(&dec_id, "GET", "/non/object?a=1", &secret).unwrap();
+        let ret = DecAuthToken::verify(&dec_id, "GET", "/non/object?a=1", &token, &secret.public())
+            .unwrap();
+        assert!(!ret.nonce.is_empty());
+
+        // 凭证绑定到请求，换方法或路径都无法通过
+        assert!(
+            DecAuthToken::verify(&dec_id, "PUT", "/non/object?a=1", &token, &secret.public())
+                .is_err()
+        );
+        assert!(
+            DecAuthToken::verify(&dec_id, "GET", "/non/object?a=2", &token, &secret.public())
+                .is_err()
+        );
+
+        let other = PrivateKey::generate_secp256k1().unwrap();
+        assert!(
+            DecAuthToken::verify(&dec_id, "GET", "/non/object?a=1", &token, &other.public())
+                .is_err()
+        );
+        assert!(DecAuthToken::verify(&dec_id, "GET", "/", "invalid", &secret.public()).is_err());
+    }
+}
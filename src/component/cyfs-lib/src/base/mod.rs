@@ -1,7 +1,9 @@
 
 mod config;
+mod dec_auth;
 mod exp_filter;
 mod front;
+mod name_record;
 mod protocol;
 mod range;
 mod request;
@@ -12,8 +14,10 @@ mod tcp_listener;
 mod zone;
 
 pub use config::*;
+pub use dec_auth::*;
 pub use exp_filter::*;
 pub use front::*;
+pub use name_record::*;
 pub use protocol::*;
 pub use range::*;
 pub use request::*;
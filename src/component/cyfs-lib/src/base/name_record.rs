@@ -0,0 +1,171 @@
+use cyfs_base::*;
+use cyfs_core::*;
+
+// zone内本地名字注册表，位于system dec的root_state上: ${CYFS_NAME_REGISTRY_PATH}/${name} -> record_id
+pub const CYFS_NAME_REGISTRY_PATH: &str = "/data/names";
+
+const NAME_RECORD_ID_PREFIX: &str = "cyfs-name-record:";
+
+// 本地名字注册记录，使用Storage对象承载NameInfo，必须由NameInfo.owner对desc签名
+pub struct NameRegistryRecord;
+
+impl NameRegistryRecord {
+    pub fn registry_path(name: &str) -> String {
+        format!("{}/{}", CYFS_NAME_REGISTRY_PATH, name)
+    }
+
+    pub fn check_name(name: &str) -> BuckyResult<()> {
+        if name.is_empty()
+            || name.chars().count() > CYFS_NAME_MAX_LENGTH
+            || name.find('/').is_some()
+        {
+            let msg = format!("invalid name for name registry: {}", name);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        Ok(())
+    }
+
+    pub fn create(name: &str, info: &NameInfo) -> BuckyResult<Storage> {
+        Self::check_name(name)?;
+
+        if info.owner.is_none() {
+            let msg = format!("name registry record should has owner! name={}", name);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        let id = format!("{}{}", NAME_RECORD_ID_PREFIX, name);
+        let record = Storage::create(&id, info.to_vec()?);
+
+        Ok(record)
+    }
+
+    // 使用owner的私钥对记录签名
+    pub async fn sign(record: &mut Storage, secret: &PrivateKey) -> BuckyResult<()> {
+        let signer = RsaCPUObjectSigner::new(secret.public(), secret.clone());
+        sign_and_push_named_object_desc(
+            &signer,
+            record,
+            &SignatureSource::RefIndex(SIGNATURE_SOURCE_REFINDEX_OWNER),
+        )
+        .await
+    }
+
+    pub fn decode(record: &Storage) -> BuckyResult<(String, NameInfo)> {
+        let name = match record.id().strip_prefix(NAME_RECORD_ID_PREFIX) {
+            Some(name) => name.to_owned(),
+            None => {
+                let msg = format!("invalid name registry record id: {}", record.id());
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+            }
+        };
+
+        let info = NameInfo::clone_from_slice(record.value()).map_err(|e| {
+            let msg = format!("decode name info from record failed! name={}, {}", name, e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+
+        Ok((name, info))
+    }
+
+    // 校验记录的名字和owner签名，成功后返回记录里的NameInfo
+    pub async fn verify(
+        name: &str,
+        record: &Storage,
+        owner: &AnyNamedObject,
+    ) -> BuckyResult<NameInfo> {
+        let (record_name, info) = Self::decode(record)?;
+        if record_name != name {
+            let msg = format!(
+                "name registry record's name unmatch! expect={}, got={}",
+                name, record_name
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::Unmatch, msg));
+        }
+
+        let owner_id = owner.calculate_id();
+        if info.owner.as_ref() != Some(&owner_id) {
+            let msg = format!(
+                "name registry record's owner unmatch! name={}, owner={:?}, got={}",
+                name, info.owner, owner_id
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::Unmatch, msg));
+        }
+
+        let public_key = match owner.public_key() {
+            Some(PublicKeyRef::Single(key)) => key.to_owned(),
+            _ => {
+                let msg = format!(
+                    "name registry record's owner has no single public key! name={}, owner={}",
+                    name, owner_id
+                );
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+            }
+        };
+
+        let verifier = RsaCPUObjectVerifier::new(public_key);
+        if let Some(signs) = record.signs().desc_signs() {
+            for sign in signs {
+                if verify_object_desc_sign(&verifier, record, sign).await? {
+                    return Ok(info);
+                }
+            }
+        }
+
+        let msg = format!(
+            "verify name registry record's sign failed! name={}, owner={}",
+            name, owner_id
+        );
+        error!("{}", msg);
+        Err(BuckyError::new(BuckyErrorCode::InvalidSignature, msg))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[async_std::test]
+    async fn test_name_record() {
+        let secret = PrivateKey::generate_rsa(1024).unwrap();
+        let people = People::new(None, vec![], secret.public(), None, None, None).build();
+        let owner_id = people.desc().calculate_id();
+        let owner = AnyNamedObject::Standard(StandardObject::People(people));
+
+        let info = NameInfo {
+            sub_records: Default::default(),
+            record: NameRecord {
+                link: NameLink::ObjectLink(owner_id.clone()),
+                user_data: "".to_owned(),
+            },
+            owner: Some(owner_id.clone()),
+        };
+
+        let mut record = NameRegistryRecord::create("test-name", &info).unwrap();
+
+        // 未签名的记录不能通过校验
+        let ret = NameRegistryRecord::verify("test-name", &record, &owner).await;
+        assert_eq!(ret.unwrap_err().code(), BuckyErrorCode::InvalidSignature);
+
+        NameRegistryRecord::sign(&mut record, &secret).await.unwrap();
+        let buf = record.to_vec().unwrap();
+        let record = Storage::clone_from_slice(&buf).unwrap();
+
+        let ret = NameRegistryRecord::verify("test-name", &record, &owner)
+            .await
+            .unwrap();
+        assert_eq!(ret.owner, Some(owner_id));
+
+        let ret = NameRegistryRecord::verify("other-name", &record, &owner).await;
+        assert_eq!(ret.unwrap_err().code(), BuckyErrorCode::Unmatch);
+
+        assert!(NameRegistryRecord::create("a/b", &info).is_err());
+    }
+}
@@ -370,4 +370,182 @@ impl RequestorRangeHelper {
 
         resp
     }
+
+    // https://developer.mozilla.org/en-US/docs/Web/HTTP/Range_requests#multipart_ranges
+    // body里面是按顺序拼接的各个range的数据，转换为multipart/byteranges格式
+    pub fn encode_multipart_byteranges(
+        resp: &mut http_types::Response,
+        ranges: &Vec<Range<u64>>,
+        size: u64,
+    ) {
+        assert!(ranges.len() > 1);
+
+        let boundary = format!("CYFS-BYTERANGES-{:016x}", rand::random::<u64>());
+        let content_type = resp.content_type().map(|v| v.to_string());
+
+        let data = resp.take_body().into_reader();
+        let reader = MultipartByteRangesReader::new(
+            ranges,
+            size,
+            &boundary,
+            content_type.as_deref(),
+            Box::new(data),
+        );
+        let len = reader.len();
+
+        resp.remove_header(http_types::headers::CONTENT_RANGE);
+        resp.insert_header(
+            http_types::headers::CONTENT_TYPE,
+            format!("multipart/byteranges; boundary={}", boundary),
+        );
+
+        let reader = async_std::io::BufReader::new(reader);
+        resp.set_body(http_types::Body::from_reader(reader, Some(len as usize)));
+    }
+}
+
+enum MultipartByteRangesSegment {
+    Bytes(Vec<u8>),
+    Data(u64),
+}
+
+pub struct MultipartByteRangesReader {
+    segments: std::collections::VecDeque<MultipartByteRangesSegment>,
+    pos: usize,
+    len: u64,
+    data: Box<dyn async_std::io::Read + Unpin + Send + Sync + 'static>,
+}
+
+impl MultipartByteRangesReader {
+    pub fn new(
+        ranges: &Vec<Range<u64>>,
+        size: u64,
+        boundary: &str,
+        content_type: Option<&str>,
+        data: Box<dyn async_std::io::Read + Unpin + Send + Sync + 'static>,
+    ) -> Self {
+        let mut segments = std::collections::VecDeque::new();
+        for (i, range) in ranges.iter().enumerate() {
+            let mut header = String::new();
+            if i > 0 {
+                header.push_str("\r\n");
+            }
+            header.push_str(&format!("--{}\r\n", boundary));
+            if let Some(content_type) = content_type {
+                header.push_str(&format!("Content-Type: {}\r\n", content_type));
+            }
+            header.push_str(&format!(
+                "Content-Range: bytes {}-{}/{}\r\n\r\n",
+                range.start,
+                range.end - 1,
+                size
+            ));
+
+            segments.push_back(MultipartByteRangesSegment::Bytes(header.into_bytes()));
+            segments.push_back(MultipartByteRangesSegment::Data(range.end - range.start));
+        }
+        segments.push_back(MultipartByteRangesSegment::Bytes(
+            format!("\r\n--{}--\r\n", boundary).into_bytes(),
+        ));
+
+        let len = segments
+            .iter()
+            .map(|seg| match seg {
+                MultipartByteRangesSegment::Bytes(v) => v.len() as u64,
+                MultipartByteRangesSegment::Data(len) => *len,
+            })
+            .sum();
+
+        Self {
+            segments,
+            pos: 0,
+            len,
+            data,
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+impl async_std::io::Read for MultipartByteRangesReader {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            match this.segments.front_mut() {
+                None => return std::task::Poll::Ready(Ok(0)),
+                Some(MultipartByteRangesSegment::Bytes(bytes)) => {
+                    if this.pos >= bytes.len() {
+                        this.segments.pop_front();
+                        this.pos = 0;
+                        continue;
+                    }
+
+                    let n = std::cmp::min(buf.len(), bytes.len() - this.pos);
+                    buf[..n].copy_from_slice(&bytes[this.pos..this.pos + n]);
+                    this.pos += n;
+                    return std::task::Poll::Ready(Ok(n));
+                }
+                Some(MultipartByteRangesSegment::Data(remain)) => {
+                    if *remain == 0 {
+                        this.segments.pop_front();
+                        continue;
+                    }
+
+                    let max = std::cmp::min(buf.len() as u64, *remain) as usize;
+                    let n = match std::pin::Pin::new(&mut this.data).poll_read(cx, &mut buf[..max]) {
+                        std::task::Poll::Ready(Ok(n)) => n,
+                        std::task::Poll::Ready(Err(e)) => return std::task::Poll::Ready(Err(e)),
+                        std::task::Poll::Pending => return std::task::Poll::Pending,
+                    };
+                    if n == 0 {
+                        let msg = format!("range data ended early! remain={}", remain);
+                        error!("{}", msg);
+                        return std::task::Poll::Ready(Err(std::io::Error::new(
+                            std::io::ErrorKind::UnexpectedEof,
+                            msg,
+                        )));
+                    }
+
+                    *remain -= n as u64;
+                    return std::task::Poll::Ready(Ok(n));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_std::io::ReadExt;
+
+    #[async_std::test]
+    async fn test_multipart_byteranges() {
+        // 数据是各个range按顺序拼接的结果
+        let data = b"0123ab".to_vec();
+        let ranges = vec![0..4, 10..12];
+        let mut reader = MultipartByteRangesReader::new(
+            &ranges,
+            100,
+            "XX",
+            Some("text/plain"),
+            Box::new(async_std::io::Cursor::new(data)),
+        );
+        let len = reader.len();
+
+        let mut body = String::new();
+        reader.read_to_string(&mut body).await.unwrap();
+        assert_eq!(body.len() as u64, len);
+
+        let expect = "--XX\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-3/100\r\n\r\n0123\r\n\
+            --XX\r\nContent-Type: text/plain\r\nContent-Range: bytes 10-11/100\r\n\r\nab\r\n\
+            --XX--\r\n";
+        assert_eq!(body, expect);
+    }
 }
@@ -44,6 +44,10 @@ impl RouterEventItem {
             category: self.category.clone(),
             id: self.id.clone(),
             dec_id: self.dec_id.clone(),
+            dec_auth: self.dec_id.as_ref().and_then(|id| {
+                let path = DecAuthToken::ws_path("add_event", &self.category.to_string(), &self.id);
+                DecAuthKeyManager::gen_token(id, CYFS_DEC_AUTH_WS_METHOD, &path)
+            }),
             index: self.index,
             routine: requestor.sid().to_string(),
         };
@@ -104,6 +108,11 @@ impl RouterEventUnregisterItem {
             category: self.category.clone(),
             id: self.id.clone(),
             dec_id: self.dec_id.clone(),
+            dec_auth: self.dec_id.as_ref().and_then(|id| {
+                let path =
+                    DecAuthToken::ws_path("remove_event", &self.category.to_string(), &self.id);
+                DecAuthKeyManager::gen_token(id, CYFS_DEC_AUTH_WS_METHOD, &path)
+            }),
         };
 
         let msg = req.encode_string();
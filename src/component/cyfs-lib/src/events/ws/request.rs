@@ -11,6 +11,9 @@ pub struct RouterWSAddEventParam {
     pub category: RouterEventCategory,
     pub id: String,
     pub dec_id: Option<ObjectId>,
+
+    // dec_id对应的身份凭证，协议栈的鉴权接口上需要校验
+    pub dec_auth: Option<String>,
     pub index: i32,
     pub routine: String,
 }
@@ -19,6 +22,9 @@ pub struct RouterWSRemoveEventParam {
     pub category: RouterEventCategory,
     pub id: String,
     pub dec_id: Option<ObjectId>,
+
+    // dec_id对应的身份凭证，协议栈的鉴权接口上需要校验
+    pub dec_auth: Option<String>,
 }
 
 impl JsonCodec<Self> for RouterWSAddEventParam {
@@ -28,6 +34,7 @@ impl JsonCodec<Self> for RouterWSAddEventParam {
         JsonCodecHelper::encode_string_field(&mut obj, "category", &self.category);
         JsonCodecHelper::encode_string_field(&mut obj, "id", &self.id);
         JsonCodecHelper::encode_option_string_field(&mut obj, "dec_id", self.dec_id.as_ref());
+        JsonCodecHelper::encode_option_string_field(&mut obj, "dec_auth", self.dec_auth.as_ref());
         JsonCodecHelper::encode_string_field(&mut obj, "index", &self.index);
         JsonCodecHelper::encode_string_field(&mut obj, "routine", &self.routine);
 
@@ -39,6 +46,7 @@ impl JsonCodec<Self> for RouterWSAddEventParam {
             category: JsonCodecHelper::decode_string_field(req_obj, "category")?,
             id: JsonCodecHelper::decode_string_field(req_obj, "id")?,
             dec_id: JsonCodecHelper::decode_option_string_field(req_obj, "dec_id")?,
+            dec_auth: JsonCodecHelper::decode_option_string_field(req_obj, "dec_auth")?,
             index: JsonCodecHelper::decode_string_field(req_obj, "index")?,
             routine: JsonCodecHelper::decode_string_field(req_obj, "routine")?,
         })
@@ -52,6 +60,7 @@ impl JsonCodec<Self> for RouterWSRemoveEventParam {
         JsonCodecHelper::encode_string_field(&mut obj, "category", &self.category);
         JsonCodecHelper::encode_string_field(&mut obj, "id", &self.id);
         JsonCodecHelper::encode_option_string_field(&mut obj, "dec_id", self.dec_id.as_ref());
+        JsonCodecHelper::encode_option_string_field(&mut obj, "dec_auth", self.dec_auth.as_ref());
 
         obj
     }
@@ -61,6 +70,7 @@ impl JsonCodec<Self> for RouterWSRemoveEventParam {
             category: JsonCodecHelper::decode_string_field(req_obj, "category")?,
             id: JsonCodecHelper::decode_string_field(req_obj, "id")?,
             dec_id: JsonCodecHelper::decode_option_string_field(req_obj, "dec_id")?,
+            dec_auth: JsonCodecHelper::decode_option_string_field(req_obj, "dec_auth")?,
         })
     }
 }
@@ -46,6 +46,214 @@ impl FromStr for NONDataType {
     }
 }
 
+// objectmap范围投影一次最多返回的元素个数，保证结果可以用simple模式的objectmap承载
+pub const NON_OBJECT_PROJECTION_RANGE_MAX_COUNT: u32 = 128;
+
+// get_object的对象投影，在持有对象的协议栈上计算，只返回对象的一部分以减少传输的数据量
+// 和inner_path可以同时使用，此时投影作用在inner_path指向的对象上
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NONObjectProjection {
+    // 只返回desc部分和desc签名，结果是NONObjectPart，不是一个完整的对象
+    Desc,
+
+    // 只返回body部分和body签名，结果是NONObjectPart，不是一个完整的对象
+    // 适用于已经缓存了desc只需要更新body的情况
+    Body,
+
+    // 只返回objectmap里指定范围的元素，结果是包含这些元素的一个新的objectmap
+    ObjectMapRange { start: u32, count: u32 },
+
+    // 只返回map类型的objectmap里key在[begin, end)范围内的元素，结果是包含这些元素的一个新的objectmap
+    // end为空表示没有上界；结果按key排序，超出单次上限时只返回最小的部分，调用方可以从最后一个key之后继续获取
+    ObjectMapKeyRange { begin: String, end: String },
+}
+
+impl NONObjectProjection {
+    fn encode_key(key: &str) -> String {
+        percent_encoding::utf8_percent_encode(key, percent_encoding::NON_ALPHANUMERIC).to_string()
+    }
+
+    fn decode_key(value: &str) -> Option<String> {
+        percent_encoding::percent_decode_str(value)
+            .decode_utf8()
+            .ok()
+            .map(|v| v.to_string())
+    }
+}
+
+impl ToString for NONObjectProjection {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Desc => "desc".to_owned(),
+            Self::Body => "body".to_owned(),
+            Self::ObjectMapRange { start, count } => {
+                format!("objectmap-range:{}:{}", start, count)
+            }
+            // key里可能包含分隔符，需要编码
+            Self::ObjectMapKeyRange { begin, end } => {
+                format!(
+                    "objectmap-key-range:{}:{}",
+                    Self::encode_key(begin),
+                    Self::encode_key(end)
+                )
+            }
+        }
+    }
+}
+
+impl FromStr for NONObjectProjection {
+    type Err = BuckyError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "desc" => return Ok(Self::Desc),
+            "body" => return Ok(Self::Body),
+            _ => {}
+        }
+
+        if let Some(range) = value.strip_prefix("objectmap-key-range:") {
+            let parts: Vec<&str> = range.split(':').collect();
+            if parts.len() == 2 {
+                if let (Some(begin), Some(end)) =
+                    (Self::decode_key(parts[0]), Self::decode_key(parts[1]))
+                {
+                    return Ok(Self::ObjectMapKeyRange { begin, end });
+                }
+            }
+        }
+
+        if let Some(range) = value.strip_prefix("objectmap-range:") {
+            let parts: Vec<&str> = range.split(':').collect();
+            if parts.len() == 2 {
+                if let (Ok(start), Ok(count)) = (parts[0].parse(), parts[1].parse()) {
+                    return Ok(Self::ObjectMapRange { start, count });
+                }
+            }
+        }
+
+        let msg = format!("unknown non object projection: {}", value);
+        error!("{}", msg);
+
+        Err(BuckyError::new(BuckyErrorCode::InvalidData, msg))
+    }
+}
+
+// desc/body投影的结果，只是对象的一部分，没有自己的object_id
+// source_object_id是投影来源的对象，调用方需要结合自己持有的另一部分来校验
+#[derive(Clone, Debug, RawEncode, RawDecode)]
+pub struct NONObjectPart {
+    pub source_object_id: ObjectId,
+
+    // 来源对象的类型
+    pub obj_type: u16,
+
+    // desc投影时为desc的编码
+    pub desc: Option<Vec<u8>>,
+    pub desc_signs: Vec<Signature>,
+
+    // body投影时为body的编码
+    pub body: Option<Vec<u8>>,
+    pub body_signs: Vec<Signature>,
+}
+
+impl NONObjectPart {
+    pub fn new_desc(object_id: &ObjectId, object: &AnyNamedObject) -> BuckyResult<Self> {
+        let desc_signs = object
+            .signs()
+            .and_then(|signs| signs.desc_signs())
+            .map(|list| list.clone())
+            .unwrap_or_default();
+
+        Ok(Self {
+            source_object_id: object_id.to_owned(),
+            obj_type: object.obj_type(),
+            desc: Some(object.desc_raw()?),
+            desc_signs,
+            body: None,
+            body_signs: vec![],
+        })
+    }
+
+    pub fn new_body(object_id: &ObjectId, object: &AnyNamedObject) -> BuckyResult<Self> {
+        let body = object.body_raw()?;
+        if body.is_none() {
+            let msg = format!("body projection but object has no body! object={}", object_id);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotFound, msg));
+        }
+
+        let body_signs = object
+            .signs()
+            .and_then(|signs| signs.body_signs())
+            .map(|list| list.clone())
+            .unwrap_or_default();
+
+        Ok(Self {
+            source_object_id: object_id.to_owned(),
+            obj_type: object.obj_type(),
+            desc: None,
+            desc_signs: vec![],
+            body,
+            body_signs,
+        })
+    }
+}
+
+impl fmt::Display for NONObjectPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "source_object_id: {}, obj_type: {}, desc: {:?}, body: {:?}",
+            self.source_object_id,
+            self.obj_type,
+            self.desc.as_ref().map(|v| v.len()),
+            self.body.as_ref().map(|v| v.len()),
+        )
+    }
+}
+
+impl JsonCodec<NONObjectPart> for NONObjectPart {
+    fn encode_json(&self) -> Map<String, Value> {
+        let mut obj = Map::new();
+
+        obj.insert(
+            "source_object_id".to_owned(),
+            Value::String(self.source_object_id.to_string()),
+        );
+
+        // 签名等字段使用raw编码
+        let part_raw = self.to_vec().unwrap();
+        obj.insert("part_raw".to_owned(), Value::String(hex::encode(&part_raw)));
+
+        obj
+    }
+
+    fn decode_json(obj: &Map<String, Value>) -> BuckyResult<NONObjectPart> {
+        let source_object_id: ObjectId =
+            JsonCodecHelper::decode_string_field(obj, "source_object_id")?;
+
+        let part_raw: String = JsonCodecHelper::decode_string_field(obj, "part_raw")?;
+        let part_raw = hex::decode(&part_raw).map_err(|e| {
+            let msg = format!("invalid part_raw hex buffer! {}", e);
+            error!("{}", msg);
+
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+
+        let part = NONObjectPart::clone_from_slice(&part_raw)?;
+        if part.source_object_id != source_object_id {
+            let msg = format!(
+                "object part source_object_id unmatch! expect={}, got={}",
+                source_object_id, part.source_object_id
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::Unmatch, msg));
+        }
+
+        Ok(part)
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum NONAction {
     // non
@@ -638,4 +846,37 @@ impl ObjectSelectorDataProvider for NONObjectInfo {
     fn insert_time(&self) -> &u64 {
         &0
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection_codec() {
+        let list = vec![
+            NONObjectProjection::Desc,
+            NONObjectProjection::Body,
+            NONObjectProjection::ObjectMapRange { start: 10, count: 20 },
+            NONObjectProjection::ObjectMapKeyRange {
+                begin: "a:b/c%d".to_owned(),
+                end: "测试".to_owned(),
+            },
+            NONObjectProjection::ObjectMapKeyRange {
+                begin: "".to_owned(),
+                end: "".to_owned(),
+            },
+        ];
+
+        for projection in list {
+            let value = projection.to_string();
+            assert!(value.is_ascii());
+            let got = NONObjectProjection::from_str(&value).unwrap();
+            assert_eq!(got, projection);
+        }
+
+        assert!(NONObjectProjection::from_str("objectmap-range:1").is_err());
+        assert!(NONObjectProjection::from_str("objectmap-key-range:a:b:c").is_err());
+        assert!(NONObjectProjection::from_str("objectmap-key-range:%ff:b").is_err());
+        assert!(NONObjectProjection::from_str("unknown").is_err());
+    }
+}
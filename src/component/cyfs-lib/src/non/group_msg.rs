@@ -0,0 +1,144 @@
+use super::output_request::*;
+use super::processor::*;
+use cyfs_base::*;
+use cyfs_core::*;
+
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GroupMsgDeliveryState {
+    Pending,
+    Delivered,
+    Failed(BuckyErrorCode),
+}
+
+// 群消息(或者成员变化)在每个成员上的投递状态
+#[derive(Clone, Debug)]
+pub struct GroupMsgDelivery {
+    pub object_id: ObjectId,
+    pub states: HashMap<ObjectId, GroupMsgDeliveryState>,
+}
+
+impl GroupMsgDelivery {
+    pub fn new(object_id: ObjectId, members: &[ObjectId], exclude: Option<&ObjectId>) -> Self {
+        let states = members
+            .iter()
+            .filter(|id| Some(*id) != exclude)
+            .map(|id| (id.to_owned(), GroupMsgDeliveryState::Pending))
+            .collect();
+
+        Self { object_id, states }
+    }
+
+    // 还未成功投递的成员，包括失败的
+    pub fn pending_list(&self) -> Vec<ObjectId> {
+        self.states
+            .iter()
+            .filter(|(_, state)| **state != GroupMsgDeliveryState::Delivered)
+            .map(|(id, _)| id.to_owned())
+            .collect()
+    }
+
+    pub fn delivered_count(&self) -> usize {
+        self.states
+            .values()
+            .filter(|state| **state == GroupMsgDeliveryState::Delivered)
+            .count()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.delivered_count() == self.states.len()
+    }
+}
+
+// 通过NON post_object把群消息逐个投递到成员所在的zone
+pub struct GroupMsgFanout {
+    non: NONOutputProcessorRef,
+
+    // 接收方注册的post_object handler所在的路径
+    req_path: Option<String>,
+}
+
+impl GroupMsgFanout {
+    pub fn new(non: NONOutputProcessorRef, req_path: Option<String>) -> Self {
+        Self { non, req_path }
+    }
+
+    // sender为消息owner对应的people对象，消息必须带有sender的desc签名
+    pub async fn send_msg(
+        &self,
+        group: &Group,
+        msg: &GroupMsg,
+        sender: &People,
+    ) -> BuckyResult<GroupMsgDelivery> {
+        group.check_msg(msg, sender)?;
+
+        let mut delivery = GroupMsgDelivery::new(
+            msg.desc().calculate_id(),
+            group.members(),
+            msg.desc().owner().as_ref(),
+        );
+        self.post(msg.to_vec()?, &mut delivery).await;
+
+        Ok(delivery)
+    }
+
+    // 成员变化需要通知到变化前后的所有成员，被移除的成员也能知道自己被移出
+    pub async fn send_change(
+        &self,
+        group: &Group,
+        change: &GroupMemberChange,
+    ) -> BuckyResult<GroupMsgDelivery> {
+        let mut members = group.members().clone();
+        for id in change.members() {
+            if !members.contains(id) {
+                members.push(id.to_owned());
+            }
+        }
+
+        let mut delivery = GroupMsgDelivery::new(
+            change.desc().calculate_id(),
+            &members,
+            change.desc().author().as_ref(),
+        );
+        self.post(change.to_vec()?, &mut delivery).await;
+
+        Ok(delivery)
+    }
+
+    // 对未成功投递的成员重试
+    pub async fn retry(&self, object_raw: Vec<u8>, delivery: &mut GroupMsgDelivery) {
+        self.post(object_raw, delivery).await
+    }
+
+    async fn post(&self, object_raw: Vec<u8>, delivery: &mut GroupMsgDelivery) {
+        for member in delivery.pending_list() {
+            let mut req = NONPostObjectOutputRequest::new_router(
+                Some(member.clone()),
+                delivery.object_id.clone(),
+                object_raw.clone(),
+            );
+            req.common.req_path = self.req_path.clone();
+
+            let state = match self.non.post_object(req).await {
+                Ok(_) => GroupMsgDeliveryState::Delivered,
+                Err(e) => {
+                    warn!(
+                        "post group object to member failed! object={}, member={}, {}",
+                        delivery.object_id, member, e
+                    );
+                    GroupMsgDeliveryState::Failed(e.code())
+                }
+            };
+
+            delivery.states.insert(member, state);
+        }
+
+        info!(
+            "post group object complete! object={}, delivered={}/{}",
+            delivery.object_id,
+            delivery.delivered_count(),
+            delivery.states.len()
+        );
+    }
+}
@@ -50,6 +50,9 @@ pub struct NONGetObjectInputRequest {
 
     // object_id在dir情况下适用
     pub inner_path: Option<String>,
+
+    // 只获取对象的一部分
+    pub projection: Option<NONObjectProjection>,
 }
 
 impl fmt::Display for NONGetObjectInputRequest {
@@ -57,7 +60,13 @@ impl fmt::Display for NONGetObjectInputRequest {
         write!(f, "common: {}", self.common)?;
         write!(f, ", object_id: {}", self.object_id)?;
 
-        write!(f, ", inner_path: {:?}", self.inner_path)
+        write!(f, ", inner_path: {:?}", self.inner_path)?;
+
+        if let Some(projection) = &self.projection {
+            write!(f, ", projection: {}", projection.to_string())?;
+        }
+
+        Ok(())
     }
 }
 
@@ -79,6 +88,9 @@ pub struct NONGetObjectInputResponse {
 
     pub object: NONObjectInfo,
 
+    // desc/body投影时只返回对象的一部分，此时object为空
+    pub part: Option<NONObjectPart>,
+
     // 对file有效
     pub attr: Option<Attributes>,
 }
@@ -98,10 +110,18 @@ impl NONGetObjectInputResponse {
             object,
             object_expires_time: None,
             object_update_time: None,
+            part: None,
             attr: None,
         }
     }
 
+    pub fn new_with_part(part: NONObjectPart) -> Self {
+        let object = NONObjectInfo::new(ObjectId::default(), vec![], None);
+        let mut ret = Self::new_with_object(object);
+        ret.part = Some(part);
+        ret
+    }
+
     pub fn init_times(&mut self) -> BuckyResult<()> {
         let t = self.object.get_update_time()?;
         if t > 0 {
@@ -120,6 +140,10 @@ impl fmt::Display for NONGetObjectInputResponse {
         write!(f, ", object_update_time: {:?}", self.object_update_time)?;
         write!(f, ", object_expires_time: {:?}", self.object_expires_time)?;
 
+        if let Some(part) = &self.part {
+            write!(f, ", part: {}", part)?;
+        }
+
         if let Some(attr) = &self.attr {
             write!(f, ", attr: {:?}", attr)?;
         }
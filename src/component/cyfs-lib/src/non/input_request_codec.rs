@@ -39,6 +39,11 @@ impl JsonCodec<NONGetObjectInputRequest> for NONGetObjectInputRequest {
             "inner_path",
             self.inner_path.as_ref(),
         );
+        JsonCodecHelper::encode_option_string_field(
+            &mut obj,
+            "projection",
+            self.projection.as_ref(),
+        );
 
         obj
     }
@@ -48,6 +53,7 @@ impl JsonCodec<NONGetObjectInputRequest> for NONGetObjectInputRequest {
             common: JsonCodecHelper::decode_field(obj, "common")?,
             object_id: JsonCodecHelper::decode_string_field(obj, "object_id")?,
             inner_path: JsonCodecHelper::decode_option_string_field(obj, "inner_path")?,
+            projection: JsonCodecHelper::decode_option_string_field(obj, "projection")?,
         })
     }
 }
@@ -56,7 +62,11 @@ impl JsonCodec<NONGetObjectInputResponse> for NONGetObjectInputResponse {
     fn encode_json(&self) -> Map<String, Value> {
         let mut obj = Map::new();
 
-        JsonCodecHelper::encode_field(&mut obj, "object", &self.object);
+        // 投影结果只有对象的一部分，object为空
+        match &self.part {
+            Some(part) => JsonCodecHelper::encode_field(&mut obj, "part", part),
+            None => JsonCodecHelper::encode_field(&mut obj, "object", &self.object),
+        }
         JsonCodecHelper::encode_option_string_field(
             &mut obj,
             "object_expires_time",
@@ -80,8 +90,15 @@ impl JsonCodec<NONGetObjectInputResponse> for NONGetObjectInputResponse {
     fn decode_json(obj: &Map<String, Value>) -> BuckyResult<NONGetObjectInputResponse> {
         let attr = JsonCodecHelper::decode_option_int_field(obj, "attr")?;
 
+        let part: Option<NONObjectPart> = JsonCodecHelper::decode_option_field(obj, "part")?;
+        let object = match part {
+            Some(_) => NONObjectInfo::new(ObjectId::default(), vec![], None),
+            None => JsonCodecHelper::decode_field(obj, "object")?,
+        };
+
         Ok(Self {
-            object: JsonCodecHelper::decode_field(obj, "object")?,
+            object,
+            part,
             object_expires_time: JsonCodecHelper::decode_option_string_field(
                 obj,
                 "object_expires_time",
@@ -1,4 +1,5 @@
 mod def;
+mod group_msg;
 mod input_request;
 mod input_request_codec;
 mod output_request;
@@ -7,6 +8,7 @@ mod request;
 mod requestor;
 
 pub use def::*;
+pub use group_msg::*;
 pub use input_request::*;
 pub use input_request_codec::*;
 pub use output_request::*;
@@ -181,6 +181,9 @@ pub struct NONGetObjectOutputRequest {
 
     // inner_path在dir情况下适用
     pub inner_path: Option<String>,
+
+    // 只获取对象的一部分，由持有对象的协议栈计算
+    pub projection: Option<NONObjectProjection>,
 }
 
 impl NONGetObjectOutputRequest {
@@ -189,6 +192,7 @@ impl NONGetObjectOutputRequest {
             common: NONOutputRequestCommon::new(level),
             object_id,
             inner_path,
+            projection: None,
         }
     }
 
@@ -249,7 +253,13 @@ impl fmt::Display for NONGetObjectOutputRequest {
         write!(f, "common: {}", self.common)?;
         write!(f, ", object_id: {}", self.object_id)?;
 
-        write!(f, ", inner_path: {:?}", self.inner_path)
+        write!(f, ", inner_path: {:?}", self.inner_path)?;
+
+        if let Some(projection) = &self.projection {
+            write!(f, ", projection: {}", projection.to_string())?;
+        }
+
+        Ok(())
     }
 }
 
@@ -260,6 +270,9 @@ pub struct NONGetObjectOutputResponse {
 
     pub object: NONObjectInfo,
 
+    // desc/body投影时只返回对象的一部分，此时object为空
+    pub part: Option<NONObjectPart>,
+
     // 对file有效
     pub attr: Option<Attributes>,
 }
@@ -270,6 +283,10 @@ impl fmt::Display for NONGetObjectOutputResponse {
         write!(f, ", object_update_time: {:?}", self.object_update_time)?;
         write!(f, ", object_expires_time: {:?}", self.object_expires_time)?;
 
+        if let Some(part) = &self.part {
+            write!(f, ", part: {}", part)?;
+        }
+
         if let Some(attr) = &self.attr {
             write!(f, ", attr: {:?}", attr)?;
         }
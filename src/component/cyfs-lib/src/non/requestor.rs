@@ -94,13 +94,54 @@ impl NONRequestorHelper {
         }
     }
 
+    pub fn encode_object_part<T>(req: &mut T, part: &NONObjectPart) -> BuckyResult<()>
+    where
+        T: BodyOp + HeaderOp,
+    {
+        req.insert_header(cyfs_base::CYFS_OBJECT_PART, part.source_object_id.to_string());
+
+        req.set_body(part.to_vec()?);
+        req.set_content_type(CYFS_OBJECT_MIME.clone());
+
+        Ok(())
+    }
+
+    pub async fn decode_object_part<T>(req: &mut T) -> BuckyResult<Option<NONObjectPart>>
+    where
+        T: BodyOp + HeaderOp,
+    {
+        let ret: Option<ObjectId> =
+            RequestorHelper::decode_optional_header(req, cyfs_base::CYFS_OBJECT_PART)?;
+        if ret.is_none() {
+            return Ok(None);
+        }
+
+        let source_object_id = ret.unwrap();
+        let part: NONObjectPart = RequestorHelper::decode_raw_object_body(req).await?;
+        if part.source_object_id != source_object_id {
+            let msg = format!(
+                "object part source_object_id unmatch! header={}, got={}",
+                source_object_id, part.source_object_id
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::Unmatch, msg));
+        }
+
+        Ok(Some(part))
+    }
+
     pub async fn decode_get_object_response<T>(
         resp: &mut T,
     ) -> BuckyResult<NONGetObjectOutputResponse>
     where
         T: BodyOp + HeaderOp,
     {
-        let object = Self::decode_object_info(resp).await?;
+        // desc/body投影的结果没有object-id，只有对象的一部分
+        let part = Self::decode_object_part(resp).await?;
+        let object = match part {
+            Some(_) => NONObjectInfo::new(ObjectId::default(), vec![], None),
+            None => Self::decode_object_info(resp).await?,
+        };
         let attr: Option<u32> =
             RequestorHelper::decode_optional_header(resp, cyfs_base::CYFS_ATTRIBUTES)?;
         let attr = attr.map(|v| Attributes::new(v));
@@ -112,6 +153,7 @@ impl NONRequestorHelper {
 
         let ret = NONGetObjectOutputResponse {
             object,
+            part,
             object_expires_time,
             object_update_time,
             attr,
@@ -278,6 +320,10 @@ impl NONRequestor {
             req.inner_path.as_deref(),
         );
 
+        if let Some(projection) = &req.projection {
+            http_req.insert_header(cyfs_base::CYFS_PROJECTION, projection.to_string());
+        }
+
         http_req
     }
 
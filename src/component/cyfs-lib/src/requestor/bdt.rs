@@ -1,4 +1,5 @@
 use super::requestor::*;
+use crate::base::CYFS_CURRENT_API_EDITION;
 use cyfs_base::*;
 use cyfs_bdt::*;
 
@@ -117,6 +118,12 @@ impl BdtHttpRequestor {
 
 #[async_trait::async_trait]
 impl HttpRequestor for BdtHttpRequestor {
+    // 跨设备的请求不需要携带本地dec凭证，避免凭证泄露给远端设备
+    fn add_default_headers(&self, mut req: Request) -> Request {
+        req.insert_header(CYFS_API_EDITION, CYFS_CURRENT_API_EDITION.to_string());
+        req
+    }
+
     async fn request_ext(
         &self,
         req: &mut Option<Request>,
@@ -1,4 +1,4 @@
-use crate::base::CYFS_CURRENT_API_EDITION;
+use crate::base::{DecAuthKeyManager, CYFS_CURRENT_API_EDITION};
 use cyfs_base::*;
 
 use async_std::net::SocketAddr;
@@ -20,6 +20,7 @@ pub trait HttpRequestor: Send + Sync {
 
     fn add_default_headers(&self, mut req: Request) -> Request {
         req.insert_header(CYFS_API_EDITION, CYFS_CURRENT_API_EDITION.to_string());
+        DecAuthKeyManager::sign_request(&mut req);
         req
     }
 
@@ -69,6 +69,11 @@ impl RouterHandlerItem {
             category: self.category.clone(),
             id: self.id.clone(),
             dec_id: self.dec_id.clone(),
+            dec_auth: self.dec_id.as_ref().and_then(|id| {
+                let category = format!("{}/{}", self.chain, self.category);
+                let path = DecAuthToken::ws_path("add_handler", &category, &self.id);
+                DecAuthKeyManager::gen_token(id, CYFS_DEC_AUTH_WS_METHOD, &path)
+            }),
             param,
         };
 
@@ -132,6 +137,11 @@ impl RouterHandlerUnregisterItem {
             category: self.category.clone(),
             id: self.id.clone(),
             dec_id: self.dec_id.clone(),
+            dec_auth: self.dec_id.as_ref().and_then(|id| {
+                let category = format!("{}/{}", self.chain, self.category);
+                let path = DecAuthToken::ws_path("remove_handler", &category, &self.id);
+                DecAuthKeyManager::gen_token(id, CYFS_DEC_AUTH_WS_METHOD, &path)
+            }),
         };
 
         let msg = req.encode_string();
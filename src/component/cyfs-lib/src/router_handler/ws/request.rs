@@ -12,6 +12,9 @@ pub struct RouterWSAddHandlerParam {
     pub id: String,
     pub dec_id: Option<ObjectId>,
 
+    // dec_id对应的身份凭证，协议栈的鉴权接口上需要校验
+    pub dec_auth: Option<String>,
+
     pub param: RouterAddHandlerParam,
 }
 
@@ -21,6 +24,9 @@ pub struct RouterWSRemoveHandlerParam {
 
     pub id: String,
     pub dec_id: Option<ObjectId>,
+
+    // dec_id对应的身份凭证，协议栈的鉴权接口上需要校验
+    pub dec_auth: Option<String>,
 }
 
 impl JsonCodec<RouterWSAddHandlerParam> for RouterWSAddHandlerParam {
@@ -31,6 +37,7 @@ impl JsonCodec<RouterWSAddHandlerParam> for RouterWSAddHandlerParam {
         JsonCodecHelper::encode_string_field(&mut obj, "category", &self.category);
         JsonCodecHelper::encode_string_field(&mut obj, "id", &self.id);
         JsonCodecHelper::encode_option_string_field(&mut obj, "dec_id", self.dec_id.as_ref());
+        JsonCodecHelper::encode_option_string_field(&mut obj, "dec_auth", self.dec_auth.as_ref());
         JsonCodecHelper::encode_field(&mut obj, "param", &self.param);
 
         obj
@@ -42,6 +49,7 @@ impl JsonCodec<RouterWSAddHandlerParam> for RouterWSAddHandlerParam {
             category: JsonCodecHelper::decode_string_field(req_obj, "category")?,
             id: JsonCodecHelper::decode_string_field(req_obj, "id")?,
             dec_id: JsonCodecHelper::decode_option_string_field(req_obj, "dec_id")?,
+            dec_auth: JsonCodecHelper::decode_option_string_field(req_obj, "dec_auth")?,
             param: JsonCodecHelper::decode_field(req_obj, "param")?,
         })
     }
@@ -54,6 +62,7 @@ impl JsonCodec<RouterWSRemoveHandlerParam> for RouterWSRemoveHandlerParam {
         JsonCodecHelper::encode_string_field(&mut obj, "chain", &self.chain);
         JsonCodecHelper::encode_string_field(&mut obj, "category", &self.category);
         JsonCodecHelper::encode_option_string_field(&mut obj, "dec_id", self.dec_id.as_ref());
+        JsonCodecHelper::encode_option_string_field(&mut obj, "dec_auth", self.dec_auth.as_ref());
         JsonCodecHelper::encode_string_field(&mut obj, "id", &self.id);
 
         obj
@@ -65,6 +74,7 @@ impl JsonCodec<RouterWSRemoveHandlerParam> for RouterWSRemoveHandlerParam {
             category: JsonCodecHelper::decode_string_field(req_obj, "category")?,
             id: JsonCodecHelper::decode_string_field(req_obj, "id")?,
             dec_id: JsonCodecHelper::decode_option_string_field(req_obj, "dec_id")?,
+            dec_auth: JsonCodecHelper::decode_option_string_field(req_obj, "dec_auth")?,
         })
     }
 }
@@ -1,4 +1,5 @@
 use super::uni_stack::*;
+use crate::base::DecAuthKeyManager;
 use crate::crypto::*;
 use crate::events::*;
 use crate::ndn::*;
@@ -372,6 +373,7 @@ impl SharedCyfsStack {
         let dec_id = Arc::new(OnceCell::new());
         if let Some(id) = &param.dec_id {
             dec_id.set(id.clone()).unwrap();
+            Self::load_dec_auth_key(id)?;
         }
 
         let mut requestor_holder = RequestorHolder::new(param.requestor_config.clone());
@@ -569,9 +571,22 @@ impl SharedCyfsStack {
 
     // If it is not specified during initialization, it can be delayed init once
     pub fn bind_dec(&self, dec_id: ObjectId) {
+        if let Err(e) = Self::load_dec_auth_key(&dec_id) {
+            error!("load dec auth key on bind dec failed! dec={}, {}", dec_id, e);
+        }
         self.dec_id.set(dec_id).unwrap();
     }
 
+    // 由app-manager启动的app，会通过环境变量拿到安装时分配的dec私钥
+    fn load_dec_auth_key(dec_id: &ObjectId) -> BuckyResult<()> {
+        if DecAuthKeyManager::contains(dec_id) {
+            return Ok(());
+        }
+
+        DecAuthKeyManager::load_from_env(dec_id)?;
+        Ok(())
+    }
+
     pub fn dec_id(&self) -> Option<&ObjectId> {
         self.dec_id.get()
     }
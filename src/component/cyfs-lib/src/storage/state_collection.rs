@@ -0,0 +1,667 @@
+use super::collection::CollectionCodec;
+use super::state_storage::StateStorage;
+use cyfs_base::*;
+
+use async_std::sync::Mutex as AsyncMutex;
+use std::marker::PhantomData;
+
+// 数值使用data类型的object_id存储，大端编码
+fn u64_to_object_id(value: u64) -> ObjectId {
+    ObjectIdDataBuilder::new()
+        .data(&value.to_be_bytes())
+        .build()
+        .unwrap()
+}
+
+fn object_id_to_u64(path: &str, id: &ObjectId) -> BuckyResult<u64> {
+    if !id.is_data() || id.data_len() != 8 {
+        let msg = format!(
+            "invalid state storage number value! path={}, value={}",
+            path, id
+        );
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+    }
+
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(id.data());
+    Ok(u64::from_be_bytes(buf))
+}
+
+fn i64_to_object_id(value: i64) -> ObjectId {
+    u64_to_object_id(value as u64)
+}
+
+fn object_id_to_i64(path: &str, id: &ObjectId) -> BuckyResult<i64> {
+    object_id_to_u64(path, id).map(|v| v as i64)
+}
+
+fn check_map_content_type(
+    storage: &StateStorage,
+    list: &[ObjectMapContentItem],
+) -> BuckyResult<()> {
+    if !list.is_empty() && list[0].content_type() != ObjectMapSimpleContentType::Map {
+        let msg = format!(
+            "state storage is not valid map type! path={}, type={}",
+            storage.path(),
+            list[0].content_type().as_str()
+        );
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+    }
+
+    Ok(())
+}
+
+/*
+有序索引，基于map实现
+key使用CollectionCodec编码后转为hex字符串，所以排序规则为编码后的字节序
+数值类型的key需要使用大端编码(比如RawCodec)才能保证和数值大小的顺序一致
+编码后的key长度不能超过(OBJECT_MAP_KEY_MAX_LEN - 1) / 2
+
+注意：objectmap不提供按key有序遍历的能力，range/last/list每次调用都会读出整个map，
+复杂度为O(n)，和limit无关；只适合小集合，大集合需要自己按key分桶(比如按前缀拆成多个子map)
+*/
+pub struct StateStorageIndex<K>
+where
+    K: CollectionCodec<K>,
+{
+    storage: StateStorage,
+    _phantom: PhantomData<K>,
+}
+
+const STATE_STORAGE_INDEX_KEY_PREFIX: &str = "k";
+
+impl<K> StateStorageIndex<K>
+where
+    K: CollectionCodec<K>,
+{
+    pub fn new(storage: StateStorage) -> Self {
+        Self {
+            storage,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn storage(&self) -> &StateStorage {
+        &self.storage
+    }
+
+    pub fn into_storage(self) -> StateStorage {
+        self.storage
+    }
+
+    pub async fn save(&self) -> BuckyResult<()> {
+        self.storage.save().await
+    }
+
+    pub async fn abort(mut self) {
+        self.storage.abort().await
+    }
+
+    fn encode_key(&self, key: &K) -> BuckyResult<String> {
+        let buf = key.encode()?;
+        let key = format!("{}{}", STATE_STORAGE_INDEX_KEY_PREFIX, hex::encode(buf));
+        if key.len() > OBJECT_MAP_KEY_MAX_LEN {
+            let msg = format!(
+                "state storage index key extend limit! path={}, len={}, max={}",
+                self.storage.path(),
+                key.len(),
+                OBJECT_MAP_KEY_MAX_LEN
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+        }
+
+        Ok(key)
+    }
+
+    fn decode_key(&self, key: &str) -> BuckyResult<K> {
+        let buf = key
+            .strip_prefix(STATE_STORAGE_INDEX_KEY_PREFIX)
+            .and_then(|v| hex::decode(v).ok())
+            .ok_or_else(|| {
+                let msg = format!(
+                    "invalid state storage index key! path={}, key={}",
+                    self.storage.path(),
+                    key
+                );
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+            })?;
+
+        K::decode(&buf)
+    }
+
+    pub async fn get(&self, key: &K) -> BuckyResult<Option<ObjectId>> {
+        let key = self.encode_key(key)?;
+        self.storage.stub().get_by_key(key).await
+    }
+
+    pub async fn set(&self, key: &K, value: &ObjectId) -> BuckyResult<Option<ObjectId>> {
+        let key = self.encode_key(key)?;
+        let ret = self
+            .storage
+            .stub()
+            .set_with_key(key, value, None, true)
+            .await?;
+
+        if Some(*value) != ret {
+            self.storage.set_dirty(true);
+        }
+
+        Ok(ret)
+    }
+
+    pub async fn insert(&self, key: &K, value: &ObjectId) -> BuckyResult<()> {
+        let key = self.encode_key(key)?;
+        self.storage.stub().insert_with_key(key, value).await?;
+        self.storage.set_dirty(true);
+
+        Ok(())
+    }
+
+    pub async fn remove(&self, key: &K) -> BuckyResult<Option<ObjectId>> {
+        let key = self.encode_key(key)?;
+        let ret = self.storage.stub().remove_with_key(key, None).await?;
+        if ret.is_some() {
+            self.storage.set_dirty(true);
+        }
+
+        Ok(ret)
+    }
+
+    // 范围查询[begin, end)，为空表示不限制，结果按key升序排列
+    // 需要读出整个map后在内存里筛选，O(n)，limit只减少排序和返回的开销
+    pub async fn range(
+        &self,
+        begin: Option<&K>,
+        end: Option<&K>,
+        limit: Option<usize>,
+    ) -> BuckyResult<Vec<(K, ObjectId)>> {
+        let begin = match begin {
+            Some(key) => Some(self.encode_key(key)?),
+            None => None,
+        };
+        let end = match end {
+            Some(key) => Some(self.encode_key(key)?),
+            None => None,
+        };
+
+        let list = self.list_raw().await?;
+        let list = Self::select_range(list, begin.as_ref(), end.as_ref(), limit);
+
+        self.convert_list(list)
+    }
+
+    // 倒序返回最后的limit个元素，同样需要读出整个map，O(n)
+    pub async fn last(&self, limit: usize) -> BuckyResult<Vec<(K, ObjectId)>> {
+        let list = self.list_raw().await?;
+        let list = Self::select_last(list, limit);
+
+        self.convert_list(list)
+    }
+
+    pub async fn list(&self) -> BuckyResult<Vec<(K, ObjectId)>> {
+        let mut list = self.list_raw().await?;
+        list.sort_by(|left, right| left.0.cmp(&right.0));
+
+        self.convert_list(list)
+    }
+
+    // 先筛选再只对需要返回的部分排序
+    fn select_range(
+        mut list: Vec<(String, ObjectId)>,
+        begin: Option<&String>,
+        end: Option<&String>,
+        limit: Option<usize>,
+    ) -> Vec<(String, ObjectId)> {
+        list.retain(|(key, _)| {
+            if let Some(begin) = begin {
+                if key < begin {
+                    return false;
+                }
+            }
+            if let Some(end) = end {
+                if key >= end {
+                    return false;
+                }
+            }
+            true
+        });
+
+        if let Some(limit) = limit {
+            if limit == 0 {
+                return vec![];
+            }
+            if limit < list.len() {
+                list.select_nth_unstable_by(limit - 1, |left, right| left.0.cmp(&right.0));
+                list.truncate(limit);
+            }
+        }
+
+        list.sort_by(|left, right| left.0.cmp(&right.0));
+        list
+    }
+
+    fn select_last(mut list: Vec<(String, ObjectId)>, limit: usize) -> Vec<(String, ObjectId)> {
+        if limit == 0 {
+            return vec![];
+        }
+        if limit < list.len() {
+            list.select_nth_unstable_by(limit - 1, |left, right| right.0.cmp(&left.0));
+            list.truncate(limit);
+        }
+
+        list.sort_by(|left, right| right.0.cmp(&left.0));
+        list
+    }
+
+    // objectmap在hub模式下不保证key有序，返回的列表是无序的
+    async fn list_raw(&self) -> BuckyResult<Vec<(String, ObjectId)>> {
+        let list = self.storage.stub().list().await?;
+        check_map_content_type(&self.storage, &list)?;
+
+        let list: Vec<(String, ObjectId)> = list
+            .into_iter()
+            .map(|item| match item {
+                ObjectMapContentItem::Map(kp) => kp,
+                _ => unreachable!(),
+            })
+            .collect();
+
+        Ok(list)
+    }
+
+    fn convert_list(&self, list: Vec<(String, ObjectId)>) -> BuckyResult<Vec<(K, ObjectId)>> {
+        let mut result = Vec::with_capacity(list.len());
+        for (key, value) in list {
+            result.push((self.decode_key(&key)?, value));
+        }
+
+        Ok(result)
+    }
+}
+
+/*
+先进先出队列，基于map实现，支持ack
+push分配递增的序号，pop按序号依次取出但不删除，需要ack后才会删除
+未ack的元素可以通过recover重新投递
+*/
+pub struct StateStorageQueue {
+    storage: StateStorage,
+
+    // 读写head/tail需要串行
+    lock: AsyncMutex<()>,
+}
+
+const STATE_STORAGE_QUEUE_HEAD_KEY: &str = "head";
+const STATE_STORAGE_QUEUE_TAIL_KEY: &str = "tail";
+const STATE_STORAGE_QUEUE_ITEM_KEY_PREFIX: &str = "i";
+
+impl StateStorageQueue {
+    pub fn new(storage: StateStorage) -> Self {
+        Self {
+            storage,
+            lock: AsyncMutex::new(()),
+        }
+    }
+
+    pub fn storage(&self) -> &StateStorage {
+        &self.storage
+    }
+
+    pub fn into_storage(self) -> StateStorage {
+        self.storage
+    }
+
+    pub async fn save(&self) -> BuckyResult<()> {
+        self.storage.save().await
+    }
+
+    pub async fn abort(mut self) {
+        self.storage.abort().await
+    }
+
+    fn item_key(seq: u64) -> String {
+        format!("{}{:016x}", STATE_STORAGE_QUEUE_ITEM_KEY_PREFIX, seq)
+    }
+
+    fn parse_item_key(&self, key: &str) -> Option<u64> {
+        key.strip_prefix(STATE_STORAGE_QUEUE_ITEM_KEY_PREFIX)
+            .and_then(|v| u64::from_str_radix(v, 16).ok())
+    }
+
+    async fn get_number(&self, key: &str) -> BuckyResult<u64> {
+        match self.storage.stub().get_by_key(key).await? {
+            Some(id) => object_id_to_u64(self.storage.path(), &id),
+            None => Ok(0),
+        }
+    }
+
+    async fn set_number(&self, key: &str, value: u64) -> BuckyResult<()> {
+        self.storage
+            .stub()
+            .set_with_key(key, &u64_to_object_id(value), None, true)
+            .await?;
+        self.storage.set_dirty(true);
+
+        Ok(())
+    }
+
+    // 返回分配的序号
+    pub async fn push(&self, value: &ObjectId) -> BuckyResult<u64> {
+        let _lock = self.lock.lock().await;
+
+        let seq = self.get_number(STATE_STORAGE_QUEUE_TAIL_KEY).await?;
+        self.storage
+            .stub()
+            .insert_with_key(Self::item_key(seq), value)
+            .await?;
+        self.set_number(STATE_STORAGE_QUEUE_TAIL_KEY, seq + 1).await?;
+
+        Ok(seq)
+    }
+
+    // 取出下一个待投递的元素，元素在ack之前仍然保留在队列中
+    pub async fn pop(&self) -> BuckyResult<Option<(u64, ObjectId)>> {
+        let _lock = self.lock.lock().await;
+
+        let tail = self.get_number(STATE_STORAGE_QUEUE_TAIL_KEY).await?;
+        let mut head = self.get_number(STATE_STORAGE_QUEUE_HEAD_KEY).await?;
+        let mut ret = None;
+        while head < tail {
+            let seq = head;
+            head += 1;
+
+            // 已经被ack的元素直接跳过
+            if let Some(value) = self
+                .storage
+                .stub()
+                .get_by_key(Self::item_key(seq))
+                .await?
+            {
+                ret = Some((seq, value));
+                break;
+            }
+        }
+
+        self.set_number(STATE_STORAGE_QUEUE_HEAD_KEY, head).await?;
+
+        Ok(ret)
+    }
+
+    // 查看下一个待投递的元素，不改变投递位置
+    pub async fn peek(&self) -> BuckyResult<Option<(u64, ObjectId)>> {
+        let _lock = self.lock.lock().await;
+
+        let tail = self.get_number(STATE_STORAGE_QUEUE_TAIL_KEY).await?;
+        let mut seq = self.get_number(STATE_STORAGE_QUEUE_HEAD_KEY).await?;
+        while seq < tail {
+            if let Some(value) = self
+                .storage
+                .stub()
+                .get_by_key(Self::item_key(seq))
+                .await?
+            {
+                return Ok(Some((seq, value)));
+            }
+            seq += 1;
+        }
+
+        Ok(None)
+    }
+
+    pub async fn ack(&self, seq: u64) -> BuckyResult<bool> {
+        let ret = self
+            .storage
+            .stub()
+            .remove_with_key(Self::item_key(seq), None)
+            .await?;
+        if ret.is_some() {
+            self.storage.set_dirty(true);
+        }
+
+        Ok(ret.is_some())
+    }
+
+    // 把投递位置重置到最早的未ack元素，返回需要重新投递的元素个数
+    pub async fn recover(&self) -> BuckyResult<usize> {
+        let _lock = self.lock.lock().await;
+
+        let head = self.get_number(STATE_STORAGE_QUEUE_HEAD_KEY).await?;
+        let list = self.list_items().await?;
+
+        let pending: Vec<u64> = list
+            .iter()
+            .map(|(seq, _)| *seq)
+            .filter(|seq| *seq < head)
+            .collect();
+
+        if let Some(first) = pending.first() {
+            self.set_number(STATE_STORAGE_QUEUE_HEAD_KEY, *first).await?;
+        }
+
+        Ok(pending.len())
+    }
+
+    // 所有未ack的元素，包括已经投递的和还未投递的，按序号升序
+    pub async fn list(&self) -> BuckyResult<Vec<(u64, ObjectId)>> {
+        self.list_items().await
+    }
+
+    pub async fn len(&self) -> BuckyResult<usize> {
+        Ok(self.list_items().await?.len())
+    }
+
+    async fn list_items(&self) -> BuckyResult<Vec<(u64, ObjectId)>> {
+        let list = self.storage.stub().list().await?;
+        check_map_content_type(&self.storage, &list)?;
+
+        let mut items: Vec<(u64, ObjectId)> = list
+            .into_iter()
+            .filter_map(|item| match item {
+                ObjectMapContentItem::Map((key, value)) => {
+                    self.parse_item_key(&key).map(|seq| (seq, value))
+                }
+                _ => unreachable!(),
+            })
+            .collect();
+
+        items.sort_by(|left, right| left.0.cmp(&right.0));
+
+        Ok(items)
+    }
+}
+
+// 计数器集合，基于map实现，每个key对应一个i64计数
+pub struct StateStorageCounter {
+    storage: StateStorage,
+
+    // 读改写需要串行
+    lock: AsyncMutex<()>,
+}
+
+impl StateStorageCounter {
+    pub fn new(storage: StateStorage) -> Self {
+        Self {
+            storage,
+            lock: AsyncMutex::new(()),
+        }
+    }
+
+    pub fn storage(&self) -> &StateStorage {
+        &self.storage
+    }
+
+    pub fn into_storage(self) -> StateStorage {
+        self.storage
+    }
+
+    pub async fn save(&self) -> BuckyResult<()> {
+        self.storage.save().await
+    }
+
+    pub async fn abort(mut self) {
+        self.storage.abort().await
+    }
+
+    // 不存在的计数器返回0
+    pub async fn get(&self, name: impl Into<String>) -> BuckyResult<i64> {
+        match self.storage.stub().get_by_key(name).await? {
+            Some(id) => object_id_to_i64(self.storage.path(), &id),
+            None => Ok(0),
+        }
+    }
+
+    // 返回之前的值
+    pub async fn set(&self, name: impl Into<String>, value: i64) -> BuckyResult<i64> {
+        let _lock = self.lock.lock().await;
+
+        let ret = self
+            .storage
+            .stub()
+            .set_with_key(name, &i64_to_object_id(value), None, true)
+            .await?;
+
+        let prev = match ret {
+            Some(id) => object_id_to_i64(self.storage.path(), &id)?,
+            None => 0,
+        };
+
+        if prev != value {
+            self.storage.set_dirty(true);
+        }
+
+        Ok(prev)
+    }
+
+    // 返回增加后的值
+    pub async fn inc(&self, name: impl Into<String>, delta: i64) -> BuckyResult<i64> {
+        let name = name.into();
+        let _lock = self.lock.lock().await;
+
+        let prev = self.storage.stub().get_by_key(name.clone()).await?;
+        let value = match &prev {
+            Some(id) => object_id_to_i64(self.storage.path(), id)?,
+            None => 0,
+        };
+
+        let new_value = value.checked_add(delta).ok_or_else(|| {
+            let msg = format!(
+                "state storage counter overflow! path={}, name={}, value={}, delta={}",
+                self.storage.path(),
+                name,
+                value,
+                delta
+            );
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::OutOfLimit, msg)
+        })?;
+
+        if delta != 0 {
+            self.storage
+                .stub()
+                .set_with_key(name, &i64_to_object_id(new_value), prev, true)
+                .await?;
+            self.storage.set_dirty(true);
+        }
+
+        Ok(new_value)
+    }
+
+    pub async fn dec(&self, name: impl Into<String>, delta: i64) -> BuckyResult<i64> {
+        self.inc(name, -delta).await
+    }
+
+    // 返回删除前的值
+    pub async fn remove(&self, name: impl Into<String>) -> BuckyResult<Option<i64>> {
+        let _lock = self.lock.lock().await;
+
+        let ret = self.storage.stub().remove_with_key(name, None).await?;
+        match ret {
+            Some(id) => {
+                self.storage.set_dirty(true);
+                Ok(Some(object_id_to_i64(self.storage.path(), &id)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn list(&self) -> BuckyResult<Vec<(String, i64)>> {
+        let list = self.storage.stub().list().await?;
+        check_map_content_type(&self.storage, &list)?;
+
+        let mut result = Vec::with_capacity(list.len());
+        for item in list {
+            match item {
+                ObjectMapContentItem::Map((name, id)) => {
+                    let value = object_id_to_i64(self.storage.path(), &id)?;
+                    result.push((name, value));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_number_codec() {
+        for v in [0u64, 1, 255, 256, u64::MAX] {
+            let id = u64_to_object_id(v);
+            assert!(id.is_data());
+            assert_eq!(object_id_to_u64("/test", &id).unwrap(), v);
+        }
+
+        for v in [0i64, -1, i64::MIN, i64::MAX] {
+            let id = i64_to_object_id(v);
+            assert_eq!(object_id_to_i64("/test", &id).unwrap(), v);
+        }
+
+        let id = ObjectIdDataBuilder::new().data("abc").build().unwrap();
+        assert!(object_id_to_u64("/test", &id).is_err());
+    }
+
+    #[test]
+    fn test_queue_key() {
+        let key = StateStorageQueue::item_key(10);
+        assert_eq!(key, "i000000000000000a");
+
+        // 序号编码后的字符串顺序和数值顺序一致
+        assert!(StateStorageQueue::item_key(9) < StateStorageQueue::item_key(10));
+        assert!(StateStorageQueue::item_key(0xff) < StateStorageQueue::item_key(0x100));
+    }
+
+    #[test]
+    fn test_index_select() {
+        let list: Vec<(String, ObjectId)> = ["05", "01", "04", "02", "03"]
+            .iter()
+            .map(|key| (key.to_string(), ObjectId::default()))
+            .collect();
+        let keys = |list: Vec<(String, ObjectId)>| -> Vec<String> {
+            list.into_iter().map(|(key, _)| key).collect()
+        };
+
+        type Index = StateStorageIndex<u64>;
+        let begin = "02".to_owned();
+        let end = "05".to_owned();
+        assert_eq!(
+            keys(Index::select_range(list.clone(), Some(&begin), Some(&end), None)),
+            ["02", "03", "04"]
+        );
+        assert_eq!(
+            keys(Index::select_range(list.clone(), Some(&begin), None, Some(2))),
+            ["02", "03"]
+        );
+        assert!(Index::select_range(list.clone(), None, None, Some(0)).is_empty());
+
+        assert_eq!(keys(Index::select_last(list.clone(), 2)), ["05", "04"]);
+        assert_eq!(keys(Index::select_last(list.clone(), 10)).len(), 5);
+    }
+}
@@ -5,15 +5,22 @@ use cyfs_base::*;
 use async_std::sync::Mutex as AsyncMutex;
 use once_cell::sync::OnceCell;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicUsize, Ordering},
     Arc,
 };
 
+// 同一组内的storage共享一个path_op_env，从而可以原子的提交多个storage
+struct StorageGroupShared {
+    // 组内还未abort的storage数量，最后一个abort的负责释放path_op_env
+    refs: AtomicUsize,
+}
+
 #[derive(Clone)]
 struct StorageOpData {
     path_stub: PathOpEnvStub,
     single_stub: SingleOpEnvStub,
     current: Arc<AsyncMutex<Option<ObjectId>>>,
+    group: Option<Arc<StorageGroupShared>>,
 }
 
 struct StorageOpDataHolder {
@@ -188,13 +195,17 @@ impl StateStorage {
         }
     }
 
-    async fn load(&self) -> BuckyResult<StorageOpDataHolder> {
+    fn create_global_state_stub(&self) -> GlobalStateStub {
         let dec_id = match &self.dec_id {
             Some(dec_id) => Some(dec_id.to_owned()),
             None => Some(cyfs_core::get_system_dec_app().to_owned()),
         };
 
-        let stub = GlobalStateStub::new(self.global_state.clone(), self.target.clone(), dec_id);
+        GlobalStateStub::new(self.global_state.clone(), self.target.clone(), dec_id)
+    }
+
+    async fn load(&self) -> BuckyResult<StorageOpDataHolder> {
+        let stub = self.create_global_state_stub();
 
         let path_stub = stub.create_path_op_env().await?;
         path_stub
@@ -202,6 +213,15 @@ impl StateStorage {
             .await
             .unwrap();
 
+        self.load_with_path_stub(&stub, path_stub, None).await
+    }
+
+    async fn load_with_path_stub(
+        &self,
+        stub: &GlobalStateStub,
+        path_stub: PathOpEnvStub,
+        group: Option<Arc<StorageGroupShared>>,
+    ) -> BuckyResult<StorageOpDataHolder> {
         let single_stub = stub.create_single_op_env().await?;
 
         let current = path_stub.get_by_path(&self.path).await?;
@@ -218,6 +238,7 @@ impl StateStorage {
             path_stub,
             single_stub,
             current: Arc::new(AsyncMutex::new(current)),
+            group,
         };
 
         let mut holder = StorageOpDataHolder {
@@ -306,11 +327,18 @@ impl StateStorage {
             );
         }
 
-        if let Err(e) = op_data.path_stub.abort().await {
-            error!(
-                "abort state storage path stub error! path={}, {}",
-                self.path, e
-            );
+        let release_path_stub = match &op_data.group {
+            Some(group) => group.refs.fetch_sub(1, Ordering::SeqCst) == 1,
+            None => true,
+        };
+
+        if release_path_stub {
+            if let Err(e) = op_data.path_stub.abort().await {
+                error!(
+                    "abort state storage path stub error! path={}, {}",
+                    self.path, e
+                );
+            }
         }
 
         self.set_dirty(false);
@@ -371,6 +399,242 @@ impl StateStorage {
 
         Ok(())
     }
+
+    // 使用同一个path_op_env初始化一组storage，替代每个storage单独调用init
+    // 组内的storage必须属于同一个global_state/target/dec，之后可以使用commit_group原子的提交
+    pub async fn init_group(list: &[&StateStorage]) -> BuckyResult<()> {
+        if list.is_empty() {
+            return Ok(());
+        }
+
+        let first = list[0];
+        for storage in list {
+            assert!(storage.op_data.get().is_none());
+
+            if storage.target != first.target || storage.dec_id != first.dec_id {
+                let msg = format!(
+                    "state storage group should be in the same target and dec! path={}, first={}",
+                    storage.path, first.path
+                );
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+            }
+        }
+
+        let stub = first.create_global_state_stub();
+        let path_stub = stub.create_path_op_env().await?;
+
+        let path_list: Vec<String> = list.iter().map(|item| item.path.clone()).collect();
+        path_stub.lock(path_list.clone(), u64::MAX).await?;
+
+        let group = Arc::new(StorageGroupShared {
+            refs: AtomicUsize::new(list.len()),
+        });
+
+        let mut holders = Vec::with_capacity(list.len());
+        for storage in list {
+            match storage
+                .load_with_path_stub(&stub, path_stub.clone(), Some(group.clone()))
+                .await
+            {
+                Ok(holder) => holders.push(holder),
+                Err(e) => {
+                    error!(
+                        "init state storage group failed! path={}, {}",
+                        storage.path, e
+                    );
+
+                    for mut holder in holders {
+                        holder.stop_keep_alive(&storage.path).await;
+                        let _ = holder.op_data.single_stub.abort().await;
+                    }
+                    let _ = path_stub.abort().await;
+
+                    return Err(e);
+                }
+            }
+        }
+
+        for (storage, holder) in list.iter().zip(holders.into_iter()) {
+            if let Err(_) = storage.op_data.set(holder) {
+                unreachable!();
+            }
+        }
+
+        info!("init state storage group success! paths={:?}", path_list);
+
+        Ok(())
+    }
+
+    // 原子的提交一组storage，要么全部更新到global_state，要么都不更新
+    pub async fn commit_group(list: &[&StateStorage]) -> BuckyResult<()> {
+        if list.is_empty() {
+            return Ok(());
+        }
+
+        let mut group = None;
+        for storage in list {
+            let op_data = match storage.op_data.get() {
+                Some(holder) => &holder.op_data,
+                None => {
+                    let msg = format!(
+                        "commit state storage group but not init yet! path={}",
+                        storage.path
+                    );
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::ErrorState, msg));
+                }
+            };
+
+            let valid = match (&group, &op_data.group) {
+                (None, Some(current)) => {
+                    group = Some(current.clone());
+                    true
+                }
+                (Some(prev), Some(current)) => Arc::ptr_eq(prev, current),
+                _ => false,
+            };
+
+            if !valid {
+                let msg = format!(
+                    "commit state storage group but not in the same group! path={}",
+                    storage.path
+                );
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+            }
+        }
+
+        // 按照path排序后加锁，避免并发提交时出现死锁
+        let mut list = list.to_vec();
+        list.sort_by(|left, right| left.path.cmp(&right.path));
+
+        let mut dirty_list = vec![];
+        for storage in list {
+            let ret = storage
+                .dirty
+                .compare_exchange(true, false, Ordering::AcqRel, Ordering::Acquire);
+            if ret.is_ok() {
+                dirty_list.push(storage);
+            }
+        }
+
+        if dirty_list.is_empty() {
+            return Ok(());
+        }
+
+        let ret = Self::commit_group_impl(&dirty_list).await;
+        if ret.is_err() {
+            for storage in dirty_list {
+                storage.set_dirty(true);
+            }
+        }
+
+        ret
+    }
+
+    async fn commit_group_impl(list: &[&StateStorage]) -> BuckyResult<()> {
+        let op_data_list: Vec<&StorageOpData> = list
+            .iter()
+            .map(|storage| &storage.op_data.get().unwrap().op_data)
+            .collect();
+
+        let mut current_list = Vec::with_capacity(list.len());
+        for op_data in &op_data_list {
+            current_list.push(op_data.current.lock().await);
+        }
+
+        let mut new_list = Vec::with_capacity(list.len());
+        for (i, op_data) in op_data_list.iter().enumerate() {
+            let new = op_data.single_stub.update().await.map_err(|e| {
+                error!(
+                    "commit state storage group failed! path={}, {}",
+                    list[i].path, e
+                );
+                e
+            })?;
+            new_list.push(new);
+        }
+
+        // 所有变化都先写入共享的path_op_env，任何一个失败都需要回滚之前已经写入的
+        let path_stub = &op_data_list[0].path_stub;
+        let mut changed = vec![];
+        for (i, storage) in list.iter().enumerate() {
+            let prev = current_list[i].clone();
+            let new = new_list[i];
+            if Some(new) == prev {
+                continue;
+            }
+
+            if let Err(e) = path_stub
+                .set_with_path(&storage.path, &new, prev.clone(), true)
+                .await
+            {
+                error!(
+                    "update state storage group but failed! path={}, current={}, prev={:?}, {}",
+                    storage.path, new, prev, e
+                );
+
+                Self::revert_group_changes(path_stub, list, &changed, &current_list, &new_list)
+                    .await;
+                return Err(e);
+            }
+
+            changed.push(i);
+        }
+
+        if changed.is_empty() {
+            debug!("commit state storage group but not changed!");
+            return Ok(());
+        }
+
+        if let Err(e) = path_stub.update().await {
+            error!("commit state storage group to global state failed! {}", e);
+
+            Self::revert_group_changes(path_stub, list, &changed, &current_list, &new_list).await;
+            return Err(e);
+        }
+
+        for i in changed {
+            info!(
+                "commit state storage in group success! path={}, current={}, prev={:?}",
+                list[i].path, new_list[i], current_list[i],
+            );
+            *current_list[i] = Some(new_list[i]);
+        }
+
+        Ok(())
+    }
+
+    async fn revert_group_changes(
+        path_stub: &PathOpEnvStub,
+        list: &[&StateStorage],
+        changed: &[usize],
+        current_list: &[async_std::sync::MutexGuard<'_, Option<ObjectId>>],
+        new_list: &[ObjectId],
+    ) {
+        for i in changed {
+            let path = &list[*i].path;
+            let new = &new_list[*i];
+            let ret = match &*current_list[*i] {
+                Some(prev) => path_stub
+                    .set_with_path(path, prev, Some(new.to_owned()), false)
+                    .await
+                    .map(|_| ()),
+                None => path_stub
+                    .remove_with_path(path, Some(new.to_owned()))
+                    .await
+                    .map(|_| ()),
+            };
+
+            if let Err(e) = ret {
+                error!(
+                    "revert state storage group change failed! path={}, value={}, {}",
+                    path, new, e
+                );
+            }
+        }
+    }
 }
 
 pub struct StateStorageMap {
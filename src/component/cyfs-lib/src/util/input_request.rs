@@ -1,5 +1,5 @@
 use super::output_request::*;
-use crate::{base::*, TransPublishChunkMethod};
+use crate::{base::*, RouterHandlerCategory, TransPublishChunkMethod};
 use cyfs_base::*;
 use std::path::PathBuf;
 
@@ -102,3 +102,18 @@ pub struct UtilBuildDirFromObjectMapInputRequest {
 }
 
 pub type UtilBuildDirFromObjectMapInputResponse = UtilBuildDirFromObjectMapOutputResponse;
+
+// explain_acl
+pub struct UtilExplainAclInputRequest {
+    pub common: UtilInputRequestCommon,
+
+    pub source_device: Option<DeviceId>,
+    pub source_dec: Option<ObjectId>,
+
+    pub category: RouterHandlerCategory,
+    pub req_path: Option<String>,
+    pub object_id: Option<ObjectId>,
+    pub obj_type: Option<u16>,
+}
+
+pub type UtilExplainAclInputResponse = UtilExplainAclOutputResponse;
@@ -1,4 +1,7 @@
-use crate::{prelude::*, GlobalStateAccessMode, TransPublishChunkMethod};
+use crate::{
+    prelude::*, DeviceZoneCategory, GlobalStateAccessMode, RouterHandlerCategory,
+    TransPublishChunkMethod,
+};
 use crate::zone::ZoneRole;
 use cyfs_base::*;
 use cyfs_core::ZoneId;
@@ -188,6 +191,29 @@ impl Display for OODNetworkType {
     }
 }
 
+// 本地meta缓存的状态，用以判断当前是否处于离线状态以及缓存数据的新旧
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MetaCacheStatus {
+    // 最近一次访问meta链是否成功
+    pub online: bool,
+
+    pub last_success_time: u64,
+    pub last_fail_time: u64,
+
+    // 后台刷新最近一次完成的时刻
+    pub last_sync_time: u64,
+
+    // 持久化缓存里的对象和名字条目数
+    pub object_count: u64,
+    pub name_count: u64,
+
+    // 超过刷新周期仍未刷新成功的条目数
+    pub stale_count: u64,
+
+    // 最旧条目的更新时刻
+    pub oldest_update_time: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OODStatus {
     pub network: OODNetworkType,
@@ -222,6 +248,10 @@ pub struct OODStatus {
     // zone local root-state
     pub zone_root_state: Option<ObjectId>,
     pub zone_root_state_revision: u64,
+
+    // local meta cache status
+    #[serde(default)]
+    pub meta: MetaCacheStatus,
 }
 
 #[derive(Debug, Clone)]
@@ -276,11 +306,15 @@ impl UtilGetNOCInfoOutputRequest {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UtilGetNOCInfoOutputResponse {
     pub stat: NamedObjectCacheStat,
+
+    // ndc的chunk统计，老版本的协议栈不返回
+    #[serde(default)]
+    pub ndc_stat: Option<cyfs_util::NamedDataCacheStat>,
 }
 
 impl Display for UtilGetNOCInfoOutputResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "stat: {:?}", self.stat)
+        write!(f, "stat: {:?}, ndc_stat: {:?}", self.stat, self.ndc_stat)
     }
 }
 
@@ -557,3 +591,73 @@ pub struct UtilBuildDirFromObjectMapOutputRequest {
 pub struct UtilBuildDirFromObjectMapOutputResponse {
     pub object_id: ObjectId,
 }
+
+// explain_acl
+// 模拟一次请求，返回acl策略中会命中的规则，不会真正执行也不消耗限速计数
+#[derive(Debug, Clone)]
+pub struct UtilExplainAclOutputRequest {
+    pub common: UtilOutputRequestCommon,
+
+    // 模拟的请求来源，为空则使用当前设备
+    pub source_device: Option<DeviceId>,
+    pub source_dec: Option<ObjectId>,
+
+    pub category: RouterHandlerCategory,
+    pub req_path: Option<String>,
+    pub object_id: Option<ObjectId>,
+    pub obj_type: Option<u16>,
+}
+
+impl Display for UtilExplainAclOutputRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "common: {}, source_device: {:?}, source_dec: {:?}, category: {}, req_path: {:?}, object_id: {:?}, obj_type: {:?}",
+            self.common,
+            self.source_device,
+            self.source_dec,
+            self.category,
+            self.req_path,
+            self.object_id,
+            self.obj_type
+        )
+    }
+}
+
+impl UtilExplainAclOutputRequest {
+    pub fn new(category: RouterHandlerCategory) -> Self {
+        Self {
+            common: UtilOutputRequestCommon::default(),
+            source_device: None,
+            source_dec: None,
+            category,
+            req_path: None,
+            object_id: None,
+            obj_type: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UtilExplainAclOutputResponse {
+    // 来源设备解析出来的zone分类
+    pub zone_category: DeviceZoneCategory,
+
+    // 命中的规则，为空表示没有规则命中，由各个api的acl模块决定
+    pub rule_index: Option<u32>,
+    pub rule_id: Option<String>,
+    pub action: Option<String>,
+}
+
+impl Display for UtilExplainAclOutputResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "zone_category: {}, rule_index: {:?}, rule_id: {:?}, action: {:?}",
+            self.zone_category.as_str(),
+            self.rule_index,
+            self.rule_id,
+            self.action
+        )
+    }
+}
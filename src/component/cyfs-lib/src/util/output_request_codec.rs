@@ -338,3 +338,53 @@ impl JsonCodec<UtilBuildDirFromObjectMapOutputResponse>
         })
     }
 }
+
+impl JsonCodec<UtilExplainAclOutputRequest> for UtilExplainAclOutputRequest {
+    fn encode_json(&self) -> Map<String, Value> {
+        let mut obj = Map::new();
+        JsonCodecHelper::encode_field(&mut obj, "common", &self.common);
+        JsonCodecHelper::encode_option_string_field(
+            &mut obj,
+            "source_device",
+            self.source_device.as_ref(),
+        );
+        JsonCodecHelper::encode_option_string_field(&mut obj, "source_dec", self.source_dec.as_ref());
+        JsonCodecHelper::encode_string_field(&mut obj, "category", &self.category);
+        JsonCodecHelper::encode_option_string_field(&mut obj, "req_path", self.req_path.as_ref());
+        JsonCodecHelper::encode_option_string_field(&mut obj, "object_id", self.object_id.as_ref());
+        JsonCodecHelper::encode_option_number_field(&mut obj, "obj_type", self.obj_type);
+        obj
+    }
+
+    fn decode_json(obj: &Map<String, Value>) -> BuckyResult<UtilExplainAclOutputRequest> {
+        Ok(Self {
+            common: JsonCodecHelper::decode_field(obj, "common")?,
+            source_device: JsonCodecHelper::decode_option_string_field(obj, "source_device")?,
+            source_dec: JsonCodecHelper::decode_option_string_field(obj, "source_dec")?,
+            category: JsonCodecHelper::decode_string_field(obj, "category")?,
+            req_path: JsonCodecHelper::decode_option_string_field(obj, "req_path")?,
+            object_id: JsonCodecHelper::decode_option_string_field(obj, "object_id")?,
+            obj_type: JsonCodecHelper::decode_option_int_field(obj, "obj_type")?,
+        })
+    }
+}
+
+impl JsonCodec<UtilExplainAclOutputResponse> for UtilExplainAclOutputResponse {
+    fn encode_json(&self) -> Map<String, Value> {
+        let mut obj = Map::new();
+        JsonCodecHelper::encode_string_field(&mut obj, "zone_category", &self.zone_category);
+        JsonCodecHelper::encode_option_number_field(&mut obj, "rule_index", self.rule_index);
+        JsonCodecHelper::encode_option_string_field(&mut obj, "rule_id", self.rule_id.as_ref());
+        JsonCodecHelper::encode_option_string_field(&mut obj, "action", self.action.as_ref());
+        obj
+    }
+
+    fn decode_json(obj: &Map<String, Value>) -> BuckyResult<UtilExplainAclOutputResponse> {
+        Ok(Self {
+            zone_category: JsonCodecHelper::decode_string_field(obj, "zone_category")?,
+            rule_index: JsonCodecHelper::decode_option_int_field(obj, "rule_index")?,
+            rule_id: JsonCodecHelper::decode_option_string_field(obj, "rule_id")?,
+            action: JsonCodecHelper::decode_option_string_field(obj, "action")?,
+        })
+    }
+}
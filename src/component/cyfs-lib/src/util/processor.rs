@@ -41,6 +41,9 @@ pub trait UtilOutputProcessor: Sync + Send + 'static {
 
     async fn build_dir_from_object_map(&self, req: UtilBuildDirFromObjectMapOutputRequest)
                                        -> BuckyResult<UtilBuildDirFromObjectMapOutputResponse>;
+
+    async fn explain_acl(&self, req: UtilExplainAclOutputRequest)
+        -> BuckyResult<UtilExplainAclOutputResponse>;
 }
 
 pub type UtilOutputProcessorRef = Arc<Box<dyn UtilOutputProcessor>>;
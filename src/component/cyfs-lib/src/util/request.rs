@@ -36,3 +36,6 @@ pub type UtilBuildFileResponse = UtilBuildFileOutputResponse;
 
 pub type UtilBuildDirFromObjectMapRequest = UtilBuildDirFromObjectMapOutputRequest;
 pub type UtilBuildDirFromObjectMapResponse = UtilBuildDirFromObjectMapOutputResponse;
+
+pub type UtilExplainAclRequest = UtilExplainAclOutputRequest;
+pub type UtilExplainAclResponse = UtilExplainAclOutputResponse;
@@ -472,6 +472,39 @@ impl UtilRequestor {
             Err(e)
         }
     }
+
+    pub async fn explain_acl(
+        &self,
+        req: UtilExplainAclOutputRequest,
+    ) -> BuckyResult<UtilExplainAclOutputResponse> {
+        let url = self.service_url.join("explain_acl").unwrap();
+        let mut http_req = Request::new(Method::Post, url);
+        self.encode_common_headers(&req.common, &mut http_req);
+        let body = req.encode_string();
+        http_req.set_body(body);
+
+        let mut resp = self.requestor.request(http_req).await?;
+        if resp.status().is_success() {
+            let content = RequestorHelper::decode_json_body(&mut resp)
+                .await
+                .map_err(|e| {
+                    let msg = format!("parse explain acl resp body error! err={}", e);
+                    error!("{}", msg);
+                    BuckyError::new(BuckyErrorCode::InvalidData, msg)
+                })?;
+
+            Ok(content)
+        } else {
+            let e = RequestorHelper::error_from_resp(&mut resp).await;
+            error!(
+                "util explain_acl failed: status={}, {}",
+                resp.status(),
+                e
+            );
+
+            Err(e)
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -547,4 +580,11 @@ impl UtilOutputProcessor for UtilRequestor {
         -> BuckyResult<UtilBuildDirFromObjectMapOutputResponse> {
         Self::build_dir_from_object_map(self, req).await
     }
+
+    async fn explain_acl(
+        &self,
+        req: UtilExplainAclOutputRequest,
+    ) -> BuckyResult<UtilExplainAclOutputResponse> {
+        Self::explain_acl(self, req).await
+    }
 }
@@ -1,22 +1,28 @@
 use super::blob::*;
 use cyfs_base::*;
 use cyfs_lib::*;
+use cyfs_util::StorageCryptoRef;
 
 use std::path::{Path, PathBuf};
 
 pub struct FileBlobStorage {
     root: PathBuf,
+
+    // 启用存储加密后，对象以加密形式落盘
+    crypto: Option<StorageCryptoRef>,
+
     #[cfg(target_os = "windows")]
     upgrade: super::old_base36::FileBlobStorageUpgrade,
 }
 
 impl FileBlobStorage {
-    pub fn new(root: PathBuf) -> Self {
+    pub fn new(root: PathBuf, crypto: Option<StorageCryptoRef>) -> Self {
         Self {
             #[cfg(target_os = "windows")]
             upgrade: super::old_base36::FileBlobStorageUpgrade::new(root.clone()),
 
             root,
+            crypto,
         }
     }
 
@@ -79,6 +85,7 @@ impl FileBlobStorage {
             BuckyError::new(BuckyErrorCode::IoError, msg)
         })?;
 
+        let object_raw = cyfs_util::StorageCrypto::load_data(self.crypto.as_ref(), object_raw)?;
         let info = NONObjectInfo::new_from_object_raw(object_raw)?;
         Ok(info)
     }
@@ -108,7 +115,8 @@ impl BlobStorage for FileBlobStorage {
     async fn put_object(&self, data: NONObjectInfo) -> BuckyResult<()> {
         let path = self.get_full_path(&data.object_id, true).await?;
 
-        Self::write(&path, &data.object_raw).await.map_err(|e| {
+        let contents = cyfs_util::StorageCrypto::store_data(self.crypto.as_ref(), data.object_raw.clone())?;
+        Self::write(&path, &contents).await.map_err(|e| {
             let msg = format!(
                 "save object blob to file error! path={}, size={}bytes, {}",
                 path.display(),
@@ -219,7 +227,7 @@ mod test {
     async fn test_dir() {
         let root = get_cyfs_root_path().join("tmp").join("test_blob_storage");
         std::fs::create_dir_all(&root).unwrap();
-        let storage = FileBlobStorage::new(root);
+        let storage = FileBlobStorage::new(root, None);
 
         let count: usize = 1024 * 1024;
         for i in 0..count {
@@ -234,7 +242,7 @@ mod test {
 
     async fn test_file() {
         let root = get_cyfs_root_path().join("data").join("named-object-cache");
-        let storage = FileBlobStorage::new(root);
+        let storage = FileBlobStorage::new(root, None);
 
         let path = "C:\\cyfs\\data\\named-object-cache\\objects\\000\\p0v\\3afs9n7ybn4p7xcor02mnwvy8rr2hqamoi0h6iiyq44ip0v000";
         let obj = storage.load_object(&PathBuf::from(path)).await.unwrap();
@@ -6,9 +6,13 @@ pub use blob::*;
 pub use file::*;
 
 use cyfs_base::*;
+use cyfs_util::{StorageCrypto, StorageCryptoMigrateStat, StorageCryptoRef};
 use std::path::Path;
 
-pub async fn create_blob_storage(root: &Path) -> BuckyResult<Box<dyn BlobStorage>> {
+pub async fn create_blob_storage(
+    root: &Path,
+    crypto: Option<StorageCryptoRef>,
+) -> BuckyResult<Box<dyn BlobStorage>> {
     let dir = root.join("objects");
 
     if !dir.is_dir() {
@@ -24,7 +28,17 @@ pub async fn create_blob_storage(root: &Path) -> BuckyResult<Box<dyn BlobStorage
         }
     }
 
-    let blob = FileBlobStorage::new(dir);
+    let blob = FileBlobStorage::new(dir, crypto);
 
     Ok(Box::new(blob))
+}
+
+// 把noc下已有的对象迁移到当前的加密状态，需要在协议栈停止后执行
+pub async fn migrate_blob_storage_crypto(
+    root: &Path,
+    crypto: &StorageCryptoRef,
+    encrypt: bool,
+) -> BuckyResult<StorageCryptoMigrateStat> {
+    let dir = root.join("objects");
+    StorageCrypto::migrate_dir(crypto, &dir, encrypt, &[]).await
 }
\ No newline at end of file
@@ -9,7 +9,8 @@ mod relation;
 
 pub use noc::*;
 pub use relation::*;
-pub use blob::{BlobStorage, create_blob_storage};
+pub use blob::{BlobStorage, create_blob_storage, migrate_blob_storage_crypto};
+pub use storage::migrate_noc_storage_crypto;
 
 #[macro_use]
 extern crate log;
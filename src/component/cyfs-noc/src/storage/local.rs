@@ -3,7 +3,7 @@ use crate::meta::*;
 use cyfs_base::*;
 use cyfs_lib::*;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 pub struct NamedObjectLocalStorage {
@@ -12,14 +12,18 @@ pub struct NamedObjectLocalStorage {
 }
 
 impl NamedObjectLocalStorage {
-    pub async fn new(isolate: &str) -> BuckyResult<Self> {
+    pub fn data_dir(isolate: &str) -> PathBuf {
         let dir = cyfs_util::get_cyfs_root_path().join("data");
         let dir = if isolate.len() > 0 {
             dir.join(isolate)
         } else {
             dir
         };
-        let dir = dir.join("named-object-cache");
+        dir.join("named-object-cache")
+    }
+
+    pub async fn new(isolate: &str) -> BuckyResult<Self> {
+        let dir = Self::data_dir(isolate);
 
         if !dir.is_dir() {
             if let Err(e) = std::fs::create_dir_all(&dir) {
@@ -31,7 +35,8 @@ impl NamedObjectLocalStorage {
         }
 
         // Init blob module
-        let blob = create_blob_storage(&dir).await?;
+        let crypto = cyfs_util::StorageCryptoManager::get(isolate);
+        let blob = create_blob_storage(&dir, crypto).await?;
 
         let meta = Self::init_meta(&dir)?;
 
@@ -545,3 +550,13 @@ impl NamedObjectCache for NamedObjectLocalStorage {
         self.meta.bind_object_meta_access_provider(object_meta_access_provider)
     }
 }
+
+// 迁移isolate下noc对象的加密状态，需要在协议栈停止后执行
+pub async fn migrate_noc_storage_crypto(
+    isolate: &str,
+    crypto: &cyfs_util::StorageCryptoRef,
+    encrypt: bool,
+) -> BuckyResult<cyfs_util::StorageCryptoMigrateStat> {
+    let dir = NamedObjectLocalStorage::data_dir(isolate);
+    migrate_blob_storage_crypto(&dir, crypto, encrypt).await
+}
@@ -17,4 +17,10 @@ lazy_static! {
     pub static ref PERF_DEC_ID: ObjectId = ObjectId::from_str("9tGpLNnAAYE9Dd4ooNiSjtP5MeL9CNLf9Rxu6AFEc12M").unwrap();
 }
 
-pub static PERF_REPORT_PATH: &str = "/.perf/report";
\ No newline at end of file
+pub static PERF_REPORT_PATH: &str = "/.perf/report";
+
+pub fn register_perf_objects_format() {
+    use cyfs_base::*;
+
+    FORMAT_FACTORY.register(cyfs_core::CoreObjectType::PerfOperation, format_json::<Perf>);
+}
\ No newline at end of file
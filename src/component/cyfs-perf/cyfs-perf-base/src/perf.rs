@@ -2,11 +2,12 @@ use crate::codec::*;
 use crate::items::*;
 use cyfs_base::*;
 use cyfs_core::CoreObjectType;
+use serde::Serialize;
 
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct PerfDescContent {
     device: DeviceId,
 
@@ -73,7 +74,7 @@ impl TryFrom<&PerfDescContent> for protos::PerfDescContent {
 
 ::cyfs_base::impl_default_protobuf_raw_codec!(PerfDescContent);
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct PerfBodyContent {
     time_range: PerfTimeRange,
     all: HashMap<String, PerfIsolateEntity>,
@@ -136,6 +137,9 @@ impl TryFrom<&PerfBodyContent> for protos::PerfBodyContent {
 
 ::cyfs_base::impl_default_protobuf_raw_codec!(PerfBodyContent);
 
+impl ObjectFormatAutoWithSerde for PerfDescContent {}
+impl ObjectFormatAutoWithSerde for PerfBodyContent {}
+
 type PerfType = NamedObjType<PerfDescContent, PerfBodyContent>;
 type PerfBuilder = NamedObjectBuilder<PerfDescContent, PerfBodyContent>;
 type PerfDesc = NamedObjectDesc<PerfDescContent>;
@@ -299,4 +303,18 @@ mod test {
         let name = path.join("perf.desc");
         std::fs::write(&name, buf2).unwrap();
     }
+
+    #[test]
+    fn test_format() {
+        crate::register_perf_objects_format();
+
+        let list = PerfIsolateEntityList::default();
+        let perf_obj = Perf::create(DeviceId::default(), ObjectId::default(), None, "test".to_owned(), "1.0.0".to_owned(), list);
+        let perf_id = perf_obj.desc().calculate_id();
+
+        let value = FORMAT_FACTORY.format(cyfs_core::CoreObjectType::PerfOperation as u16, &perf_obj.to_vec().unwrap()).unwrap();
+        assert_eq!(value["desc"]["object_id"], perf_id.to_string());
+        assert_eq!(value["desc"]["content"]["id"], "test");
+        assert_eq!(value["desc"]["content"]["version"], "1.0.0");
+    }
 }
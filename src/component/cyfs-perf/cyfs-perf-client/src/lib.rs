@@ -8,5 +8,7 @@ pub use client::*;
 pub use config::*;
 pub use isolate::*;
 
+pub use cyfs_perf_base::register_perf_objects_format;
+
 #[macro_use]
 extern crate log;
\ No newline at end of file
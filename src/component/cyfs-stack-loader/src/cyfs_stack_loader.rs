@@ -110,6 +110,17 @@ impl CyfsStackConfigLoader {
                     self.load_meta(v.as_table().unwrap())?;
                 }
 
+                "name" => {
+                    if !v.is_table() {
+                        let msg = format!("invalid non stack.name field format: {:?}", v);
+                        error!("{}", msg);
+
+                        return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                    }
+
+                    self.load_name(v.as_table().unwrap())?;
+                }
+
                 "bdt" => {
                     if !v.is_table() {
                         let msg = format!("invalid non stack.bdt field format: {:?}", v);
@@ -150,6 +161,11 @@ impl CyfsStackConfigLoader {
                         TomlHelper::decode_from_boolean(v)?;
                 }
 
+                "app_legacy_ip_auth" => {
+                    self.params.cyfs_stack_params.config.app_legacy_ip_auth =
+                        TomlHelper::decode_from_boolean(v)?;
+                }
+
                 "isolate" => {
                     if !v.is_str() {
                         error!("invalid object stack.isolate field format: {:?}", v);
@@ -202,6 +218,35 @@ impl CyfsStackConfigLoader {
         Ok(())
     }
 
+    fn load_name(&mut self, node: &toml::value::Table) -> BuckyResult<()> {
+        for (k, v) in node {
+            match k.as_str() {
+                "local_registry" => {
+                    self.params.cyfs_stack_params.name.local_registry =
+                        TomlHelper::decode_from_string(v)?;
+                }
+                "lan_discovery" => {
+                    self.params.cyfs_stack_params.name.lan_discovery =
+                        TomlHelper::decode_from_string(v)?;
+                }
+                "lan_multicast_addr" => {
+                    self.params.cyfs_stack_params.name.lan_multicast_addr =
+                        TomlHelper::decode_from_string(v)?;
+                }
+                "meta_chain" => {
+                    self.params.cyfs_stack_params.name.meta_chain =
+                        TomlHelper::decode_from_boolean(v)?;
+                }
+
+                _ => {
+                    warn!("unknown non stack.name field: {}", k.as_str());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn load_noc(&mut self, node: &toml::value::Table) -> BuckyResult<()> {
         for (k, _v) in node {
             match k.as_str() {
@@ -0,0 +1,342 @@
+use super::spill::AuditLogSpill;
+use super::store::AuditLogStore;
+use crate::acl::AuditLevel;
+use cyfs_base::*;
+use cyfs_lib::*;
+
+use async_std::channel::{Receiver, Sender, TryRecvError, TrySendError};
+use once_cell::sync::OnceCell;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+// 写入队列的长度，队列满时不能阻塞请求处理：deny记录落盘，allow记录丢弃并计数
+const AUDIT_LOG_QUEUE_SIZE: usize = 1024 * 4;
+
+// 每次批量写入的最大条数
+const AUDIT_LOG_BATCH_SIZE: usize = 256;
+
+// 清理过期记录的间隔，1小时
+const AUDIT_LOG_PURGE_INTERVAL: u64 = 1000 * 1000 * 60 * 60;
+
+const DAY_IN_MICROSECONDS: u64 = 1000 * 1000 * 60 * 60 * 24;
+
+#[derive(Clone, Debug)]
+pub(crate) struct AuditLogConfig {
+    pub level: AuditLevel,
+    pub retention_days: u32,
+    pub max_records: u64,
+}
+
+pub struct AuditLogManager {
+    root: PathBuf,
+    level: OnceCell<AuditLevel>,
+    store: OnceCell<Arc<AuditLogStore>>,
+
+    tx: Sender<AuditLogRecord>,
+    rx: Mutex<Option<Receiver<AuditLogRecord>>>,
+
+    spill: Arc<AuditLogSpill>,
+}
+
+impl AuditLogManager {
+    pub(crate) fn new(isolate: Option<&String>) -> Self {
+        let mut root = cyfs_util::get_cyfs_root_path();
+        root.push("data");
+        if let Some(isolate) = isolate {
+            if isolate.len() > 0 {
+                root.push(isolate.as_str());
+            }
+        }
+        root.push("audit");
+
+        let (tx, rx) = async_std::channel::bounded(AUDIT_LOG_QUEUE_SIZE);
+        let spill = Arc::new(AuditLogSpill::new(root.join("audit.spill")));
+
+        Self {
+            root,
+            level: OnceCell::new(),
+            store: OnceCell::new(),
+            tx,
+            rx: Mutex::new(Some(rx)),
+            spill,
+        }
+    }
+
+    // 审计日志初始化失败不影响协议栈的启动，只是不再记录
+    pub(crate) async fn init(&self, config: AuditLogConfig) {
+        info!("will init audit log: config={:?}", config);
+
+        let _ = self.level.set(config.level);
+
+        let store = match self.open_store().await {
+            Ok(store) => Arc::new(store),
+            Err(e) => {
+                error!("init audit log store failed! {}", e);
+                return;
+            }
+        };
+
+        if let Err(_) = self.store.set(store.clone()) {
+            unreachable!();
+        }
+
+        let rx = self.rx.lock().unwrap().take().unwrap();
+        let spill = self.spill.clone();
+        async_std::task::spawn(async move {
+            Self::run_writer(store, rx, spill, config).await;
+        });
+    }
+
+    async fn open_store(&self) -> BuckyResult<AuditLogStore> {
+        if !self.root.is_dir() {
+            async_std::fs::create_dir_all(&self.root)
+                .await
+                .map_err(|e| {
+                    let msg = format!(
+                        "create audit log dir failed! dir={}, {}",
+                        self.root.display(),
+                        e
+                    );
+                    error!("{}", msg);
+                    BuckyError::new(BuckyErrorCode::IoError, msg)
+                })?;
+        }
+
+        let file = self.root.join("audit.db");
+        info!("will open audit log store: db={}", file.display());
+
+        AuditLogStore::open(&file).await
+    }
+
+    async fn run_writer(
+        store: Arc<AuditLogStore>,
+        rx: Receiver<AuditLogRecord>,
+        spill: Arc<AuditLogSpill>,
+        config: AuditLogConfig,
+    ) {
+        // 上次退出前落盘但还没导入的记录
+        Self::flush_spill(&store, &spill).await;
+
+        let mut last_purge = 0;
+        loop {
+            let now = bucky_time_now();
+            if now - last_purge >= AUDIT_LOG_PURGE_INTERVAL {
+                last_purge = now;
+                Self::purge(&store, &config, now).await;
+            }
+
+            let record = match rx.recv().await {
+                Ok(record) => record,
+                Err(_) => {
+                    warn!("audit log channel closed, writer will stop!");
+                    break;
+                }
+            };
+
+            let mut list = vec![record];
+            while list.len() < AUDIT_LOG_BATCH_SIZE {
+                match rx.try_recv() {
+                    Ok(record) => list.push(record),
+                    Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+                }
+            }
+
+            if let Err(e) = store.append(&list).await {
+                error!(
+                    "write audit log records failed! count={}, {}",
+                    list.len(),
+                    e
+                );
+                for record in &list {
+                    spill.add_dropped(record.decision);
+                }
+            }
+
+            Self::flush_spill(&store, &spill).await;
+        }
+    }
+
+    // 导入队列满时落盘的deny记录，并把丢弃的条数作为缺口记录写入
+    async fn flush_spill(store: &AuditLogStore, spill: &AuditLogSpill) {
+        let spilled = spill.take_spilled();
+        if !spilled.is_empty() {
+            if let Err(e) = store.append(&spilled).await {
+                error!(
+                    "write spilled audit log records failed! count={}, {}",
+                    spilled.len(),
+                    e
+                );
+
+                // 写回磁盘，下次再导入
+                for record in &spilled {
+                    if let Err(_) = spill.spill(record) {
+                        spill.add_dropped(record.decision);
+                    }
+                }
+            }
+        }
+
+        let dropped = spill.take_dropped();
+        if !dropped.is_empty() {
+            warn!("audit log records dropped: {:?}", dropped);
+            if let Err(e) = store.append(&dropped).await {
+                error!("write audit log dropped records failed! {}", e);
+                spill.restore_dropped(&dropped);
+            }
+        }
+    }
+
+    async fn purge(store: &AuditLogStore, config: &AuditLogConfig, now: u64) {
+        let before_time = if config.retention_days > 0 {
+            now.saturating_sub(config.retention_days as u64 * DAY_IN_MICROSECONDS)
+        } else {
+            0
+        };
+
+        if let Err(e) = store.purge(before_time, config.max_records).await {
+            error!("purge audit log records failed! {}", e);
+        }
+    }
+
+    fn level(&self) -> AuditLevel {
+        self.level.get().cloned().unwrap_or(AuditLevel::Off)
+    }
+
+    fn should_record(&self, op_type: RequestOpType, decision: AuditDecision) -> bool {
+        let level = self.level();
+        match decision {
+            AuditDecision::Deny => level >= AuditLevel::Deny,
+            AuditDecision::Allow => match op_type {
+                RequestOpType::Read => level >= AuditLevel::All,
+                RequestOpType::Write | RequestOpType::Call => level >= AuditLevel::Write,
+            },
+        }
+    }
+
+    pub fn record(&self, record: AuditLogRecord) {
+        if !self.should_record(record.op_type, record.decision) {
+            return;
+        }
+
+        if self.store.get().is_none() {
+            return;
+        }
+
+        if let Err(e) = self.tx.try_send(record) {
+            match e {
+                TrySendError::Full(record) => match record.decision {
+                    AuditDecision::Deny => {
+                        if let Err(_) = self.spill.spill(&record) {
+                            self.spill.add_dropped(record.decision);
+                        }
+                    }
+                    AuditDecision::Allow => {
+                        warn!(
+                            "audit log queue is full, record will be dropped! category={}, source={:?}",
+                            record.category, record.source_device,
+                        );
+                        self.spill.add_dropped(record.decision);
+                    }
+                },
+                TrySendError::Closed(_) => {
+                    error!("audit log queue closed!");
+                }
+            }
+        }
+    }
+
+    pub fn record_allow(
+        &self,
+        source: &RequestSourceInfo,
+        category: &str,
+        op_type: RequestOpType,
+        object_id: Option<&ObjectId>,
+        req_path: Option<&str>,
+    ) {
+        self.record(Self::new_record(
+            source,
+            category,
+            op_type,
+            object_id,
+            req_path,
+            AuditDecision::Allow,
+            None,
+        ));
+    }
+
+    pub fn record_deny(
+        &self,
+        source: &RequestSourceInfo,
+        category: &str,
+        op_type: RequestOpType,
+        object_id: Option<&ObjectId>,
+        req_path: Option<&str>,
+        reason: &BuckyError,
+    ) {
+        self.record(Self::new_record(
+            source,
+            category,
+            op_type,
+            object_id,
+            req_path,
+            AuditDecision::Deny,
+            Some(reason.msg().to_owned()),
+        ));
+    }
+
+    // 根据请求的处理结果记录，只有成功和权限相关的错误才需要审计
+    pub fn record_result<T>(
+        &self,
+        source: &RequestSourceInfo,
+        category: &str,
+        op_type: RequestOpType,
+        object_id: Option<&ObjectId>,
+        req_path: Option<&str>,
+        ret: &BuckyResult<T>,
+    ) {
+        match ret {
+            Ok(_) => self.record_allow(source, category, op_type, object_id, req_path),
+            Err(e) => match e.code() {
+                BuckyErrorCode::PermissionDenied | BuckyErrorCode::OutOfLimit => {
+                    self.record_deny(source, category, op_type, object_id, req_path, e)
+                }
+                _ => {}
+            },
+        }
+    }
+
+    fn new_record(
+        source: &RequestSourceInfo,
+        category: &str,
+        op_type: RequestOpType,
+        object_id: Option<&ObjectId>,
+        req_path: Option<&str>,
+        decision: AuditDecision,
+        reason: Option<String>,
+    ) -> AuditLogRecord {
+        AuditLogRecord {
+            id: 0,
+            time: bucky_time_now(),
+            source_device: source.zone.device.clone(),
+            source_zone: source.zone.zone_category.clone(),
+            source_dec: source.dec.clone(),
+            category: category.to_owned(),
+            op_type,
+            object_id: object_id.cloned(),
+            req_path: req_path.map(|v| v.to_owned()),
+            decision,
+            reason,
+        }
+    }
+
+    pub async fn query(&self, filter: &AuditLogFilter) -> BuckyResult<Vec<AuditLogRecord>> {
+        match self.store.get() {
+            Some(store) => store.query(filter).await,
+            None => {
+                let msg = format!("audit log store not init or init failed!");
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::ErrorState, msg))
+            }
+        }
+    }
+}
@@ -0,0 +1,6 @@
+mod manager;
+mod spill;
+mod store;
+
+pub use manager::*;
+pub use spill::AUDIT_LOG_DROPPED_CATEGORY;
@@ -0,0 +1,198 @@
+use cyfs_base::*;
+use cyfs_lib::*;
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// 丢弃记录的缺口在日志里的分类，reason里是丢弃的条数
+pub const AUDIT_LOG_DROPPED_CATEGORY: &str = "audit.dropped";
+
+// 写入队列满时的兜底：deny记录追加到磁盘文件，由写入任务再导入存储；
+// allow记录只计数，计数作为一条缺口记录写入日志
+pub(super) struct AuditLogSpill {
+    file: PathBuf,
+    lock: Mutex<()>,
+
+    dropped_allow: AtomicU64,
+    dropped_deny: AtomicU64,
+}
+
+impl AuditLogSpill {
+    pub fn new(file: PathBuf) -> Self {
+        Self {
+            file,
+            lock: Mutex::new(()),
+            dropped_allow: AtomicU64::new(0),
+            dropped_deny: AtomicU64::new(0),
+        }
+    }
+
+    pub fn spill(&self, record: &AuditLogRecord) -> BuckyResult<()> {
+        let _guard = self.lock.lock().unwrap();
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file)
+            .map_err(|e| {
+                let msg = format!(
+                    "open audit log spill file failed! file={}, {}",
+                    self.file.display(),
+                    e
+                );
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::IoError, msg)
+            })?;
+
+        writeln!(file, "{}", record.encode_string()).map_err(|e| {
+            let msg = format!(
+                "write audit log spill file failed! file={}, {}",
+                self.file.display(),
+                e
+            );
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::IoError, msg)
+        })
+    }
+
+    // 取出所有落盘的记录，取出后文件删除
+    pub fn take_spilled(&self) -> Vec<AuditLogRecord> {
+        let content = {
+            let _guard = self.lock.lock().unwrap();
+            if !self.file.is_file() {
+                return vec![];
+            }
+
+            let content = match std::fs::read_to_string(&self.file) {
+                Ok(content) => content,
+                Err(e) => {
+                    error!(
+                        "read audit log spill file failed! file={}, {}",
+                        self.file.display(),
+                        e
+                    );
+                    return vec![];
+                }
+            };
+
+            if let Err(e) = std::fs::remove_file(&self.file) {
+                error!(
+                    "remove audit log spill file failed! file={}, {}",
+                    self.file.display(),
+                    e
+                );
+                return vec![];
+            }
+
+            content
+        };
+
+        let mut list = vec![];
+        for line in content.lines().filter(|line| !line.is_empty()) {
+            match AuditLogRecord::decode_string(line) {
+                Ok(record) => list.push(record),
+                Err(e) => {
+                    error!("decode spilled audit log record failed! {}", e);
+                    self.add_dropped(AuditDecision::Deny);
+                }
+            }
+        }
+
+        list
+    }
+
+    pub fn add_dropped(&self, decision: AuditDecision) {
+        match decision {
+            AuditDecision::Allow => self.dropped_allow.fetch_add(1, Ordering::SeqCst),
+            AuditDecision::Deny => self.dropped_deny.fetch_add(1, Ordering::SeqCst),
+        };
+    }
+
+    // 把丢弃计数转为缺口记录，计数清零
+    pub fn take_dropped(&self) -> Vec<AuditLogRecord> {
+        let mut list = vec![];
+        for (decision, counter) in [
+            (AuditDecision::Allow, &self.dropped_allow),
+            (AuditDecision::Deny, &self.dropped_deny),
+        ] {
+            let count = counter.swap(0, Ordering::SeqCst);
+            if count > 0 {
+                list.push(Self::new_dropped_record(decision, count));
+            }
+        }
+
+        list
+    }
+
+    // 缺口记录没能写入存储时，把计数加回去，下次再写
+    pub fn restore_dropped(&self, list: &[AuditLogRecord]) {
+        for record in list
+            .iter()
+            .filter(|record| record.category == AUDIT_LOG_DROPPED_CATEGORY)
+        {
+            let count = record
+                .reason
+                .as_deref()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0);
+            match record.decision {
+                AuditDecision::Allow => self.dropped_allow.fetch_add(count, Ordering::SeqCst),
+                AuditDecision::Deny => self.dropped_deny.fetch_add(count, Ordering::SeqCst),
+            };
+        }
+    }
+
+    fn new_dropped_record(decision: AuditDecision, count: u64) -> AuditLogRecord {
+        let source = RequestSourceInfo::new_local_system();
+        AuditLogRecord {
+            id: 0,
+            time: bucky_time_now(),
+            source_device: source.zone.device.clone(),
+            source_zone: source.zone.zone_category.clone(),
+            source_dec: source.dec.clone(),
+            category: AUDIT_LOG_DROPPED_CATEGORY.to_owned(),
+            op_type: RequestOpType::Write,
+            object_id: None,
+            req_path: None,
+            decision,
+            reason: Some(count.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_audit_log_spill() {
+        let file = std::env::temp_dir().join(format!("cyfs-audit-spill-{}", bucky_time_now()));
+        let spill = AuditLogSpill::new(file.clone());
+
+        let mut record = AuditLogSpill::new_dropped_record(AuditDecision::Deny, 0);
+        record.category = "put_object".to_owned();
+        record.reason = Some("rejected".to_owned());
+        spill.spill(&record).unwrap();
+        spill.spill(&record).unwrap();
+
+        let list = spill.take_spilled();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].category, "put_object");
+        assert_eq!(list[0].decision, AuditDecision::Deny);
+        assert!(!file.exists());
+        assert!(spill.take_spilled().is_empty());
+
+        spill.add_dropped(AuditDecision::Allow);
+        spill.add_dropped(AuditDecision::Allow);
+        let list = spill.take_dropped();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].decision, AuditDecision::Allow);
+        assert_eq!(list[0].reason.as_deref(), Some("2"));
+        assert!(spill.take_dropped().is_empty());
+
+        spill.restore_dropped(&list);
+        assert_eq!(spill.take_dropped()[0].reason.as_deref(), Some("2"));
+    }
+}
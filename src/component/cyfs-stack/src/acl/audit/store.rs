@@ -0,0 +1,204 @@
+use crate::trans_api::{sql_query, SqlPool, SqlRow, SqlRowObject};
+use cyfs_base::*;
+use cyfs_lib::*;
+
+use std::convert::TryFrom;
+use std::path::Path;
+use std::str::FromStr;
+
+// 单次查询的最大条数
+const AUDIT_LOG_QUERY_MAX_PAGE_SIZE: u32 = 1024;
+
+pub(super) struct AuditLogStore {
+    pool: SqlPool,
+}
+
+impl AuditLogStore {
+    pub async fn open(file: &Path) -> BuckyResult<Self> {
+        let pool = SqlPool::open(
+            format!("sqlite://{}", file.to_string_lossy().to_string()).as_str(),
+            2,
+        )
+        .await?;
+
+        let ret = Self { pool };
+        ret.init().await?;
+
+        Ok(ret)
+    }
+
+    async fn init(&self) -> BuckyResult<()> {
+        let mut conn = self.pool.get_conn().await?;
+        let sql = r#"create table if not exists "audit_log" (
+            "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+            "time" INTEGER NOT NULL,
+            "source_device" TEXT,
+            "source_zone" TEXT NOT NULL,
+            "source_dec" TEXT NOT NULL,
+            "category" TEXT NOT NULL,
+            "op_type" TEXT NOT NULL,
+            "object_id" TEXT,
+            "req_path" TEXT,
+            "decision" INTEGER NOT NULL,
+            "reason" TEXT
+            )"#;
+        conn.execute_sql(sql_query(sql)).await?;
+
+        let sql = r#"create index if not exists time_index on audit_log (time)"#;
+        conn.execute_sql(sql_query(sql)).await?;
+
+        let sql = r#"create index if not exists device_index on audit_log (source_device, time)"#;
+        conn.execute_sql(sql_query(sql)).await?;
+
+        let sql = r#"create index if not exists dec_index on audit_log (source_dec, time)"#;
+        conn.execute_sql(sql_query(sql)).await?;
+
+        Ok(())
+    }
+
+    // 只追加，不提供修改和单条删除
+    pub async fn append(&self, list: &[AuditLogRecord]) -> BuckyResult<()> {
+        let mut conn = self.pool.get_conn().await?;
+        conn.begin_transaction().await?;
+
+        let sql = r#"insert into audit_log (time, source_device, source_zone, source_dec, category, op_type, object_id, req_path, decision, reason)
+            values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"#;
+        for record in list {
+            conn.execute_sql(
+                sql_query(sql)
+                    .bind(record.time as i64)
+                    .bind(record.source_device.as_ref().map(|v| v.to_string()))
+                    .bind(record.source_zone.to_string())
+                    .bind(record.source_dec.to_string())
+                    .bind(record.category.clone())
+                    .bind(request_op_type_as_str(record.op_type))
+                    .bind(record.object_id.as_ref().map(|v| v.to_string()))
+                    .bind(record.req_path.clone())
+                    .bind(record.decision as i32)
+                    .bind(record.reason.clone()),
+            )
+            .await?;
+        }
+
+        conn.commit_transaction().await?;
+
+        Ok(())
+    }
+
+    // 按保留时长和最大条数清理过期记录
+    pub async fn purge(&self, before_time: u64, max_records: u64) -> BuckyResult<()> {
+        let mut conn = self.pool.get_conn().await?;
+
+        if before_time > 0 {
+            let sql = r#"delete from audit_log where time < ?1"#;
+            conn.execute_sql(sql_query(sql).bind(before_time as i64))
+                .await?;
+        }
+
+        if max_records > 0 {
+            let sql = r#"delete from audit_log where id <= (select max(id) from audit_log) - ?1"#;
+            conn.execute_sql(sql_query(sql).bind(max_records as i64))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn query(&self, filter: &AuditLogFilter) -> BuckyResult<Vec<AuditLogRecord>> {
+        let mut conditions = vec![];
+        let mut params = vec![];
+
+        if let Some(source_device) = &filter.source_device {
+            conditions.push("source_device = ?");
+            params.push(source_device.to_string());
+        }
+        if let Some(source_dec) = &filter.source_dec {
+            conditions.push("source_dec = ?");
+            params.push(source_dec.to_string());
+        }
+        if let Some(category) = &filter.category {
+            conditions.push("category = ?");
+            params.push(category.to_owned());
+        }
+
+        let mut sql = "select * from audit_log where 1 = 1".to_owned();
+        for condition in conditions {
+            sql.push_str(" and ");
+            sql.push_str(condition);
+        }
+        if filter.decision.is_some() {
+            sql.push_str(" and decision = ?");
+        }
+        if filter.begin_time.is_some() {
+            sql.push_str(" and time >= ?");
+        }
+        if filter.end_time.is_some() {
+            sql.push_str(" and time < ?");
+        }
+        sql.push_str(" order by id desc limit ? offset ?");
+
+        let page_size = if filter.page_size == 0 || filter.page_size > AUDIT_LOG_QUERY_MAX_PAGE_SIZE {
+            AUDIT_LOG_QUERY_MAX_PAGE_SIZE
+        } else {
+            filter.page_size
+        };
+
+        let mut query = sql_query(&sql);
+        for param in params {
+            query = query.bind(param);
+        }
+        if let Some(decision) = &filter.decision {
+            query = query.bind(*decision as i32);
+        }
+        if let Some(begin_time) = &filter.begin_time {
+            query = query.bind(*begin_time as i64);
+        }
+        if let Some(end_time) = &filter.end_time {
+            query = query.bind(*end_time as i64);
+        }
+        query = query
+            .bind(page_size as i64)
+            .bind(filter.page_index as i64 * page_size as i64);
+
+        let mut conn = self.pool.get_conn().await?;
+        let rows = conn.query_all(query).await?;
+
+        let mut list = Vec::with_capacity(rows.len());
+        for row in rows {
+            list.push(Self::decode_row(&row)?);
+        }
+
+        Ok(list)
+    }
+
+    fn decode_row(row: &SqlRowObject) -> BuckyResult<AuditLogRecord> {
+        let source_device: Option<String> = row.get("source_device");
+        let source_zone: String = row.get("source_zone");
+        let source_dec: String = row.get("source_dec");
+        let op_type: String = row.get("op_type");
+        let object_id: Option<String> = row.get("object_id");
+        let decision: i32 = row.get("decision");
+
+        let record = AuditLogRecord {
+            id: row.get::<i64, _>("id") as u64,
+            time: row.get::<i64, _>("time") as u64,
+            source_device: match source_device {
+                Some(v) => Some(DeviceId::from_str(&v)?),
+                None => None,
+            },
+            source_zone: DeviceZoneCategory::from_str(&source_zone)?,
+            source_dec: ObjectId::from_str(&source_dec)?,
+            category: row.get("category"),
+            op_type: request_op_type_from_str(&op_type)?,
+            object_id: match object_id {
+                Some(v) => Some(ObjectId::from_str(&v)?),
+                None => None,
+            },
+            req_path: row.get("req_path"),
+            decision: AuditDecision::try_from(decision as u8)?,
+            reason: row.get("reason"),
+        };
+
+        Ok(record)
+    }
+}
@@ -1,12 +1,61 @@
 use cyfs_base::*;
 
+use std::str::FromStr;
 use toml::Value as Toml;
 
+// 审计日志的记录级别
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum AuditLevel {
+    // 不记录
+    Off,
+    // 只记录被拒绝的请求
+    Deny,
+    // 记录被拒绝的请求和所有写操作
+    Write,
+    // 记录所有请求
+    All,
+}
+
+impl AuditLevel {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Off => "off",
+            Self::Deny => "deny",
+            Self::Write => "write",
+            Self::All => "all",
+        }
+    }
+}
+
+impl FromStr for AuditLevel {
+    type Err = BuckyError;
+
+    fn from_str(s: &str) -> BuckyResult<Self> {
+        match s {
+            "off" => Ok(Self::Off),
+            "deny" => Ok(Self::Deny),
+            "write" => Ok(Self::Write),
+            "all" => Ok(Self::All),
+            v @ _ => {
+                let msg = format!("unknown audit level: {}", v);
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg))
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AclConfig {
     // 一些基础的权限控制策略
     pub read_bypass_ood: bool,
     pub write_bypass_ood: bool,
+
+    // 审计日志配置
+    pub audit_level: AuditLevel,
+    pub audit_retention_days: u32,
+    // 0表示不限制条数
+    pub audit_max_records: u64,
 }
 
 impl Default for AclConfig {
@@ -14,6 +63,9 @@ impl Default for AclConfig {
         Self {
             read_bypass_ood: true,
             write_bypass_ood: false,
+            audit_level: AuditLevel::Write,
+            audit_retention_days: 30,
+            audit_max_records: 1000000,
         }
     }
 }
@@ -73,6 +125,42 @@ impl AclConfigLoader {
                         }
                     }
                 }
+                "audit-level" => {
+                    match v.as_str() {
+                        Some(s) => {
+                            config.audit_level = AuditLevel::from_str(s)?;
+                        }
+                        None => {
+                            let msg = format!("acl [config] node invalid type: {} = {:?}", k, v);
+                            error!("{}", msg);
+                            return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                        }
+                    }
+                }
+                "audit-retention-days" => {
+                    match v.as_integer() {
+                        Some(n) if n >= 0 => {
+                            config.audit_retention_days = n as u32;
+                        }
+                        _ => {
+                            let msg = format!("acl [config] node invalid type: {} = {:?}", k, v);
+                            error!("{}", msg);
+                            return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                        }
+                    }
+                }
+                "audit-max-records" => {
+                    match v.as_integer() {
+                        Some(n) if n >= 0 => {
+                            config.audit_max_records = n as u64;
+                        }
+                        _ => {
+                            let msg = format!("acl [config] node invalid type: {} = {:?}", k, v);
+                            error!("{}", msg);
+                            return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                        }
+                    }
+                }
                 _ => {
                     warn!("unknown acl [config] node: {} = {:?}", k, v);
                 }
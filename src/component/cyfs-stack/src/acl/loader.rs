@@ -1,4 +1,5 @@
 use super::config::*;
+use super::policy::*;
 use cyfs_base::*;
 
 use std::path::PathBuf;
@@ -52,16 +53,19 @@ impl AclFileLoader {
 pub(super) struct AclLoader<'a> {
     file_loader: AclFileLoader,
     config: &'a mut AclConfig,
+    policy: &'a mut AclPolicy,
 }
 
 impl<'a> AclLoader<'a> {
     pub fn new(
         file_loader: AclFileLoader,
         config: &'a mut AclConfig,
+        policy: &'a mut AclPolicy,
     ) -> Self {
         Self {
             file_loader,
             config,
+            policy,
         }
     }
 
@@ -76,6 +80,11 @@ impl<'a> AclLoader<'a> {
                     self.config.load(node)?;
                 }
 
+                // [[policy]]规则列表，按声明顺序匹配
+                if let Some(node) = table.get("policy") {
+                    self.policy.load(node)?;
+                }
+
                 Ok(())
             }
             _ => {
@@ -1,6 +1,8 @@
+use super::audit::*;
 use super::config::AclConfig;
 use super::loader::AclFileLoader;
 use super::loader::AclLoader;
+use super::policy::*;
 use super::zone_cache::*;
 use crate::resolver::DeviceCache;
 use crate::rmeta_api::GlobalStateMetaLocalService;
@@ -78,6 +80,9 @@ pub struct AclManager {
     local_zone_cache: LocalZoneCache,
 
     config: OnceCell<AclConfig>,
+    policy: OnceCell<AclPolicy>,
+
+    audit: AuditLogManager,
 }
 
 impl AclManager {
@@ -91,6 +96,7 @@ impl AclManager {
         let local_zone_cache = LocalZoneCache::new(zone_manager.clone(), noc.clone());
 
         let file_loader = AclFileLoader::new(config_isolate.as_ref());
+        let audit = AuditLogManager::new(config_isolate.as_ref());
 
         Self {
             local_global_state_meta,
@@ -99,12 +105,23 @@ impl AclManager {
             file_loader,
             local_zone_cache,
             config: OnceCell::new(),
+            policy: OnceCell::new(),
+            audit,
         }
     }
 
     pub async fn init(&self) -> BuckyResult<()> {
         // First load some acl config
-        self.load().await;
+        self.load().await?;
+
+        let config = self.config();
+        self.audit
+            .init(AuditLogConfig {
+                level: config.audit_level,
+                retention_days: config.audit_retention_days,
+                max_records: config.audit_max_records,
+            })
+            .await;
 
         let current_info = self.zone_manager.get_current_info().await?;
         if !current_info.zone_role.is_ood_device() || current_info.zone_role.is_active_ood() {
@@ -115,20 +132,28 @@ impl AclManager {
         Ok(())
     }
 
-    async fn load(&self) {
+    async fn load(&self) -> BuckyResult<()> {
         let mut config = AclConfig::default();
-        let mut loader = AclLoader::new(self.file_loader.clone(), &mut config);
+        let mut policy = AclPolicy::default();
+        let mut loader = AclLoader::new(self.file_loader.clone(), &mut config, &mut policy);
 
-        // 加载外部配置
+        // 加载外部配置，没有配置时使用默认值；配置有误时不能以默认策略放行，协议栈初始化失败
         if let Err(e) = loader.load().await {
             if e.code() == BuckyErrorCode::NotFound {
                 warn!("load acl config but not found! {}", e);
             } else {
-                warn!("load acl config failed! {}", e);
+                error!("load acl config failed! {}", e);
+                return Err(e);
             }
         }
 
         self.config.set(config).unwrap();
+
+        if let Err(_) = self.policy.set(policy) {
+            unreachable!();
+        }
+
+        Ok(())
     }
 
     pub fn zone_manager(&self) -> &ZoneManagerRef {
@@ -139,6 +164,19 @@ impl AclManager {
         self.config.get().unwrap()
     }
 
+    pub fn policy(&self) -> &AclPolicy {
+        self.policy.get().unwrap()
+    }
+
+    // 在各个api的acl模块之前调用，检查全局的acl策略
+    pub fn check_policy(&self, req: &AclPolicyRequest<'_>) -> BuckyResult<()> {
+        self.policy().check(req)
+    }
+
+    pub fn audit(&self) -> &AuditLogManager {
+        &self.audit
+    }
+
     pub fn global_state_meta(&self) -> &GlobalStateMetaLocalService {
         &self.local_global_state_meta
     }
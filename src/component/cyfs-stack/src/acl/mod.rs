@@ -1,7 +1,12 @@
 
+mod audit;
 mod config;
 mod loader;
 mod manager;
+mod policy;
 mod zone_cache;
 
-pub use manager::*;
\ No newline at end of file
+pub use audit::*;
+pub use config::AuditLevel;
+pub use manager::*;
+pub use policy::*;
\ No newline at end of file
@@ -0,0 +1,534 @@
+use cyfs_base::*;
+use cyfs_lib::*;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use toml::Value as Toml;
+
+// 限速计数器数量超过该值后，清理已经过期的计数窗口
+const RATE_LIMIT_GC_THRESHOLD: usize = 1024;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AclPolicyAction {
+    // 通过策略检查，后续仍然需要经过各个api的acl模块
+    Accept,
+
+    // 直接拒绝
+    Reject,
+
+    // 同一个来源(device+dec)在period秒内最多允许count次请求，未超出限制的同accept
+    RateLimit { count: u32, period: u64 },
+}
+
+impl AclPolicyAction {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Accept => "accept",
+            Self::Reject => "reject",
+            Self::RateLimit { .. } => "rate-limit",
+        }
+    }
+}
+
+impl std::fmt::Display for AclPolicyAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RateLimit { count, period } => {
+                write!(f, "{}({}/{}s)", self.as_str(), count, period)
+            }
+            _ => write!(f, "{}", self.as_str()),
+        }
+    }
+}
+
+// 一条策略规则，所有指定了的条件都满足才算匹配，未指定的条件匹配任意值
+#[derive(Clone, Debug)]
+pub struct AclPolicyRule {
+    pub id: String,
+
+    // 来源
+    pub device: Option<DeviceId>,
+    pub zone: Option<DeviceZoneCategory>,
+    pub dec: Option<ObjectId>,
+
+    pub category: Option<RouterHandlerCategory>,
+
+    // 目标对象
+    pub obj_type_code: Option<ObjectTypeCode>,
+    pub obj_type: Option<u16>,
+
+    // 按路径前缀匹配
+    pub req_path: Option<String>,
+
+    pub action: AclPolicyAction,
+}
+
+impl AclPolicyRule {
+    fn is_match(&self, req: &AclPolicyRequest) -> bool {
+        if let Some(device) = &self.device {
+            if req.source.zone.device.as_ref() != Some(device) {
+                return false;
+            }
+        }
+
+        if let Some(zone) = &self.zone {
+            if req.source.zone.zone_category != *zone {
+                return false;
+            }
+        }
+
+        if let Some(dec) = &self.dec {
+            if req.source.dec != *dec {
+                return false;
+            }
+        }
+
+        if let Some(category) = &self.category {
+            if req.category != *category {
+                return false;
+            }
+        }
+
+        if let Some(code) = &self.obj_type_code {
+            match req.object_id {
+                Some(object_id) if object_id.obj_type_code() == *code => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(obj_type) = &self.obj_type {
+            if req.obj_type != Some(*obj_type) {
+                return false;
+            }
+        }
+
+        if let Some(prefix) = &self.req_path {
+            match req.req_path {
+                Some(req_path) if Self::is_path_match(prefix, req_path) => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+
+    fn is_path_match(prefix: &str, req_path: &str) -> bool {
+        let prefix = prefix.trim_end_matches('/');
+        if prefix.is_empty() {
+            return true;
+        }
+
+        match req_path.strip_prefix(prefix) {
+            Some(left) => left.is_empty() || left.starts_with('/'),
+            None => false,
+        }
+    }
+}
+
+// 参与策略匹配的请求信息
+pub struct AclPolicyRequest<'a> {
+    pub source: &'a RequestSourceInfo,
+    pub category: RouterHandlerCategory,
+    pub req_path: Option<&'a str>,
+    pub object_id: Option<&'a ObjectId>,
+    pub obj_type: Option<u16>,
+}
+
+impl<'a> std::fmt::Display for AclPolicyRequest<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "source=({}), category={}, req_path={:?}, object={:?}, obj_type={:?}",
+            self.source, self.category, self.req_path, self.object_id, self.obj_type
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AclPolicyMatchResult {
+    pub index: usize,
+    pub id: String,
+    pub action: AclPolicyAction,
+}
+
+struct RateLimitWindow {
+    start: u64,
+    // 窗口所属规则的周期(微秒)，回收时按各自的周期判断是否过期
+    period: u64,
+    count: u32,
+}
+
+impl RateLimitWindow {
+    fn is_expired(&self, now: u64) -> bool {
+        now >= self.start.saturating_add(self.period)
+    }
+}
+
+#[derive(Clone, Hash, Eq, PartialEq)]
+struct RateLimitKey {
+    index: usize,
+    device: Option<DeviceId>,
+    dec: ObjectId,
+}
+
+// 全局的声明式acl策略，在各个api的acl模块之前检查，按顺序匹配，第一条匹配的规则生效
+pub struct AclPolicy {
+    rules: Vec<AclPolicyRule>,
+    limits: Mutex<HashMap<RateLimitKey, RateLimitWindow>>,
+}
+
+impl Default for AclPolicy {
+    fn default() -> Self {
+        Self {
+            rules: vec![],
+            limits: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl AclPolicy {
+    pub fn load(&mut self, value: &Toml) -> BuckyResult<()> {
+        AclPolicyLoader::load(self, value)
+    }
+
+    pub fn rules(&self) -> &Vec<AclPolicyRule> {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn find(&self, req: &AclPolicyRequest) -> Option<AclPolicyMatchResult> {
+        self.rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.is_match(req))
+            .map(|(index, rule)| AclPolicyMatchResult {
+                index,
+                id: rule.id.clone(),
+                action: rule.action.clone(),
+            })
+    }
+
+    // 只返回匹配的规则，不会真正执行，也不会消耗限速计数
+    pub fn explain(&self, req: &AclPolicyRequest) -> Option<AclPolicyMatchResult> {
+        self.find(req)
+    }
+
+    pub fn check(&self, req: &AclPolicyRequest) -> BuckyResult<()> {
+        if self.rules.is_empty() {
+            return Ok(());
+        }
+
+        let ret = match self.find(req) {
+            Some(ret) => ret,
+            None => return Ok(()),
+        };
+
+        match ret.action {
+            AclPolicyAction::Accept => {
+                debug!("acl policy accepted: rule={}, req={}", ret.id, req);
+                Ok(())
+            }
+            AclPolicyAction::Reject => {
+                let msg = format!("acl policy rejected: rule={}, req={}", ret.id, req);
+                warn!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg))
+            }
+            AclPolicyAction::RateLimit { count, period } => {
+                let key = RateLimitKey {
+                    index: ret.index,
+                    device: req.source.zone.device.clone(),
+                    dec: req.source.dec.clone(),
+                };
+
+                if self.acquire(key, count, period) {
+                    Ok(())
+                } else {
+                    let msg = format!(
+                        "acl policy rate limit exceeded: rule={}, limit={}/{}s, req={}",
+                        ret.id, count, period, req
+                    );
+                    warn!("{}", msg);
+                    Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg))
+                }
+            }
+        }
+    }
+
+    // 固定窗口计数
+    fn acquire(&self, key: RateLimitKey, count: u32, period: u64) -> bool {
+        let now = bucky_time_now();
+        let period = period.saturating_mul(1000 * 1000);
+
+        let mut limits = self.limits.lock().unwrap();
+        if limits.len() >= RATE_LIMIT_GC_THRESHOLD {
+            limits.retain(|_, window| !window.is_expired(now));
+        }
+
+        let window = limits.entry(key).or_insert(RateLimitWindow {
+            start: now,
+            period,
+            count: 0,
+        });
+
+        if window.is_expired(now) {
+            window.start = now;
+            window.period = period;
+            window.count = 0;
+        }
+
+        if window.count >= count {
+            return false;
+        }
+
+        window.count += 1;
+        true
+    }
+}
+
+struct AclPolicyLoader;
+
+impl AclPolicyLoader {
+    fn load(policy: &mut AclPolicy, value: &Toml) -> BuckyResult<()> {
+        match value {
+            Toml::Array(list) => {
+                for item in list {
+                    let rule = Self::load_rule(policy.rules.len(), item)?;
+                    info!("load acl policy rule: {:?}", rule);
+                    policy.rules.push(rule);
+                }
+
+                Ok(())
+            }
+            _ => {
+                let msg = format!("acl [[policy]] node not invalid array: {:?}", value);
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg))
+            }
+        }
+    }
+
+    fn load_rule(index: usize, value: &Toml) -> BuckyResult<AclPolicyRule> {
+        let table = match value {
+            Toml::Table(table) => table,
+            _ => {
+                let msg = format!("acl [[policy]] item not invalid table: {:?}", value);
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+            }
+        };
+
+        let mut rule = AclPolicyRule {
+            id: format!("policy-{}", index),
+            device: None,
+            zone: None,
+            dec: None,
+            category: None,
+            obj_type_code: None,
+            obj_type: None,
+            req_path: None,
+            action: AclPolicyAction::Accept,
+        };
+
+        let mut action = None;
+        let mut limit = None;
+        let mut period = None;
+
+        for (k, v) in table {
+            match k.as_str() {
+                "id" => rule.id = Self::load_str(k, v)?.to_owned(),
+                "device" => rule.device = Some(Self::load_from_str(k, v)?),
+                "zone" => rule.zone = Some(Self::load_from_str(k, v)?),
+                "dec" => rule.dec = Some(Self::load_from_str(k, v)?),
+                "category" => rule.category = Some(Self::load_from_str(k, v)?),
+                "obj-type-code" => {
+                    rule.obj_type_code = Some(ObjectTypeCode::from(Self::load_int(k, v)? as u16))
+                }
+                "obj-type" => rule.obj_type = Some(Self::load_int(k, v)? as u16),
+                "req-path" => rule.req_path = Some(Self::load_str(k, v)?.to_owned()),
+                "action" => action = Some(Self::load_str(k, v)?.to_owned()),
+                "limit" => limit = Some(Self::load_int(k, v)? as u32),
+                "period" => period = Some(Self::load_int(k, v)? as u64),
+                _ => {
+                    warn!("unknown acl [[policy]] item: {} = {:?}", k, v);
+                }
+            }
+        }
+
+        rule.action = match action.as_deref() {
+            Some("accept") => AclPolicyAction::Accept,
+            Some("reject") => AclPolicyAction::Reject,
+            Some("rate-limit") => match (limit, period) {
+                (Some(count), Some(period)) if period > 0 => {
+                    AclPolicyAction::RateLimit { count, period }
+                }
+                _ => {
+                    let msg = format!(
+                        "acl [[policy]] rate-limit action should specify limit and period! rule={}",
+                        rule.id
+                    );
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                }
+            },
+            v @ _ => {
+                let msg = format!(
+                    "acl [[policy]] invalid action! rule={}, action={:?}",
+                    rule.id, v
+                );
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+            }
+        };
+
+        Ok(rule)
+    }
+
+    fn load_str<'a>(k: &str, v: &'a Toml) -> BuckyResult<&'a str> {
+        v.as_str().ok_or_else(|| {
+            let msg = format!("acl [[policy]] item invalid type: {} = {:?}", k, v);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })
+    }
+
+    fn load_int(k: &str, v: &Toml) -> BuckyResult<i64> {
+        match v.as_integer() {
+            Some(v) if v >= 0 => Ok(v),
+            _ => {
+                let msg = format!("acl [[policy]] item invalid type: {} = {:?}", k, v);
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg))
+            }
+        }
+    }
+
+    fn load_from_str<T>(k: &str, v: &Toml) -> BuckyResult<T>
+    where
+        T: FromStr,
+        <T as FromStr>::Err: std::fmt::Display,
+    {
+        let s = Self::load_str(k, v)?;
+        T::from_str(s).map_err(|e| {
+            let msg = format!("acl [[policy]] item invalid value: {} = {}, {}", k, s, e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn load(content: &str) -> AclPolicy {
+        let value: Toml = toml::from_str(content).unwrap();
+        let mut policy = AclPolicy::default();
+        policy
+            .load(value.as_table().unwrap().get("policy").unwrap())
+            .unwrap();
+        policy
+    }
+
+    #[test]
+    fn test_acl_policy() {
+        let policy = load(
+            r#"
+            [[policy]]
+            id = "deny-other-put"
+            zone = "other-zone"
+            category = "put_object"
+            action = "reject"
+
+            [[policy]]
+            id = "limit-other-get"
+            zone = "other-zone"
+            req-path = "/public"
+            action = "rate-limit"
+            limit = 2
+            period = 60
+            "#,
+        );
+        assert_eq!(policy.rules().len(), 2);
+
+        let mut source = RequestSourceInfo::new_other_zone_dec(None);
+        source.zone.device = Some(DeviceId::default());
+
+        let put = AclPolicyRequest {
+            source: &source,
+            category: RouterHandlerCategory::PutObject,
+            req_path: None,
+            object_id: None,
+            obj_type: None,
+        };
+        assert_eq!(policy.explain(&put).unwrap().id, "deny-other-put");
+        assert!(policy.check(&put).is_err());
+
+        let get = AclPolicyRequest {
+            source: &source,
+            category: RouterHandlerCategory::GetObject,
+            req_path: Some("/public/a"),
+            object_id: None,
+            obj_type: None,
+        };
+        assert_eq!(policy.explain(&get).unwrap().index, 1);
+        assert!(policy.check(&get).is_ok());
+        assert!(policy.check(&get).is_ok());
+        assert_eq!(
+            policy.check(&get).unwrap_err().code(),
+            BuckyErrorCode::OutOfLimit
+        );
+
+        let other_path = AclPolicyRequest {
+            source: &source,
+            category: RouterHandlerCategory::GetObject,
+            req_path: Some("/publicity"),
+            object_id: None,
+            obj_type: None,
+        };
+        assert!(policy.explain(&other_path).is_none());
+
+        let local = RequestSourceInfo::new_local_system();
+        let local_put = AclPolicyRequest {
+            source: &local,
+            category: RouterHandlerCategory::PutObject,
+            req_path: None,
+            object_id: None,
+            obj_type: None,
+        };
+        assert!(policy.check(&local_put).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limit_gc() {
+        let policy = AclPolicy::default();
+        let key = |index: usize| RateLimitKey {
+            index,
+            device: None,
+            dec: ObjectId::default(),
+        };
+
+        // 长周期规则的窗口已经开始了一段时间
+        assert!(policy.acquire(key(0), 1, 3600));
+        policy
+            .limits
+            .lock()
+            .unwrap()
+            .get_mut(&key(0))
+            .unwrap()
+            .start -= 10 * 1000 * 1000;
+
+        // 短周期规则触发回收，不能按短周期回收掉长周期的窗口
+        for index in 1..=RATE_LIMIT_GC_THRESHOLD {
+            assert!(policy.acquire(key(index), 1, 1));
+        }
+        assert!(!policy.acquire(key(0), 1, 3600));
+
+        // 超大的周期不会溢出
+        assert!(policy.acquire(key(0xffff), 1, u64::MAX));
+        assert!(!policy.acquire(key(0xffff), 1, u64::MAX));
+    }
+}
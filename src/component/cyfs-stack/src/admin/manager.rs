@@ -1,7 +1,9 @@
+use crate::acl::AclManagerRef;
 use crate::config::StackGlobalConfig;
 use crate::crypto_api::*;
 use crate::zone::ZoneRoleManager;
 use cyfs_base::*;
+use cyfs_core::*;
 use cyfs_lib::*;
 use cyfs_util::*;
 
@@ -29,9 +31,7 @@ impl EventListenerAsyncRoutine<RouterHandlerPostObjectRequest, RouterHandlerPost
             .on_admin_command(&param.request.common.source, &param.request.object)
             .await
         {
-            Ok(()) => Ok(NONPostObjectInputResponse {
-                object: None,
-            }),
+            Ok(object) => Ok(NONPostObjectInputResponse { object }),
             Err(e) => Err(e),
         };
 
@@ -52,6 +52,7 @@ pub struct AdminManager {
     role_manager: ZoneRoleManager,
     obj_verifier: Arc<ObjectVerifier>,
     config: StackGlobalConfig,
+    acl: AclManagerRef,
 }
 
 impl AdminManager {
@@ -59,11 +60,13 @@ impl AdminManager {
         role_manager: ZoneRoleManager,
         obj_verifier: Arc<ObjectVerifier>,
         config: StackGlobalConfig,
+        acl: AclManagerRef,
     ) -> Self {
         Self {
             role_manager,
             obj_verifier,
             config,
+            acl,
         }
     }
 
@@ -106,7 +109,11 @@ impl AdminManager {
         Ok(())
     }
 
-    async fn on_admin_command(&self, source: &RequestSourceInfo, object: &NONObjectInfo) -> BuckyResult<()> {
+    async fn on_admin_command(
+        &self,
+        source: &RequestSourceInfo,
+        object: &NONObjectInfo,
+    ) -> BuckyResult<Option<NONObjectInfo>> {
         // decode to AdminObject
         let admin_object = AdminObject::clone_from_slice(&object.object_raw).map_err(|e| {
             let msg = format!(
@@ -199,14 +206,34 @@ impl AdminManager {
         }
     }
 
-    async fn process_command(&self, cmd: AdminCommand) -> BuckyResult<()> {
+    async fn process_command(&self, cmd: AdminCommand) -> BuckyResult<Option<NONObjectInfo>> {
         match cmd {
             AdminCommand::GlobalStateAccessMode(access_mode) => {
-                self.process_access_mode(access_mode).await
+                self.process_access_mode(access_mode).await?;
+                Ok(None)
+            }
+            AdminCommand::QueryAuditLog(filter) => {
+                let object = self.process_query_audit_log(filter).await?;
+                Ok(Some(object))
             }
         }
     }
 
+    // 查询结果编码为json，通过Text对象返回
+    async fn process_query_audit_log(&self, filter: AuditLogFilter) -> BuckyResult<NONObjectInfo> {
+        let list = self.acl.audit().query(&filter).await?;
+        info!(
+            "query audit log success! filter={:?}, count={}",
+            filter,
+            list.len()
+        );
+
+        let result = AuditLogQueryResult { list };
+        let text = Text::create(AUDIT_LOG_QUERY_RESULT_TEXT_ID, "", result.encode_string());
+
+        NONObjectInfo::new_from_object_raw(text.to_vec()?)
+    }
+
     async fn process_access_mode(
         &self,
         access_mode: AdminGlobalStateAccessModeData,
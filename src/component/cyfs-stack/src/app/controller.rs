@@ -6,7 +6,8 @@ use cyfs_lib::*;
 use cyfs_util::*;
 
 use std::collections::{hash_map::Entry, HashMap};
-use std::sync::{Arc, RwLock};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
 
 struct OnAppActionWatcher {
     owner: AppController,
@@ -81,19 +82,42 @@ impl EventListenerAsyncRoutine<RouterHandlerPostObjectRequest, RouterHandlerPost
     }
 }
 
+#[derive(Clone)]
+struct AuthenticatedAppInfo {
+    dec_info: DecIpInfo,
+    public_key: Option<PublicKey>,
+}
+
 #[derive(Clone)]
 pub(crate) struct AuthenticatedAppList {
     gateway_ip: Arc<RwLock<Option<String>>>,
-    list: Arc<RwLock<HashMap<String, DecIpInfo>>>,
+    list: Arc<RwLock<HashMap<String, AuthenticatedAppInfo>>>,
     storage: Arc<AppLocalStateStorage>,
+
+    // 是否允许没有分配密钥的app使用旧的ip匹配方式鉴权
+    legacy_ip_auth: bool,
+
+    // 有效期内已经使用过的凭证，用以拒绝重放
+    used_nonces: Arc<Mutex<UsedNonceCache>>,
 }
 
+// (dec_id, nonce) -> timestamp，超出有效期的记录定期清理
+#[derive(Default)]
+struct UsedNonceCache {
+    list: HashMap<(String, String), u64>,
+    last_gc: u64,
+}
+
+const USED_NONCE_GC_INTERVAL: u64 = 1000 * 1000 * 60;
+
 impl AuthenticatedAppList {
-    fn new(config_isolate: Option<String>) -> Self {
+    pub(crate) fn new(config_isolate: Option<String>, legacy_ip_auth: bool) -> Self {
         Self {
             gateway_ip: Arc::new(RwLock::new(None)),
             list: Arc::new(RwLock::new(HashMap::new())),
             storage: Arc::new(AppLocalStateStorage::new(config_isolate)),
+            legacy_ip_auth,
+            used_nonces: Arc::new(Mutex::new(UsedNonceCache::default())),
         }
     }
 
@@ -113,6 +137,7 @@ impl AuthenticatedAppList {
             let dec_info = DecIpInfo {
                 name: item.name,
                 ip: item.ip,
+                public_key: item.public_key,
             };
             self.register(&item.dec_id, &dec_info);
         }
@@ -129,10 +154,11 @@ impl AuthenticatedAppList {
         let list = self.list.read().unwrap();
         let list = list
             .iter()
-            .map(|(dec_id, dec_info)| AppLocalStateSavedData {
+            .map(|(dec_id, info)| AppLocalStateSavedData {
                 dec_id: dec_id.to_owned(),
-                name: dec_info.name.clone(),
-                ip: dec_info.ip.clone(),
+                name: info.dec_info.name.clone(),
+                ip: info.dec_info.ip.clone(),
+                public_key: info.dec_info.public_key.clone(),
             })
             .collect();
 
@@ -153,6 +179,25 @@ impl AuthenticatedAppList {
     }
 
     fn register(&self, dec_id: &str, dec_info: &DecIpInfo) {
+        let public_key = match &dec_info.public_key {
+            Some(value) => match PublicKey::clone_from_hex(value, &mut vec![]) {
+                Ok(key) => Some(key),
+                Err(e) => {
+                    error!(
+                        "invalid authenticated app public key! dec_id={}, key={}, {}",
+                        dec_id, value, e
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let info = AuthenticatedAppInfo {
+            dec_info: dec_info.to_owned(),
+            public_key,
+        };
+
         let mut list = self.list.write().unwrap();
         match list.entry(dec_id.to_owned()) {
             Entry::Vacant(v) => {
@@ -160,12 +205,12 @@ impl AuthenticatedAppList {
                     "register authenticated app: dec_id={}, dec_info={:?}",
                     dec_id, dec_info
                 );
-                v.insert(dec_info.to_owned());
+                v.insert(info);
             }
             Entry::Occupied(mut o) => {
                 warn!("register authenticated app but already exists! now will replace, dec_id={}, old dec_info={:?}, new dec_info={:?}", 
-                    dec_id, o.get(), dec_info);
-                o.insert(dec_info.to_owned());
+                    dec_id, o.get().dec_info, dec_info);
+                o.insert(info);
             }
         }
     }
@@ -173,10 +218,10 @@ impl AuthenticatedAppList {
     fn unregister(&self, dec_id: &str) -> usize {
         let mut list = self.list.write().unwrap();
         match list.remove(dec_id) {
-            Some(dec_info) => {
+            Some(info) => {
                 info!(
                     "unregister authenticated app: dec_id={}, dec_info={:?}",
-                    dec_id, dec_info
+                    dec_id, info.dec_info
                 );
             }
             None => {
@@ -190,16 +235,36 @@ impl AuthenticatedAppList {
         list.len()
     }
 
-    pub fn check_auth(&self, dec_id: &str, addr: &str) -> BuckyResult<()> {
+    pub fn check_auth(
+        &self,
+        dec_id: &str,
+        method: &str,
+        path: &str,
+        token: Option<&str>,
+        addr: &str,
+    ) -> BuckyResult<()> {
         let list = self.list.read().unwrap();
         match list.get(dec_id) {
-            Some(dec_info) => {
-                if dec_info.ip == addr {
+            Some(info) => {
+                if let Some(public_key) = &info.public_key {
+                    return self.check_token(dec_id, method, path, token, public_key);
+                }
+
+                if !self.legacy_ip_auth {
+                    let msg = format!(
+                        "app has no auth key and legacy ip auth is disabled! dec_id={}, addr={}",
+                        dec_id, addr
+                    );
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+                }
+
+                if info.dec_info.ip == addr {
                     Ok(())
                 } else {
                     let msg = format!(
                         "app auth info not match! dec_id={}, addr={}, register addr={}",
-                        dec_id, addr, dec_info.ip
+                        dec_id, addr, info.dec_info.ip
                     );
                     error!("{}", msg);
                     Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg))
@@ -212,6 +277,96 @@ impl AuthenticatedAppList {
             }
         }
     }
+
+    // 本地接口的校验：已注册的app必须携带凭证，ip匹配只在legacy模式下使用
+    // 没有注册的dec(系统服务、开发工具等)没有分配密钥，保持原有行为
+    pub fn check_local_auth(
+        &self,
+        dec_id: &str,
+        method: &str,
+        path: &str,
+        token: Option<&str>,
+        addr: &str,
+    ) -> BuckyResult<()> {
+        let public_key = {
+            let list = self.list.read().unwrap();
+            match list.get(dec_id) {
+                Some(info) => info.public_key.clone(),
+                None => return Ok(()),
+            }
+        };
+
+        match public_key {
+            Some(public_key) => self.check_token(dec_id, method, path, token, &public_key),
+            None => {
+                if self.legacy_ip_auth {
+                    Ok(())
+                } else {
+                    let msg = format!(
+                        "app has no auth key and legacy ip auth is disabled! dec_id={}, addr={}",
+                        dec_id, addr
+                    );
+                    error!("{}", msg);
+                    Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg))
+                }
+            }
+        }
+    }
+}
+
+impl AuthenticatedAppList {
+    fn check_token(
+        &self,
+        dec_id: &str,
+        method: &str,
+        path: &str,
+        token: Option<&str>,
+        public_key: &PublicKey,
+    ) -> BuckyResult<()> {
+        let token = token.ok_or_else(|| {
+            let msg = format!("app auth token not specified! dec_id={}", dec_id);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::PermissionDenied, msg)
+        })?;
+
+        let id = ObjectId::from_str(dec_id)?;
+        let token = DecAuthToken::verify(&id, method, path, token, public_key).map_err(|e| {
+            let msg = format!("app auth token verify failed! dec_id={}, {}", dec_id, e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::PermissionDenied, msg)
+        })?;
+
+        self.check_replay(dec_id, token)
+    }
+
+    // 同一个nonce在有效期内只能使用一次，过期的记录顺带清理
+    fn check_replay(&self, dec_id: &str, token: DecAuthToken) -> BuckyResult<()> {
+        let now = bucky_time_now();
+        let mut used = self.used_nonces.lock().unwrap();
+        if now >= used.last_gc + USED_NONCE_GC_INTERVAL {
+            used.list.retain(|_, timestamp| {
+                now <= *timestamp + CYFS_DEC_AUTH_TOKEN_VALID_DURATION
+                    && *timestamp <= now + CYFS_DEC_AUTH_TOKEN_VALID_DURATION
+            });
+            used.last_gc = now;
+        }
+
+        match used.list.entry((dec_id.to_owned(), token.nonce)) {
+            Entry::Vacant(v) => {
+                v.insert(token.timestamp);
+                Ok(())
+            }
+            Entry::Occupied(o) => {
+                let msg = format!(
+                    "app auth token already used! dec_id={}, nonce={}",
+                    dec_id,
+                    o.key().1
+                );
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg))
+            }
+        }
+    }
 }
 
 // manage all app/dec's register/unresiter actions
@@ -224,10 +379,13 @@ pub(crate) struct AppController {
 const APP_MANAGER_CONTROLLER_HANDLER_ID: &str = "system_app_manager_controller";
 
 impl AppController {
-    pub fn new(config_isolate: Option<String>, listener_manager: ObjectListenerManagerRef) -> Self {
+    pub fn new(
+        auth_app_list: AuthenticatedAppList,
+        listener_manager: ObjectListenerManagerRef,
+    ) -> Self {
         Self {
             listener_manager,
-            auth_app_list: AuthenticatedAppList::new(config_isolate),
+            auth_app_list,
         }
     }
 
@@ -292,17 +450,22 @@ impl AppController {
                     self.auth_app_list.register(&dec_id, dec_info);
                 }
 
-                self.auth_app_list.set_gateway_ip(&action.docker_gateway_ip);
+                // 非docker运行的app没有网关地址，只通过本地接口访问
+                if !action.docker_gateway_ip.is_empty() {
+                    self.auth_app_list.set_gateway_ip(&action.docker_gateway_ip);
+                }
 
                 let _ = self.auth_app_list.save().await;
 
                 if self.auth_app_list.count() > 0 {
-                    self.listener_manager
-                        .start_authenticated_interface(
-                            &action.docker_gateway_ip,
-                            self.auth_app_list.clone(),
-                        )
-                        .await?;
+                    if let Some(docker_gateway_ip) = self.auth_app_list.gateway_ip() {
+                        self.listener_manager
+                            .start_authenticated_interface(
+                                &docker_gateway_ip,
+                                self.auth_app_list.clone(),
+                            )
+                            .await?;
+                    }
                 }
             }
             AppManagerActionEnum::UnregisterDec(action) => {
@@ -327,3 +490,41 @@ impl AppController {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_local_auth() {
+        let list = AuthenticatedAppList::new(None, false);
+        let secret = PrivateKey::generate_secp256k1().unwrap();
+        let dec_id = ObjectId::default();
+        let dec_id_str = dec_id.to_string();
+
+        // 没有注册的dec不做校验
+        list.check_local_auth(&dec_id_str, "GET", "/", None, "127.0.0.1")
+            .unwrap();
+
+        let info = DecIpInfo {
+            name: "test".to_owned(),
+            ip: "127.0.0.1".to_owned(),
+            public_key: Some(secret.public().to_hex().unwrap()),
+        };
+        list.register(&dec_id_str, &info);
+
+        // 已注册的app必须携带凭证
+        assert!(list
+            .check_local_auth(&dec_id_str, "GET", "/non/object", None, "127.0.0.1")
+            .is_err());
+
+        let token = DecAuthToken::sign(&dec_id, "GET", "/non/object", &secret).unwrap();
+        list.check_local_auth(&dec_id_str, "GET", "/non/object", Some(&token), "127.0.0.1")
+            .unwrap();
+
+        // 同一个凭证不能重放
+        assert!(list
+            .check_local_auth(&dec_id_str, "GET", "/non/object", Some(&token), "127.0.0.1")
+            .is_err());
+    }
+}
@@ -10,6 +10,9 @@ pub(crate) struct AppLocalStateSavedData {
     pub name: String,
     pub dec_id: String,
     pub ip: String,
+
+    #[serde(default)]
+    pub public_key: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -162,6 +162,15 @@ impl ObjectVerifier {
 
         let mut verify_result = VerifyObjectResult::default();
 
+        // 被owner吊销的device，它的密钥产生的签名都不再有效
+        if self.is_sign_object_revoked(&sign_object).await {
+            warn!(
+                "sign object already been revoked by owner! obj={}, sign_obj={}",
+                req.object_id, sign_object.object_id
+            );
+            return Ok(verify_result);
+        }
+
         let valid = match pk {
             PublicKeyRef::Single(pk) => {
                 let verifier = self.new_verifier(pk);
@@ -191,6 +200,32 @@ impl ObjectVerifier {
         Ok(verify_result)
     }
 
+    async fn is_sign_object_revoked(&self, sign_object: &ObjectInfo) -> bool {
+        let device = match sign_object.object.as_ref() {
+            AnyNamedObject::Standard(StandardObject::Device(device)) => device,
+            _ => return false,
+        };
+
+        let owner_id = match device.desc().owner() {
+            Some(owner) if owner.obj_type_code() == ObjectTypeCode::People => owner.to_owned(),
+            _ => return false,
+        };
+
+        match self.search_object(&owner_id).await {
+            Ok(AnyNamedObject::Standard(StandardObject::People(people))) => {
+                people.is_device_revoked(&device.desc().device_id())
+            }
+            Ok(_) => false,
+            Err(e) => {
+                warn!(
+                    "search sign device's owner failed, revoke list will not be checked! device={}, owner={}, {}",
+                    sign_object.object_id, owner_id, e
+                );
+                false
+            }
+        }
+    }
+
     fn new_verifier(&self, pk: &PublicKey) -> Box<dyn Verifier> {
         let verifier = RsaCPUObjectVerifier::new(pk.clone());
         Box::new(verifier) as Box<dyn Verifier>
@@ -46,7 +46,15 @@ impl RouterEventWebSocketHandler {
                     let req = RouterWSAddEventParam::decode_string(&content)?;
 
                     if let Some(auth) = auth {
-                        auth.check_option_dec(req.dec_id.as_ref(), &source)?;
+                        let path =
+                            DecAuthToken::ws_path("add_event", &req.category.to_string(), &req.id);
+                        auth.check_option_dec(
+                            req.dec_id.as_ref(),
+                            CYFS_DEC_AUTH_WS_METHOD,
+                            &path,
+                            req.dec_auth.as_deref(),
+                            &source,
+                        )?;
                     }
 
                     self.on_add_event_request(session_requestor, req)
@@ -58,7 +66,18 @@ impl RouterEventWebSocketHandler {
                     let req = RouterWSRemoveEventParam::decode_string(&content)?;
 
                     if let Some(auth) = auth {
-                        auth.check_option_dec(req.dec_id.as_ref(), &source)?;
+                        let path = DecAuthToken::ws_path(
+                            "remove_event",
+                            &req.category.to_string(),
+                            &req.id,
+                        );
+                        auth.check_option_dec(
+                            req.dec_id.as_ref(),
+                            CYFS_DEC_AUTH_WS_METHOD,
+                            &path,
+                            req.dec_auth.as_deref(),
+                            &source,
+                        )?;
                     }
 
                     self.on_remove_event_request(req).map(|v| Some(v))
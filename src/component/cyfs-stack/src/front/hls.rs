@@ -0,0 +1,200 @@
+use cyfs_base::*;
+use cyfs_lib::*;
+
+use std::fmt::Write;
+use std::ops::Range;
+
+// mpeg-ts的包长度，分段边界需要对齐到包
+const TS_PACKET_SIZE: u64 = 188;
+
+/*
+为已知分段方式的文件生成HLS播放列表
+1. 文件内容必须是mpeg-ts，按照chunk边界(对齐到ts包)切分为片段
+2. 片段使用EXT-X-BYTERANGE引用同一个文件，不需要额外存储片段文件
+3. 片段时长按码率恒定估算，总时长由请求通过hls参数指定
+*/
+#[derive(Clone, Debug)]
+pub(crate) struct FrontHlsParam {
+    // 文件的总时长，单位秒
+    pub duration: f64,
+}
+
+impl FrontHlsParam {
+    pub fn from_request(url: &http_types::Url) -> BuckyResult<Option<Self>> {
+        let duration: f64 = match RequestorHelper::value_from_querys("hls", url)? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        if !duration.is_finite() || duration <= 0.0 {
+            let msg = format!("invalid request url hls query param! {}", url);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        Ok(Some(Self { duration }))
+    }
+}
+
+pub(crate) struct FrontHlsPlaylist;
+
+impl FrontHlsPlaylist {
+    pub const CONTENT_TYPE: &'static str = "application/vnd.apple.mpegurl";
+
+    // 片段使用流式模式读取，去掉hls参数
+    pub fn segment_uri(url: &http_types::Url) -> String {
+        let mut querys: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(k, _)| k != "hls" && k != "stream" && k != "mode")
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+        querys.push(("mode".to_owned(), "data".to_owned()));
+        querys.push(("stream".to_owned(), "true".to_owned()));
+
+        let mut url = url.clone();
+        url.query_pairs_mut().clear().extend_pairs(querys);
+
+        match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_owned(),
+        }
+    }
+
+    pub fn segments(file_id: &ObjectId, file: &File) -> BuckyResult<Vec<Range<u64>>> {
+        let chunk_list = file
+            .body()
+            .as_ref()
+            .and_then(|body| body.content().inner_chunk_list())
+            .ok_or_else(|| {
+                let msg = format!("hls file object should has chunk list! file={}", file_id);
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::NotSupport, msg)
+            })?;
+
+        let len = file.desc().content().len();
+        let mut segments = vec![];
+        let mut start = 0;
+        let mut offset = 0;
+        for chunk in chunk_list {
+            offset += chunk.len() as u64;
+
+            // 最后一个片段包含剩余的全部内容
+            let end = if offset >= len {
+                len
+            } else {
+                offset / TS_PACKET_SIZE * TS_PACKET_SIZE
+            };
+            if end > start {
+                segments.push(start..end);
+                start = end;
+            }
+        }
+
+        Ok(segments)
+    }
+
+    pub fn generate(
+        file_id: &ObjectId,
+        file: &File,
+        param: &FrontHlsParam,
+        segment_uri: &str,
+    ) -> BuckyResult<String> {
+        let segments = Self::segments(file_id, file)?;
+        let len = file.desc().content().len();
+        if segments.is_empty() || len == 0 {
+            let msg = format!("hls file object is empty! file={}", file_id);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+        }
+
+        let durations: Vec<f64> = segments
+            .iter()
+            .map(|seg| param.duration * (seg.end - seg.start) as f64 / len as f64)
+            .collect();
+        let target_duration = durations.iter().cloned().fold(0.0, f64::max).ceil() as u64;
+
+        let mut playlist = String::new();
+        let _ = writeln!(playlist, "#EXTM3U");
+        let _ = writeln!(playlist, "#EXT-X-VERSION:4");
+        let _ = writeln!(playlist, "#EXT-X-TARGETDURATION:{}", target_duration.max(1));
+        let _ = writeln!(playlist, "#EXT-X-MEDIA-SEQUENCE:0");
+        let _ = writeln!(playlist, "#EXT-X-PLAYLIST-TYPE:VOD");
+        for (seg, duration) in segments.iter().zip(durations.iter()) {
+            let _ = writeln!(playlist, "#EXTINF:{:.3},", duration);
+            let _ = writeln!(playlist, "#EXT-X-BYTERANGE:{}@{}", seg.end - seg.start, seg.start);
+            let _ = writeln!(playlist, "{}", segment_uri);
+        }
+        let _ = writeln!(playlist, "#EXT-X-ENDLIST");
+
+        Ok(playlist)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playlist() {
+        let chunk_list = vec![
+            ChunkId::new(&HashValue::default(), 1000),
+            ChunkId::new(&HashValue::default(), 1000),
+            ChunkId::new(&HashValue::default(), 500),
+        ];
+        let file = File::new(
+            ObjectId::default(),
+            2500,
+            HashValue::default(),
+            ChunkList::ChunkInList(chunk_list),
+        )
+        .no_create_time()
+        .build();
+        let file_id = file.desc().calculate_id();
+
+        let segments = FrontHlsPlaylist::segments(&file_id, &file).unwrap();
+        assert_eq!(segments, vec![0..940, 940..1880, 1880..2500]);
+
+        let url = http_types::Url::parse(&format!(
+            "http://127.0.0.1/o/{}?hls=25&mode=object",
+            file_id
+        ))
+        .unwrap();
+        let uri = FrontHlsPlaylist::segment_uri(&url);
+        assert_eq!(uri, format!("/o/{}?mode=data&stream=true", file_id));
+
+        let param = FrontHlsParam::from_request(&url).unwrap().unwrap();
+        let playlist = FrontHlsPlaylist::generate(&file_id, &file, &param, &uri).unwrap();
+        let expected = format!(
+            "#EXTM3U\n\
+            #EXT-X-VERSION:4\n\
+            #EXT-X-TARGETDURATION:10\n\
+            #EXT-X-MEDIA-SEQUENCE:0\n\
+            #EXT-X-PLAYLIST-TYPE:VOD\n\
+            #EXTINF:9.400,\n\
+            #EXT-X-BYTERANGE:940@0\n\
+            {uri}\n\
+            #EXTINF:9.400,\n\
+            #EXT-X-BYTERANGE:940@940\n\
+            {uri}\n\
+            #EXTINF:6.200,\n\
+            #EXT-X-BYTERANGE:620@1880\n\
+            {uri}\n\
+            #EXT-X-ENDLIST\n",
+            uri = uri
+        );
+        assert_eq!(playlist, expected);
+
+        // 空文件不能生成播放列表
+        let empty = File::new(
+            ObjectId::default(),
+            0,
+            HashValue::default(),
+            ChunkList::ChunkInList(vec![]),
+        )
+        .no_create_time()
+        .build();
+        let empty_id = empty.desc().calculate_id();
+        let ret = FrontHlsPlaylist::generate(&empty_id, &empty, &param, &uri);
+        assert_eq!(ret.unwrap_err().code(), BuckyErrorCode::NotSupport);
+    }
+}
@@ -0,0 +1,320 @@
+use super::def::*;
+use super::request::*;
+use cyfs_lib::*;
+
+use cyfs_base::*;
+use http_types::conditional::{IfModifiedSince, LastModified};
+use http_types::headers::{CACHE_CONTROL, ETAG, EXPIRES, IF_NONE_MATCH, LAST_MODIFIED};
+use http_types::StatusCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// 内容寻址的数据和对象，url不变内容就不会变，可以一直缓存
+const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+// 可能变化的内容，每次使用前都需要通过etag重新验证
+const REVALIDATE_CACHE_CONTROL: &str = "no-cache";
+
+#[derive(Clone, Debug)]
+pub(crate) struct FrontETag {
+    // 带引号的强etag
+    pub value: String,
+
+    // 内容是否只由etag决定
+    pub immutable: bool,
+}
+
+impl FrontETag {
+    fn new(value: String, immutable: bool) -> Self {
+        Self {
+            value: format!("\"{}\"", value),
+            immutable,
+        }
+    }
+
+    // chunk和file都是内容寻址的，id就是强etag
+    pub fn from_data(resp: &NDNGetDataInputResponse) -> Self {
+        Self::new(resp.object_id.to_string(), true)
+    }
+
+    // 对象的body可能更新，但object_id不变，所以需要加上body的更新时间
+    pub fn from_object(resp: &NONGetObjectInputResponse, format: FrontRequestObjectFormat) -> Self {
+        let mut value = resp.object.object_id.to_string();
+        let mut immutable = true;
+        if let Some(update_time) = resp.object_update_time {
+            value = format!("{}-{}", value, update_time);
+            immutable = false;
+        }
+        if format == FrontRequestObjectFormat::Json {
+            value = format!("{}-json", value);
+        }
+
+        Self::new(value, immutable)
+    }
+
+    pub fn from_o_response(resp: &FrontOResponse, format: FrontRequestObjectFormat) -> Option<Self> {
+        if let Some(data) = &resp.data {
+            Some(Self::from_data(data))
+        } else if let Some(object) = &resp.object {
+            Some(Self::from_object(object, format))
+        } else {
+            None
+        }
+    }
+
+    // root-state的路径随时可能指向别的对象，内容只能重新验证
+    pub fn from_r_response(resp: &FrontRResponse, format: FrontRequestObjectFormat) -> Option<Self> {
+        let etag = if let Some(data) = &resp.data {
+            Self::from_data(data)
+        } else if let Some(object) = &resp.object {
+            Self::from_object(object, format)
+        } else {
+            return None;
+        };
+
+        Some(Self {
+            immutable: false,
+            ..etag
+        })
+    }
+}
+
+// 对象的更新时间作为Last-Modified
+pub(crate) fn last_modified_of_o_response(resp: &FrontOResponse) -> Option<u64> {
+    resp.object.as_ref().and_then(|object| object.object_update_time)
+}
+
+pub(crate) fn last_modified_of_r_response(resp: &FrontRResponse) -> Option<u64> {
+    resp.object.as_ref().and_then(|object| object.object_update_time)
+}
+
+/*
+基于etag和Last-Modified的http缓存处理
+1. 成功的响应都带上etag和cache-control，有更新时间的带上Last-Modified
+2. 请求带了If-None-Match并且匹配的，返回304；只在经过权限检查获取到内容之后才判断，避免泄露对象是否存在
+3. 没有If-None-Match时才检查If-Modified-Since
+*/
+pub(crate) struct FrontHttpCache {
+    if_none_match: Option<String>,
+    if_modified_since: Option<SystemTime>,
+
+    // url是否指向固定的内容，比如直接用object_id而不是name访问
+    immutable_url: bool,
+}
+
+impl FrontHttpCache {
+    pub fn new(req: &http_types::Request, immutable_url: bool) -> Self {
+        let if_none_match = req
+            .header(IF_NONE_MATCH)
+            .map(|values| values.iter().map(|v| v.as_str()).collect::<Vec<_>>().join(","));
+
+        // 格式错误的If-Modified-Since直接忽略
+        let if_modified_since = IfModifiedSince::from_headers(req)
+            .unwrap_or(None)
+            .map(|v| v.modified());
+
+        Self {
+            if_none_match,
+            if_modified_since,
+            immutable_url,
+        }
+    }
+
+    // https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/If-None-Match
+    // If-None-Match使用弱比较，忽略W/前缀
+    pub fn is_not_modified(&self, etag: &str) -> bool {
+        match &self.if_none_match {
+            Some(value) => value.split(',').map(|v| v.trim()).any(|v| {
+                v == "*" || v.trim_start_matches("W/") == etag.trim_start_matches("W/")
+            }),
+            None => false,
+        }
+    }
+
+    // http时间只精确到秒
+    pub fn is_modified_since(&self, last_modified: u64) -> bool {
+        let since = match &self.if_modified_since {
+            Some(since) => since,
+            None => return true,
+        };
+
+        let secs = |time: &SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .unwrap_or(Duration::from_secs(0))
+                .as_secs()
+        };
+
+        secs(&bucky_time_to_system_time(last_modified)) > secs(since)
+    }
+
+    fn cache_control(&self, etag: &FrontETag) -> &'static str {
+        if self.immutable_url && etag.immutable {
+            IMMUTABLE_CACHE_CONTROL
+        } else {
+            REVALIDATE_CACHE_CONTROL
+        }
+    }
+
+    pub fn apply(
+        &self,
+        etag: Option<FrontETag>,
+        last_modified: Option<u64>,
+        mut resp: tide::Response,
+    ) -> tide::Response {
+        if !resp.status().is_success() {
+            return resp;
+        }
+
+        if let Some(etag) = &etag {
+            resp.insert_header(ETAG, etag.value.as_str());
+            resp.insert_header(CACHE_CONTROL, self.cache_control(etag));
+        }
+        if let Some(last_modified) = last_modified {
+            LastModified::new(bucky_time_to_system_time(last_modified)).apply(&mut resp);
+        }
+
+        // https://www.rfc-editor.org/rfc/rfc9110#section-13.2.2
+        // 有If-None-Match时忽略If-Modified-Since
+        let not_modified = if self.if_none_match.is_some() {
+            match &etag {
+                Some(etag) => self.is_not_modified(&etag.value),
+                None => false,
+            }
+        } else {
+            match last_modified {
+                Some(last_modified) => !self.is_modified_since(last_modified),
+                None => false,
+            }
+        };
+
+        if not_modified {
+            info!(
+                "front request not modified! etag={:?}, last-modified={:?}, if-none-match={:?}, if-modified-since={:?}",
+                etag.as_ref().map(|v| &v.value), last_modified, self.if_none_match, self.if_modified_since
+            );
+            return Self::new_not_modified_response(&resp);
+        }
+
+        resp
+    }
+
+    fn new_not_modified_response(resp: &tide::Response) -> tide::Response {
+        let mut not_modified = tide::Response::new(StatusCode::NotModified);
+
+        // 304响应需要保留缓存相关的header
+        let names = [
+            ETAG,
+            CACHE_CONTROL,
+            LAST_MODIFIED,
+            EXPIRES,
+            cyfs_base::CYFS_OBJECT_ID.into(),
+        ];
+        for name in names {
+            if let Some(values) = resp.header(&name) {
+                let value = values.last().as_str().to_owned();
+                not_modified.insert_header(name, value);
+            }
+        }
+
+        not_modified
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_request() -> http_types::Request {
+        http_types::Request::new(
+            http_types::Method::Get,
+            http_types::Url::parse("http://127.0.0.1/test").unwrap(),
+        )
+    }
+
+    fn new_cache(if_none_match: Option<&str>, immutable_url: bool) -> FrontHttpCache {
+        let mut req = new_request();
+        if let Some(v) = if_none_match {
+            req.insert_header(IF_NONE_MATCH, v);
+        }
+
+        FrontHttpCache::new(&req, immutable_url)
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let cache = new_cache(Some("\"a\", W/\"b\""), true);
+        assert!(cache.is_not_modified("\"a\""));
+        assert!(cache.is_not_modified("\"b\""));
+        assert!(!cache.is_not_modified("\"c\""));
+
+        let cache = new_cache(Some("*"), true);
+        assert!(cache.is_not_modified("\"c\""));
+
+        let cache = new_cache(None, true);
+        assert!(!cache.is_not_modified("\"a\""));
+    }
+
+    #[test]
+    fn test_apply() {
+        let etag = FrontETag::new("a".to_owned(), true);
+
+        let cache = new_cache(None, true);
+        let resp = cache.apply(Some(etag.clone()), None, tide::Response::new(StatusCode::Ok));
+        assert_eq!(resp.header(ETAG).unwrap().as_str(), "\"a\"");
+        assert_eq!(resp.header(CACHE_CONTROL).unwrap().as_str(), IMMUTABLE_CACHE_CONTROL);
+
+        let cache = new_cache(None, false);
+        let resp = cache.apply(Some(etag.clone()), None, tide::Response::new(StatusCode::Ok));
+        assert_eq!(resp.header(CACHE_CONTROL).unwrap().as_str(), REVALIDATE_CACHE_CONTROL);
+
+        let cache = new_cache(Some("\"a\""), true);
+        let mut resp = tide::Response::new(StatusCode::PartialContent);
+        resp.insert_header(LAST_MODIFIED, "Mon, 01 Jan 2024 00:00:00 +0000");
+        resp.insert_header(EXPIRES, "Mon, 01 Jan 2024 00:00:00 +0000");
+        let resp = cache.apply(Some(etag.clone()), None, resp);
+        assert_eq!(resp.status(), StatusCode::NotModified);
+        assert_eq!(resp.header(ETAG).unwrap().as_str(), "\"a\"");
+        assert!(resp.header(LAST_MODIFIED).is_some());
+
+        let resp = cache.apply(Some(etag), None, tide::Response::new(StatusCode::NotFound));
+        assert_eq!(resp.status(), StatusCode::NotFound);
+        assert!(resp.header(ETAG).is_none());
+    }
+
+    #[test]
+    fn test_if_modified_since() {
+        // 2024-01-01 00:00:00 UTC
+        let since = UNIX_EPOCH + Duration::from_secs(1704067200);
+        let to_bucky_time = |time: SystemTime| {
+            unix_time_to_bucky_time(time.duration_since(UNIX_EPOCH).unwrap().as_micros() as u64)
+        };
+
+        let mut req = new_request();
+        IfModifiedSince::new(since).apply(&mut req);
+        let cache = FrontHttpCache::new(&req, false);
+
+        // 同一秒内的更新不算修改
+        let last_modified = to_bucky_time(since + Duration::from_millis(500));
+        assert!(!cache.is_modified_since(last_modified));
+        let resp = cache.apply(None, Some(last_modified), tide::Response::new(StatusCode::Ok));
+        assert_eq!(resp.status(), StatusCode::NotModified);
+        assert!(resp.header(LAST_MODIFIED).is_some());
+
+        let last_modified = to_bucky_time(since + Duration::from_secs(1));
+        assert!(cache.is_modified_since(last_modified));
+        let resp = cache.apply(None, Some(last_modified), tide::Response::new(StatusCode::Ok));
+        assert_eq!(resp.status(), StatusCode::Ok);
+        let value = LastModified::from_headers(resp.as_ref()).unwrap().unwrap();
+        assert_eq!(value.modified(), since + Duration::from_secs(1));
+
+        // If-None-Match优先，不匹配时忽略If-Modified-Since
+        req.insert_header(IF_NONE_MATCH, "\"b\"");
+        let cache = FrontHttpCache::new(&req, false);
+        let etag = FrontETag::new("a".to_owned(), false);
+        let resp = cache.apply(Some(etag), Some(to_bucky_time(since)), tide::Response::new(StatusCode::Ok));
+        assert_eq!(resp.status(), StatusCode::Ok);
+
+        // 没有If-Modified-Since时都认为已修改
+        let cache = new_cache(None, false);
+        assert!(cache.is_modified_since(to_bucky_time(since)));
+    }
+}
@@ -61,6 +61,14 @@ impl FrontRequestHandlerEndpoint {
             handler.clone(),
         ));
 
+        // build object from json
+        server.at("/o").post(FrontRequestHandlerEndpoint::new(
+            zone_manager.clone(),
+            protocol.to_owned(),
+            FrontRequestType::O,
+            handler.clone(),
+        ));
+
         // r protocol
         server.at("/r/*must").get(FrontRequestHandlerEndpoint::new(
             zone_manager.clone(),
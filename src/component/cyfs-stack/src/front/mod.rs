@@ -1,4 +1,6 @@
 mod def;
+mod hls;
+mod http_cache;
 mod listener;
 mod protocol;
 mod request;
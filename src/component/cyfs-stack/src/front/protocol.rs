@@ -1,4 +1,6 @@
 use super::def::*;
+use super::hls::*;
+use super::http_cache::*;
 use super::http_request::FrontInputHttpRequest;
 use super::listener::FrontRequestType;
 use super::request::*;
@@ -308,6 +310,18 @@ impl FrontProtocolHandler {
         }
     }
 
+    fn stream_from_request(url: &http_types::Url) -> BuckyResult<bool> {
+        match RequestorHelper::value_from_querys("stream", url) {
+            Ok(Some(v)) => Ok(v),
+            Ok(None) => Ok(false),
+            Err(e) => {
+                let msg = format!("invalid request url stream query param! {}, {}", url, e);
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg))
+            }
+        }
+    }
+
     fn referer_objects_from_request(
         url: &http_types::Url,
     ) -> BuckyResult<Vec<NDNDataRefererObject>> {
@@ -397,6 +411,17 @@ impl FrontProtocolHandler {
         Ok(segs)
     }
 
+    // 第一段直接使用object_id而不是name时，url指向的内容是固定的
+    fn is_immutable_route(route_param: &str) -> bool {
+        let root = route_param
+            .trim_start_matches('/')
+            .split('/')
+            .next()
+            .unwrap_or("");
+
+        !root.is_empty() && root.split(',').all(|item| item.len() > CYFS_NAME_MAX_LENGTH)
+    }
+
     fn gen_inner_path(segs: &[&str]) -> String {
         let path = segs.join("/");
         format!("/{}", path)
@@ -422,26 +447,34 @@ impl FrontProtocolHandler {
 
         match req_type {
             FrontRequestType::O => {
-                let route_param = Self::extract_route_param(&req.request)?;
-                let resp = self.process_o_request(req, route_param, format).await?;
+                if req.request.method() == http_types::Method::Post {
+                    return self.process_build_request_and_encode(req, format).await;
+                }
 
-                let http_resp = self.encode_o_response(resp, format).await;
-                Ok(http_resp)
+                let route_param = Self::extract_route_param(&req.request)?;
+                self.process_o_request_and_encode(req, route_param, format)
+                    .await
             }
             FrontRequestType::R | FrontRequestType::L => {
                 let route_param = Self::extract_route_param(&req.request)?;
+                let cache = FrontHttpCache::new(req.request.as_ref(), false);
                 let resp = self.process_r_request(req_type, req, route_param).await?;
 
+                let etag = FrontETag::from_r_response(&resp, format);
+                let last_modified = last_modified_of_r_response(&resp);
                 let http_resp = self.encode_r_response(resp, format).await;
-                Ok(http_resp)
+                Ok(cache.apply(etag, last_modified, http_resp))
             }
             FrontRequestType::A => {
                 let route_param = Self::extract_route_param(&req.request)?;
                 let is_cyfs_browser = Self::is_cyfs_browser(&req.request.as_ref());
+                let cache = FrontHttpCache::new(req.request.as_ref(), false);
                 let resp = self.process_a_request(req, route_param, format).await?;
 
+                let etag = Self::etag_of_a_response(&resp, format);
+                let last_modified = Self::last_modified_of_a_response(&resp);
                 let http_resp = self.encode_a_response(resp, format, is_cyfs_browser).await;
-                Ok(http_resp)
+                Ok(cache.apply(etag, last_modified, http_resp))
             }
             FrontRequestType::Any => {
                 let route_param = Self::extract_option_route_param(&req.request)?;
@@ -515,22 +548,27 @@ impl FrontProtocolHandler {
 
         match req_type {
             FrontRequestType::O => {
-                let resp = self.process_o_request(req, req_route_param, format).await?;
-                let http_resp = self.encode_o_response(resp, format).await;
-                Ok(http_resp)
+                self.process_o_request_and_encode(req, req_route_param, format)
+                    .await
             }
             FrontRequestType::A => {
                 let is_cyfs_browser = Self::is_cyfs_browser(&req.request.as_ref());
+                let cache = FrontHttpCache::new(req.request.as_ref(), false);
                 let resp = self.process_a_request(req, req_route_param, format).await?;
+                let etag = Self::etag_of_a_response(&resp, format);
+                let last_modified = Self::last_modified_of_a_response(&resp);
                 let http_resp = self.encode_a_response(resp, format, is_cyfs_browser).await;
-                Ok(http_resp)
+                Ok(cache.apply(etag, last_modified, http_resp))
             }
             FrontRequestType::R | FrontRequestType::L => {
+                let cache = FrontHttpCache::new(req.request.as_ref(), false);
                 let resp = self
                     .process_r_request(req_type, req, req_route_param)
                     .await?;
+                let etag = FrontETag::from_r_response(&resp, format);
+                let last_modified = last_modified_of_r_response(&resp);
                 let http_resp = self.encode_r_response(resp, format).await;
-                Ok(http_resp)
+                Ok(cache.apply(etag, last_modified, http_resp))
             }
             FrontRequestType::Any => {
                 unreachable!()
@@ -538,12 +576,77 @@ impl FrontProtocolHandler {
         }
     }
 
-    async fn process_o_request<State>(
+    // POST /o，body为ObjectJsonBuildParam的json编码
+    async fn process_build_request_and_encode<State>(
+        &self,
+        mut req: FrontInputHttpRequest<State>,
+        format: FrontRequestObjectFormat,
+    ) -> BuckyResult<tide::Response> {
+        let body = req.request.body_string().await.map_err(|e| {
+            let msg = format!(
+                "read front build request body error! url={}, {}",
+                req.request.url(),
+                e
+            );
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::IoError, msg)
+        })?;
+
+        let param = ObjectJsonBuildParam::decode_string(&body)?;
+        let req_path = Self::req_path_from_request(req.request.url())?;
+        let flags = Self::flags_from_request(req.request.url())?;
+
+        let build_req = FrontBuildRequest {
+            source: req.source,
+            req_path,
+            param,
+            flags,
+        };
+
+        let resp = self.service.process_build_request(build_req).await?;
+
+        // 默认返回对象的json视图
+        let format = match format {
+            FrontRequestObjectFormat::Default => FrontRequestObjectFormat::Json,
+            _ => format,
+        };
+
+        Ok(NONRequestHandler::encode_get_object_response(resp, format))
+    }
+
+    async fn process_o_request_and_encode<State>(
+        &self,
+        req: FrontInputHttpRequest<State>,
+        route_param: String,
+        format: FrontRequestObjectFormat,
+    ) -> BuckyResult<tide::Response> {
+        let url = req.request.url().clone();
+        let hls = FrontHlsParam::from_request(&url)?;
+        let cache = FrontHttpCache::new(
+            req.request.as_ref(),
+            Self::is_immutable_route(&route_param),
+        );
+
+        let o_req = self.build_o_request(req, route_param, format).await?;
+
+        // 条件请求也必须先经过non/ndn的权限检查，否则无权限的请求可以通过304探测对象是否存在
+        let resp = self.service.process_o_request(o_req).await?;
+        if let Some(hls) = hls {
+            return Self::encode_hls_response(resp, &hls, &url);
+        }
+
+        let etag = FrontETag::from_o_response(&resp, format);
+        let last_modified = last_modified_of_o_response(&resp);
+        let http_resp = self.encode_o_response(resp, format).await;
+        Ok(cache.apply(etag, last_modified, http_resp))
+    }
+
+    async fn build_o_request<State>(
         &self,
         req: FrontInputHttpRequest<State>,
         route_param: String,
         format: FrontRequestObjectFormat,
-    ) -> BuckyResult<FrontOResponse> {
+    ) -> BuckyResult<FrontORequest> {
         let segs = Self::parse_url_segs(&route_param)?;
         let url = req.request.url();
 
@@ -555,10 +658,16 @@ impl FrontProtocolHandler {
             return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
         }
 
-        let mode = Self::mode_from_request(url)?;
+        // hls播放列表由文件对象生成，不需要获取文件数据
+        let mode = if FrontHlsParam::from_request(url)?.is_some() {
+            FrontRequestGetMode::Object
+        } else {
+            Self::mode_from_request(url)?
+        };
         let flags = Self::flags_from_request(url)?;
 
         let range = Self::range_from_request(req.request.as_ref())?;
+        let stream = Self::stream_from_request(url)?;
 
         let referer_objects = Self::referer_objects_from_request(&url)?;
         let context = Self::context_from_request(&url)?;
@@ -604,6 +713,7 @@ impl FrontProtocolHandler {
 
                     mode,
                     format,
+                    stream,
 
                     referer_objects,
                     context,
@@ -639,6 +749,7 @@ impl FrontProtocolHandler {
 
                     mode,
                     format,
+                    stream,
 
                     referer_objects,
                     context,
@@ -649,7 +760,7 @@ impl FrontProtocolHandler {
             }
         };
 
-        self.service.process_o_request(o_req).await
+        Ok(o_req)
     }
 
     fn parse_dec_seg(
@@ -1014,6 +1125,43 @@ impl FrontProtocolHandler {
         self.service.process_a_request(a_req).await
     }
 
+    // 多个range的请求，按照multipart/byteranges格式返回
+    fn encode_data_response(resp: NDNGetDataInputResponse) -> tide::Response {
+        let multi_ranges = match &resp.range {
+            Some(NDNDataResponseRange::Range((ranges, size))) if ranges.len() > 1 => {
+                Some((ranges.clone(), *size))
+            }
+            _ => None,
+        };
+
+        let mut http_resp = NDNRequestHandler::encode_get_data_response(resp);
+        if let Some((ranges, size)) = multi_ranges {
+            if http_resp.status().is_success() {
+                RequestorRangeHelper::encode_multipart_byteranges(http_resp.as_mut(), &ranges, size);
+            }
+        }
+
+        http_resp
+    }
+
+    fn etag_of_a_response(resp: &FrontAResponse, format: FrontRequestObjectFormat) -> Option<FrontETag> {
+        match resp {
+            // dec app的当前版本会变化，不能作为固定内容缓存
+            FrontAResponse::Response(o_resp) => FrontETag::from_o_response(o_resp, format).map(|etag| FrontETag {
+                immutable: false,
+                ..etag
+            }),
+            FrontAResponse::Redirect(_) => None,
+        }
+    }
+
+    fn last_modified_of_a_response(resp: &FrontAResponse) -> Option<u64> {
+        match resp {
+            FrontAResponse::Response(o_resp) => last_modified_of_o_response(o_resp),
+            FrontAResponse::Redirect(_) => None,
+        }
+    }
+
     async fn encode_o_response(
         &self,
         resp: FrontOResponse,
@@ -1021,7 +1169,7 @@ impl FrontProtocolHandler {
     ) -> tide::Response {
         match resp.data {
             Some(data_resp) => {
-                let mut http_resp = NDNRequestHandler::encode_get_data_response(data_resp);
+                let mut http_resp = Self::encode_data_response(data_resp);
 
                 if let Some(object_resp) = resp.object {
                     NONRequestHandler::encode_get_object_response_times(
@@ -1039,13 +1187,43 @@ impl FrontProtocolHandler {
         }
     }
 
+    fn encode_hls_response(
+        resp: FrontOResponse,
+        param: &FrontHlsParam,
+        url: &http_types::Url,
+    ) -> BuckyResult<tide::Response> {
+        let object = match resp.object {
+            Some(object_resp) if object_resp.object.object_id.obj_type_code() == ObjectTypeCode::File => {
+                object_resp.object
+            }
+            _ => {
+                let msg = format!("hls playlist only support file object! url={}", url);
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+            }
+        };
+
+        let file: AnyNamedObject = object.object.as_ref().unwrap().clone().into();
+        let file = file.into_file();
+
+        let segment_uri = FrontHlsPlaylist::segment_uri(url);
+        let playlist = FrontHlsPlaylist::generate(&object.object_id, &file, param, &segment_uri)?;
+
+        let mut http_resp = RequestorHelper::new_response(http_types::StatusCode::Ok);
+        http_resp.insert_header(cyfs_base::CYFS_OBJECT_ID, object.object_id.to_string());
+        http_resp.set_body(playlist);
+        http_resp.set_content_type(FrontHlsPlaylist::CONTENT_TYPE);
+
+        Ok(http_resp.into())
+    }
+
     async fn encode_r_response(
         &self,
         resp: FrontRResponse,
         format: FrontRequestObjectFormat,
     ) -> tide::Response {
         let mut http_resp = if let Some(data_resp) = resp.data {
-            let mut http_resp = NDNRequestHandler::encode_get_data_response(data_resp);
+            let mut http_resp = Self::encode_data_response(data_resp);
 
             if let Some(object_resp) = resp.object {
                 NONRequestHandler::encode_get_object_response_times(
@@ -21,6 +21,9 @@ pub struct FrontORequest {
     pub mode: FrontRequestGetMode,
     pub format: FrontRequestObjectFormat,
 
+    // 流式读取文件数据，比如播放视频
+    pub stream: bool,
+
     pub flags: u32,
 }
 
@@ -29,6 +32,18 @@ pub struct FrontOResponse {
     pub data: Option<NDNGetDataInputResponse>,
 }
 
+// 由json构造core对象，使用当前协议栈的device签名后保存
+#[derive(Clone, Debug)]
+pub struct FrontBuildRequest {
+    pub source: RequestSourceInfo,
+
+    pub req_path: Option<String>,
+
+    pub param: ObjectJsonBuildParam,
+
+    pub flags: u32,
+}
+
 #[derive(Clone, Debug)]
 pub struct FrontRRequest {
     // 来源信息
@@ -80,6 +95,8 @@ pub struct FrontNDNRequest {
     pub context: Option<String>,
     pub group: Option<String>,
 
+    pub stream: bool,
+
     pub flags: u32,
 }
 
@@ -96,6 +113,7 @@ impl FrontNDNRequest {
             referer_objects: req.referer_objects,
             context: req.context,
             group: req.group,
+            stream: req.stream,
             flags: req.flags,
         }
     }
@@ -127,6 +145,7 @@ impl FrontNDNRequest {
             referer_objects,
             context: req.context,
             group: req.group,
+            stream: req.stream,
             flags: req.flags,
         }
     }
@@ -149,6 +168,7 @@ impl FrontNDNRequest {
             referer_objects: vec![],
             context: req.context,
             group: req.group,
+            stream: false,
             flags: req.flags,
         }
     }
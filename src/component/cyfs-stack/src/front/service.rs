@@ -2,14 +2,22 @@ use super::def::*;
 use super::request::*;
 use crate::app::AppInstallStatus;
 use crate::app::AppService;
+use crate::crypto::CryptoInputProcessorRef;
 use crate::ndn::NDNInputProcessorRef;
-use crate::ndn_api::NDNForwardObjectData;
+use crate::ndn_api::{NDNForwardObjectData, NDNForwardStreamParam};
 use crate::non::NONInputProcessorRef;
 use crate::resolver::OodResolver;
 use crate::root_state::GlobalStateAccessorInputProcessorRef;
 use cyfs_base::*;
+use cyfs_bdt::DownloadTaskPriority;
 use cyfs_lib::*;
 
+// 流式读取时预先下载的chunk数
+const FRONT_STREAM_PREFETCH_CHUNKS: usize = 2;
+
+// 流式读取期望的最低速度，单位byte/s
+const FRONT_STREAM_MIN_SPEED: u32 = 1024 * 1024;
+
 enum GlobalStateResponse {
     Object(RootStateAccessorGetObjectByPathInputResponse),
     List(RootStateAccessorListInputResponse),
@@ -18,6 +26,7 @@ enum GlobalStateResponse {
 pub(crate) struct FrontService {
     non: NONInputProcessorRef,
     ndn: NDNInputProcessorRef,
+    crypto: CryptoInputProcessorRef,
 
     root_state: GlobalStateAccessorInputProcessorRef,
     local_cache: GlobalStateAccessorInputProcessorRef,
@@ -31,6 +40,7 @@ impl FrontService {
     pub fn new(
         non: NONInputProcessorRef,
         ndn: NDNInputProcessorRef,
+        crypto: CryptoInputProcessorRef,
         root_state: GlobalStateAccessorInputProcessorRef,
         local_cache: GlobalStateAccessorInputProcessorRef,
         app: AppService,
@@ -39,6 +49,7 @@ impl FrontService {
         Self {
             non,
             ndn,
+            crypto,
             root_state,
             local_cache,
             app,
@@ -91,6 +102,70 @@ impl FrontService {
         Ok(resp)
     }
 
+    pub async fn process_build_request(
+        &self,
+        req: FrontBuildRequest,
+    ) -> BuckyResult<NONGetObjectInputResponse> {
+        info!("will process build request: {:?}", req);
+
+        let mut param = req.param;
+        if param.dec_id.is_none() {
+            param.dec_id = req.source.get_opt_dec().cloned();
+        }
+
+        let object_raw = BUILDER_FACTORY.build(&param)?;
+        let object = NONObjectInfo::new_from_object_raw(object_raw)?;
+
+        // 使用当前device签名desc，受crypto的权限控制
+        let sign_req = CryptoSignObjectInputRequest {
+            common: CryptoInputRequestCommon {
+                req_path: req.req_path.clone(),
+                source: req.source.clone(),
+                target: None,
+                flags: req.flags,
+            },
+            object,
+            flags: CRYPTO_REQUEST_FLAG_SIGN_BY_DEVICE | CRYPTO_REQUEST_FLAG_SIGN_PUSH_DESC,
+        };
+
+        let sign_resp = self.crypto.sign_object(sign_req).await?;
+        let object = match sign_resp.object {
+            Some(object) => object,
+            None => {
+                let msg = format!(
+                    "sign object built from json but not signed! obj_type={}, result={}",
+                    param.object_type,
+                    sign_resp.result.to_string(),
+                );
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::Pending, msg));
+            }
+        };
+
+        let put_req = NONPutObjectInputRequest {
+            common: NONInputRequestCommon {
+                req_path: req.req_path,
+                source: req.source,
+                level: NONAPILevel::Router,
+                target: None,
+                flags: req.flags,
+            },
+            object: object.clone(),
+            access: None,
+        };
+
+        let put_resp = self.non.put_object(put_req).await?;
+        info!(
+            "put object built from json success! object={}, result={:?}",
+            object.object_id, put_resp.result
+        );
+
+        let mut resp = NONGetObjectInputResponse::new_with_object(object);
+        resp.init_times()?;
+
+        Ok(resp)
+    }
+
     async fn process_get_object(
         &self,
         req: FrontORequest,
@@ -121,6 +196,7 @@ impl FrontService {
             common,
             object_id: req.object_id,
             inner_path: req.inner_path,
+            projection: None,
         };
 
         self.non.get_object(non_req).await
@@ -167,10 +243,13 @@ impl FrontService {
         let file: AnyNamedObject = req.object.object.as_ref().unwrap().clone().into();
         let file = file.into_file();
 
-        let data = NDNForwardObjectData {
-            file,
-            file_id: req.object.object_id.clone(),
-        };
+        let mut data = NDNForwardObjectData::new(file, req.object.object_id.clone());
+        if req.stream {
+            data.stream = Some(NDNForwardStreamParam {
+                priority: DownloadTaskPriority::Realtime(FRONT_STREAM_MIN_SPEED),
+                prefetch: FRONT_STREAM_PREFETCH_CHUNKS,
+            });
+        }
 
         // FIXME how to decide the file target? and multi target support
         let target = if req.target.len() > 0 {
@@ -476,6 +555,7 @@ impl FrontService {
 
                             mode: req.mode,
                             format: req.format,
+                            stream: false,
 
                             referer_objects: req.referer_objects,
                             context: req.context,
@@ -518,6 +598,7 @@ impl FrontService {
 
                             mode: req.mode,
                             format: req.format,
+                            stream: false,
 
                             referer_objects: req.referer_objects,
                             context: req.context,
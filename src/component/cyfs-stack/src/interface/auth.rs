@@ -5,16 +5,37 @@ use cyfs_base::*;
 #[derive(Clone)]
 pub struct InterfaceAuth {
     auth_app_list: AuthenticatedAppList,
+
+    // 本地127.0.0.1接口：没有注册的dec(系统服务和开发工具)不持有密钥，不做校验
+    local: bool,
 }
 
 impl InterfaceAuth {
+    // docker网桥上的鉴权接口，只允许已注册的app访问
     pub(crate) fn new(auth_app_list: AuthenticatedAppList) -> Self {
         Self {
             auth_app_list,
+            local: false,
         }
     }
 
-    pub fn check_dec(&self, dec_id: &ObjectId, source: &HttpRequestSource)-> BuckyResult<()> {
+    // 本地http/ws接口，已注册app的dec必须携带凭证
+    pub(crate) fn new_local(auth_app_list: AuthenticatedAppList) -> Self {
+        Self {
+            auth_app_list,
+            local: true,
+        }
+    }
+
+    // token为请求携带的dec身份凭证，绑定到请求的method和path
+    pub fn check_dec(
+        &self,
+        dec_id: &ObjectId,
+        method: &str,
+        path: &str,
+        token: Option<&str>,
+        source: &HttpRequestSource,
+    ) -> BuckyResult<()> {
         let addr = match source {
             HttpRequestSource::Remote((device_id, _)) => {
                 device_id.to_string()
@@ -25,17 +46,34 @@ impl InterfaceAuth {
         };
 
         let dec_id_str = dec_id.to_string();
-        self.auth_app_list.check_auth(&dec_id_str, &addr)
+        if self.local {
+            self.auth_app_list
+                .check_local_auth(&dec_id_str, method, path, token, &addr)
+        } else {
+            self.auth_app_list
+                .check_auth(&dec_id_str, method, path, token, &addr)
+        }
     }
 
-    pub fn check_option_dec(&self, dec_id: Option<&ObjectId>, source: &HttpRequestSource)-> BuckyResult<()> {
-        match  dec_id {
-            Some(dec_id) => self.check_dec(dec_id, source,),
+    pub fn check_option_dec(
+        &self,
+        dec_id: Option<&ObjectId>,
+        method: &str,
+        path: &str,
+        token: Option<&str>,
+        source: &HttpRequestSource,
+    ) -> BuckyResult<()> {
+        match dec_id {
+            Some(dec_id) => self.check_dec(dec_id, method, path, token, source),
             None => {
+                if self.local {
+                    return Ok(());
+                }
+
                 let msg = format!("request's dec_id not specified! source={:?}", source);
                 error!("{}", msg);
                 Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg))
             }
         }
     }
-}
\ No newline at end of file
+}
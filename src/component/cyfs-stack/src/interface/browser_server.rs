@@ -31,6 +31,10 @@ enum RequestSourceString<'a> {
     Other,
 }
 
+// 沙盒已按origin校验过dec的浏览器请求，内层的dec凭证校验据此跳过
+#[derive(Clone)]
+pub(super) struct BrowserVerifiedRequest;
+
 pub(super) struct BrowserSanboxHttpServer {
     mode: BrowserSanboxMode,
     handler: HttpServerHandlerRef,
@@ -415,10 +419,14 @@ impl HttpServerHandler for BrowserSanboxHttpServer {
         mut req: http_types::Request,
     ) -> http_types::Result<http_types::Response> {
         if source.is_local() && req.method() != http_types::Method::Options {
+            let is_browser = matches!(Self::extract_source(&req), Ok(Some(_)));
             let ret = self.verify_dec(req);
             match ret {
                 Ok(mreq) => {
                     req = mreq;
+                    if is_browser {
+                        req.ext_mut().insert(BrowserVerifiedRequest);
+                    }
                 }
                 Err(e) => {
                     return Ok(RequestorHelper::trans_error(e));
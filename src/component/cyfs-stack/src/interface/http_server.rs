@@ -1,4 +1,5 @@
 use super::auth::InterfaceAuth;
+use super::browser_server::BrowserVerifiedRequest;
 use cyfs_base::*;
 use cyfs_lib::*;
 
@@ -158,10 +159,19 @@ impl AuthenticatedHttpServer {
         source: &HttpRequestSource,
         req: &mut http_types::Request,
     ) -> BuckyResult<()> {
-        // extract dec_id from headers, must been existed!
-        let dec_id: ObjectId = RequestorHelper::decode_header(req, cyfs_base::CYFS_DEC_ID)?;
+        // 浏览器请求的dec已经由沙盒按origin绑定，浏览器无法持有app密钥
+        if req.ext::<BrowserVerifiedRequest>().is_some() {
+            return Ok(());
+        }
+
+        // extract dec_id from headers or query pairs, header/query都要覆盖，避免绕过校验
+        let dec_id: Option<ObjectId> = RequestorHelper::dec_id_from_request(req)?;
+        let token: Option<String> = RequestorHelper::decode_optional_header(req, cyfs_base::CYFS_DEC_AUTH)?;
 
-        self.auth.check_dec(&dec_id, source)
+        let method = req.method().to_string();
+        let path = DecAuthToken::request_path(req.url());
+        self.auth
+            .check_option_dec(dec_id.as_ref(), &method, &path, token.as_deref(), source)
     }
 }
 
@@ -131,9 +131,13 @@ impl ObjectListenerManager {
         root_state: &GlobalStateService,
         local_cache: &GlobalStateLocalService,
         global_state_meta: &GlobalStateMetaService,
+        auth_app_list: &AuthenticatedAppList,
     ) {
         assert!(self.listeners.is_empty());
 
+        // 本地http/ws接口同样校验已注册app的dec凭证
+        let local_auth = InterfaceAuth::new_local(auth_app_list.clone());
+
         let default_handler = HttpDefaultHandler::default();
 
         // 首先初始化三个基础的http_server
@@ -175,7 +179,8 @@ impl ObjectListenerManager {
             );
 
             let raw_handler = RawHttpServer::new(server.into_server());
-            let http_server = DefaultHttpServer::new(raw_handler.into(), default_handler.clone());
+            let auth_handler = AuthenticatedHttpServer::new(raw_handler.into(), local_auth.clone());
+            let http_server = DefaultHttpServer::new(auth_handler.into(), default_handler.clone());
             let http_server = match config.get_stack_params().front.browser_mode {
                 BrowserSanboxMode::None => http_server.into(),
                 mode @ _ => {
@@ -244,7 +249,7 @@ impl ObjectListenerManager {
                 router_handlers.clone(),
                 router_events.clone(),
                 addr,
-                None,
+                Some(local_auth),
             );
             self.ws_event_interface = Some(ws_event_interface);
             self.router_events_manager = Some(router_events.clone());
@@ -47,7 +47,7 @@ impl MetaFailCacheImpl {
     }
 
     // 错误分两种，meta返回的正常错误，和异常错误，异常错误下需要采用规避错误
-    fn is_meta_error(error: &BuckyError) -> bool {
+    pub fn is_meta_error(error: &BuckyError) -> bool {
         match error.code() {
             BuckyErrorCode::NotFound | BuckyErrorCode::CodeError | BuckyErrorCode::MetaError(_) => {
                 true
@@ -146,6 +146,11 @@ impl MetaFailCache {
     pub fn get(&self, key: &MetaCacheKey) -> Option<BuckyError> {
         self.0.lock().unwrap().get(key)
     }
+
+    // 非meta返回的错误，说明meta链当前不可达
+    pub fn is_meta_error(error: &BuckyError) -> bool {
+        MetaFailCacheImpl::is_meta_error(error)
+    }
 }
 
 #[cfg(test)]
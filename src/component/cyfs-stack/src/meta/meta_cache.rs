@@ -1,4 +1,5 @@
 use cyfs_base::{AnyNamedObject, BuckyResult, ObjectId, NameInfo, NameState};
+use cyfs_lib::MetaCacheStatus;
 
 use async_trait::async_trait;
 use std::sync::Arc;
@@ -17,6 +18,9 @@ pub trait MetaCache: Sync + Send {
 
     async fn get_name(&self, name: &str) -> BuckyResult<Option<(NameInfo, NameState)>>;
 
+    // status of the local meta cache, include the persistent store and the sync state
+    fn get_status(&self) -> MetaCacheStatus;
+
     fn clone_meta(&self) -> Box<dyn MetaCache>;
 }
 
@@ -1,40 +1,164 @@
-use super::meta_cache::*;
-use cyfs_base::{
-    AnyNamedObject, BuckyError, BuckyErrorCode, BuckyResult, DeviceId, ObjectId, ObjectTypeCode,
-    RawDecode, RawEncode,
-};
+use super::fail_cache::MetaFailCache;
+use super::raw_meta::RawMetaCache;
+use super::store::MetaCacheStore;
+use cyfs_base::*;
 
-use cyfs_lib::*;
-use cyfs_noc::*;
-
-use async_trait::async_trait;
-use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
-#[derive(RawEncode, RawDecode)]
-struct SyncState {
-    last_check_time: u64,
-}
+// 启动后首次同步的延时，以及之后的同步间隔
+const META_SYNC_FIRST_DELAY: Duration = Duration::from_secs(60);
+const META_SYNC_INTERVAL: Duration = Duration::from_secs(60 * 30);
+
+// 超过一天没有更新的条目需要从链上刷新
+const META_SYNC_REFRESH_TIMEOUT_IN_MICRO_SECS: u64 = 1000 * 1000 * 60 * 60 * 24;
+
+// 超过三天没有成功更新的条目视为陈旧，通过ood status上报
+const META_SYNC_STALE_TIMEOUT_IN_MICRO_SECS: u64 = 1000 * 1000 * 60 * 60 * 24 * 3;
+
+// 每轮最多刷新的条目数
+const META_SYNC_BATCH_LIMIT: u32 = 64;
 
-struct MetaSync {
-    noc: NamedObjectCacheRef,
-    meta_cache: NamedObjectCacheRef,
-    state: NOCRawStorage<HashMap<ObjectId, SyncState>>,
+// 后台定期从meta链刷新持久化缓存里的条目
+pub(super) struct MetaSync {
+    cache: RawMetaCache,
+    store: Arc<MetaCacheStore>,
 }
 
 impl MetaSync {
-    pub fn new(noc: NamedObjectCacheRef, meta_cache: NamedObjectCacheRef) -> Self {
-        let state = NOCRawStorage::new("", noc.clone());
-        Self {
-            noc,
-            meta_cache,
-            state,
+    pub fn new(cache: RawMetaCache, store: Arc<MetaCacheStore>) -> Self {
+        Self { cache, store }
+    }
+
+    pub fn start(self) {
+        async_std::task::spawn(async move {
+            async_std::task::sleep(META_SYNC_FIRST_DELAY).await;
+
+            loop {
+                self.sync_once().await;
+
+                async_std::task::sleep(META_SYNC_INTERVAL).await;
+            }
+        });
+    }
+
+    async fn sync_once(&self) {
+        let now = bucky_time_now();
+        let update_before = now - META_SYNC_REFRESH_TIMEOUT_IN_MICRO_SECS;
+
+        if let Err(e) = self.refresh(update_before).await {
+            warn!("meta sync refresh stopped! {}", e);
+        }
+
+        if let Err(e) = self.store.purge_expired(now).await {
+            error!("purge expired items from meta cache store failed! {}", e);
+        }
+
+        let stale_before = now - META_SYNC_STALE_TIMEOUT_IN_MICRO_SECS;
+        match self.store.stat(stale_before).await {
+            Ok(stat) => {
+                info!(
+                    "meta sync complete: objects={}, names={}, stale={}, oldest={}",
+                    stat.object_count, stat.name_count, stat.stale_count, stat.oldest_update_time
+                );
+
+                self.cache.update_status(|status| {
+                    status.last_sync_time = bucky_time_now();
+                    status.object_count = stat.object_count;
+                    status.name_count = stat.name_count;
+                    status.stale_count = stat.stale_count;
+                    status.oldest_update_time = stat.oldest_update_time;
+                });
+            }
+            Err(e) => {
+                error!("stat meta cache store failed! {}", e);
+            }
+        }
+    }
+
+    // 链不可达时中止本轮刷新，等待下一轮
+    async fn refresh(&self, update_before: u64) -> BuckyResult<()> {
+        let list = self
+            .store
+            .list_stale_objects(update_before, META_SYNC_BATCH_LIMIT)
+            .await?;
+        for object_id in list {
+            if let Err(e) = self.cache.flush_object(&object_id).await {
+                if !MetaFailCache::is_meta_error(&e) {
+                    return Err(e);
+                }
+
+                warn!("meta sync refresh object failed! obj={}, {}", object_id, e);
+            }
+        }
+
+        let list = self
+            .store
+            .list_stale_names(update_before, META_SYNC_BATCH_LIMIT)
+            .await?;
+        for name in list {
+            if let Err(e) = self.cache.flush_name(&name).await {
+                if !MetaFailCache::is_meta_error(&e) {
+                    return Err(e);
+                }
+
+                warn!("meta sync refresh name failed! name={}, {}", name, e);
+            }
         }
+
+        Ok(())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta::raw_meta::tests::*;
+
+    #[async_std::test]
+    async fn test_sync_offline() {
+        let (cache, store) = new_offline_cache("sync").await;
+
+        let (object_id, object_raw) = new_people();
+        store.save_object(&object_id, Some(&object_raw), 0).await.unwrap();
+
+        let now = bucky_time_now();
+        let (not_found_id, _) = new_people();
+        store.save_object(&not_found_id, None, now).await.unwrap();
+
+        let sync = MetaSync::new(cache.clone(), store.clone());
+
+        // 链不可达时中止刷新，已有的条目保留
+        let e = sync.refresh(bucky_time_now() + 1).await.unwrap_err();
+        assert!(!MetaFailCache::is_meta_error(&e));
+        let item = store.get_object(&object_id).await.unwrap().unwrap();
+        assert_eq!(item.value, Some(object_raw));
+
+        sync.sync_once().await;
+
+        // 超时的不存在结果被清除，统计结果上报到状态
+        assert!(store.get_object(&not_found_id).await.unwrap().is_none());
+
+        let status = cache.get_status();
+        assert!(!status.online);
+        assert!(status.last_sync_time >= now);
+        assert_eq!(status.object_count, 1);
+        assert_eq!(status.name_count, 0);
+        assert_eq!(status.stale_count, 0);
+        assert_eq!(status.oldest_update_time, item.update_time);
+    }
+
+    #[async_std::test]
+    async fn test_refresh_nothing() {
+        let (cache, store) = new_offline_cache("sync-empty").await;
+
+        let (object_id, object_raw) = new_people();
+        store.save_object(&object_id, Some(&object_raw), 0).await.unwrap();
 
-    fn select(&self) {
-        let mut filter = NamedObjectCacheSelectObjectFilter::default();
-        filter.obj_type_code = Some(ObjectTypeCode::Device);
-        //self.noc.select_object(filter: &NamedObjectCacheSelectObjectFilter, opt: Option<NamedObjectCacheSelectObjectOption>)
+        // 没有到期的条目时不会访问meta链
+        let sync = MetaSync::new(cache.clone(), store);
+        sync.refresh(0).await.unwrap();
+        assert!(!cache.get_status().online);
+        assert_eq!(cache.get_status().last_fail_time, 0);
     }
 }
@@ -3,6 +3,8 @@ mod raw_meta;
 mod fail_handler;
 mod fail_cache;
 mod cache;
+mod store;
+mod meta_sync;
 
 pub(crate) use meta_cache::*;
 pub(crate) use raw_meta::*;
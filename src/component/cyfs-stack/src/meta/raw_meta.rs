@@ -1,7 +1,10 @@
 use super::cache::{MetaMemoryCacheForObject, MetaMemoryCacheForName};
 use super::fail_cache::*;
 use super::meta_cache::*;
+use super::meta_sync::MetaSync;
+use super::store::*;
 use cyfs_base::*;
+use cyfs_debug::Mutex;
 use cyfs_lib::*;
 use cyfs_meta_lib::{MetaClient, MetaClientHelper, MetaMinerTarget};
 
@@ -13,6 +16,9 @@ use std::sync::Arc;
 const OBJECT_CACHE_TIMEOUT_IN_SECS: u64 = 60 * 15;
 const NAME_CACHE_TIMEOUT_IN_SECS: u64 = 60 * 15;
 
+// Not found results in the persistent store are valid for one hour
+const NOT_FOUND_STORE_TIMEOUT_IN_MICRO_SECS: u64 = 1000 * 1000 * 60 * 60;
+
 #[derive(Clone)]
 pub(crate) struct RawMetaCache {
     noc: NamedObjectCacheRef,
@@ -25,27 +31,107 @@ pub(crate) struct RawMetaCache {
 
     // Error cache, avoid quickly initiating query operations to the chain in short time
     fail_cache: MetaFailCache,
+
+    // Persistent cache in sqlite, used when the meta chain is unreachable
+    store: Option<Arc<MetaCacheStore>>,
+
+    status: Arc<Mutex<MetaCacheStatus>>,
 }
 
 impl RawMetaCache {
-    pub fn new(target: MetaMinerTarget, noc: NamedObjectCacheRef) -> MetaCacheRef {
+    pub async fn new(target: MetaMinerTarget, noc: NamedObjectCacheRef, isolate: &str) -> MetaCacheRef {
         info!("raw meta cache: {}", target.to_string());
         let meta_client =
             MetaClient::new_target(target).with_timeout(std::time::Duration::from_secs(60 * 2));
 
-        let ret = Self {
+        // The persistent store is optional, the stack can still work without it
+        let store = match Self::open_store(isolate).await {
+            Ok(store) => Some(Arc::new(store)),
+            Err(e) => {
+                error!("open meta cache store failed! {}", e);
+                None
+            }
+        };
+
+        let ret = Self::new_with_store(meta_client, noc, store);
+
+        if let Some(store) = &ret.store {
+            MetaSync::new(ret.clone(), store.clone()).start();
+        }
+
+        Arc::new(Box::new(ret))
+    }
+
+    pub(super) fn new_with_store(
+        meta_client: MetaClient,
+        noc: NamedObjectCacheRef,
+        store: Option<Arc<MetaCacheStore>>,
+    ) -> Self {
+        Self {
             noc,
             meta_client: Arc::new(meta_client),
             device_id: DeviceId::default(),
 
-            
             object_memory_cache: MetaMemoryCacheForObject::new(OBJECT_CACHE_TIMEOUT_IN_SECS),
             name_memory_cache: MetaMemoryCacheForName::new(NAME_CACHE_TIMEOUT_IN_SECS),
 
             fail_cache: MetaFailCache::new(),
-        };
 
-        Arc::new(Box::new(ret))
+            store,
+            status: Arc::new(Mutex::new(MetaCacheStatus::default())),
+        }
+    }
+
+    async fn open_store(isolate: &str) -> BuckyResult<MetaCacheStore> {
+        let mut dir = cyfs_util::get_cyfs_root_path();
+        dir.push("data");
+        if isolate.len() > 0 {
+            dir.push(isolate);
+        }
+        dir.push("meta-cache");
+
+        if !dir.is_dir() {
+            if let Err(e) = std::fs::create_dir_all(&dir) {
+                let msg = format!(
+                    "create meta cache dir failed! dir={}, err={}",
+                    dir.display(),
+                    e
+                );
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::IoError, msg));
+            }
+        }
+
+        MetaCacheStore::open(&dir.join("meta.db")).await
+    }
+
+    pub fn get_status(&self) -> MetaCacheStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub(super) fn update_status(&self, f: impl FnOnce(&mut MetaCacheStatus)) {
+        f(&mut self.status.lock().unwrap())
+    }
+
+    fn on_meta_success(&self) {
+        let mut status = self.status.lock().unwrap();
+        status.online = true;
+        status.last_success_time = bucky_time_now();
+    }
+
+    fn on_meta_error(&self, e: &BuckyError) {
+        // Meta returns the normal error, means the chain is still online
+        if MetaFailCache::is_meta_error(e) {
+            self.on_meta_success();
+            return;
+        }
+
+        let mut status = self.status.lock().unwrap();
+        if status.online {
+            warn!("meta chain become unreachable! {}", e);
+        }
+        status.online = false;
+        status.last_fail_time = bucky_time_now();
     }
 
     async fn get_from_meta(
@@ -62,11 +148,12 @@ impl RawMetaCache {
             return ret;
         }
 
-        MetaClientHelper::get_object(&self.meta_client, object_id)
-            .await
-            .map(|ret| {
+        match MetaClientHelper::get_object(&self.meta_client, object_id).await {
+            Ok(ret) => {
                 self.fail_cache.on_success();
-                match ret {
+                self.on_meta_success();
+
+                let resp = match ret {
                     Some((object, object_raw)) => {
                         let object = Arc::new(object);
                         let resp = MetaObjectCacheData { object, object_raw };
@@ -77,12 +164,78 @@ impl RawMetaCache {
                             .add(key.clone(), BuckyError::from(BuckyErrorCode::NotFound));
                         None
                     }
-                }
-            })
-            .map_err(|e| {
+                };
+
+                self.save_object_to_store(object_id, resp.as_ref()).await;
+
+                Ok(resp)
+            }
+            Err(e) => {
+                self.on_meta_error(&e);
                 self.fail_cache.add(key, e.clone());
-                e
-            })
+                Err(e)
+            }
+        }
+    }
+
+    async fn save_object_to_store(&self, object_id: &ObjectId, resp: Option<&MetaObjectCacheData>) {
+        if let Some(store) = &self.store {
+            let (object_raw, expire_time) = match resp {
+                Some(data) => (Some(&data.object_raw), 0),
+                None => (None, bucky_time_now() + NOT_FOUND_STORE_TIMEOUT_IN_MICRO_SECS),
+            };
+
+            if let Err(e) = store.save_object(object_id, object_raw, expire_time).await {
+                error!("save meta object to store failed! obj={}, {}", object_id, e);
+            }
+        }
+    }
+
+    // Load object from persistent store when the meta chain is unreachable, return the origin error if not found
+    async fn get_object_from_store(
+        &self,
+        object_id: &ObjectId,
+        e: BuckyError,
+    ) -> BuckyResult<Option<MetaObjectCacheData>> {
+        let store = match &self.store {
+            Some(store) => store,
+            None => return Err(e),
+        };
+
+        let item = match store.get_object(object_id).await {
+            Ok(Some(item)) if !item.is_expired(bucky_time_now()) => item,
+            Ok(_) => return Err(e),
+            Err(store_err) => {
+                error!("load meta object from store failed! obj={}, {}", object_id, store_err);
+                return Err(e);
+            }
+        };
+
+        match item.value {
+            Some(object_raw) => {
+                let (object, _) = AnyNamedObject::raw_decode(&object_raw).map_err(|e| {
+                    error!("invalid stored meta object format! obj={} err={}", object_id, e);
+                    e
+                })?;
+
+                warn!(
+                    "meta chain unreachable, will use object from meta cache store! obj={}, update_time={}",
+                    object_id, item.update_time
+                );
+
+                Ok(Some(MetaObjectCacheData {
+                    object: Arc::new(object),
+                    object_raw,
+                }))
+            }
+            None => {
+                warn!(
+                    "meta chain unreachable, object not found in meta cache store! obj={}",
+                    object_id
+                );
+                Ok(None)
+            }
+        }
     }
 
     // 返回值表示对象有没有发生更新
@@ -158,8 +311,17 @@ impl RawMetaCache {
             return Ok(Some(ret));
         }
 
-        // Then try get from meta chain via network
-        let resp = self.get_from_meta(object_id).await?;
+        // Then try get from meta chain via network, and fallback to the persistent store if the chain is unreachable
+        let resp = match self.get_from_meta(object_id).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                if MetaFailCache::is_meta_error(&e) {
+                    return Err(e);
+                }
+
+                return self.get_object_from_store(object_id, e).await;
+            }
+        };
 
         // Cache the result if success
         if let Some(data) = &resp {
@@ -210,6 +372,12 @@ impl RawMetaCache {
 
                     Ok(None)
                 } else {
+                    if !MetaFailCache::is_meta_error(&e) {
+                        if let Some(ret) = self.get_name_from_store(name).await {
+                            return Ok(ret);
+                        }
+                    }
+
                     let msg = format!("get name from meta chain failed! name={} err={}", name, e);
                     error!("{}", msg);
 
@@ -225,17 +393,70 @@ impl RawMetaCache {
             return Err(e);
         }
 
-        self.meta_client
-            .get_name(name)
-            .await
-            .map_err(|e| {
-                self.fail_cache.add(key, e.clone());
-                e
-            })
-            .map(|v| {
+        match self.meta_client.get_name(name).await {
+            Ok(v) => {
                 self.fail_cache.on_success();
-                v
-            })
+                self.on_meta_success();
+                self.save_name_to_store(name, v.as_ref()).await;
+                Ok(v)
+            }
+            Err(e) => {
+                self.on_meta_error(&e);
+                if e.code() == BuckyErrorCode::NotFound {
+                    self.save_name_to_store(name, None).await;
+                }
+                self.fail_cache.add(key, e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    async fn save_name_to_store(&self, name: &str, value: Option<&(NameInfo, NameState)>) {
+        if let Some(store) = &self.store {
+            let expire_time = match value {
+                Some(_) => 0,
+                None => bucky_time_now() + NOT_FOUND_STORE_TIMEOUT_IN_MICRO_SECS,
+            };
+
+            if let Err(e) = store.save_name(name, value, expire_time).await {
+                error!("save meta name to store failed! name={}, {}", name, e);
+            }
+        }
+    }
+
+    async fn get_name_from_store(&self, name: &str) -> Option<Option<(NameInfo, NameState)>> {
+        let store = self.store.as_ref()?;
+
+        match store.get_name(name).await {
+            Ok(Some(item)) if !item.is_expired(bucky_time_now()) => {
+                warn!(
+                    "meta chain unreachable, will use name from meta cache store! name={}, found={}, update_time={}",
+                    name,
+                    item.value.is_some(),
+                    item.update_time
+                );
+                Some(item.value)
+            }
+            Ok(_) => None,
+            Err(e) => {
+                error!("load meta name from store failed! name={}, {}", name, e);
+                None
+            }
+        }
+    }
+
+    // Force refresh the name from meta chain, used by meta sync
+    pub(super) async fn flush_name(&self, name: &str) -> BuckyResult<()> {
+        self.fail_cache.remove(&MetaCacheKey::Name(name.to_owned()));
+
+        let v = match self.get_name_impl(name).await {
+            Ok(v) => v,
+            Err(e) if e.code() == BuckyErrorCode::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        self.name_memory_cache.add(name.to_owned(), v);
+        Ok(())
     }
 }
 
@@ -253,7 +474,130 @@ impl MetaCache for RawMetaCache {
         RawMetaCache::get_name(&self, name).await
     }
 
+    fn get_status(&self) -> MetaCacheStatus {
+        RawMetaCache::get_status(&self)
+    }
+
     fn clone_meta(&self) -> Box<dyn MetaCache> {
         Box::new(self.clone()) as Box<dyn MetaCache>
     }
 }
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    // 指向一个不可达的meta链
+    pub(in crate::meta) async fn new_offline_cache(name: &str) -> (RawMetaCache, Arc<MetaCacheStore>) {
+        let file = std::env::temp_dir().join(format!("test_raw_meta_{}.db", name));
+        if file.exists() {
+            std::fs::remove_file(&file).unwrap();
+        }
+        let store = Arc::new(MetaCacheStore::open(&file).await.unwrap());
+
+        let noc = cyfs_noc::NamedObjectCacheManager::create(&format!("test-raw-meta-{}", name))
+            .await
+            .unwrap();
+
+        let target = MetaMinerTarget::from_str("http://127.0.0.1:1").unwrap();
+        let meta_client = MetaClient::new_target(target).with_timeout(std::time::Duration::from_secs(5));
+
+        let cache = RawMetaCache::new_with_store(meta_client, noc, Some(store.clone()));
+        (cache, store)
+    }
+
+    pub(in crate::meta) fn new_people() -> (ObjectId, Vec<u8>) {
+        let secret = PrivateKey::generate_rsa(1024).unwrap();
+        let people = People::new(None, vec![], secret.public(), None, None, None).build();
+        (people.desc().calculate_id(), people.to_vec().unwrap())
+    }
+
+    fn new_info(owner_id: &ObjectId) -> NameInfo {
+        NameInfo {
+            sub_records: Default::default(),
+            record: NameRecord {
+                link: NameLink::ObjectLink(owner_id.clone()),
+                user_data: "".to_owned(),
+            },
+            owner: Some(owner_id.clone()),
+        }
+    }
+
+    #[async_std::test]
+    async fn test_object_fallback() {
+        let (cache, store) = new_offline_cache("object").await;
+
+        let (object_id, object_raw) = new_people();
+        store.save_object(&object_id, Some(&object_raw), 0).await.unwrap();
+
+        let now = bucky_time_now();
+        let (not_found_id, _) = new_people();
+        store
+            .save_object(&not_found_id, None, now + NOT_FOUND_STORE_TIMEOUT_IN_MICRO_SECS)
+            .await
+            .unwrap();
+
+        let (expired_id, _) = new_people();
+        store.save_object(&expired_id, None, now).await.unwrap();
+
+        let (unknown_id, _) = new_people();
+
+        // 链不可达时使用持久化缓存里的结果
+        let data = cache.get_object(&object_id).await.unwrap().unwrap();
+        assert_eq!(data.object_raw, object_raw);
+        assert_eq!(data.object.calculate_id(), object_id);
+
+        let status = cache.get_status();
+        assert!(!status.online);
+        assert!(status.last_fail_time >= now);
+
+        assert!(cache.get_object(&not_found_id).await.unwrap().is_none());
+
+        // 超时的不存在结果和缓存里没有的对象返回原始错误
+        let e = cache.get_object(&expired_id).await.unwrap_err();
+        assert!(!MetaFailCache::is_meta_error(&e));
+        let e = cache.get_object(&unknown_id).await.unwrap_err();
+        assert!(!MetaFailCache::is_meta_error(&e));
+
+        // 强制刷新不走持久化缓存
+        assert!(cache.flush_object(&object_id).await.is_err());
+    }
+
+    #[async_std::test]
+    async fn test_name_fallback() {
+        let (cache, store) = new_offline_cache("name").await;
+
+        let (owner_id, _) = new_people();
+        let value = (new_info(&owner_id), NameState::Normal);
+        store.save_name("test", Some(&value), 0).await.unwrap();
+
+        let now = bucky_time_now();
+        store
+            .save_name("not-found", None, now + NOT_FOUND_STORE_TIMEOUT_IN_MICRO_SECS)
+            .await
+            .unwrap();
+
+        let (info, state) = cache.get_name("test").await.unwrap().unwrap();
+        assert_eq!(state, NameState::Normal);
+        assert_eq!(info.owner, Some(owner_id));
+        assert!(!cache.get_status().online);
+
+        assert!(cache.get_name("not-found").await.unwrap().is_none());
+        assert!(cache.get_name("unknown").await.is_err());
+    }
+
+    #[async_std::test]
+    async fn test_without_store() {
+        let (cache, store) = new_offline_cache("without-store").await;
+        let (object_id, object_raw) = new_people();
+        store.save_object(&object_id, Some(&object_raw), 0).await.unwrap();
+
+        let cache = RawMetaCache {
+            store: None,
+            ..cache
+        };
+
+        assert!(cache.get_object(&object_id).await.is_err());
+    }
+}
@@ -0,0 +1,412 @@
+use crate::trans_api::{sql_query, SqlPool, SqlRow, SqlRowObject};
+use cyfs_base::*;
+
+use std::path::Path;
+use std::str::FromStr;
+
+pub(super) struct MetaStoreItem<T> {
+    // 为空表示链上不存在的结果
+    pub value: Option<T>,
+    pub update_time: u64,
+
+    // 只对不存在的结果有效，超时后需要重新查询
+    pub expire_time: u64,
+}
+
+impl<T> MetaStoreItem<T> {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.value.is_none() && self.expire_time <= now
+    }
+}
+
+pub(super) struct MetaStoreStat {
+    pub object_count: u64,
+    pub name_count: u64,
+    pub stale_count: u64,
+    pub oldest_update_time: u64,
+}
+
+// meta链查询结果的持久化缓存，协议栈重启后在没有网络时仍然可以使用
+pub(super) struct MetaCacheStore {
+    pool: SqlPool,
+}
+
+impl MetaCacheStore {
+    pub async fn open(file: &Path) -> BuckyResult<Self> {
+        let pool = SqlPool::open(
+            format!("sqlite://{}", file.to_string_lossy().to_string()).as_str(),
+            2,
+        )
+        .await?;
+
+        let ret = Self { pool };
+        ret.init().await?;
+
+        Ok(ret)
+    }
+
+    async fn init(&self) -> BuckyResult<()> {
+        let mut conn = self.pool.get_conn().await?;
+        let sql = r#"create table if not exists "meta_object" (
+            "object_id" TEXT PRIMARY KEY NOT NULL,
+            "object_raw" BLOB,
+            "update_time" INTEGER NOT NULL,
+            "expire_time" INTEGER NOT NULL
+            )"#;
+        conn.execute_sql(sql_query(sql)).await?;
+
+        let sql = r#"create index if not exists object_update_time_index on meta_object (update_time)"#;
+        conn.execute_sql(sql_query(sql)).await?;
+
+        let sql = r#"create table if not exists "meta_name" (
+            "name" TEXT PRIMARY KEY NOT NULL,
+            "name_info" BLOB,
+            "name_state" INTEGER NOT NULL,
+            "update_time" INTEGER NOT NULL,
+            "expire_time" INTEGER NOT NULL
+            )"#;
+        conn.execute_sql(sql_query(sql)).await?;
+
+        let sql = r#"create index if not exists name_update_time_index on meta_name (update_time)"#;
+        conn.execute_sql(sql_query(sql)).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_object(
+        &self,
+        object_id: &ObjectId,
+    ) -> BuckyResult<Option<MetaStoreItem<Vec<u8>>>> {
+        let sql = r#"select * from meta_object where object_id = ?1"#;
+        let mut conn = self.pool.get_conn().await?;
+        let rows = conn
+            .query_all(sql_query(sql).bind(object_id.to_string()))
+            .await?;
+
+        match rows.first() {
+            Some(row) => Ok(Some(MetaStoreItem {
+                value: row.get("object_raw"),
+                update_time: row.get::<i64, _>("update_time") as u64,
+                expire_time: row.get::<i64, _>("expire_time") as u64,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn save_object(
+        &self,
+        object_id: &ObjectId,
+        object_raw: Option<&Vec<u8>>,
+        expire_time: u64,
+    ) -> BuckyResult<()> {
+        let sql = r#"insert or replace into meta_object (object_id, object_raw, update_time, expire_time)
+            values (?1, ?2, ?3, ?4)"#;
+        let mut conn = self.pool.get_conn().await?;
+        conn.execute_sql(
+            sql_query(sql)
+                .bind(object_id.to_string())
+                .bind(object_raw.cloned())
+                .bind(bucky_time_now() as i64)
+                .bind(expire_time as i64),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_name(
+        &self,
+        name: &str,
+    ) -> BuckyResult<Option<MetaStoreItem<(NameInfo, NameState)>>> {
+        let sql = r#"select * from meta_name where name = ?1"#;
+        let mut conn = self.pool.get_conn().await?;
+        let rows = conn.query_all(sql_query(sql).bind(name.to_owned())).await?;
+
+        let row = match rows.first() {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let value = match row.get::<Option<Vec<u8>>, _>("name_info") {
+            Some(buf) => {
+                let info = NameInfo::clone_from_slice(&buf)?;
+                let state = Self::decode_name_state(row.get("name_state"))?;
+                Some((info, state))
+            }
+            None => None,
+        };
+
+        Ok(Some(MetaStoreItem {
+            value,
+            update_time: row.get::<i64, _>("update_time") as u64,
+            expire_time: row.get::<i64, _>("expire_time") as u64,
+        }))
+    }
+
+    pub async fn save_name(
+        &self,
+        name: &str,
+        value: Option<&(NameInfo, NameState)>,
+        expire_time: u64,
+    ) -> BuckyResult<()> {
+        let (name_info, name_state) = match value {
+            Some((info, state)) => (Some(info.to_vec()?), *state as i32),
+            None => (None, 0),
+        };
+
+        let sql = r#"insert or replace into meta_name (name, name_info, name_state, update_time, expire_time)
+            values (?1, ?2, ?3, ?4, ?5)"#;
+        let mut conn = self.pool.get_conn().await?;
+        conn.execute_sql(
+            sql_query(sql)
+                .bind(name.to_owned())
+                .bind(name_info)
+                .bind(name_state)
+                .bind(bucky_time_now() as i64)
+                .bind(expire_time as i64),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    fn decode_name_state(state: i32) -> BuckyResult<NameState> {
+        if state < NameState::Normal as i32 || state > NameState::ActiveAuction as i32 {
+            let msg = format!("invalid name state in meta cache store: {}", state);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
+        }
+
+        Ok(NameState::from(state))
+    }
+
+    // 列出需要刷新的条目，只包括链上存在的结果
+    pub async fn list_stale_objects(
+        &self,
+        update_before: u64,
+        limit: u32,
+    ) -> BuckyResult<Vec<ObjectId>> {
+        let sql = r#"select object_id from meta_object where object_raw is not null and update_time < ?1
+            order by update_time limit ?2"#;
+        let mut conn = self.pool.get_conn().await?;
+        let rows = conn
+            .query_all(
+                sql_query(sql)
+                    .bind(update_before as i64)
+                    .bind(limit as i64),
+            )
+            .await?;
+
+        let mut list = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id: String = row.get("object_id");
+            list.push(ObjectId::from_str(&id)?);
+        }
+
+        Ok(list)
+    }
+
+    pub async fn list_stale_names(&self, update_before: u64, limit: u32) -> BuckyResult<Vec<String>> {
+        let sql = r#"select name from meta_name where name_info is not null and update_time < ?1
+            order by update_time limit ?2"#;
+        let mut conn = self.pool.get_conn().await?;
+        let rows = conn
+            .query_all(
+                sql_query(sql)
+                    .bind(update_before as i64)
+                    .bind(limit as i64),
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get("name")).collect())
+    }
+
+    // 清除已经超时的不存在的结果
+    pub async fn purge_expired(&self, now: u64) -> BuckyResult<()> {
+        let mut conn = self.pool.get_conn().await?;
+
+        let sql = r#"delete from meta_object where object_raw is null and expire_time < ?1"#;
+        conn.execute_sql(sql_query(sql).bind(now as i64)).await?;
+
+        let sql = r#"delete from meta_name where name_info is null and expire_time < ?1"#;
+        conn.execute_sql(sql_query(sql).bind(now as i64)).await?;
+
+        Ok(())
+    }
+
+    pub async fn stat(&self, stale_before: u64) -> BuckyResult<MetaStoreStat> {
+        let mut conn = self.pool.get_conn().await?;
+
+        let sql = r#"select count(*) as count, min(update_time) as oldest from meta_object where object_raw is not null"#;
+        let row = conn.query_one(sql_query(sql)).await?;
+        let (object_count, object_oldest) = Self::decode_count_row(&row);
+
+        let sql = r#"select count(*) as count, min(update_time) as oldest from meta_name where name_info is not null"#;
+        let row = conn.query_one(sql_query(sql)).await?;
+        let (name_count, name_oldest) = Self::decode_count_row(&row);
+
+        let sql = r#"select count(*) as count from meta_object where object_raw is not null and update_time < ?1"#;
+        let row = conn
+            .query_one(sql_query(sql).bind(stale_before as i64))
+            .await?;
+        let stale_objects = row.get::<i64, _>("count") as u64;
+
+        let sql = r#"select count(*) as count from meta_name where name_info is not null and update_time < ?1"#;
+        let row = conn
+            .query_one(sql_query(sql).bind(stale_before as i64))
+            .await?;
+        let stale_names = row.get::<i64, _>("count") as u64;
+
+        let oldest_update_time = match (object_oldest, name_oldest) {
+            (Some(a), Some(b)) => std::cmp::min(a, b),
+            (Some(a), None) => a,
+            (None, Some(b)) => b,
+            (None, None) => 0,
+        };
+
+        Ok(MetaStoreStat {
+            object_count,
+            name_count,
+            stale_count: stale_objects + stale_names,
+            oldest_update_time,
+        })
+    }
+
+    fn decode_count_row(row: &SqlRowObject) -> (u64, Option<u64>) {
+        let count = row.get::<i64, _>("count") as u64;
+        let oldest = row.get::<Option<i64>, _>("oldest").map(|v| v as u64);
+
+        (count, oldest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn open_store(name: &str) -> MetaCacheStore {
+        let file = std::env::temp_dir().join(format!("test_meta_cache_store_{}.db", name));
+        if file.exists() {
+            std::fs::remove_file(&file).unwrap();
+        }
+
+        MetaCacheStore::open(&file).await.unwrap()
+    }
+
+    fn new_info(owner_id: &ObjectId) -> NameInfo {
+        NameInfo {
+            sub_records: Default::default(),
+            record: NameRecord {
+                link: NameLink::ObjectLink(owner_id.clone()),
+                user_data: "".to_owned(),
+            },
+            owner: Some(owner_id.clone()),
+        }
+    }
+
+    #[async_std::test]
+    async fn test_object() {
+        let store = open_store("object").await;
+
+        let secret = PrivateKey::generate_rsa(1024).unwrap();
+        let people = People::new(None, vec![], secret.public(), None, None, None).build();
+        let object_id = people.desc().calculate_id();
+        let object_raw = people.to_vec().unwrap();
+
+        assert!(store.get_object(&object_id).await.unwrap().is_none());
+
+        let now = bucky_time_now();
+        store.save_object(&object_id, Some(&object_raw), 0).await.unwrap();
+        let item = store.get_object(&object_id).await.unwrap().unwrap();
+        assert_eq!(item.value.as_ref(), Some(&object_raw));
+        assert!(item.update_time >= now);
+        assert!(!item.is_expired(bucky_time_now()));
+
+        // 链上不存在的结果在超时后失效
+        let not_found_id = ObjectId::default();
+        store.save_object(&not_found_id, None, now + 1000).await.unwrap();
+        let item = store.get_object(&not_found_id).await.unwrap().unwrap();
+        assert!(item.value.is_none());
+        assert!(!item.is_expired(now));
+        assert!(item.is_expired(now + 1000));
+
+        store.purge_expired(now + 1000).await.unwrap();
+        assert!(store.get_object(&not_found_id).await.unwrap().is_some());
+        store.purge_expired(now + 1001).await.unwrap();
+        assert!(store.get_object(&not_found_id).await.unwrap().is_none());
+
+        // 存在的结果不会被清除
+        assert!(store.get_object(&object_id).await.unwrap().is_some());
+    }
+
+    #[async_std::test]
+    async fn test_name() {
+        let store = open_store("name").await;
+
+        let owner_id = ObjectId::default();
+        let value = (new_info(&owner_id), NameState::Normal);
+
+        assert!(store.get_name("test").await.unwrap().is_none());
+
+        store.save_name("test", Some(&value), 0).await.unwrap();
+        let item = store.get_name("test").await.unwrap().unwrap();
+        let (info, state) = item.value.unwrap();
+        assert_eq!(state, NameState::Normal);
+        assert_eq!(info.owner, Some(owner_id));
+        match info.record.link {
+            NameLink::ObjectLink(id) => assert_eq!(id, owner_id),
+            _ => unreachable!(),
+        }
+
+        let now = bucky_time_now();
+        store.save_name("not-found", None, now).await.unwrap();
+        let item = store.get_name("not-found").await.unwrap().unwrap();
+        assert!(item.value.is_none());
+        assert!(item.is_expired(now));
+
+        store.purge_expired(now + 1).await.unwrap();
+        assert!(store.get_name("not-found").await.unwrap().is_none());
+        assert!(store.get_name("test").await.unwrap().is_some());
+
+        // 覆盖写入
+        store.save_name("test", None, now + 1000).await.unwrap();
+        let item = store.get_name("test").await.unwrap().unwrap();
+        assert!(item.value.is_none());
+    }
+
+    #[async_std::test]
+    async fn test_stale() {
+        let store = open_store("stale").await;
+
+        let stat = store.stat(bucky_time_now()).await.unwrap();
+        assert_eq!(stat.object_count, 0);
+        assert_eq!(stat.name_count, 0);
+        assert_eq!(stat.stale_count, 0);
+        assert_eq!(stat.oldest_update_time, 0);
+
+        let object_id = ObjectId::default();
+        let value = (new_info(&object_id), NameState::Normal);
+
+        let begin = bucky_time_now();
+        store.save_object(&object_id, Some(&vec![1, 2, 3]), 0).await.unwrap();
+        store.save_name("test", Some(&value), 0).await.unwrap();
+        store.save_name("not-found", None, begin + 1000 * 1000).await.unwrap();
+        let end = bucky_time_now() + 1;
+
+        // 不存在的结果不需要刷新，也不计入统计
+        assert!(store.list_stale_objects(begin, 10).await.unwrap().is_empty());
+        assert!(store.list_stale_names(begin, 10).await.unwrap().is_empty());
+        assert_eq!(store.list_stale_objects(end, 10).await.unwrap(), vec![object_id.clone()]);
+        assert_eq!(store.list_stale_names(end, 10).await.unwrap(), vec!["test".to_owned()]);
+        assert!(store.list_stale_names(end, 0).await.unwrap().is_empty());
+
+        let stat = store.stat(begin).await.unwrap();
+        assert_eq!(stat.object_count, 1);
+        assert_eq!(stat.name_count, 1);
+        assert_eq!(stat.stale_count, 0);
+        assert!(stat.oldest_update_time >= begin && stat.oldest_update_time < end);
+
+        let stat = store.stat(end).await.unwrap();
+        assert_eq!(stat.stale_count, 2);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::meta::*;
+use crate::zone::ZoneManagerRef;
+use cyfs_base::*;
+
+use std::sync::Arc;
+
+// name查询但不存在的超时时间，默认一小时
+pub(super) const NAME_CACHE_NOT_FOUND_TIMEOUT_IN_MICRO_SECS: u64 = 1000 * 1000 * 60 * 60;
+// const NAME_CACHE_NOT_FOUND_TIMEOUT_IN_MICRO_SECS: u64 = 1000 * 1000 * 60; // 暂时改为一分钟
+
+// 本地后端(注册表/局域网)的数据随时可能变化，不存在的结果只缓存一分钟
+pub(super) const NAME_CACHE_LOCAL_NOT_FOUND_TIMEOUT_IN_MICRO_SECS: u64 = 1000 * 1000 * 60;
+
+pub(crate) struct NameResolveResult {
+    pub info: NameInfo,
+    pub state: NameState,
+
+    // 结果的缓存有效期，为空则使用默认值
+    pub ttl: Option<u64>,
+}
+
+#[async_trait::async_trait]
+pub(crate) trait NameResolverBackend: Send + Sync {
+    fn name(&self) -> &str;
+
+    // 查询不存在的结果的缓存有效期
+    fn not_found_ttl(&self) -> u64 {
+        NAME_CACHE_NOT_FOUND_TIMEOUT_IN_MICRO_SECS
+    }
+
+    // 解析链按顺序生效，后面的后端需要等待前面的后端给出结果；返回false则和后面的后端并发竞争
+    fn block_chain(&self) -> bool {
+        true
+    }
+
+    fn bind_zone_manager(&self, _zone_manager: &ZoneManagerRef) {}
+
+    async fn get_name(&self, name: &str) -> BuckyResult<Option<NameResolveResult>>;
+}
+
+pub(crate) type NameResolverBackendRef = Arc<Box<dyn NameResolverBackend>>;
+
+// 基于meta链的解析后端
+pub(crate) struct MetaChainNameBackend {
+    meta_cache: MetaCacheRef,
+}
+
+impl MetaChainNameBackend {
+    pub fn new(meta_cache: MetaCacheRef) -> Self {
+        Self { meta_cache }
+    }
+
+    pub fn into_ref(self) -> NameResolverBackendRef {
+        Arc::new(Box::new(self))
+    }
+}
+
+#[async_trait::async_trait]
+impl NameResolverBackend for MetaChainNameBackend {
+    fn name(&self) -> &str {
+        "meta"
+    }
+
+    async fn get_name(&self, name: &str) -> BuckyResult<Option<NameResolveResult>> {
+        let dur = std::time::Duration::from_secs(30);
+        match async_std::future::timeout(dur, self.meta_cache.get_name(name)).await {
+            Ok(ret) => {
+                let ret = ret?.map(|(info, state)| NameResolveResult {
+                    info,
+                    state,
+                    ttl: None,
+                });
+                Ok(ret)
+            }
+            Err(async_std::future::TimeoutError { .. }) => {
+                error!("get name from meta cache timeout: name={}", name);
+                Err(BuckyError::from(BuckyErrorCode::Timeout))
+            }
+        }
+    }
+}
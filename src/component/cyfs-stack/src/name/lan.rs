@@ -0,0 +1,488 @@
+use super::backend::*;
+use super::local_registry::*;
+use crate::meta::MetaCacheRef;
+use crate::zone::ZoneManagerRef;
+use cyfs_base::*;
+use cyfs_core::*;
+use cyfs_lib::*;
+
+use async_std::net::UdpSocket;
+use once_cell::sync::OnceCell;
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// 发出查询后等待应答的时长
+const LAN_NAME_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+// 应答里携带的缓存有效期(秒)，查询方会限制在一个上限内
+const LAN_NAME_ANSWER_TTL_SECS: u32 = 60 * 5;
+const LAN_NAME_ANSWER_MAX_TTL_SECS: u32 = 60 * 60;
+
+const LAN_NAME_PACKAGE_MAX_SIZE: usize = 1024 * 64;
+
+#[derive(RawEncode, RawDecode)]
+struct LanNameQuery {
+    seq: u32,
+    name: String,
+}
+
+#[derive(RawEncode, RawDecode)]
+struct LanNameAnswer {
+    seq: u32,
+    name: String,
+    ttl: u32,
+
+    // owner签名的注册记录，owner对象由查询方自己获取
+    record: Vec<u8>,
+}
+
+#[derive(RawEncode, RawDecode)]
+enum LanNamePackage {
+    Query(LanNameQuery),
+    Answer(LanNameAnswer),
+}
+
+// 基于局域网组播的名字发现，查询时向组播地址广播，其它协议栈用各自的本地注册表应答
+// 应答里的记录需要owner签名，并且owner只能是当前zone的owner，或者是meta链上该名字的owner
+#[derive(Clone)]
+pub(crate) struct LanNameDiscovery {
+    multicast_addr: SocketAddrV4,
+    registry: LocalNameRegistry,
+    verifier: LanNameAnswerVerifier,
+    next_seq: Arc<AtomicU32>,
+}
+
+impl LanNameDiscovery {
+    pub fn new(
+        multicast_addr: SocketAddr,
+        registry: LocalNameRegistry,
+        meta_cache: MetaCacheRef,
+    ) -> BuckyResult<Self> {
+        let multicast_addr = match multicast_addr {
+            SocketAddr::V4(addr) if addr.ip().is_multicast() => addr,
+            _ => {
+                let msg = format!(
+                    "invalid lan name discovery multicast addr: {}",
+                    multicast_addr
+                );
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+            }
+        };
+
+        Ok(Self {
+            multicast_addr,
+            registry,
+            verifier: LanNameAnswerVerifier::new(meta_cache),
+            next_seq: Arc::new(AtomicU32::new(bucky_time_now() as u32)),
+        })
+    }
+
+    pub fn into_ref(self) -> NameResolverBackendRef {
+        Arc::new(Box::new(self))
+    }
+
+    // 加入组播组，应答局域网内其它协议栈的查询
+    pub fn start(&self) -> BuckyResult<()> {
+        let socket = self.bind_multicast().map_err(|e| {
+            let msg = format!(
+                "bind lan name discovery multicast addr failed! addr={}, {}",
+                self.multicast_addr, e
+            );
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::IoError, msg)
+        })?;
+
+        info!("lan name discovery listening on {}", self.multicast_addr);
+
+        let this = self.clone();
+        async_std::task::spawn(async move {
+            this.run_responder(socket).await;
+        });
+
+        Ok(())
+    }
+
+    fn bind_multicast(&self) -> std::io::Result<UdpSocket> {
+        // 同一台机器上可能有多个协议栈，需要复用端口
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_reuse_address(true)?;
+
+        let bind_addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, self.multicast_addr.port());
+        socket.bind(&SockAddr::from(bind_addr))?;
+        socket.join_multicast_v4(self.multicast_addr.ip(), &Ipv4Addr::UNSPECIFIED)?;
+        socket.set_nonblocking(true)?;
+
+        let socket: std::net::UdpSocket = socket.into();
+        Ok(UdpSocket::from(socket))
+    }
+
+    async fn run_responder(&self, socket: UdpSocket) {
+        let mut buf = vec![0u8; LAN_NAME_PACKAGE_MAX_SIZE];
+        loop {
+            let (len, from) = match socket.recv_from(&mut buf).await {
+                Ok(ret) => ret,
+                Err(e) => {
+                    error!("recv from lan name discovery socket failed! {}", e);
+                    async_std::task::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+
+            let query = match LanNamePackage::clone_from_slice(&buf[..len]) {
+                Ok(LanNamePackage::Query(query)) => query,
+                Ok(LanNamePackage::Answer(_)) => continue,
+                Err(e) => {
+                    debug!("recv invalid lan name package! from={}, {}", from, e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = self.on_query(&socket, query, &from).await {
+                warn!("process lan name query failed! from={}, {}", from, e);
+            }
+        }
+    }
+
+    async fn on_query(
+        &self,
+        socket: &UdpSocket,
+        query: LanNameQuery,
+        from: &SocketAddr,
+    ) -> BuckyResult<()> {
+        let record = match self.registry.load_record(&query.name).await? {
+            Some(record) => record,
+            None => return Ok(()),
+        };
+
+        info!(
+            "will answer lan name query: name={}, from={}, link={}",
+            query.name, from, record.info.record.link
+        );
+
+        let answer = LanNamePackage::Answer(LanNameAnswer {
+            seq: query.seq,
+            name: query.name,
+            ttl: LAN_NAME_ANSWER_TTL_SECS,
+            record: record.record.object_raw,
+        });
+
+        let buf = answer.to_vec()?;
+        socket.send_to(&buf, from).await?;
+
+        Ok(())
+    }
+
+    async fn query(&self, name: &str) -> BuckyResult<Option<NameResolveResult>> {
+        let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)).await?;
+        socket.set_multicast_loop_v4(true)?;
+
+        let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+        let query = LanNamePackage::Query(LanNameQuery {
+            seq,
+            name: name.to_owned(),
+        });
+        let buf = query.to_vec()?;
+        socket.send_to(&buf, self.multicast_addr).await?;
+
+        let deadline = Instant::now() + LAN_NAME_QUERY_TIMEOUT;
+        let mut buf = vec![0u8; LAN_NAME_PACKAGE_MAX_SIZE];
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+
+            let (len, from) =
+                match async_std::future::timeout(deadline - now, socket.recv_from(&mut buf)).await
+                {
+                    Ok(ret) => ret?,
+                    Err(async_std::future::TimeoutError { .. }) => break,
+                };
+
+            let answer = match LanNamePackage::clone_from_slice(&buf[..len]) {
+                Ok(LanNamePackage::Answer(answer)) => answer,
+                Ok(LanNamePackage::Query(_)) => continue,
+                Err(e) => {
+                    debug!("recv invalid lan name package! from={}, {}", from, e);
+                    continue;
+                }
+            };
+
+            if answer.seq != seq || answer.name != name {
+                continue;
+            }
+
+            match self.verifier.verify(&answer).await {
+                Ok(info) => {
+                    info!(
+                        "get name from lan: name={}, from={}, link={}",
+                        name, from, info.record.link
+                    );
+
+                    let ttl = std::cmp::min(answer.ttl, LAN_NAME_ANSWER_MAX_TTL_SECS) as u64;
+                    return Ok(Some(NameResolveResult {
+                        info,
+                        state: NameState::Normal,
+                        ttl: Some(ttl * 1000 * 1000),
+                    }));
+                }
+                Err(e) => {
+                    warn!(
+                        "verify lan name answer failed! name={}, from={}, {}",
+                        name, from, e
+                    );
+                }
+            }
+        }
+
+        debug!("get name from lan but no answer: name={}", name);
+        Ok(None)
+    }
+}
+
+// 校验应答里的注册记录
+// 任何人都可以用自己的people签名一条记录，所以只信任当前zone的owner和meta链上该名字的owner
+#[derive(Clone)]
+struct LanNameAnswerVerifier {
+    meta_cache: MetaCacheRef,
+    zone_manager: Arc<OnceCell<ZoneManagerRef>>,
+}
+
+impl LanNameAnswerVerifier {
+    fn new(meta_cache: MetaCacheRef) -> Self {
+        Self {
+            meta_cache,
+            zone_manager: Arc::new(OnceCell::new()),
+        }
+    }
+
+    async fn verify(&self, answer: &LanNameAnswer) -> BuckyResult<NameInfo> {
+        let record = Storage::clone_from_slice(&answer.record)?;
+        let (_, info) = NameRegistryRecord::decode(&record)?;
+        let owner_id = info.owner.ok_or_else(|| {
+            let msg = format!("lan name answer's record has no owner! name={}", answer.name);
+            warn!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidData, msg)
+        })?;
+
+        let owner = self.load_trusted_owner(&answer.name, &owner_id).await?;
+
+        NameRegistryRecord::verify(&answer.name, &record, &owner).await
+    }
+
+    async fn load_trusted_owner(
+        &self,
+        name: &str,
+        owner_id: &ObjectId,
+    ) -> BuckyResult<AnyNamedObject> {
+        if let Some(zone_manager) = self.zone_manager.get() {
+            let current_info = zone_manager.get_current_info().await?;
+            if current_info.owner_id == *owner_id {
+                return Ok(current_info.owner.as_ref().clone());
+            }
+        }
+
+        let meta_owner = match self.meta_cache.get_name(name).await {
+            Ok(Some((info, _))) => info.owner,
+            Ok(None) => None,
+            Err(e) if e.code() == BuckyErrorCode::NotFound => None,
+            Err(e) => {
+                warn!("get name owner from meta failed! name={}, {}", name, e);
+                return Err(e);
+            }
+        };
+
+        if meta_owner.as_ref() != Some(owner_id) {
+            let msg = format!(
+                "lan name answer's owner is not trusted! name={}, owner={}, meta owner={:?}",
+                name, owner_id, meta_owner
+            );
+            warn!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+        }
+
+        match self.meta_cache.get_object(owner_id).await? {
+            Some(data) => Ok(data.object.as_ref().clone()),
+            None => {
+                let msg = format!(
+                    "lan name answer's owner not found on meta! name={}, owner={}",
+                    name, owner_id
+                );
+                warn!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::NotFound, msg))
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl NameResolverBackend for LanNameDiscovery {
+    fn name(&self) -> &str {
+        "lan"
+    }
+
+    fn not_found_ttl(&self) -> u64 {
+        NAME_CACHE_LOCAL_NOT_FOUND_TIMEOUT_IN_MICRO_SECS
+    }
+
+    // 没有应答时需要等到超时，不阻塞解析链里后面的后端
+    fn block_chain(&self) -> bool {
+        false
+    }
+
+    fn bind_zone_manager(&self, zone_manager: &ZoneManagerRef) {
+        let _ = self.verifier.zone_manager.set(zone_manager.clone());
+    }
+
+    async fn get_name(&self, name: &str) -> BuckyResult<Option<NameResolveResult>> {
+        if NameRegistryRecord::check_name(name).is_err() {
+            return Ok(None);
+        }
+
+        self.query(name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta::{MetaCache, MetaObjectCacheData};
+
+    use std::collections::HashMap;
+
+    // meta链上注册的名字和对象
+    #[derive(Clone, Default)]
+    struct TestMetaCache {
+        names: HashMap<String, NameInfo>,
+        objects: HashMap<ObjectId, AnyNamedObject>,
+    }
+
+    #[async_trait::async_trait]
+    impl MetaCache for TestMetaCache {
+        async fn get_object(
+            &self,
+            object_id: &ObjectId,
+        ) -> BuckyResult<Option<MetaObjectCacheData>> {
+            let ret = self.objects.get(object_id).map(|object| MetaObjectCacheData {
+                object_raw: object.to_vec().unwrap(),
+                object: Arc::new(object.clone()),
+            });
+            Ok(ret)
+        }
+
+        async fn flush_object(&self, _object_id: &ObjectId) -> BuckyResult<bool> {
+            Ok(false)
+        }
+
+        async fn get_name(&self, name: &str) -> BuckyResult<Option<(NameInfo, NameState)>> {
+            Ok(self.names.get(name).map(|info| (info.clone(), NameState::Normal)))
+        }
+
+        fn get_status(&self) -> MetaCacheStatus {
+            MetaCacheStatus::default()
+        }
+
+        fn clone_meta(&self) -> Box<dyn MetaCache> {
+            Box::new(self.clone())
+        }
+    }
+
+    fn new_people() -> (PrivateKey, AnyNamedObject) {
+        let secret = PrivateKey::generate_rsa(1024).unwrap();
+        let people = People::new(None, vec![], secret.public(), None, None, None).build();
+        (secret, AnyNamedObject::Standard(StandardObject::People(people)))
+    }
+
+    fn new_info(owner_id: &ObjectId) -> NameInfo {
+        NameInfo {
+            sub_records: Default::default(),
+            record: NameRecord {
+                link: NameLink::ObjectLink(owner_id.clone()),
+                user_data: "".to_owned(),
+            },
+            owner: Some(owner_id.clone()),
+        }
+    }
+
+    async fn new_answer(name: &str, owner_id: &ObjectId, secret: &PrivateKey) -> LanNameAnswer {
+        let mut record = NameRegistryRecord::create(name, &new_info(owner_id)).unwrap();
+        NameRegistryRecord::sign(&mut record, secret).await.unwrap();
+
+        LanNameAnswer {
+            seq: 1,
+            name: name.to_owned(),
+            ttl: LAN_NAME_ANSWER_TTL_SECS,
+            record: record.to_vec().unwrap(),
+        }
+    }
+
+    #[async_std::test]
+    async fn test_verify_answer() {
+        let (secret, owner) = new_people();
+        let owner_id = owner.calculate_id();
+        let (attacker_secret, attacker) = new_people();
+        let attacker_id = attacker.calculate_id();
+
+        let mut meta = TestMetaCache::default();
+        meta.names.insert("test-name".to_owned(), new_info(&owner_id));
+        meta.objects.insert(owner_id.clone(), owner.clone());
+        meta.objects.insert(attacker_id.clone(), attacker.clone());
+        let verifier = LanNameAnswerVerifier::new(Arc::new(Box::new(meta)));
+
+        // meta链上的owner签名的记录
+        let answer = new_answer("test-name", &owner_id, &secret).await;
+        let info = verifier.verify(&answer).await.unwrap();
+        assert_eq!(info.owner, Some(owner_id.clone()));
+
+        // 自签名的记录，即使签名有效，owner也不可信
+        let answer = new_answer("test-name", &attacker_id, &attacker_secret).await;
+        let e = verifier.verify(&answer).await.unwrap_err();
+        assert_eq!(e.code(), BuckyErrorCode::PermissionDenied);
+
+        // 冒用owner但签名不对
+        let answer = new_answer("test-name", &owner_id, &attacker_secret).await;
+        let e = verifier.verify(&answer).await.unwrap_err();
+        assert_eq!(e.code(), BuckyErrorCode::InvalidSignature);
+
+        // meta链上没有注册的名字，不信任任何owner
+        let answer = new_answer("other-name", &owner_id, &secret).await;
+        let e = verifier.verify(&answer).await.unwrap_err();
+        assert_eq!(e.code(), BuckyErrorCode::PermissionDenied);
+    }
+
+    #[test]
+    fn test_package_codec() {
+        let query = LanNamePackage::Query(LanNameQuery {
+            seq: 10,
+            name: "test-name".to_owned(),
+        });
+        let buf = query.to_vec().unwrap();
+        match LanNamePackage::clone_from_slice(&buf).unwrap() {
+            LanNamePackage::Query(query) => {
+                assert_eq!(query.seq, 10);
+                assert_eq!(query.name, "test-name");
+            }
+            LanNamePackage::Answer(_) => unreachable!(),
+        }
+
+        let answer = LanNamePackage::Answer(LanNameAnswer {
+            seq: 11,
+            name: "test-name".to_owned(),
+            ttl: LAN_NAME_ANSWER_TTL_SECS,
+            record: vec![1, 2, 3],
+        });
+        let buf = answer.to_vec().unwrap();
+        match LanNamePackage::clone_from_slice(&buf).unwrap() {
+            LanNamePackage::Answer(answer) => {
+                assert_eq!(answer.seq, 11);
+                assert_eq!(answer.ttl, LAN_NAME_ANSWER_TTL_SECS);
+                assert_eq!(answer.record, vec![1, 2, 3]);
+            }
+            LanNamePackage::Query(_) => unreachable!(),
+        }
+    }
+}
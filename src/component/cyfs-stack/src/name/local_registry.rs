@@ -0,0 +1,143 @@
+use super::backend::*;
+use crate::root_state::GlobalStateInputProcessorRef;
+use crate::root_state::GlobalStateOutputTransformer;
+use cyfs_base::*;
+use cyfs_core::*;
+use cyfs_lib::*;
+
+use std::sync::Arc;
+
+// 本地注册表的结果缓存一分钟，保证root_state上的修改可以较快生效
+const LOCAL_NAME_CACHE_TIMEOUT_IN_MICRO_SECS: u64 = 1000 * 1000 * 60;
+
+pub(crate) struct LocalNameRecord {
+    pub info: NameInfo,
+    pub record: NONObjectInfo,
+    pub owner: NONObjectInfo,
+}
+
+// zone内的本地名字注册表，记录保存在system dec的root_state上，需要owner签名
+#[derive(Clone)]
+pub(crate) struct LocalNameRegistry {
+    root_state_stub: GlobalStateStub,
+    noc: NamedObjectCacheRef,
+}
+
+impl LocalNameRegistry {
+    pub fn new(root_state: GlobalStateInputProcessorRef, noc: NamedObjectCacheRef) -> Self {
+        let processor =
+            GlobalStateOutputTransformer::new(root_state, RequestSourceInfo::new_local_system());
+        let root_state_stub = GlobalStateStub::new(
+            processor,
+            None,
+            Some(cyfs_core::get_system_dec_app().to_owned()),
+        );
+
+        Self {
+            root_state_stub,
+            noc,
+        }
+    }
+
+    pub fn into_ref(self) -> NameResolverBackendRef {
+        Arc::new(Box::new(self))
+    }
+
+    // 读取并校验name对应的注册记录
+    pub async fn load_record(&self, name: &str) -> BuckyResult<Option<LocalNameRecord>> {
+        if NameRegistryRecord::check_name(name).is_err() {
+            return Ok(None);
+        }
+
+        let op_env = self.root_state_stub.create_path_op_env().await?;
+
+        let path = NameRegistryRecord::registry_path(name);
+        let ret = op_env.get_by_path(&path).await;
+        let _ = op_env.abort().await;
+
+        let record_id = match ret? {
+            Some(id) => id,
+            None => {
+                debug!(
+                    "get name from local registry but not found! name={}, path={}",
+                    name, path
+                );
+                return Ok(None);
+            }
+        };
+
+        let record = self.load_object(&record_id).await?;
+        let record_obj = Storage::clone_from_slice(&record.object_raw)?;
+
+        let (_, info) = NameRegistryRecord::decode(&record_obj)?;
+        let owner = match &info.owner {
+            Some(owner_id) => self.load_object(owner_id).await?,
+            None => {
+                let msg = format!(
+                    "name registry record has no owner! name={}, record={}",
+                    name, record_id
+                );
+                warn!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
+            }
+        };
+
+        let info = {
+            let owner_obj = owner.object_if_none_then_decode()?;
+            NameRegistryRecord::verify(name, &record_obj, &owner_obj).await?
+        };
+
+        info!(
+            "get name from local registry: name={}, record={}, link={}",
+            name, record_id, info.record.link
+        );
+
+        Ok(Some(LocalNameRecord {
+            info,
+            record,
+            owner,
+        }))
+    }
+
+    async fn load_object(&self, object_id: &ObjectId) -> BuckyResult<NONObjectInfo> {
+        let noc_req = NamedObjectCacheGetObjectRequest {
+            source: RequestSourceInfo::new_local_system(),
+            object_id: object_id.to_owned(),
+            last_access_rpath: None,
+            flags: 0,
+        };
+
+        match self.noc.get_object(&noc_req).await? {
+            Some(data) => Ok(data.object),
+            None => {
+                let msg = format!(
+                    "load object for local name registry but not found! id={}",
+                    object_id
+                );
+                warn!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::NotFound, msg))
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl NameResolverBackend for LocalNameRegistry {
+    fn name(&self) -> &str {
+        "local-registry"
+    }
+
+    fn not_found_ttl(&self) -> u64 {
+        NAME_CACHE_LOCAL_NOT_FOUND_TIMEOUT_IN_MICRO_SECS
+    }
+
+    async fn get_name(&self, name: &str) -> BuckyResult<Option<NameResolveResult>> {
+        let ret = self.load_record(name).await?.map(|record| NameResolveResult {
+            info: record.info,
+            state: NameState::Normal,
+            ttl: Some(LOCAL_NAME_CACHE_TIMEOUT_IN_MICRO_SECS),
+        });
+
+        Ok(ret)
+    }
+}
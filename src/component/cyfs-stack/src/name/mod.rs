@@ -1,4 +1,10 @@
+mod backend;
+mod lan;
+mod local_registry;
 mod name_cache;
 mod name_resolver;
 
-pub(crate) use name_resolver::*;
\ No newline at end of file
+pub(crate) use backend::*;
+pub(crate) use lan::*;
+pub(crate) use local_registry::*;
+pub(crate) use name_resolver::*;
@@ -56,18 +56,25 @@ impl fmt::Display for NameItemStatus {
 pub(super) struct NameCacheItem {
     pub status: NameItemStatus,
 
-    // 最后一次从meta查找的结果，只有成功后才更新status和link
+    // 最后一次从解析链查找的结果，只有成功后才更新status和link
     pub last_resolve_status: NameItemStatus,
 
-    // 最后一次从meta查找时刻
+    // 最后一次从解析链查找时刻
     pub last_tick: u64,
 
+    // 缓存的有效期，由给出结果的解析后端决定，0表示使用默认值
+    pub ttl: u64,
+
     pub link: Option<NameLink>,
 }
 
 impl fmt::Display for NameCacheItem {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "({},{},{:?})", self.status, self.last_tick, self.link)
+        write!(
+            f,
+            "({},{},{},{:?})",
+            self.status, self.last_tick, self.ttl, self.link
+        )
     }
 }
 
@@ -77,6 +84,7 @@ impl NameCacheItem {
             status: NameItemStatus::Init,
             last_resolve_status: NameItemStatus::Init,
             last_tick: bucky_time_now(),
+            ttl: 0,
             link: None,
         }
     }
@@ -90,6 +98,7 @@ impl NameCacheItem {
         self.status = NameItemStatus::Init;
         self.last_resolve_status = NameItemStatus::Init;
         self.last_tick = bucky_time_now();
+        self.ttl = 0;
         self.link = None;
     }
 }
@@ -138,6 +147,9 @@ impl JsonCodec<NameCacheItem> for NameCacheItem {
             "last_tick".to_owned(),
             Value::String(self.last_tick.to_string()),
         );
+        if self.ttl > 0 {
+            obj.insert("ttl".to_owned(), Value::String(self.ttl.to_string()));
+        }
 
         if let Some(ref link) = self.link {
             obj.insert("link".to_owned(), Value::Object(link.encode_json()));
@@ -149,6 +161,7 @@ impl JsonCodec<NameCacheItem> for NameCacheItem {
     fn decode_json(obj: &Map<String, Value>) -> BuckyResult<Self> {
         let mut status = None;
         let mut last_tick = None;
+        let mut ttl = 0;
         let mut link = None;
 
         for (k, v) in obj {
@@ -161,6 +174,9 @@ impl JsonCodec<NameCacheItem> for NameCacheItem {
                 "last_tick" => {
                     last_tick = Some(JsonCodecHelper::decode_from_string(v)?);
                 }
+                "ttl" => {
+                    ttl = JsonCodecHelper::decode_from_string(v)?;
+                }
                 "link" => {
                     link = Some(JsonCodecHelper::decode_from_object(v)?);
                 }
@@ -195,6 +211,7 @@ impl JsonCodec<NameCacheItem> for NameCacheItem {
             status: status.clone(),
             last_resolve_status: status,
             last_tick: last_tick.unwrap(),
+            ttl,
             link,
         };
 
@@ -1,10 +1,12 @@
+use super::backend::*;
 use super::name_cache::*;
-use crate::meta::*;
+use crate::zone::ZoneManagerRef;
 use cyfs_base::*;
 use cyfs_lib::*;
 
 use cyfs_debug::Mutex;
 use futures::future::{AbortHandle, Abortable};
+use futures::stream::{FuturesUnordered, StreamExt};
 use std::collections::{hash_map::Entry, HashMap};
 use std::net::IpAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -14,10 +16,6 @@ use std::sync::Arc;
 const NAME_CACHE_TIMEOUT_IN_MICRO_SECS: u64 = 1000 * 1000 * 60 * 60 * 24;
 // const NAME_CACHE_TIMEOUT_IN_MICRO_SECS: u64 = 1000 * 1000 * 60; // 暂时改为一分钟
 
-// name查询但不存在的超时时间，默认一小时
-const NAME_CACHE_NOT_FOUND_TIMEOUT_IN_MICRO_SECS: u64 = 1000 * 1000 * 60 * 60;
-// const NAME_CACHE_NOT_FOUND_TIMEOUT_IN_MICRO_SECS: u64 = 1000 * 1000 * 60; // 暂时改为一分钟
-
 // 查询出错重试的最大时长，共享同一个
 const NAME_CACHE_ERROR_MAX_RETRY_INTERVAL_IN_MICRO_SECS: u64 = 1000 * 1000 * 60 * 60;
 // const NAME_CACHE_ERROR_MAX_RETRY_INTERVAL_IN_MICRO_SECS: u64 = 1000 * 1000 * 60; // 暂时改为一分钟
@@ -40,10 +38,166 @@ struct NameResolvingItem {
     // result: Option<BuckyResult<Option<(NameInfo, NameState)>>>,
 }
 
+// 解析链，按顺序生效，第一个给出结果的后端生效
+// 每个后端不存在的结果单独记录，在各自的有效期内不再重复查询
+struct NameResolveChain {
+    backends: Vec<NameResolverBackendRef>,
+
+    // name -> 每个后端最近一次返回不存在的时刻，0表示没有记录
+    not_found: Mutex<HashMap<String, Vec<u64>>>,
+}
+
+impl NameResolveChain {
+    fn new(backends: Vec<NameResolverBackendRef>) -> Self {
+        Self {
+            backends,
+            not_found: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn reset(&self, name: &str) {
+        self.not_found.lock().unwrap().remove(name);
+    }
+
+    // 所有后端都不存在时，缓存结果的有效期以最先过期的后端为准
+    fn not_found_ttl(&self, name: &str) -> u64 {
+        let now = bucky_time_now();
+        let list = self.not_found.lock().unwrap();
+        let ret = match list.get(name) {
+            Some(ticks) => self
+                .backends
+                .iter()
+                .zip(ticks.iter())
+                .map(|(backend, tick)| (tick + backend.not_found_ttl()).saturating_sub(now))
+                .min(),
+            None => None,
+        };
+
+        // 有效期为0表示使用默认值，所以至少为1
+        ret.unwrap_or(NAME_CACHE_NOT_FOUND_TIMEOUT_IN_MICRO_SECS).max(1)
+    }
+
+    fn skip_list(&self, name: &str, force: bool) -> Vec<bool> {
+        let now = bucky_time_now();
+        let list = self.not_found.lock().unwrap();
+        match list.get(name) {
+            Some(ticks) if !force => self
+                .backends
+                .iter()
+                .zip(ticks.iter())
+                .map(|(backend, tick)| *tick > 0 && now.saturating_sub(*tick) < backend.not_found_ttl())
+                .collect(),
+            _ => vec![false; self.backends.len()],
+        }
+    }
+
+    fn on_not_found(&self, name: &str, index: usize) {
+        let mut list = self.not_found.lock().unwrap();
+        let ticks = list
+            .entry(name.to_owned())
+            .or_insert_with(|| vec![0; self.backends.len()]);
+        ticks[index] = bucky_time_now();
+    }
+
+    // 各个后端并发查询，但按照解析链的顺序生效；所有后端都没有结果时，只要有一个出错就认为解析出错
+    async fn resolve(&self, name: &str, force: bool) -> BuckyResult<Option<NameResolveResult>> {
+        let skip_list = self.skip_list(name, force);
+
+        let mut results: Vec<Option<BuckyResult<Option<NameResolveResult>>>> = vec![];
+        let mut pending = FuturesUnordered::new();
+        for (index, backend) in self.backends.iter().enumerate() {
+            if skip_list[index] {
+                debug!(
+                    "resolve name but backend's not found still valid: name={}, backend={}",
+                    name,
+                    backend.name()
+                );
+                results.push(Some(Ok(None)));
+                continue;
+            }
+
+            results.push(None);
+            let backend = backend.clone();
+            let name = name.to_owned();
+            pending.push(async move { (index, backend.get_name(&name).await) });
+        }
+
+        loop {
+            let mut found = None;
+            let mut complete = true;
+            for (index, ret) in results.iter().enumerate() {
+                match ret {
+                    Some(Ok(Some(_))) => {
+                        found = Some(index);
+                        break;
+                    }
+                    Some(_) => {}
+                    None => {
+                        complete = false;
+                        if self.backends[index].block_chain() {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            if let Some(index) = found {
+                info!(
+                    "resolve name from backend success: name={}, backend={}",
+                    name,
+                    self.backends[index].name()
+                );
+                self.reset(name);
+                return results[index].take().unwrap();
+            }
+
+            if complete {
+                break;
+            }
+
+            let (index, ret) = pending.next().await.unwrap();
+            let backend = &self.backends[index];
+            match &ret {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    debug!(
+                        "resolve name from backend but not found: name={}, backend={}",
+                        name,
+                        backend.name()
+                    );
+                    self.on_not_found(name, index);
+                }
+                Err(e) => {
+                    warn!(
+                        "resolve name from backend error: name={}, backend={}, {}",
+                        name,
+                        backend.name(),
+                        e
+                    );
+                }
+            }
+            results[index] = Some(ret);
+        }
+
+        let mut last_err = None;
+        for ret in results {
+            if let Some(Err(e)) = ret {
+                last_err = Some(e);
+            }
+        }
+
+        match last_err {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct NameResolver {
     cache: NOCCollectionSync<NameCache>,
-    meta_cache: MetaCacheRef,
+
+    chain: Arc<NameResolveChain>,
 
     resolving_list: Arc<Mutex<HashMap<String, NameResolvingItem>>>,
 
@@ -51,10 +205,14 @@ pub struct NameResolver {
 }
 
 impl NameResolver {
-    pub fn new(meta_cache: MetaCacheRef, noc: NamedObjectCacheRef) -> Self {
+    pub(crate) fn new(backends: Vec<NameResolverBackendRef>, noc: NamedObjectCacheRef) -> Self {
         let id = "cyfs-name-cache";
+
+        let list: Vec<&str> = backends.iter().map(|backend| backend.name()).collect();
+        info!("name resolver chain: {:?}", list);
+
         Self {
-            meta_cache,
+            chain: Arc::new(NameResolveChain::new(backends)),
             cache: NOCCollectionSync::new(id, noc),
             resolving_list: Arc::new(Mutex::new(HashMap::new())),
             next_retry_interval: Arc::new(AtomicU64::new(1000 * 1000 * 2)),
@@ -83,7 +241,16 @@ impl NameResolver {
         Ok(())
     }
 
+    // 部分后端依赖zone_manager，需要在zone_manager初始化后绑定
+    pub(crate) fn bind_zone_manager(&self, zone_manager: &ZoneManagerRef) {
+        for backend in self.chain.backends.iter() {
+            backend.bind_zone_manager(zone_manager);
+        }
+    }
+
     pub fn reset_name(&self, name: &str) -> bool {
+        self.chain.reset(name);
+
         let mut data = self.cache.coll().lock().unwrap();
         match data.try_get(name) {
             Some(item) => {
@@ -198,12 +365,12 @@ impl NameResolver {
                 NameItemStatus::Init => {
                     // assert!(item.link.is_none());
 
-                    // 向meta发起解析
+                    // 向解析链发起解析
                 }
             }
         }
 
-        self.resolve_from_meta(name).await;
+        self.resolve_from_backends(name, false).await;
 
         Ok(LookupResult::Continue(()))
     }
@@ -212,12 +379,22 @@ impl NameResolver {
         let now = bucky_time_now();
         if item.status == NameItemStatus::Ready {
             assert!(item.last_tick > 0);
-            if now - item.last_tick >= NAME_CACHE_TIMEOUT_IN_MICRO_SECS {
+            let timeout = if item.ttl > 0 {
+                item.ttl
+            } else {
+                NAME_CACHE_TIMEOUT_IN_MICRO_SECS
+            };
+            if now - item.last_tick >= timeout {
                 return true;
             }
         } else if item.status == NameItemStatus::NotFound {
             assert!(item.last_tick > 0);
-            if now - item.last_tick >= NAME_CACHE_NOT_FOUND_TIMEOUT_IN_MICRO_SECS {
+            let timeout = if item.ttl > 0 {
+                item.ttl
+            } else {
+                NAME_CACHE_NOT_FOUND_TIMEOUT_IN_MICRO_SECS
+            };
+            if now - item.last_tick >= timeout {
                 return true;
             }
         } else if item.last_resolve_status == NameItemStatus::Error {
@@ -232,14 +409,14 @@ impl NameResolver {
     }
 
     async fn resolve_impl(&self, name: &str) -> BuckyResult<LookupResult> {
-        // 首先从meta解析，meta解析内部会更新缓存，再从缓存读取结果(last_resolve_status)
-        self.resolve_from_meta(name).await;
+        // 首先从解析链解析，内部会更新缓存，再从缓存读取结果(last_resolve_status)
+        self.resolve_from_backends(name, true).await;
 
         let mut data = self.cache.coll().lock().unwrap();
         let item = data.get(name);
 
         // 这里只判断last_resolve_status
-        // 如果缓存里面有结果，但是从meta解析失败了，那么也要认为resolve失败
+        // 如果缓存里面有结果，但是从解析链解析失败了，那么也要认为resolve失败
         // 这种情况下status=NotFound/Ready,但last_resolve_status=Error
         match item.last_resolve_status {
             NameItemStatus::Ready => {
@@ -273,7 +450,8 @@ impl NameResolver {
         }
     }
 
-    async fn resolve_from_meta(&self, name: &str) {
+    // force为true时忽略各个后端缓存的不存在结果
+    async fn resolve_from_backends(&self, name: &str, force: bool) {
         info!("will resolve name: {}", name);
 
         let (abort_handle, abort_registration) = AbortHandle::new_pair();
@@ -289,7 +467,7 @@ impl NameResolver {
                     let this = self.clone();
                     let name = name.to_owned();
                     async_std::task::spawn(async move {
-                        this.resolve_from_meta_impl(&name).await;
+                        this.resolve_from_backends_impl(&name, force).await;
                     });
                 }
                 Entry::Occupied(mut o) => {
@@ -303,18 +481,8 @@ impl NameResolver {
         let _ = Abortable::new(async_std::future::pending::<()>(), abort_registration).await;
     }
 
-    async fn resolve_from_meta_impl(&self, name: &str) {
-        let dur = std::time::Duration::from_secs(30);
-        let result;
-        match async_std::future::timeout(dur, self.meta_cache.get_name(name)).await {
-            Ok(ret) => {
-                result = ret;
-            }
-            Err(async_std::future::TimeoutError { .. }) => {
-                error!("get name from meta cache timeout: name={}", name);
-                result = Err(BuckyError::from(BuckyErrorCode::Timeout));
-            }
-        };
+    async fn resolve_from_backends_impl(&self, name: &str, force: bool) {
+        let result = self.chain.resolve(name, force).await;
 
         // 更新缓存
         self.update_resolve_result(name, result);
@@ -329,10 +497,10 @@ impl NameResolver {
         }
     }
 
-    fn update_resolve_result(&self, name: &str, ret: BuckyResult<Option<(NameInfo, NameState)>>) {
+    fn update_resolve_result(&self, name: &str, ret: BuckyResult<Option<NameResolveResult>>) {
         let mut data = self.cache.coll().lock().unwrap();
         let item = data.get(name);
-        // 有可能不为none，比如强制向meta发起了解析
+        // 有可能不为none，比如强制向解析链发起了解析
         // assert!(item.link.is_none());
 
         // 更新最后一次操作的时间戳
@@ -340,24 +508,26 @@ impl NameResolver {
 
         match ret {
             Ok(None) => {
-                info!("resolve name from meta but not found: name={}", name);
+                info!("resolve name but not found: name={}", name);
                 item.status = NameItemStatus::NotFound;
                 item.last_resolve_status = NameItemStatus::NotFound;
+                item.ttl = self.chain.not_found_ttl(name);
             }
             Ok(Some(value)) => {
                 item.status = NameItemStatus::Ready;
                 item.last_resolve_status = NameItemStatus::Ready;
+                item.ttl = value.ttl.unwrap_or(NAME_CACHE_TIMEOUT_IN_MICRO_SECS);
 
-                let link = value.0.record.link;
+                let link = value.info.record.link;
                 info!(
-                    "resolve name from meta success: name={}, current={:?}, new={:?}",
-                    name, item.link, link
+                    "resolve name success: name={}, state={:?}, current={:?}, new={:?}, ttl={}",
+                    name, value.state, item.link, link, item.ttl
                 );
                 item.link = Some(link);
             }
             Err(e) => {
                 info!(
-                    "resolve name from meta error: name={}, status={}, {}",
+                    "resolve name error: name={}, status={}, {}",
                     name, item.status, e
                 );
 
@@ -379,3 +549,203 @@ impl NameResolver {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::AtomicUsize;
+    use std::time::{Duration, Instant};
+
+    struct TestBackend {
+        name: &'static str,
+        delay: Duration,
+        link: Option<ObjectId>,
+        error: bool,
+        block_chain: bool,
+        not_found_ttl: u64,
+        count: AtomicUsize,
+    }
+
+    impl TestBackend {
+        fn new(name: &'static str, delay_ms: u64, link: Option<ObjectId>) -> Self {
+            Self {
+                name,
+                delay: Duration::from_millis(delay_ms),
+                link,
+                error: false,
+                block_chain: true,
+                not_found_ttl: NAME_CACHE_NOT_FOUND_TIMEOUT_IN_MICRO_SECS,
+                count: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl NameResolverBackend for Arc<TestBackend> {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn not_found_ttl(&self) -> u64 {
+            self.not_found_ttl
+        }
+
+        fn block_chain(&self) -> bool {
+            self.block_chain
+        }
+
+        async fn get_name(&self, _name: &str) -> BuckyResult<Option<NameResolveResult>> {
+            self.count.fetch_add(1, Ordering::SeqCst);
+            async_std::task::sleep(self.delay).await;
+
+            if self.error {
+                return Err(BuckyError::from(BuckyErrorCode::Timeout));
+            }
+
+            let ret = self.link.as_ref().map(|link| NameResolveResult {
+                info: NameInfo {
+                    sub_records: Default::default(),
+                    record: NameRecord {
+                        link: NameLink::ObjectLink(link.clone()),
+                        user_data: "".to_owned(),
+                    },
+                    owner: None,
+                },
+                state: NameState::Normal,
+                ttl: None,
+            });
+            Ok(ret)
+        }
+    }
+
+    fn new_chain(backends: &[Arc<TestBackend>]) -> NameResolveChain {
+        let list = backends
+            .iter()
+            .map(|backend| Arc::new(Box::new(backend.clone()) as Box<dyn NameResolverBackend>))
+            .collect();
+        NameResolveChain::new(list)
+    }
+
+    fn link_of(ret: Option<NameResolveResult>) -> ObjectId {
+        match ret.unwrap().info.record.link {
+            NameLink::ObjectLink(id) => id,
+            _ => unreachable!(),
+        }
+    }
+
+    fn new_id(index: u8) -> ObjectId {
+        let mut buf = [0u8; 32];
+        buf[1] = index;
+        ObjectId::clone_from_slice(&buf).unwrap()
+    }
+
+    #[async_std::test]
+    async fn test_chain_order() {
+        // 阻塞的后端即使较慢，也优先于后面的后端
+        let registry = Arc::new(TestBackend::new("registry", 200, Some(new_id(1))));
+        let meta = Arc::new(TestBackend::new("meta", 0, Some(new_id(2))));
+        let chain = new_chain(&[registry.clone(), meta.clone()]);
+
+        let ret = chain.resolve("test", false).await.unwrap();
+        assert_eq!(link_of(ret), new_id(1));
+
+        // 前面的后端没有结果时，使用后面的
+        let registry = Arc::new(TestBackend::new("registry", 0, None));
+        let chain = new_chain(&[registry.clone(), meta.clone()]);
+        let ret = chain.resolve("test", false).await.unwrap();
+        assert_eq!(link_of(ret), new_id(2));
+    }
+
+    #[async_std::test]
+    async fn test_chain_race() {
+        // 局域网查询和meta竞争，没有应答时不需要等到超时
+        let mut lan = TestBackend::new("lan", 2000, None);
+        lan.block_chain = false;
+        let lan = Arc::new(lan);
+        let meta = Arc::new(TestBackend::new("meta", 50, Some(new_id(2))));
+        let chain = new_chain(&[lan.clone(), meta.clone()]);
+
+        let begin = Instant::now();
+        let ret = chain.resolve("test", false).await.unwrap();
+        assert_eq!(link_of(ret), new_id(2));
+        assert!(begin.elapsed() < Duration::from_secs(1));
+
+        // 先给出结果的局域网应答同样生效
+        let mut lan = TestBackend::new("lan", 50, Some(new_id(1)));
+        lan.block_chain = false;
+        let lan = Arc::new(lan);
+        let meta = Arc::new(TestBackend::new("meta", 2000, Some(new_id(2))));
+        let chain = new_chain(&[lan.clone(), meta.clone()]);
+
+        let begin = Instant::now();
+        let ret = chain.resolve("test", false).await.unwrap();
+        assert_eq!(link_of(ret), new_id(1));
+        assert!(begin.elapsed() < Duration::from_secs(1));
+    }
+
+    #[async_std::test]
+    async fn test_chain_not_found() {
+        // 局域网不存在的结果很快过期，meta的不存在结果一直有效
+        let mut lan = TestBackend::new("lan", 0, None);
+        lan.not_found_ttl = 0;
+        let lan = Arc::new(lan);
+        let meta = Arc::new(TestBackend::new("meta", 0, None));
+        let chain = new_chain(&[lan.clone(), meta.clone()]);
+
+        assert!(chain.resolve("test", false).await.unwrap().is_none());
+        assert_eq!(lan.count.load(Ordering::SeqCst), 1);
+        assert_eq!(meta.count.load(Ordering::SeqCst), 1);
+        assert_eq!(chain.not_found_ttl("test"), 1);
+
+        // 只重新查询已经过期的后端
+        assert!(chain.resolve("test", false).await.unwrap().is_none());
+        assert_eq!(lan.count.load(Ordering::SeqCst), 2);
+        assert_eq!(meta.count.load(Ordering::SeqCst), 1);
+
+        // 强制解析时查询所有后端
+        assert!(chain.resolve("test", true).await.unwrap().is_none());
+        assert_eq!(lan.count.load(Ordering::SeqCst), 3);
+        assert_eq!(meta.count.load(Ordering::SeqCst), 2);
+
+        // reset之后重新查询所有后端
+        chain.reset("test");
+        assert!(chain.resolve("test", false).await.unwrap().is_none());
+        assert_eq!(meta.count.load(Ordering::SeqCst), 3);
+
+        // 其它名字不受影响
+        assert!(chain.resolve("other", false).await.unwrap().is_none());
+        assert_eq!(meta.count.load(Ordering::SeqCst), 4);
+
+        // meta的有效期内
+        let ttl = {
+            let meta_only = new_chain(&[meta.clone()]);
+            assert!(meta_only.resolve("test", false).await.unwrap().is_none());
+            meta_only.not_found_ttl("test")
+        };
+        assert!(ttl > NAME_CACHE_NOT_FOUND_TIMEOUT_IN_MICRO_SECS - 1000 * 1000 * 60);
+    }
+
+    #[async_std::test]
+    async fn test_chain_error() {
+        let mut meta = TestBackend::new("meta", 0, None);
+        meta.error = true;
+        let meta = Arc::new(meta);
+        let lan = Arc::new(TestBackend::new("lan", 0, None));
+        let chain = new_chain(&[lan.clone(), meta.clone()]);
+
+        let e = chain.resolve("test", false).await.unwrap_err();
+        assert_eq!(e.code(), BuckyErrorCode::Timeout);
+
+        // 出错的后端不记录不存在的结果，下次还会查询
+        let _ = chain.resolve("test", false).await;
+        assert_eq!(meta.count.load(Ordering::SeqCst), 2);
+        assert_eq!(lan.count.load(Ordering::SeqCst), 1);
+
+        // 有结果时忽略其它后端的错误
+        let lan = Arc::new(TestBackend::new("lan", 0, Some(new_id(1))));
+        let chain = new_chain(&[meta.clone(), lan.clone()]);
+        let ret = chain.resolve("test", false).await.unwrap();
+        assert_eq!(link_of(ret), new_id(1));
+    }
+}
@@ -57,6 +57,42 @@ impl NDNAclInputProcessor {
         }
     }
 
+    fn check_policy(
+        &self,
+        common: &NDNInputRequestCommon,
+        category: RouterHandlerCategory,
+        object_id: &ObjectId,
+    ) -> BuckyResult<()> {
+        let req = AclPolicyRequest {
+            source: &common.source,
+            category,
+            req_path: common.req_path.as_deref(),
+            object_id: Some(object_id),
+            obj_type: None,
+        };
+
+        self.acl.check_policy(&req)
+    }
+
+    // 请求处理完成后记录审计日志
+    fn audit<T>(
+        &self,
+        common: &NDNInputRequestCommon,
+        category: &str,
+        op_type: RequestOpType,
+        object_id: Option<&ObjectId>,
+        ret: &BuckyResult<T>,
+    ) {
+        self.acl.audit().record_result(
+            &common.source,
+            category,
+            op_type,
+            object_id,
+            common.req_path.as_deref(),
+            ret,
+        );
+    }
+
     async fn check_access(
         &self,
         req_path: &RequestGlobalStatePath,
@@ -159,7 +195,7 @@ impl NDNAclInputProcessor {
 
         let (file_id, file) = self.loader()?.get_file_object(&req, None).await?;
         assert_eq!(file_id, file.desc().calculate_id());
-        let user_data = NDNForwardObjectData { file, file_id };
+        let user_data = NDNForwardObjectData::new(file, file_id);
         req.common.user_data = Some(user_data.to_any());
 
         Ok(req)
@@ -169,63 +205,132 @@ impl NDNAclInputProcessor {
 #[async_trait::async_trait]
 impl NDNInputProcessor for NDNAclInputProcessor {
     async fn put_data(&self, req: NDNPutDataInputRequest) -> BuckyResult<NDNPutDataInputResponse> {
-        if !req.common.source.is_current_zone() {
-            let msg = format!(
-                "put_data only allow within the same zone! {}",
-                req.object_id
-            );
-            warn!("{}", msg);
-            return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
-        }
+        let common = req.common.clone();
+        let object_id = req.object_id.clone();
 
-        self.next.put_data(req).await
-    }
+        let ret: BuckyResult<NDNPutDataInputResponse> = async {
+            self.check_policy(&req.common, RouterHandlerCategory::PutData, &req.object_id)?;
 
-    async fn get_data(&self, req: NDNGetDataInputRequest) -> BuckyResult<NDNGetDataInputResponse> {
-        let req = match req.object_id.obj_type_code() {
-            ObjectTypeCode::Chunk => self.on_get_chunk(req).await?,
-            ObjectTypeCode::File | ObjectTypeCode::Dir | ObjectTypeCode::ObjectMap => {
-                self.on_get_file(req).await?
-            }
-            code @ _ => {
+            if !req.common.source.is_current_zone() {
                 let msg = format!(
-                    "ndn get data but unsupport object type: id={}, type={:?}",
-                    req.object_id, code,
+                    "put_data only allow within the same zone! {}",
+                    req.object_id
                 );
-                error!("{}", msg);
-                return Err(BuckyError::new(BuckyErrorCode::UnSupport, msg));
+                warn!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
             }
-        };
 
-        self.next.get_data(req).await
+            self.next.put_data(req).await
+        }
+        .await;
+
+        self.audit(
+            &common,
+            RouterHandlerCategory::PutData.as_str(),
+            RequestOpType::Write,
+            Some(&object_id),
+            &ret,
+        );
+
+        ret
+    }
+
+    async fn get_data(&self, req: NDNGetDataInputRequest) -> BuckyResult<NDNGetDataInputResponse> {
+        let common = req.common.clone();
+        let object_id = req.object_id.clone();
+
+        let ret: BuckyResult<NDNGetDataInputResponse> = async {
+            self.check_policy(&req.common, RouterHandlerCategory::GetData, &req.object_id)?;
+
+            let req = match req.object_id.obj_type_code() {
+                ObjectTypeCode::Chunk => self.on_get_chunk(req).await?,
+                ObjectTypeCode::File | ObjectTypeCode::Dir | ObjectTypeCode::ObjectMap => {
+                    self.on_get_file(req).await?
+                }
+                code @ _ => {
+                    let msg = format!(
+                        "ndn get data but unsupport object type: id={}, type={:?}",
+                        req.object_id, code,
+                    );
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::UnSupport, msg));
+                }
+            };
+
+            self.next.get_data(req).await
+        }
+        .await;
+
+        self.audit(
+            &common,
+            RouterHandlerCategory::GetData.as_str(),
+            RequestOpType::Read,
+            Some(&object_id),
+            &ret,
+        );
+
+        ret
     }
 
     async fn delete_data(
         &self,
         req: NDNDeleteDataInputRequest,
     ) -> BuckyResult<NDNDeleteDataInputResponse> {
-        if !req.common.source.is_current_zone() {
-            let msg = format!(
-                "delete_data only allow within the same zone! {}",
-                req.object_id
-            );
-            warn!("{}", msg);
-            return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+        let common = req.common.clone();
+        let object_id = req.object_id.clone();
+
+        let ret: BuckyResult<NDNDeleteDataInputResponse> = async {
+            self.check_policy(&req.common, RouterHandlerCategory::DeleteData, &req.object_id)?;
+
+            if !req.common.source.is_current_zone() {
+                let msg = format!(
+                    "delete_data only allow within the same zone! {}",
+                    req.object_id
+                );
+                warn!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+            }
+
+            self.next.delete_data(req).await
         }
+        .await;
+
+        self.audit(
+            &common,
+            RouterHandlerCategory::DeleteData.as_str(),
+            RequestOpType::Write,
+            Some(&object_id),
+            &ret,
+        );
 
-        self.next.delete_data(req).await
+        ret
     }
 
     async fn query_file(
         &self,
         req: NDNQueryFileInputRequest,
     ) -> BuckyResult<NDNQueryFileInputResponse> {
-        if !req.common.source.is_current_zone() {
-            let msg = format!("query_file only allow within the same zone! {}", req);
-            warn!("{}", msg);
-            return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+        let common = req.common.clone();
+
+        let ret: BuckyResult<NDNQueryFileInputResponse> = async {
+            if !req.common.source.is_current_zone() {
+                let msg = format!("query_file only allow within the same zone! {}", req);
+                warn!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+            }
+
+            self.next.query_file(req).await
         }
+        .await;
+
+        self.audit(
+            &common,
+            "query_file",
+            RequestOpType::Read,
+            None,
+            &ret,
+        );
 
-        self.next.query_file(req).await
+        ret
     }
 }
@@ -1,4 +1,5 @@
 use crate::ndn::*;
+use cyfs_bdt::DownloadTaskPriority;
 use cyfs_bdt_ext::*;
 use cyfs_base::*;
 use cyfs_lib::*;
@@ -6,14 +7,32 @@ use cyfs_lib::*;
 use std::convert::TryFrom;
 use std::sync::Arc;
 
+// 流式读取参数，比如浏览器里面播放视频
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct NDNForwardStreamParam {
+    pub priority: DownloadTaskPriority,
+
+    // 预先下载读取位置之后的chunk数
+    pub prefetch: usize,
+}
+
 pub(crate) struct NDNForwardObjectData {
     pub file: File,
     pub file_id: ObjectId,
+    pub stream: Option<NDNForwardStreamParam>,
 }
 
 pub(crate) type NDNForwardObjectDataRef = Arc<NDNForwardObjectData>;
 
 impl NDNForwardObjectData {
+    pub fn new(file: File, file_id: ObjectId) -> Self {
+        Self {
+            file,
+            file_id,
+            stream: None,
+        }
+    }
+
     pub fn to_any(self) -> NDNInputRequestUserData {
         Arc::new(self)
     }
@@ -81,9 +100,25 @@ impl NDNForwardDataOutputProcessor {
         }
 
         let (data, length, group) = if need_process {
-            self.data_manager
-                .get_file(&req.common.source, &file, req.group.as_deref(), ranges)
-                .await?
+            match &udata.stream {
+                Some(stream) => {
+                    self.data_manager
+                        .get_file_with_priority(
+                            &req.common.source,
+                            &file,
+                            req.group.as_deref(),
+                            ranges,
+                            stream.priority,
+                            stream.prefetch,
+                        )
+                        .await?
+                }
+                None => {
+                    self.data_manager
+                        .get_file(&req.common.source, &file, req.group.as_deref(), ranges)
+                        .await?
+                }
+            }
         } else {
             (zero_bytes_reader(), 0, None)
         };
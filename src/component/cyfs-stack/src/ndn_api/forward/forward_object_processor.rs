@@ -44,7 +44,7 @@ impl NDNForwardObjectProcessor {
                     );
                     let (file_id, file) = self.get_file_object(&req).await?;
     
-                    let user_data = NDNForwardObjectData { file, file_id };
+                    let user_data = NDNForwardObjectData::new(file, file_id);
                     req.common.user_data = Some(user_data.to_any());
                 } else {
                     // already loaded outside
@@ -185,6 +185,7 @@ impl NDNObjectLoader {
 
             object_id: req_object,
             inner_path: req_inner_path,
+            projection: None,
         };
 
         let resp = self.non_processor.get_object(get_req).await?;
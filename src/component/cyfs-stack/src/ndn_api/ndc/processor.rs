@@ -146,7 +146,7 @@ impl NDCLevelInputProcessor {
         } else {
             let (file_id, file) = self.object_loader.get_file_object(&req, None).await?;
             assert_eq!(file_id, file.desc().calculate_id());
-            let user_data = NDNForwardObjectData { file, file_id };
+            let user_data = NDNForwardObjectData::new(file, file_id);
             Arc::new(user_data)
         };
 
@@ -67,12 +67,14 @@ impl NONInputTransformer {
 
             object_id: req.object_id,
             inner_path: req.inner_path,
+            projection: req.projection,
         };
 
         let out_resp = self.processor.get_object(out_req).await?;
 
         let resp = NONGetObjectInputResponse {
             object: out_resp.object,
+            part: out_resp.part,
             object_expires_time: out_resp.object_expires_time,
             object_update_time: out_resp.object_update_time,
             attr: out_resp.attr,
@@ -250,12 +252,14 @@ impl NONOutputTransformer {
 
             object_id: req.object_id,
             inner_path: req.inner_path,
+            projection: req.projection,
         };
 
         let in_resp = self.processor.get_object(in_req).await?;
 
         let resp = NONGetObjectOutputResponse {
             object: in_resp.object,
+            part: in_resp.part,
             object_expires_time: in_resp.object_expires_time,
             object_update_time: in_resp.object_update_time,
             attr: in_resp.attr,
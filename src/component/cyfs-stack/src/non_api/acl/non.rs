@@ -1,4 +1,4 @@
-use crate::acl::AclManagerRef;
+use crate::acl::{AclManagerRef, AclPolicyRequest};
 use crate::non::*;
 use cyfs_base::*;
 use cyfs_lib::*;
@@ -15,6 +15,77 @@ impl NONAclInputProcessor {
         let ret = Self { acl, next };
         Arc::new(Box::new(ret))
     }
+
+    fn check_policy(
+        &self,
+        common: &NONInputRequestCommon,
+        category: RouterHandlerCategory,
+        object_id: Option<&ObjectId>,
+        obj_type: Option<u16>,
+    ) -> BuckyResult<()> {
+        let req = AclPolicyRequest {
+            source: &common.source,
+            category,
+            req_path: common.req_path.as_deref(),
+            object_id,
+            obj_type,
+        };
+
+        self.acl.check_policy(&req)
+    }
+
+    // 标准对象可以直接从object_id得到obj_type，core和dec对象需要加载对象后才能确定
+    fn obj_type_of_id(object_id: &ObjectId) -> Option<u16> {
+        match object_id.obj_type_code() {
+            ObjectTypeCode::Custom => None,
+            code => Some(code.to_u16()),
+        }
+    }
+
+    async fn load_obj_type(&self, req: &NONDeleteObjectInputRequest) -> BuckyResult<Option<u16>> {
+        if let Some(obj_type) = Self::obj_type_of_id(&req.object_id) {
+            return Ok(Some(obj_type));
+        }
+
+        let get_req = NONGetObjectInputRequest {
+            common: req.common.clone(),
+            object_id: req.object_id.clone(),
+            inner_path: req.inner_path.clone(),
+            projection: None,
+        };
+
+        match self.next.get_object(get_req).await {
+            Ok(resp) => Ok(resp.object.object.as_ref().map(|o| o.obj_type())),
+            Err(e) if e.code() == BuckyErrorCode::NotFound => Ok(None),
+            Err(e) => {
+                error!(
+                    "load object for acl policy check failed! obj={}, {}",
+                    req.object_id, e
+                );
+                Err(e)
+            }
+        }
+    }
+
+    // 请求处理完成后记录审计日志，rmeta等权限检查在后续的处理器里面，所以需要等待整个请求完成
+    fn audit<T>(
+        &self,
+        source: &RequestSourceInfo,
+        category: RouterHandlerCategory,
+        op_type: RequestOpType,
+        object_id: Option<&ObjectId>,
+        req_path: Option<&str>,
+        ret: &BuckyResult<T>,
+    ) {
+        self.acl.audit().record_result(
+            source,
+            category.as_str(),
+            op_type,
+            object_id,
+            req_path,
+            ret,
+        );
+    }
 }
 
 #[async_trait::async_trait]
@@ -23,58 +94,200 @@ impl NONInputProcessor for NONAclInputProcessor {
         &self,
         req: NONPutObjectInputRequest,
     ) -> BuckyResult<NONPutObjectInputResponse> {
-        if !req.common.source.is_current_zone() {
-            let msg = format!(
-                "put_object only allow within the same zone! {}",
-                req.object.object_id
-            );
-            warn!("{}", msg);
-            return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+        let source = req.common.source.clone();
+        let req_path = req.common.req_path.clone();
+        let object_id = req.object.object_id.clone();
+
+        let ret: BuckyResult<NONPutObjectInputResponse> = async {
+            self.check_policy(
+                &req.common,
+                RouterHandlerCategory::PutObject,
+                Some(&req.object.object_id),
+                req.object.object.as_ref().map(|o| o.obj_type()),
+            )?;
+
+            if !req.common.source.is_current_zone() {
+                let msg = format!(
+                    "put_object only allow within the same zone! {}",
+                    req.object.object_id
+                );
+                warn!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+            }
+
+            self.next.put_object(req).await
         }
+        .await;
+
+        self.audit(
+            &source,
+            RouterHandlerCategory::PutObject,
+            RequestOpType::Write,
+            Some(&object_id),
+            req_path.as_deref(),
+            &ret,
+        );
 
-        self.next.put_object(req).await
+        ret
     }
 
     async fn get_object(
         &self,
         req: NONGetObjectInputRequest,
     ) -> BuckyResult<NONGetObjectInputResponse> {
-        self.next.get_object(req).await
+        let source = req.common.source.clone();
+        let req_path = req.common.req_path.clone();
+        let object_id = req.object_id.clone();
+
+        let ret: BuckyResult<NONGetObjectInputResponse> = async {
+            let obj_type = Self::obj_type_of_id(&req.object_id);
+            self.check_policy(
+                &req.common,
+                RouterHandlerCategory::GetObject,
+                Some(&req.object_id),
+                obj_type,
+            )?;
+
+            let common = req.common.clone();
+            let resp = self.next.get_object(req).await?;
+
+            // 返回的对象类型和请求前判断的不一致时(custom对象或者inner_path)，需要再次检查
+            // desc/body投影时按投影来源的对象检查
+            let (resp_object_id, resp_obj_type) = match &resp.part {
+                Some(part) => (&part.source_object_id, Some(part.obj_type)),
+                None => (
+                    &resp.object.object_id,
+                    resp.object.object.as_ref().map(|o| o.obj_type()),
+                ),
+            };
+            if resp_obj_type.is_some() && resp_obj_type != obj_type {
+                self.check_policy(
+                    &common,
+                    RouterHandlerCategory::GetObject,
+                    Some(resp_object_id),
+                    resp_obj_type,
+                )?;
+            }
+
+            Ok(resp)
+        }
+        .await;
+
+        self.audit(
+            &source,
+            RouterHandlerCategory::GetObject,
+            RequestOpType::Read,
+            Some(&object_id),
+            req_path.as_deref(),
+            &ret,
+        );
+
+        ret
     }
 
     async fn post_object(
         &self,
         req: NONPostObjectInputRequest,
     ) -> BuckyResult<NONPostObjectInputResponse> {
-        self.next.post_object(req).await
+        let source = req.common.source.clone();
+        let req_path = req.common.req_path.clone();
+        let object_id = req.object.object_id.clone();
+
+        let ret: BuckyResult<NONPostObjectInputResponse> = async {
+            self.check_policy(
+                &req.common,
+                RouterHandlerCategory::PostObject,
+                Some(&req.object.object_id),
+                req.object.object.as_ref().map(|o| o.obj_type()),
+            )?;
+
+            self.next.post_object(req).await
+        }
+        .await;
+
+        self.audit(
+            &source,
+            RouterHandlerCategory::PostObject,
+            RequestOpType::Call,
+            Some(&object_id),
+            req_path.as_deref(),
+            &ret,
+        );
+
+        ret
     }
 
     async fn select_object(
         &self,
         req: NONSelectObjectInputRequest,
     ) -> BuckyResult<NONSelectObjectInputResponse> {
-        if !req.common.source.is_current_zone() {
-            let msg = format!("select_object only allow within the same zone! {}", req);
-            warn!("{}", msg);
-            return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+        let source = req.common.source.clone();
+        let req_path = req.common.req_path.clone();
+
+        let ret: BuckyResult<NONSelectObjectInputResponse> = async {
+            self.check_policy(&req.common, RouterHandlerCategory::SelectObject, None, None)?;
+
+            if !req.common.source.is_current_zone() {
+                let msg = format!("select_object only allow within the same zone! {}", req);
+                warn!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+            }
+
+            self.next.select_object(req).await
         }
+        .await;
 
-        self.next.select_object(req).await
+        self.audit(
+            &source,
+            RouterHandlerCategory::SelectObject,
+            RequestOpType::Read,
+            None,
+            req_path.as_deref(),
+            &ret,
+        );
+
+        ret
     }
 
     async fn delete_object(
         &self,
         req: NONDeleteObjectInputRequest,
     ) -> BuckyResult<NONDeleteObjectInputResponse> {
-        if !req.common.source.is_current_zone() {
-            let msg = format!(
-                "delete_object only allow within the same zone! {}",
-                req.object_id
-            );
-            warn!("{}", msg);
-            return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+        let source = req.common.source.clone();
+        let req_path = req.common.req_path.clone();
+        let object_id = req.object_id.clone();
+
+        let ret: BuckyResult<NONDeleteObjectInputResponse> = async {
+            if !req.common.source.is_current_zone() {
+                let msg = format!(
+                    "delete_object only allow within the same zone! {}",
+                    req.object_id
+                );
+                warn!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+            }
+
+            let obj_type = self.load_obj_type(&req).await?;
+            self.check_policy(
+                &req.common,
+                RouterHandlerCategory::DeleteObject,
+                Some(&req.object_id),
+                obj_type,
+            )?;
+
+            self.next.delete_object(req).await
         }
+        .await;
+
+        self.audit(
+            &source,
+            RouterHandlerCategory::DeleteObject,
+            RequestOpType::Write,
+            Some(&object_id),
+            req_path.as_deref(),
+            &ret,
+        );
 
-        self.next.delete_object(req).await
+        ret
     }
 }
@@ -34,6 +34,7 @@ impl RequestUtil {
     ) {
         origin.object_id = handler.object_id;
         origin.inner_path = handler.inner_path;
+        origin.projection = handler.projection;
         Self::update_request_common(&mut origin.common, handler.common);
     }
     fn update_get_object_response(
@@ -41,6 +42,7 @@ impl RequestUtil {
         handler: NONGetObjectInputResponse,
     ) {
         origin.object = handler.object;
+        origin.part = handler.part;
     }
 
     fn update_post_object_request(
@@ -194,6 +194,7 @@ impl NONDirLoader {
             common: common.to_owned(),
             object_id: object_id.to_owned(),
             inner_path: None,
+            projection: None,
         };
 
         // dir+inner_path mode, only check the root object's access_string
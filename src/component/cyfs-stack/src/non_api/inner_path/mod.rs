@@ -1,5 +1,6 @@
 mod dir_loader;
 mod service;
 mod objectmap_loader;
+mod projection;
 
 pub(crate) use service::*;
\ No newline at end of file
@@ -0,0 +1,471 @@
+use cyfs_base::*;
+use cyfs_lib::*;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+// key范围投影时遍历objectmap的步长
+const OBJECT_MAP_KEY_RANGE_STEP: usize = 64;
+
+// 投影的结果，desc/body投影只返回对象的一部分，objectmap范围投影返回一个新的对象
+#[derive(Debug)]
+enum NONProjectedObject {
+    Object(ObjectId, AnyNamedObject),
+    Part(NONObjectPart),
+}
+
+// 在持有对象的协议栈上计算get_object的对象投影
+pub(crate) struct NONObjectProjector {
+    op_env_cache: ObjectMapOpEnvCacheRef,
+}
+
+impl NONObjectProjector {
+    pub fn new(noc: NamedObjectCacheRef) -> Self {
+        let noc_cache = ObjectMapNOCCacheAdapter::new_noc_cache(noc);
+        Self::new_with_cache(noc_cache)
+    }
+
+    fn new_with_cache(noc_cache: ObjectMapNOCCacheRef) -> Self {
+        let root_cache = ObjectMapRootMemoryCache::new_ref(None, noc_cache, 60 * 5, 1024);
+        let op_env_cache = ObjectMapOpEnvMemoryCache::new_ref(root_cache);
+
+        Self { op_env_cache }
+    }
+
+    pub async fn project(
+        &self,
+        mut resp: NONGetObjectInputResponse,
+        projection: &NONObjectProjection,
+    ) -> BuckyResult<NONGetObjectInputResponse> {
+        let ret = self.project_object(&resp.object, projection).await?;
+        match ret {
+            NONProjectedObject::Object(object_id, object) => {
+                info!(
+                    "get object with projection: object={}, projection={}, got={}",
+                    resp.object.object_id,
+                    projection.to_string(),
+                    object_id
+                );
+
+                let object_raw = object.to_vec()?;
+                resp.object = NONObjectInfo::new(object_id, object_raw, Some(Arc::new(object)));
+            }
+            NONProjectedObject::Part(part) => {
+                info!(
+                    "get object with projection: object={}, projection={}, got part: {}",
+                    resp.object.object_id,
+                    projection.to_string(),
+                    part
+                );
+
+                // 部分对象不能使用原对象的object_id，object置空
+                resp.object = NONObjectInfo::new(ObjectId::default(), vec![], None);
+                resp.part = Some(part);
+            }
+        }
+
+        Ok(resp)
+    }
+
+    async fn project_object(
+        &self,
+        info: &NONObjectInfo,
+        projection: &NONObjectProjection,
+    ) -> BuckyResult<NONProjectedObject> {
+        let object = info.object_if_none_then_decode()?;
+
+        let ret = match projection {
+            NONObjectProjection::Desc => {
+                NONProjectedObject::Part(NONObjectPart::new_desc(&info.object_id, &object)?)
+            }
+            NONObjectProjection::Body => {
+                NONProjectedObject::Part(NONObjectPart::new_body(&info.object_id, &object)?)
+            }
+            NONObjectProjection::ObjectMapRange { start, count } => {
+                let object_map = Self::object_map_of(info, &object)?;
+                let slice = self.range(object_map, *start, *count).await?;
+                let object_id = slice.flush_id();
+
+                NONProjectedObject::Object(
+                    object_id,
+                    AnyNamedObject::Standard(StandardObject::ObjectMap(slice)),
+                )
+            }
+            NONObjectProjection::ObjectMapKeyRange { begin, end } => {
+                let object_map = Self::object_map_of(info, &object)?;
+                let slice = self.key_range(object_map, begin, end).await?;
+                let object_id = slice.flush_id();
+
+                NONProjectedObject::Object(
+                    object_id,
+                    AnyNamedObject::Standard(StandardObject::ObjectMap(slice)),
+                )
+            }
+        };
+
+        Ok(ret)
+    }
+
+    fn object_map_of<'a>(
+        info: &NONObjectInfo,
+        object: &'a AnyNamedObject,
+    ) -> BuckyResult<&'a ObjectMap> {
+        match object {
+            AnyNamedObject::Standard(StandardObject::ObjectMap(object_map)) => Ok(object_map),
+            _ => {
+                let msg = format!(
+                    "objectmap range projection but target is not objectmap! object={}",
+                    info.object_id
+                );
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
+            }
+        }
+    }
+
+    fn new_slice(object_map: &ObjectMap) -> ObjectMap {
+        ObjectMap::new(
+            object_map.content_type(),
+            object_map.desc().owner().to_owned(),
+            object_map.desc().dec_id().to_owned(),
+        )
+        .build()
+    }
+
+    async fn range(&self, object_map: &ObjectMap, start: u32, count: u32) -> BuckyResult<ObjectMap> {
+        if count == 0 || count > NON_OBJECT_PROJECTION_RANGE_MAX_COUNT {
+            let msg = format!(
+                "invalid objectmap range projection count: {}, max={}",
+                count, NON_OBJECT_PROJECTION_RANGE_MAX_COUNT
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        let content_type = object_map.content_type();
+        match content_type {
+            ObjectMapSimpleContentType::Map | ObjectMapSimpleContentType::Set => {}
+            _ => {
+                let msg = format!(
+                    "objectmap range projection not support diff objectmap! object={}",
+                    object_map.flush_id()
+                );
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+            }
+        }
+
+        // 先跳过start个元素，再读取count个
+        let mut it = ObjectMapIterator::new(true, object_map, self.op_env_cache.clone());
+        if start > 0 {
+            it.skip(object_map, start as usize).await?;
+        }
+
+        let mut it = it.into_iterator();
+        let list = it.next(object_map, count as usize).await?;
+
+        let mut slice = Self::new_slice(object_map);
+
+        for item in list.list {
+            match item {
+                ObjectMapContentItem::Map((key, value)) => {
+                    slice.insert_with_key(&self.op_env_cache, &key, &value).await?;
+                }
+                ObjectMapContentItem::Set(value) => {
+                    slice.insert(&self.op_env_cache, &value).await?;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(slice)
+    }
+    // objectmap的hub模式下元素不按key排序，所以需要遍历全部元素，只保留范围内最小的一批key
+    async fn key_range(&self, object_map: &ObjectMap, begin: &str, end: &str) -> BuckyResult<ObjectMap> {
+        if object_map.content_type() != ObjectMapSimpleContentType::Map {
+            let msg = format!(
+                "objectmap key range projection only support map! object={}, type={:?}",
+                object_map.flush_id(),
+                object_map.content_type(),
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+        }
+
+        let max_count = NON_OBJECT_PROJECTION_RANGE_MAX_COUNT as usize;
+        let mut items = BTreeMap::new();
+
+        let mut it = ObjectMapIterator::new(false, object_map, self.op_env_cache.clone());
+        while !it.is_end() {
+            let list = it.next(object_map, OBJECT_MAP_KEY_RANGE_STEP).await?;
+            for item in list.list {
+                let (key, value) = item.into_map_item();
+                if key.as_str() < begin || (!end.is_empty() && key.as_str() >= end) {
+                    continue;
+                }
+
+                items.insert(key, value);
+                if items.len() > max_count {
+                    let last = items.keys().next_back().unwrap().to_owned();
+                    items.remove(&last);
+                }
+            }
+        }
+
+        let mut slice = Self::new_slice(object_map);
+        for (key, value) in items {
+            slice.insert_with_key(&self.op_env_cache, &key, &value).await?;
+        }
+
+        Ok(slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl NONProjectedObject {
+        fn into_object(self) -> (ObjectId, AnyNamedObject) {
+            match self {
+                Self::Object(object_id, object) => (object_id, object),
+                Self::Part(_) => unreachable!(),
+            }
+        }
+
+        fn into_part(self) -> NONObjectPart {
+            match self {
+                Self::Part(part) => part,
+                Self::Object(..) => unreachable!(),
+            }
+        }
+    }
+
+    fn new_projector() -> NONObjectProjector {
+        NONObjectProjector::new_with_cache(ObjectMapMemoryNOCCache::new())
+    }
+
+    fn new_value(key: &str) -> ObjectId {
+        ChunkId::calculate_sync(key.as_bytes()).unwrap().object_id()
+    }
+
+    fn new_info(object: AnyNamedObject) -> NONObjectInfo {
+        let object_id = object.calculate_id();
+        let object_raw = object.to_vec().unwrap();
+        NONObjectInfo::new(object_id, object_raw, Some(Arc::new(object)))
+    }
+
+    async fn new_object_map(projector: &NONObjectProjector, count: usize) -> NONObjectInfo {
+        let mut object_map = ObjectMap::new(ObjectMapSimpleContentType::Map, None, None)
+            .no_create_time()
+            .build();
+        for i in 0..count {
+            let key = format!("key_{:0>3}", i);
+            object_map
+                .insert_with_key(&projector.op_env_cache, &key, &new_value(&key))
+                .await
+                .unwrap();
+        }
+
+        new_info(AnyNamedObject::Standard(StandardObject::ObjectMap(object_map)))
+    }
+
+    async fn list_items(
+        projector: &NONObjectProjector,
+        object: &AnyNamedObject,
+    ) -> BTreeMap<String, ObjectId> {
+        let object_map = match object {
+            AnyNamedObject::Standard(StandardObject::ObjectMap(object_map)) => object_map,
+            _ => unreachable!(),
+        };
+
+        let mut items = BTreeMap::new();
+        let mut it = ObjectMapIterator::new(false, object_map, projector.op_env_cache.clone());
+        while !it.is_end() {
+            let list = it.next(object_map, 16).await.unwrap();
+            for item in list.list {
+                let (key, value) = item.into_map_item();
+                assert!(items.insert(key, value).is_none());
+            }
+        }
+
+        items
+    }
+
+    fn keys(begin: usize, end: usize) -> Vec<String> {
+        (begin..end).map(|i| format!("key_{:0>3}", i)).collect()
+    }
+
+    #[async_std::test]
+    async fn test_desc_body() {
+        let projector = new_projector();
+
+        let secret = PrivateKey::generate_rsa(1024).unwrap();
+        let mut people = People::new(None, vec![], secret.public(), None, None, None).build();
+        let signer = RsaCPUObjectSigner::new(secret.public(), secret.clone());
+        sign_and_set_named_object(&signer, &mut people, &SignatureSource::RefIndex(0))
+            .await
+            .unwrap();
+        let info = new_info(AnyNamedObject::Standard(StandardObject::People(people)));
+
+        let object = info.object.as_ref().unwrap();
+
+        // desc/body投影只返回对象的一部分，不是一个完整的对象
+        let part = projector
+            .project_object(&info, &NONObjectProjection::Desc)
+            .await
+            .unwrap()
+            .into_part();
+        assert_eq!(part.source_object_id, info.object_id);
+        assert_eq!(part.obj_type, object.obj_type());
+        assert!(part.body.is_none());
+        assert!(part.body_signs.is_empty());
+        assert!(!part.desc_signs.is_empty());
+        let desc = PeopleDesc::clone_from_slice(part.desc.as_ref().unwrap()).unwrap();
+        assert_eq!(desc.calculate_id(), info.object_id);
+
+        let part = projector
+            .project_object(&info, &NONObjectProjection::Body)
+            .await
+            .unwrap()
+            .into_part();
+        assert_eq!(part.source_object_id, info.object_id);
+        assert!(part.desc.is_none());
+        assert!(part.desc_signs.is_empty());
+        assert!(!part.body_signs.is_empty());
+        assert_eq!(part.body, object.body_raw().unwrap());
+
+        let buf = part.to_vec().unwrap();
+        let got = NONObjectPart::clone_from_slice(&buf).unwrap();
+        assert_eq!(got.body, part.body);
+        assert_eq!(got.source_object_id, part.source_object_id);
+
+        let mut resp = NONGetObjectInputResponse::new_with_object(info.clone());
+        resp = projector.project(resp, &NONObjectProjection::Body).await.unwrap();
+        assert!(resp.object.is_empty());
+        assert_ne!(resp.object.object_id, info.object_id);
+        assert!(resp.part.is_some());
+
+        // 没有body的对象不支持body投影
+        let mut object = info.object.as_ref().unwrap().as_ref().clone();
+        object.clear_body().unwrap();
+        let e = projector
+            .project_object(&new_info(object), &NONObjectProjection::Body)
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), BuckyErrorCode::NotFound);
+
+        let e = projector
+            .project_object(&info, &NONObjectProjection::ObjectMapRange { start: 0, count: 1 })
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), BuckyErrorCode::NotSupport);
+    }
+
+    #[async_std::test]
+    async fn test_range() {
+        let projector = new_projector();
+        let info = new_object_map(&projector, 300).await;
+
+        let (object_id, object) = projector
+            .project_object(&info, &NONObjectProjection::ObjectMapRange { start: 10, count: 5 })
+            .await
+            .unwrap()
+            .into_object();
+        assert_ne!(object_id, info.object_id);
+        assert_eq!(object.calculate_id(), object_id);
+
+        let items = list_items(&projector, &object).await;
+        assert_eq!(items.len(), 5);
+        for (key, value) in items {
+            assert_eq!(value, new_value(&key));
+        }
+
+        // 越过末尾时只返回剩余的元素
+        let (_, object) = projector
+            .project_object(&info, &NONObjectProjection::ObjectMapRange { start: 295, count: 10 })
+            .await
+            .unwrap()
+            .into_object();
+        assert_eq!(list_items(&projector, &object).await.len(), 5);
+
+        for count in [0, NON_OBJECT_PROJECTION_RANGE_MAX_COUNT + 1] {
+            let e = projector
+                .project_object(&info, &NONObjectProjection::ObjectMapRange { start: 0, count })
+                .await
+                .unwrap_err();
+            assert_eq!(e.code(), BuckyErrorCode::InvalidParam);
+        }
+    }
+
+    #[async_std::test]
+    async fn test_key_range() {
+        let projector = new_projector();
+        let info = new_object_map(&projector, 300).await;
+
+        let projection = NONObjectProjection::ObjectMapKeyRange {
+            begin: "key_010".to_owned(),
+            end: "key_020".to_owned(),
+        };
+        let (object_id, object) = projector
+            .project_object(&info, &projection)
+            .await
+            .unwrap()
+            .into_object();
+        assert_eq!(object.calculate_id(), object_id);
+
+        let items = list_items(&projector, &object).await;
+        assert_eq!(items.keys().cloned().collect::<Vec<_>>(), keys(10, 20));
+        for (key, value) in items {
+            assert_eq!(value, new_value(&key));
+        }
+
+        // 没有上界时只返回最小的一批key
+        let projection = NONObjectProjection::ObjectMapKeyRange {
+            begin: "key_100".to_owned(),
+            end: "".to_owned(),
+        };
+        let (_, object) = projector
+            .project_object(&info, &projection)
+            .await
+            .unwrap()
+            .into_object();
+        let items = list_items(&projector, &object).await;
+        let max = NON_OBJECT_PROJECTION_RANGE_MAX_COUNT as usize;
+        assert_eq!(items.keys().cloned().collect::<Vec<_>>(), keys(100, 100 + max));
+
+        let projection = NONObjectProjection::ObjectMapKeyRange {
+            begin: "key_299".to_owned(),
+            end: "".to_owned(),
+        };
+        let (_, object) = projector
+            .project_object(&info, &projection)
+            .await
+            .unwrap()
+            .into_object();
+        assert_eq!(list_items(&projector, &object).await.len(), 1);
+
+        let projection = NONObjectProjection::ObjectMapKeyRange {
+            begin: "x".to_owned(),
+            end: "y".to_owned(),
+        };
+        let (_, object) = projector
+            .project_object(&info, &projection)
+            .await
+            .unwrap()
+            .into_object();
+        assert!(list_items(&projector, &object).await.is_empty());
+
+        // set类型没有key
+        let mut object_map = ObjectMap::new(ObjectMapSimpleContentType::Set, None, None)
+            .no_create_time()
+            .build();
+        object_map
+            .insert(&projector.op_env_cache, &new_value("test"))
+            .await
+            .unwrap();
+        let set_info = new_info(AnyNamedObject::Standard(StandardObject::ObjectMap(object_map)));
+        let e = projector.project_object(&set_info, &projection).await.unwrap_err();
+        assert_eq!(e.code(), BuckyErrorCode::NotSupport);
+    }
+}
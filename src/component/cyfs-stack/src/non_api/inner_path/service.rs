@@ -1,5 +1,6 @@
 use super::dir_loader::*;
 use super::objectmap_loader::*;
+use super::projection::*;
 use crate::non::*;
 use crate::NamedDataComponents;
 use cyfs_base::*;
@@ -13,6 +14,7 @@ pub(crate) struct NONInnerPathServiceProcessor {
 
     dir_loader: NONDirLoader,
     objectmap_loader: NONObjectMapLoader,
+    projector: NONObjectProjector,
 
     noc: NamedObjectCacheRef,
     relation: NamedObjectRelationCacheRef,
@@ -32,11 +34,13 @@ impl NONInnerPathServiceProcessor {
 
         // TODO objectmap loader should use non instead noc?
         let objectmap_loader = NONObjectMapLoader::new(noc.clone());
+        let projector = NONObjectProjector::new(noc.clone());
 
         let ret = Self {
             next: non_processor,
             dir_loader,
             objectmap_loader,
+            projector,
             noc,
             relation,
         };
@@ -125,18 +129,8 @@ impl NONInnerPathServiceProcessor {
             }
         }
     }
-}
-
-#[async_trait::async_trait]
-impl NONInputProcessor for NONInnerPathServiceProcessor {
-    async fn put_object(
-        &self,
-        req: NONPutObjectInputRequest,
-    ) -> BuckyResult<NONPutObjectInputResponse> {
-        self.next.put_object(req).await
-    }
 
-    async fn get_object(
+    async fn get_object_without_projection(
         &self,
         req: NONGetObjectInputRequest,
     ) -> BuckyResult<NONGetObjectInputResponse> {
@@ -204,6 +198,30 @@ impl NONInputProcessor for NONInnerPathServiceProcessor {
 
         self.next.get_object(req).await
     }
+}
+
+#[async_trait::async_trait]
+impl NONInputProcessor for NONInnerPathServiceProcessor {
+    async fn put_object(
+        &self,
+        req: NONPutObjectInputRequest,
+    ) -> BuckyResult<NONPutObjectInputResponse> {
+        self.next.put_object(req).await
+    }
+
+    async fn get_object(
+        &self,
+        mut req: NONGetObjectInputRequest,
+    ) -> BuckyResult<NONGetObjectInputResponse> {
+        // 投影作用在最终找到的对象上
+        match req.projection.take() {
+            Some(projection) => {
+                let resp = self.get_object_without_projection(req).await?;
+                self.projector.project(resp, &projection).await
+            }
+            None => self.get_object_without_projection(req).await,
+        }
+    }
 
     async fn post_object(
         &self,
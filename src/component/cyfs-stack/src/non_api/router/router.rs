@@ -560,8 +560,8 @@ impl NONRouter {
                 e
             });
 
-        // Try to cache relation
-        if req.is_with_inner_path_relation() {
+        // Try to cache relation, the projection result is not the inner_path target object
+        if req.is_with_inner_path_relation() && req.projection.is_none() {
             let cache_key = NamedObjectRelationCacheKey {
                 object_id: req.object_id.clone(),
                 relation_type: NamedObjectRelationType::InnerPath,
@@ -594,7 +594,8 @@ impl NONRouter {
         }
 
         // After the get_object are successful, the local needs to cache locally
-        if ret.is_ok() && save_to_noc {
+        // 投影的结果只是对象的一部分，不能缓存到noc
+        if ret.is_ok() && save_to_noc && req.projection.is_none() {
             let put_req = NONPutObjectInputRequest {
                 common: req.common.clone(),
                 object: ret.as_ref().unwrap().object.clone(),
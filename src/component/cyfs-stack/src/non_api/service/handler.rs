@@ -169,16 +169,25 @@ impl NONRequestHandler {
 
         Self::encode_get_object_response_times(&mut http_resp, &resp);
 
-        match format {
-            FrontRequestObjectFormat::Raw | FrontRequestObjectFormat::Default => {
-                NONRequestorHelper::encode_object_info(&mut http_resp, resp.object);
+        if let Some(part) = &resp.part {
+            // 投影结果只是对象的一部分，不能按对象编码
+            if let Err(e) = NONRequestorHelper::encode_object_part(&mut http_resp, part) {
+                return RequestorHelper::trans_error(e);
             }
-            FrontRequestObjectFormat::Json => {
-                http_resp
-                    .insert_header(cyfs_base::CYFS_OBJECT_ID, resp.object.object_id.to_string());
-
-                http_resp.set_body(resp.object.format_json().to_string());
-                http_resp.set_content_type(::tide::http::mime::JSON);
+        } else {
+            match format {
+                FrontRequestObjectFormat::Raw | FrontRequestObjectFormat::Default => {
+                    NONRequestorHelper::encode_object_info(&mut http_resp, resp.object);
+                }
+                FrontRequestObjectFormat::Json => {
+                    http_resp.insert_header(
+                        cyfs_base::CYFS_OBJECT_ID,
+                        resp.object.object_id.to_string(),
+                    );
+
+                    http_resp.set_body(resp.object.format_json().to_string());
+                    http_resp.set_content_type(::tide::http::mime::JSON);
+                }
             }
         }
 
@@ -239,10 +248,14 @@ impl NONRequestHandler {
         let inner_path =
             RequestorHelper::decode_optional_header_with_utf8_decoding(&req.request, cyfs_base::CYFS_INNER_PATH)?;
 
+        let projection =
+            RequestorHelper::decode_optional_header(&req.request, cyfs_base::CYFS_PROJECTION)?;
+
         let get_req = NONGetObjectInputRequest {
             common,
             object_id,
             inner_path,
+            projection,
         };
 
         info!("recv get_object request: {}", get_req);
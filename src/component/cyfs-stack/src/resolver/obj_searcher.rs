@@ -248,6 +248,7 @@ impl ZoneSearcher {
             },
             object_id: object_id.to_owned(),
             inner_path: None,
+            projection: None,
         };
         let resp = non_processor.get_object(req).await?;
 
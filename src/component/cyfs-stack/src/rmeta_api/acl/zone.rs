@@ -1,3 +1,4 @@
+use crate::acl::AclManagerRef;
 use crate::rmeta::*;
 use cyfs_base::*;
 use cyfs_lib::*;
@@ -6,17 +7,36 @@ use std::sync::Arc;
 
 // 限定在同zone内操作
 pub(crate) struct GlobalStateMetaAclInnerInputProcessor {
+    acl: AclManagerRef,
     next: GlobalStateMetaInputProcessorRef,
 }
 
 impl GlobalStateMetaAclInnerInputProcessor {
-    pub(crate) fn new(next: GlobalStateMetaInputProcessorRef) -> GlobalStateMetaInputProcessorRef {
-        let ret = Self { next };
+    pub(crate) fn new(
+        acl: AclManagerRef,
+        next: GlobalStateMetaInputProcessorRef,
+    ) -> GlobalStateMetaInputProcessorRef {
+        let ret = Self { acl, next };
 
         Arc::new(Box::new(ret))
     }
 
+    // rmeta的修改都是写操作，统一记录审计日志
     fn check_access(&self, service: &str, common: &MetaInputRequestCommon) -> BuckyResult<()> {
+        let ret = self.check_access_impl(service, common);
+        self.acl.audit().record_result(
+            &common.source,
+            service,
+            RequestOpType::Write,
+            common.target_dec_id.as_ref(),
+            None,
+            &ret,
+        );
+
+        ret
+    }
+
+    fn check_access_impl(&self, service: &str, common: &MetaInputRequestCommon) -> BuckyResult<()> {
         common.source.check_current_zone(service)?;
 
         if common
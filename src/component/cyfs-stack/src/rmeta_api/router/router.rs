@@ -1,4 +1,5 @@
 use super::super::acl::GlobalStateMetaAclInnerInputProcessor;
+use crate::acl::AclManagerRef;
 use crate::forward::ForwardProcessorManager;
 use crate::meta::ObjectFailHandler;
 use crate::rmeta::*;
@@ -31,12 +32,13 @@ impl Clone for GlobalStateMetaServiceRouter {
 impl GlobalStateMetaServiceRouter {
     pub(crate) fn new(
         category: GlobalStateCategory,
+        acl: AclManagerRef,
         forward: ForwardProcessorManager,
         zone_manager: ZoneManagerRef,
         fail_handler: ObjectFailHandler,
         processor: GlobalStateMetaInputProcessorRef,
     ) -> Self {
-        let processor = GlobalStateMetaAclInnerInputProcessor::new(processor);
+        let processor = GlobalStateMetaAclInnerInputProcessor::new(acl, processor);
         Self {
             category,
             processor,
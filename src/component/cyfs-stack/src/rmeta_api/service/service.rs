@@ -1,6 +1,7 @@
 use super::super::local::*;
 use super::super::router::GlobalStateMetaServiceRouter;
 use super::default::GlobalStateDefaultMetas;
+use crate::acl::AclManagerRef;
 use crate::forward::ForwardProcessorManager;
 use crate::meta::ObjectFailHandler;
 use crate::rmeta::*;
@@ -240,6 +241,7 @@ pub struct GlobalStateMetaService {
 impl GlobalStateMetaService {
     pub(crate) fn new(
         local_service: GlobalStateMetaLocalService,
+        acl: AclManagerRef,
         forward: ForwardProcessorManager,
         zone_manager: ZoneManagerRef,
         fail_handler: ObjectFailHandler,
@@ -247,6 +249,7 @@ impl GlobalStateMetaService {
         // root-state
         let root_state_meta_router = GlobalStateMetaServiceRouter::new(
             GlobalStateCategory::RootState,
+            acl.clone(),
             forward.clone(),
             zone_manager.clone(),
             fail_handler.clone(),
@@ -257,6 +260,7 @@ impl GlobalStateMetaService {
         // local-cache
         let local_cache_meta_router = GlobalStateMetaServiceRouter::new(
             GlobalStateCategory::LocalCache,
+            acl,
             forward,
             zone_manager,
             fail_handler,
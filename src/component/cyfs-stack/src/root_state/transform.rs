@@ -702,6 +702,7 @@ impl GlobalStateAccessorOutputProcessor for GlobalStateAccessorOutputTransformer
         let resp = RootStateAccessorGetObjectByPathOutputResponse {
             object: NONGetObjectOutputResponse {
                 object: in_resp.object.object,
+                part: in_resp.object.part,
                 object_expires_time: in_resp.object.object_expires_time,
                 object_update_time: in_resp.object.object_update_time,
                 attr: in_resp.object.attr,
@@ -768,6 +769,7 @@ impl GlobalStateAccessorInputProcessor for GlobalStateAccessorInputTransformer {
         let resp = RootStateAccessorGetObjectByPathInputResponse {
             object: NONGetObjectInputResponse {
                 object: out_resp.object.object,
+                part: out_resp.object.part,
                 object_expires_time: out_resp.object.object_expires_time,
                 object_update_time: out_resp.object.object_update_time,
                 attr: out_resp.object.attr,
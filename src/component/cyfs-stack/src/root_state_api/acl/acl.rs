@@ -66,8 +66,17 @@ impl GlobalStateAccessorInputProcessor for GlobalStateAccessorAclInputProcessor
     ) -> BuckyResult<RootStateAccessorGetObjectByPathInputResponse> {
         // info!("get_object_by_path acl: {}", req);
 
-        let dec_id = self.check_access(&req.common, &req.inner_path).await?;
-        req.common.source.set_verified(dec_id);
+        let ret = self.check_access(&req.common, &req.inner_path).await;
+        self.acl.audit().record_result(
+            &req.common.source,
+            "global_state.get_object_by_path",
+            RequestOpType::Read,
+            None,
+            Some(req.inner_path.as_str()),
+            &ret,
+        );
+
+        req.common.source.set_verified(ret?);
 
         self.next.get_object_by_path(req).await
     }
@@ -78,8 +87,17 @@ impl GlobalStateAccessorInputProcessor for GlobalStateAccessorAclInputProcessor
     ) -> BuckyResult<RootStateAccessorListInputResponse> {
         // info!("list acl: {}", req);
 
-        let dec_id = self.check_access(&req.common, &req.inner_path).await?;
-        req.common.source.set_verified(dec_id);
+        let ret = self.check_access(&req.common, &req.inner_path).await;
+        self.acl.audit().record_result(
+            &req.common.source,
+            "global_state.list",
+            RequestOpType::Read,
+            None,
+            Some(req.inner_path.as_str()),
+            &ret,
+        );
+
+        req.common.source.set_verified(ret?);
 
         self.next.list(req).await
     }
@@ -37,6 +37,58 @@ impl GlobalStateInputProcessor for GlobalStateAclZoneInputProcessor {
         &self,
         req: RootStateGetCurrentRootInputRequest,
     ) -> BuckyResult<RootStateGetCurrentRootInputResponse> {
+        let ret = self.check_get_current_root(&req).await;
+        self.acl.audit().record_result(
+            &req.common.source,
+            "global_state.get_root",
+            RequestOpType::Read,
+            None,
+            None,
+            &ret,
+        );
+        ret?;
+
+        self.next.get_current_root(req).await
+    }
+
+    async fn create_op_env(
+        &self,
+        req: RootStateCreateOpEnvInputRequest,
+    ) -> BuckyResult<RootStateCreateOpEnvInputResponse> {
+        let ret = self.check_create_op_env(&req).await;
+        // 带写权限的op_env按写操作记录
+        let (op_type, req_path) = match &req.access {
+            Some(access) => {
+                let op_type = if access.access as u8 & AccessPermissions::WriteOnly as u8 != 0 {
+                    RequestOpType::Write
+                } else if access.access as u8 & AccessPermissions::ReadOnly as u8 != 0 {
+                    RequestOpType::Read
+                } else {
+                    RequestOpType::Call
+                };
+                (op_type, Some(access.path.as_str()))
+            }
+            None => (RequestOpType::Write, None),
+        };
+        self.acl.audit().record_result(
+            &req.common.source,
+            "global_state.create_op_env",
+            op_type,
+            None,
+            req_path,
+            &ret,
+        );
+        ret?;
+
+        self.next.create_op_env(req).await
+    }
+}
+
+impl GlobalStateAclZoneInputProcessor {
+    async fn check_get_current_root(
+        &self,
+        req: &RootStateGetCurrentRootInputRequest,
+    ) -> BuckyResult<()> {
         req.common
             .source
             .check_current_zone("global_state.get_root")?;
@@ -73,13 +125,10 @@ impl GlobalStateInputProcessor for GlobalStateAclZoneInputProcessor {
                 .await?;
         }
 
-        self.next.get_current_root(req).await
+        Ok(())
     }
 
-    async fn create_op_env(
-        &self,
-        req: RootStateCreateOpEnvInputRequest,
-    ) -> BuckyResult<RootStateCreateOpEnvInputResponse> {
+    async fn check_create_op_env(&self, req: &RootStateCreateOpEnvInputRequest) -> BuckyResult<()> {
         req.common
             .source
             .check_current_zone("global_state.create_op_env")?;
@@ -116,7 +165,7 @@ impl GlobalStateInputProcessor for GlobalStateAclZoneInputProcessor {
                 .await?;
         }
 
-        self.next.create_op_env(req).await
+        Ok(())
     }
 }
 
@@ -132,8 +181,18 @@ impl OpEnvAclInnerInputProcessor {
         Arc::new(Box::new(ret))
     }
 
-    fn check_access(&self, service: &str, common: &OpEnvInputRequestCommon) -> BuckyResult<()> {
-        common.source.check_current_zone(service)?;
+    fn check_access(
+        &self,
+        service: &str,
+        common: &OpEnvInputRequestCommon,
+        op_type: RequestOpType,
+    ) -> BuckyResult<()> {
+        if let Err(e) = common.source.check_current_zone(service) {
+            self.acl
+                .audit()
+                .record_deny(&common.source, service, op_type, None, None, &e);
+            return Err(e);
+        }
 
         Ok(())
     }
@@ -147,13 +206,13 @@ impl OpEnvInputProcessor for OpEnvAclInnerInputProcessor {
 
     // single_op_env methods
     async fn load(&self, req: OpEnvLoadInputRequest) -> BuckyResult<()> {
-        self.check_access("op_env.load", &req.common)?;
+        self.check_access("op_env.load", &req.common, RequestOpType::Read)?;
 
         self.next.load(req).await
     }
 
     async fn load_by_path(&self, req: OpEnvLoadByPathInputRequest) -> BuckyResult<()> {
-        self.check_access("op_env.load_by_path", &req.common)?;
+        self.check_access("op_env.load_by_path", &req.common, RequestOpType::Read)?;
 
         // 如果是跨dec加载path，那么需要额外的rmeta校验权限
         if !req
@@ -180,14 +239,14 @@ impl OpEnvInputProcessor for OpEnvAclInnerInputProcessor {
     }
 
     async fn create_new(&self, req: OpEnvCreateNewInputRequest) -> BuckyResult<()> {
-        self.check_access("op_env.create_new", &req.common)?;
+        self.check_access("op_env.create_new", &req.common, RequestOpType::Write)?;
 
         self.next.create_new(req).await
     }
 
     // lock
     async fn lock(&self, req: OpEnvLockInputRequest) -> BuckyResult<()> {
-        self.check_access("op_env.lock", &req.common)?;
+        self.check_access("op_env.lock", &req.common, RequestOpType::Write)?;
 
         self.next.lock(req).await
     }
@@ -197,20 +256,32 @@ impl OpEnvInputProcessor for OpEnvAclInnerInputProcessor {
         &self,
         req: OpEnvGetCurrentRootInputRequest,
     ) -> BuckyResult<OpEnvGetCurrentRootInputResponse> {
-        self.check_access("op_env.get_current_root", &req.common)?;
+        self.check_access("op_env.get_current_root", &req.common, RequestOpType::Read)?;
 
         self.next.get_current_root(req).await
     }
 
     // transcation
     async fn commit(&self, req: OpEnvCommitInputRequest) -> BuckyResult<OpEnvCommitInputResponse> {
-        self.check_access("op_env.commit", &req.common)?;
+        self.check_access("op_env.commit", &req.common, RequestOpType::Write)?;
+
+        let source = req.common.source.clone();
+        let ret = self.next.commit(req).await;
+        if let Ok(resp) = &ret {
+            self.acl.audit().record_allow(
+                &source,
+                "op_env.commit",
+                RequestOpType::Write,
+                Some(&resp.dec_root),
+                None,
+            );
+        }
 
-        self.next.commit(req).await
+        ret
     }
 
     async fn abort(&self, req: OpEnvAbortInputRequest) -> BuckyResult<()> {
-        self.check_access("op_env.abort", &req.common)?;
+        self.check_access("op_env.abort", &req.common, RequestOpType::Write)?;
 
         self.next.abort(req).await
     }
@@ -220,13 +291,13 @@ impl OpEnvInputProcessor for OpEnvAclInnerInputProcessor {
         &self,
         req: OpEnvGetByKeyInputRequest,
     ) -> BuckyResult<OpEnvGetByKeyInputResponse> {
-        self.check_access("op_env.get_by_key", &req.common)?;
+        self.check_access("op_env.get_by_key", &req.common, RequestOpType::Read)?;
 
         self.next.get_by_key(req).await
     }
 
     async fn insert_with_key(&self, req: OpEnvInsertWithKeyInputRequest) -> BuckyResult<()> {
-        self.check_access("op_env.insert_with_key", &req.common)?;
+        self.check_access("op_env.insert_with_key", &req.common, RequestOpType::Write)?;
 
         self.next.insert_with_key(req).await
     }
@@ -235,7 +306,7 @@ impl OpEnvInputProcessor for OpEnvAclInnerInputProcessor {
         &self,
         req: OpEnvSetWithKeyInputRequest,
     ) -> BuckyResult<OpEnvSetWithKeyInputResponse> {
-        self.check_access("op_env.set_with_key", &req.common)?;
+        self.check_access("op_env.set_with_key", &req.common, RequestOpType::Write)?;
 
         self.next.set_with_key(req).await
     }
@@ -244,7 +315,7 @@ impl OpEnvInputProcessor for OpEnvAclInnerInputProcessor {
         &self,
         req: OpEnvRemoveWithKeyInputRequest,
     ) -> BuckyResult<OpEnvRemoveWithKeyInputResponse> {
-        self.check_access("op_env.remove_with_key", &req.common)?;
+        self.check_access("op_env.remove_with_key", &req.common, RequestOpType::Write)?;
 
         self.next.remove_with_key(req).await
     }
@@ -254,38 +325,38 @@ impl OpEnvInputProcessor for OpEnvAclInnerInputProcessor {
         &self,
         req: OpEnvContainsInputRequest,
     ) -> BuckyResult<OpEnvContainsInputResponse> {
-        self.check_access("op_env.contains", &req.common)?;
+        self.check_access("op_env.contains", &req.common, RequestOpType::Read)?;
 
         self.next.contains(req).await
     }
 
     async fn insert(&self, req: OpEnvInsertInputRequest) -> BuckyResult<OpEnvInsertInputResponse> {
-        self.check_access("op_env.insert", &req.common)?;
+        self.check_access("op_env.insert", &req.common, RequestOpType::Write)?;
 
         self.next.insert(req).await
     }
 
     async fn remove(&self, req: OpEnvRemoveInputRequest) -> BuckyResult<OpEnvRemoveInputResponse> {
-        self.check_access("op_env.remove", &req.common)?;
+        self.check_access("op_env.remove", &req.common, RequestOpType::Write)?;
 
         self.next.remove(req).await
     }
 
     // iterator methods
     async fn next(&self, req: OpEnvNextInputRequest) -> BuckyResult<OpEnvNextInputResponse> {
-        self.check_access("op_env.next", &req.common)?;
+        self.check_access("op_env.next", &req.common, RequestOpType::Read)?;
 
         self.next.next(req).await
     }
 
     async fn reset(&self, req: OpEnvResetInputRequest) -> BuckyResult<()> {
-        self.check_access("op_env.reset", &req.common)?;
+        self.check_access("op_env.reset", &req.common, RequestOpType::Read)?;
 
         self.next.reset(req).await
     }
 
     async fn list(&self, req: OpEnvListInputRequest) -> BuckyResult<OpEnvListInputResponse> {
-        self.check_access("op_env.list", &req.common)?;
+        self.check_access("op_env.list", &req.common, RequestOpType::Read)?;
 
         self.next.list(req).await
     }
@@ -295,7 +366,7 @@ impl OpEnvInputProcessor for OpEnvAclInnerInputProcessor {
         &self,
         req: OpEnvMetadataInputRequest,
     ) -> BuckyResult<OpEnvMetadataInputResponse> {
-        self.check_access("op_env.metadata", &req.common)?;
+        self.check_access("op_env.metadata", &req.common, RequestOpType::Read)?;
 
         self.next.metadata(req).await
     }
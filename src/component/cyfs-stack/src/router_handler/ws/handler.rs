@@ -43,7 +43,15 @@ impl RouterHandlerWebSocketHandler {
                     let req = RouterWSAddHandlerParam::decode_string(&content)?;
 
                     if let Some(auth) = auth {
-                        auth.check_option_dec(req.dec_id.as_ref(), &source)?;
+                        let category = format!("{}/{}", req.chain, req.category);
+                        let path = DecAuthToken::ws_path("add_handler", &category, &req.id);
+                        auth.check_option_dec(
+                            req.dec_id.as_ref(),
+                            CYFS_DEC_AUTH_WS_METHOD,
+                            &path,
+                            req.dec_auth.as_deref(),
+                            &source,
+                        )?;
                     }
 
                     let mut source = self.zone_manager.get_current_source_info(&req.dec_id).await?;
@@ -58,7 +66,15 @@ impl RouterHandlerWebSocketHandler {
                     let req = RouterWSRemoveHandlerParam::decode_string(&content)?;
 
                     if let Some(auth) = auth {
-                        auth.check_option_dec(req.dec_id.as_ref(), &source)?;
+                        let category = format!("{}/{}", req.chain, req.category);
+                        let path = DecAuthToken::ws_path("remove_handler", &category, &req.id);
+                        auth.check_option_dec(
+                            req.dec_id.as_ref(),
+                            CYFS_DEC_AUTH_WS_METHOD,
+                            &path,
+                            req.dec_auth.as_deref(),
+                            &source,
+                        )?;
                     }
 
                     self.on_remove_handler_request(req).map(|v| Some(v))
@@ -3,7 +3,7 @@ use super::params::*;
 use super::uni_stack::*;
 use crate::acl::{AclManager, AclManagerRef};
 use crate::admin::AdminManager;
-use crate::app::{AppController, AppService};
+use crate::app::{AppController, AppService, AuthenticatedAppList};
 use crate::config::*;
 use crate::crypto::CryptoOutputTransformer;
 use crate::crypto_api::{CryptoService, ObjectCrypto, ObjectVerifier};
@@ -14,7 +14,7 @@ use crate::interface::{
     ObjectListenerManager, ObjectListenerManagerParams, ObjectListenerManagerRef,
 };
 use crate::meta::*;
-use crate::name::NameResolver;
+use crate::name::*;
 use crate::ndn::NDNOutputTransformer;
 use crate::ndn_api::{BdtNDNEventHandler, NDNService};
 use crate::non::NONOutputTransformer;
@@ -122,16 +122,15 @@ impl CyfsStackImpl {
             None => "",
         };
 
+        // 存储加密需要在noc和chunk缓存初始化之前加载
+        cyfs_util::StorageCryptoManager::load_and_install(isolate, &bdt_param.secret)?;
+
         let noc = Self::init_raw_noc(isolate, known_objects).await?;
         let noc_relation = NamedObjectRelationCacheManager::create(isolate)
         .await?;
 
         // meta with cache
-        let raw_meta_cache = RawMetaCache::new(param.meta.target, noc.clone());
-
-        // 名字解析服务
-        let name_resolver = NameResolver::new(raw_meta_cache.clone(), noc.clone());
-        name_resolver.start().await?;
+        let raw_meta_cache = RawMetaCache::new(param.meta.target, noc.clone(), isolate).await;
 
         // init global state manager
         let global_state_manager = GlobalStateManager::new(noc.clone(), config.clone());
@@ -153,6 +152,15 @@ impl CyfsStackImpl {
 
         let current_root = local_root_state.state().get_current_root();
 
+        // 名字解析服务，本地注册表依赖当前zone的global_state
+        let name_resolver = Self::init_name_resolver(
+            &param.name,
+            raw_meta_cache.clone(),
+            &local_root_state,
+            noc.clone(),
+        )
+        .await?;
+
         let task_manager = Self::init_task_manager(isolate).await?;
         let trans_store = create_trans_store(isolate).await?;
         // let chunk_manager = Arc::new(ChunkManager::new());
@@ -227,6 +235,7 @@ impl CyfsStackImpl {
         zone_manager.init().await?;
 
         fail_handler.bind_zone_manager(zone_manager.clone());
+        name_resolver.bind_zone_manager(&zone_manager);
 
         // first init current zone info
         let zm = zone_manager.clone();
@@ -330,9 +339,11 @@ impl CyfsStackImpl {
             bdt_stack.clone(),
             forward_manager.clone(),
             zone_manager.clone(),
+            acl_manager.clone(),
             fail_handler.clone(),
             ood_resoler.clone(),
             task_manager.clone(),
+            raw_meta_cache.clone(),
             config.clone(),
         );
 
@@ -383,6 +394,7 @@ impl CyfsStackImpl {
         // load global state meta service
         let global_state_meta = Self::load_global_state_meta_service(
             local_global_state_meta,
+            acl_manager.clone(),
             forward_manager.clone(),
             zone_manager.clone(),
             fail_handler.clone(),
@@ -395,6 +407,7 @@ impl CyfsStackImpl {
             let front_service = FrontService::new(
                 non_service.clone_processor(),
                 ndn_service.clone_processor(),
+                crypto_service.clone_processor(),
                 root_state.clone_accessor_processor(),
                 local_cache.clone_accessor_processor(),
                 app_service,
@@ -421,6 +434,7 @@ impl CyfsStackImpl {
             zone_role_manager.clone(),
             services.crypto_service.local_service().verifier().clone(),
             config.clone(),
+            acl_manager.clone(),
         );
 
         let mut stack = Self {
@@ -535,6 +549,12 @@ impl CyfsStackImpl {
             init_params.ws_listener = param.interface.ws_listener;
         }
 
+        // 本地接口和docker网关接口共用同一份app鉴权列表
+        let auth_app_list = AuthenticatedAppList::new(
+            param.config.isolate.clone(),
+            param.config.app_legacy_ip_auth,
+        );
+
         interface.init(
             init_params,
             &stack.config,
@@ -547,6 +567,7 @@ impl CyfsStackImpl {
             &stack.root_state,
             &stack.local_cache,
             &stack.global_state_meta,
+            &auth_app_list,
         );
 
         let interface = Arc::new(interface);
@@ -555,7 +576,7 @@ impl CyfsStackImpl {
         }
 
         // init app controller
-        let app_controller = AppController::new(param.config.isolate.clone(), interface);
+        let app_controller = AppController::new(auth_app_list, interface);
         app_controller.init(&system_router_handlers).await?;
 
         if let Err(_) = stack.app_controller.set(app_controller) {
@@ -692,12 +713,13 @@ impl CyfsStackImpl {
 
     fn load_global_state_meta_service(
         local_service: GlobalStateMetaLocalService,
+        acl: AclManagerRef,
         forward: ForwardProcessorManager,
         zone_manager: ZoneManagerRef,
         fail_handler: ObjectFailHandler,
     ) -> GlobalStateMetaService {
         let global_state_meta =
-            GlobalStateMetaService::new(local_service, forward, zone_manager, fail_handler);
+            GlobalStateMetaService::new(local_service, acl, forward, zone_manager, fail_handler);
 
         global_state_meta
     }
@@ -738,6 +760,55 @@ impl CyfsStackImpl {
         Ok((bdt_stack, event))
     }
 
+    async fn init_name_resolver(
+        params: &CyfsStackNameParams,
+        meta_cache: MetaCacheRef,
+        local_root_state: &GlobalStateLocalService,
+        noc: NamedObjectCacheRef,
+    ) -> BuckyResult<NameResolver> {
+        let registry =
+            LocalNameRegistry::new(local_root_state.clone_global_state_processor(), noc.clone());
+
+        let lan = if params.lan_discovery != NameResolverPosition::Disable {
+            let lan = LanNameDiscovery::new(
+                params.lan_multicast_addr,
+                registry.clone(),
+                meta_cache.clone(),
+            )?;
+            lan.start()?;
+            Some(lan)
+        } else {
+            None
+        };
+
+        // 按照配置组装解析链：before -> meta -> after
+        let mut before = vec![];
+        let mut after = vec![];
+        match params.local_registry {
+            NameResolverPosition::Before => before.push(registry.clone().into_ref()),
+            NameResolverPosition::After => after.push(registry.clone().into_ref()),
+            NameResolverPosition::Disable => {}
+        }
+        if let Some(lan) = lan {
+            match params.lan_discovery {
+                NameResolverPosition::Before => before.push(lan.into_ref()),
+                NameResolverPosition::After => after.push(lan.into_ref()),
+                NameResolverPosition::Disable => unreachable!(),
+            }
+        }
+
+        let mut backends = before;
+        if params.meta_chain {
+            backends.push(MetaChainNameBackend::new(meta_cache).into_ref());
+        }
+        backends.append(&mut after);
+
+        let name_resolver = NameResolver::new(backends, noc);
+        name_resolver.start().await?;
+
+        Ok(name_resolver)
+    }
+
     async fn init_raw_noc(
         isolate: &str,
         known_objects: CyfsStackKnownObjects,
@@ -942,6 +1013,9 @@ impl CyfsStackImpl {
         if !INIT_DONE.swap(true, Ordering::SeqCst) {
             cyfs_core::register_core_objects_format();
             cyfs_lib::register_core_objects_format();
+            cyfs_meta_lib::register_meta_objects_format();
+            cyfs_perf_client::register_perf_objects_format();
+            cyfs_core::register_core_objects_builder();
         }
     }
 
@@ -1,3 +1,4 @@
+use cyfs_base::*;
 use cyfs_lib::*;
 use cyfs_meta_lib::MetaMinerTarget;
 
@@ -16,6 +17,9 @@ pub struct CyfsStackConfigParams {
 
     // Whether to enable perf_service
     pub perf_service: bool,
+
+    // 鉴权接口上是否允许没有分配密钥的app继续使用ip匹配的方式，默认关闭
+    pub app_legacy_ip_auth: bool,
 }
 
 impl Default for CyfsStackConfigParams {
@@ -25,6 +29,7 @@ impl Default for CyfsStackConfigParams {
             sync_service: true,
             shared_stack: true,
             perf_service: true,
+            app_legacy_ip_auth: false,
         }
     }
 }
@@ -60,6 +65,68 @@ impl Default for CyfsStackMetaParams {
     }
 }
 
+// 名字解析后端在解析链里相对于meta链的位置
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NameResolverPosition {
+    Disable,
+    Before,
+    After,
+}
+
+impl NameResolverPosition {
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Self::Disable => "disable",
+            Self::Before => "before",
+            Self::After => "after",
+        }
+    }
+}
+
+impl std::str::FromStr for NameResolverPosition {
+    type Err = BuckyError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ret = match s {
+            "disable" => Self::Disable,
+            "before" => Self::Before,
+            "after" => Self::After,
+            _ => {
+                let msg = format!("unknown name resolver position: {}", s);
+                warn!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
+            }
+        };
+
+        Ok(ret)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CyfsStackNameParams {
+    // zone内的本地名字注册表(system dec的root_state)，默认在meta链之前查询
+    pub local_registry: NameResolverPosition,
+
+    // 局域网组播发现，默认关闭
+    pub lan_discovery: NameResolverPosition,
+
+    // 局域网组播发现使用的组播地址
+    pub lan_multicast_addr: SocketAddr,
+
+    // 是否从meta链解析，离线环境下可以关闭
+    pub meta_chain: bool,
+}
+
+impl Default for CyfsStackNameParams {
+    fn default() -> Self {
+        Self {
+            local_registry: NameResolverPosition::Before,
+            lan_discovery: NameResolverPosition::Disable,
+            lan_multicast_addr: "239.255.6.18:1318".parse().unwrap(),
+            meta_chain: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CyfsStackNOCParams {}
 
@@ -128,6 +195,9 @@ pub struct CyfsStackParams {
 
     // front module config
     pub front: CyfsStackFrontParams,
+
+    // name resolver config
+    pub name: CyfsStackNameParams,
 }
 
 impl CyfsStackParams {
@@ -139,6 +209,7 @@ impl CyfsStackParams {
             interface: CyfsStackInterfaceParams::new_empty(),
             meta: CyfsStackMetaParams::default(),
             front: CyfsStackFrontParams::default(),
+            name: CyfsStackNameParams::default(),
         }
     }
 
@@ -149,6 +220,7 @@ impl CyfsStackParams {
             interface: CyfsStackInterfaceParams::default(),
             meta: CyfsStackMetaParams::default(),
             front: CyfsStackFrontParams::default(),
+            name: CyfsStackNameParams::default(),
         }
     }
 }
@@ -331,6 +331,8 @@ impl DeviceSyncClient {
 
             zone_root_state: zone_state.zone_root_state,
             zone_root_state_revision: zone_state.zone_root_state_revision,
+
+            meta: MetaCacheStatus::default(),
         };
 
         self.ping_client.fill_ood_status(&mut status);
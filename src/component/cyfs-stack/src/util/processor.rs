@@ -41,6 +41,9 @@ pub trait UtilInputProcessor: Sync + Send + 'static {
 
     async fn build_dir_from_object_map(&self, req: UtilBuildDirFromObjectMapInputRequest)
         -> BuckyResult<UtilBuildDirFromObjectMapInputResponse>;
+
+    async fn explain_acl(&self, req: UtilExplainAclInputRequest)
+        -> BuckyResult<UtilExplainAclInputResponse>;
 }
 
 pub type UtilInputProcessorRef = Arc<Box<dyn UtilInputProcessor>>;
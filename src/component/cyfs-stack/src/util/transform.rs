@@ -117,6 +117,7 @@ impl UtilInputTransformer {
 
         let resp = UtilGetNOCInfoInputResponse {
             stat: out_resp.stat,
+            ndc_stat: out_resp.ndc_stat,
         };
 
         Ok(resp)
@@ -220,6 +221,24 @@ impl UtilInputTransformer {
         let out_resp = self.processor.build_dir_from_object_map(out_req).await?;
         Ok(out_resp)
     }
+
+    async fn explain_acl(
+        &self,
+        req: UtilExplainAclInputRequest,
+    ) -> BuckyResult<UtilExplainAclInputResponse> {
+        let out_req = UtilExplainAclOutputRequest {
+            common: Self::convert_common(req.common),
+            source_device: req.source_device,
+            source_dec: req.source_dec,
+            category: req.category,
+            req_path: req.req_path,
+            object_id: req.object_id,
+            obj_type: req.obj_type,
+        };
+
+        let out_resp = self.processor.explain_acl(out_req).await?;
+        Ok(out_resp)
+    }
 }
 
 #[async_trait::async_trait]
@@ -300,6 +319,13 @@ impl UtilInputProcessor for UtilInputTransformer {
     ) -> BuckyResult<UtilBuildDirFromObjectMapInputResponse> {
         UtilInputTransformer::build_dir_from_object_map(&self, req).await
     }
+
+    async fn explain_acl(
+        &self,
+        req: UtilExplainAclInputRequest,
+    ) -> BuckyResult<UtilExplainAclInputResponse> {
+        UtilInputTransformer::explain_acl(&self, req).await
+    }
 }
 
 pub(crate) struct UtilOutputTransformer {
@@ -488,4 +514,21 @@ impl UtilOutputProcessor for UtilOutputTransformer {
         let resp = self.processor.build_dir_from_object_map(in_req).await?;
         Ok(resp)
     }
+
+    async fn explain_acl(
+        &self,
+        req: UtilExplainAclOutputRequest,
+    ) -> BuckyResult<UtilExplainAclOutputResponse> {
+        let in_req = UtilExplainAclInputRequest {
+            common: self.convert_common(req.common),
+            source_device: req.source_device,
+            source_dec: req.source_dec,
+            category: req.category,
+            req_path: req.req_path,
+            object_id: req.object_id,
+            obj_type: req.obj_type,
+        };
+        let resp = self.processor.explain_acl(in_req).await?;
+        Ok(resp)
+    }
 }
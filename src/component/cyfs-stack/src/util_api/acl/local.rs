@@ -137,4 +137,13 @@ impl UtilInputProcessor for UtilAclInnerInputProcessor {
 
         self.next.build_dir_from_object_map(req).await
     }
+
+    async fn explain_acl(
+        &self,
+        req: UtilExplainAclInputRequest,
+    ) -> BuckyResult<UtilExplainAclInputResponse> {
+        self.check_local_zone_permit("util in explain_acl", &req.common.source)?;
+
+        self.next.explain_acl(req).await
+    }
 }
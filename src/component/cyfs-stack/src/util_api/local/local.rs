@@ -1,6 +1,8 @@
 use super::bdt_access_info::BdtNetworkAccessInfoManager;
 use super::dir_helper::*;
+use crate::acl::{AclManagerRef, AclPolicyRequest};
 use crate::config::StackGlobalConfig;
+use crate::meta::MetaCacheRef;
 use crate::resolver::OodResolver;
 use crate::sync::DeviceSyncClient;
 use crate::util::*;
@@ -22,8 +24,10 @@ use std::sync::Arc;
 
 pub(crate) struct UtilLocalService {
     noc: NamedObjectCacheRef,
+    ndc: Box<dyn NamedDataCache>,
     bdt_stack: StackGuard,
     zone_manager: ZoneManagerRef,
+    acl: AclManagerRef,
 
     ood_resolver: OodResolver,
 
@@ -33,6 +37,8 @@ pub(crate) struct UtilLocalService {
 
     task_manager: Arc<TaskManager>,
 
+    meta_cache: MetaCacheRef,
+
     config: StackGlobalConfig,
 }
 
@@ -40,12 +46,15 @@ impl Clone for UtilLocalService {
     fn clone(&self) -> Self {
         Self {
             noc: self.noc.clone(),
+            ndc: self.ndc.clone(),
             bdt_stack: self.bdt_stack.clone(),
             zone_manager: self.zone_manager.clone(),
+            acl: self.acl.clone(),
             ood_resolver: self.ood_resolver.clone(),
             sync_client: self.sync_client.clone(),
             access_info_manager: self.access_info_manager.clone(),
             task_manager: self.task_manager.clone(),
+            meta_cache: self.meta_cache.clone(),
             config: self.config.clone(),
         }
     }
@@ -57,14 +66,16 @@ impl UtilLocalService {
         ndc: Box<dyn NamedDataCache>,
         bdt_stack: StackGuard,
         zone_manager: ZoneManagerRef,
+        acl: AclManagerRef,
         ood_resolver: OodResolver,
         task_manager: Arc<TaskManager>,
+        meta_cache: MetaCacheRef,
         config: StackGlobalConfig,
     ) -> Self {
         let access_info_manager = BdtNetworkAccessInfoManager::new(bdt_stack.clone());
 
         task_manager
-            .register_task_factory(BuildFileTaskFactory::new(noc.clone(), ndc))
+            .register_task_factory(BuildFileTaskFactory::new(noc.clone(), ndc.clone()))
             .unwrap();
         task_manager
             .register_task_factory(BuildDirTaskFactory::new(
@@ -75,12 +86,15 @@ impl UtilLocalService {
 
         Self {
             noc,
+            ndc,
             bdt_stack,
             zone_manager,
+            acl,
             ood_resolver,
             sync_client: Arc::new(OnceCell::new()),
             access_info_manager,
             task_manager,
+            meta_cache,
             config,
         }
     }
@@ -204,7 +218,10 @@ impl UtilLocalService {
         let sync_client = sync_client.unwrap();
 
         let flush_ping = (req.common.flags | CYFS_ROUTER_REQUEST_FLAG_FLUSH) != 0;
-        let status = sync_client.get_ood_status(flush_ping).await?;
+        let mut status = sync_client.get_ood_status(flush_ping).await?;
+
+        // 本地meta缓存的状态，离线时可以据此判断缓存数据的新旧
+        status.meta = self.meta_cache.get_status();
 
         Ok(UtilGetOODStatusInputResponse { status })
     }
@@ -260,7 +277,16 @@ impl UtilLocalService {
     ) -> BuckyResult<UtilGetNOCInfoInputResponse> {
         let stat = self.noc.stat().await?;
 
-        Ok(UtilGetNOCInfoInputResponse { stat })
+        // ndc统计失败不影响noc的统计结果
+        let ndc_stat = match self.ndc.stat().await {
+            Ok(stat) => Some(stat),
+            Err(e) => {
+                warn!("get ndc stat failed! {}", e);
+                None
+            }
+        };
+
+        Ok(UtilGetNOCInfoInputResponse { stat, ndc_stat })
     }
 
     pub async fn get_network_access_info(
@@ -393,6 +419,49 @@ impl UtilLocalService {
         .await?;
         Ok(UtilBuildDirFromObjectMapInputResponse { object_id: dir_id })
     }
+
+    pub async fn explain_acl(
+        &self,
+        req: UtilExplainAclInputRequest,
+    ) -> BuckyResult<UtilExplainAclInputResponse> {
+        let device = match req.source_device {
+            Some(device) => device,
+            None => self.zone_manager.get_current_device_id().to_owned(),
+        };
+
+        let source = self
+            .zone_manager
+            .resolve_source_info(&req.source_dec, device)
+            .await?;
+
+        let policy_req = AclPolicyRequest {
+            source: &source,
+            category: req.category,
+            req_path: req.req_path.as_deref(),
+            object_id: req.object_id.as_ref(),
+            obj_type: req.obj_type,
+        };
+
+        let ret = self.acl.policy().explain(&policy_req);
+        info!("explain acl policy: req={}, result={:?}", policy_req, ret);
+
+        let resp = match ret {
+            Some(ret) => UtilExplainAclInputResponse {
+                zone_category: source.zone.zone_category,
+                rule_index: Some(ret.index as u32),
+                rule_id: Some(ret.id),
+                action: Some(ret.action.to_string()),
+            },
+            None => UtilExplainAclInputResponse {
+                zone_category: source.zone.zone_category,
+                rule_index: None,
+                rule_id: None,
+                action: None,
+            },
+        };
+
+        Ok(resp)
+    }
 }
 
 #[async_trait::async_trait]
@@ -473,4 +542,11 @@ impl UtilInputProcessor for UtilLocalService {
     ) -> BuckyResult<UtilBuildDirFromObjectMapInputResponse> {
         UtilLocalService::build_dir_from_object_map(self, req).await
     }
+
+    async fn explain_acl(
+        &self,
+        req: UtilExplainAclInputRequest,
+    ) -> BuckyResult<UtilExplainAclInputResponse> {
+        UtilLocalService::explain_acl(self, req).await
+    }
 }
@@ -249,4 +249,12 @@ impl UtilInputProcessor for UtilRouter {
         let processor = self.get_processor(req.common.target.as_ref()).await?;
         processor.build_dir_from_object_map(req).await
     }
+
+    async fn explain_acl(
+        &self,
+        req: UtilExplainAclInputRequest,
+    ) -> BuckyResult<UtilExplainAclInputResponse> {
+        let processor = self.get_processor(req.common.target.as_ref()).await?;
+        processor.explain_acl(req).await
+    }
 }
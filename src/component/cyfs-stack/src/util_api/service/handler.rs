@@ -448,4 +448,52 @@ impl UtilRequestHandler {
         };
         self.processor.build_dir_from_object_map(in_req).await
     }
+
+    // explain_acl
+    pub async fn process_explain_acl_request<State>(
+        &self,
+        req: NONInputHttpRequest<State>,
+    ) -> Response {
+        match self.on_explain_acl_request(req).await {
+            Ok(resp) => {
+                let mut http_resp = RequestorHelper::new_response(StatusCode::Ok);
+
+                http_resp.set_content_type(::tide::http::mime::JSON);
+                http_resp.set_body(resp.encode_string());
+
+                http_resp.into()
+            }
+            Err(e) => RequestorHelper::trans_error(e),
+        }
+    }
+
+    async fn on_explain_acl_request<State>(
+        &self,
+        mut req: NONInputHttpRequest<State>,
+    ) -> BuckyResult<UtilExplainAclInputResponse> {
+        let body = req.request.body_string().await.map_err(|e| {
+            let msg = format!("explain acl failed, read body bytes error! {}", e);
+            error!("{}", msg);
+
+            BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+        })?;
+
+        let out_req = UtilExplainAclOutputRequest::decode_string(body.as_str())?;
+
+        let in_req = UtilExplainAclInputRequest {
+            common: UtilInputRequestCommon {
+                req_path: out_req.common.req_path,
+                source: req.source,
+                target: out_req.common.target,
+                flags: out_req.common.flags,
+            },
+            source_device: out_req.source_device,
+            source_dec: out_req.source_dec,
+            category: out_req.category,
+            req_path: out_req.req_path,
+            object_id: out_req.object_id,
+            obj_type: out_req.obj_type,
+        };
+        self.processor.explain_acl(in_req).await
+    }
 }
@@ -18,6 +18,7 @@ enum UtilRequestType {
     GetVersionInfo,
     BuildFile,
     BuildDirFromObjectMap,
+    ExplainAcl,
 }
 
 pub(crate) struct UtilRequestHandlerEndpoint {
@@ -76,6 +77,7 @@ impl UtilRequestHandlerEndpoint {
                     .process_build_dir_from_object_map_request(req)
                     .await
             }
+            UtilRequestType::ExplainAcl => self.handler.process_explain_acl_request(req).await,
         }
     }
 
@@ -290,6 +292,20 @@ impl UtilRequestHandlerEndpoint {
                 UtilRequestType::BuildDirFromObjectMap,
                 handler.clone(),
             ));
+
+        // explain_acl
+        server.at("/util/explain_acl").post(Self::new(
+            zone_manager.clone(),
+            protocol.to_owned(),
+            UtilRequestType::ExplainAcl,
+            handler.clone(),
+        ));
+        server.at("/util/explain_acl/*must").post(Self::new(
+            zone_manager.clone(),
+            protocol.to_owned(),
+            UtilRequestType::ExplainAcl,
+            handler.clone(),
+        ));
     }
 }
 
@@ -1,8 +1,9 @@
 use super::super::local::UtilLocalService;
 use super::super::router::UtilRouter;
+use crate::acl::AclManagerRef;
 use crate::config::StackGlobalConfig;
 use crate::forward::ForwardProcessorManager;
-use crate::meta::ObjectFailHandler;
+use crate::meta::{MetaCacheRef, ObjectFailHandler};
 use crate::resolver::OodResolver;
 use crate::util::*;
 use crate::zone::*;
@@ -23,9 +24,11 @@ impl UtilService {
         bdt_stack: StackGuard,
         forward: ForwardProcessorManager,
         zone_manager: ZoneManagerRef,
+        acl: AclManagerRef,
         fail_handler: ObjectFailHandler,
         ood_resolver: OodResolver,
         task_manager: Arc<TaskManager>,
+        meta_cache: MetaCacheRef,
         config: StackGlobalConfig,
     ) -> Self {
         let local_service = UtilLocalService::new(
@@ -33,8 +36,10 @@ impl UtilService {
             ndc,
             bdt_stack.clone(),
             zone_manager.clone(),
+            acl,
             ood_resolver,
             task_manager,
+            meta_cache,
             config,
         );
 
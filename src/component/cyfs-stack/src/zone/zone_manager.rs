@@ -581,10 +581,19 @@ impl ZoneManager {
 
         match device.desc().owner() {
             Some(owner) => {
-                // People,SimpleGroup对象存在ood_list
+                // owner是people的情况下，需要检查device是否已经被吊销
                 let mut owner = owner.to_owned();
+                let owner_object = if owner.obj_type_code() == ObjectTypeCode::People {
+                    let object = self.search_object(&owner).await?;
+                    Self::check_device_revoked(&object, device_id)?;
+                    Some(object)
+                } else {
+                    None
+                };
+
+                // People,SimpleGroup对象存在ood_list
                 let (ood_work_mode, ood_list) =
-                    self.search_zone_ood_by_owner(&mut owner, None).await?;
+                    self.search_zone_ood_by_owner(&mut owner, owner_object).await?;
                 Ok((owner, ood_work_mode, ood_list))
             }
             None => match device.category() {
@@ -609,6 +618,57 @@ impl ZoneManager {
         }
     }
 
+    // 被owner吊销的device(比如私钥泄露后更换了密钥)，不允许再加入zone和发起zone内请求
+    pub fn check_device_revoked(owner: &AnyNamedObject, device_id: &DeviceId) -> BuckyResult<()> {
+        if let AnyNamedObject::Standard(StandardObject::People(people)) = owner {
+            if people.is_device_revoked(device_id) {
+                let msg = format!(
+                    "device already been revoked by owner! device={}, owner={}",
+                    device_id,
+                    people.desc().calculate_id()
+                );
+                warn!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+            }
+        }
+
+        Ok(())
+    }
+
+    // 检查source是否被吊销，需要使用source自己的owner，而不只是当前zone的owner
+    async fn check_source_revoked(
+        &self,
+        current_info: &CurrentZoneInfo,
+        source: &DeviceId,
+    ) -> BuckyResult<()> {
+        Self::check_device_revoked(&current_info.owner, source)?;
+
+        let device = match self.device_manager.get(source).await {
+            Some(device) => device,
+            None => return Ok(()),
+        };
+
+        let owner_id = match device.desc().owner() {
+            Some(owner) if owner.obj_type_code() == ObjectTypeCode::People => owner.to_owned(),
+            _ => return Ok(()),
+        };
+        if owner_id == current_info.owner_id {
+            return Ok(());
+        }
+
+        match self.search_object(&owner_id).await {
+            Ok(owner) => Self::check_device_revoked(&owner, source),
+            Err(e) => {
+                // 查找不到owner时无法检查吊销列表，后续按照other zone处理
+                warn!(
+                    "search source device's owner failed, revoke list will not be checked! source={}, owner={}, {}",
+                    source, owner_id, e
+                );
+                Ok(())
+            }
+        }
+    }
+
     /*
     async fn verify_owner_body(obj: &AnyNamedObject) -> BuckyResult<()> {
         let pk = obj.public_key();
@@ -858,6 +918,8 @@ impl ZoneManager {
                 break ret;
             }
 
+            self.check_source_revoked(&current_info, &source).await?;
+
             let current_zone = self.get_current_zone().await?;
             if current_zone.is_known_device(&source) {
                 let mut ret = RequestSourceInfo::new_zone_dec(dec.to_owned());
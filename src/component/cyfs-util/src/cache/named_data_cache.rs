@@ -2,6 +2,7 @@ use cyfs_base::*;
 
 use async_trait::async_trait;
 use int_enum::IntEnum;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct FileDirRef {
@@ -263,7 +264,7 @@ pub struct SelectChunkResponse {
     pub list: Vec<SelectChunkData>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NamedDataCacheStat {
     // chunks count
     pub count: u64,
@@ -37,7 +37,7 @@ impl GatewayRegister {
             BuckyError::from(msg)
         })?;
 
-        assert!(server_type == "http" || server_type == "stream");
+        assert!(server_type == "http" || server_type == "stream" || server_type == "upstream");
         assert!(id.len() > 0);
 
         let mut register = GatewayRegister::new(id, server_type);
@@ -52,7 +52,7 @@ impl GatewayRegister {
     }
 
     pub async fn unregister(id: String, server_type: String) -> Result<(), BuckyError> {
-        assert!(server_type == "http" || server_type == "stream");
+        assert!(server_type == "http" || server_type == "stream" || server_type == "upstream");
         assert!(id.len() > 0);
 
         let register = GatewayRegister::new(id, server_type);
@@ -0,0 +1,679 @@
+use crate::get_cyfs_root_path;
+use cyfs_base::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// 加密后的数据格式: magic(4bytes) + generation(u32 be) + nonce + 密文 + tag
+const STORAGE_CRYPTO_MAGIC: &[u8; 4] = b"CYSE";
+const STORAGE_CRYPTO_HEADER_LEN: usize = 8;
+
+const STORAGE_CRYPTO_CONFIG_FILE: &str = "storage-crypto.json";
+// PBKDF2-HMAC-SHA256的迭代次数
+const STORAGE_CRYPTO_PASSPHRASE_ROUNDS: u32 = 1024 * 256;
+
+// 使用口令派生密钥时，协议栈从该环境变量读取口令
+pub const CYFS_STORAGE_PASSPHRASE_ENV: &str = "CYFS_STORAGE_PASSPHRASE";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageCryptoKeySource {
+    Device,
+    Passphrase,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StorageCryptoGeneration {
+    pub generation: u32,
+
+    // 密钥的mix_hash，用来校验口令或者device是否匹配
+    pub check: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StorageCryptoConfig {
+    pub source: StorageCryptoKeySource,
+    pub salt: String,
+    pub current: u32,
+    pub generations: Vec<StorageCryptoGeneration>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StorageCryptoMigrateStat {
+    pub total: u64,
+    pub migrated: u64,
+    pub failed: u64,
+}
+
+pub struct StorageCrypto {
+    current: u32,
+    keys: HashMap<u32, AesKey>,
+}
+
+pub type StorageCryptoRef = Arc<StorageCrypto>;
+
+impl StorageCrypto {
+    pub fn current_generation(&self) -> u32 {
+        self.current
+    }
+
+    // 加密后相对于明文增加的长度
+    pub const fn overhead() -> usize {
+        STORAGE_CRYPTO_HEADER_LEN + AesKey::gcm_overhead()
+    }
+
+    pub fn is_encrypted(data: &[u8]) -> bool {
+        data.len() >= Self::overhead() && &data[0..4] == STORAGE_CRYPTO_MAGIC
+    }
+
+    pub const fn header_len() -> usize {
+        STORAGE_CRYPTO_HEADER_LEN
+    }
+
+    // 只检查头部: magic正确并且对应generation的密钥已加载
+    pub fn check_header(&self, header: &[u8]) -> bool {
+        if header.len() < STORAGE_CRYPTO_HEADER_LEN || &header[0..4] != STORAGE_CRYPTO_MAGIC {
+            return false;
+        }
+
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(&header[4..8]);
+        self.keys.contains_key(&u32::from_be_bytes(buf))
+    }
+
+    pub fn encrypted_generation(data: &[u8]) -> Option<u32> {
+        if !Self::is_encrypted(data) {
+            return None;
+        }
+
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(&data[4..8]);
+        Some(u32::from_be_bytes(buf))
+    }
+
+    pub fn encrypt(&self, data: &[u8]) -> BuckyResult<Vec<u8>> {
+        let key = self.keys.get(&self.current).unwrap();
+        let encrypted = key.gcm_encrypt(data)?;
+
+        let mut ret = Vec::with_capacity(STORAGE_CRYPTO_HEADER_LEN + encrypted.len());
+        ret.extend_from_slice(STORAGE_CRYPTO_MAGIC);
+        ret.extend_from_slice(&self.current.to_be_bytes());
+        ret.extend_from_slice(&encrypted);
+
+        Ok(ret)
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> BuckyResult<Vec<u8>> {
+        let generation = Self::encrypted_generation(data).ok_or_else(|| {
+            let msg = format!("storage data is not encrypted! len={}", data.len());
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidData, msg)
+        })?;
+
+        let key = self.keys.get(&generation).ok_or_else(|| {
+            let msg = format!(
+                "storage data encrypted with unknown key generation! generation={}, current={}",
+                generation, self.current
+            );
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::CryptoError, msg)
+        })?;
+
+        key.gcm_decrypt(&data[STORAGE_CRYPTO_HEADER_LEN..])
+    }
+
+    // 写入磁盘前调用，未启用加密则原样返回
+    pub fn store_data(crypto: Option<&StorageCryptoRef>, data: Vec<u8>) -> BuckyResult<Vec<u8>> {
+        match crypto {
+            Some(crypto) => crypto.encrypt(&data),
+            None => Ok(data),
+        }
+    }
+
+    // 从磁盘读取后调用，兼容启用加密之前写入的明文数据；带magic但解密失败的数据不能当作明文返回
+    pub fn load_data(crypto: Option<&StorageCryptoRef>, data: Vec<u8>) -> BuckyResult<Vec<u8>> {
+        if !Self::is_encrypted(&data) {
+            return Ok(data);
+        }
+
+        match crypto {
+            Some(crypto) => crypto.decrypt(&data).map_err(|e| {
+                let msg = format!("decrypt storage data failed! len={}, {}", data.len(), e);
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::CryptoError, msg)
+            }),
+            None => {
+                let msg = format!(
+                    "storage data is encrypted but storage crypto not loaded! len={}",
+                    data.len()
+                );
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::CryptoError, msg))
+            }
+        }
+    }
+
+    // 把dir下的所有文件转换为使用当前密钥加密(encrypt=true)或者明文(encrypt=false)，需要在协议栈停止后执行
+    pub async fn migrate_dir(
+        crypto: &StorageCryptoRef,
+        dir: &Path,
+        encrypt: bool,
+        skip_names: &[&str],
+    ) -> BuckyResult<StorageCryptoMigrateStat> {
+        let mut stat = StorageCryptoMigrateStat::default();
+        if !dir.is_dir() {
+            return Ok(stat);
+        }
+
+        for entry in walkdir::WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy();
+            if skip_names.iter().any(|v| *v == name) || name.ends_with(".migrate") {
+                continue;
+            }
+
+            stat.total += 1;
+            match Self::migrate_file(crypto, entry.path(), encrypt).await {
+                Ok(true) => stat.migrated += 1,
+                Ok(false) => {}
+                Err(_) => stat.failed += 1,
+            }
+        }
+
+        info!(
+            "migrate storage crypto dir complete! dir={}, encrypt={}, stat={:?}",
+            dir.display(),
+            encrypt,
+            stat
+        );
+
+        Ok(stat)
+    }
+
+    async fn migrate_file(crypto: &StorageCryptoRef, path: &Path, encrypt: bool) -> BuckyResult<bool> {
+        let data = async_std::fs::read(path).await.map_err(|e| {
+            let msg = format!("read storage file error! file={}, {}", path.display(), e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::IoError, msg)
+        })?;
+
+        let generation = Self::encrypted_generation(&data);
+        let plain = match generation {
+            Some(_) => match crypto.decrypt(&data) {
+                Ok(plain) => Some(plain),
+                Err(e) => {
+                    error!("migrate storage file but decrypt failed! file={}, {}", path.display(), e);
+                    return Err(e);
+                }
+            },
+            None => None,
+        };
+
+        let target = match (plain, encrypt) {
+            (Some(plain), true) => {
+                if generation == Some(crypto.current) {
+                    return Ok(false);
+                }
+                crypto.encrypt(&plain)?
+            }
+            (Some(plain), false) => plain,
+            (None, true) => crypto.encrypt(&data)?,
+            (None, false) => return Ok(false),
+        };
+
+        // 先写入临时文件再替换，避免中途失败损坏原文件
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".migrate");
+        let tmp = PathBuf::from(tmp);
+
+        if let Err(e) = async_std::fs::write(&tmp, &target).await {
+            let msg = format!("write storage migrate file error! file={}, {}", tmp.display(), e);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::IoError, msg));
+        }
+
+        if let Err(e) = async_std::fs::rename(&tmp, path).await {
+            let _ = async_std::fs::remove_file(&tmp).await;
+            let msg = format!("replace storage file error! file={}, {}", path.display(), e);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::IoError, msg));
+        }
+
+        Ok(true)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref STORAGE_CRYPTO_LIST: Mutex<HashMap<String, StorageCryptoRef>> = Mutex::new(HashMap::new());
+}
+
+// 每个isolate一份加密配置，保存在{cyfs_root}/data/{isolate}/storage-crypto.json
+pub struct StorageCryptoManager;
+
+impl StorageCryptoManager {
+    fn config_path(isolate: &str) -> PathBuf {
+        let dir = get_cyfs_root_path().join("data");
+        let dir = if isolate.len() > 0 {
+            dir.join(isolate)
+        } else {
+            dir
+        };
+
+        dir.join(STORAGE_CRYPTO_CONFIG_FILE)
+    }
+
+    pub fn load_config(isolate: &str) -> BuckyResult<Option<StorageCryptoConfig>> {
+        let path = Self::config_path(isolate);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let s = std::fs::read_to_string(&path).map_err(|e| {
+            let msg = format!("read storage crypto config error! file={}, {}", path.display(), e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::IoError, msg)
+        })?;
+
+        let config = serde_json::from_str(&s).map_err(|e| {
+            let msg = format!("invalid storage crypto config! file={}, {}", path.display(), e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+
+        Ok(Some(config))
+    }
+
+    fn save_config(isolate: &str, config: &StorageCryptoConfig) -> BuckyResult<()> {
+        let path = Self::config_path(isolate);
+        if let Some(dir) = path.parent() {
+            if !dir.is_dir() {
+                std::fs::create_dir_all(dir).map_err(|e| {
+                    let msg = format!("create storage crypto dir error! dir={}, {}", dir.display(), e);
+                    error!("{}", msg);
+                    BuckyError::new(BuckyErrorCode::IoError, msg)
+                })?;
+            }
+        }
+
+        let s = serde_json::to_string_pretty(config).unwrap();
+        std::fs::write(&path, s).map_err(|e| {
+            let msg = format!("save storage crypto config error! file={}, {}", path.display(), e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::IoError, msg)
+        })?;
+
+        info!("save storage crypto config success! file={}, config={:?}", path.display(), config);
+        Ok(())
+    }
+
+    fn derive_key(
+        config: &StorageCryptoConfig,
+        generation: u32,
+        secret: &PrivateKey,
+        passphrase: Option<&str>,
+    ) -> BuckyResult<AesKey> {
+        let mut salt = hex::decode(&config.salt).map_err(|e| {
+            let msg = format!("invalid storage crypto salt! {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+        salt.extend_from_slice(&generation.to_be_bytes());
+
+        let key = match config.source {
+            StorageCryptoKeySource::Device => AesKey::derive(&secret.to_vec()?, &salt),
+            StorageCryptoKeySource::Passphrase => {
+                let passphrase = passphrase.ok_or_else(|| {
+                    let msg = format!(
+                        "storage crypto use passphrase but not specified! env={}",
+                        CYFS_STORAGE_PASSPHRASE_ENV
+                    );
+                    error!("{}", msg);
+                    BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+                })?;
+                AesKey::derive_from_passphrase(passphrase, &salt, STORAGE_CRYPTO_PASSPHRASE_ROUNDS)
+            }
+        };
+
+        Ok(key)
+    }
+
+    fn key_check(key: &AesKey) -> String {
+        hex::encode(key.mix_hash(None).as_ref().as_slice())
+    }
+
+    fn open_with_config(
+        config: &StorageCryptoConfig,
+        secret: &PrivateKey,
+        passphrase: Option<&str>,
+    ) -> BuckyResult<StorageCryptoRef> {
+        let mut keys = HashMap::new();
+        for item in &config.generations {
+            let key = Self::derive_key(config, item.generation, secret, passphrase)?;
+            if Self::key_check(&key) != item.check {
+                let msg = format!(
+                    "storage crypto key not match, device or passphrase changed? generation={}",
+                    item.generation
+                );
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::CryptoError, msg));
+            }
+
+            keys.insert(item.generation, key);
+        }
+
+        if !keys.contains_key(&config.current) {
+            let msg = format!("storage crypto current generation missing! current={}", config.current);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
+        }
+
+        Ok(Arc::new(StorageCrypto {
+            current: config.current,
+            keys,
+        }))
+    }
+
+    // 打开isolate的存储加密，未启用返回None
+    pub fn open(
+        isolate: &str,
+        secret: &PrivateKey,
+        passphrase: Option<&str>,
+    ) -> BuckyResult<Option<StorageCryptoRef>> {
+        match Self::load_config(isolate)? {
+            Some(config) => {
+                let crypto = Self::open_with_config(&config, secret, passphrase)?;
+                info!(
+                    "open storage crypto success! isolate={}, source={:?}, current={}",
+                    isolate, config.source, config.current
+                );
+                Ok(Some(crypto))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn new_generation(
+        config: &mut StorageCryptoConfig,
+        generation: u32,
+        secret: &PrivateKey,
+        passphrase: Option<&str>,
+    ) -> BuckyResult<()> {
+        let key = Self::derive_key(config, generation, secret, passphrase)?;
+        config.generations.push(StorageCryptoGeneration {
+            generation,
+            check: Self::key_check(&key),
+        });
+        config.current = generation;
+
+        Ok(())
+    }
+
+    // 启用加密，已有的明文数据依然可读，需要执行迁移后才会全部加密
+    pub fn enable(
+        isolate: &str,
+        source: StorageCryptoKeySource,
+        secret: &PrivateKey,
+        passphrase: Option<&str>,
+    ) -> BuckyResult<StorageCryptoRef> {
+        if Self::load_config(isolate)?.is_some() {
+            let msg = format!("storage crypto already enabled! isolate={}", isolate);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::AlreadyExists, msg));
+        }
+
+        let mut config = StorageCryptoConfig {
+            source,
+            salt: hex::encode(&AesKey::random().as_slice()[0..16]),
+            current: 0,
+            generations: vec![],
+        };
+        Self::new_generation(&mut config, 0, secret, passphrase)?;
+
+        let crypto = Self::open_with_config(&config, secret, passphrase)?;
+        Self::save_config(isolate, &config)?;
+
+        Ok(crypto)
+    }
+
+    // 生成新的密钥用于后续写入，旧密钥保留用于读取，迁移完成后可以通过prune移除
+    pub fn rotate(
+        isolate: &str,
+        secret: &PrivateKey,
+        passphrase: Option<&str>,
+    ) -> BuckyResult<StorageCryptoRef> {
+        let mut config = Self::load_config(isolate)?.ok_or_else(|| {
+            let msg = format!("rotate storage crypto but not enabled! isolate={}", isolate);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::NotFound, msg)
+        })?;
+
+        // 先校验旧密钥
+        Self::open_with_config(&config, secret, passphrase)?;
+
+        let generation = config.generations.iter().map(|v| v.generation).max().unwrap_or(0) + 1;
+        Self::new_generation(&mut config, generation, secret, passphrase)?;
+
+        let crypto = Self::open_with_config(&config, secret, passphrase)?;
+        Self::save_config(isolate, &config)?;
+
+        info!("rotate storage crypto key success! isolate={}, current={}", isolate, generation);
+        Ok(crypto)
+    }
+
+    // 只保留当前密钥，必须在所有数据迁移到当前密钥之后执行
+    pub fn prune(isolate: &str) -> BuckyResult<()> {
+        if let Some(mut config) = Self::load_config(isolate)? {
+            let current = config.current;
+            config.generations.retain(|v| v.generation == current);
+            Self::save_config(isolate, &config)?;
+        }
+
+        Ok(())
+    }
+
+    // 关闭加密，必须在所有数据迁移为明文之后执行
+    pub fn disable(isolate: &str) -> BuckyResult<()> {
+        let path = Self::config_path(isolate);
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|e| {
+                let msg = format!("remove storage crypto config error! file={}, {}", path.display(), e);
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::IoError, msg)
+            })?;
+
+            info!("storage crypto disabled! isolate={}", isolate);
+        }
+
+        Ok(())
+    }
+
+    // 进程内按isolate注册，noc和chunk cache初始化时通过get获取
+    pub fn install(isolate: &str, crypto: StorageCryptoRef) {
+        STORAGE_CRYPTO_LIST
+            .lock()
+            .unwrap()
+            .insert(isolate.to_owned(), crypto);
+    }
+
+    pub fn get(isolate: &str) -> Option<StorageCryptoRef> {
+        STORAGE_CRYPTO_LIST.lock().unwrap().get(isolate).cloned()
+    }
+
+    // 协议栈启动时调用，口令从环境变量读取
+    pub fn load_and_install(isolate: &str, secret: &PrivateKey) -> BuckyResult<Option<StorageCryptoRef>> {
+        let passphrase = std::env::var(CYFS_STORAGE_PASSPHRASE_ENV).ok();
+        let ret = Self::open(isolate, secret, passphrase.as_deref())?;
+        if let Some(crypto) = &ret {
+            Self::install(isolate, crypto.clone());
+        }
+
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_storage_crypto() {
+        let mut keys = HashMap::new();
+        keys.insert(0, AesKey::derive(b"secret", b"0"));
+        keys.insert(1, AesKey::derive(b"secret", b"1"));
+
+        let old = Arc::new(StorageCrypto {
+            current: 0,
+            keys: keys.clone(),
+        });
+        let crypto = Arc::new(StorageCrypto { current: 1, keys });
+
+        let data = b"test storage crypto data".to_vec();
+        let encrypted = StorageCrypto::store_data(Some(&old), data.clone()).unwrap();
+        assert!(StorageCrypto::is_encrypted(&encrypted));
+        assert_eq!(StorageCrypto::encrypted_generation(&encrypted), Some(0));
+        assert_eq!(encrypted.len(), data.len() + StorageCrypto::overhead());
+
+        // 轮换后旧数据依然可读
+        let plain = StorageCrypto::load_data(Some(&crypto), encrypted.clone()).unwrap();
+        assert_eq!(plain, data);
+
+        // 明文数据兼容
+        let plain = StorageCrypto::load_data(Some(&crypto), data.clone()).unwrap();
+        assert_eq!(plain, data);
+
+        assert!(StorageCrypto::load_data(None, encrypted.clone()).is_err());
+
+        // 带magic但解密失败的数据不能当作明文返回
+        let mut tampered = encrypted;
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0x01;
+        let e = StorageCrypto::load_data(Some(&crypto), tampered).unwrap_err();
+        assert_eq!(e.code(), BuckyErrorCode::CryptoError);
+
+        let mut header = StorageCrypto::store_data(Some(&crypto), data).unwrap();
+        assert!(crypto.check_header(&header));
+        header[4..8].copy_from_slice(&2u32.to_be_bytes());
+        assert!(!crypto.check_header(&header));
+    }
+
+    fn test_isolate(name: &str) -> String {
+        format!("test-storage-crypto-{}-{}", name, rand::random::<u32>())
+    }
+
+    fn remove_isolate(isolate: &str) {
+        let _ = std::fs::remove_dir_all(get_cyfs_root_path().join("data").join(isolate));
+    }
+
+    #[async_std::test]
+    async fn test_migrate_dir() {
+        let mut keys = HashMap::new();
+        keys.insert(0, AesKey::derive(b"secret", b"0"));
+        keys.insert(1, AesKey::derive(b"secret", b"1"));
+        let old = Arc::new(StorageCrypto {
+            current: 0,
+            keys: keys.clone(),
+        });
+        let crypto = Arc::new(StorageCrypto { current: 1, keys });
+
+        let dir = std::env::temp_dir().join(test_isolate("migrate"));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+
+        let plain = b"plain data".to_vec();
+        std::fs::write(dir.join("plain"), &plain).unwrap();
+        std::fs::write(dir.join("sub").join("old"), old.encrypt(&plain).unwrap()).unwrap();
+        std::fs::write(dir.join("current"), crypto.encrypt(&plain).unwrap()).unwrap();
+        std::fs::write(dir.join("skip"), &plain).unwrap();
+
+        // 明文和旧密钥的数据迁移到当前密钥，当前密钥的数据和跳过的文件不变
+        let stat = StorageCrypto::migrate_dir(&crypto, &dir, true, &["skip"]).await.unwrap();
+        assert_eq!(stat.total, 3);
+        assert_eq!(stat.migrated, 2);
+        assert_eq!(stat.failed, 0);
+        for name in ["plain", "sub/old", "current"] {
+            let data = std::fs::read(dir.join(name)).unwrap();
+            assert_eq!(StorageCrypto::encrypted_generation(&data), Some(1));
+            assert_eq!(crypto.decrypt(&data).unwrap(), plain);
+        }
+        assert_eq!(std::fs::read(dir.join("skip")).unwrap(), plain);
+
+        // 解密失败的文件计入failed并保持原样
+        let mut tampered = crypto.encrypt(&plain).unwrap();
+        tampered[StorageCrypto::header_len()] ^= 0x01;
+        std::fs::write(dir.join("tampered"), &tampered).unwrap();
+
+        let stat = StorageCrypto::migrate_dir(&crypto, &dir, false, &["skip"]).await.unwrap();
+        assert_eq!(stat.total, 4);
+        assert_eq!(stat.migrated, 3);
+        assert_eq!(stat.failed, 1);
+        assert_eq!(std::fs::read(dir.join("sub").join("old")).unwrap(), plain);
+        assert_eq!(std::fs::read(dir.join("tampered")).unwrap(), tampered);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_manager() {
+        let isolate = test_isolate("manager");
+        let secret = PrivateKey::generate_rsa(1024).unwrap();
+        let other = PrivateKey::generate_rsa(1024).unwrap();
+
+        assert!(StorageCryptoManager::open(&isolate, &secret, None).unwrap().is_none());
+
+        let crypto = StorageCryptoManager::enable(&isolate, StorageCryptoKeySource::Device, &secret, None).unwrap();
+        assert_eq!(crypto.current_generation(), 0);
+        let data0 = crypto.encrypt(b"generation 0").unwrap();
+
+        assert!(StorageCryptoManager::enable(&isolate, StorageCryptoKeySource::Device, &secret, None).is_err());
+        assert_eq!(
+            StorageCryptoManager::open(&isolate, &other, None).unwrap_err().code(),
+            BuckyErrorCode::CryptoError
+        );
+
+        // 轮换后新数据用新密钥，旧数据依然可读
+        let crypto = StorageCryptoManager::rotate(&isolate, &secret, None).unwrap();
+        assert_eq!(crypto.current_generation(), 1);
+        let data1 = crypto.encrypt(b"generation 1").unwrap();
+        assert_eq!(StorageCrypto::encrypted_generation(&data1), Some(1));
+
+        let crypto = StorageCryptoManager::open(&isolate, &secret, None).unwrap().unwrap();
+        assert_eq!(crypto.decrypt(&data0).unwrap(), b"generation 0");
+        assert_eq!(crypto.decrypt(&data1).unwrap(), b"generation 1");
+
+        // prune后只保留当前密钥
+        StorageCryptoManager::prune(&isolate).unwrap();
+        let config = StorageCryptoManager::load_config(&isolate).unwrap().unwrap();
+        assert_eq!(config.generations.len(), 1);
+        assert_eq!(config.current, 1);
+
+        let crypto = StorageCryptoManager::open(&isolate, &secret, None).unwrap().unwrap();
+        assert!(crypto.decrypt(&data0).is_err());
+        assert_eq!(crypto.decrypt(&data1).unwrap(), b"generation 1");
+
+        StorageCryptoManager::disable(&isolate).unwrap();
+        assert!(StorageCryptoManager::open(&isolate, &secret, None).unwrap().is_none());
+
+        remove_isolate(&isolate);
+    }
+
+    #[test]
+    fn test_manager_passphrase() {
+        let isolate = test_isolate("passphrase");
+        let secret = PrivateKey::generate_rsa(1024).unwrap();
+
+        assert!(StorageCryptoManager::enable(&isolate, StorageCryptoKeySource::Passphrase, &secret, None).is_err());
+
+        let crypto = StorageCryptoManager::enable(&isolate, StorageCryptoKeySource::Passphrase, &secret, Some("passphrase")).unwrap();
+        let data = crypto.encrypt(b"data").unwrap();
+
+        assert!(StorageCryptoManager::open(&isolate, &secret, Some("other")).is_err());
+        let crypto = StorageCryptoManager::open(&isolate, &secret, Some("passphrase")).unwrap().unwrap();
+        assert_eq!(crypto.decrypt(&data).unwrap(), b"data");
+
+        remove_isolate(&isolate);
+    }
+}
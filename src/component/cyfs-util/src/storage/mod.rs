@@ -1,7 +1,9 @@
+mod crypto;
 mod file_storage;
 mod local_store;
 mod sqlite_storage;
 
+pub use crypto::*;
 pub use file_storage::FileStorage;
 pub use local_store::LocalStore;
 pub use sqlite_storage::SqliteStorage;
@@ -12,6 +12,7 @@ use tide::{
     prelude::*
 };
 use cyfs_base::*;
+use cyfs_bdt::pn::service::Service;
 use super::storage::Storage;
 
 
@@ -33,17 +34,19 @@ body: {
 
 struct ServerImpl {
     pn: DeviceId, 
-    storage: Storage
+    storage: Storage, 
+    service: Service
 }
 
 #[derive(Clone)]
 struct Server(Arc<ServerImpl>);
 
 impl Server {
-    fn new(pn: DeviceId, storage: Storage) -> Self {
+    fn new(pn: DeviceId, storage: Storage, service: Service) -> Self {
         Self(Arc::new(ServerImpl {
             pn, 
-            storage
+            storage, 
+            service
         }))
     }
 
@@ -54,15 +57,19 @@ impl Server {
     fn pn(&self) -> &DeviceId {
         &self.0.pn
     }
+
+    fn service(&self) -> &Service {
+        &self.0.service
+    }
 }
 
-pub async fn listen(port: u16, pn: DeviceId, storage: Option<Storage>) -> BuckyResult<()> {
+pub async fn listen(port: u16, pn: DeviceId, storage: Option<Storage>, service: Service) -> BuckyResult<()> {
     if storage.is_none() {
         return Ok(());
     }
     let storage = storage.unwrap();
 
-    let mut server = tide::with_state(Server::new(pn, storage));  
+    let mut server = tide::with_state(Server::new(pn, storage, service));  
 
     /*
     获取PN列表
@@ -155,6 +162,46 @@ pub async fn listen(port: u16, pn: DeviceId, storage: Option<Storage>) -> BuckyR
     */
     server.at("/pn/white_list").post(add_pn_white_list);
 
+
+    /*
+    查询中转流量
+    req
+    uri: /pn/usage
+    method: POST
+    body: {
+        device: DeviceId,   ood的device id，不填返回所有device
+    }
+
+    resp
+    body: [{
+        device: DeviceId, 
+        owner: ObjectId, 没有的话是null
+        bytes: Number, 已经中转的字节数
+        dropped_bytes: Number, 超过带宽限制被丢弃的字节数
+        tunnels: Number, 当前的proxy tunnel数
+        update_time: Number, 
+    }]
+    */
+    server.at("/pn/usage").post(query_usage);
+
+
+    /*
+    清零中转流量
+    req
+    uri: /pn/usage/reset
+    method: POST
+    body: {
+        device: DeviceId,   ood的device id，不填清零所有device
+    }
+
+    resp:
+    body: {
+        err: 0 成功, 
+        count: Number 清零的device数
+    }
+    */
+    server.at("/pn/usage/reset").post(reset_usage);
+
     let _ = server.listen(format!("127.0.0.1:{}", port).as_str()).await?;
     Ok(())
 }
@@ -191,6 +238,19 @@ where
     d.deserialize_str(DeviceIdVisitor {})
 }
 
+fn option_device_id_deserialize<'de, D>(d: D) -> Result<Option<DeviceId>, D::Error> 
+where 
+    D: Deserializer<'de>,  
+{
+    let value: Option<String> = Option::deserialize(d)?;
+    match value {
+        Some(value) => DeviceId::from_str(value.as_str()).map(|id| Some(id)).map_err(|_| {
+            de::Error::invalid_value(de::Unexpected::Str(value.as_str()), &"device id")
+        }), 
+        None => Ok(None)
+    }
+}
+
 async fn list_pn(req: Request<Server>) -> tide::Result {
     #[derive(Serialize)]
     struct PnInfo {
@@ -358,3 +418,68 @@ async fn add_pn_white_list(mut req: Request<Server>) -> tide::Result {
         err: err_code.into()
     })?))
 }
+
+
+
+async fn query_usage(mut req: Request<Server>) -> tide::Result {
+    #[derive(Deserialize)]
+    struct UsageReq {
+        #[serde(default, deserialize_with = "option_device_id_deserialize")]
+        device: Option<DeviceId>
+    }
+
+    #[derive(Serialize)]
+    struct UsageInfo {
+        #[serde(serialize_with = "device_id_serialize")]
+        device: DeviceId, 
+        owner: Option<String>, 
+        bytes: u64, 
+        dropped_bytes: u64, 
+        tunnels: usize, 
+        update_time: u64
+    }
+
+    let usage_req: UsageReq = req.body_json().await?;
+    let records = req.state().storage().usage_of(usage_req.device.as_ref())?;
+
+    let resp: Vec<UsageInfo> = records.into_iter().map(|record| {
+        // tunnel数只在内存里
+        let tunnels = req.state().service().usage_of(&record.device).map(|u| u.tunnels).unwrap_or(0);
+        UsageInfo {
+            owner: record.owner.map(|o| o.to_string()), 
+            bytes: record.bytes, 
+            dropped_bytes: record.dropped_bytes, 
+            tunnels, 
+            update_time: record.update_time, 
+            device: record.device
+        }
+    }).collect();
+
+    Ok(Response::from(Body::from_json(&resp)?))
+}
+
+
+
+async fn reset_usage(mut req: Request<Server>) -> tide::Result {
+    #[derive(Deserialize)]
+    struct ResetReq {
+        #[serde(default, deserialize_with = "option_device_id_deserialize")]
+        device: Option<DeviceId>
+    }
+
+    #[derive(Serialize)]
+    struct ResetResp {
+        err: u16, 
+        count: usize
+    }
+
+    let reset_req: ResetReq = req.body_json().await?;
+    // 先清内存里未上报的部分，避免下次上报又写回去
+    req.state().service().reset_usage(reset_req.device.as_ref());
+    let count = req.state().storage().reset_usage(reset_req.device.as_ref())?;
+
+    Ok(Response::from(Body::from_json(&ResetResp {
+        err: BuckyErrorCode::Ok.into(), 
+        count
+    })?))
+}
@@ -1,13 +1,14 @@
 use log::*;
 use std::{
     path::{Path, PathBuf}, 
+    str::FromStr, 
     collections::{BTreeSet}, 
     sync::RwLock
 };
 use async_std::sync::Arc;
 use rusqlite;
 use cyfs_base::*;
-use cyfs_bdt::pn::service::{ProxyDeviceStub, ProxyServiceEvents};
+use cyfs_bdt::pn::service::{ProxyDeviceStub, ProxyServiceEvents, ProxyUsageReport};
 
 pub struct Config {
     pub bandwidth: Vec<(u32, usize/*limit*/)> 
@@ -25,6 +26,16 @@ impl Config {
     }
 }
 
+// 持久化的device中转流量，最多比内存中的实时统计落后一个上报周期
+#[derive(Clone, Debug)]
+pub struct UsageRecord {
+    pub device: DeviceId, 
+    pub owner: Option<ObjectId>, 
+    pub bytes: u64, 
+    pub dropped_bytes: u64, 
+    pub update_time: u64
+}
+
 struct StorageImpl {
     path: PathBuf, 
     config: Config, 
@@ -56,6 +67,13 @@ impl Storage {
             device TEXT UNIQUE NOT NULL PRIMARY KEY,
             bandwidth INTEGER NOT NULL
         );", [])?;
+        let _ = conn.execute("CREATE TABLE IF NOT EXISTS usage (
+            device TEXT UNIQUE NOT NULL PRIMARY KEY,
+            owner TEXT,
+            bytes INTEGER NOT NULL,
+            dropped_bytes INTEGER NOT NULL,
+            update_time INTEGER NOT NULL
+        );", [])?;
         Ok(())
     }
 
@@ -136,6 +154,72 @@ impl Storage {
         Ok(result)
         
     }
+
+    pub fn add_usage(&self, usage: &[ProxyUsageReport]) -> BuckyResult<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let now = bucky_time_now() as i64;
+        for u in usage {
+            let device = u.device.to_string();
+            let owner = u.owner.as_ref().map(|o| o.to_string());
+            let changed = tx.execute(
+                "UPDATE usage SET bytes=bytes+?, dropped_bytes=dropped_bytes+?, owner=IFNULL(?, owner), update_time=? WHERE device=?;", 
+                rusqlite::params![u.bytes as i64, u.dropped_bytes as i64, owner, now, device])?;
+            if changed == 0 {
+                tx.execute(
+                    "INSERT INTO usage (device, owner, bytes, dropped_bytes, update_time) VALUES (?, ?, ?, ?, ?);", 
+                    rusqlite::params![device, owner, u.bytes as i64, u.dropped_bytes as i64, now])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    // device为None时返回所有device
+    pub fn usage_of(&self, device: Option<&DeviceId>) -> BuckyResult<Vec<UsageRecord>> {
+        let conn = self.conn()?;
+        let sql = "SELECT device, owner, bytes, dropped_bytes, update_time FROM usage";
+        let mut stmt;
+        let mut rows = if let Some(device) = device {
+            stmt = conn.prepare(format!("{} WHERE device=?;", sql).as_str())?;
+            stmt.query([device.to_string()])?
+        } else {
+            stmt = conn.prepare(format!("{};", sql).as_str())?;
+            stmt.query([])?
+        };
+
+        let mut result = vec![];
+        while let Some(row) = rows.next()? {
+            let device: String = row.get(0)?;
+            let owner: Option<String> = row.get(1)?;
+            let bytes: i64 = row.get(2)?;
+            let dropped_bytes: i64 = row.get(3)?;
+            let update_time: i64 = row.get(4)?;
+
+            let owner = match owner {
+                Some(owner) => Some(ObjectId::from_str(owner.as_str())?), 
+                None => None
+            };
+            result.push(UsageRecord {
+                device: DeviceId::from_str(device.as_str())?, 
+                owner, 
+                bytes: bytes as u64, 
+                dropped_bytes: dropped_bytes as u64, 
+                update_time: update_time as u64
+            });
+        }
+        Ok(result)
+    }
+
+    pub fn reset_usage(&self, device: Option<&DeviceId>) -> BuckyResult<usize> {
+        let conn = self.conn()?;
+        let count = if let Some(device) = device {
+            conn.execute("DELETE FROM usage WHERE device=?;", [device.to_string()])?
+        } else {
+            conn.execute("DELETE FROM usage;", [])?
+        };
+        Ok(count)
+    }
 }
 
 #[async_trait::async_trait]
@@ -152,4 +236,11 @@ impl ProxyServiceEvents for Storage {
             Err(BuckyError::new(BuckyErrorCode::PermissionDenied, "not allowed"))
         }
     }
+
+    async fn on_usage(&self, usage: &[ProxyUsageReport]) -> BuckyResult<()> {
+        self.add_usage(usage).map_err(|err| {
+            error!("save pn usage failed for {}", err);
+            err
+        })
+    }
 }
@@ -1,8 +1,12 @@
 use async_std::task;
 use clap::{App, Arg};
 use cyfs_base::*;
-use cyfs_bdt::pn::{self, service::ProxyServiceEvents};
+use cyfs_bdt::pn::{
+    self,
+    service::{ProxyLimit, ProxyLimitConfig, ProxyServiceEvents},
+};
 use std::{
+    collections::HashMap,
     io::Read,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::Path,
@@ -13,6 +17,37 @@ mod auth;
 
 const APP_NAME: &str = "pn-miner";
 
+fn parse_limit_arg<T: FromStr>(matches: &clap::ArgMatches, name: &str) -> BuckyResult<Option<T>> {
+    match matches.value_of(name) {
+        Some(v) => T::from_str(v).map(|v| Some(v)).map_err(|_| {
+            let msg = format!("invalid {} {}", name, v);
+            log::error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+        }),
+        None => Ok(None),
+    }
+}
+
+fn load_limit_config(matches: &clap::ArgMatches) -> BuckyResult<ProxyLimitConfig> {
+    let mut owner_keys = HashMap::new();
+    for path in matches.values_of("owner-desc").map(|v| v.collect()).unwrap_or(vec![]) {
+        let (people, _) = People::decode_from_file(Path::new(path), &mut vec![])?;
+        owner_keys.insert(people.desc().calculate_id(), people.desc().public_key().clone());
+    }
+
+    Ok(ProxyLimitConfig {
+        device: ProxyLimit {
+            bandwidth: parse_limit_arg(matches, "device-bandwidth")?,
+            max_tunnels: parse_limit_arg(matches, "device-tunnels")?,
+        },
+        owner: ProxyLimit {
+            bandwidth: parse_limit_arg(matches, "owner-bandwidth")?,
+            max_tunnels: parse_limit_arg(matches, "owner-tunnels")?,
+        },
+        owner_keys,
+    })
+}
+
 fn load_device_info(folder_path: &Path) -> Result<(Device, PrivateKey), BuckyError> {
     let mut file_path = folder_path.to_path_buf();
     file_path.push(APP_NAME.to_owned() + ".desc");
@@ -45,6 +80,37 @@ async fn main() {
                 .value_name("port")
                 .help("auth server port")
                 .default_value("80"),
+        )
+        .arg(
+            Arg::with_name("device-bandwidth")
+                .long("device-bandwidth")
+                .takes_value(true)
+                .help("max proxied bytes per second of each device"),
+        )
+        .arg(
+            Arg::with_name("device-tunnels")
+                .long("device-tunnels")
+                .takes_value(true)
+                .help("max concurrent proxy tunnels of each device"),
+        )
+        .arg(
+            Arg::with_name("owner-bandwidth")
+                .long("owner-bandwidth")
+                .takes_value(true)
+                .help("max proxied bytes per second of devices with same owner, proxy from devices not signed by owners in --owner-desc is refused"),
+        )
+        .arg(
+            Arg::with_name("owner-tunnels")
+                .long("owner-tunnels")
+                .takes_value(true)
+                .help("max concurrent proxy tunnels of devices with same owner, proxy from devices not signed by owners in --owner-desc is refused"),
+        )
+        .arg(
+            Arg::with_name("owner-desc")
+                .long("owner-desc")
+                .takes_value(true)
+                .multiple(true)
+                .help("people desc files of trusted owners, only devices signed by these owners are accounted by owner"),
         );
     cyfs_debug::CyfsLoggerBuilder::new_app(APP_NAME)
         .level("info")
@@ -62,6 +128,13 @@ async fn main() {
     let auth_port = u16::from_str(matches.value_of("port").unwrap())
         .map_err(|err| log::error!("invalid auth port {}", err))
         .unwrap();
+    let limit = match load_limit_config(&matches) {
+        Ok(limit) => limit,
+        Err(err) => {
+            println!("ERROR: load limit config failed {}", err);
+            std::process::exit(1);
+        }
+    };
 
     task::block_on(async {
         let data_folder = ::cyfs_util::get_app_data_dir(APP_NAME);
@@ -104,7 +177,7 @@ async fn main() {
                 local_device.clone(),
                 private_key,
                 vec![(proxy_local, Some(proxy_outer))],
-                None,
+                Some(pn::service::Config::with_limit(limit)),
                 auth_store
                     .clone()
                     .map(|s| Box::new(s) as Box<dyn ProxyServiceEvents>),
@@ -112,9 +185,14 @@ async fn main() {
             .await
             {
                 log::info!("pn-miner auth server listen on {}", auth_port);
-                if auth::interface::listen(auth_port, local_device.desc().device_id(), auth_store)
-                    .await
-                    .is_ok()
+                if auth::interface::listen(
+                    auth_port,
+                    local_device.desc().device_id(),
+                    auth_store,
+                    service.clone(),
+                )
+                .await
+                .is_ok()
                 {
                     loop {
                         let _ = task::sleep(Duration::from_secs(100000)).await;
@@ -1,43 +1,55 @@
 use std::net::{Ipv4Addr, Ipv6Addr};
 use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
 
 use cyfs_base::*;
-use cyfs_bdt::{sn::service::*, ReceiptWithSignature, SnServiceReceipt};
+use cyfs_bdt::sn::service::*;
 
 const APP_NAME: &str = "sn-miner";
 
-struct SnServiceContractServerImpl {}
-
-impl SnServiceContractServerImpl {
-    fn new() -> SnServiceContractServerImpl {
-        SnServiceContractServerImpl {}
-    }
-}
-
-impl SnServiceContractServer for SnServiceContractServerImpl {
-    fn check_receipt(
-        &self,
-        _client_device: &Device,
-        _local_receipt: &SnServiceReceipt,
-        _client_receipt: &Option<ReceiptWithSignature>,
-        _last_request_time: &ReceiptRequestTime,
-    ) -> IsAcceptClient {
-        IsAcceptClient::Accept(false)
-    }
-
-    fn verify_auth(&self, _client_device_id: &DeviceId) -> IsAcceptClient {
-        IsAcceptClient::Accept(false)
-    }
-}
-
 #[async_std::main]
 async fn main() {
     let data_folder = cyfs_util::get_app_data_dir(APP_NAME);
     let default_desc_path = data_folder.join(APP_NAME);
+    let default_ledger_path = data_folder.join("sn-usage.db");
     let matches = clap::App::new(APP_NAME).version(cyfs_base::get_version())
         .arg(clap::Arg::with_name("desc").short("d").long("desc").takes_value(true)
             .default_value(default_desc_path.to_str().unwrap())
-            .help("sn desc/sec files, exclude extension")).get_matches();
+            .help("sn desc/sec files, exclude extension"))
+        .arg(clap::Arg::with_name("allow-device").long("allow-device").takes_value(true).multiple(true)
+            .help("only serve the specified devices and the devices of allowed owners"))
+        .arg(clap::Arg::with_name("allow-owner").long("allow-owner").takes_value(true).multiple(true)
+            .help("people(zone) desc files, only serve the devices signed by these owners and the allowed devices"))
+        .arg(clap::Arg::with_name("ledger").long("ledger")
+            .help("record usage of each client and apply quota"))
+        .arg(clap::Arg::with_name("ledger-file").long("ledger-file").takes_value(true)
+            .default_value(default_ledger_path.to_str().unwrap())
+            .help("sqlite file of usage ledger"))
+        .arg(clap::Arg::with_name("quota-period").long("quota-period").takes_value(true)
+            .help("quota period in seconds, default is 30 days"))
+        .arg(clap::Arg::with_name("quota-ping").long("quota-ping").takes_value(true)
+            .help("max ping count of each client in quota period"))
+        .arg(clap::Arg::with_name("quota-called").long("quota-called").takes_value(true)
+            .help("max called count of each client in quota period"))
+        .arg(clap::Arg::with_name("quota-call").long("quota-call").takes_value(true)
+            .help("max call count of each client in quota period"))
+        .subcommand(clap::SubCommand::with_name("usage").about("inspect and reset usage of clients in ledger")
+            .arg(clap::Arg::with_name("action").required(true).possible_values(&["list", "show", "reset"]))
+            .arg(clap::Arg::with_name("device").help("device id, reset all clients if not set for reset action")))
+        .arg(clap::Arg::with_name("cluster-listen").long("cluster-listen").takes_value(true)
+            .help("tcp address for sn cluster communication, run as standalone sn if not set"))
+        .arg(clap::Arg::with_name("cluster-member").long("cluster-member").takes_value(true).multiple(true)
+            .help("sn cluster member, format as {desc file}@{cluster address}")).get_matches();
+
+    if let ("usage", Some(sub)) = matches.subcommand() {
+        let ledger_file = Path::new(matches.value_of("ledger-file").unwrap());
+        if let Err(e) = run_usage_command(ledger_file, sub) {
+            println!("ERROR: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     match load_device_info(Path::new(matches.value_of("desc").unwrap())) {
         Ok((device, private_key)) => {
@@ -56,11 +68,36 @@ async fn main() {
 
             log::info!("sn-miner load device from {}, id {}", matches.value_of("desc").unwrap(), device.desc().object_id());
 
-            let service = SnService::new(
-                device,
-                private_key,
-                Box::new(SnServiceContractServerImpl::new()),
-            );
+            let contract = match load_contract(&matches) {
+                Ok(contract) => contract,
+                Err(e) => {
+                    println!("ERROR: load contract err {}", e);
+                    std::process::exit(1);
+                }
+            };
+
+            let service = match matches.value_of("cluster-listen") {
+                Some(listen) => {
+                    let members = matches.values_of("cluster-member").map(|v| v.collect()).unwrap_or(vec![]);
+                    match load_cluster_config(listen, &members) {
+                        Ok(config) => SnService::new_with_cluster(
+                            device,
+                            private_key,
+                            contract,
+                            config,
+                        ),
+                        Err(e) => {
+                            println!("ERROR: load cluster config err {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                None => SnService::new(
+                    device,
+                    private_key,
+                    contract,
+                ),
+            };
 
             let _ = service.start().await;
         }
@@ -73,6 +110,119 @@ async fn main() {
     println!("exit.");
 }
 
+fn parse_arg<T: FromStr>(matches: &clap::ArgMatches, name: &str) -> BuckyResult<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    match matches.value_of(name) {
+        Some(v) => v.parse().map(|v| Some(v)).map_err(|e| {
+            BuckyError::new(BuckyErrorCode::InvalidParam, format!("invalid {} {}, {}", name, v, e))
+        }),
+        None => Ok(None),
+    }
+}
+
+fn load_contract(matches: &clap::ArgMatches) -> BuckyResult<Box<dyn SnServiceContractServer + Send + Sync>> {
+    let mut devices = vec![];
+    for id in matches.values_of("allow-device").map(|v| v.collect()).unwrap_or(vec![]) {
+        devices.push(DeviceId::from_str(id)?);
+    }
+    let mut owners = vec![];
+    for path in matches.values_of("allow-owner").map(|v| v.collect()).unwrap_or(vec![]) {
+        let (people, _) = People::decode_from_file(Path::new(path), &mut vec![])?;
+        owners.push(people);
+    }
+
+    let authorizer: Option<Box<dyn SnServiceContractServer + Send + Sync>> = if devices.is_empty() && owners.is_empty() {
+        None
+    } else {
+        let owner_ids: Vec<ObjectId> = owners.iter().map(|people| people.desc().calculate_id()).collect();
+        log::info!("sn-miner serve only allowed clients, devices={:?}, owners={:?}", devices, owner_ids);
+        Some(Box::new(SnAllowListAuthorizer::new(devices, owners)))
+    };
+
+    if !matches.is_present("ledger") {
+        return Ok(authorizer.unwrap_or(Box::new(SnAcceptAllContract)));
+    }
+
+    let mut quota = SnUsageQuota::default();
+    if let Some(period) = parse_arg::<u64>(matches, "quota-period")? {
+        quota.period = Duration::from_secs(period);
+    }
+    quota.max_ping = parse_arg(matches, "quota-ping")?;
+    quota.max_called = parse_arg(matches, "quota-called")?;
+    quota.max_call = parse_arg(matches, "quota-call")?;
+
+    let ledger = SnReceiptLedger::open(Path::new(matches.value_of("ledger-file").unwrap()), quota, authorizer)?;
+    Ok(Box::new(ledger))
+}
+
+fn print_usage(usage: &SnClientUsage) {
+    println!(
+        "{} owner={} period_start={} ping={} called={} call={} update_time={}",
+        usage.device_id,
+        usage.owner.as_ref().map(|o| o.to_string()).unwrap_or("-".to_owned()),
+        usage.period_start,
+        usage.ping_count,
+        usage.called_count,
+        usage.call_peer_count,
+        usage.update_time,
+    );
+}
+
+fn run_usage_command(ledger_file: &Path, matches: &clap::ArgMatches) -> BuckyResult<()> {
+    let ledger = SnReceiptLedger::open(ledger_file, SnUsageQuota::default(), None)?;
+    let device = match matches.value_of("device") {
+        Some(id) => Some(DeviceId::from_str(id)?),
+        None => None,
+    };
+
+    match matches.value_of("action").unwrap() {
+        "list" => {
+            for usage in ledger.list_usage()? {
+                print_usage(&usage);
+            }
+        }
+        "show" => {
+            let device = device.ok_or_else(|| {
+                BuckyError::new(BuckyErrorCode::InvalidParam, "device id is required for show action")
+            })?;
+            match ledger.get_usage(&device)? {
+                Some(usage) => print_usage(&usage),
+                None => println!("{} not found", device),
+            }
+        }
+        "reset" => {
+            let count = ledger.reset_usage(device.as_ref())?;
+            println!("reset {} clients", count);
+        }
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+fn load_cluster_config(listen: &str, members: &[&str]) -> BuckyResult<SnClusterConfig> {
+    let listen = listen.parse().map_err(|e| {
+        BuckyError::new(BuckyErrorCode::InvalidFormat, format!("invalid cluster listen address {}, {}", listen, e))
+    })?;
+
+    let mut list = vec![];
+    for member in members {
+        let (desc_path, addr) = member.split_once('@').ok_or_else(|| {
+            BuckyError::new(BuckyErrorCode::InvalidFormat, format!("invalid cluster member {}", member))
+        })?;
+        let (device, _) = Device::decode_from_file(Path::new(desc_path), &mut vec![])?;
+        let addr = addr.parse().map_err(|e| {
+            BuckyError::new(BuckyErrorCode::InvalidFormat, format!("invalid cluster member address {}, {}", addr, e))
+        })?;
+
+        list.push(SnClusterMember { device, addr });
+    }
+
+    Ok(SnClusterConfig::new(listen, list))
+}
+
 fn load_device_info(folder_path: &Path) -> BuckyResult<(Device, PrivateKey)> {
     let (mut device, _) = Device::decode_from_file(folder_path.with_extension("desc").as_path(), &mut vec![])?;
     let (private_key, _) = PrivateKey::decode_from_file(folder_path.with_extension("sec").as_path(), &mut vec![])?;
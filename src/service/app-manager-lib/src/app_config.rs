@@ -9,8 +9,9 @@ use serde::{Deserialize, Serialize};
 
 /*
 [config]
-sandbox = "default"    // default\no\docker
+sandbox = "default"    // default\no\docker\native
 repo_mode = "local"     // named_data/local
+dependency = "auto"     // auto\refuse
 
 [app]
 include = []
@@ -20,6 +21,7 @@ source = "all"              // all\system\user
 [app.sandbox]
 id1 = "no"
 id2 = "docker"
+id3 = "native"
 */
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -36,14 +38,17 @@ pub struct ManagerConfig {
     #[serde(default)]
     pub sandbox: SandBoxMode,
     #[serde(default)]
-    pub repo_mode: RepoMode
+    pub repo_mode: RepoMode,
+    #[serde(default)]
+    pub dependency: DependencyMode,
 }
 
 impl Default for ManagerConfig {
     fn default() -> Self {
         Self {
             sandbox: SandBoxMode::default(),
-            repo_mode: RepoMode::default()
+            repo_mode: RepoMode::default(),
+            dependency: DependencyMode::default(),
         }
     }
 }
@@ -127,8 +132,16 @@ impl AppManagerConfig {
         self.app.use_docker() || self.config.sandbox == SandBoxMode::Docker
     }
 
+    pub fn app_sandbox_mode(&self, id: &DecAppId) -> SandBoxMode {
+        self.app.sandbox.get(id).cloned().unwrap_or(self.config.sandbox.clone())
+    }
+
     pub fn app_use_docker(&self, id: &DecAppId) -> bool {
-        self.app.sandbox.get(id).map(|s|*s == SandBoxMode::Docker).unwrap_or(self.config.sandbox == SandBoxMode::Docker)
+        self.app_sandbox_mode(id) == SandBoxMode::Docker
+    }
+
+    pub fn app_use_native_sandbox(&self, id: &DecAppId) -> bool {
+        self.app_sandbox_mode(id) == SandBoxMode::Native
     }
 }
 
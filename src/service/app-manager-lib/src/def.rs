@@ -13,7 +13,10 @@ pub enum SandBoxMode {
     No,
 
     // use docker as sandbox
-    Docker
+    Docker,
+
+    // use linux namespaces and seccomp as sandbox, no docker required
+    Native,
 }
 
 #[derive(Clone, Serialize, PartialEq)]
@@ -63,6 +66,9 @@ impl Display for SandBoxMode {
             SandBoxMode::Docker => {
                 f.write_str("docker")
             }
+            SandBoxMode::Native => {
+                f.write_str("native")
+            }
         }
     }
 }
@@ -74,6 +80,7 @@ impl FromStr for SandBoxMode {
         match s {
             "no" => Ok(Self::No),
             "docker" => Ok(Self::Docker),
+            "native" => Ok(Self::Native),
             "default" => Ok(Self::default()),
             v @ _ => {
                 let msg = format!("unknown app manager sandbox mode type: {}", v);
@@ -104,6 +111,58 @@ impl Default for SandBoxMode {
     }
 }
 
+// 安装app时依赖缺失的处理方式
+#[derive(Clone, Serialize, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyMode {
+    // 自动安装缺失或者版本不满足的依赖
+    Auto,
+
+    // 依赖不满足时拒绝安装
+    Refuse,
+}
+
+impl Default for DependencyMode {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+impl Display for DependencyMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DependencyMode::Auto => f.write_str("auto"),
+            DependencyMode::Refuse => f.write_str("refuse"),
+        }
+    }
+}
+
+impl FromStr for DependencyMode {
+    type Err = BuckyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "refuse" => Ok(Self::Refuse),
+            v @ _ => {
+                let msg = format!("unknown app manager dependency mode: {}", v);
+                error!("{}", msg);
+
+                return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DependencyMode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(TStringVisitor::<Self>::new())
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum AppSource {
@@ -0,0 +1,69 @@
+use cyfs_base::*;
+use cyfs_core::DecAppId;
+use log::*;
+use std::path::PathBuf;
+
+// 容器内私钥文件的挂载位置，app通过CYFS_DEC_AUTH_KEY_FILE环境变量找到它
+pub const APP_AUTH_KEY_CONTAINER_PATH: &str = "/opt/cyfs-auth/dec.sec";
+
+// 非docker运行的app注册到协议栈时使用的地址，只用于legacy模式下的ip校验
+pub const LOCAL_APP_IP: &str = "127.0.0.1";
+
+// app安装时分配的dec私钥，公钥注册到协议栈，用以校验app请求携带的凭证
+pub fn get_app_auth_key_path(app_id: &DecAppId) -> PathBuf {
+    cyfs_util::get_cyfs_root_path()
+        .join("etc")
+        .join("app-manager")
+        .join("auth")
+        .join(format!("{}.sec", app_id))
+}
+
+pub fn create_app_auth_key(app_id: &DecAppId) -> BuckyResult<PrivateKey> {
+    let file = get_app_auth_key_path(app_id);
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| {
+            let msg = format!("create app auth key dir failed! dir={}, {}", dir.display(), e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::IoError, msg)
+        })?;
+    }
+
+    let secret = PrivateKey::generate_secp256k1()?;
+    secret.encode_to_file(&file, false)?;
+
+    // 私钥只允许app-manager读取，容器内通过只读挂载访问
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Err(e) = std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o600)) {
+            warn!("set app auth key file permissions failed! file={}, {}", file.display(), e);
+        }
+    }
+
+    info!("create app auth key success, app:{}, file:{}", app_id, file.display());
+    Ok(secret)
+}
+
+// 升级前已经安装的app没有私钥，启动时补充分配
+pub fn load_or_create_app_auth_key(app_id: &DecAppId) -> BuckyResult<PrivateKey> {
+    let file = get_app_auth_key_path(app_id);
+    if file.exists() {
+        match PrivateKey::decode_from_file(&file, &mut vec![]) {
+            Ok((secret, _)) => return Ok(secret),
+            Err(e) => {
+                warn!("load app auth key failed, will recreate, app:{}, err:{}", app_id, e);
+            }
+        }
+    }
+
+    create_app_auth_key(app_id)
+}
+
+pub fn remove_app_auth_key(app_id: &DecAppId) {
+    let file = get_app_auth_key_path(app_id);
+    if file.exists() {
+        if let Err(e) = std::fs::remove_file(&file) {
+            warn!("remove app auth key failed, app:{}, err:{}", app_id, e);
+        }
+    }
+}
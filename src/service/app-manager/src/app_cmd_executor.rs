@@ -1,5 +1,8 @@
+use crate::app_auth_key::*;
 use crate::app_controller::{AppActionResult, AppController};
+use crate::app_dependency::*;
 use crate::app_install_detail::AppInstallDetail;
+use crate::app_resource_monitor::*;
 use crate::docker_api::*;
 use crate::docker_network_manager::{DockerNetworkManager, CYFS_BRIDGE_NAME};
 use crate::non_helper::*;
@@ -20,6 +23,8 @@ pub struct AppCmdExecutor {
     cmd_list: Arc<Mutex<AppCmdList>>,
     non_helper: Arc<NonHelper>,
     config: AppManagerConfig,
+    resource_monitor: Arc<AppResourceMonitor>,
+    dependency_graph: Arc<AppDependencyGraph>,
     is_idle: AtomicBool,
 }
 
@@ -31,6 +36,8 @@ impl AppCmdExecutor {
         cmd_list: Arc<Mutex<AppCmdList>>,
         non_helper: Arc<NonHelper>,
         config: AppManagerConfig,
+        resource_monitor: Arc<AppResourceMonitor>,
+        dependency_graph: Arc<AppDependencyGraph>,
     ) -> Self {
         Self {
             owner,
@@ -41,6 +48,8 @@ impl AppCmdExecutor {
             cmd_list,
             non_helper,
             config,
+            resource_monitor,
+            dependency_graph,
             is_idle: AtomicBool::new(true),
         }
     }
@@ -191,8 +200,11 @@ impl AppCmdExecutor {
             } else {
                 Some(quota.mem)
             },
+            pids_limit: None,
+            net_bandwidth: None,
             ip: None,
             network: None,
+            auth_key_file: None,
         };
 
         let mut sub_err = SubErrorCode::None;
@@ -200,7 +212,25 @@ impl AppCmdExecutor {
         //获取App对应的容器IP
         let use_docker = self.config.app_use_docker(app_id);
         loop {
+            //依赖的app要先于自己运行
+            if let Err(e) = self.check_dependencies(app_id) {
+                sub_err = e;
+                break;
+            }
+
             if use_docker {
+                //合并app声明的配额，获取失败时只使用用户设置的配额
+                let declared_quota = match self.non_helper.get_dec_app(app_id.object_id(), None).await {
+                    Ok(dec_app) => dec_app.quota().cloned(),
+                    Err(e) => {
+                        warn!("get dec app for quota failed, app:{}, err:{}", app_id, e);
+                        None
+                    }
+                };
+                let app_quota = merge_app_quota(declared_quota.as_ref(), &quota);
+                apply_app_quota(&mut run_config, &app_quota, &quota);
+                self.resource_monitor.set_quota(app_id, app_quota);
+
                 info!("app {} use docker register ip", app_id);
                 match self.docker_network_manager.get_valid_app_ip(app_id) {
                     Ok(ip) => {
@@ -212,6 +242,7 @@ impl AppCmdExecutor {
                         }
 
                         run_config.ip = Some(ip);
+                        run_config.auth_key_file = Some(get_app_auth_key_path(app_id));
                     }
                     Err(e) => {
                         error!("assign container ip failed, app:{}, err:{}", app_id, e);
@@ -220,6 +251,15 @@ impl AppCmdExecutor {
                     }
                 }
                 run_config.network = Some(CYFS_BRIDGE_NAME.to_owned());
+            } else {
+                // 直接运行和native sandbox运行的app同样分配私钥，通过本地接口携带凭证访问协议栈
+                if let Err(e) = self.register_app(app_id, LOCAL_APP_IP).await {
+                    error!("register app to stack failed, app:{}, err:{}", app_id, e);
+                    sub_err = SubErrorCode::RegisterAppFailed;
+                    break;
+                }
+
+                run_config.auth_key_file = Some(get_app_auth_key_path(app_id));
             }
 
             if let Err(e) = self.app_controller.start_app(app_id, run_config).await {
@@ -251,6 +291,49 @@ impl AppCmdExecutor {
         Ok(())
     }
 
+    // 检查app依赖的app是否都已安装并且在运行
+    pub(crate) fn check_dependencies(&self, app_id: &DecAppId) -> AppActionResult<()> {
+        let status_list = self.status_list.read().unwrap().clone();
+        for dep in self.dependency_graph.dependencies(app_id) {
+            let status = match status_list.get(&dep.id) {
+                Some(status) => status.lock().unwrap().clone(),
+                None => {
+                    warn!("app dependency not found, app:{}, dep:{}", app_id, dep.id);
+                    return Err(SubErrorCode::DependencyMissing);
+                }
+            };
+
+            let version = match status.version() {
+                Some(version) if is_installed_status(status.status()) => version,
+                _ => {
+                    warn!(
+                        "app dependency not installed, app:{}, dep:{}, status:{}",
+                        app_id, dep.id, status.status()
+                    );
+                    return Err(SubErrorCode::DependencyMissing);
+                }
+            };
+
+            if !dependency_version_matches(version, &dep.version) {
+                warn!(
+                    "app dependency version mismatch, app:{}, dep:{}, req:{}, installed:{}",
+                    app_id, dep.id, dep.version, version
+                );
+                return Err(SubErrorCode::DependencyVersionMismatch);
+            }
+
+            if !is_serving_status(status.status()) {
+                warn!(
+                    "app dependency not running, app:{}, dep:{}, status:{}",
+                    app_id, dep.id, status.status()
+                );
+                return Err(SubErrorCode::DependencyNotRunning);
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) async fn execute_stop(
         &self,
         status: Arc<Mutex<AppLocalStatus>>,
@@ -275,6 +358,13 @@ impl AppCmdExecutor {
             warn!("stop app failed, app:{}, err:{}", app_id, e);
             target_status_code = AppLocalStatusCode::StopFailed;
             sub_err = e;
+        } else {
+            //被资源监控主动停止的app，保留停止原因
+            if let Some(reason) = self.resource_monitor.take_stop_reason(app_id) {
+                sub_err = reason;
+            }
+            self.resource_monitor.remove(app_id);
+            status.lock().unwrap().set_usage(None);
         }
 
         let _ = self
@@ -372,6 +462,11 @@ impl AppCmdExecutor {
             .install_app(app_id, version, &dec_app)
             .await?;
 
+        // 安装时为app分配新的dec私钥，重装会替换掉旧的
+        if let Err(e) = create_app_auth_key(app_id) {
+            warn!("create app auth key failed, app:{}, err:{}", app_id, e);
+        }
+
         // 获取权限配置并且设置到local status
         let permissions = self
             .app_controller
@@ -456,6 +551,10 @@ impl AppCmdExecutor {
             warn!("uninstall app failed, app:{}, err:{}", app_id, e);
             target_status_code = AppLocalStatusCode::UninstallFailed;
             sub_err = e;
+        } else {
+            self.resource_monitor.remove(app_id);
+            status.lock().unwrap().set_usage(None);
+            remove_app_auth_key(app_id);
         }
 
         let _ = self
@@ -484,9 +583,11 @@ impl AppCmdExecutor {
                 app_name = app_id.to_string();
             }
         }
+        let secret = load_or_create_app_auth_key(app_id)?;
         let dec_ip_info = DecIpInfo {
             name: app_name,
             ip: container_ip.to_owned(),
+            public_key: Some(secret.public().to_hex()?),
         };
 
         info!(
@@ -494,11 +595,18 @@ impl AppCmdExecutor {
             app_id, dec_ip_info
         );
 
+        // 没有启用docker时不存在网关，协议栈不会启动网关上的鉴权接口
+        let gateway_ip = if self.config.use_docker() {
+            self.docker_network_manager.gateway_ip()
+        } else {
+            String::new()
+        };
+
         let mut dec_map = HashMap::<String, DecIpInfo>::new();
         dec_map.insert(app_id.to_string(), dec_ip_info);
         let action = AppManagerAction::create_register_dec(
             self.owner.clone(),
-            self.docker_network_manager.gateway_ip(),
+            gateway_ip,
             dec_map,
         );
 
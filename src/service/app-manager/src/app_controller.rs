@@ -284,10 +284,18 @@ impl AppController {
                 SubErrorCode::LoadFailed
             })?;
 
-            dapp.start().map_err(|e| {
-                warn!("start app directly failed, appId: {}, {}", app_id, e);
-                SubErrorCode::CommondFailed
-            })?;
+            if self.config.app_use_native_sandbox(app_id) {
+                info!("run app in native sandbox:{}", app_id);
+                dapp.start_in_sandbox(config.auth_key_file.as_deref()).map_err(|e| {
+                    warn!("start app in native sandbox failed, appId: {}, {}", app_id, e);
+                    SubErrorCode::CommondFailed
+                })?;
+            } else {
+                dapp.start(config.auth_key_file.as_deref()).map_err(|e| {
+                    warn!("start app directly failed, appId: {}, {}", app_id, e);
+                    SubErrorCode::CommondFailed
+                })?;
+            }
 
             self.dapp_instance.write().unwrap().insert(app_id.clone(), dapp);
         }
@@ -467,6 +475,7 @@ impl AppController {
                 },
                 object_id: app_id.clone().into(),
                 inner_path: None,
+                projection: None,
             })
             .await?;
         let dec_app = DecApp::clone_from_slice(&resp.object.object_raw)?;
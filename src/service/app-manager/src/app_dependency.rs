@@ -0,0 +1,460 @@
+use cyfs_base::*;
+use cyfs_core::*;
+use log::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+// 判断已安装的版本是否满足依赖声明的版本范围
+pub(crate) fn dependency_version_matches(version: &str, req: &str) -> bool {
+    let req = match semver::VersionReq::parse(req) {
+        Ok(req) => req,
+        Err(e) => {
+            warn!("invalid dependency version req: {}, {}", req, e);
+            return false;
+        }
+    };
+
+    match semver::Version::parse(&SemVerHelper::fix_semver(version)) {
+        Ok(mut version) => {
+            // 已安装的预览版按正式版本号比较，除非版本范围里显式指定了pre
+            if !version.pre.is_empty() && !req.comparators.iter().any(|c| !c.pre.is_empty()) {
+                version.pre = semver::Prerelease::EMPTY;
+            }
+            req.matches(&version)
+        }
+        Err(e) => {
+            warn!("invalid installed app version: {}, {}", version, e);
+            false
+        }
+    }
+}
+
+// 这些状态下app已经安装或者正在按指定的版本安装，可以满足依赖
+pub(crate) fn is_installed_status(status: AppLocalStatusCode) -> bool {
+    match status {
+        AppLocalStatusCode::Installing
+        | AppLocalStatusCode::NoService
+        | AppLocalStatusCode::Stop
+        | AppLocalStatusCode::Stopping
+        | AppLocalStatusCode::StopFailed
+        | AppLocalStatusCode::Starting
+        | AppLocalStatusCode::StartFailed
+        | AppLocalStatusCode::Running
+        | AppLocalStatusCode::RunException => true,
+        _ => false,
+    }
+}
+
+// 依赖处于这些状态时，被依赖方可以启动
+pub(crate) fn is_serving_status(status: AppLocalStatusCode) -> bool {
+    status == AppLocalStatusCode::Running || status == AppLocalStatusCode::NoService
+}
+
+// 解析过程中得到的依赖关系，解析和安装都成功后才提交到AppDependencyGraph
+pub(crate) type DependencyEdges = HashMap<DecAppId, Vec<DecAppDependency>>;
+
+/* 安装前解析app的依赖，包括间接依赖
+dec_apps是从app开始遍历依赖获取到的DecApp对象，installed是已安装app的版本
+依赖未安装或者已安装的版本不满足时，can_install的依赖选出要安装的版本，否则返回错误
+返回解析得到的依赖关系，和需要安装的依赖和版本，按安装顺序排列；失败时不会修改graph */
+pub(crate) fn resolve_dependency_installs(
+    graph: &AppDependencyGraph,
+    app_id: &DecAppId,
+    dec_apps: &HashMap<DecAppId, DecApp>,
+    installed: &HashMap<DecAppId, String>,
+    can_install: impl Fn(&DecAppId) -> bool,
+) -> Result<(DependencyEdges, Vec<(DecAppId, String)>), SubErrorCode> {
+    let mut edges = DependencyEdges::new();
+    let mut reqs: HashMap<DecAppId, Vec<String>> = HashMap::new();
+    let mut pending = vec![app_id.clone()];
+    while let Some(id) = pending.pop() {
+        if edges.contains_key(&id) {
+            continue;
+        }
+
+        let dec_app = dec_apps.get(&id).ok_or_else(|| {
+            warn!("dec app for dependency not found, app:{}, dep:{}", app_id, id);
+            SubErrorCode::DependencyMissing
+        })?;
+
+        let dependencies = dec_app.dependencies().clone();
+        if let Err(_) = graph.check_cycle_with(&edges, &id, &dependencies) {
+            return Err(SubErrorCode::DependencyCycle);
+        }
+
+        for dep in &dependencies {
+            reqs.entry(dep.id.clone()).or_insert_with(Vec::new).push(dep.version.clone());
+            pending.push(dep.id.clone());
+        }
+        edges.insert(id, dependencies);
+    }
+
+    let mut list = vec![];
+    for id in graph.all_dependencies_with(&edges, app_id) {
+        let dep_reqs = reqs.remove(&id).unwrap_or_default();
+        let installed_version = installed.get(&id);
+
+        let missing_err = match installed_version {
+            Some(version) => {
+                if dep_reqs.iter().all(|req| dependency_version_matches(version, req)) {
+                    continue;
+                }
+                SubErrorCode::DependencyVersionMismatch
+            }
+            None => SubErrorCode::DependencyMissing,
+        };
+
+        if !can_install(&id) {
+            warn!(
+                "app dependency not satisfied, app:{}, dep:{}, req:{:?}, installed:{:?}",
+                app_id, id, dep_reqs, installed_version
+            );
+            return Err(missing_err);
+        }
+
+        let req: Vec<&str> = dep_reqs.iter().map(|s| s.as_str()).filter(|s| *s != "*").collect();
+        let req = if req.is_empty() { "*".to_owned() } else { req.join(", ") };
+        let version = dec_apps
+            .get(&id)
+            .unwrap()
+            .find_version(&req, None)
+            .map_err(|e| {
+                warn!(
+                    "no matching version for app dependency, app:{}, dep:{}, req:{}, err:{}",
+                    app_id, id, req, e
+                );
+                missing_err
+            })?
+            .0
+            .to_owned();
+
+        list.push((id, version));
+    }
+
+    Ok((edges, list))
+}
+
+/*
+app之间的依赖关系，key是app，value是它直接依赖的app
+启动按依赖顺序，被依赖的app先启动；停止则反过来，依赖它的app先停止
+*/
+pub struct AppDependencyGraph {
+    deps: RwLock<HashMap<DecAppId, Vec<DecAppDependency>>>,
+}
+
+impl AppDependencyGraph {
+    pub fn new() -> Self {
+        Self {
+            deps: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn set(&self, app_id: &DecAppId, dependencies: Vec<DecAppDependency>) {
+        if !dependencies.is_empty() {
+            info!(
+                "app dependencies: app:{}, deps:{:?}",
+                app_id, dependencies
+            );
+        }
+        self.deps
+            .write()
+            .unwrap()
+            .insert(app_id.clone(), dependencies);
+    }
+
+    // 提交解析得到的依赖关系
+    pub fn commit(&self, edges: DependencyEdges) {
+        for (app_id, dependencies) in edges {
+            self.set(&app_id, dependencies);
+        }
+    }
+
+    pub fn remove(&self, app_id: &DecAppId) {
+        self.deps.write().unwrap().remove(app_id);
+    }
+
+    pub fn dependencies(&self, app_id: &DecAppId) -> Vec<DecAppDependency> {
+        self.deps
+            .read()
+            .unwrap()
+            .get(app_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // app直接或者间接依赖的所有app，按启动顺序排列
+    pub fn all_dependencies(&self, app_id: &DecAppId) -> Vec<DecAppId> {
+        let deps = self.deps.read().unwrap();
+        Self::all_dependencies_impl(&deps, app_id)
+    }
+
+    // 同all_dependencies，pending里尚未提交的依赖关系优先
+    pub fn all_dependencies_with(&self, pending: &DependencyEdges, app_id: &DecAppId) -> Vec<DecAppId> {
+        let deps = self.merge(pending);
+        Self::all_dependencies_impl(&deps, app_id)
+    }
+
+    fn merge(&self, pending: &DependencyEdges) -> HashMap<DecAppId, Vec<DecAppDependency>> {
+        let mut deps = self.deps.read().unwrap().clone();
+        for (app_id, dependencies) in pending {
+            deps.insert(app_id.clone(), dependencies.clone());
+        }
+        deps
+    }
+
+    fn all_dependencies_impl(
+        deps: &HashMap<DecAppId, Vec<DecAppDependency>>,
+        app_id: &DecAppId,
+    ) -> Vec<DecAppId> {
+        let mut visited = HashSet::new();
+        let mut list = vec![];
+        Self::visit(deps, app_id, &mut visited, &mut list);
+
+        // 去掉自己
+        list.pop();
+        list
+    }
+
+    // 直接或者间接依赖app的所有app，按停止顺序排列
+    pub fn all_dependents(&self, app_id: &DecAppId) -> Vec<DecAppId> {
+        let deps = self.deps.read().unwrap();
+
+        let mut dependents = HashSet::new();
+        let mut pending = vec![app_id.clone()];
+        while let Some(id) = pending.pop() {
+            for (app, list) in deps.iter() {
+                if list.iter().any(|dep| dep.id == id) && dependents.insert(app.clone()) {
+                    pending.push(app.clone());
+                }
+            }
+        }
+        dependents.remove(app_id);
+
+        let mut list = Self::sort_impl(&deps, dependents.into_iter().collect());
+        list.reverse();
+        list
+    }
+
+    // 按依赖排序，被依赖的app排在前面；循环依赖会被截断，不影响其它app的顺序
+    pub fn sort(&self, apps: Vec<DecAppId>) -> Vec<DecAppId> {
+        let deps = self.deps.read().unwrap();
+        Self::sort_impl(&deps, apps)
+    }
+
+    fn sort_impl(
+        deps: &HashMap<DecAppId, Vec<DecAppDependency>>,
+        mut apps: Vec<DecAppId>,
+    ) -> Vec<DecAppId> {
+        // 先按id排序，保证同样的输入得到同样的顺序
+        apps.sort();
+
+        let set: HashSet<DecAppId> = apps.iter().cloned().collect();
+        let mut visited = HashSet::new();
+        let mut list = vec![];
+        for app_id in &apps {
+            Self::visit(deps, app_id, &mut visited, &mut list);
+        }
+
+        list.retain(|id| set.contains(id));
+        list
+    }
+
+    // 深度优先，依赖先于自己放入list，循环依赖在visited处截断
+    fn visit(
+        deps: &HashMap<DecAppId, Vec<DecAppDependency>>,
+        app_id: &DecAppId,
+        visited: &mut HashSet<DecAppId>,
+        list: &mut Vec<DecAppId>,
+    ) {
+        if !visited.insert(app_id.clone()) {
+            return;
+        }
+
+        if let Some(dependencies) = deps.get(app_id) {
+            for dep in dependencies {
+                Self::visit(deps, &dep.id, visited, list);
+            }
+        }
+
+        list.push(app_id.clone());
+    }
+
+    // 检查加入app的依赖后是否会形成循环依赖
+    pub fn check_cycle(&self, app_id: &DecAppId, dependencies: &Vec<DecAppDependency>) -> BuckyResult<()> {
+        let deps = self.deps.read().unwrap();
+        Self::check_cycle_impl(&deps, app_id, dependencies)
+    }
+
+    pub fn check_cycle_with(
+        &self,
+        pending: &DependencyEdges,
+        app_id: &DecAppId,
+        dependencies: &Vec<DecAppDependency>,
+    ) -> BuckyResult<()> {
+        let deps = self.merge(pending);
+        Self::check_cycle_impl(&deps, app_id, dependencies)
+    }
+
+    fn check_cycle_impl(
+        deps: &HashMap<DecAppId, Vec<DecAppDependency>>,
+        app_id: &DecAppId,
+        dependencies: &Vec<DecAppDependency>,
+    ) -> BuckyResult<()> {
+        let mut pending: Vec<DecAppId> = dependencies.iter().map(|dep| dep.id.clone()).collect();
+        let mut visited = HashSet::new();
+        while let Some(id) = pending.pop() {
+            if &id == app_id {
+                let msg = format!("app dependency cycle detected! app:{}", app_id);
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
+            }
+
+            if !visited.insert(id.clone()) {
+                continue;
+            }
+
+            if let Some(list) = deps.get(&id) {
+                pending.extend(list.iter().map(|dep| dep.id.clone()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn app_id(name: &str) -> DecAppId {
+        DecAppId::try_from(DecApp::generate_id(ObjectId::default(), name)).unwrap()
+    }
+
+    fn dep(id: &DecAppId) -> DecAppDependency {
+        DecAppDependency {
+            id: id.clone(),
+            version: "*".to_owned(),
+        }
+    }
+
+    fn dec_app(name: &str, versions: &[&str], deps: &[(&DecAppId, &str)]) -> DecApp {
+        let mut dec_app = DecApp::create(ObjectId::default(), name);
+        for version in versions {
+            dec_app.set_source(version.to_string(), ObjectId::default(), None);
+        }
+        for (id, req) in deps {
+            dec_app.set_dependency((*id).clone(), req.to_string());
+        }
+        dec_app
+    }
+
+    #[test]
+    fn test_version_matches() {
+        assert!(dependency_version_matches("1.2.0.10", "^1.2"));
+        assert!(dependency_version_matches("1.3.0.12", ">=1.2"));
+        assert!(!dependency_version_matches("2.0.0.1", "^1.2"));
+        assert!(!dependency_version_matches("1.1.0.1", "^1.2"));
+        assert!(dependency_version_matches("1.2.1.3-preview", "^1.2"));
+        assert!(!dependency_version_matches("1.2.0.1", "invalid"));
+    }
+
+    #[test]
+    fn test_order() {
+        let im = app_id("im");
+        let chat = app_id("chat");
+        let group = app_id("group");
+        let other = app_id("other");
+
+        let graph = AppDependencyGraph::new();
+        graph.set(&chat, vec![dep(&im)]);
+        graph.set(&group, vec![dep(&chat), dep(&im)]);
+        graph.set(&im, vec![]);
+
+        let list = graph.sort(vec![group.clone(), other.clone(), chat.clone(), im.clone()]);
+        let pos = |id: &DecAppId| list.iter().position(|v| v == id).unwrap();
+        assert_eq!(list.len(), 4);
+        assert!(pos(&im) < pos(&chat));
+        assert!(pos(&chat) < pos(&group));
+
+        assert_eq!(graph.all_dependencies(&group), vec![im.clone(), chat.clone()]);
+        assert_eq!(graph.all_dependents(&im), vec![group.clone(), chat.clone()]);
+        assert!(graph.all_dependents(&other).is_empty());
+
+        graph.check_cycle(&other, &vec![dep(&group)]).unwrap();
+        graph.check_cycle(&im, &vec![dep(&group)]).unwrap_err();
+    }
+
+    #[test]
+    fn test_resolve() {
+        let im = app_id("im");
+        let chat = app_id("chat");
+        let group = app_id("group");
+        let store = app_id("store");
+
+        let mut dec_apps = HashMap::new();
+        dec_apps.insert(store.clone(), dec_app("store", &["1.0.0", "1.1.0"], &[]));
+        dec_apps.insert(im.clone(), dec_app("im", &["1.2.0", "2.0.0"], &[(&store, "^1.0")]));
+        dec_apps.insert(chat.clone(), dec_app("chat", &["1.0.0"], &[(&im, "^1.2")]));
+        dec_apps.insert(group.clone(), dec_app("group", &["1.0.0"], &[(&chat, "*"), (&im, ">=1.0")]));
+
+        let graph = AppDependencyGraph::new();
+
+        // store已安装并满足版本，只需要安装im和chat，依赖先安装
+        let mut installed = HashMap::new();
+        installed.insert(store.clone(), "1.0.0".to_owned());
+        let (edges, list) =
+            resolve_dependency_installs(&graph, &group, &dec_apps, &installed, |_| true).unwrap();
+        assert_eq!(
+            list,
+            vec![(im.clone(), "1.2.0".to_owned()), (chat.clone(), "1.0.0".to_owned())]
+        );
+        assert_eq!(edges.len(), 4);
+
+        // 解析不会修改graph，提交后才生效
+        assert!(graph.all_dependencies(&group).is_empty());
+        graph.commit(edges);
+        assert_eq!(graph.all_dependencies(&group), vec![store.clone(), im.clone(), chat.clone()]);
+        assert_eq!(graph.all_dependents(&store), vec![group.clone(), chat.clone(), im.clone()]);
+
+        // 不允许安装依赖时返回错误
+        let ret = resolve_dependency_installs(&graph, &group, &dec_apps, &installed, |id| id != &im);
+        assert_eq!(ret.unwrap_err(), SubErrorCode::DependencyMissing);
+
+        // 已安装的版本不满足
+        installed.insert(im.clone(), "2.0.0".to_owned());
+        let ret = resolve_dependency_installs(&graph, &chat, &dec_apps, &installed, |_| false);
+        assert_eq!(ret.unwrap_err(), SubErrorCode::DependencyVersionMismatch);
+    }
+
+    #[test]
+    fn test_resolve_failed_not_commit() {
+        let a = app_id("a");
+        let b = app_id("b");
+        let c = app_id("c");
+        let d = app_id("d");
+
+        // 依赖链更深处的循环依赖
+        let mut dec_apps = HashMap::new();
+        dec_apps.insert(a.clone(), dec_app("a", &["1.0.0"], &[(&b, "*")]));
+        dec_apps.insert(b.clone(), dec_app("b", &["1.0.0"], &[(&c, "*")]));
+        dec_apps.insert(c.clone(), dec_app("c", &["1.0.0"], &[(&b, "*")]));
+
+        let graph = AppDependencyGraph::new();
+        let installed = HashMap::new();
+        let ret = resolve_dependency_installs(&graph, &a, &dec_apps, &installed, |_| true);
+        assert_eq!(ret.unwrap_err(), SubErrorCode::DependencyCycle);
+        assert!(graph.all_dependencies(&a).is_empty());
+        assert!(graph.all_dependents(&b).is_empty());
+
+        // 缺少DecApp对象，或者没有满足的版本
+        dec_apps.insert(c.clone(), dec_app("c", &["1.0.0"], &[(&d, "*")]));
+        let ret = resolve_dependency_installs(&graph, &a, &dec_apps, &installed, |_| true);
+        assert_eq!(ret.unwrap_err(), SubErrorCode::DependencyMissing);
+
+        dec_apps.insert(d.clone(), dec_app("d", &["1.0.0"], &[]));
+        dec_apps.insert(c.clone(), dec_app("c", &["1.0.0"], &[(&d, "^2.0")]));
+        let ret = resolve_dependency_installs(&graph, &a, &dec_apps, &installed, |_| true);
+        assert_eq!(ret.unwrap_err(), SubErrorCode::DependencyMissing);
+        assert!(graph.all_dependents(&d).is_empty());
+    }
+}
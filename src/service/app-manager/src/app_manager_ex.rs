@@ -1,6 +1,8 @@
 use crate::app_cmd_executor::AppCmdExecutor;
 use crate::app_controller::AppController;
+use crate::app_dependency::*;
 use crate::app_install_detail::AppInstallDetail;
+use crate::app_resource_monitor::*;
 use crate::event_handler::EventListener;
 use crate::non_helper::*;
 use async_std::channel::{Receiver, Sender};
@@ -13,7 +15,7 @@ use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
 use version_compare::Version;
-use app_manager_lib::{AppManagerConfig, AppSource, RepoMode};
+use app_manager_lib::{AppManagerConfig, AppSource, DependencyMode, RepoMode};
 use cyfs_util::get_cyfs_root_path;
 use crate::docker_api::DockerApi;
 
@@ -52,6 +54,8 @@ pub struct AppManager {
     non_helper: Arc<NonHelper>,
     config: AppManagerConfig,
     start_couter: Arc<RwLock<HashMap<DecAppId, u8>>>,
+    resource_monitor: Arc<AppResourceMonitor>,
+    dependency_graph: Arc<AppDependencyGraph>,
 }
 
 impl AppManager {
@@ -78,6 +82,8 @@ impl AppManager {
             non_helper: Arc::new(NonHelper::new(owner, shared_stack)),
             config,
             start_couter: Arc::new(RwLock::new(HashMap::new())),
+            resource_monitor: Arc::new(AppResourceMonitor::new()),
+            dependency_graph: Arc::new(AppDependencyGraph::new()),
         }
     }
 
@@ -109,12 +115,19 @@ impl AppManager {
             self.status_list.clone(),
             cmd_list,
             self.non_helper.clone(),
-            self.config.clone()
+            self.config.clone(),
+            self.resource_monitor.clone(),
+            self.dependency_graph.clone(),
         ));
 
         self.cmd_executor.as_ref().unwrap().init()
     }
 
+    // app资源事件：oom，cpu限流，磁盘超额
+    pub fn resource_event(&self) -> &AppResourceEventManager {
+        self.resource_monitor.event()
+    }
+
     pub async fn start(manager: Arc<AppManager>) {
         if manager.config.use_docker() {
             info!("check dec app base docker image");
@@ -232,7 +245,7 @@ impl AppManager {
                 Some(status) => match cmd_code {
                     CmdCode::Remove => self.on_remove_cmd(app_id, status.clone()).await,
                     CmdCode::Install(_) | CmdCode::Uninstall | CmdCode::Start | CmdCode::Stop => {
-                        self.on_common_cmd(app_id, status.clone(), cmd.clone(), from_user)
+                        self.on_common_cmd_with_dependencies(app_id, status.clone(), cmd.clone(), from_user)
                             .await
                     }
                     CmdCode::SetPermission(_) => {
@@ -384,9 +397,16 @@ impl AppManager {
     async fn check_app_status_on_startup(&self) {
         info!("######[START] check app status on startup!");
         self.fix_status_on_startup().await;
+        self.load_app_dependencies().await;
 
+        //按依赖顺序处理，被依赖的app先安装和启动
         let status_list = self.status_list.read().unwrap().clone();
-        for (app_id, status) in status_list {
+        let app_list = self
+            .dependency_graph
+            .sort(status_list.keys().cloned().collect());
+        let mut restart_list = vec![];
+        for app_id in app_list {
+            let status = status_list.get(&app_id).unwrap().clone();
             let status_code = status.lock().unwrap().status();
             info!("### app:{}, status should be: {}", app_id, status_code);
 
@@ -450,7 +470,7 @@ impl AppManager {
                             "### [STARTUP CEHCK] app need restart, app:{}, status:{}",
                             app_id, status_code
                         );
-                        let _ = self.restart_app(&app_id, status.clone()).await;
+                        restart_list.push((app_id.clone(), status.clone()));
                     }
                 } else {
                     unreachable!();
@@ -458,6 +478,11 @@ impl AppManager {
             }
         }
 
+        //重启命令插在队列头部，倒序插入才能保证依赖顺序
+        for (app_id, status) in restart_list.into_iter().rev() {
+            let _ = self.restart_app(&app_id, status).await;
+        }
+
         info!("######[END] check app status on startup!");
     }
 
@@ -477,9 +502,72 @@ impl AppManager {
             if status_code == AppLocalStatusCode::Running {
                 //进入running说明启动成功，检查一下服务在不在，不在的话算运行异常
                 //这里考虑重启ood以后，已经处于running状态的app，如果正在运行，要重启一次，如果没运行，要拉起一次
+                //先采样资源使用，容器被重新拉起后就无法判断是否被oom kill了
+                //超出磁盘配额被停止的app不再检查运行状态
+                if self.config.app_use_docker(&app_id) {
+                    if self.update_app_usage(&app_id, status.clone()).await {
+                        continue;
+                    }
+                }
                 self.check_running_app(&app_id, status.clone()).await;
                 continue;
             }
+
+            //因为依赖没有运行而启动失败的app，在依赖恢复后重新启动
+            let sub_error = status.lock().unwrap().sub_error();
+            if status_code == AppLocalStatusCode::StartFailed
+                && sub_error == SubErrorCode::DependencyNotRunning
+            {
+                if self.cmd_executor.as_ref().unwrap().check_dependencies(&app_id).is_ok() {
+                    info!("###[STATUS CHECK] app dependencies are running now, will start it, app:{}", app_id);
+                    let _ = self.restart_app(&app_id, status.clone()).await;
+                }
+            }
+        }
+    }
+
+    //采样app的资源使用，超出磁盘配额时停止app，返回app是否因此被停止
+    async fn update_app_usage(&self, app_id: &DecAppId, status: Arc<Mutex<AppLocalStatus>>) -> bool {
+        let usage = match self.resource_monitor.sample(app_id).await {
+            Ok(usage) => usage,
+            Err(e) => {
+                warn!("[USAGE CHECK] sample app resource usage failed, app:{}, err:{}", app_id, e);
+                return false;
+            }
+        };
+
+        let disk_quota_exceeded = self.resource_monitor.is_disk_quota_exceeded(app_id, &usage);
+
+        let status_clone;
+        {
+            let mut status = status.lock().unwrap();
+            if status.status() != AppLocalStatusCode::Running {
+                return false;
+            }
+            if usage.oom_killed {
+                info!("[USAGE CHECK] app is oom killed, app:{}", app_id);
+                status.set_sub_error(SubErrorCode::OutOfMemory);
+            }
+            status.set_usage(Some(usage));
+            status_clone = status.clone();
+        }
+
+        let _ = self.non_helper.put_local_status(&status_clone).await;
+
+        if !disk_quota_exceeded {
+            return false;
+        }
+
+        warn!("[USAGE CHECK] app disk usage exceeds quota, will stop it, app:{}", app_id);
+        self.resource_monitor.set_stop_reason(app_id, SubErrorCode::DiskQuotaExceeded);
+        let stop_cmd = AppCmd::stop(self.owner.clone(), app_id.clone());
+        match self.on_common_cmd_with_dependencies(app_id, status, stop_cmd, false).await {
+            Ok(_) => true,
+            Err(e) => {
+                error!("[USAGE CHECK] stop app for disk quota failed, app:{}, err:{}", app_id, e);
+                self.resource_monitor.take_stop_reason(app_id);
+                false
+            }
         }
     }
 
@@ -727,12 +815,239 @@ impl AppManager {
         let _ = self.non_helper.put_app_local_list(&app_list).await;
 
         self.status_list.write().unwrap().remove(&app_id);
+        self.dependency_graph.remove(app_id);
 
         info!("app removed. {}", app_id);
 
         Ok(())
     }
 
+    //先处理依赖：安装和启动前准备好依赖的app，停止和卸载前先停止依赖它的app
+    //依赖的命令先于自己进入cmdlist，保证执行顺序
+    async fn on_common_cmd_with_dependencies(
+        &self,
+        app_id: &DecAppId,
+        status: Arc<Mutex<AppLocalStatus>>,
+        cmd: AppCmd,
+        from_user: bool,
+    ) -> BuckyResult<()> {
+        match cmd.cmd() {
+            CmdCode::Install(install) => {
+                let edges = self
+                    .install_dependencies(app_id, status.clone(), install.run_after_install)
+                    .await?;
+
+                // app的安装命令成功后才提交依赖关系
+                let ret = self.on_common_cmd(app_id, status, cmd, from_user).await;
+                if ret.is_ok() {
+                    self.dependency_graph.commit(edges);
+                }
+                return ret;
+            }
+            CmdCode::Start => {
+                self.start_dependencies(app_id, &DependencyEdges::new()).await;
+            }
+            CmdCode::Uninstall | CmdCode::Stop => {
+                self.stop_dependents(app_id).await;
+            }
+            _ => {}
+        }
+
+        self.on_common_cmd(app_id, status, cmd, from_user).await
+    }
+
+    // 从DecApp对象加载已添加app的依赖，获取失败的app认为没有依赖
+    async fn load_app_dependencies(&self) {
+        let app_list: Vec<DecAppId> = self.status_list.read().unwrap().keys().cloned().collect();
+        for app_id in app_list {
+            match self.non_helper.get_dec_app(app_id.object_id(), None).await {
+                Ok(dec_app) => {
+                    self.dependency_graph
+                        .set(&app_id, dec_app.dependencies().clone());
+                }
+                Err(e) => {
+                    warn!("get dec app for dependencies failed, app:{}, err:{}", app_id, e);
+                }
+            }
+        }
+    }
+
+    /* 安装前解析app的依赖，包括间接依赖
+    依赖未安装或者已安装的版本不满足时，auto模式下选出要安装的版本，refuse模式下返回错误
+    返回解析得到的依赖关系，和需要安装的依赖和版本，按安装顺序排列
+    依赖关系不会在这里写入dependency_graph，由调用方在安装成功后提交 */
+    async fn resolve_dependencies(
+        &self,
+        app_id: &DecAppId,
+    ) -> Result<(DependencyEdges, Vec<(DecAppId, String)>), SubErrorCode> {
+        let mut dec_apps: HashMap<DecAppId, DecApp> = HashMap::new();
+        let mut pending = vec![app_id.clone()];
+        while let Some(id) = pending.pop() {
+            if dec_apps.contains_key(&id) {
+                continue;
+            }
+
+            let dec_app = match self.non_helper.get_dec_app(id.object_id(), None).await {
+                Ok(dec_app) => dec_app,
+                Err(e) if &id == app_id => {
+                    // app本身获取失败，交给安装流程处理
+                    warn!("get dec app failed, skip resolve dependencies, app:{}, err:{}", id, e);
+                    return Ok((DependencyEdges::new(), vec![]));
+                }
+                Err(e) => {
+                    warn!("get dec app for dependency failed, app:{}, dep:{}, err:{}", app_id, id, e);
+                    return Err(SubErrorCode::DependencyMissing);
+                }
+            };
+
+            pending.extend(dec_app.dependencies().iter().map(|dep| dep.id.clone()));
+            dec_apps.insert(id, dec_app);
+        }
+
+        let installed: HashMap<DecAppId, String> = self
+            .status_list
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|(id, status)| {
+                let status = status.lock().unwrap();
+                if is_installed_status(status.status()) {
+                    status.version().map(|v| (id.clone(), v.to_owned()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let refuse = self.config.config.dependency == DependencyMode::Refuse;
+        resolve_dependency_installs(
+            &self.dependency_graph,
+            app_id,
+            &dec_apps,
+            &installed,
+            |id| !refuse && !self.config.app.exclude.contains(id),
+        )
+    }
+
+    // 安装app的依赖，返回尚未提交的依赖关系，app的安装命令成功后再提交
+    async fn install_dependencies(
+        &self,
+        app_id: &DecAppId,
+        status: Arc<Mutex<AppLocalStatus>>,
+        run_after_install: bool,
+    ) -> BuckyResult<DependencyEdges> {
+        let ret = match self.resolve_dependencies(app_id).await {
+            Ok((mut edges, list)) => {
+                let mut ret = Ok(());
+                for (dep, version) in list {
+                    info!(
+                        "will install app dependency, app:{}, dep:{}, ver:{}",
+                        app_id, dep, version
+                    );
+                    if let Err(e) = self.install_dependency(&dep, &version, run_after_install).await {
+                        warn!("install app dependency failed, app:{}, dep:{}, err:{}", app_id, dep, e);
+                        ret = Err(SubErrorCode::DependencyMissing);
+                        break;
+                    }
+
+                    // 依赖安装成功，提交它自己的依赖关系
+                    if let Some(deps) = edges.remove(&dep) {
+                        self.dependency_graph.set(&dep, deps);
+                    }
+                }
+                ret.map(|_| edges)
+            }
+            Err(e) => Err(e),
+        };
+
+        let edges = match ret {
+            Ok(edges) => edges,
+            Err(sub_err) => {
+                let status_clone = {
+                    let mut status = status.lock().unwrap();
+                    let status_code = status.status();
+                    if status_code == AppLocalStatusCode::Init
+                        || status_code == AppLocalStatusCode::Uninstalled
+                    {
+                        status.set_status(AppLocalStatusCode::InstallFailed);
+                    }
+                    status.set_sub_error(sub_err);
+                    status.clone()
+                };
+                let _ = self.non_helper.put_local_status(&status_clone).await;
+
+                let code = match sub_err {
+                    SubErrorCode::DependencyMissing => BuckyErrorCode::NotFound,
+                    SubErrorCode::DependencyVersionMismatch => BuckyErrorCode::NotMatch,
+                    _ => BuckyErrorCode::InvalidData,
+                };
+                let msg = format!("app dependencies not satisfied, app:{}, err:{}", app_id, sub_err);
+                warn!("{}", msg);
+                return Err(BuckyError::new(code, msg));
+            }
+        };
+
+        if run_after_install {
+            self.start_dependencies(app_id, &edges).await;
+        }
+
+        Ok(edges)
+    }
+
+    async fn install_dependency(
+        &self,
+        dep: &DecAppId,
+        version: &str,
+        run_after_install: bool,
+    ) -> BuckyResult<()> {
+        if let Err(e) = self.on_add_cmd(dep).await {
+            if e.code() != BuckyErrorCode::AlreadyExists {
+                return Err(e);
+            }
+        }
+
+        let status = self.status_list.read().unwrap().get(dep).cloned().unwrap();
+        let install_cmd =
+            AppCmd::install(self.owner.clone(), dep.clone(), version, run_after_install);
+        self.on_common_cmd(dep, status, install_cmd, false).await
+    }
+
+    // 按依赖顺序启动已安装但没有运行的依赖，pending是尚未提交的依赖关系
+    async fn start_dependencies(&self, app_id: &DecAppId, pending: &DependencyEdges) {
+        let status_list = self.status_list.read().unwrap().clone();
+        for dep in self.dependency_graph.all_dependencies_with(pending, app_id) {
+            if let Some(status) = status_list.get(&dep) {
+                let status_code = status.lock().unwrap().status();
+                if AppCmdExecutor::is_valid_pre_status(&CmdCode::Start, status_code) {
+                    info!(
+                        "will start app dependency, app:{}, dep:{}, status:{}",
+                        app_id, dep, status_code
+                    );
+                    let start_cmd = AppCmd::start(self.owner.clone(), dep.clone());
+                    let _ = self.on_common_cmd(&dep, status.clone(), start_cmd, false).await;
+                }
+            }
+        }
+    }
+
+    // 停止依赖这个app的其它app，依赖链最外层的先停止
+    async fn stop_dependents(&self, app_id: &DecAppId) {
+        let status_list = self.status_list.read().unwrap().clone();
+        for dependent in self.dependency_graph.all_dependents(app_id) {
+            if let Some(status) = status_list.get(&dependent) {
+                let status_code = status.lock().unwrap().status();
+                if AppCmdExecutor::is_valid_pre_status(&CmdCode::Stop, status_code) {
+                    info!(
+                        "will stop app dependent, app:{}, dependent:{}, status:{}",
+                        app_id, dependent, status_code
+                    );
+                    let stop_cmd = AppCmd::stop(self.owner.clone(), dependent.clone());
+                    let _ = self.on_common_cmd(&dependent, status.clone(), stop_cmd, false).await;
+                }
+            }
+        }
+    }
+
     //接收到用户发起的通用的cmd，包括install，uninstall，start，stop。
     //from_user表示是否是来自用户的操作，如果是用户操作的start和install，需要重置retry_counter
     async fn on_common_cmd(
@@ -0,0 +1,187 @@
+use crate::docker_api::RunConfig;
+use crate::docker_stats::*;
+use cyfs_base::*;
+use cyfs_core::*;
+use cyfs_util::*;
+use log::*;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AppResourceEventType {
+    // 容器内进程因为超出内存配额被杀掉
+    OomKilled,
+    // 采样周期内cpu被限流
+    Throttled,
+    // 数据目录超出磁盘配额
+    DiskQuotaExceeded,
+}
+
+#[derive(Clone, Debug)]
+pub struct AppResourceEvent {
+    pub app_id: DecAppId,
+    pub event_type: AppResourceEventType,
+    pub usage: AppResourceUsage,
+}
+
+pub type FnAppResourceEvent = dyn EventListenerSyncRoutine<AppResourceEvent, ()>;
+pub type AppResourceEventManager = SyncEventManagerSync<AppResourceEvent, ()>;
+
+// 合并app声明的配额和用户设置的配额，用户设置的非零值优先
+// AppQuota.cpu是相对权重，和DecAppQuota.cpu的绝对限制分开处理
+pub(crate) fn merge_app_quota(declared: Option<&DecAppQuota>, user: &AppQuota) -> DecAppQuota {
+    let mut quota = declared.cloned().unwrap_or_default();
+    if user.mem > 0 {
+        quota.mem = Some(user.mem as u64);
+    }
+    if user.disk_space > 0 {
+        quota.disk_space = Some(user.disk_space as u64);
+    }
+
+    quota
+}
+
+pub(crate) fn apply_app_quota(config: &mut RunConfig, quota: &DecAppQuota, user: &AppQuota) {
+    config.cpu_core = quota.cpu.map(|v| v as f64 / 100.0);
+    config.cpu_shares = if user.cpu == 0 { None } else { Some(user.cpu) };
+    config.memory = quota.mem.map(|v| v as i64);
+    config.pids_limit = quota.pids.map(|v| v as i64);
+    config.net_bandwidth = quota.net_bandwidth;
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+struct AppResourceState {
+    quota: DecAppQuota,
+    usage: Option<AppResourceUsage>,
+}
+
+/*
+定期采样docker app的资源使用情况
+oom/cpu限流/磁盘超额只在状态变化时触发一次事件，避免每个周期重复通知
+*/
+pub struct AppResourceMonitor {
+    client: DockerStatsClient,
+    apps: Mutex<HashMap<DecAppId, AppResourceState>>,
+    // 因为超出配额被主动停止的app，停止完成后作为sub_error
+    stop_reasons: Mutex<HashMap<DecAppId, SubErrorCode>>,
+    event: AppResourceEventManager,
+}
+
+impl AppResourceMonitor {
+    pub fn new() -> Self {
+        Self {
+            client: DockerStatsClient::new(DockerEndpoint::from_env()),
+            apps: Mutex::new(HashMap::new()),
+            stop_reasons: Mutex::new(HashMap::new()),
+            event: AppResourceEventManager::new(),
+        }
+    }
+
+    pub fn event(&self) -> &AppResourceEventManager {
+        &self.event
+    }
+
+    // app启动时记录生效的配额，每次启动都会重置上一次的采样
+    pub fn set_quota(&self, app_id: &DecAppId, quota: DecAppQuota) {
+        info!("app resource quota: app:{}, quota:{:?}", app_id, quota);
+        self.apps.lock().unwrap().insert(
+            app_id.clone(),
+            AppResourceState { quota, usage: None },
+        );
+    }
+
+    pub fn remove(&self, app_id: &DecAppId) {
+        self.apps.lock().unwrap().remove(app_id);
+    }
+
+    // 每次采样都检查，超额的app重新启动后也会被再次停止
+    pub fn is_disk_quota_exceeded(&self, app_id: &DecAppId, usage: &AppResourceUsage) -> bool {
+        let apps = self.apps.lock().unwrap();
+        match apps.get(app_id).and_then(|state| state.quota.disk_space) {
+            Some(disk_space) => usage.disk_usage > disk_space * 1024 * 1024,
+            None => false,
+        }
+    }
+
+    pub fn set_stop_reason(&self, app_id: &DecAppId, reason: SubErrorCode) {
+        self.stop_reasons.lock().unwrap().insert(app_id.clone(), reason);
+    }
+
+    pub fn take_stop_reason(&self, app_id: &DecAppId) -> Option<SubErrorCode> {
+        self.stop_reasons.lock().unwrap().remove(app_id)
+    }
+
+    pub async fn sample(&self, app_id: &DecAppId) -> BuckyResult<AppResourceUsage> {
+        let id = app_id.to_string();
+        let container_name = format!("decapp-{}", id.to_lowercase());
+
+        let state = self.client.container_state(&container_name).await?;
+        let stats = if state.running {
+            self.client.container_stats(&container_name).await?
+        } else {
+            ContainerStats::default()
+        };
+
+        let data_dir = get_app_data_dir(&id);
+        let disk_usage = async_std::task::spawn_blocking(move || dir_size(&data_dir)).await;
+
+        let usage = stats.to_usage(disk_usage, state.oom_killed);
+        debug!("app resource usage: app:{}, usage:{:?}", app_id, usage);
+
+        self.check_usage(app_id, &usage);
+
+        Ok(usage)
+    }
+
+    fn check_usage(&self, app_id: &DecAppId, usage: &AppResourceUsage) {
+        let mut events = vec![];
+        {
+            let mut apps = self.apps.lock().unwrap();
+            let state = apps
+                .entry(app_id.clone())
+                .or_insert_with(|| AppResourceState {
+                    quota: DecAppQuota::default(),
+                    usage: None,
+                });
+
+            let last = state.usage.take().unwrap_or_default();
+            if usage.oom_killed && !last.oom_killed {
+                events.push(AppResourceEventType::OomKilled);
+            }
+            if usage.throttled && !last.throttled {
+                events.push(AppResourceEventType::Throttled);
+            }
+            if let Some(disk_space) = state.quota.disk_space {
+                let limit = disk_space * 1024 * 1024;
+                if usage.disk_usage > limit && last.disk_usage <= limit {
+                    events.push(AppResourceEventType::DiskQuotaExceeded);
+                }
+            }
+
+            state.usage = Some(usage.clone());
+        }
+
+        for event_type in events {
+            warn!(
+                "app resource event: app:{}, event:{:?}, usage:{:?}",
+                app_id, event_type, usage
+            );
+            let event = AppResourceEvent {
+                app_id: app_id.clone(),
+                event_type,
+                usage: usage.clone(),
+            };
+            let _ = self.event.emit(&event);
+        }
+    }
+}
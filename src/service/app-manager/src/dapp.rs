@@ -12,6 +12,7 @@ use std::sync::Mutex;
 use std::time::Duration;
 use wait_timeout::ChildExt;
 use crate::process_util::{run, try_stop_process_by_pid};
+use crate::native_sandbox::NativeSandbox;
 
 const STATUS_CMD_TIME_OUT_IN_SECS: u64 = 15;
 const STOP_CMD_TIME_OUT_IN_SECS: u64 = 60;
@@ -34,6 +35,8 @@ pub struct DApp {
     info: DAppInfo,
     work_dir: PathBuf,
     process: Mutex<Option<Child>>,
+    // native sandbox模式下持有协议栈转发
+    sandbox: Mutex<Option<NativeSandbox>>,
 }
 
 fn get_str(value: &Value, key: &str) -> BuckyResult<String> {
@@ -131,6 +134,7 @@ impl DApp {
             info: app_info,
             work_dir: path.clone(),
             process: Mutex::new(None),
+            sandbox: Mutex::new(None),
         })
     }
 
@@ -201,9 +205,11 @@ impl DApp {
             .join(format!("app_manager_app_{}", self.dec_id))
     }
 
-    pub fn start(&self) -> BuckyResult<bool> {
+    // auth_key_file为app-manager分配给app的dec私钥，通过环境变量告知app
+    pub fn start(&self, auth_key_file: Option<&Path>) -> BuckyResult<bool> {
         if !self.status()? {
-            let child = run(&self.info.start, &self.work_dir, true, None, Some(self.get_pid_file_path().as_path()))?;
+            let envs = Self::auth_key_envs(auth_key_file);
+            let child = run(&self.info.start, &self.work_dir, true, None, Some(self.get_pid_file_path().as_path()), &envs)?;
             *self.process.lock().unwrap() = Some(child);
             info!(
                 "start app:{} {} success!",
@@ -215,6 +221,38 @@ impl DApp {
         Ok(false)
     }
 
+    // 在native sandbox里启动app，停止和状态检查与直接运行的方式一致
+    pub fn start_in_sandbox(&self, auth_key_file: Option<&Path>) -> BuckyResult<bool> {
+        if !self.status()? {
+            let (sandbox, child) = NativeSandbox::spawn(
+                &self.dec_id,
+                &self.info.start,
+                &self.work_dir,
+                Some(self.get_pid_file_path().as_path()),
+                auth_key_file,
+            )?;
+            *self.process.lock().unwrap() = Some(child);
+            *self.sandbox.lock().unwrap() = Some(sandbox);
+            info!(
+                "start app in sandbox:{} {} success!",
+                self.dec_id, self.info.id
+            );
+
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn auth_key_envs(auth_key_file: Option<&Path>) -> Vec<(&'static str, String)> {
+        match auth_key_file {
+            Some(file) => vec![(
+                cyfs_lib::CYFS_DEC_AUTH_KEY_FILE_ENV,
+                file.to_string_lossy().to_string(),
+            )],
+            None => vec![],
+        }
+    }
+
     //time_out == 0 wait forever
     fn run_cmd(
         &self,
@@ -224,7 +262,7 @@ impl DApp {
         time_out: u64,
         record_pid: Option<&Path>
     ) -> BuckyResult<i32> {
-        let mut process = run(cmd, &self.work_dir, detach, stdout, record_pid)?;
+        let mut process = run(cmd, &self.work_dir, detach, stdout, record_pid, &[])?;
 
         let app_id = self.info.id.as_str();
 
@@ -336,6 +374,9 @@ impl DApp {
     }
 
     pub fn stop(&self) -> BuckyResult<bool> {
+        // 停止沙箱的协议栈转发
+        let _ = self.sandbox.lock().unwrap().take();
+
         match self.status() {
             Err(e) => {
                 warn!("check app status failed, app:{}, err:{}", &self.info.id, e);
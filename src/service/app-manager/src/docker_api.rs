@@ -5,6 +5,7 @@ use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, ExitStatus, Stdio};
 use std::time::Duration;
+use crate::app_auth_key::APP_AUTH_KEY_CONTAINER_PATH;
 use crate::docker_network_manager::*;
 use itertools::Itertools;
 use wait_timeout::ChildExt;
@@ -18,9 +19,16 @@ pub struct RunConfig {
     pub cpu_shares: Option<i64>,
     // 单位 MiB:  ->  *1048576   = xxx bytes
     pub memory: Option<i64>,
+    // 最大进程/线程数
+    pub pids_limit: Option<i64>,
+    // 出口带宽限制，单位kbit/s，容器启动后在host侧的veth上通过tc设置，容器内无法修改
+    pub net_bandwidth: Option<u64>,
 
     pub network: Option<String>,
     pub ip: Option<String>,
+
+    // app的dec私钥文件，只读挂载到容器内
+    pub auth_key_file: Option<PathBuf>,
 }
 
 impl Default for RunConfig {
@@ -29,8 +37,11 @@ impl Default for RunConfig {
             cpu_core: None,
             cpu_shares: Some(100),
             memory: Some(1024),
+            pids_limit: None,
+            net_bandwidth: None,
             network: None,
             ip: None,
+            auth_key_file: None,
         }
     }
 }
@@ -132,6 +143,58 @@ fn add_bind_volume<P: AsRef<Path>, Q: AsRef<Path>>(args: &mut Vec<String>, sourc
     args.push(mounts)
 }
 
+fn run_tc(args: &[&str]) -> BuckyResult<()> {
+    let output = Command::new("tc").args(args).output().map_err(|e| {
+        let msg = format!("spawn cmd: tc {} err {}", args.join(" "), e);
+        error!("{}", msg);
+        BuckyError::new(BuckyErrorCode::Failed, msg)
+    })?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        let msg = format!("cmd: tc {} fail, exit code {}, {}", args.join(" "), code_to_string(output.status.code()), String::from_utf8_lossy(&output.stderr));
+        error!("{}", msg);
+        Err(BuckyError::new(BuckyErrorCode::Failed, msg))
+    }
+}
+
+// 容器eth0在host侧对应的veth
+fn get_container_host_veth(name: &str) -> BuckyResult<String> {
+    let output = run_docker(vec!["exec", name, "cat", "/sys/class/net/eth0/iflink"])?.wait_with_output()?;
+    let iflink = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || iflink.is_empty() {
+        let msg = format!("get container {} eth0 iflink failed, exit code {}", name, code_to_string(output.status.code()));
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::Failed, msg));
+    }
+
+    for entry in std::fs::read_dir("/sys/class/net")? {
+        let entry = entry?;
+        if let Ok(index) = std::fs::read_to_string(entry.path().join("ifindex")) {
+            if index.trim() == iflink {
+                return Ok(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+
+    let msg = format!("host veth of container {} not found, iflink {}", name, iflink);
+    error!("{}", msg);
+    Err(BuckyError::new(BuckyErrorCode::NotFound, msg))
+}
+
+// 容器的出口流量就是host侧veth的入口流量，在host侧做ingress police，容器内即使有NET_ADMIN也无法移除
+fn limit_container_bandwidth(name: &str, net_bandwidth: u64) -> BuckyResult<()> {
+    let veth = get_container_host_veth(name)?;
+    info!("limit container {} bandwidth on host veth {}, {}kbit", name, veth, net_bandwidth);
+
+    let rate = format!("{}kbit", net_bandwidth);
+    let _ = run_tc(&["qdisc", "del", "dev", &veth, "ingress"]);
+    run_tc(&["qdisc", "add", "dev", &veth, "handle", "ffff:", "ingress"])?;
+    run_tc(&["filter", "add", "dev", &veth, "parent", "ffff:", "protocol", "all", "u32", "match", "u32", "0", "0",
+        "police", "rate", &rate, "burst", "32k", "drop", "flowid", ":1"])
+}
+
 fn code_to_string(code: Option<i32>) -> String {
     code.map(|i|{i.to_string()}).unwrap_or("signal".to_owned())
 }
@@ -326,6 +389,11 @@ impl DockerApi {
     /// uninstall
     /// remove image, remove dockerfile.gz
     pub async fn uninstall(&self, id: &str) -> BuckyResult<()> {
+        // 运行容器没有使用--rm，停止后会保留，这里一并移除
+        let container_name = format!("decapp-{}", id.to_lowercase());
+        if let Ok(child) = run_docker(vec!["rm", "-f", &container_name]) {
+            let _ = child.wait_with_output();
+        }
         let _ = self._remove_dockerfile(id);
 
         Ok(())
@@ -393,7 +461,8 @@ impl DockerApi {
             "--log-driver".to_string(), "json-file".to_string(),
             "--log-opt".to_string(), "max-size=100m".to_string(),
             "--log-opt".to_string(), "max-file=3".to_string(),
-            "--rm".to_string(), "-d".to_string(), "--init".to_string()
+            // 不使用--rm，容器退出后保留状态用以检查是否被oom kill，下次启动前会先移除
+            "-d".to_string(), "--init".to_string()
         ];
 
         // 容器启动的host配置
@@ -424,9 +493,23 @@ impl DockerApi {
             create_args.push(cpu_shares.to_string());
         }
 
+        // 进程数限制
+        if let Some(pids_limit) = config.pids_limit {
+            create_args.push("--pids-limit".to_string());
+            create_args.push(pids_limit.to_string());
+        }
+
+        // dec私钥，app内的SharedCyfsStack通过环境变量加载
+        if let Some(auth_key_file) = &config.auth_key_file {
+            add_bind_volume(&mut create_args, auth_key_file, APP_AUTH_KEY_CONTAINER_PATH, true);
+            create_args.push("-e".to_string());
+            create_args.push(format!("{}={}", cyfs_lib::CYFS_DEC_AUTH_KEY_FILE_ENV, APP_AUTH_KEY_CONTAINER_PATH));
+        }
+
         // ip和network 配置
         // 通过docker network inspect cyfs_br 可以快速查看container的ip是否配置正确
-        if config.ip.is_some() && config.network.is_some() {
+        let has_network = config.ip.is_some() && config.network.is_some();
+        if has_network {
             create_args.push("--network".to_string());
             create_args.push(config.network.unwrap());
 
@@ -448,6 +531,18 @@ impl DockerApi {
 
         if output.success() {
             info!("run container {} success", container_name);
+
+            // 带宽限制，设置失败时停止容器，不允许无限制运行
+            if let Some(net_bandwidth) = config.net_bandwidth {
+                if has_network {
+                    if let Err(e) = limit_container_bandwidth(&container_name, net_bandwidth) {
+                        error!("limit container {} bandwidth failed, stop it. {}", container_name, e);
+                        let _ = stop_docker(&container_name);
+                        return Err(e);
+                    }
+                }
+            }
+
             Ok(())
         } else {
             error!("run container {} fail, exit code {}", container_name, output.code().map(|i|{i.to_string()}).unwrap_or("signal".to_owned()));
@@ -0,0 +1,379 @@
+use async_std::io::{ReadExt, WriteExt};
+use async_std::net::TcpStream;
+use cyfs_base::*;
+use cyfs_core::AppResourceUsage;
+use log::*;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const DOCKER_API_VERSION: &str = "v1.41";
+const DOCKER_DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+const DOCKER_REQUEST_TIMEOUT_IN_SECS: u64 = 10;
+
+// docker engine的访问地址，优先使用DOCKER_HOST环境变量
+#[derive(Debug, Clone)]
+pub(crate) enum DockerEndpoint {
+    Unix(PathBuf),
+    Tcp(String),
+}
+
+impl DockerEndpoint {
+    pub fn from_env() -> Self {
+        match std::env::var("DOCKER_HOST") {
+            Ok(host) => {
+                if let Some(addr) = host.strip_prefix("tcp://") {
+                    Self::Tcp(addr.trim_end_matches('/').to_owned())
+                } else if let Some(path) = host.strip_prefix("unix://") {
+                    Self::Unix(PathBuf::from(path))
+                } else {
+                    warn!("unsupport DOCKER_HOST value: {}, use default socket", host);
+                    Self::Unix(PathBuf::from(DOCKER_DEFAULT_SOCKET))
+                }
+            }
+            Err(_) => Self::Unix(PathBuf::from(DOCKER_DEFAULT_SOCKET)),
+        }
+    }
+}
+
+// 一次采样得到的容器状态
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ContainerStats {
+    // 单核cpu的百分比
+    pub cpu_usage: u32,
+    pub mem_usage: u64,
+    pub mem_limit: u64,
+    pub net_rx: u64,
+    pub net_tx: u64,
+    pub block_read: u64,
+    pub block_write: u64,
+    pub pids: u32,
+    // 采样周期内是否有cpu被限流
+    pub throttled: bool,
+}
+
+impl ContainerStats {
+    pub fn to_usage(&self, disk_usage: u64, oom_killed: bool) -> AppResourceUsage {
+        AppResourceUsage {
+            cpu_usage: self.cpu_usage,
+            mem_usage: self.mem_usage,
+            mem_limit: self.mem_limit,
+            net_rx: self.net_rx,
+            net_tx: self.net_tx,
+            block_read: self.block_read,
+            block_write: self.block_write,
+            disk_usage,
+            pids: self.pids,
+            oom_killed,
+            throttled: self.throttled,
+            update_time: bucky_time_now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ContainerState {
+    pub running: bool,
+    pub oom_killed: bool,
+}
+
+fn get_u64(value: &Value, pointer: &str) -> u64 {
+    value.pointer(pointer).and_then(|v| v.as_u64()).unwrap_or(0)
+}
+
+// 解析 GET /containers/{id}/stats?stream=false 的返回
+pub(crate) fn parse_container_stats(value: &Value) -> ContainerStats {
+    let mut stats = ContainerStats::default();
+
+    // cpu使用率，和docker stats命令的计算方式一致
+    let cpu_delta = get_u64(value, "/cpu_stats/cpu_usage/total_usage")
+        .saturating_sub(get_u64(value, "/precpu_stats/cpu_usage/total_usage"));
+    let system_delta = get_u64(value, "/cpu_stats/system_cpu_usage")
+        .saturating_sub(get_u64(value, "/precpu_stats/system_cpu_usage"));
+    let mut online_cpus = get_u64(value, "/cpu_stats/online_cpus");
+    if online_cpus == 0 {
+        online_cpus = value
+            .pointer("/cpu_stats/cpu_usage/percpu_usage")
+            .and_then(|v| v.as_array())
+            .map(|v| v.len() as u64)
+            .unwrap_or(1);
+    }
+    if cpu_delta > 0 && system_delta > 0 {
+        let usage = cpu_delta as f64 / system_delta as f64 * online_cpus as f64 * 100.0;
+        stats.cpu_usage = usage.round() as u32;
+    }
+    stats.throttled = get_u64(value, "/cpu_stats/throttling_data/throttled_periods")
+        > get_u64(value, "/precpu_stats/throttling_data/throttled_periods");
+
+    // 内存使用量需要扣除page cache，cgroup v1为total_inactive_file，v2为inactive_file
+    let usage = get_u64(value, "/memory_stats/usage");
+    let inactive_file = match value.pointer("/memory_stats/stats/total_inactive_file") {
+        Some(v) => v.as_u64().unwrap_or(0),
+        None => get_u64(value, "/memory_stats/stats/inactive_file"),
+    };
+    stats.mem_usage = usage.saturating_sub(inactive_file);
+    stats.mem_limit = get_u64(value, "/memory_stats/limit");
+
+    if let Some(networks) = value.get("networks").and_then(|v| v.as_object()) {
+        for (_, network) in networks {
+            stats.net_rx += get_u64(network, "/rx_bytes");
+            stats.net_tx += get_u64(network, "/tx_bytes");
+        }
+    }
+
+    if let Some(list) = value
+        .pointer("/blkio_stats/io_service_bytes_recursive")
+        .and_then(|v| v.as_array())
+    {
+        for item in list {
+            let op = item.get("op").and_then(|v| v.as_str()).unwrap_or("");
+            let bytes = get_u64(item, "/value");
+            if op.eq_ignore_ascii_case("read") {
+                stats.block_read += bytes;
+            } else if op.eq_ignore_ascii_case("write") {
+                stats.block_write += bytes;
+            }
+        }
+    }
+
+    stats.pids = get_u64(value, "/pids_stats/current") as u32;
+
+    stats
+}
+
+// 解析 GET /containers/{id}/json 的返回
+pub(crate) fn parse_container_state(value: &Value) -> ContainerState {
+    ContainerState {
+        running: value
+            .pointer("/State/Running")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        oom_killed: value
+            .pointer("/State/OOMKilled")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+    }
+}
+
+// docker engine api的简单客户端，只用到几个GET接口，直接使用HTTP/1.0避免处理chunked
+pub(crate) struct DockerStatsClient {
+    endpoint: DockerEndpoint,
+}
+
+impl DockerStatsClient {
+    pub fn new(endpoint: DockerEndpoint) -> Self {
+        Self { endpoint }
+    }
+
+    pub async fn container_stats(&self, name: &str) -> BuckyResult<ContainerStats> {
+        let path = format!("/containers/{}/stats?stream=false", name);
+        let value = self.get(&path).await?;
+        Ok(parse_container_stats(&value))
+    }
+
+    pub async fn container_state(&self, name: &str) -> BuckyResult<ContainerState> {
+        let path = format!("/containers/{}/json", name);
+        let value = self.get(&path).await?;
+        Ok(parse_container_state(&value))
+    }
+
+    async fn get(&self, path: &str) -> BuckyResult<Value> {
+        let req = format!(
+            "GET /{}{} HTTP/1.0\r\nHost: docker\r\nAccept: application/json\r\n\r\n",
+            DOCKER_API_VERSION, path
+        );
+
+        let ret = async_std::future::timeout(
+            Duration::from_secs(DOCKER_REQUEST_TIMEOUT_IN_SECS),
+            self.request(req.as_bytes()),
+        )
+        .await;
+
+        let resp = match ret {
+            Ok(ret) => ret?,
+            Err(_) => {
+                let msg = format!("docker api request timeout! path={}", path);
+                warn!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::Timeout, msg));
+            }
+        };
+
+        Self::parse_response(path, &resp)
+    }
+
+    async fn request(&self, req: &[u8]) -> BuckyResult<Vec<u8>> {
+        let mut resp = vec![];
+        match &self.endpoint {
+            DockerEndpoint::Tcp(addr) => {
+                let mut stream = TcpStream::connect(addr).await.map_err(|e| {
+                    let msg = format!("connect to docker api failed! addr={}, {}", addr, e);
+                    warn!("{}", msg);
+                    BuckyError::new(BuckyErrorCode::ConnectFailed, msg)
+                })?;
+                stream.write_all(req).await?;
+                stream.read_to_end(&mut resp).await?;
+            }
+            #[cfg(unix)]
+            DockerEndpoint::Unix(path) => {
+                let mut stream = async_std::os::unix::net::UnixStream::connect(path)
+                    .await
+                    .map_err(|e| {
+                        let msg = format!(
+                            "connect to docker api failed! socket={}, {}",
+                            path.display(),
+                            e
+                        );
+                        warn!("{}", msg);
+                        BuckyError::new(BuckyErrorCode::ConnectFailed, msg)
+                    })?;
+                stream.write_all(req).await?;
+                stream.read_to_end(&mut resp).await?;
+            }
+            #[cfg(not(unix))]
+            DockerEndpoint::Unix(path) => {
+                let msg = format!(
+                    "unix socket docker endpoint not support on this platform! socket={}",
+                    path.display()
+                );
+                warn!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+            }
+        }
+
+        Ok(resp)
+    }
+
+    fn parse_response(path: &str, resp: &[u8]) -> BuckyResult<Value> {
+        let pos = resp
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or_else(|| {
+                let msg = format!("invalid docker api response! path={}", path);
+                warn!("{}", msg);
+                BuckyError::new(BuckyErrorCode::InvalidData, msg)
+            })?;
+
+        let head = String::from_utf8_lossy(&resp[..pos]);
+        let status = head
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|code| code.parse::<u16>().ok())
+            .unwrap_or(0);
+
+        let body = &resp[pos + 4..];
+        if status != 200 {
+            let msg = format!(
+                "docker api return error! path={}, status={}, body={}",
+                path,
+                status,
+                String::from_utf8_lossy(body)
+            );
+            warn!("{}", msg);
+            let code = if status == 404 {
+                BuckyErrorCode::NotFound
+            } else {
+                BuckyErrorCode::Failed
+            };
+            return Err(BuckyError::new(code, msg));
+        }
+
+        serde_json::from_slice(body).map_err(|e| {
+            let msg = format!("parse docker api response failed! path={}, {}", path, e);
+            warn!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidData, msg)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::net::TcpListener;
+    use async_std::prelude::*;
+
+    const STATS_RESP: &str = r#"{
+        "cpu_stats": {
+            "cpu_usage": {"total_usage": 300000000},
+            "system_cpu_usage": 2000000000,
+            "online_cpus": 2,
+            "throttling_data": {"periods": 20, "throttled_periods": 5}
+        },
+        "precpu_stats": {
+            "cpu_usage": {"total_usage": 100000000},
+            "system_cpu_usage": 1000000000,
+            "throttling_data": {"periods": 10, "throttled_periods": 2}
+        },
+        "memory_stats": {
+            "usage": 104857600,
+            "limit": 268435456,
+            "stats": {"inactive_file": 4857600}
+        },
+        "networks": {
+            "eth0": {"rx_bytes": 1000, "tx_bytes": 2000},
+            "eth1": {"rx_bytes": 10, "tx_bytes": 20}
+        },
+        "blkio_stats": {
+            "io_service_bytes_recursive": [
+                {"major": 8, "minor": 0, "op": "read", "value": 4096},
+                {"major": 8, "minor": 0, "op": "write", "value": 8192}
+            ]
+        },
+        "pids_stats": {"current": 12}
+    }"#;
+
+    const INSPECT_RESP: &str = r#"{"State": {"Running": false, "OOMKilled": true}}"#;
+
+    async fn start_mock_docker() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        async_std::task::spawn(async move {
+            let mut incoming = listener.incoming();
+            while let Some(Ok(mut stream)) = incoming.next().await {
+                let mut buf = vec![0u8; 4096];
+                let len = stream.read(&mut buf).await.unwrap();
+                let req = String::from_utf8_lossy(&buf[..len]).to_string();
+                let (status, body) = if req.starts_with("GET /v1.41/containers/decapp-test/stats?stream=false ") {
+                    ("200 OK", STATS_RESP)
+                } else if req.starts_with("GET /v1.41/containers/decapp-test/json ") {
+                    ("200 OK", INSPECT_RESP)
+                } else {
+                    ("404 Not Found", r#"{"message": "No such container"}"#)
+                };
+                let resp = format!(
+                    "HTTP/1.0 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(resp.as_bytes()).await.unwrap();
+            }
+        });
+
+        addr
+    }
+
+    #[async_std::test]
+    async fn test_container_stats() {
+        let addr = start_mock_docker().await;
+        let client = DockerStatsClient::new(DockerEndpoint::Tcp(addr));
+
+        let stats = client.container_stats("decapp-test").await.unwrap();
+        assert_eq!(stats.cpu_usage, 40);
+        assert!(stats.throttled);
+        assert_eq!(stats.mem_usage, 100000000);
+        assert_eq!(stats.mem_limit, 268435456);
+        assert_eq!(stats.net_rx, 1010);
+        assert_eq!(stats.net_tx, 2020);
+        assert_eq!(stats.block_read, 4096);
+        assert_eq!(stats.block_write, 8192);
+        assert_eq!(stats.pids, 12);
+
+        let state = client.container_state("decapp-test").await.unwrap();
+        assert!(!state.running);
+        assert!(state.oom_killed);
+
+        let err = client.container_stats("decapp-none").await.unwrap_err();
+        assert_eq!(err.code(), BuckyErrorCode::NotFound);
+    }
+}
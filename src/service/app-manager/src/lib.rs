@@ -1,3 +1,4 @@
 mod process_util;
+mod native_sandbox;
 pub mod package;
 pub mod dapp;
\ No newline at end of file
@@ -15,14 +15,20 @@ use std::fs;
 use std::{str::FromStr, sync::Arc};
 
 mod app_acl_util;
+mod app_auth_key;
 mod app_cmd_executor;
 mod app_controller;
+mod app_dependency;
 mod app_install_detail;
 mod app_manager_ex;
+mod app_resource_monitor;
 mod dapp;
 mod docker_api;
 mod docker_network_manager;
+mod docker_stats;
 mod event_handler;
+mod native_sandbox;
+mod native_sandbox_launcher;
 mod non_helper;
 mod package;
 mod process_util;
@@ -169,6 +175,11 @@ async fn main_run() -> BuckyResult<()> {
 }*/
 
 fn main() -> BuckyResult<()> {
+    // native sandbox的启动器，需要在创建任何线程之前处理
+    if let Some(config_file) = native_sandbox_launcher::get_sandbox_exec_arg() {
+        native_sandbox_launcher::sandbox_exec(&config_file);
+    }
+
     cyfs_debug::ProcessDeadHelper::patch_task_min_thread();
 
     async_std::task::block_on(main_run())
@@ -0,0 +1,326 @@
+use cyfs_base::*;
+use cyfs_util::*;
+use log::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Child;
+
+/*
+不依赖docker的轻量沙箱，只支持linux
+app-manager以SANDBOX_EXEC_ARG参数重新启动自身作为启动器，启动器在新的user/mount/pid/net/ipc/uts namespace里拉起app：
+1. 降权到SANDBOX_UID，并映射为namespace内的root
+2. pivot_root到tmpfs构造的最小根目录：只读的/usr,/lib,/bin,/sbin,/etc，少量设备文件，私有的/tmp，
+   以及app目录(只读)，数据目录，日志目录和私有的tmp目录，和docker模式保持一致，宿主机的其它目录不可见
+3. 新的网络namespace里只有lo，协议栈端口通过unix socket转发到宿主机的协议栈
+4. app进程在pid namespace内是1号进程，启动器退出时app也会被kill
+5. exec前设置seccomp，禁止mount/ptrace/加载内核模块等系统调用
+*/
+
+pub(crate) const SANDBOX_EXEC_ARG: &str = "--sandbox-exec";
+
+// nobody/nogroup
+const SANDBOX_UID: u32 = 65534;
+const SANDBOX_GID: u32 = 65534;
+
+// 沙箱内app需要访问的协议栈端口
+const SANDBOX_STACK_PORTS: [u16; 2] = [NON_STACK_HTTP_PORT, NON_STACK_WS_PORT];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct SandboxBind {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub read_only: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct SandboxStackPort {
+    pub port: u16,
+    pub socket: PathBuf,
+}
+
+// 宿主机生成，传递给启动器的配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct SandboxConfig {
+    pub app_id: String,
+    pub cmd: Vec<String>,
+    pub work_dir: PathBuf,
+    // cyfs根目录，binds的target都必须在这个目录下
+    pub root_dir: PathBuf,
+    pub binds: Vec<SandboxBind>,
+    pub uid: u32,
+    pub gid: u32,
+    pub stack_ports: Vec<SandboxStackPort>,
+}
+
+fn get_sandbox_run_dir(id: &str) -> PathBuf {
+    get_cyfs_root_path().join("run").join("sandbox").join(id)
+}
+
+fn get_sandbox_config_path(id: &str) -> PathBuf {
+    get_cyfs_root_path()
+        .join("run")
+        .join("sandbox")
+        .join(format!("{}.json", id))
+}
+
+fn get_sandbox_tmp_dir(id: &str) -> PathBuf {
+    get_temp_path().join("sandbox").join(id)
+}
+
+pub(crate) fn build_sandbox_binds(id: &str, root_dir: &Path) -> Vec<SandboxBind> {
+    let bind = |source: PathBuf, target: PathBuf, read_only: bool| SandboxBind {
+        source,
+        target,
+        read_only,
+    };
+
+    vec![
+        // app service目录只读
+        bind(get_app_dir(id), get_app_dir(id), true),
+        // 私有的可写目录
+        bind(get_app_data_dir(id), get_app_data_dir(id), false),
+        bind(get_app_log_dir(id), get_app_log_dir(id), false),
+        bind(get_sandbox_tmp_dir(id), root_dir.join("tmp"), false),
+        // 协议栈转发用的unix socket所在目录
+        bind(get_sandbox_run_dir(id), get_sandbox_run_dir(id), false),
+    ]
+}
+
+// 宿主机侧的沙箱实例，持有协议栈转发任务，drop时停止转发
+pub(crate) struct NativeSandbox {
+    id: String,
+    relays: Vec<async_std::task::JoinHandle<()>>,
+}
+
+impl Drop for NativeSandbox {
+    fn drop(&mut self) {
+        let relays: Vec<_> = self.relays.drain(..).collect();
+        if relays.is_empty() {
+            return;
+        }
+
+        info!("stop sandbox stack relay, app:{}", self.id);
+        async_std::task::spawn(async move {
+            for relay in relays {
+                relay.cancel().await;
+            }
+        });
+    }
+}
+
+impl NativeSandbox {
+    #[cfg(not(target_os = "linux"))]
+    pub fn spawn(
+        id: &str,
+        _cmd: &str,
+        _work_dir: &Path,
+        _record_pid: Option<&Path>,
+        _auth_key_file: Option<&Path>,
+    ) -> BuckyResult<(Self, Child)> {
+        let msg = format!(
+            "native sandbox only support on linux! app:{}",
+            id
+        );
+        error!("{}", msg);
+        Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn spawn(
+        id: &str,
+        cmd: &str,
+        work_dir: &Path,
+        record_pid: Option<&Path>,
+        auth_key_file: Option<&Path>,
+    ) -> BuckyResult<(Self, Child)> {
+        let args: Vec<&str> = ProcessUtil::parse_cmd(cmd);
+        if args.len() == 0 {
+            error!("parse cmd {} failed, cmd empty?", cmd);
+            return Err(BuckyError::from(BuckyErrorCode::InvalidData));
+        }
+        let program = which::which(args[0]).unwrap_or_else(|_| work_dir.join(args[0]));
+        let mut cmd_list = vec![program.to_string_lossy().to_string()];
+        cmd_list.extend(args[1..].iter().map(|s| s.to_string()));
+
+        let root_dir = get_cyfs_root_path();
+        let mut binds = build_sandbox_binds(id, &root_dir);
+        let run_dir = get_sandbox_run_dir(id);
+        for item in &binds {
+            std::fs::create_dir_all(&item.source)?;
+        }
+
+        // dec私钥在cyfs根目录下，以只读方式挂载到沙箱里的同一位置
+        if let Some(file) = auth_key_file {
+            binds.push(SandboxBind {
+                source: file.to_owned(),
+                target: file.to_owned(),
+                read_only: true,
+            });
+        }
+
+        // 以root运行时沙箱里的app降权为nobody，可写目录需要修改owner
+        let is_root = unsafe { libc::geteuid() } == 0;
+        let (uid, gid) = if is_root {
+            for item in binds.iter().filter(|item| !item.read_only) {
+                chown_all(&item.source, SANDBOX_UID, SANDBOX_GID);
+            }
+
+            // 私钥文件权限为0600，降权后的app需要成为owner才能读取
+            if let Some(file) = auth_key_file {
+                chown_all(file, SANDBOX_UID, SANDBOX_GID);
+            }
+            (SANDBOX_UID, SANDBOX_GID)
+        } else {
+            unsafe { (libc::geteuid(), libc::getegid()) }
+        };
+
+        let mut sandbox = Self {
+            id: id.to_owned(),
+            relays: vec![],
+        };
+        let mut stack_ports = vec![];
+        for port in SANDBOX_STACK_PORTS {
+            let socket = run_dir.join(format!("stack_{}.sock", port));
+            sandbox.start_relay(port, &socket)?;
+            stack_ports.push(SandboxStackPort { port, socket });
+        }
+
+        let config = SandboxConfig {
+            app_id: id.to_owned(),
+            cmd: cmd_list,
+            work_dir: work_dir.to_owned(),
+            root_dir,
+            binds,
+            uid,
+            gid,
+            stack_ports,
+        };
+        let config_path = get_sandbox_config_path(id);
+        std::fs::write(&config_path, serde_json::to_string(&config)?)?;
+
+        info!("will start app in native sandbox, app:{}, config:{:?}", id, config);
+
+        let mut command = std::process::Command::new(std::env::current_exe()?);
+        command
+            .arg(SANDBOX_EXEC_ARG)
+            .arg(&config_path)
+            .current_dir(work_dir);
+        // 启动器的环境变量会被app继承
+        if let Some(file) = auth_key_file {
+            command.env(cyfs_lib::CYFS_DEC_AUTH_KEY_FILE_ENV, file);
+        }
+        ProcessUtil::detach(&mut command);
+
+        let child = command.spawn().map_err(|e| {
+            error!("spawn sandbox launcher failed! app:{}, err:{}", id, e);
+            BuckyError::from(BuckyErrorCode::ExecuteError)
+        })?;
+
+        if let Some(path) = record_pid {
+            info!("write process pid {} to {}", child.id(), path.display());
+            let _ = std::fs::write(path, child.id().to_string().as_bytes());
+        }
+
+        Ok((sandbox, child))
+    }
+
+    // 把沙箱内对unix socket的连接转发到宿主机的协议栈端口
+    #[cfg(target_os = "linux")]
+    fn start_relay(&mut self, port: u16, socket: &Path) -> BuckyResult<()> {
+        use async_std::net::TcpStream;
+        use async_std::os::unix::net::{UnixListener, UnixStream};
+        use async_std::prelude::*;
+        use std::os::unix::fs::PermissionsExt;
+
+        if socket.exists() {
+            let _ = std::fs::remove_file(socket);
+        }
+        let listener = std::os::unix::net::UnixListener::bind(socket).map_err(|e| {
+            let msg = format!(
+                "bind sandbox relay socket failed! socket={}, {}",
+                socket.display(),
+                e
+            );
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::AddrInUse, msg)
+        })?;
+        std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o666))?;
+        let listener = UnixListener::from(listener);
+
+        let id = self.id.clone();
+        let relay = async_std::task::spawn(async move {
+            let mut incoming = listener.incoming();
+            while let Some(stream) = incoming.next().await {
+                let stream: UnixStream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        warn!("sandbox relay accept error! app:{}, port:{}, {}", id, port, e);
+                        continue;
+                    }
+                };
+
+                let id = id.clone();
+                async_std::task::spawn(async move {
+                    let upstream = match TcpStream::connect(("127.0.0.1", port)).await {
+                        Ok(upstream) => upstream,
+                        Err(e) => {
+                            warn!("sandbox relay connect to stack failed! app:{}, port:{}, {}", id, port, e);
+                            return;
+                        }
+                    };
+
+                    let (mut reader, mut writer) = (stream.clone(), upstream.clone());
+                    let up = async_std::io::copy(&mut reader, &mut writer);
+                    let (mut reader, mut writer) = (upstream, stream);
+                    let down = async_std::io::copy(&mut reader, &mut writer);
+                    let _ = up.race(down).await;
+                });
+            }
+        });
+
+        self.relays.push(relay);
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn chown_all(path: &Path, uid: u32, gid: u32) {
+    use std::os::unix::ffi::OsStrExt;
+
+    for entry in walkdir::WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+        let c_path = match std::ffi::CString::new(entry.path().as_os_str().as_bytes()) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if unsafe { libc::lchown(c_path.as_ptr(), uid, gid) } != 0 {
+            warn!(
+                "chown sandbox path failed! path={}, {}",
+                entry.path().display(),
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sandbox_binds() {
+        let id = "9tGpLNnYywrCAWoCcyhAcLZtrQpDZtRAg3ai2w47aap2";
+        let root_dir = get_cyfs_root_path();
+        let binds = build_sandbox_binds(id, &root_dir);
+
+        for item in &binds {
+            assert!(item.target.starts_with(&root_dir));
+        }
+
+        let app_dir = binds.iter().find(|item| item.source == get_app_dir(id)).unwrap();
+        assert!(app_dir.read_only);
+        assert!(binds
+            .iter()
+            .filter(|item| item.source != get_app_dir(id))
+            .all(|item| !item.read_only));
+    }
+}
@@ -0,0 +1,733 @@
+use crate::native_sandbox::{SandboxConfig, SANDBOX_EXEC_ARG};
+use cyfs_base::*;
+
+/*
+native sandbox的启动器，只在app-manager的可执行程序里使用
+宿主机侧以SANDBOX_EXEC_ARG参数重新启动app-manager，在这里进入namespace并拉起app
+*/
+
+pub(crate) fn get_sandbox_exec_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    match args.next() {
+        Some(arg) if arg == SANDBOX_EXEC_ARG => args.next(),
+        _ => None,
+    }
+}
+
+// 启动器入口，不会返回
+pub(crate) fn sandbox_exec(config_file: &str) -> ! {
+    let code = match std::fs::read_to_string(config_file)
+        .map_err(|e| BuckyError::from(e))
+        .and_then(|s| {
+            serde_json::from_str::<SandboxConfig>(&s).map_err(|e| {
+                BuckyError::new(
+                    BuckyErrorCode::InvalidFormat,
+                    format!("parse sandbox config error: {}", e),
+                )
+            })
+        })
+        .and_then(|config| launcher::run(config))
+    {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("sandbox exec failed! config={}, {}", config_file, e);
+            255
+        }
+    };
+
+    std::process::exit(code);
+}
+
+
+#[cfg(not(target_os = "linux"))]
+mod launcher {
+    use super::*;
+
+    pub fn run(config: SandboxConfig) -> BuckyResult<i32> {
+        let msg = format!("native sandbox only support on linux! app:{}", config.app_id);
+        Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod launcher {
+    use super::*;
+    use std::ffi::CString;
+    use std::path::{Path, PathBuf};
+    use std::os::unix::ffi::OsStrExt;
+
+    fn check(ret: libc::c_int, what: &str) -> BuckyResult<()> {
+        if ret < 0 {
+            let msg = format!("sandbox {} failed: {}", what, std::io::Error::last_os_error());
+            return Err(BuckyError::new(BuckyErrorCode::Failed, msg));
+        }
+        Ok(())
+    }
+
+    fn to_cstring<P: AsRef<Path>>(path: P) -> BuckyResult<CString> {
+        CString::new(path.as_ref().as_os_str().as_bytes()).map_err(|e| {
+            BuckyError::new(BuckyErrorCode::InvalidParam, format!("invalid path: {}", e))
+        })
+    }
+
+    fn mount(
+        source: Option<&Path>,
+        target: &Path,
+        fstype: Option<&str>,
+        flags: libc::c_ulong,
+        data: Option<&str>,
+    ) -> BuckyResult<()> {
+        let source = source.map(|v| to_cstring(v)).transpose()?;
+        let c_target = to_cstring(target)?;
+        let fstype = fstype.map(|v| CString::new(v).unwrap());
+        let data = data.map(|v| CString::new(v).unwrap());
+
+        let ret = unsafe {
+            libc::mount(
+                source.as_ref().map_or(std::ptr::null(), |v| v.as_ptr()),
+                c_target.as_ptr(),
+                fstype.as_ref().map_or(std::ptr::null(), |v| v.as_ptr()),
+                flags,
+                data.as_ref()
+                    .map_or(std::ptr::null(), |v| v.as_ptr() as *const libc::c_void),
+            )
+        };
+        check(ret, &format!("mount {}", target.display()))
+    }
+
+    // user namespace内重新挂载bind mount时，必须保留原挂载点上被锁定的标志
+    fn locked_mount_flags(path: &Path) -> BuckyResult<libc::c_ulong> {
+        let c_path = to_cstring(path)?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        check(
+            unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) },
+            "statvfs",
+        )?;
+
+        let mut flags = 0;
+        for (st, ms) in [
+            (libc::ST_NOSUID, libc::MS_NOSUID),
+            (libc::ST_NODEV, libc::MS_NODEV),
+            (libc::ST_NOEXEC, libc::MS_NOEXEC),
+            (libc::ST_NOATIME, libc::MS_NOATIME),
+            (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+            (libc::ST_RELATIME, libc::MS_RELATIME),
+        ] {
+            if stat.f_flag & st != 0 {
+                flags |= ms;
+            }
+        }
+        Ok(flags)
+    }
+
+    fn drop_privileges(uid: u32, gid: u32) -> BuckyResult<()> {
+        if unsafe { libc::geteuid() } != 0 {
+            return Ok(());
+        }
+
+        check(unsafe { libc::setgroups(0, std::ptr::null()) }, "setgroups")?;
+        check(unsafe { libc::setgid(gid) }, "setgid")?;
+        check(unsafe { libc::setuid(uid) }, "setuid")?;
+        Ok(())
+    }
+
+    fn write_id_maps(uid: u32, gid: u32) -> BuckyResult<()> {
+        std::fs::write("/proc/self/setgroups", "deny")?;
+        std::fs::write("/proc/self/uid_map", format!("0 {} 1", uid))?;
+        std::fs::write("/proc/self/gid_map", format!("0 {} 1", gid))?;
+        Ok(())
+    }
+
+    // 新根目录里的路径
+    fn in_root(root: &Path, path: &Path) -> PathBuf {
+        root.join(path.strip_prefix("/").unwrap_or(path))
+    }
+
+    fn bind_mount(source: &Path, target: &Path, read_only: bool) -> BuckyResult<()> {
+        if source.is_dir() {
+            std::fs::create_dir_all(target)?;
+        } else {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(target, b"")?;
+        }
+
+        mount(Some(source), target, None, libc::MS_BIND | libc::MS_REC, None)?;
+        if read_only {
+            let flags = locked_mount_flags(source)?;
+            mount(
+                None,
+                target,
+                None,
+                libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY | flags,
+                None,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // 只读的系统目录，usr合并的发行版上/bin,/lib等是指向/usr的符号链接，需要在新根目录里保留
+    const SYSTEM_DIRS: [&str; 7] = ["/usr", "/lib", "/lib64", "/lib32", "/bin", "/sbin", "/etc"];
+
+    // app可以访问的设备
+    const DEVICES: [&str; 6] = [
+        "/dev/null",
+        "/dev/zero",
+        "/dev/full",
+        "/dev/random",
+        "/dev/urandom",
+        "/dev/tty",
+    ];
+
+    fn setup_mounts(config: &SandboxConfig) -> BuckyResult<()> {
+        // 所有挂载都不传播到宿主机
+        mount(None, Path::new("/"), None, libc::MS_REC | libc::MS_PRIVATE, None)?;
+
+        // 在私有的tmpfs里构造新根目录
+        let tmp = Path::new("/tmp");
+        mount(
+            Some(Path::new("tmpfs")),
+            tmp,
+            Some("tmpfs"),
+            libc::MS_NOSUID | libc::MS_NODEV,
+            Some("mode=1777"),
+        )?;
+
+        let root = tmp.join(".sandbox_root");
+        std::fs::create_dir_all(&root)?;
+        mount(
+            Some(Path::new("tmpfs")),
+            &root,
+            Some("tmpfs"),
+            libc::MS_NOSUID | libc::MS_NODEV,
+            Some("mode=755"),
+        )?;
+
+        for dir in SYSTEM_DIRS {
+            let source = Path::new(dir);
+            let target = in_root(&root, source);
+            match std::fs::symlink_metadata(source) {
+                Ok(meta) if meta.file_type().is_symlink() => {
+                    std::os::unix::fs::symlink(std::fs::read_link(source)?, &target)?;
+                }
+                Ok(meta) if meta.is_dir() => {
+                    bind_mount(source, &target, true)?;
+                }
+                _ => continue,
+            }
+        }
+
+        for dev in DEVICES {
+            let source = Path::new(dev);
+            if source.exists() {
+                bind_mount(source, &in_root(&root, source), false)?;
+            }
+        }
+        let shm = in_root(&root, Path::new("/dev/shm"));
+        std::fs::create_dir_all(&shm)?;
+        mount(
+            Some(Path::new("tmpfs")),
+            &shm,
+            Some("tmpfs"),
+            libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+            Some("mode=1777"),
+        )?;
+
+        // /proc在fork出app进程后挂载，/tmp是私有的tmpfs
+        std::fs::create_dir_all(in_root(&root, Path::new("/proc")))?;
+        let new_tmp = in_root(&root, tmp);
+        std::fs::create_dir_all(&new_tmp)?;
+        mount(
+            Some(Path::new("tmpfs")),
+            &new_tmp,
+            Some("tmpfs"),
+            libc::MS_NOSUID | libc::MS_NODEV,
+            Some("mode=1777"),
+        )?;
+
+        // app自身的目录，和docker模式保持一致
+        for item in &config.binds {
+            if !item.target.starts_with(&config.root_dir) {
+                let msg = format!(
+                    "sandbox bind target not in root dir! target={}, root={}",
+                    item.target.display(),
+                    config.root_dir.display()
+                );
+                return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+            }
+
+            bind_mount(&item.source, &in_root(&root, &item.target), item.read_only)?;
+        }
+
+        // 切换到新根目录，并卸载旧的根目录，宿主机上的其它目录在沙箱内不可见
+        let old_root = root.join(".old_root");
+        std::fs::create_dir_all(&old_root)?;
+        let c_root = to_cstring(&root)?;
+        let c_old_root = to_cstring(&old_root)?;
+        check(
+            unsafe { libc::syscall(libc::SYS_pivot_root, c_root.as_ptr(), c_old_root.as_ptr()) }
+                as libc::c_int,
+            "pivot_root",
+        )?;
+        std::env::set_current_dir("/")?;
+
+        let c_old_root = to_cstring("/.old_root")?;
+        check(
+            unsafe { libc::umount2(c_old_root.as_ptr(), libc::MNT_DETACH) },
+            "umount old root",
+        )?;
+        std::fs::remove_dir("/.old_root")?;
+
+        // 新根目录本身只读，app只能写入自己的可写目录和/tmp
+        mount(
+            None,
+            Path::new("/"),
+            None,
+            libc::MS_REMOUNT | libc::MS_BIND | libc::MS_RDONLY | libc::MS_NOSUID | libc::MS_NODEV,
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    fn setup_loopback() -> BuckyResult<()> {
+        #[repr(C)]
+        struct IfReqFlags {
+            name: [u8; libc::IFNAMSIZ],
+            flags: libc::c_short,
+            _pad: [u8; 22],
+        }
+
+        let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+        check(fd, "create socket")?;
+
+        let mut req = IfReqFlags {
+            name: [0; libc::IFNAMSIZ],
+            flags: (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short,
+            _pad: [0; 22],
+        };
+        req.name[..2].copy_from_slice(b"lo");
+
+        let ret = unsafe { libc::ioctl(fd, libc::SIOCSIFFLAGS as _, &mut req as *mut IfReqFlags) };
+        unsafe { libc::close(fd) };
+        check(ret, "set lo up")
+    }
+
+    // 在沙箱的网络namespace内监听协议栈端口，转发到宿主机提供的unix socket
+    fn bind_stack_ports(
+        config: &SandboxConfig,
+    ) -> BuckyResult<Vec<(std::net::TcpListener, PathBuf)>> {
+        let mut list = vec![];
+        for item in &config.stack_ports {
+            let listener = std::net::TcpListener::bind(("127.0.0.1", item.port))?;
+            list.push((listener, item.socket.clone()));
+        }
+        Ok(list)
+    }
+
+    fn run_stack_relays(listeners: Vec<(std::net::TcpListener, PathBuf)>) {
+        for (listener, socket) in listeners {
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let stream = match stream {
+                        Ok(stream) => stream,
+                        Err(_) => continue,
+                    };
+                    let upstream = match std::os::unix::net::UnixStream::connect(&socket) {
+                        Ok(upstream) => upstream,
+                        Err(e) => {
+                            eprintln!("sandbox relay connect {} failed: {}", socket.display(), e);
+                            continue;
+                        }
+                    };
+
+                    let (mut reader, mut writer) = match (stream.try_clone(), upstream.try_clone()) {
+                        (Ok(r), Ok(w)) => (r, w),
+                        _ => continue,
+                    };
+                    std::thread::spawn(move || {
+                        let _ = std::io::copy(&mut reader, &mut writer);
+                        let _ = writer.shutdown(std::net::Shutdown::Write);
+                    });
+
+                    let (mut reader, mut writer) = (upstream, stream);
+                    std::thread::spawn(move || {
+                        let _ = std::io::copy(&mut reader, &mut writer);
+                        let _ = writer.shutdown(std::net::Shutdown::Write);
+                    });
+                }
+            });
+        }
+    }
+
+    const BPF_LD: u16 = 0x00;
+    const BPF_W: u16 = 0x00;
+    const BPF_ABS: u16 = 0x20;
+    const BPF_JMP: u16 = 0x05;
+    const BPF_JEQ: u16 = 0x10;
+    const BPF_JGE: u16 = 0x30;
+    const BPF_JSET: u16 = 0x40;
+    const BPF_K: u16 = 0x00;
+    const BPF_RET: u16 = 0x06;
+
+    const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+    const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+    const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+    // seccomp_data里args[0]的低32位，只支持小端架构
+    const SECCOMP_DATA_ARG0: u32 = 16;
+
+    // clone3的参数在用户内存里，bpf无法检查flags，x86_64和aarch64上都是435
+    pub(super) const SYS_CLONE3: libc::c_long = 435;
+
+    // 创建新namespace的clone flags，沙箱内禁止再创建namespace
+    const CLONE_NAMESPACE_FLAGS: libc::c_int = libc::CLONE_NEWUSER
+        | libc::CLONE_NEWNS
+        | libc::CLONE_NEWPID
+        | libc::CLONE_NEWNET
+        | libc::CLONE_NEWIPC
+        | libc::CLONE_NEWUTS
+        | libc::CLONE_NEWCGROUP;
+
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: Option<u32> = Some(0xC000_003E);
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: Option<u32> = Some(0xC000_00B7);
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    const AUDIT_ARCH: Option<u32> = None;
+
+    #[repr(C)]
+    pub(super) struct SockFilter {
+        code: u16,
+        jt: u8,
+        jf: u8,
+        k: u32,
+    }
+
+    #[repr(C)]
+    struct SockFprog {
+        len: u16,
+        filter: *const SockFilter,
+    }
+
+    fn stmt(code: u16, k: u32) -> SockFilter {
+        SockFilter { code, jt: 0, jf: 0, k }
+    }
+
+    fn jump(code: u16, k: u32, jt: u8, jf: u8) -> SockFilter {
+        SockFilter { code, jt, jf, k }
+    }
+
+    // 沙箱内禁止的系统调用，返回EPERM
+    fn denied_syscalls() -> Vec<libc::c_long> {
+        let mut list = vec![
+            libc::SYS_mount,
+            libc::SYS_umount2,
+            libc::SYS_pivot_root,
+            libc::SYS_chroot,
+            libc::SYS_swapon,
+            libc::SYS_swapoff,
+            libc::SYS_reboot,
+            libc::SYS_kexec_load,
+            libc::SYS_init_module,
+            libc::SYS_finit_module,
+            libc::SYS_delete_module,
+            libc::SYS_ptrace,
+            libc::SYS_process_vm_readv,
+            libc::SYS_process_vm_writev,
+            libc::SYS_bpf,
+            libc::SYS_perf_event_open,
+            libc::SYS_keyctl,
+            libc::SYS_add_key,
+            libc::SYS_request_key,
+            libc::SYS_unshare,
+            libc::SYS_setns,
+            libc::SYS_open_by_handle_at,
+            libc::SYS_userfaultfd,
+            libc::SYS_acct,
+            libc::SYS_settimeofday,
+            libc::SYS_clock_settime,
+            libc::SYS_quotactl,
+            libc::SYS_syslog,
+        ];
+
+        #[cfg(target_arch = "x86_64")]
+        list.extend_from_slice(&[libc::SYS_iopl, libc::SYS_ioperm, libc::SYS_kexec_file_load]);
+
+        list
+    }
+
+    pub(super) fn build_seccomp_filter() -> Option<Vec<SockFilter>> {
+        let arch = AUDIT_ARCH?;
+        let denied = denied_syscalls();
+
+        let mut filter = vec![
+            // 非本机架构的系统调用直接kill
+            stmt(BPF_LD | BPF_W | BPF_ABS, 4),
+            jump(BPF_JMP | BPF_JEQ | BPF_K, arch, 1, 0),
+            stmt(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS),
+            stmt(BPF_LD | BPF_W | BPF_ABS, 0),
+        ];
+
+        // x86_64上禁止x32 abi，避免绕过过滤
+        #[cfg(target_arch = "x86_64")]
+        {
+            filter.push(jump(BPF_JMP | BPF_JGE | BPF_K, 0x4000_0000, 0, 1));
+            filter.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_ERRNO | libc::EPERM as u32));
+        }
+
+        for nr in denied {
+            filter.push(jump(BPF_JMP | BPF_JEQ | BPF_K, nr as u32, 0, 1));
+            filter.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_ERRNO | libc::EPERM as u32));
+        }
+
+        // clone3返回ENOSYS，libc会回退到clone，再由下面的规则检查flags
+        filter.push(jump(BPF_JMP | BPF_JEQ | BPF_K, SYS_CLONE3 as u32, 0, 1));
+        filter.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_ERRNO | libc::ENOSYS as u32));
+
+        // 带namespace flags的clone返回EPERM，普通的fork/线程创建不受影响
+        filter.push(jump(BPF_JMP | BPF_JEQ | BPF_K, libc::SYS_clone as u32, 0, 3));
+        filter.push(stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_ARG0));
+        filter.push(jump(BPF_JMP | BPF_JSET | BPF_K, CLONE_NAMESPACE_FLAGS as u32, 0, 1));
+        filter.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_ERRNO | libc::EPERM as u32));
+        filter.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_ALLOW));
+
+        Some(filter)
+    }
+
+    pub(super) fn apply_seccomp(filter: &Option<Vec<SockFilter>>) -> BuckyResult<()> {
+        check(
+            unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) },
+            "set no new privs",
+        )?;
+
+        match filter {
+            Some(filter) => {
+                let prog = SockFprog {
+                    len: filter.len() as u16,
+                    filter: filter.as_ptr(),
+                };
+                check(
+                    unsafe {
+                        libc::prctl(
+                            libc::PR_SET_SECCOMP,
+                            libc::SECCOMP_MODE_FILTER,
+                            &prog as *const SockFprog,
+                        )
+                    },
+                    "set seccomp filter",
+                )
+            }
+            None => {
+                eprintln!("seccomp filter not support on this arch, skip");
+                Ok(())
+            }
+        }
+    }
+
+    // fork出来的app进程，是pid namespace内的1号进程
+    fn exec_app(
+        config: &SandboxConfig,
+        program: &CString,
+        argv: &Vec<CString>,
+        filter: &Option<Vec<SockFilter>>,
+    ) -> BuckyResult<()> {
+        // 启动器退出时app也随之退出
+        check(
+            unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL, 0, 0, 0) },
+            "set pdeathsig",
+        )?;
+
+        mount(
+            Some(Path::new("proc")),
+            Path::new("/proc"),
+            Some("proc"),
+            libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+            None,
+        )?;
+
+        std::env::set_current_dir(&config.work_dir)?;
+
+        apply_seccomp(filter)?;
+
+        let mut c_argv: Vec<*const libc::c_char> = argv.iter().map(|v| v.as_ptr()).collect();
+        c_argv.push(std::ptr::null());
+        unsafe { libc::execvp(program.as_ptr(), c_argv.as_ptr()) };
+
+        let msg = format!(
+            "sandbox exec {:?} failed: {}",
+            config.cmd,
+            std::io::Error::last_os_error()
+        );
+        Err(BuckyError::new(BuckyErrorCode::ExecuteError, msg))
+    }
+
+    fn wait_app(pid: libc::pid_t) -> i32 {
+        loop {
+            let mut status = 0;
+            let ret = unsafe { libc::waitpid(pid, &mut status, 0) };
+            if ret < 0 {
+                if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return 255;
+            }
+
+            if libc::WIFEXITED(status) {
+                return libc::WEXITSTATUS(status);
+            }
+            if libc::WIFSIGNALED(status) {
+                return 128 + libc::WTERMSIG(status);
+            }
+        }
+    }
+
+    pub fn run(config: SandboxConfig) -> BuckyResult<i32> {
+        if config.cmd.is_empty() {
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, "sandbox cmd is empty"));
+        }
+
+        // fork之后不再分配内存，提前准备好
+        let program = CString::new(config.cmd[0].as_str()).map_err(|e| {
+            BuckyError::new(BuckyErrorCode::InvalidParam, format!("invalid cmd: {}", e))
+        })?;
+        let mut argv = vec![];
+        for arg in &config.cmd {
+            argv.push(CString::new(arg.as_str()).map_err(|e| {
+                BuckyError::new(BuckyErrorCode::InvalidParam, format!("invalid cmd: {}", e))
+            })?);
+        }
+        let filter = build_seccomp_filter();
+
+        drop_privileges(config.uid, config.gid)?;
+
+        let flags = libc::CLONE_NEWUSER
+            | libc::CLONE_NEWNS
+            | libc::CLONE_NEWPID
+            | libc::CLONE_NEWNET
+            | libc::CLONE_NEWIPC
+            | libc::CLONE_NEWUTS;
+        check(unsafe { libc::unshare(flags) }, "unshare")?;
+
+        write_id_maps(config.uid, config.gid)?;
+
+        let hostname = b"decapp";
+        check(
+            unsafe { libc::sethostname(hostname.as_ptr() as *const libc::c_char, hostname.len()) },
+            "sethostname",
+        )?;
+
+        setup_mounts(&config)?;
+        setup_loopback()?;
+        let listeners = bind_stack_ports(&config)?;
+
+        let pid = unsafe { libc::fork() };
+        check(pid, "fork")?;
+        if pid == 0 {
+            drop(listeners);
+            if let Err(e) = exec_app(&config, &program, &argv, &filter) {
+                eprintln!("sandbox start app failed! app:{}, {}", config.app_id, e);
+            }
+            unsafe { libc::_exit(127) };
+        }
+
+        run_stack_relays(listeners);
+
+        Ok(wait_app(pid))
+    }
+}
+
+
+#[cfg(all(test, target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod tests {
+    use super::*;
+
+    fn errno() -> libc::c_int {
+        unsafe { *libc::__errno_location() }
+    }
+
+    // 不带新栈的clone，和fork一样在子进程里返回0
+    fn raw_clone(flags: libc::c_int) -> libc::c_long {
+        let none: libc::c_long = 0;
+        unsafe { libc::syscall(libc::SYS_clone, (flags | libc::SIGCHLD) as libc::c_long, none, none, none, none) }
+    }
+
+    // 在fork出来的子进程里应用过滤器后检查系统调用的结果，返回值非0表示第几项检查失败
+    fn check_filter_in_child(filter: &Option<Vec<launcher::SockFilter>>) -> libc::c_int {
+        if launcher::apply_seccomp(filter).is_err() {
+            return 1;
+        }
+
+        // 被禁止的系统调用
+        if unsafe { libc::unshare(libc::CLONE_NEWUSER) } != -1 || errno() != libc::EPERM {
+            return 2;
+        }
+
+        // 带namespace flags的clone
+        let ret = raw_clone(libc::CLONE_NEWUSER);
+        if ret == 0 {
+            unsafe { libc::_exit(0) };
+        }
+        if ret != -1 || errno() != libc::EPERM {
+            return 3;
+        }
+
+        let ret = raw_clone(libc::CLONE_NEWNET);
+        if ret == 0 {
+            unsafe { libc::_exit(0) };
+        }
+        if ret != -1 || errno() != libc::EPERM {
+            return 4;
+        }
+
+        // clone3直接返回ENOSYS
+        let none: libc::c_long = 0;
+        let ret = unsafe { libc::syscall(launcher::SYS_CLONE3, none, none) };
+        if ret != -1 || errno() != libc::ENOSYS {
+            return 5;
+        }
+
+        // 普通的fork不受影响
+        let pid = raw_clone(0);
+        if pid == 0 {
+            unsafe { libc::_exit(0) };
+        }
+        if pid < 0 {
+            return 6;
+        }
+        let mut status = 0;
+        if unsafe { libc::waitpid(pid as libc::pid_t, &mut status, 0) } < 0
+            || !libc::WIFEXITED(status)
+            || libc::WEXITSTATUS(status) != 0
+        {
+            return 7;
+        }
+
+        // 允许的系统调用
+        if unsafe { libc::getpid() } <= 0 {
+            return 8;
+        }
+
+        0
+    }
+
+    #[test]
+    fn test_seccomp_filter() {
+        let filter = launcher::build_seccomp_filter();
+        assert!(filter.is_some());
+
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            let code = check_filter_in_child(&filter);
+            unsafe { libc::_exit(code) };
+        }
+
+        let mut status = 0;
+        assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+        assert!(libc::WIFEXITED(status));
+        assert_eq!(libc::WEXITSTATUS(status), 0);
+    }
+}
@@ -8,7 +8,7 @@ use log::*;
 use cyfs_core::DecAppId;
 use cyfs_util::ProcessUtil;
 
-pub fn run(cmd: &str, work_dir: &Path, detach: bool, stdout: Option<File>, record_pid: Option<&Path>) -> BuckyResult<Child> {
+pub fn run(cmd: &str, work_dir: &Path, detach: bool, stdout: Option<File>, record_pid: Option<&Path>, envs: &[(&str, String)]) -> BuckyResult<Child> {
     let args: Vec<&str> = ProcessUtil::parse_cmd(cmd);
     if args.len() == 0 {
         error!("parse cmd {} failed, cmd empty?", cmd);
@@ -19,6 +19,9 @@ pub fn run(cmd: &str, work_dir: &Path, detach: bool, stdout: Option<File>, recor
     info!("program full path: {}", program.display());
     let mut command = Command::new(program);
     command.args(&args[1..]).current_dir(work_dir);
+    for (key, value) in envs {
+        command.env(key, value);
+    }
     if let Some(out) = stdout {
         command.stdout(out);
     }
@@ -68,7 +71,7 @@ pub fn try_stop_process_by_pid(pid_path: &Path, match_work_dir: Option<&Path>) -
             {
                 cmd = format!("kill -9 {}", &pid);
             }
-            run(&cmd, &Path::new("."), false, None, None)?.wait()?;
+            run(&cmd, &Path::new("."), false, None, None, &[])?.wait()?;
         }
     } else {
         info!("not found or not file: pid path {}", pid_path.display());
@@ -81,6 +81,13 @@ impl MimeHelper {
             }
         }
 
+        // 多range的响应已经是multipart/byteranges格式，不能覆盖
+        if let Some(v) = resp.content_type() {
+            if v.essence() == "multipart/byteranges" {
+                return;
+            }
+        }
+
         // 根据扩展名来判断
         // ndn的get_data的inner_path会反馈在url path上
         if Self::try_set_mime_from_ext(&url, resp) {
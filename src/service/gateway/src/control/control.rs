@@ -1,5 +1,6 @@
 use crate::server::http::HttpServerManager;
 use crate::server::stream::StreamServerManager;
+use crate::upstream::UPSTREAM_GROUP_MANAGER;
 use cyfs_base::{BuckyError, BuckyErrorCode};
 
 use async_std::prelude::*;
@@ -217,7 +218,7 @@ impl ControlServer {
     /*
     {
         "id": "{id}",
-        "type": "http|stream",
+        "type": "http|stream|upstream",
         "value": {
 
         }
@@ -278,7 +279,7 @@ impl ControlServer {
     /*
     {
         "id": "{id}",
-        "type": "http|stream",
+        "type": "http|stream|upstream",
     }
     */
     pub fn unregister_server(&self, value: &str) -> Result<(), BuckyError> {
@@ -304,26 +305,53 @@ impl ControlServer {
         }
     }
 
+    fn parse_server_value(server: &DynamicServerInfo) -> Result<toml::value::Table, BuckyError> {
+        let value: toml::Value = serde_json::from_str(&server.value).map_err(|e| {
+            let msg = format!(
+                "invalid server value format! id={}, type={}, value={}, {}",
+                server.id, server.server_type, server.value, e
+            );
+            error!("{}", msg);
+
+            BuckyError::from((BuckyErrorCode::InvalidFormat, msg))
+        })?;
+
+        match value {
+            toml::Value::Table(table) => Ok(table),
+            _ => {
+                let msg = format!(
+                    "server value is not an object! id={}, type={}, value={}",
+                    server.id, server.server_type, server.value
+                );
+                error!("{}", msg);
+
+                Err(BuckyError::from((BuckyErrorCode::InvalidFormat, msg)))
+            }
+        }
+    }
+
     fn add_server(&self, server: &DynamicServerInfo) -> Result<(), BuckyError> {
         match server.server_type.as_str() {
             "http" => {
-                let value: toml::Value = serde_json::from_str(&server.value).unwrap();
+                let value = Self::parse_server_value(server)?;
 
-                self.http_server_manager
-                    .load_server(value.as_table().unwrap())?;
+                self.http_server_manager.load_server(&value)?;
 
                 self.http_server_manager.start();
             }
             "stream" => {
-                let value: toml::Value = serde_json::from_str(&server.value).unwrap();
-                self.stream_server_manager
-                    .load_server(value.as_table().unwrap())?;
+                let value = Self::parse_server_value(server)?;
+                self.stream_server_manager.load_server(&value)?;
 
                 self.stream_server_manager.start();
             }
+            "upstream" => {
+                let value = Self::parse_server_value(server)?;
+                UPSTREAM_GROUP_MANAGER.lock().unwrap().load_group(&value)?;
+            }
             value @ _ => {
                 let msg = format!(
-                    "invalid gateway server type, only http/stream accept! type={}",
+                    "invalid gateway server type, only http/stream/upstream accept! type={}",
                     value
                 );
                 error!("{}", msg);
@@ -343,9 +371,15 @@ impl ControlServer {
             "stream" => {
                 self.stream_server_manager.remove_server(&server.id)?;
             }
+            "upstream" => {
+                UPSTREAM_GROUP_MANAGER
+                    .lock()
+                    .unwrap()
+                    .remove_group(&server.id)?;
+            }
             value @ _ => {
                 let msg = format!(
-                    "invalid gateway server type, only http/stream accept! type={}",
+                    "invalid gateway server type, only http/stream/upstream accept! type={}",
                     value
                 );
                 error!("{}", msg);
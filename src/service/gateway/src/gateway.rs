@@ -4,6 +4,7 @@ use std::path::{Path, PathBuf};
 use crate::control::HttpControlInterface;
 use crate::server::http::HttpServerManager;
 use crate::server::stream::StreamServerManager;
+use crate::upstream::UPSTREAM_GROUP_MANAGER;
 use cyfs_base::*;
 use cyfs_lib::*;
 use cyfs_stack_loader::STACK_MANAGER;
@@ -195,6 +196,19 @@ impl Gateway {
                         return Err(BuckyError::from(msg));
                     }
                 }
+                "upstream" => {
+                    if v.is_array() {
+                        UPSTREAM_GROUP_MANAGER
+                            .lock()
+                            .unwrap()
+                            .load(v.as_array().unwrap())?;
+                    } else {
+                        let msg = format!("config invalid upstream node format: {:?}", v);
+                        error!("{}", msg);
+
+                        return Err(BuckyError::from(msg));
+                    }
+                }
                 _ => {
                     warn!("unknown service config node: {}", &k);
                 }
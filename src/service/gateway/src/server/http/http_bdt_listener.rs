@@ -237,6 +237,7 @@ impl HttpBdtListener {
                 // req插入remote_peer_id头部
                 // 注意这里用insert而不是append，防止用户自带此header导致错误peerid被结算攻击
                 req.insert_header(cyfs_base::CYFS_REMOTE_DEVICE, device_id.to_string());
+                req.set_peer_addr(Some(device_id.to_string()));
 
                 let resp = self.base.dispatch_request(req).await;
                 Ok(resp)
@@ -1,7 +1,7 @@
 use http_types::{headers::HOST, Request};
 use std::sync::{Arc, Mutex};
 
-use super::http_location::HttpLocationManager;
+use super::http_location::{HttpLocationManager, HttpProxyTarget};
 use super::server_name::ServerName;
 use cyfs_stack_loader::VAR_MANAGER;
 use cyfs_base::BuckyError;
@@ -107,7 +107,7 @@ impl HttpForward {
         return None;
     }
 
-    pub fn find_dispatch(&self, req: &Request) -> Option<String> {
+    pub fn find_dispatch(&self, req: &Request) -> Option<HttpProxyTarget> {
         // 判断server是否匹配
         if self.check_host(&req).is_none() {
             return None;
@@ -126,7 +126,7 @@ impl HttpForward {
             return None;
         }
 
-        Some(item.unwrap().to_owned())
+        item
     }
 }
 
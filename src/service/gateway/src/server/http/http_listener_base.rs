@@ -5,9 +5,11 @@ use http_types::{
     Method, Request, Response, StatusCode, Url,
 };
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use super::http_forward::HTTP_FORWARD_MANAGER;
-use cyfs_base::BuckyError;
+use super::http_location::HttpProxyTarget;
+use cyfs_base::{BuckyError, BuckyErrorCode};
 
 #[derive(Debug, Clone)]
 pub(super) struct HttpListenerBase {
@@ -63,7 +65,7 @@ impl HttpListenerBase {
     }
 
     async fn dispatch_request_impl(&self, req: Request) -> Response {
-        let mut proxy_pass: Option<String> = None;
+        let mut proxy_pass: Option<HttpProxyTarget> = None;
 
         let forwards;
         {
@@ -95,9 +97,57 @@ impl HttpListenerBase {
             return Response::new(StatusCode::NotFound);
         }
 
-        let proxy_pass = Url::parse(&proxy_pass.unwrap()).unwrap();
+        let mut target = proxy_pass.unwrap();
+        let mut req = req;
+        let mut tried = vec![];
+        let resp = loop {
+            // 幂等请求在upstream节点失败后可以重试下一个节点，请求需要在转发前复制
+            let retry_req = match &target.selection {
+                Some(_) if Self::is_idempotent(&req) => Some(req.clone()),
+                _ => None,
+            };
+
+            let proxy_pass = Url::parse(&target.url).unwrap();
+            let timeout = target.selection.as_ref().and_then(|v| v.timeout());
+            let resp = HttpListenerBase::proxy_pass_with_timeout(req, proxy_pass, timeout).await;
+
+            let selection = match &target.selection {
+                Some(v) => v,
+                None => break resp,
+            };
+
+            // 反馈给upstream group，用以被动健康检查，5xx和超时都视为失败
+            let failed = match &resp {
+                Ok(resp) => resp.status().is_server_error(),
+                Err(_) => true,
+            };
+            if !failed {
+                selection.report_success();
+                break resp;
+            }
+            selection.report_failure();
+
+            let retry_req = match retry_req {
+                Some(v) => v,
+                None => break resp,
+            };
+
+            tried.push(selection.address.clone());
+            match target.next_target(&retry_req, &tried) {
+                Some(next) => {
+                    warn!(
+                        "upstream peer failed, will retry next peer: url={}, failed={:?}, next={}",
+                        retry_req.url(),
+                        selection.address,
+                        next.url
+                    );
+                    target = next;
+                    req = retry_req;
+                }
+                None => break resp,
+            }
+        };
 
-        let resp = HttpListenerBase::proxy_pass(req, proxy_pass).await;
         if resp.is_err() {
             let mut res = Response::new(StatusCode::InternalServerError);
             let _ret = res.insert_header("Content-Type", "text/plain");
@@ -110,6 +160,42 @@ impl HttpListenerBase {
         return resp;
     }
 
+    fn is_idempotent(req: &Request) -> bool {
+        match req.method() {
+            Method::Get | Method::Head => true,
+            _ => false,
+        }
+    }
+
+    async fn proxy_pass_with_timeout(
+        req: Request,
+        target_url: Url,
+        timeout: Option<Duration>,
+    ) -> Result<Response, BuckyError> {
+        let timeout = match timeout {
+            Some(v) => v,
+            None => return HttpListenerBase::proxy_pass(req, target_url).await,
+        };
+
+        match async_std::future::timeout(
+            timeout,
+            HttpListenerBase::proxy_pass(req, target_url.clone()),
+        )
+        .await
+        {
+            Ok(ret) => ret,
+            Err(_) => {
+                let msg = format!(
+                    "proxy_pass timeout! proxy_pass={}, timeout={:?}",
+                    target_url, timeout
+                );
+                error!("{}", msg);
+
+                Err(BuckyError::new(BuckyErrorCode::Timeout, msg))
+            }
+        }
+    }
+
     async fn proxy_pass(mut req: Request, target_url: Url) -> Result<Response, BuckyError> {
         let host = target_url.host_str().unwrap_or("localhost");
         let port = target_url.port().unwrap_or(80);
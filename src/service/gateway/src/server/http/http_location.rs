@@ -1,5 +1,6 @@
 use http_types::{Method, Request, Url};
 
+use crate::upstream::{UpstreamSelection, UpstreamTarget};
 use cyfs_base::BuckyError;
 use cyfs_stack_loader::VAR_MANAGER;
 
@@ -32,6 +33,32 @@ pub(super) struct HttpStreamLocation {
     proxy_pass_mode: HttpProxyPassMode,
     origin_proxy_pass: String,
     proxy_pass: Option<Url>,
+
+    // 使用upstream group时，proxy_pass的host和port会被替换为选中的节点
+    upstream: Option<String>,
+}
+
+// 匹配到的转发目标，selection需要保持到请求结束
+pub(super) struct HttpProxyTarget {
+    pub url: String,
+    pub selection: Option<UpstreamSelection>,
+
+    // 使用upstream group时保存匹配的location，用以在节点失败后重新选择
+    location: Option<HttpStreamLocation>,
+}
+
+impl HttpProxyTarget {
+    // 选择一个未尝试过的upstream节点，生成新的转发目标
+    pub fn next_target(&self, req: &Request, tried: &[(String, u16)]) -> Option<HttpProxyTarget> {
+        let location = self.location.as_ref()?;
+        match location.make_target(req, tried) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                warn!("no more upstream peer to retry: url={}, {}", req.url(), e);
+                None
+            }
+        }
+    }
 }
 
 impl HttpStreamLocation {
@@ -43,10 +70,11 @@ impl HttpStreamLocation {
             proxy_pass_mode: HttpProxyPassMode::Pass,
             origin_proxy_pass: String::from(""),
             proxy_pass: None,
+            upstream: None,
         }
     }
 
-    pub fn try_match(&self, req: &Request) -> Option<String> {
+    pub fn try_match(&self, req: &Request) -> Option<HttpProxyTarget> {
         let path = req.url().path();
 
         if !self.method_test(req) {
@@ -56,14 +84,14 @@ impl HttpStreamLocation {
         match self.mode {
             HttpLocationPathMode::Equal => {
                 if self.path == path {
-                    if let Ok(target_url) = self.make_target(req.url()) {
+                    if let Ok(target_url) = self.make_target(req, &[]) {
                         return Some(target_url);
                     }
                 }
             }
             HttpLocationPathMode::Prefix => {
                 if path.starts_with(&self.path) {
-                    if let Ok(target_url) = self.make_target(req.url()) {
+                    if let Ok(target_url) = self.make_target(req, &[]) {
                         return Some(target_url);
                     }
                 }
@@ -135,9 +163,64 @@ impl HttpStreamLocation {
         Ok(())
     }
 
-    fn make_target(&self, url: &Url) -> Result<String, BuckyError> {
+    fn make_target(
+        &self,
+        req: &Request,
+        tried: &[(String, u16)],
+    ) -> Result<HttpProxyTarget, BuckyError> {
+        let url = req.url();
+        let (proxy_pass, selection) =
+            self.select_proxy_pass(Self::client_hash_key(req).as_deref(), tried)?;
+        let target_url = self.make_target_url(url, &proxy_pass)?;
+
+        let location = match self.upstream {
+            Some(_) => Some(self.clone()),
+            None => None,
+        };
+
+        Ok(HttpProxyTarget {
+            url: target_url,
+            selection,
+            location,
+        })
+    }
+
+    // hash策略使用的客户端标识：tcp连接取对端ip，bdt连接取对端device_id
+    fn client_hash_key(req: &Request) -> Option<String> {
+        req.peer_addr().map(|addr| match addr.parse::<std::net::SocketAddr>() {
+            Ok(addr) => addr.ip().to_string(),
+            Err(_) => addr.to_owned(),
+        })
+    }
+
+    fn select_proxy_pass(
+        &self,
+        hash_key: Option<&str>,
+        tried: &[(String, u16)],
+    ) -> Result<(Url, Option<UpstreamSelection>), BuckyError> {
+        let mut proxy_pass = self.proxy_pass.as_ref().unwrap().clone();
+
+        let upstream = match &self.upstream {
+            Some(id) => UpstreamTarget::Group(id.clone()),
+            None => return Ok((proxy_pass, None)),
+        };
+
+        let selection = upstream.select_exclude(hash_key, tried)?;
+        proxy_pass.set_host(Some(&selection.address.0))?;
+        if let Err(_) = proxy_pass.set_port(Some(selection.address.1)) {
+            let msg = format!(
+                "set_port for proxy_pass error! proxy_pass={}, upstream={}",
+                proxy_pass, upstream
+            );
+            error!("{}", msg);
+            return Err(BuckyError::from(msg));
+        }
+
+        Ok((proxy_pass, Some(selection)))
+    }
+
+    fn make_target_url(&self, url: &Url, proxy_pass: &Url) -> Result<String, BuckyError> {
         let target_url;
-        let proxy_pass = self.proxy_pass.as_ref().unwrap();
         match self.proxy_pass_mode {
             HttpProxyPassMode::Join => {
                 let left = &url.path()[self.path.len()..];
@@ -149,7 +232,7 @@ impl HttpStreamLocation {
             HttpProxyPassMode::Pass => {
                 let mut v = url.clone();
 
-                if let Err(_) = v.set_scheme(self.proxy_pass.as_ref().unwrap().scheme()) {
+                if let Err(_) = v.set_scheme(proxy_pass.scheme()) {
                     let msg = format!(
                         "set_scheme for url error! url={}, proxy_pass={}",
                         v, proxy_pass
@@ -157,7 +240,7 @@ impl HttpStreamLocation {
                     error!("{}", msg);
                 }
 
-                if let Err(e) = v.set_host(self.proxy_pass.as_ref().unwrap().host_str()) {
+                if let Err(e) = v.set_host(proxy_pass.host_str()) {
                     let msg = format!(
                         "set_host for url error! url={}, proxy_pass={}, err={}",
                         v, proxy_pass, e
@@ -165,7 +248,7 @@ impl HttpStreamLocation {
                     error!("{}", msg);
                 }
 
-                if let Err(_) = v.set_port(self.proxy_pass.as_ref().unwrap().port()) {
+                if let Err(_) = v.set_port(proxy_pass.port()) {
                     let msg = format!(
                         "set_port for url error! url={}, proxy_pass={}",
                         v, proxy_pass
@@ -177,7 +260,7 @@ impl HttpStreamLocation {
             }
             HttpProxyPassMode::Contact => {
                 let left = &url.path()[self.path.len()..];
-                target_url = self.proxy_pass.as_ref().unwrap().as_str().to_owned() + left;
+                target_url = proxy_pass.as_str().to_owned() + left;
             }
         }
 
@@ -249,16 +332,39 @@ impl HttpLocationManager {
 
                     location.load_proxy_pass(&proxy_pass)?;
                 }
+                "upstream" => match v.as_str() {
+                    Some(id) if id.len() > 0 => {
+                        location.upstream = Some(id.to_owned());
+                    }
+                    _ => {
+                        let msg = format!("invalid location upstream field: {:?}", v);
+                        error!("{}", msg);
+
+                        return Err(BuckyError::from(msg));
+                    }
+                },
                 _ => {
                     error!("unknown location filed: {}", k.as_str());
                 }
             }
         }
 
+        // 只配置了upstream的情况下，直接转发到选中的节点
+        if location.upstream.is_some() && location.proxy_pass.is_none() {
+            location.load_proxy_pass("upstream")?;
+        }
+
+        if location.proxy_pass.is_none() {
+            let msg = format!("location proxy_pass or upstream field missing! path={}", location.path);
+            error!("{}", msg);
+
+            return Err(BuckyError::from(msg));
+        }
+
         Ok(location)
     }
 
-    pub fn search(&self, req: &Request) -> Option<String> {
+    pub fn search(&self, req: &Request) -> Option<HttpProxyTarget> {
         for item in &self.location_list {
             if let Some(target_url) = item.try_match(req) {
                 return Some(target_url);
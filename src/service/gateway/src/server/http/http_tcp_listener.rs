@@ -210,6 +210,9 @@ impl HttpTcpListener {
                 // 用户自己的请求不可附带CYFS_REMOTE_PEER，避免被攻击
                 req.remove_header(cyfs_base::CYFS_REMOTE_DEVICE);
 
+                // upstream的hash策略依赖对端地址
+                req.set_peer_addr(Some(peer_addr));
+
                 let resp = base.dispatch_request(req).await;
                 Ok(resp)
             },
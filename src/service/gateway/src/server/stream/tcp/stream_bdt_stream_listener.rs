@@ -1,4 +1,4 @@
-use crate::upstream::TcpUpStreamForBdt;
+use crate::upstream::{TcpUpStreamForBdt, UpstreamTarget};
 use cyfs_base::BuckyError;
 use cyfs_bdt::StreamGuard as BdtStream;
 use cyfs_stack_loader::ListenerUtil;
@@ -12,7 +12,7 @@ use std::sync::{Arc, Mutex};
 pub struct StreamBdtListener {
     pub stack: String,
     pub vport: u16,
-    proxy_pass: (String, u16),
+    proxy_pass: UpstreamTarget,
 
     pub running: bool,
     canceler: Option<AbortHandle>,
@@ -23,16 +23,15 @@ impl StreamBdtListener {
         Self {
             stack: listener.0,
             vport: listener.1,
-            proxy_pass: ("".to_owned(), 0),
+            proxy_pass: UpstreamTarget::Addr(("".to_owned(), 0)),
 
             running: false,
             canceler: None,
         }
     }
 
-    pub fn bind_proxy_pass(&mut self, proxy_pass: &(String, u16)) {
-        assert!(proxy_pass.0.len() > 0);
-        assert!(proxy_pass.1 > 0);
+    pub fn bind_proxy_pass(&mut self, proxy_pass: &UpstreamTarget) {
+        assert!(proxy_pass.is_valid());
 
         self.proxy_pass = proxy_pass.clone();
     }
@@ -129,13 +128,13 @@ impl StreamBdtListener {
                     Some(v) => match v {
                         Ok(pre_stream) => {
                             info!(
-                                "recv new bdt connection, listen={}, remote={:?}, proxy_pass={:?}",
+                                "recv new bdt connection, listen={}, remote={:?}, proxy_pass={}",
                                 listen2,
                                 pre_stream.stream.remote(),
                                 proxy_pass
                             );
 
-                            let address = (proxy_pass.0.clone(), proxy_pass.1 as u32);
+                            let address = proxy_pass.clone();
                             task::spawn(async move {
                                 Self::process(address, pre_stream.stream).await;
                             });
@@ -180,10 +179,10 @@ impl StreamBdtListener {
         Ok(())
     }
 
-    async fn process(proxy_pass: (String, u32), stream: BdtStream) {
+    async fn process(proxy_pass: UpstreamTarget, stream: BdtStream) {
         if let Err(e) = stream.confirm(&vec![]).await {
             error!(
-                "bdt stream confirm error! proxy_pass={:?}, remote={:?}, {}",
+                "bdt stream confirm error! proxy_pass={}, remote={:?}, {}",
                 proxy_pass,
                 stream.remote(),
                 e,
@@ -211,7 +210,7 @@ impl StreamBdtListenerManager {
         }
     }
 
-    pub fn bind_proxy_pass(&mut self, proxy_pass: &(String, u16)) {
+    pub fn bind_proxy_pass(&mut self, proxy_pass: &UpstreamTarget) {
         for server in &self.server_list {
             let mut server = server.lock().unwrap();
             server.bind_proxy_pass(proxy_pass);
@@ -1,4 +1,4 @@
-use crate::upstream::TcpUpStream;
+use crate::upstream::{TcpUpStream, UpstreamTarget};
 use cyfs_base::BuckyError;
 use cyfs_stack_loader::ListenerUtil;
 
@@ -11,7 +11,7 @@ use std::sync::{Arc, Mutex};
 
 pub struct StreamTcpListener {
     pub listen: SocketAddr,
-    proxy_pass: (String, u16),
+    proxy_pass: UpstreamTarget,
 
     pub running: bool,
     canceler: Option<AbortHandle>,
@@ -21,16 +21,15 @@ impl StreamTcpListener {
     pub fn new(listen: SocketAddr) -> StreamTcpListener {
         StreamTcpListener {
             listen,
-            proxy_pass: ("".to_owned(), 0),
+            proxy_pass: UpstreamTarget::Addr(("".to_owned(), 0)),
 
             running: false,
             canceler: None,
         }
     }
 
-    pub fn bind_proxy_pass(&mut self, proxy_pass: &(String, u16)) {
-        assert!(proxy_pass.0.len() > 0);
-        assert!(proxy_pass.1 > 0);
+    pub fn bind_proxy_pass(&mut self, proxy_pass: &UpstreamTarget) {
+        assert!(proxy_pass.is_valid());
 
         self.proxy_pass = proxy_pass.clone();
     }
@@ -125,7 +124,7 @@ impl StreamTcpListener {
 
             loop {
                 let incoming_ret = incoming.next().await;
-                let address = (proxy_pass.0.clone(), proxy_pass.1);
+                let address = proxy_pass.clone();
 
                 match incoming_ret {
                     Some(v) => match v {
@@ -190,7 +189,7 @@ impl StreamTcpListenerManager {
         }
     }
 
-    pub fn bind_proxy_pass(&mut self, proxy_pass: &(String, u16)) {
+    pub fn bind_proxy_pass(&mut self, proxy_pass: &UpstreamTarget) {
         for server in &self.server_list {
             let mut server = server.lock().unwrap();
             server.bind_proxy_pass(proxy_pass);
@@ -4,10 +4,11 @@ use super::super::StreamServer;
 use super::stream_bdt_stream_listener::StreamBdtListenerManager;
 use super::stream_tcp_listener::StreamTcpListenerManager;
 use cyfs_base::BuckyError;
+use crate::upstream::UpstreamTarget;
 use cyfs_stack_loader::VAR_MANAGER;
 
 pub struct TcpStreamServer {
-    proxy_pass: (String, u16),
+    proxy_pass: UpstreamTarget,
 
     tcp_listener_manager: StreamTcpListenerManager,
     bdt_listener_manager: StreamBdtListenerManager,
@@ -16,7 +17,7 @@ pub struct TcpStreamServer {
 impl TcpStreamServer {
     pub fn new() -> TcpStreamServer {
         TcpStreamServer {
-            proxy_pass: (String::from(""), 0),
+            proxy_pass: UpstreamTarget::Addr((String::from(""), 0)),
             tcp_listener_manager: StreamTcpListenerManager::new(),
             bdt_listener_manager: StreamBdtListenerManager::new(),
         }
@@ -102,7 +103,7 @@ impl StreamServer for TcpStreamServer {
                     let proxy_pass = VAR_MANAGER.translate_addr_str(proxy_pass)?;
 
                     match cyfs_util::parse_address(&proxy_pass) {
-                        Ok(ret) => self.proxy_pass = ret,
+                        Ok(ret) => self.proxy_pass = UpstreamTarget::Addr(ret),
                         Err(e) => {
                             error!("invalid server block field: proxy_pass: {:?}, err={}", v, e);
 
@@ -110,6 +111,20 @@ impl StreamServer for TcpStreamServer {
                         }
                     }
                 }
+                "upstream" => {
+                    // 指向一个upstream group，和proxy_pass二选一
+                    match v.as_str() {
+                        Some(id) if id.len() > 0 => {
+                            self.proxy_pass = UpstreamTarget::Group(id.to_owned());
+                        }
+                        _ => {
+                            return BuckyError::error_with_log(format!(
+                                "invalid server block field: upstream: {:?}",
+                                v
+                            ));
+                        }
+                    }
+                }
                 "id" | "protocol" => {}
                 _ => {
                     warn!("unknown server block field: {}", k);
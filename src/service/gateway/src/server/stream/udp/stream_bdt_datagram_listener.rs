@@ -1,4 +1,4 @@
-use crate::upstream::{UdpUpStreamManager, UpstreamDatagramSender};
+use crate::upstream::{UdpUpStreamManager, UpstreamDatagramSender, UpstreamTarget};
 use cyfs_stack_loader::STACK_MANAGER;
 use cyfs_base::BuckyError;
 use cyfs_stack_loader::ListenerUtil;
@@ -10,7 +10,7 @@ use std::sync::{Arc, Mutex};
 pub struct DatagramBdtListener {
     pub stack: String,
     pub vport: u16,
-    proxy_pass: (String, u16),
+    proxy_pass: UpstreamTarget,
 
     pub running: bool,
     canceler: Option<AbortHandle>,
@@ -21,15 +21,14 @@ impl DatagramBdtListener {
         Self {
             stack: listener.0,
             vport: listener.1,
-            proxy_pass: ("".to_owned(), 0),
+            proxy_pass: UpstreamTarget::Addr(("".to_owned(), 0)),
             running: false,
             canceler: None,
         }
     }
 
-    pub fn bind_proxy_pass(&mut self, proxy_pass: &(String, u16)) {
-        assert!(proxy_pass.0.len() > 0);
-        assert!(proxy_pass.1 > 0);
+    pub fn bind_proxy_pass(&mut self, proxy_pass: &UpstreamTarget) {
+        assert!(proxy_pass.is_valid());
 
         self.proxy_pass = proxy_pass.clone();
     }
@@ -118,7 +117,7 @@ impl DatagramBdtListener {
 
         let channel_addr = format!("{}:{}", stack_name, vport);
         let _channel_addr2 = channel_addr.clone();
-        let proxy_pass_str = format!("{}:{}", proxy_pass.0, proxy_pass.1);
+        let proxy_pass_str = proxy_pass.to_string();
         let mut upstream_manager = UdpUpStreamManager::new(&channel_addr, &proxy_pass_str);
         upstream_manager.start();
         /*
@@ -213,7 +212,7 @@ impl DatagramBdtListenerManager {
         }
     }
 
-    pub fn bind_proxy_pass(&mut self, proxy_pass: &(String, u16)) {
+    pub fn bind_proxy_pass(&mut self, proxy_pass: &UpstreamTarget) {
         for server in &self.server_list {
             let mut server = server.lock().unwrap();
             server.bind_proxy_pass(proxy_pass);
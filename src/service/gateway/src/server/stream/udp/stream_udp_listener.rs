@@ -1,4 +1,4 @@
-use crate::upstream::UdpUpStreamManager;
+use crate::upstream::{UdpUpStreamManager, UpstreamTarget};
 use crate::upstream::{UpstreamDatagramSender, MAXIMUM_UDP_PAYLOAD_SIZE};
 use cyfs_base::BuckyError;
 use cyfs_stack_loader::ListenerUtil;
@@ -11,7 +11,7 @@ use std::sync::{Arc, Mutex};
 
 pub struct StreamUdpListener {
     pub listen: SocketAddr,
-    proxy_pass: (String, u16),
+    proxy_pass: UpstreamTarget,
 
     pub running: bool,
     canceler: Option<AbortHandle>,
@@ -21,16 +21,15 @@ impl StreamUdpListener {
     pub fn new(listen: SocketAddr) -> StreamUdpListener {
         StreamUdpListener {
             listen,
-            proxy_pass: ("".to_owned(), 0),
+            proxy_pass: UpstreamTarget::Addr(("".to_owned(), 0)),
 
             running: false,
             canceler: None,
         }
     }
 
-    pub fn bind_proxy_pass(&mut self, proxy_pass: &(String, u16)) {
-        assert!(proxy_pass.0.len() > 0);
-        assert!(proxy_pass.1 > 0);
+    pub fn bind_proxy_pass(&mut self, proxy_pass: &UpstreamTarget) {
+        assert!(proxy_pass.is_valid());
 
         self.proxy_pass = proxy_pass.clone();
     }
@@ -86,7 +85,7 @@ impl StreamUdpListener {
 
                 if let Err(e) = cyfs_util::init_udp_socket(&v) {
                     error!(
-                        "init udp socket error! addr={}, proxy_pass={:?}, err={}",
+                        "init udp socket error! addr={}, proxy_pass={}, err={}",
                         addr, proxy_pass, e
                     );
                 }
@@ -107,7 +106,7 @@ impl StreamUdpListener {
         let receiver = Arc::new(listener);
         let sender = receiver.clone_sender();
 
-        let proxy_pass_str = format!("{}:{}", proxy_pass.0, proxy_pass.1);
+        let proxy_pass_str = proxy_pass.to_string();
         let addr2 = addr.clone();
 
         let mut upstream_manager = UdpUpStreamManager::new(&addr.to_string(), &proxy_pass_str);
@@ -153,7 +152,7 @@ impl StreamUdpListener {
                             },
                             Err(e) => {
                                 error!(
-                                    "pick udp upstram error! proxy_pass={:?}, src_addr={}, err={}",
+                                    "pick udp upstram error! proxy_pass={}, src_addr={}, err={}",
                                     proxy_pass, src_addr, e
                                 );
                             }
@@ -202,7 +201,7 @@ impl StreamUdpListenerManager {
         }
     }
 
-    pub fn bind_proxy_pass(&mut self, proxy_pass: &(String, u16)) {
+    pub fn bind_proxy_pass(&mut self, proxy_pass: &UpstreamTarget) {
         for server in &self.server_list {
             let mut server = server.lock().unwrap();
             server.bind_proxy_pass(proxy_pass);
@@ -4,10 +4,11 @@ use super::super::StreamServer;
 use super::stream_bdt_datagram_listener::DatagramBdtListenerManager;
 use super::stream_udp_listener::StreamUdpListenerManager;
 use cyfs_base::BuckyError;
+use crate::upstream::UpstreamTarget;
 use cyfs_stack_loader::VAR_MANAGER;
 
 pub struct UdpStreamServer {
-    proxy_pass: (String, u16),
+    proxy_pass: UpstreamTarget,
 
     udp_listener_manager: StreamUdpListenerManager,
     bdt_listener_manager: DatagramBdtListenerManager,
@@ -16,7 +17,7 @@ pub struct UdpStreamServer {
 impl UdpStreamServer {
     pub fn new() -> UdpStreamServer {
         UdpStreamServer {
-            proxy_pass: (String::from(""), 0),
+            proxy_pass: UpstreamTarget::Addr((String::from(""), 0)),
             udp_listener_manager: StreamUdpListenerManager::new(),
             bdt_listener_manager: DatagramBdtListenerManager::new(),
         }
@@ -102,7 +103,7 @@ impl StreamServer for UdpStreamServer {
                     let proxy_pass = VAR_MANAGER.translate_addr_str(proxy_pass)?;
 
                     match cyfs_util::parse_address(&proxy_pass) {
-                        Ok(ret) => self.proxy_pass = ret,
+                        Ok(ret) => self.proxy_pass = UpstreamTarget::Addr(ret),
                         Err(e) => {
                             error!("invalid server block field: proxy_pass: {:?}, err={}", v, e);
 
@@ -110,6 +111,20 @@ impl StreamServer for UdpStreamServer {
                         }
                     }
                 }
+                "upstream" => {
+                    // 指向一个upstream group，和proxy_pass二选一
+                    match v.as_str() {
+                        Some(id) if id.len() > 0 => {
+                            self.proxy_pass = UpstreamTarget::Group(id.to_owned());
+                        }
+                        _ => {
+                            return BuckyError::error_with_log(format!(
+                                "invalid server block field: upstream: {:?}",
+                                v
+                            ));
+                        }
+                    }
+                }
                 "id" | "protocol" => {}
                 _ => {
                     warn!("unknown server block field: {}", k);
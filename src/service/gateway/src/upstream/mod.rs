@@ -2,8 +2,10 @@ pub mod tcp_up_stream;
 pub mod udp_up_stream;
 pub mod udp_sender;
 mod peer_assoc;
+mod upstream_group;
 
 pub use peer_assoc::*;
+pub use upstream_group::*;
 
 pub use tcp_up_stream::TcpUpStream;
 pub use tcp_up_stream::TcpUpStreamForBdt;
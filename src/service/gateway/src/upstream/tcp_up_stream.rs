@@ -1,5 +1,5 @@
-use super::{AssociationProtocol, PEER_ASSOC_MANAGER};
-use cyfs_base::BuckyError;
+use super::{AssociationProtocol, UpstreamSelection, UpstreamTarget, PEER_ASSOC_MANAGER};
+use cyfs_base::{BuckyError, BuckyErrorCode};
 
 use async_std::net::TcpStream;
 use async_std::prelude::*;
@@ -10,19 +10,64 @@ use std::net::Shutdown;
 use std::time::Duration;
 
 pub struct TcpUpStream {
-    address: (String, u16),
+    target: UpstreamTarget,
 }
 
 impl TcpUpStream {
-    pub fn new(address: &(String, u16)) -> TcpUpStream {
+    pub fn new(target: &UpstreamTarget) -> TcpUpStream {
         TcpUpStream {
-            address: (address.0.clone(), address.1),
+            target: target.clone(),
         }
     }
 
-    //pub fn new_conn() -> Result<TcpStream, BuckyError> {
+    // 连接上游，如果是upstream group，那么失败后会尝试其余的节点
+    pub async fn connect(
+        target: &UpstreamTarget,
+        hash_key: Option<&str>,
+    ) -> Result<(TcpStream, UpstreamSelection), BuckyError> {
+        let mut last_err = None;
+        let mut tried = vec![];
+        for _ in 0..target.max_tries() {
+            // 失败后跳过已经尝试过的节点
+            let selection = match target.select_exclude(hash_key, &tried) {
+                Ok(v) => v,
+                Err(e) => {
+                    last_err = Some(e);
+                    break;
+                }
+            };
+
+            let str = format!("{}:{}", selection.address.0, selection.address.1);
+            match TcpStream::connect(str).await {
+                Ok(stream) => {
+                    debug!(
+                        "connect tcp up stream success! target={}, addr={:?}",
+                        target, selection.address
+                    );
+                    selection.report_success();
+
+                    return Ok((stream, selection));
+                }
+                Err(e) => {
+                    error!(
+                        "connect tcp up stream error, target={}, addr={:?}, e={}",
+                        target, selection.address, e
+                    );
+                    selection.report_failure();
+
+                    tried.push(selection.address.clone());
+                    last_err = Some(BuckyError::from(e));
+                }
+            }
+        }
 
-    //}
+        Err(last_err.unwrap_or_else(|| {
+            BuckyError::new(
+                BuckyErrorCode::ConnectFailed,
+                format!("connect tcp up stream failed! target={}", target),
+            )
+        }))
+    }
 
     pub fn init_up_stream(stream: &TcpStream) {
         #[cfg(unix)]
@@ -60,18 +105,10 @@ impl TcpUpStream {
     }
 
     pub async fn bind(&self, stream: TcpStream) -> Result<(), BuckyError> {
-        let str = format!("{}:{}", self.address.0, self.address.1);
-
-        let ret = TcpStream::connect(str).await;
-        if let Err(e) = ret {
-            error!(
-                "connect tcp up stream error, addr={:?}, e={}",
-                self.address, e
-            );
-            return Err(BuckyError::from(e));
-        }
+        let hash_key = stream.peer_addr().ok().map(|v| v.ip().to_string());
 
-        let up_stream = ret.unwrap();
+        // selection需要保持到连接结束，用以统计上游的活跃连接数
+        let (up_stream, _selection) = Self::connect(&self.target, hash_key.as_deref()).await?;
         Self::init_up_stream(&up_stream);
 
         let up_stream2 = up_stream.clone();
@@ -164,33 +201,23 @@ impl TcpUpStream {
 }
 
 pub struct TcpUpStreamForBdt {
-    address: (String, u32),
+    target: UpstreamTarget,
 }
 
 impl TcpUpStreamForBdt {
-    pub fn new(address: &(String, u32)) -> TcpUpStreamForBdt {
+    pub fn new(target: &UpstreamTarget) -> TcpUpStreamForBdt {
         TcpUpStreamForBdt {
-            address: (address.0.clone(), address.1),
+            target: target.clone(),
         }
     }
 
     pub async fn bind(&self, stream: BdtStream) -> Result<(), BuckyError> {
-        let str = format!("{}:{}", self.address.0, self.address.1);
-
-        let ret = TcpStream::connect(str).await;
-        if let Err(e) = ret {
-            error!(
-                "connect tcp up stream error, addr={:?}, e={}",
-                self.address, e
-            );
-            return Err(BuckyError::from(e));
-        } else {
-            debug!("connect tcp up stream success! addr={:?}", self.address);
-        }
+        // bdt来源使用对端的device_id作为hash key
+        let hash_key = stream.remote().0.to_string();
 
         // 目前只能connect发起后才能拿到port进行关联，但如果对方收到连接后立刻查询，可能还没走到这里
         // 对方要在连接上收到数据后再进行反查操作
-        let up_stream = ret.unwrap();
+        let (up_stream, _selection) = TcpUpStream::connect(&self.target, Some(&hash_key)).await?;
         TcpUpStream::init_up_stream(&up_stream);
 
         // 保存peerid和upstream端口关联
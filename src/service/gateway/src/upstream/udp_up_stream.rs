@@ -1,6 +1,6 @@
 use super::{
-    AssociationProtocol, UpstreamDatagramSender, DEFAULT_TIMEOUT, MAXIMUM_UDP_PAYLOAD_SIZE,
-    PEER_ASSOC_MANAGER,
+    AssociationProtocol, UpstreamDatagramSender, UpstreamSelection, UpstreamTarget,
+    DEFAULT_TIMEOUT, MAXIMUM_UDP_PAYLOAD_SIZE, PEER_ASSOC_MANAGER,
 };
 use cyfs_base::{BuckyError, DeviceId};
 
@@ -18,6 +18,9 @@ pub struct UdpUpStream {
     pub proxy_pass: (String, u16),
     pub sock: Arc<UdpSocket>,
 
+    // 选中的上游节点，关联存在期间一直持有
+    selection: UpstreamSelection,
+
     canceler: Option<AbortHandle>,
 
     // 来源是不是bdt协议
@@ -44,15 +47,32 @@ impl UdpUpStream {
 
     pub async fn new(
         src_address: String,
-        proxy_pass: &(String, u16),
+        target: &UpstreamTarget,
         remote_device_id: Option<&DeviceId>,
     ) -> Result<UdpUpStream, BuckyError> {
-        let dest = Self::create_udp_socket(proxy_pass).await?;
+        // 同一个来源的包总是发往同一个上游节点，bdt来源使用device_id作为hash key
+        let hash_key = match remote_device_id {
+            Some(device_id) => device_id.to_string(),
+            None => src_address.clone(),
+        };
+
+        let selection = target.select(Some(&hash_key))?;
+        let dest = match Self::create_udp_socket(&selection.address).await {
+            Ok(v) => {
+                selection.report_success();
+                v
+            }
+            Err(e) => {
+                selection.report_failure();
+                return Err(e);
+            }
+        };
 
         Ok(UdpUpStream {
             src_address,
-            proxy_pass: proxy_pass.to_owned(),
+            proxy_pass: selection.address.clone(),
             sock: Arc::new(dest),
+            selection,
             canceler: None,
             remote_device_id: remote_device_id.map(|v| v.clone()),
         })
@@ -318,7 +338,7 @@ impl UdpUpStreamManager {
     pub async fn pick_stream(
         &mut self,
         addr: &str,
-        proxy_pass: &(String, u16),
+        proxy_pass: &UpstreamTarget,
         sender: &Box<dyn UpstreamDatagramSender>,
         remote_device_id: Option<&DeviceId>,
     ) -> Result<Arc<UdpSocket>, BuckyError> {
@@ -329,7 +349,7 @@ impl UdpUpStreamManager {
             }
         }
 
-        info!("will assoc socket {} <---> {:?}", addr, proxy_pass);
+        info!("will assoc socket {} <---> {}", addr, proxy_pass);
 
         let mut stream = UdpUpStream::new(addr.to_owned(), proxy_pass, remote_device_id).await?;
         stream.bind(sender, self.clone()).await?;
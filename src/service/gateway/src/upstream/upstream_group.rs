@@ -0,0 +1,936 @@
+use cyfs_base::*;
+use cyfs_stack_loader::VAR_MANAGER;
+
+use async_std::net::TcpStream;
+use async_std::prelude::*;
+use futures::future::{AbortHandle, Aborted};
+use http_types::{Method, Request, Url};
+use lazy_static::lazy_static;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UpstreamPolicy {
+    RoundRobin,
+    LeastConn,
+    Hash,
+}
+
+impl UpstreamPolicy {
+    pub fn from(value: &str) -> BuckyResult<Self> {
+        let ret = match value {
+            "round_robin" => Self::RoundRobin,
+            "least_conn" => Self::LeastConn,
+            "hash" => Self::Hash,
+            v @ _ => {
+                let msg = format!("invalid upstream policy: {}", v);
+                error!("{}", msg);
+
+                return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+            }
+        };
+
+        Ok(ret)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum UpstreamHealthCheckType {
+    // try to connect the target with tcp
+    Tcp,
+
+    // send GET request with the path, any 2xx/3xx status is treat as healthy
+    Http(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct UpstreamHealthCheck {
+    pub check_type: UpstreamHealthCheckType,
+    pub interval: Duration,
+    pub timeout: Duration,
+
+    // continuous success count to mark a peer as up, and continuous fail count to mark as down
+    pub rise: u32,
+    pub fall: u32,
+}
+
+impl Default for UpstreamHealthCheck {
+    fn default() -> Self {
+        Self {
+            check_type: UpstreamHealthCheckType::Tcp,
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(3),
+            rise: 2,
+            fall: 3,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UpstreamGroupConfig {
+    pub id: String,
+    pub policy: UpstreamPolicy,
+    pub servers: Vec<(String, u16)>,
+
+    // passive check: after max_fails continuous failures, the peer will be ejected for fail_timeout
+    pub max_fails: u32,
+    pub fail_timeout: Duration,
+
+    // http upstream response timeout, a timeout is reported as a failure of the peer
+    pub timeout: Duration,
+
+    pub health_check: Option<UpstreamHealthCheck>,
+}
+
+impl UpstreamGroupConfig {
+    /*
+    {
+        id: "xxx",
+        policy: "round_robin|least_conn|hash",
+        server: ["127.0.0.1:1000", "127.0.0.1:1001"],
+        max_fails: 3,
+        fail_timeout: 30,
+        timeout: 60,
+        health_check: {
+            type: "tcp|http",
+            path: "/check",
+            interval: 10,
+            timeout: 3,
+            rise: 2,
+            fall: 3,
+        }
+    }
+    */
+    pub fn load(node: &toml::value::Table) -> BuckyResult<Self> {
+        let mut id = None;
+        let mut policy = UpstreamPolicy::RoundRobin;
+        let mut servers = Vec::new();
+        let mut max_fails = 3;
+        let mut fail_timeout = Duration::from_secs(30);
+        let mut timeout = Duration::from_secs(60);
+        let mut health_check = None;
+
+        for (k, v) in node {
+            match k.as_str() {
+                "id" => {
+                    id = v.as_str().map(|v| v.to_owned());
+                }
+                "policy" => {
+                    policy = UpstreamPolicy::from(v.as_str().unwrap_or(""))?;
+                }
+                "server" => {
+                    let list = match v {
+                        toml::Value::String(v) => vec![v.as_str()],
+                        toml::Value::Array(list) => list.iter().filter_map(|v| v.as_str()).collect(),
+                        _ => {
+                            let msg = format!("invalid upstream server field: {:?}", v);
+                            error!("{}", msg);
+
+                            return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                        }
+                    };
+
+                    for item in list {
+                        let addr = VAR_MANAGER.translate_addr_str(item)?;
+                        servers.push(cyfs_util::parse_address(&addr)?);
+                    }
+                }
+                "max_fails" => {
+                    max_fails = Self::load_u32(k, v)?;
+                }
+                "fail_timeout" => {
+                    fail_timeout = Duration::from_secs(Self::load_u32(k, v)? as u64);
+                }
+                "timeout" => {
+                    timeout = Duration::from_secs(Self::load_u32(k, v)? as u64);
+                }
+                "health_check" => match v.as_table() {
+                    Some(node) => {
+                        health_check = Some(Self::load_health_check(node)?);
+                    }
+                    None => {
+                        let msg = format!("invalid upstream health_check field: {:?}", v);
+                        error!("{}", msg);
+
+                        return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                    }
+                },
+                _ => {
+                    warn!("unknown upstream block field: {}", k);
+                }
+            }
+        }
+
+        let id = id.ok_or_else(|| {
+            let msg = format!("upstream block id field missing! node={:?}", node);
+            error!("{}", msg);
+
+            BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+        })?;
+
+        if servers.is_empty() {
+            let msg = format!("upstream block server list is empty! id={}", id);
+            error!("{}", msg);
+
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        Ok(Self {
+            id,
+            policy,
+            servers,
+            max_fails,
+            fail_timeout,
+            timeout,
+            health_check,
+        })
+    }
+
+    fn load_health_check(node: &toml::value::Table) -> BuckyResult<UpstreamHealthCheck> {
+        let mut check = UpstreamHealthCheck::default();
+        let mut check_type = "tcp";
+        let mut path = "/";
+
+        for (k, v) in node {
+            match k.as_str() {
+                "type" => {
+                    check_type = v.as_str().unwrap_or("");
+                }
+                "path" => {
+                    path = v.as_str().unwrap_or("/");
+                }
+                "interval" => {
+                    check.interval = Duration::from_secs(Self::load_u32(k, v)? as u64);
+                }
+                "timeout" => {
+                    check.timeout = Duration::from_secs(Self::load_u32(k, v)? as u64);
+                }
+                "rise" => {
+                    check.rise = Self::load_u32(k, v)?;
+                }
+                "fall" => {
+                    check.fall = Self::load_u32(k, v)?;
+                }
+                _ => {
+                    warn!("unknown upstream health_check field: {}", k);
+                }
+            }
+        }
+
+        check.check_type = match check_type {
+            "tcp" => UpstreamHealthCheckType::Tcp,
+            "http" => UpstreamHealthCheckType::Http(path.to_owned()),
+            v @ _ => {
+                let msg = format!("invalid upstream health_check type: {}", v);
+                error!("{}", msg);
+
+                return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+            }
+        };
+
+        Ok(check)
+    }
+
+    fn load_u32(key: &str, v: &toml::Value) -> BuckyResult<u32> {
+        match v.as_integer() {
+            Some(v) if v >= 0 && v <= u32::MAX as i64 => Ok(v as u32),
+            _ => {
+                let msg = format!("invalid upstream field: {}={:?}", key, v);
+                error!("{}", msg);
+
+                Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg))
+            }
+        }
+    }
+}
+
+struct UpstreamPeer {
+    address: (String, u16),
+
+    // active health check state
+    healthy: bool,
+    check_success: u32,
+    check_fails: u32,
+
+    // passive check state
+    fails: u32,
+    ejected_until: Option<Instant>,
+
+    active: Arc<AtomicUsize>,
+}
+
+impl UpstreamPeer {
+    fn new(address: (String, u16)) -> Self {
+        Self {
+            address,
+            healthy: true,
+            check_success: 0,
+            check_fails: 0,
+            fails: 0,
+            ejected_until: None,
+            active: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn is_available(&self, now: &Instant) -> bool {
+        if !self.healthy {
+            return false;
+        }
+
+        match &self.ejected_until {
+            Some(until) => now >= until,
+            None => true,
+        }
+    }
+}
+
+// selected peer, the active connection count will be released on drop
+pub struct UpstreamPeerGuard {
+    group: UpstreamGroup,
+    index: usize,
+    address: (String, u16),
+    active: Arc<AtomicUsize>,
+}
+
+impl Drop for UpstreamPeerGuard {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl UpstreamPeerGuard {
+    pub fn address(&self) -> &(String, u16) {
+        &self.address
+    }
+
+    pub fn report_success(&self) {
+        self.group.on_peer_success(self.index);
+    }
+
+    pub fn report_failure(&self) {
+        self.group.on_peer_failure(self.index);
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.group.config.timeout
+    }
+}
+
+#[derive(Clone)]
+pub struct UpstreamGroup {
+    config: Arc<UpstreamGroupConfig>,
+    peers: Arc<Mutex<Vec<UpstreamPeer>>>,
+    next: Arc<AtomicUsize>,
+    canceler: Arc<Mutex<Option<AbortHandle>>>,
+}
+
+impl UpstreamGroup {
+    pub fn new(config: UpstreamGroupConfig) -> Self {
+        let peers = config
+            .servers
+            .iter()
+            .map(|v| UpstreamPeer::new(v.clone()))
+            .collect();
+
+        Self {
+            config: Arc::new(config),
+            peers: Arc::new(Mutex::new(peers)),
+            next: Arc::new(AtomicUsize::new(0)),
+            canceler: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    pub fn peer_count(&self) -> usize {
+        self.config.servers.len()
+    }
+
+    // hash_key is used only by the hash policy, usually the remote address or device id
+    pub fn select(&self, hash_key: Option<&str>) -> BuckyResult<UpstreamPeerGuard> {
+        self.select_exclude(hash_key, &[])
+    }
+
+    // select a peer except the excluded ones, used to retry on the next peer after a failure
+    pub fn select_exclude(
+        &self,
+        hash_key: Option<&str>,
+        exclude: &[(String, u16)],
+    ) -> BuckyResult<UpstreamPeerGuard> {
+        let now = Instant::now();
+        let peers = self.peers.lock().unwrap();
+
+        let all: Vec<usize> = (0..peers.len())
+            .filter(|i| !exclude.contains(&peers[*i].address))
+            .collect();
+        let available: Vec<usize> = all
+            .iter()
+            .filter(|i| peers[**i].is_available(&now))
+            .cloned()
+            .collect();
+
+        // all peers are down, fallback to all the peers instead of reject the request
+        let candidates = if available.is_empty() {
+            warn!(
+                "all upstream peers are unavailable, will try all: id={}",
+                self.config.id
+            );
+            all
+        } else {
+            available
+        };
+
+        if candidates.is_empty() {
+            let msg = format!(
+                "upstream group has no peer: id={}, exclude={:?}",
+                self.config.id, exclude
+            );
+            error!("{}", msg);
+
+            return Err(BuckyError::new(BuckyErrorCode::NotFound, msg));
+        }
+
+        let index = match self.config.policy {
+            UpstreamPolicy::RoundRobin => {
+                let next = self.next.fetch_add(1, Ordering::SeqCst);
+                candidates[next % candidates.len()]
+            }
+            UpstreamPolicy::LeastConn => {
+                // take turns between peers with the same connection count
+                let next = self.next.fetch_add(1, Ordering::SeqCst);
+                let mut selected = candidates[next % candidates.len()];
+                for i in &candidates {
+                    if peers[*i].active.load(Ordering::SeqCst)
+                        < peers[selected].active.load(Ordering::SeqCst)
+                    {
+                        selected = *i;
+                    }
+                }
+                selected
+            }
+            UpstreamPolicy::Hash => {
+                // rendezvous hash: a key only moves when its own peer becomes unavailable
+                let key = hash_key.unwrap_or("");
+                *candidates
+                    .iter()
+                    .max_by_key(|i| Self::peer_weight(key, &peers[**i].address))
+                    .unwrap()
+            }
+        };
+
+        let peer = &peers[index];
+        peer.active.fetch_add(1, Ordering::SeqCst);
+
+        debug!(
+            "select upstream peer: id={}, policy={:?}, peer={:?}",
+            self.config.id, self.config.policy, peer.address
+        );
+
+        Ok(UpstreamPeerGuard {
+            group: self.clone(),
+            index,
+            address: peer.address.clone(),
+            active: peer.active.clone(),
+        })
+    }
+
+    fn peer_weight(key: &str, address: &(String, u16)) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        address.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn on_peer_success(&self, index: usize) {
+        let mut peers = self.peers.lock().unwrap();
+        let peer = &mut peers[index];
+        peer.fails = 0;
+        peer.ejected_until = None;
+    }
+
+    fn on_peer_failure(&self, index: usize) {
+        if self.config.max_fails == 0 {
+            return;
+        }
+
+        let mut peers = self.peers.lock().unwrap();
+        let peer = &mut peers[index];
+        peer.fails += 1;
+        if peer.fails >= self.config.max_fails {
+            warn!(
+                "upstream peer failed too many times and will be ejected: id={}, peer={:?}, fails={}, timeout={:?}",
+                self.config.id, peer.address, peer.fails, self.config.fail_timeout
+            );
+
+            peer.fails = 0;
+            peer.ejected_until = Some(Instant::now() + self.config.fail_timeout);
+        }
+    }
+
+    fn on_check_result(&self, index: usize, ret: bool) {
+        let check = self.config.health_check.as_ref().unwrap();
+
+        let mut peers = self.peers.lock().unwrap();
+        let peer = &mut peers[index];
+        if ret {
+            peer.check_fails = 0;
+            peer.check_success += 1;
+            if !peer.healthy && peer.check_success >= check.rise {
+                info!(
+                    "upstream peer health check recovered: id={}, peer={:?}",
+                    self.config.id, peer.address
+                );
+                peer.healthy = true;
+            }
+        } else {
+            peer.check_success = 0;
+            peer.check_fails += 1;
+            if peer.healthy && peer.check_fails >= check.fall {
+                warn!(
+                    "upstream peer health check failed and will be marked down: id={}, peer={:?}",
+                    self.config.id, peer.address
+                );
+                peer.healthy = false;
+            }
+        }
+    }
+
+    pub fn start(&self) {
+        let check = match &self.config.health_check {
+            Some(v) => v.clone(),
+            None => return,
+        };
+
+        let this = self.clone();
+        let (future, handle) = futures::future::abortable(async move {
+            let mut interval = async_std::stream::interval(check.interval);
+            while let Some(_) = interval.next().await {
+                this.check_once(&check).await;
+            }
+        });
+
+        {
+            let mut canceler = self.canceler.lock().unwrap();
+            assert!(canceler.is_none());
+            *canceler = Some(handle);
+        }
+
+        let id = self.config.id.clone();
+        async_std::task::spawn(async move {
+            match future.await {
+                Ok(_) => {
+                    info!("upstream health check complete: id={}", id);
+                }
+                Err(Aborted) => {
+                    info!("upstream health check aborted: id={}", id);
+                }
+            }
+        });
+    }
+
+    pub fn stop(&self) {
+        if let Some(canceler) = self.canceler.lock().unwrap().take() {
+            info!("will stop upstream health check: id={}", self.config.id);
+            canceler.abort();
+        }
+    }
+
+    async fn check_once(&self, check: &UpstreamHealthCheck) {
+        for (index, address) in self.config.servers.iter().enumerate() {
+            let ret = async_std::io::timeout(check.timeout, async {
+                Self::check_peer(address, &check.check_type)
+                    .await
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))
+            })
+            .await;
+
+            if let Err(e) = &ret {
+                debug!(
+                    "upstream peer health check failed: id={}, peer={:?}, {}",
+                    self.config.id, address, e
+                );
+            }
+
+            self.on_check_result(index, ret.is_ok());
+        }
+    }
+
+    async fn check_peer(
+        address: &(String, u16),
+        check_type: &UpstreamHealthCheckType,
+    ) -> BuckyResult<()> {
+        let addr = format!("{}:{}", address.0, address.1);
+        let stream = TcpStream::connect(&addr).await.map_err(|e| {
+            let msg = format!("connect upstream peer error! addr={}, {}", addr, e);
+            BuckyError::new(BuckyErrorCode::ConnectFailed, msg)
+        })?;
+
+        match check_type {
+            UpstreamHealthCheckType::Tcp => Ok(()),
+            UpstreamHealthCheckType::Http(path) => {
+                let url = format!("http://{}{}", addr, path);
+                let url = Url::parse(&url).map_err(|e| {
+                    let msg = format!("invalid health check url: {}, {}", url, e);
+                    BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+                })?;
+
+                let mut req = Request::new(Method::Get, url);
+                req.insert_header("connection", "close");
+
+                let resp = async_h1::client::connect(stream, req).await.map_err(|e| {
+                    let msg = format!("health check request error! addr={}, {}", addr, e);
+                    BuckyError::new(BuckyErrorCode::Failed, msg)
+                })?;
+
+                let status = resp.status();
+                if status.is_success() || status.is_redirection() {
+                    Ok(())
+                } else {
+                    let msg = format!("health check got error status! addr={}, {}", addr, status);
+                    Err(BuckyError::new(BuckyErrorCode::Failed, msg))
+                }
+            }
+        }
+    }
+}
+
+pub struct UpstreamGroupManager {
+    groups: HashMap<String, UpstreamGroup>,
+}
+
+impl UpstreamGroupManager {
+    pub fn new() -> Self {
+        Self {
+            groups: HashMap::new(),
+        }
+    }
+
+    pub fn load(&mut self, upstream_node: &Vec<toml::Value>) -> BuckyResult<()> {
+        for v in upstream_node {
+            match v.as_table() {
+                Some(node) => {
+                    self.load_group(node)?;
+                }
+                None => {
+                    warn!("invalid upstream node format: {:?}", v);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // load or replace the upstream group with the same id
+    pub fn load_group(&mut self, node: &toml::value::Table) -> BuckyResult<()> {
+        let config = UpstreamGroupConfig::load(node)?;
+
+        info!(
+            "will load upstream group: id={}, policy={:?}, servers={:?}",
+            config.id, config.policy, config.servers
+        );
+
+        let group = UpstreamGroup::new(config);
+        group.start();
+
+        if let Some(old) = self.groups.insert(group.id().to_owned(), group) {
+            info!("upstream group replaced: id={}", old.id());
+            old.stop();
+        }
+
+        Ok(())
+    }
+
+    pub fn remove_group(&mut self, id: &str) -> BuckyResult<()> {
+        match self.groups.remove(id) {
+            Some(group) => {
+                info!("upstream group removed: id={}", id);
+                group.stop();
+
+                Ok(())
+            }
+            None => {
+                let msg = format!("upstream group not found! id={}", id);
+                error!("{}", msg);
+
+                Err(BuckyError::new(BuckyErrorCode::NotFound, msg))
+            }
+        }
+    }
+
+    pub fn get_group(&self, id: &str) -> Option<UpstreamGroup> {
+        self.groups.get(id).cloned()
+    }
+}
+
+lazy_static! {
+    pub static ref UPSTREAM_GROUP_MANAGER: Mutex<UpstreamGroupManager> =
+        Mutex::new(UpstreamGroupManager::new());
+}
+
+pub fn get_upstream_group(id: &str) -> BuckyResult<UpstreamGroup> {
+    UPSTREAM_GROUP_MANAGER
+        .lock()
+        .unwrap()
+        .get_group(id)
+        .ok_or_else(|| {
+            let msg = format!("upstream group not found! id={}", id);
+            error!("{}", msg);
+
+            BuckyError::new(BuckyErrorCode::NotFound, msg)
+        })
+}
+
+// the upstream target of a server block, a fixed address or a upstream group
+#[derive(Clone, Debug)]
+pub enum UpstreamTarget {
+    Addr((String, u16)),
+    Group(String),
+}
+
+impl UpstreamTarget {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Addr(addr) => addr.0.len() > 0 && addr.1 > 0,
+            Self::Group(id) => id.len() > 0,
+        }
+    }
+}
+
+impl std::fmt::Display for UpstreamTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Addr(addr) => write!(f, "{}:{}", addr.0, addr.1),
+            Self::Group(id) => write!(f, "upstream:{}", id),
+        }
+    }
+}
+
+// the upstream address picked for a connection, the guard must live as long as the connection
+pub struct UpstreamSelection {
+    pub address: (String, u16),
+    pub guard: Option<UpstreamPeerGuard>,
+}
+
+impl UpstreamSelection {
+    pub fn report_success(&self) {
+        if let Some(guard) = &self.guard {
+            guard.report_success();
+        }
+    }
+
+    pub fn report_failure(&self) {
+        if let Some(guard) = &self.guard {
+            guard.report_failure();
+        }
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.guard.as_ref().map(|guard| guard.timeout())
+    }
+}
+
+impl UpstreamTarget {
+    pub fn select(&self, hash_key: Option<&str>) -> BuckyResult<UpstreamSelection> {
+        self.select_exclude(hash_key, &[])
+    }
+
+    // the excluded peers are the ones already tried for the same request
+    pub fn select_exclude(
+        &self,
+        hash_key: Option<&str>,
+        exclude: &[(String, u16)],
+    ) -> BuckyResult<UpstreamSelection> {
+        match self {
+            Self::Addr(addr) => Ok(UpstreamSelection {
+                address: addr.clone(),
+                guard: None,
+            }),
+            Self::Group(id) => {
+                let guard = get_upstream_group(id)?.select_exclude(hash_key, exclude)?;
+                Ok(UpstreamSelection {
+                    address: guard.address().clone(),
+                    guard: Some(guard),
+                })
+            }
+        }
+    }
+
+    // max try count for a connection, for upstream group will try every peer once
+    pub fn max_tries(&self) -> usize {
+        match self {
+            Self::Addr(_) => 1,
+            Self::Group(id) => match get_upstream_group(id) {
+                Ok(group) => std::cmp::max(group.peer_count(), 1),
+                Err(_) => 1,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_group(policy: UpstreamPolicy, count: u16) -> UpstreamGroup {
+        let config = UpstreamGroupConfig {
+            id: "test".to_owned(),
+            policy,
+            servers: (0..count).map(|i| ("127.0.0.1".to_owned(), 1000 + i)).collect(),
+            max_fails: 2,
+            fail_timeout: Duration::from_millis(200),
+            timeout: Duration::from_secs(60),
+            health_check: Some(UpstreamHealthCheck {
+                rise: 2,
+                fall: 2,
+                ..Default::default()
+            }),
+        };
+
+        UpstreamGroup::new(config)
+    }
+
+    fn port_of(group: &UpstreamGroup, hash_key: Option<&str>) -> u16 {
+        group.select(hash_key).unwrap().address().1
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let group = new_group(UpstreamPolicy::RoundRobin, 3);
+        let ports: Vec<u16> = (0..6).map(|_| port_of(&group, None)).collect();
+        assert_eq!(ports, vec![1000, 1001, 1002, 1000, 1001, 1002]);
+    }
+
+    #[test]
+    fn test_least_conn() {
+        let group = new_group(UpstreamPolicy::LeastConn, 2);
+
+        let first = group.select(None).unwrap();
+        let second = group.select(None).unwrap();
+        assert_ne!(first.address(), second.address());
+
+        // the peer with less active connections is preferred
+        let first_port = first.address().1;
+        drop(first);
+        for _ in 0..4 {
+            assert_eq!(port_of(&group, None), first_port);
+        }
+    }
+
+    #[test]
+    fn test_hash() {
+        let group = new_group(UpstreamPolicy::Hash, 4);
+
+        for key in ["192.168.1.2", "192.168.1.3", "10.0.0.1"] {
+            let port = port_of(&group, Some(key));
+            for _ in 0..8 {
+                assert_eq!(port_of(&group, Some(key)), port);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_eject() {
+        let group = new_group(UpstreamPolicy::Hash, 4);
+
+        let keys: Vec<String> = (0..64).map(|i| format!("10.0.0.{}", i)).collect();
+        let before: Vec<u16> = keys.iter().map(|key| port_of(&group, Some(key))).collect();
+
+        // eject one peer, only the keys on it are moved to other peers
+        group.on_peer_failure(1);
+        group.on_peer_failure(1);
+
+        for (key, port) in keys.iter().zip(before.iter()) {
+            let now = port_of(&group, Some(key));
+            if *port == 1001 {
+                assert_ne!(now, 1001);
+            } else {
+                assert_eq!(now, *port);
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_exclude() {
+        let group = new_group(UpstreamPolicy::Hash, 3);
+
+        let mut tried = vec![];
+        for _ in 0..3 {
+            let guard = group.select_exclude(Some("10.0.0.1"), &tried).unwrap();
+            assert!(!tried.contains(guard.address()));
+            tried.push(guard.address().clone());
+        }
+
+        assert!(group.select_exclude(Some("10.0.0.1"), &tried).is_err());
+    }
+
+    #[test]
+    fn test_peer_failure() {
+        let group = new_group(UpstreamPolicy::RoundRobin, 2);
+
+        // eject the first peer after max_fails continuous failures
+        group.on_peer_failure(0);
+        assert_eq!(group.peers.lock().unwrap()[0].ejected_until, None);
+        group.on_peer_failure(0);
+        assert!(group.peers.lock().unwrap()[0].ejected_until.is_some());
+
+        for _ in 0..4 {
+            assert_eq!(port_of(&group, None), 1001);
+        }
+
+        // the peer is available again after fail_timeout
+        std::thread::sleep(Duration::from_millis(300));
+        let ports: Vec<u16> = (0..2).map(|_| port_of(&group, None)).collect();
+        assert!(ports.contains(&1000));
+
+        // success reset the fail count
+        group.on_peer_failure(0);
+        group.on_peer_success(0);
+        group.on_peer_failure(0);
+        assert_eq!(group.peers.lock().unwrap()[0].ejected_until, None);
+    }
+
+    #[test]
+    fn test_all_peers_down() {
+        let group = new_group(UpstreamPolicy::RoundRobin, 2);
+        for index in 0..2 {
+            group.on_peer_failure(index);
+            group.on_peer_failure(index);
+        }
+
+        // fallback to all the peers
+        let ports: Vec<u16> = (0..2).map(|_| port_of(&group, None)).collect();
+        assert!(ports.contains(&1000));
+        assert!(ports.contains(&1001));
+    }
+
+    #[test]
+    fn test_check_result() {
+        let group = new_group(UpstreamPolicy::RoundRobin, 2);
+
+        group.on_check_result(1, false);
+        assert!(group.peers.lock().unwrap()[1].healthy);
+        group.on_check_result(1, false);
+        assert!(!group.peers.lock().unwrap()[1].healthy);
+
+        for _ in 0..4 {
+            assert_eq!(port_of(&group, None), 1000);
+        }
+
+        // a success in between resets the rise count
+        group.on_check_result(1, true);
+        group.on_check_result(1, false);
+        group.on_check_result(1, true);
+        assert!(!group.peers.lock().unwrap()[1].healthy);
+        group.on_check_result(1, true);
+        assert!(group.peers.lock().unwrap()[1].healthy);
+
+        let ports: Vec<u16> = (0..2).map(|_| port_of(&group, None)).collect();
+        assert!(ports.contains(&1001));
+    }
+}
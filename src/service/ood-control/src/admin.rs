@@ -0,0 +1,146 @@
+use super::request::*;
+use super::restore::RestoreController;
+use cyfs_base::*;
+use cyfs_lib::RequestorHelper;
+
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tide::Response;
+
+const ADMIN_PAGE: &str = include_str!("admin.html");
+
+// Status provider for the admin console, registered by the host service (ood-daemon, etc.)
+#[async_trait]
+pub trait OODAdminStatusProvider: Send + Sync {
+    // Status of all system services managed by the host service
+    async fn get_service_status(&self) -> BuckyResult<serde_json::Value>;
+
+    // Status of all installed dec apps
+    async fn get_app_list(&self) -> BuckyResult<serde_json::Value>;
+
+    // Storage usage from the stack's noc and ndc
+    async fn get_storage_stat(&self) -> BuckyResult<OODAdminStorageStat>;
+
+    async fn get_backup_status(&self) -> BuckyResult<Option<serde_json::Value>> {
+        Ok(None)
+    }
+}
+
+pub(crate) struct AdminController {
+    provider: OnceCell<Box<dyn OODAdminStatusProvider>>,
+}
+
+impl AdminController {
+    pub fn new() -> Self {
+        Self {
+            provider: OnceCell::new(),
+        }
+    }
+
+    pub fn register_provider(&self, provider: Box<dyn OODAdminStatusProvider>) {
+        if let Err(_) = self.provider.set(provider) {
+            error!("admin status provider already registered!");
+        }
+    }
+
+    fn provider(&self) -> BuckyResult<&dyn OODAdminStatusProvider> {
+        match self.provider.get() {
+            Some(provider) => Ok(provider.as_ref()),
+            None => {
+                let msg = format!("admin status provider not registered!");
+                warn!("{}", msg);
+
+                Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
+            }
+        }
+    }
+
+    pub fn process_page_request(&self) -> Response {
+        let mut resp: Response = RequestorHelper::new_ok_response();
+        resp.set_content_type(tide::http::mime::HTML);
+        resp.set_body(ADMIN_PAGE);
+
+        resp
+    }
+
+    pub async fn process_service_status_request(&self) -> Response {
+        let ret = match self.provider() {
+            Ok(provider) => provider.get_service_status().await,
+            Err(e) => Err(e),
+        };
+
+        Self::encode_json_response(ret)
+    }
+
+    pub async fn process_app_list_request(&self) -> Response {
+        let ret = match self.provider() {
+            Ok(provider) => provider.get_app_list().await,
+            Err(e) => Err(e),
+        };
+
+        Self::encode_json_response(ret)
+    }
+
+    pub async fn process_storage_stat_request(&self) -> Response {
+        let ret = match self.provider() {
+            Ok(provider) => provider.get_storage_stat().await,
+            Err(e) => Err(e),
+        };
+
+        Self::encode_json_response(ret)
+    }
+
+    pub async fn process_backup_status_request(&self, restore: &RestoreController) -> Response {
+        let restore_tasks = restore
+            .get_all_task_status()
+            .into_iter()
+            .map(|(id, ret)| match ret {
+                Ok(status) => OODAdminRestoreTaskStatus {
+                    id,
+                    status: Some(status),
+                    error: None,
+                },
+                Err(e) => OODAdminRestoreTaskStatus {
+                    id,
+                    status: None,
+                    error: Some(e.to_string()),
+                },
+            })
+            .collect();
+
+        // The backup status is optional, ignore the error of the provider
+        let backup = match self.provider.get() {
+            Some(provider) => match provider.get_backup_status().await {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("get backup status from provider failed! {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let status = OODAdminBackupStatus {
+            restore_tasks,
+            backup,
+        };
+
+        Self::encode_json_response(Ok(status))
+    }
+
+    pub fn encode_json_response<T: Serialize>(ret: BuckyResult<T>) -> Response {
+        match ret {
+            Ok(value) => {
+                let mut resp: Response = RequestorHelper::new_ok_response();
+                let body = serde_json::to_string(&value).unwrap();
+
+                resp.set_content_type(tide::http::mime::JSON);
+                resp.set_body(body);
+
+                resp
+            }
+            Err(e) => RequestorHelper::trans_error(e),
+        }
+    }
+}
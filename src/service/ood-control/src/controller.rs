@@ -3,6 +3,7 @@ use super::device_info::*;
 use super::request::*;
 use cyfs_base::*;
 use super::restore::RestoreController;
+use super::admin::*;
 
 use once_cell::sync::OnceCell;
 use std::collections::hash_map::{Entry, HashMap};
@@ -32,6 +33,8 @@ pub(crate) struct ControllerImpl {
     external_servers: Mutex<Vec<Box<dyn ExternalServerEndPoint>>>,
 
     restore_controller: RestoreController,
+
+    admin_controller: AdminController,
 }
 
 impl ControllerImpl {
@@ -65,6 +68,8 @@ impl ControllerImpl {
             external_servers: Mutex::new(vec![]),
 
             restore_controller: RestoreController::new(),
+
+            admin_controller: AdminController::new(),
         }
     }
 
@@ -361,6 +366,10 @@ impl Controller {
         &self.0.restore_controller
     }
 
+    pub(crate) fn admin_controller(&self) -> &AdminController {
+        &self.0.admin_controller
+    }
+
     // Record a check request once
     pub fn on_check_request(&self, source: &str) {
         self.0.on_check_request(source)
@@ -395,6 +404,11 @@ impl Controller {
         self.0.external_servers.lock().unwrap().push(server);
     }
 
+    // admin console status provider, only one provider can be registered
+    pub fn register_admin_provider(&self, provider: Box<dyn OODAdminStatusProvider>) {
+        self.0.admin_controller.register_provider(provider);
+    }
+
     pub fn fetch_all_external_servers(&self) -> Vec<Box<dyn ExternalServerEndPoint>> {
         let mut ret = vec![];
         let mut list = self.0.external_servers.lock().unwrap();
@@ -1,3 +1,4 @@
+use super::admin::AdminController;
 use super::controller::*;
 use super::request::*;
 use cyfs_base::*;
@@ -8,6 +9,7 @@ use async_trait::async_trait;
 use http_types::Url;
 use tide::{Response, StatusCode};
 
+#[derive(Clone, Copy)]
 enum RequestType {
     Check,
     Bind,
@@ -17,6 +19,13 @@ enum RequestType {
     GetRestoreTaskStatus,
     GetRestoreTaskList,
     AbortRestoreTask,
+
+    AdminPage,
+    AdminBindStatus,
+    AdminServiceStatus,
+    AdminAppList,
+    AdminStorageStat,
+    AdminBackupStatus,
 }
 
 pub(crate) struct HandlerEndpoint {
@@ -107,6 +116,36 @@ impl HandlerEndpoint {
                 .handler
                 .restore_controller()
                 .process_get_remote_restore_task_list_request(req),
+
+            RequestType::AdminPage => self.handler.admin_controller().process_page_request(),
+            RequestType::AdminBindStatus => {
+                let ret = self.handler.check().await;
+                AdminController::encode_json_response(Ok(ret))
+            }
+            RequestType::AdminServiceStatus => {
+                self.handler
+                    .admin_controller()
+                    .process_service_status_request()
+                    .await
+            }
+            RequestType::AdminAppList => {
+                self.handler
+                    .admin_controller()
+                    .process_app_list_request()
+                    .await
+            }
+            RequestType::AdminStorageStat => {
+                self.handler
+                    .admin_controller()
+                    .process_storage_stat_request()
+                    .await
+            }
+            RequestType::AdminBackupStatus => {
+                self.handler
+                    .admin_controller()
+                    .process_backup_status_request(self.handler.restore_controller())
+                    .await
+            }
         }
     }
 
@@ -135,6 +174,7 @@ impl HandlerEndpoint {
     pub fn register_server(
         handler: &Controller,
         access_token: Option<String>,
+        admin_token: &str,
         server: &mut ::tide::Server<()>,
     ) {
         // check
@@ -242,6 +282,33 @@ impl HandlerEndpoint {
             handler.to_owned(),
         ));
 
+        //// admin console
+
+        // The admin console always requires access token, even on the loopback and private addrs,
+        // all listeners share the same admin token
+        let admin_list = [
+            ("/admin", RequestType::AdminPage),
+            ("/admin/api/bind", RequestType::AdminBindStatus),
+            ("/admin/api/services", RequestType::AdminServiceStatus),
+            ("/admin/api/apps", RequestType::AdminAppList),
+            ("/admin/api/storage", RequestType::AdminStorageStat),
+            ("/admin/api/backup", RequestType::AdminBackupStatus),
+        ];
+
+        for (path, req_type) in admin_list {
+            server.at(path).get(HandlerEndpoint::new(
+                req_type,
+                Some(admin_token.to_owned()),
+                handler.to_owned(),
+            ));
+
+            server.at(&format!("{}/", path)).get(HandlerEndpoint::new(
+                req_type,
+                Some(admin_token.to_owned()),
+                handler.to_owned(),
+            ));
+        }
+
         // external
         let all = handler.fetch_all_external_servers();
         for item in all {
@@ -50,15 +50,21 @@ impl ControlInterface {
             info!("will use access token: {}", access_token.as_ref().unwrap());
         }
 
+        // The admin console uses the configured access token, or the persisted one if not required
+        let admin_token = match &access_token {
+            Some(token) => token.clone(),
+            None => AccessTokenGen::new().gen_access_token(12),
+        };
+
         let (bind_addrs, display_addrs) = Self::get_tcp_hosts(&param);
 
         println!("will start ood control service at {:?}", bind_addrs);
         println!("will display control address as {:?}", display_addrs);
 
 
-        let none_auth_server = Self::new_server(controller, None);
+        let none_auth_server = Self::new_server(controller, None, &admin_token);
         let auth_server = if access_token.is_some() {
-            Self::new_server(controller, access_token.clone())
+            Self::new_server(controller, access_token.clone(), &admin_token)
         } else {
             none_auth_server.clone()
         };
@@ -96,9 +102,13 @@ impl ControlInterface {
         }
     }
 
-    fn new_server(handler: &Controller, access_token: Option<String>) -> HttpServer {
+    fn new_server(
+        handler: &Controller,
+        access_token: Option<String>,
+        admin_token: &str,
+    ) -> HttpServer {
         let mut server = HttpServer::new_server();
-        HandlerEndpoint::register_server(handler, access_token, &mut server);
+        HandlerEndpoint::register_server(handler, access_token, admin_token, &mut server);
 
         HttpServer::new(server)
     }
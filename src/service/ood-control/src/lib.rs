@@ -1,3 +1,4 @@
+mod admin;
 mod controller;
 mod device_info;
 mod access_token;
@@ -9,6 +10,7 @@ mod request;
 mod ood_controller;
 mod restore;
 
+pub use admin::OODAdminStatusProvider;
 pub use app_bind_manager::AppBindManager;
 pub use controller::*;
 pub use interface::{
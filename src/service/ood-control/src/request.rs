@@ -79,3 +79,27 @@ pub struct ActivateResult {
     pub result: u16,
     pub msg: String,
 }
+
+// admin console related
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OODAdminStorageStat {
+    pub noc: cyfs_lib::NamedObjectCacheStat,
+
+    // Old version stack may not return the ndc stat
+    pub ndc: Option<cyfs_util::NamedDataCacheStat>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OODAdminRestoreTaskStatus {
+    pub id: String,
+    pub status: Option<cyfs_backup_lib::RemoteRestoreStatus>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OODAdminBackupStatus {
+    pub restore_tasks: Vec<OODAdminRestoreTaskStatus>,
+
+    // Backup status provided by the external provider, such as ood-daemon
+    pub backup: Option<serde_json::Value>,
+}
@@ -109,6 +109,17 @@ impl RestoreController {
         self.restore_manager.abort_task(task_id).await
     }
 
+    pub fn get_all_task_status(&self) -> Vec<(String, BuckyResult<RemoteRestoreStatus>)> {
+        self.restore_manager
+            .get_tasks()
+            .into_iter()
+            .map(|id| {
+                let status = self.restore_manager.get_task_status(&id);
+                (id, status)
+            })
+            .collect()
+    }
+
     pub fn process_get_remote_restore_task_list_request(
         &self,
         _req: Request<()>,
@@ -1,5 +1,7 @@
 use crate::*;
+use crate::http_server::HandlerEndpoint;
 use cyfs_backup::*;
+use cyfs_util::HttpServer;
 
 
 async fn start() {
@@ -60,4 +62,36 @@ async fn test_restore() {
 fn test() {
     cyfs_base::init_simple_log("test-ood-control", None);
     async_std::task::block_on(test_restore());
-}
\ No newline at end of file
+}
+
+async fn admin_request(server: &::tide::Server<()>, url: &str) -> http_types::StatusCode {
+    let req = http_types::Request::new(
+        http_types::Method::Get,
+        http_types::Url::parse(url).unwrap(),
+    );
+    let resp: http_types::Response = server.respond(req).await.unwrap();
+    resp.status()
+}
+
+async fn test_admin_token() {
+    // loopback and private listeners have no access token, but admin console still need the admin token
+    let mut server = HttpServer::new_server();
+    HandlerEndpoint::register_server(&OOD_CONTROLLER, None, "admin-token", &mut server);
+
+    let status = admin_request(&server, "http://127.0.0.1:1321/admin").await;
+    assert!(!status.is_success());
+
+    let status = admin_request(&server, "http://127.0.0.1:1321/admin?access_token=wrong").await;
+    assert!(!status.is_success());
+
+    let status = admin_request(&server, "http://127.0.0.1:1321/admin?access_token=admin-token").await;
+    assert!(status.is_success());
+
+    let status = admin_request(&server, "http://127.0.0.1:1321/admin/api/bind/?access_token=admin-token").await;
+    assert!(status.is_success());
+}
+
+#[test]
+fn test_admin() {
+    async_std::task::block_on(test_admin_token());
+}
@@ -90,6 +90,10 @@ impl GatewayMonitor {
         }
     }
 
+    pub fn stack(&self) -> Option<&SharedCyfsStack> {
+        self.stack.get()
+    }
+
     pub fn zone_role(&self) -> ZoneRole {
         self.zone_role.lock().unwrap().clone()
     }
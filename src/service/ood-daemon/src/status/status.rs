@@ -1,11 +1,13 @@
 use super::service_status::*;
+use crate::daemon::GATEWAY_MONITOR;
 use cyfs_base::*;
-use cyfs_lib::RequestorHelper;
+use cyfs_lib::{RequestorHelper, UtilGetNOCInfoOutputRequest};
 use cyfs_util::*;
 use ood_control::*;
 
 use once_cell::sync::OnceCell;
 use serde::Serialize;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
 #[derive(Serialize)]
@@ -54,6 +56,7 @@ impl OODStatusManager {
 
     pub fn register_server(&self) {
         OOD_CONTROLLER.register_external_server(Box::new(self.clone()));
+        OOD_CONTROLLER.register_admin_provider(Box::new(self.clone()));
     }
 
     pub fn refresh_ood_daemon_status(&self) {
@@ -218,6 +221,52 @@ impl ExternalServerEndPoint for OODStatusManager {
     }
 }
 
+#[async_trait::async_trait]
+impl OODAdminStatusProvider for OODStatusManager {
+    async fn get_service_status(&self) -> BuckyResult<serde_json::Value> {
+        self.refresh_ood_daemon_status();
+
+        let list = self.service_list.lock().unwrap();
+        match list.iter().find(|item| item.name == "ood-daemon") {
+            Some(item) => Ok(item.status.clone()),
+            None => {
+                let msg = format!("ood-daemon service status not exists yet!");
+                warn!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::NotFound, msg))
+            }
+        }
+    }
+
+    async fn get_app_list(&self) -> BuckyResult<serde_json::Value> {
+        // dec apps are reported by app-manager with dec_id as name
+        let list = self.service_list.lock().unwrap();
+        let apps: Vec<&ServiceStatusCache> = list
+            .iter()
+            .filter(|item| ObjectId::from_str(&item.name).is_ok())
+            .collect();
+
+        Ok(serde_json::to_value(&apps).unwrap())
+    }
+
+    async fn get_storage_stat(&self) -> BuckyResult<OODAdminStorageStat> {
+        let stack = GATEWAY_MONITOR.stack().ok_or_else(|| {
+            let msg = format!("gateway stack not init yet!");
+            warn!("{}", msg);
+            BuckyError::new(BuckyErrorCode::NotInit, msg)
+        })?;
+
+        let resp = stack
+            .util()
+            .get_noc_info(UtilGetNOCInfoOutputRequest::new())
+            .await?;
+
+        Ok(OODAdminStorageStat {
+            noc: resp.stat,
+            ndc: resp.ndc_stat,
+        })
+    }
+}
+
 lazy_static::lazy_static! {
     pub static ref OOD_STATUS_MANAGER: OODStatusManager = OODStatusManager::new();
 }
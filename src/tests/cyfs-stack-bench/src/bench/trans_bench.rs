@@ -224,6 +224,7 @@ impl TransBench {
                 },
                 object_id: _dir_resp.object_id.clone(),
                 inner_path: None,
+                projection: None,
             })
             .await
             .unwrap();
@@ -1,4 +1,5 @@
 use cyfs_base::*;
+use cyfs_core::TextObj;
 use cyfs_lib::*;
 use zone_simulator::*;
 
@@ -81,10 +82,57 @@ async fn get_access_mode() -> GlobalStateAccessMode {
     info.root_state_access_mode
 }
 
+async fn query_audit_log() {
+    let stack = TestLoader::get_shared_stack(DeviceIndex::User1OOD);
+    let user = TestLoader::get_user(DeviceIndex::User1OOD);
+
+    let filter = AuditLogFilter {
+        page_index: 0,
+        page_size: 32,
+        ..Default::default()
+    };
+    let cmd = AdminCommand::QueryAuditLog(filter);
+
+    let target = stack.local_device_id().to_owned();
+    let owner = user.people.desc().calculate_id();
+    let mut admin_object = AdminObject::create(owner, target.clone(), cmd);
+
+    let signer =
+        RsaCPUObjectSigner::new(user.people.desc().public_key().to_owned(), user.sk.clone());
+    cyfs_base::sign_and_push_named_object_desc(
+        &signer,
+        &mut admin_object,
+        &SignatureSource::RefIndex(SIGNATURE_SOURCE_REFINDEX_OWNER),
+    )
+    .await
+    .unwrap();
+
+    let admin_id = admin_object.desc().calculate_id();
+    let buf = admin_object.to_vec().unwrap();
+    let mut req = NONPostObjectOutputRequest::new_router(Some(target.into()), admin_id, buf);
+    req.common.req_path = Some(cyfs_base::CYFS_SYSTEM_ADMIN_VIRTUAL_PATH.to_owned());
+    req.common.dec_id = Some(cyfs_core::get_system_dec_app().to_owned());
+    let resp = stack.non_service().post_object(req).await.unwrap();
+
+    let object = resp.object.unwrap();
+    let text = cyfs_core::Text::clone_from_slice(&object.object_raw).unwrap();
+    assert_eq!(text.id(), AUDIT_LOG_QUERY_RESULT_TEXT_ID);
+
+    let result = AuditLogQueryResult::decode_string(text.value()).unwrap();
+    assert!(result.list.len() <= 32);
+
+    info!("query audit log success! count={}", result.list.len());
+    for record in result.list {
+        info!("audit log: {}", record.encode_string());
+    }
+}
+
 pub async fn test() {
     // 使用协议栈本身的dec_id
     let dec_id = TestLoader::get_dec_id();
 
     change_access_mode(&dec_id, GlobalStateAccessMode::Read).await;
     change_access_mode(&dec_id, GlobalStateAccessMode::Write).await;
+
+    query_audit_log().await;
 }
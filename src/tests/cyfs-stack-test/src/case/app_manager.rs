@@ -6,9 +6,14 @@ use zone_simulator::*;
 use std::collections::HashMap;
 
 async fn register_app(stack: &SharedCyfsStack, dec_id: &ObjectId) {
+    // 为dec分配凭证密钥，之后本进程内该dec的请求会自动携带凭证
+    let secret = PrivateKey::generate_secp256k1().unwrap();
+    DecAuthKeyManager::register(dec_id, secret.clone());
+
     let info = DecIpInfo {
         name: "test_dec".to_owned(),
         ip: "192.168.100.110".to_owned(),
+        public_key: Some(secret.public().to_hex().unwrap()),
     };
 
     let mut dec_map = HashMap::new();
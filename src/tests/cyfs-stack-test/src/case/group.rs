@@ -0,0 +1,168 @@
+use cyfs_base::*;
+use cyfs_core::*;
+use cyfs_lib::*;
+use zone_simulator::*;
+
+use std::sync::{Arc, Mutex};
+
+pub async fn test() {
+    group_msg_fanout().await;
+
+    info!("all group test case success!");
+}
+
+struct OnGroupMsgHandler {
+    group: Group,
+
+    // 成员的people对象，用来校验消息签名
+    members: Vec<People>,
+    recv: Arc<Mutex<Vec<ObjectId>>>,
+}
+
+#[async_trait::async_trait]
+impl EventListenerAsyncRoutine<RouterHandlerPostObjectRequest, RouterHandlerPostObjectResult>
+    for OnGroupMsgHandler
+{
+    async fn call(
+        &self,
+        param: &RouterHandlerPostObjectRequest,
+    ) -> BuckyResult<RouterHandlerPostObjectResult> {
+        info!("recv group msg: {}", param.request.object.object_id);
+
+        let msg = GroupMsg::clone_from_slice(&param.request.object.object_raw)?;
+        let sender = self
+            .members
+            .iter()
+            .find(|v| Some(v.desc().calculate_id()) == *msg.desc().owner())
+            .ok_or_else(|| BuckyError::from(BuckyErrorCode::PermissionDenied))?;
+        self.group.check_msg(&msg, sender)?;
+        assert_eq!(*msg.group(), self.group.desc().calculate_id());
+
+        self.recv
+            .lock()
+            .unwrap()
+            .push(param.request.object.object_id.clone());
+
+        let resp = RouterHandlerPostObjectResult {
+            action: RouterHandlerAction::Response,
+            request: None,
+            response: Some(Ok(NONPostObjectInputResponse { object: None })),
+        };
+
+        Ok(resp)
+    }
+}
+
+async fn group_msg_fanout() {
+    let device1 = TestLoader::get_shared_stack(DeviceIndex::User1Device1);
+    let ood2 = TestLoader::get_shared_stack(DeviceIndex::User2OOD);
+
+    let dec1 = device1.dec_id().unwrap();
+    let dec2 = ood2.dec_id().unwrap();
+    assert_eq!(dec1, dec2);
+    let call_path = "/test/group/msg";
+
+    let user1 = USER1_DATA.get().unwrap();
+    let user2 = USER2_DATA.get().unwrap();
+    let user1_info = TestLoader::get_user(DeviceIndex::User1Device1);
+    let user2_info = TestLoader::get_user(DeviceIndex::User2OOD);
+
+    let group = Group::create(
+        user1.people_id.clone(),
+        "test_group",
+        vec![],
+        vec![user2.people_id.object_id().to_owned()],
+    );
+    assert!(group.is_member(user2.people_id.object_id()));
+    let group_id = group.desc().calculate_id();
+
+    // user2的zone允许其它zone的同dec调用
+    let mut access = AccessString::new(0);
+    access.set_group_permission(AccessGroup::CurrentZone, AccessPermission::Call);
+    access.set_group_permission(AccessGroup::CurrentDevice, AccessPermission::Call);
+    access.set_group_permission(AccessGroup::OthersZone, AccessPermission::Call);
+    access.set_group_permission(AccessGroup::OwnerDec, AccessPermission::Call);
+    let item = GlobalStatePathAccessItem {
+        path: call_path.to_owned(),
+        access: GlobalStatePathGroupAccess::Default(access.value()),
+    };
+    ood2.root_state_meta_stub(None, None)
+        .add_access(item)
+        .await
+        .unwrap();
+
+    let recv = Arc::new(Mutex::new(vec![]));
+    let req_path = RequestGlobalStatePath::new(Some(dec2.clone()), Some(call_path.to_owned()));
+    ood2.router_handlers()
+        .post_object()
+        .add_handler(
+            RouterHandlerChain::Handler,
+            "group_msg_fanout_handler",
+            0,
+            None,
+            Some(req_path.to_string()),
+            RouterHandlerAction::Default,
+            Some(Box::new(OnGroupMsgHandler {
+                group: group.clone(),
+                members: vec![user1_info.people.clone(), user2_info.people.clone()],
+                recv: recv.clone(),
+            })),
+        )
+        .await
+        .unwrap();
+
+    let fanout = GroupMsgFanout::new(
+        device1.non_service().clone_processor(),
+        Some(req_path.to_string()),
+    );
+
+    // 非成员发送的消息会被拒绝
+    let secret = PrivateKey::generate_rsa(1024).unwrap();
+    let stranger = People::new(None, vec![], secret.public(), None, None, None).build();
+    let mut msg = GroupMsg::create(
+        stranger.desc().people_id(),
+        group_id.clone(),
+        MsgContent::Text("hello".to_owned()),
+    );
+    let signer = RsaCPUObjectSigner::new(secret.public(), secret.clone());
+    sign_and_push_named_object_desc(
+        &signer,
+        &mut msg,
+        &SignatureSource::RefIndex(SIGNATURE_SOURCE_REFINDEX_OWNER),
+    )
+    .await
+    .unwrap();
+    let ret = fanout.send_msg(&group, &msg, &stranger).await;
+    assert_eq!(ret.unwrap_err().code(), BuckyErrorCode::PermissionDenied);
+
+    // 未签名的消息会被拒绝
+    let mut msg = GroupMsg::create(
+        user1.people_id.clone(),
+        group_id.clone(),
+        MsgContent::Text("hello group".to_owned()),
+    );
+    let ret = fanout.send_msg(&group, &msg, &user1_info.people).await;
+    assert_eq!(ret.unwrap_err().code(), BuckyErrorCode::InvalidSignature);
+
+    // 使用user1的people身份签名
+    let signer = RsaCPUObjectSigner::new(
+        user1_info.people.desc().public_key().to_owned(),
+        user1_info.sk.clone(),
+    );
+    sign_and_push_named_object_desc(
+        &signer,
+        &mut msg,
+        &SignatureSource::RefIndex(SIGNATURE_SOURCE_REFINDEX_OWNER),
+    )
+    .await
+    .unwrap();
+    let msg_id = msg.desc().calculate_id();
+    let delivery = fanout.send_msg(&group, &msg, &user1_info.people).await.unwrap();
+
+    // 发送者自己不在投递列表里
+    assert_eq!(delivery.states.len(), 1);
+    assert!(delivery.is_complete());
+    assert_eq!(*recv.lock().unwrap(), vec![msg_id]);
+
+    info!("group msg fanout success! group={}", group_id);
+}
@@ -31,6 +31,7 @@ mod shared_stack;
 mod context;
 mod backup;
 mod acl_handler;
+mod group;
 
 pub async fn test_restart() {
     let stack = TestLoader::get_stack(DeviceIndex::User1OOD);
@@ -64,6 +65,7 @@ pub async fn test() {
     mime::test().await;
     ndn::test().await;
     call::test().await;
+    group::test().await;
     object_meta_access::test().await;
 
     test_obj_searcher::test().await;
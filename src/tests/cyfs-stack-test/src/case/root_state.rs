@@ -31,6 +31,7 @@ pub async fn test() {
     test_load_with_cache(&device_stack).await;
 
     test_storage(&device_stack).await;
+    test_storage_collections(&device_stack).await;
 
     test_gbk_path(&stack).await;
 
@@ -470,6 +471,109 @@ pub async fn test_rs_access(ood: &SharedCyfsStack, device: &SharedCyfsStack) {
 }
 */
 
+pub async fn test_storage_collections(s: &SharedCyfsStack) {
+    let x1_value = ObjectId::from_str("95RvaS5anntyAoRUBi48vQoivWzX95M8xm4rkB93DdSt").unwrap();
+    let x2_value = ObjectId::from_str("95RvaS5F94aENffFhjY1FTXGgby6vUW2AkqWYhtzrtHz").unwrap();
+
+    let dec_id = Some(cyfs_core::get_system_dec_app().to_owned());
+
+    let index_storage = s.global_state_storage_ex(
+        GlobalStateCategory::LocalCache,
+        "/test/collections/index",
+        ObjectMapSimpleContentType::Map,
+        None,
+        dec_id.clone(),
+    );
+    let queue_storage = s.global_state_storage_ex(
+        GlobalStateCategory::LocalCache,
+        "/test/collections/queue",
+        ObjectMapSimpleContentType::Map,
+        None,
+        dec_id.clone(),
+    );
+    let counter_storage = s.global_state_storage_ex(
+        GlobalStateCategory::LocalCache,
+        "/test/collections/counter",
+        ObjectMapSimpleContentType::Map,
+        None,
+        dec_id.clone(),
+    );
+
+    StateStorage::init_group(&[&index_storage, &queue_storage, &counter_storage])
+        .await
+        .unwrap();
+
+    let index = StateStorageIndex::<u64>::new(index_storage);
+    let queue = StateStorageQueue::new(queue_storage);
+    let counter = StateStorageCounter::new(counter_storage);
+
+    // index
+    for (k, v) in [(300u64, &x1_value), (2, &x2_value), (10, &x1_value)] {
+        index.set(&k, v).await.unwrap();
+    }
+    let list = index.range(Some(&2), Some(&300), None).await.unwrap();
+    let keys: Vec<u64> = list.iter().map(|item| item.0).collect();
+    assert_eq!(keys, vec![2, 10]);
+
+    let list = index.last(1).await.unwrap();
+    assert_eq!(list[0].0, 300);
+
+    // queue
+    let list = queue.list().await.unwrap();
+    for (seq, _) in list {
+        queue.ack(seq).await.unwrap();
+    }
+    let seq1 = queue.push(&x1_value).await.unwrap();
+    let seq2 = queue.push(&x2_value).await.unwrap();
+    assert!(seq1 < seq2);
+
+    let (seq, value) = queue.pop().await.unwrap().unwrap();
+    assert_eq!(seq, seq1);
+    assert_eq!(value, x1_value);
+
+    // seq1未ack，recover后需要重新投递
+    assert_eq!(queue.recover().await.unwrap(), 1);
+    let (seq, _) = queue.pop().await.unwrap().unwrap();
+    assert_eq!(seq, seq1);
+    assert!(queue.ack(seq1).await.unwrap());
+
+    let (seq, value) = queue.pop().await.unwrap().unwrap();
+    assert_eq!(seq, seq2);
+    assert_eq!(value, x2_value);
+    assert!(queue.ack(seq2).await.unwrap());
+    assert!(queue.pop().await.unwrap().is_none());
+
+    // counter
+    counter.set("total", 0).await.unwrap();
+    assert_eq!(counter.inc("total", 5).await.unwrap(), 5);
+    assert_eq!(counter.dec("total", 2).await.unwrap(), 3);
+    assert_eq!(counter.get("total").await.unwrap(), 3);
+
+    StateStorage::commit_group(&[index.storage(), queue.storage(), counter.storage()])
+        .await
+        .unwrap();
+
+    index.abort().await;
+    queue.abort().await;
+    counter.abort().await;
+
+    // reload and check
+    {
+        let storage = s.global_state_storage_ex(
+            GlobalStateCategory::LocalCache,
+            "/test/collections/counter",
+            ObjectMapSimpleContentType::Map,
+            None,
+            dec_id.clone(),
+        );
+        storage.init().await.unwrap();
+
+        let counter = StateStorageCounter::new(storage);
+        assert_eq!(counter.get("total").await.unwrap(), 3);
+        counter.abort().await;
+    }
+}
+
 pub async fn test_storage(s: &SharedCyfsStack) {
     let x1_value = ObjectId::from_str("95RvaS5anntyAoRUBi48vQoivWzX95M8xm4rkB93DdSt").unwrap();
     let x2_value = ObjectId::from_str("95RvaS5F94aENffFhjY1FTXGgby6vUW2AkqWYhtzrtHz").unwrap();
@@ -210,6 +210,7 @@ async fn add_dir_impl(
             },
             object_id: _dir_resp.object_id.clone(),
             inner_path: None,
+            projection: None,
         })
         .await
         .unwrap();
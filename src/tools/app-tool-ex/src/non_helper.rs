@@ -101,6 +101,7 @@ impl NonHelper {
                 },
                 object_id: obj_id.clone(),
                 inner_path: None,
+                projection: None,
             })
             .await
     }
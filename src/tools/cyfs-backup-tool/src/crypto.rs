@@ -0,0 +1,170 @@
+use cyfs_base::*;
+use cyfs_util::{StorageCryptoKeySource, StorageCryptoManager, StorageCryptoRef};
+
+use std::str::FromStr;
+
+pub enum StorageCryptoAction {
+    Enable,
+    Rotate,
+    Migrate,
+    Disable,
+}
+
+impl StorageCryptoAction {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Enable => "enable",
+            Self::Rotate => "rotate",
+            Self::Migrate => "migrate",
+            Self::Disable => "disable",
+        }
+    }
+
+    pub fn str_list() -> String {
+        let list: Vec<&str> = [Self::Enable, Self::Rotate, Self::Migrate, Self::Disable]
+            .into_iter()
+            .map(|v| v.as_str())
+            .collect();
+        list.join(" ,")
+    }
+}
+
+impl FromStr for StorageCryptoAction {
+    type Err = BuckyError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "enable" => Self::Enable,
+            "rotate" => Self::Rotate,
+            "migrate" => Self::Migrate,
+            "disable" => Self::Disable,
+            _ => {
+                let msg = format!("unsupported storage crypto action: {}", s);
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+            }
+        })
+    }
+}
+
+// 本地存储加密的管理，需要在协议栈停止后执行
+pub struct StorageCryptoService {
+    isolate: String,
+    secret: PrivateKey,
+    passphrase: Option<String>,
+}
+
+impl StorageCryptoService {
+    pub fn new(isolate: &str, passphrase: Option<String>) -> BuckyResult<Self> {
+        let (_, secret) = cyfs_util::get_default_device_desc().map_err(|e| {
+            error!("load local device for storage crypto failed! {}", e);
+            e
+        })?;
+
+        let passphrase =
+            passphrase.or_else(|| std::env::var(cyfs_util::CYFS_STORAGE_PASSPHRASE_ENV).ok());
+
+        Ok(Self {
+            isolate: isolate.to_owned(),
+            secret,
+            passphrase,
+        })
+    }
+
+    // 备份和恢复前加载，保证读写的对象和chunk使用同样的加密配置
+    pub fn load_if_enabled(isolate: &str) -> BuckyResult<()> {
+        if StorageCryptoManager::load_config(isolate)?.is_none() {
+            return Ok(());
+        }
+
+        let service = Self::new(isolate, None)?;
+        StorageCryptoManager::load_and_install(&service.isolate, &service.secret)?;
+
+        Ok(())
+    }
+
+    // 协议栈运行在gateway或者cyfs-runtime里，ood-daemon会自动拉起gateway，都需要先停止
+    fn check_stack_stopped() -> BuckyResult<()> {
+        for name in [OOD_DAEMON_NAME, GATEWAY_NAME, CYFS_RUNTIME_NAME] {
+            if cyfs_util::process::check_process_mutex(name) {
+                let msg = format!(
+                    "storage crypto action must run after the stack stopped! running={}",
+                    name
+                );
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::ErrorState, msg));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn run(
+        &self,
+        action: StorageCryptoAction,
+        source: StorageCryptoKeySource,
+    ) -> BuckyResult<()> {
+        Self::check_stack_stopped()?;
+
+        match action {
+            StorageCryptoAction::Enable => {
+                StorageCryptoManager::enable(
+                    &self.isolate,
+                    source,
+                    &self.secret,
+                    self.passphrase.as_deref(),
+                )?;
+                Ok(())
+            }
+            StorageCryptoAction::Rotate => {
+                StorageCryptoManager::rotate(&self.isolate, &self.secret, self.passphrase.as_deref())?;
+                Ok(())
+            }
+            StorageCryptoAction::Migrate => {
+                let crypto = self.open()?;
+                self.migrate(&crypto, true).await?;
+
+                // 全部数据已经使用当前密钥加密，旧密钥不再需要
+                StorageCryptoManager::prune(&self.isolate)
+            }
+            StorageCryptoAction::Disable => {
+                let crypto = self.open()?;
+                self.migrate(&crypto, false).await?;
+
+                StorageCryptoManager::disable(&self.isolate)
+            }
+        }
+    }
+
+    fn open(&self) -> BuckyResult<StorageCryptoRef> {
+        let ret = StorageCryptoManager::open(&self.isolate, &self.secret, self.passphrase.as_deref())?;
+        ret.ok_or_else(|| {
+            let msg = format!("storage crypto not enabled! isolate={}", self.isolate);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::NotFound, msg)
+        })
+    }
+
+    async fn migrate(&self, crypto: &StorageCryptoRef, encrypt: bool) -> BuckyResult<()> {
+        let stat = cyfs_noc::migrate_noc_storage_crypto(&self.isolate, crypto, encrypt).await?;
+        info!("migrate noc storage crypto complete! isolate={}, stat={:?}", self.isolate, stat);
+        let mut failed = stat.failed;
+
+        let data_root = cyfs_util::get_cyfs_root_path().join("data");
+        let stat =
+            cyfs_chunk_cache::migrate_chunk_cache_crypto(&data_root, &self.isolate, crypto, encrypt)
+                .await?;
+        info!("migrate chunk cache crypto complete! isolate={}, stat={:?}", self.isolate, stat);
+        failed += stat.failed;
+
+        if failed > 0 {
+            let msg = format!(
+                "migrate storage crypto but some files failed! isolate={}, failed={}",
+                self.isolate, failed
+            );
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::Failed, msg));
+        }
+
+        Ok(())
+    }
+}
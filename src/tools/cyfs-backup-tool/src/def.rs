@@ -6,6 +6,7 @@ pub enum ServiceMode {
     Backup,
     Restore,
     Interactive,
+    Crypto,
 }
 
 impl ServiceMode {
@@ -14,11 +15,12 @@ impl ServiceMode {
             Self::Backup => "backup",
             Self::Restore => "restore",
             Self::Interactive => "interactive",
+            Self::Crypto => "crypto",
         }
     }
 
     pub fn str_list() -> String {
-        let list: Vec<&str> = [Self::Backup, Self::Restore, Self::Interactive]
+        let list: Vec<&str> = [Self::Backup, Self::Restore, Self::Interactive, Self::Crypto]
             .into_iter()
             .map(|v| v.as_str())
             .collect();
@@ -33,6 +35,7 @@ impl FromStr for ServiceMode {
             "backup" => Self::Backup,
             "restore" => Self::Restore,
             "interactive" => Self::Interactive,
+            "crypto" => Self::Crypto,
             _ => {
                 let msg = format!("unsupported mode: {}", s);
                 error!("{}", msg);
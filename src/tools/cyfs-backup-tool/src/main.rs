@@ -1,4 +1,5 @@
 mod backup;
+mod crypto;
 mod def;
 mod restore;
 mod server;
@@ -74,7 +75,18 @@ async fn main_run() {
         Arg::with_name("password")
             .long("password")
             .takes_value(true)
-            .help("The password used to encrypt or decrypt the target archive"),
+            .help("The password used to encrypt or decrypt the target archive, or the storage passphrase in crypto mode"),
+    ).arg(
+        Arg::with_name("crypto-action")
+            .long("crypto-action")
+            .takes_value(true)
+            .required_if("mode", ServiceMode::Crypto.as_str())
+            .help(&format!("Specify local storage crypto action in crypto mode, can be one of {}", crypto::StorageCryptoAction::str_list())),
+    ).arg(
+        Arg::with_name("key-source")
+            .long("key-source")
+            .takes_value(true)
+            .help("The storage crypto key source when enable, can be one of device ,passphrase, default is device"),
     ).arg(
         Arg::with_name("iqf")
             .long("iqf")
@@ -166,6 +178,11 @@ async fn main_run() {
                 None => None,
             };
 
+            // 本地存储启用了加密的话，需要先加载密钥
+            if let Err(e) = crypto::StorageCryptoService::load_if_enabled(isolate) {
+                std::process::exit(e.code().into());
+            }
+
             match mode {
                 ServiceMode::Backup => {
                     let mut target_file = LocalFileBackupParam::default();
@@ -266,6 +283,30 @@ async fn main_run() {
             }
         }
         ServiceMode::Interactive => Ok(()),
+        ServiceMode::Crypto => {
+            let isolate = matches.value_of("isolate").unwrap_or("");
+            let action = matches.value_of("crypto-action").unwrap();
+            let action = crypto::StorageCryptoAction::from_str(action)
+                .map_err(|e| {
+                    std::process::exit(e.code().into());
+                })
+                .unwrap();
+
+            let source = match matches.value_of("key-source").unwrap_or("device") {
+                "device" => cyfs_util::StorageCryptoKeySource::Device,
+                "passphrase" => cyfs_util::StorageCryptoKeySource::Passphrase,
+                v => {
+                    error!("invalid key-source, must be device or passphrase: {}", v);
+                    std::process::exit(BuckyErrorCode::InvalidParam.into());
+                }
+            };
+
+            let passphrase = matches.value_of("password").map(|v| v.to_owned());
+            match crypto::StorageCryptoService::new(isolate, passphrase) {
+                Ok(service) => service.run(action, source).await,
+                Err(e) => Err(e),
+            }
+        }
     };
 
     match ret {
@@ -6,6 +6,7 @@ mod named_data_client;
 mod ffs_client_util;
 mod meta_helper;
 mod actions;
+mod shell;
 
 use clap::{App, SubCommand, Arg, ArgMatches};
 
@@ -133,6 +134,7 @@ async fn main_run() {
             .arg(Arg::with_name("url").index(1).required(true).takes_value(true).help("cyfs url"))
             .arg(meta_arg.clone())
         )
+        .subcommand(shell::shell_subcommand())
         .get_matches();
 
     cyfs_debug::CyfsLoggerBuilder::new_service("cyfs-client")
@@ -314,6 +316,12 @@ async fn main_run() {
                 }
             }).await;
         },
+        ("shell", Some(matches)) => {
+            if let Err(e) = shell::run_shell(matches).await {
+                error!("run shell failed! {}", e);
+                std::process::exit(e.code().into());
+            }
+        },
         v @ _ => {
             error!("unknown command: {}", v.0);
             std::process::exit(1);
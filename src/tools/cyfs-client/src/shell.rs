@@ -0,0 +1,580 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::str::FromStr;
+
+use async_recursion::async_recursion;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use cyfs_base::*;
+use cyfs_lib::*;
+use log::*;
+
+pub fn shell_subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("shell")
+        .about("interactive shell to browse root-state, local-cache and noc of local stack")
+        .arg(Arg::with_name("runtime").long("runtime").takes_value(false)
+            .help("connect to cyfs-runtime instead of ood"))
+        .arg(Arg::with_name("dec_id").long("dec-id").takes_value(true)
+            .help("dec id used to open the stack"))
+        .arg(Arg::with_name("target_dec_id").long("target-dec-id").takes_value(true)
+            .help("target dec id of root-state and local-cache, default is system dec"))
+}
+
+const SHELL_HELP: &str = r#"commands:
+    use <root|cache>            switch between root-state and local-cache
+    dec [dec_id|system]         show or change the target dec
+    pwd                         show current path
+    ls [path]                   list the object map at path
+    cd [path]                   change current path, use '/' for root
+    cat <path>                  print the object at path
+    stat <path>                 print the meta info of the object at path
+    get <object_id>             get object from noc and print it
+    select [options]            select objects from noc, options:
+        --type <u16>            obj_type
+        --type-code <u16>       obj_type_code
+        --dec <object_id>       dec_id
+        --owner <object_id>     owner_id
+        --author <object_id>    author_id
+        --flags <u32>           flags
+        --page <u16>            page index, start from 0
+        --size <u16>            page size, default 32
+    diff <root1> <root2>        diff two object map roots
+    root                        show current root of root-state or local-cache
+    help                        show this help
+    exit                        quit the shell"#;
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum ShellCategory {
+    RootState,
+    LocalCache,
+}
+
+impl ShellCategory {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::RootState => "root-state",
+            Self::LocalCache => "local-cache",
+        }
+    }
+}
+
+struct Shell {
+    stack: SharedCyfsStack,
+    category: ShellCategory,
+    target_dec_id: Option<ObjectId>,
+    current_path: String,
+}
+
+impl Shell {
+    fn new(stack: SharedCyfsStack, target_dec_id: Option<ObjectId>) -> Self {
+        Self {
+            stack,
+            category: ShellCategory::RootState,
+            target_dec_id,
+            current_path: "/".to_owned(),
+        }
+    }
+
+    fn prompt(&self) -> String {
+        let dec = match &self.target_dec_id {
+            Some(id) => id.to_string(),
+            None => "system".to_owned(),
+        };
+        format!("{}:{}:{}> ", self.category.as_str(), dec, self.current_path)
+    }
+
+    fn accessor(&self) -> GlobalStateAccessorStub {
+        match self.category {
+            ShellCategory::RootState => self
+                .stack
+                .root_state_accessor_stub(None, self.target_dec_id.clone()),
+            ShellCategory::LocalCache => self
+                .stack
+                .local_cache_accessor_stub(None, self.target_dec_id.clone()),
+        }
+    }
+
+    fn state_stub(&self) -> GlobalStateStub {
+        match self.category {
+            ShellCategory::RootState => self.stack.root_state_stub(None, self.target_dec_id.clone()),
+            ShellCategory::LocalCache => self.stack.local_cache_stub(self.target_dec_id.clone()),
+        }
+    }
+
+    // 基于当前路径解析相对路径，支持.和..
+    fn resolve_path(current_path: &str, path: Option<&str>) -> String {
+        let path = match path {
+            Some(path) => path,
+            None => return current_path.to_owned(),
+        };
+
+        let full = if path.starts_with('/') {
+            path.to_owned()
+        } else {
+            format!("{}/{}", current_path, path)
+        };
+
+        let mut segs: Vec<&str> = vec![];
+        for seg in full.split('/') {
+            match seg {
+                "" | "." => {}
+                ".." => {
+                    segs.pop();
+                }
+                _ => segs.push(seg),
+            }
+        }
+
+        format!("/{}", segs.join("/"))
+    }
+
+    // 返回false表示退出
+    async fn exec(&mut self, line: &str) -> BuckyResult<bool> {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        if args.is_empty() {
+            return Ok(true);
+        }
+
+        let params = &args[1..];
+        match args[0] {
+            "exit" | "quit" => return Ok(false),
+            "help" => println!("{}", SHELL_HELP),
+            "use" => self.switch_category(params)?,
+            "dec" => self.switch_dec(params)?,
+            "pwd" => println!("{}", self.current_path),
+            "ls" => self.ls(params.first().cloned()).await?,
+            "cd" => self.cd(params.first().cloned()).await?,
+            "cat" => self.cat(Self::required_param(params, "path")?).await?,
+            "stat" => self.stat(Self::required_param(params, "path")?).await?,
+            "get" => self.get(Self::required_param(params, "object_id")?).await?,
+            "select" => self.select(params).await?,
+            "diff" => {
+                if params.len() != 2 {
+                    return Err(Self::invalid_param("diff need two roots: diff <root1> <root2>"));
+                }
+                self.diff(params[0], params[1]).await?;
+            }
+            "root" => self.root().await?,
+            cmd @ _ => {
+                println!("unknown command: {}, type 'help' for usage", cmd);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn invalid_param(msg: &str) -> BuckyError {
+        BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+    }
+
+    fn required_param<'a>(params: &[&'a str], name: &str) -> BuckyResult<&'a str> {
+        params
+            .first()
+            .cloned()
+            .ok_or_else(|| Self::invalid_param(&format!("missing param: {}", name)))
+    }
+
+    fn switch_category(&mut self, params: &[&str]) -> BuckyResult<()> {
+        let category = match params.first().cloned() {
+            Some("root") | Some("root-state") => ShellCategory::RootState,
+            Some("cache") | Some("local-cache") => ShellCategory::LocalCache,
+            _ => return Err(Self::invalid_param("usage: use <root|cache>")),
+        };
+
+        if category != self.category {
+            self.category = category;
+            self.current_path = "/".to_owned();
+        }
+
+        Ok(())
+    }
+
+    fn switch_dec(&mut self, params: &[&str]) -> BuckyResult<()> {
+        match params.first().cloned() {
+            None => {
+                println!("{}", self.target_dec_id.map(|id| id.to_string()).unwrap_or("system".to_owned()));
+                return Ok(());
+            }
+            Some("system") => self.target_dec_id = None,
+            Some(id) => self.target_dec_id = Some(ObjectId::from_str(id)?),
+        }
+
+        self.current_path = "/".to_owned();
+        Ok(())
+    }
+
+    async fn ls(&self, path: Option<&str>) -> BuckyResult<()> {
+        let path = Self::resolve_path(&self.current_path, path);
+        let list = self.accessor().list(&path).await?;
+        for item in list {
+            match item {
+                ObjectMapContentItem::Map((key, value)) => {
+                    let suffix = if value.obj_type_code() == ObjectTypeCode::ObjectMap { "/" } else { "" };
+                    println!("{}{}\t{}", key, suffix, value);
+                }
+                ObjectMapContentItem::Set(value) => {
+                    println!("{}", value);
+                }
+                ObjectMapContentItem::DiffMap((key, value)) => {
+                    println!("{}\t{:?}", key, value);
+                }
+                ObjectMapContentItem::DiffSet(value) => {
+                    println!("{:?}", value);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn cd(&mut self, path: Option<&str>) -> BuckyResult<()> {
+        let path = Self::resolve_path(&self.current_path, Some(path.unwrap_or("/")));
+        if path != "/" {
+            let resp = self.accessor().get_object_by_path(&path).await?;
+            if resp.object.object_id.obj_type_code() != ObjectTypeCode::ObjectMap {
+                let msg = format!("not an object map: {}, object={}", path, resp.object.object_id);
+                return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+            }
+        }
+
+        self.current_path = path;
+        Ok(())
+    }
+
+    async fn cat(&self, path: &str) -> BuckyResult<()> {
+        let path = Self::resolve_path(&self.current_path, Some(path));
+        let resp = self.accessor().get_object_by_path(&path).await?;
+        Self::print_object(resp.object)
+    }
+
+    async fn stat(&self, path: &str) -> BuckyResult<()> {
+        let path = Self::resolve_path(&self.current_path, Some(path));
+        let resp = self.accessor().get_object_by_path(&path).await?;
+        let mut info = resp.object;
+        info.decode()?;
+
+        let object = info.object();
+        let to_string = |id: &Option<ObjectId>| id.map(|id| id.to_string()).unwrap_or("None".to_owned());
+        println!("path:        {}", path);
+        println!("object_id:   {}", info.object_id);
+        println!("obj_type:    {} ({:?})", object.obj_type(), object.obj_type_code());
+        println!("size:        {}", info.object_raw.len());
+        println!("owner:       {}", to_string(object.owner()));
+        println!("author:      {}", to_string(object.author()));
+        println!("dec_id:      {}", to_string(object.dec_id()));
+        println!("create_time: {}", object.create_time());
+        println!("update_time: {}", object.get_update_time());
+        if let Some(time) = object.expired_time() {
+            println!("expired_time: {}", time);
+        }
+        if let Some(attr) = &resp.attr {
+            println!("attr:        {:?}", attr);
+        }
+
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> BuckyResult<()> {
+        let object_id = ObjectId::from_str(id)?;
+        let req = NONGetObjectOutputRequest::new_noc(object_id, None);
+        let resp = self.stack.non_service().get_object(req).await?;
+        Self::print_object(resp.object)
+    }
+
+    fn parse_select_filter(params: &[&str]) -> BuckyResult<(SelectFilter, SelectOption)> {
+        let mut filter = SelectFilter::default();
+        let mut opt = SelectOption {
+            page_size: 32,
+            page_index: 0,
+        };
+
+        let mut iter = params.iter();
+        while let Some(name) = iter.next() {
+            let value = iter
+                .next()
+                .ok_or_else(|| Self::invalid_param(&format!("missing value of option: {}", name)))?;
+            let parse_err = |e: String| {
+                let msg = format!("invalid value of option: {}={}, {}", name, value, e);
+                BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+            };
+
+            match *name {
+                "--type" => filter.obj_type = Some(value.parse().map_err(|e: std::num::ParseIntError| parse_err(e.to_string()))?),
+                "--type-code" => filter.obj_type_code = Some(ObjectTypeCode::from_str(value)?),
+                "--dec" => filter.dec_id = Some(ObjectId::from_str(value)?),
+                "--owner" => filter.owner_id = Some(ObjectId::from_str(value)?),
+                "--author" => filter.author_id = Some(ObjectId::from_str(value)?),
+                "--flags" => filter.flags = Some(value.parse().map_err(|e: std::num::ParseIntError| parse_err(e.to_string()))?),
+                "--page" => opt.page_index = value.parse().map_err(|e: std::num::ParseIntError| parse_err(e.to_string()))?,
+                "--size" => opt.page_size = value.parse().map_err(|e: std::num::ParseIntError| parse_err(e.to_string()))?,
+                _ => return Err(Self::invalid_param(&format!("unknown select option: {}", name))),
+            }
+        }
+
+        Ok((filter, opt))
+    }
+
+    async fn select(&self, params: &[&str]) -> BuckyResult<()> {
+        let (filter, opt) = Self::parse_select_filter(params)?;
+        let req = NONSelectObjectOutputRequest::new_noc(filter, Some(opt));
+        let resp = self.stack.non_service().select_object(req).await?;
+
+        for item in &resp.objects {
+            match &item.object {
+                Some(object) => {
+                    let dec = item.meta.create_dec_id.map(|id| id.to_string()).unwrap_or("None".to_owned());
+                    println!("{}\t{:?}\tdec={}\tinsert_time={}",
+                        object.object_id, object.object_id.obj_type_code(), dec, item.meta.insert_time);
+                }
+                None => {
+                    println!("{:?}", item.meta);
+                }
+            }
+        }
+        println!("total {} objects", resp.objects.len());
+
+        Ok(())
+    }
+
+    async fn root(&self) -> BuckyResult<()> {
+        let stub = self.state_stub();
+        let (global_root, revision) = stub.get_current_root().await?;
+        println!("global root: {}, revision={}", global_root, revision);
+
+        let dec_root = stub.get_dec_root().await?;
+        println!("dec root:    {}, revision={}", dec_root.dec_root, dec_root.revision);
+
+        Ok(())
+    }
+
+    async fn diff(&self, left: &str, right: &str) -> BuckyResult<()> {
+        let left = ObjectId::from_str(left)?;
+        let right = ObjectId::from_str(right)?;
+        for id in [&left, &right] {
+            if id.obj_type_code() != ObjectTypeCode::ObjectMap {
+                let msg = format!("diff root must be object map: {}", id);
+                return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+            }
+        }
+
+        let mut count = 0;
+        self.diff_object_map("", &left, &right, &mut count).await?;
+        println!("total {} changes", count);
+
+        Ok(())
+    }
+
+    async fn load_object_map(&self, id: &ObjectId) -> BuckyResult<Vec<ObjectMapContentItem>> {
+        let env = self.state_stub().create_single_op_env().await?;
+        env.load(id.to_owned()).await?;
+        let list = env.list().await;
+        let _ = env.abort().await;
+
+        list
+    }
+
+    #[async_recursion]
+    async fn diff_object_map(
+        &self,
+        path: &str,
+        left: &ObjectId,
+        right: &ObjectId,
+        count: &mut usize,
+    ) -> BuckyResult<()> {
+        if left == right {
+            return Ok(());
+        }
+
+        let left_list = self.load_object_map(left).await?;
+        let right_list = self.load_object_map(right).await?;
+
+        let mut left_map = BTreeMap::new();
+        let mut left_set = BTreeSet::new();
+        for item in left_list {
+            match item {
+                ObjectMapContentItem::Map((key, value)) => {
+                    left_map.insert(key, value);
+                }
+                ObjectMapContentItem::Set(value) => {
+                    left_set.insert(value);
+                }
+                _ => {}
+            }
+        }
+
+        let mut right_map = BTreeMap::new();
+        let mut right_set = BTreeSet::new();
+        for item in right_list {
+            match item {
+                ObjectMapContentItem::Map((key, value)) => {
+                    right_map.insert(key, value);
+                }
+                ObjectMapContentItem::Set(value) => {
+                    right_set.insert(value);
+                }
+                _ => {}
+            }
+        }
+
+        for (key, value) in &left_map {
+            let sub_path = format!("{}/{}", path, key);
+            match right_map.get(key) {
+                None => {
+                    println!("- {}\t{}", sub_path, value);
+                    *count += 1;
+                }
+                Some(new_value) if new_value != value => {
+                    if value.obj_type_code() == ObjectTypeCode::ObjectMap
+                        && new_value.obj_type_code() == ObjectTypeCode::ObjectMap
+                    {
+                        self.diff_object_map(&sub_path, value, new_value, count).await?;
+                    } else {
+                        println!("~ {}\t{} -> {}", sub_path, value, new_value);
+                        *count += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (key, value) in &right_map {
+            if !left_map.contains_key(key) {
+                println!("+ {}/{}\t{}", path, key, value);
+                *count += 1;
+            }
+        }
+
+        for value in left_set.difference(&right_set) {
+            println!("- {}/\t{}", path, value);
+            *count += 1;
+        }
+        for value in right_set.difference(&left_set) {
+            println!("+ {}/\t{}", path, value);
+            *count += 1;
+        }
+
+        Ok(())
+    }
+
+    fn print_object(mut info: NONObjectInfo) -> BuckyResult<()> {
+        info.decode()?;
+        let value = info.format_json();
+        println!("{}", serde_json::to_string_pretty(&value).unwrap());
+
+        Ok(())
+    }
+
+    async fn run(&mut self) {
+        println!("connected to stack: device={}, type 'help' for usage", self.stack.local_device_id());
+
+        let stdin = async_std::io::stdin();
+        loop {
+            print!("{}", self.prompt());
+            let _ = std::io::stdout().flush();
+
+            let mut line = String::new();
+            match stdin.read_line(&mut line).await {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    error!("read from stdin failed! {}", e);
+                    break;
+                }
+            }
+
+            match self.exec(line.trim()).await {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    println!("error: {}", e);
+                }
+            }
+        }
+    }
+}
+
+pub async fn run_shell(matches: &ArgMatches<'_>) -> BuckyResult<()> {
+    let dec_id = match matches.value_of("dec_id") {
+        Some(id) => Some(ObjectId::from_str(id)?),
+        None => None,
+    };
+    let target_dec_id = match matches.value_of("target_dec_id") {
+        Some(id) => Some(ObjectId::from_str(id)?),
+        None => None,
+    };
+
+    let stack = if matches.is_present("runtime") {
+        SharedCyfsStack::open_runtime(dec_id).await?
+    } else {
+        SharedCyfsStack::open_default(dec_id).await?
+    };
+    stack.online().await?;
+
+    // 注册core和lib里面的对象格式化，用以cat/get时打印对象内容
+    cyfs_core::register_core_objects_format();
+    cyfs_lib::register_core_objects_format();
+    cyfs_base_meta::register_meta_objects_format();
+
+    let mut shell = Shell::new(stack, target_dec_id);
+    shell.run().await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolve_path() {
+        assert_eq!(Shell::resolve_path("/", None), "/");
+        assert_eq!(Shell::resolve_path("/a/b", None), "/a/b");
+
+        // 相对路径
+        assert_eq!(Shell::resolve_path("/", Some("a")), "/a");
+        assert_eq!(Shell::resolve_path("/a", Some("b/c")), "/a/b/c");
+        assert_eq!(Shell::resolve_path("/a", Some("./b/./c/")), "/a/b/c");
+
+        // ..
+        assert_eq!(Shell::resolve_path("/a/b", Some("..")), "/a");
+        assert_eq!(Shell::resolve_path("/a/b", Some("../c")), "/a/c");
+        assert_eq!(Shell::resolve_path("/a", Some("../../..")), "/");
+
+        // 绝对路径
+        assert_eq!(Shell::resolve_path("/a/b", Some("/")), "/");
+        assert_eq!(Shell::resolve_path("/a/b", Some("/c//d")), "/c/d");
+        assert_eq!(Shell::resolve_path("/a/b", Some("/c/../d")), "/d");
+    }
+
+    #[test]
+    fn test_parse_select_filter() {
+        let (filter, opt) = Shell::parse_select_filter(&[]).unwrap();
+        assert!(filter.obj_type.is_none());
+        assert!(filter.dec_id.is_none());
+        assert_eq!(opt.page_size, 32);
+        assert_eq!(opt.page_index, 0);
+
+        let id = ObjectId::default().to_string();
+        let params = [
+            "--type", "41", "--type-code", "7", "--dec", &id, "--owner", &id, "--author", &id,
+            "--flags", "3", "--page", "2", "--size", "10",
+        ];
+        let (filter, opt) = Shell::parse_select_filter(&params).unwrap();
+        assert_eq!(filter.obj_type, Some(41));
+        assert_eq!(filter.obj_type_code, Some(ObjectTypeCode::Chunk));
+        assert_eq!(filter.dec_id, Some(ObjectId::default()));
+        assert_eq!(filter.owner_id, Some(ObjectId::default()));
+        assert_eq!(filter.author_id, Some(ObjectId::default()));
+        assert_eq!(filter.flags, Some(3));
+        assert_eq!(opt.page_index, 2);
+        assert_eq!(opt.page_size, 10);
+
+        // 缺少值，非法的值和未知的选项
+        let err = Shell::parse_select_filter(&["--type"]).unwrap_err();
+        assert_eq!(err.code(), BuckyErrorCode::InvalidParam);
+        let err = Shell::parse_select_filter(&["--page", "abc"]).unwrap_err();
+        assert_eq!(err.code(), BuckyErrorCode::InvalidParam);
+        assert!(Shell::parse_select_filter(&["--dec", "invalid"]).is_err());
+        let err = Shell::parse_select_filter(&["--unknown", "1"]).unwrap_err();
+        assert_eq!(err.code(), BuckyErrorCode::InvalidParam);
+    }
+}
@@ -1,6 +1,7 @@
 mod util;
 mod modify;
 mod sign;
+mod revoke;
 
 use clap::{SubCommand, App, Arg};
 use crate::show::{show_desc, show_desc_subcommand};
@@ -9,6 +10,7 @@ use crate::modify::{modify_subcommand, modify_desc};
 use log::*;
 use cyfs_base::{StandardObject, FileDecoder, BuckyError, BuckyErrorCode};
 use crate::sign::{sign_subcommand, sign_desc};
+use crate::revoke::{rotate_subcommand, rotate_device, revoke_subcommand, revoke_device};
 
 pub mod desc;
 mod show;
@@ -73,6 +75,8 @@ async fn main_run() {
         .subcommand(calc_subcommand())
         .subcommand(modify_subcommand())
         .subcommand(sign_subcommand())
+        .subcommand(rotate_subcommand())
+        .subcommand(revoke_subcommand())
         .get_matches();
 
     match matches.subcommand() {
@@ -92,6 +96,12 @@ async fn main_run() {
         ("sign", Some(matches)) => {
             sign_desc(matches).await;
         }
+        ("rotate", Some(matches)) => {
+            rotate_device(matches).await;
+        }
+        ("revoke", Some(matches)) => {
+            revoke_device(matches).await;
+        }
         v @ _ => {
             error!("unknown command: {}", v.0);
             std::process::exit(1);
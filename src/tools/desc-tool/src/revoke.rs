@@ -0,0 +1,394 @@
+use clap::{App, SubCommand, Arg, ArgMatches};
+use cyfs_base::*;
+use cyfs_base_meta::SavedMetaObject;
+use cyfs_meta_lib::{MetaClient, MetaMinerTarget};
+
+use log::*;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::convert::TryFrom;
+
+pub fn rotate_subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("rotate").about("replace device key pair, old device will be revoked by owner")
+        .arg(Arg::with_name("device").takes_value(true).index(1).required(true)
+            .help("old device desc file"))
+        .arg(Arg::with_name("owner").long("owner").short("o").takes_value(true).required(true)
+            .help("owner people file path, without extension, must have .desc and .sec file"))
+        .arg(Arg::with_name("pktype").long("pktype").short("p").default_value("rsa1024")
+            .possible_values(&["rsa1024", "rsa2048", "rsa3072", "secp"])
+            .help("new private key type"))
+        .arg(Arg::with_name("save_path").long("savepath").takes_value(true)
+            .help("save path for new device desc and sec"))
+        .arg(Arg::with_name("meta_target").long("meta_target").short("m").takes_value(true)
+            .help("publish new device and owner to meta chain, dev/test/formal or url"))
+}
+
+pub fn revoke_subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("revoke").about("revoke device from owner, remove it from ood_list")
+        .arg(Arg::with_name("device").takes_value(true).index(1).required(true)
+            .help("device id to revoke"))
+        .arg(Arg::with_name("owner").long("owner").short("o").takes_value(true).required(true)
+            .help("owner people file path, without extension, must have .desc and .sec file"))
+        .arg(Arg::with_name("meta_target").long("meta_target").short("m").takes_value(true)
+            .help("publish owner to meta chain, dev/test/formal or url"))
+}
+
+struct OwnerInfo {
+    people: People,
+    secret: PrivateKey,
+    desc_file: PathBuf,
+}
+
+impl OwnerInfo {
+    fn load(path: &str) -> BuckyResult<Self> {
+        let desc_file = Path::new(path).with_extension("desc");
+        let (people, _) = People::decode_from_file(&desc_file, &mut vec![]).map_err(|e| {
+            error!("decode owner people desc failed! file={}, {}", desc_file.display(), e);
+            e
+        })?;
+
+        let sec_file = Path::new(path).with_extension("sec");
+        let (secret, _) = PrivateKey::decode_from_file(&sec_file, &mut vec![]).map_err(|e| {
+            error!("decode owner secret failed! file={}, {}", sec_file.display(), e);
+            e
+        })?;
+
+        if &secret.public() != people.desc().public_key() {
+            let msg = format!("owner secret not match people's public key! people={}", people.desc().calculate_id());
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotMatch, msg));
+        }
+
+        Ok(Self { people, secret, desc_file })
+    }
+
+    fn owner_id(&self) -> ObjectId {
+        self.people.desc().calculate_id()
+    }
+
+    // 修改了ood_list和吊销列表后，需要更新时间并重新签名body
+    async fn sign_and_save(&mut self) -> BuckyResult<()> {
+        self.people.body_mut().as_mut().unwrap().increase_update_time(bucky_time_now());
+
+        let signer = RsaCPUObjectSigner::new(self.secret.public(), self.secret.clone());
+        sign_and_set_named_object_body(&signer, &mut self.people, &SignatureSource::RefIndex(SIGNATURE_SOURCE_REFINDEX_SELF)).await?;
+
+        self.people.encode_to_file(&self.desc_file, false)?;
+        info!("save owner people desc to {}", self.desc_file.display());
+
+        Ok(())
+    }
+}
+
+fn get_key_bits(matches: &ArgMatches) -> usize {
+    match matches.value_of("pktype").unwrap() {
+        "rsa1024" => 1024,
+        "rsa2048" => 2048,
+        "rsa3072" => 3072,
+        _ => 1,
+    }
+}
+
+fn get_meta_client(matches: &ArgMatches) -> BuckyResult<Option<MetaClient>> {
+    match matches.value_of("meta_target") {
+        Some(target) => {
+            let target = MetaMinerTarget::from_str(target)?;
+            Ok(Some(MetaClient::new_target(target)))
+        }
+        None => Ok(None),
+    }
+}
+
+async fn publish_owner(meta_client: &MetaClient, owner: &OwnerInfo) -> BuckyResult<()> {
+    let hash = meta_client.update_desc(
+        &StandardObject::People(owner.people.clone()),
+        &SavedMetaObject::People(owner.people.clone()),
+        None,
+        None,
+        &owner.secret,
+    ).await.map_err(|e| {
+        error!("update owner desc on meta failed! owner={}, {}", owner.owner_id(), e);
+        e
+    })?;
+
+    info!("update owner desc on meta success! owner={}, tx={}", owner.owner_id(), hash);
+    Ok(())
+}
+
+async fn rotate_device_impl(matches: &ArgMatches<'_>) -> BuckyResult<()> {
+    let device_file = matches.value_of("device").unwrap();
+    let (old_device, _) = Device::decode_from_file(device_file.as_ref(), &mut vec![]).map_err(|e| {
+        error!("decode device desc failed! file={}, {}", device_file, e);
+        e
+    })?;
+    let old_device_id = old_device.desc().device_id();
+
+    let mut owner = OwnerInfo::load(matches.value_of("owner").unwrap())?;
+    let owner_id = owner.owner_id();
+    if old_device.desc().owner() != &Some(owner_id.clone()) {
+        let msg = format!("device's owner not match! device={}, owner={:?}, expect={}", old_device_id, old_device.desc().owner(), owner_id);
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::NotMatch, msg));
+    }
+
+    if owner.people.is_device_revoked(&old_device_id) {
+        let msg = format!("device already been revoked! device={}", old_device_id);
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::AlreadyExists, msg));
+    }
+
+    // 使用新的密钥对生成新的device，其余信息保持不变
+    let key_bits = get_key_bits(matches);
+    let secret = if key_bits < 1024 {
+        PrivateKey::generate_secp256k1()?
+    } else {
+        PrivateKey::generate_rsa(key_bits)?
+    };
+
+    let connect_info = old_device.connect_info();
+    let mut device = Device::new(
+        Some(owner_id.clone()),
+        old_device.desc().unique_id().clone(),
+        connect_info.endpoints().clone(),
+        connect_info.sn_list().clone(),
+        connect_info.passive_pn_list().clone(),
+        secret.public(),
+        old_device.desc().area().clone().unwrap_or_default(),
+        old_device.category()?,
+    ).build();
+    device.set_name(old_device.name().map(|v| v.to_owned()));
+    device.set_bdt_version(old_device.bdt_version());
+
+    let signer = RsaCPUObjectSigner::new(owner.secret.public(), owner.secret.clone());
+    sign_and_set_named_object_desc(&signer, &mut device, &SignatureSource::RefIndex(SIGNATURE_SOURCE_REFINDEX_OWNER)).await?;
+    sign_and_set_named_object_body(&signer, &mut device, &SignatureSource::RefIndex(SIGNATURE_SOURCE_REFINDEX_OWNER)).await?;
+    let device_id = device.desc().device_id();
+
+    // 新device替换掉旧device在ood_list里面的位置
+    let index = owner.people.ood_list().iter().position(|id| *id == old_device_id);
+    owner.people.revoke_device(&old_device_id);
+    if let Some(index) = index {
+        owner.people.ood_list_mut().insert(index, device_id.clone());
+    }
+
+    let file_base = Path::new(matches.value_of("save_path").unwrap_or("")).join(device_id.to_string());
+    device.encode_to_file(&file_base.with_extension("desc"), false)?;
+    secret.encode_to_file(&file_base.with_extension("sec"), false)?;
+    info!("write new device desc and sec to {}", file_base.display());
+
+    owner.sign_and_save().await?;
+
+    if let Some(meta_client) = get_meta_client(matches)? {
+        let hash = meta_client.create_desc(
+            &StandardObject::People(owner.people.clone()),
+            &SavedMetaObject::Device(device.clone()),
+            0,
+            0,
+            0,
+            &owner.secret,
+        ).await.map_err(|e| {
+            error!("create new device desc on meta failed! device={}, {}", device_id, e);
+            e
+        })?;
+        info!("create new device desc on meta success! device={}, tx={}", device_id, hash);
+
+        publish_owner(&meta_client, &owner).await?;
+    }
+
+    info!("rotate device key success! {} -> {}", old_device_id, device_id);
+    Ok(())
+}
+
+async fn revoke_device_impl(matches: &ArgMatches<'_>) -> BuckyResult<()> {
+    let id = ObjectId::from_str(matches.value_of("device").unwrap())?;
+    let device_id = DeviceId::try_from(&id)?;
+
+    let mut owner = OwnerInfo::load(matches.value_of("owner").unwrap())?;
+    if owner.people.is_device_revoked(&device_id) {
+        warn!("device already been revoked! device={}", device_id);
+    } else {
+        owner.people.revoke_device(&device_id);
+        owner.sign_and_save().await?;
+    }
+
+    if let Some(meta_client) = get_meta_client(matches)? {
+        publish_owner(&meta_client, &owner).await?;
+    }
+
+    info!("revoke device success! device={}, owner={}", device_id, owner.owner_id());
+    Ok(())
+}
+
+pub async fn rotate_device(matches: &ArgMatches<'_>) {
+    if let Err(e) = rotate_device_impl(matches).await {
+        error!("rotate device key failed! {}", e);
+        std::process::exit(e.code().into());
+    }
+}
+
+pub async fn revoke_device(matches: &ArgMatches<'_>) {
+    if let Err(e) = revoke_device_impl(matches).await {
+        error!("revoke device failed! {}", e);
+        std::process::exit(e.code().into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestEnv {
+        dir: PathBuf,
+        owner_path: String,
+        owner_id: ObjectId,
+        owner_pk: PublicKey,
+        device_id: DeviceId,
+        device_file: PathBuf,
+    }
+
+    fn prepare(name: &str) -> TestEnv {
+        let dir = std::env::temp_dir().join(format!("desc-tool-{}-{}", name, bucky_time_now()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let owner_secret = PrivateKey::generate_rsa(1024).unwrap();
+        let device_secret = PrivateKey::generate_rsa(1024).unwrap();
+
+        let people = People::new(None, vec![], owner_secret.public(), None, None, None).build();
+        let owner_id = people.desc().calculate_id();
+
+        let device = Device::new(
+            Some(owner_id.clone()),
+            UniqueId::create(name.as_bytes()),
+            vec![],
+            vec![],
+            vec![],
+            device_secret.public(),
+            Area::default(),
+            DeviceCategory::OOD,
+        )
+        .build();
+        let device_id = device.desc().device_id();
+
+        let mut people = people;
+        people.ood_list_mut().push(device_id.clone());
+
+        let owner_file = dir.join("owner");
+        people.encode_to_file(&owner_file.with_extension("desc"), false).unwrap();
+        owner_secret.encode_to_file(&owner_file.with_extension("sec"), false).unwrap();
+
+        let device_file = dir.join("device.desc");
+        device.encode_to_file(&device_file, false).unwrap();
+
+        TestEnv {
+            owner_path: owner_file.to_str().unwrap().to_owned(),
+            owner_id,
+            owner_pk: owner_secret.public(),
+            device_id,
+            device_file,
+            dir,
+        }
+    }
+
+    fn load_owner(env: &TestEnv) -> People {
+        let file = Path::new(&env.owner_path).with_extension("desc");
+        People::decode_from_file(&file, &mut vec![]).unwrap().0
+    }
+
+    // 修改后的owner必须由owner自己重新签名
+    async fn check_owner_body_sign(env: &TestEnv, people: &People) {
+        let signs = people.signs().body_signs().unwrap();
+        assert_eq!(signs.len(), 1);
+
+        let verifier = RsaCPUObjectVerifier::new(env.owner_pk.clone());
+        assert!(verify_object_body_sign(&verifier, people, &signs[0]).await.unwrap());
+    }
+
+    #[async_std::test]
+    async fn test_revoke() {
+        let env = prepare("revoke");
+        let device_id = env.device_id.to_string();
+        let args = vec!["revoke", device_id.as_str(), "--owner", env.owner_path.as_str()];
+
+        let matches = revoke_subcommand().get_matches_from(args.clone());
+        revoke_device_impl(&matches).await.unwrap();
+
+        let people = load_owner(&env);
+        assert!(people.is_device_revoked(&env.device_id));
+        assert!(people.ood_list().is_empty());
+        check_owner_body_sign(&env, &people).await;
+
+        // 重复吊销不会重复添加
+        let matches = revoke_subcommand().get_matches_from(args);
+        revoke_device_impl(&matches).await.unwrap();
+        let people = load_owner(&env);
+        assert_eq!(people.revoked_device_list(), &vec![env.device_id.clone()]);
+
+        std::fs::remove_dir_all(&env.dir).unwrap();
+    }
+
+    #[async_std::test]
+    async fn test_rotate() {
+        let env = prepare("rotate");
+        let device_file = env.device_file.to_str().unwrap().to_owned();
+        let save_path = env.dir.to_str().unwrap().to_owned();
+        let args = vec![
+            "rotate",
+            device_file.as_str(),
+            "--owner",
+            env.owner_path.as_str(),
+            "--savepath",
+            save_path.as_str(),
+        ];
+
+        let matches = rotate_subcommand().get_matches_from(args.clone());
+        rotate_device_impl(&matches).await.unwrap();
+
+        // 旧device被吊销，新device替换了它在ood_list里面的位置
+        let people = load_owner(&env);
+        assert!(people.is_device_revoked(&env.device_id));
+        assert_eq!(people.ood_list().len(), 1);
+        let new_device_id = people.ood_list()[0].clone();
+        assert_ne!(new_device_id, env.device_id);
+        check_owner_body_sign(&env, &people).await;
+
+        let file_base = env.dir.join(new_device_id.to_string());
+        let (device, _) =
+            Device::decode_from_file(&file_base.with_extension("desc"), &mut vec![]).unwrap();
+        assert_eq!(device.desc().device_id(), new_device_id);
+        assert_eq!(device.desc().owner(), &Some(env.owner_id.clone()));
+
+        let (secret, _) =
+            PrivateKey::decode_from_file(&file_base.with_extension("sec"), &mut vec![]).unwrap();
+        assert_eq!(&secret.public(), device.desc().public_key());
+
+        // 新device的desc由owner签名
+        let signs = device.signs().desc_signs().unwrap();
+        let verifier = RsaCPUObjectVerifier::new(env.owner_pk.clone());
+        assert!(verify_object_desc_sign(&verifier, &device, &signs[0]).await.unwrap());
+
+        // 已经吊销的device不能再次轮换
+        let matches = rotate_subcommand().get_matches_from(args);
+        let e = rotate_device_impl(&matches).await.unwrap_err();
+        assert_eq!(e.code(), BuckyErrorCode::AlreadyExists);
+
+        std::fs::remove_dir_all(&env.dir).unwrap();
+    }
+
+    #[async_std::test]
+    async fn test_rotate_owner_not_match() {
+        let env = prepare("rotate-not-match");
+        let other = prepare("rotate-other");
+        let device_file = env.device_file.to_str().unwrap().to_owned();
+        let args = vec!["rotate", device_file.as_str(), "--owner", other.owner_path.as_str()];
+
+        let matches = rotate_subcommand().get_matches_from(args);
+        let e = rotate_device_impl(&matches).await.unwrap_err();
+        assert_eq!(e.code(), BuckyErrorCode::NotMatch);
+
+        let people = load_owner(&other);
+        assert!(people.revoked_device_list().is_empty());
+
+        std::fs::remove_dir_all(&env.dir).unwrap();
+        std::fs::remove_dir_all(&other.dir).unwrap();
+    }
+}